@@ -0,0 +1,47 @@
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#ifndef QUACK_CHECK_H
+#define QUACK_CHECK_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Run one job, as `quack-check run --input <input_path>`. Returns a JSON string to
+// release with `quack_free_result`: `{"ok": true, "result": ...}` with what `--json`
+// prints, or `{"ok": false, "error": "...", "error_class": "...", "exit_code": N}`.
+//
+// `config_path` may be NULL for the default config lookup (`quack-check.toml` in the
+// working directory), and `out_dir` NULL for the config's `paths.out_dir`. The result
+// holds the job id, job directory, status, and completeness.
+//
+// # Safety
+//
+// Each non-NULL argument must point to a NUL-terminated string.
+char *quack_run_job(const char *config_path, const char *input_path, const char *out_dir);
+
+// Probe and classify one input, as `quack-check classify --input <input_path>`. Returns
+// JSON like `quack_run_job`, whose result holds the probe and the policy decision.
+//
+// # Safety
+//
+// Each non-NULL argument must point to a NUL-terminated string.
+char *quack_classify(const char *config_path, const char *input_path);
+
+// Release a string returned by this library. NULL is ignored.
+//
+// # Safety
+//
+// `result` must be NULL or a pointer returned by this library, not yet freed.
+void quack_free_result(char *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* QUACK_CHECK_H */
@@ -1,9 +1,12 @@
 #!/usr/bin/env python3
+import copy
 import importlib
 import inspect
 import json
+import math
 import os
 import sys
+import time
 from pathlib import Path
 
 
@@ -90,6 +93,41 @@ def normalize_ocr_langs(engine: str, langs):
     return out or langs
 
 
+# Tesseract ships separate models for vertically set CJK text.
+VERTICAL_LANGS = ("jpn", "chi_sim", "chi_tra", "kor")
+
+# Printed to stderr once the models are loaded; quack-check counts the process as
+# starting up (docling.max_concurrent_startups) until then.
+READY_MARKER = "quack:models-ready"
+# Between the halves of a split two-up page; matches postprocess::HALF_PAGE_BREAK_PLACEHOLDER.
+HALF_PAGE_BREAK = "<!-- quack:half-page-break -->"
+# Opens each tile of a page cut up for OCR; matches postprocess::tile_marker.
+TILE_MARKER = "<!-- quack:tile row {row} col {col} of {rows}x{cols} (pdf {page}) -->"
+
+
+def vertical_ocr_langs(langs):
+    """Put the `*_vert` model of each CJK language ahead of the languages given."""
+    if not isinstance(langs, list):
+        return langs
+    vert = [f"{lang}_vert" for lang in langs if lang in VERTICAL_LANGS]
+    return vert + [lang for lang in langs if lang not in vert]
+
+
+def apply_pipeline_overrides(cfg: dict, overrides: dict) -> dict:
+    """Merge tier overrides into a copy of cfg: `ocr.<key>` targets docling.ocr,
+    `accelerator.<key>` docling.accelerator, any other key docling.pipeline."""
+    cfg = copy.deepcopy(cfg)
+    docling_cfg = cfg["docling"]
+    for key, value in overrides.items():
+        if key.startswith("ocr."):
+            docling_cfg.setdefault("ocr", {})[key[len("ocr."):]] = value
+        elif key.startswith("accelerator."):
+            docling_cfg.setdefault("accelerator", {})[key[len("accelerator."):]] = value
+        else:
+            docling_cfg.setdefault("pipeline", {})[key] = value
+    return cfg
+
+
 def build_pipeline_options(cfg: dict, do_ocr: bool):
     applied = []
     ignored = []
@@ -274,6 +312,11 @@ def build_pipeline_options(cfg: dict, do_ocr: bool):
         ocr_obj = None
         engine = ocr_cfg.get("engine", "rapidocr")
         langs = normalize_ocr_langs(engine, ocr_cfg.get("langs", []))
+        vertical = bool(ocr_cfg.get("vertical_text", False))
+        if vertical and engine in ("tesseract", "tesseract_cli"):
+            langs = vertical_ocr_langs(langs)
+        elif vertical:
+            ignored.append(f"vertical_text (not supported by {engine})")
 
         if engine == "tesseract_cli" and TesseractCliOcrOptions:
             ocr_obj = TesseractCliOcrOptions()
@@ -289,6 +332,18 @@ def build_pipeline_options(cfg: dict, do_ocr: bool):
             # Fall back to rapidocr if available in the docling build (handled internally).
             ocr_obj = None
 
+        # Hybrid OCR: docling keeps the text layer and OCRs only the bitmap areas past
+        # bitmap_area_threshold, so nothing may force whole pages through OCR.
+        hybrid = bool(ocr_cfg.get("hybrid", False))
+        force_full = bool(ocr_cfg.get("force_full_page_ocr", False))
+        force_ocr = bool(ocr_cfg.get("force_ocr", False))
+        if hybrid:
+            if force_full:
+                ignored.append("force_full_page_ocr (hybrid OCR)")
+            if force_ocr:
+                ignored.append("force_ocr (hybrid OCR)")
+            force_full = force_ocr = False
+
         if ocr_obj is not None:
             set_if_present(ocr_obj, "lang", langs, applied, ignored)
             set_if_present(
@@ -298,20 +353,11 @@ def build_pipeline_options(cfg: dict, do_ocr: bool):
                 applied,
                 ignored,
             )
-            set_if_present(
-                ocr_obj,
-                "force_full_page_ocr",
-                bool(ocr_cfg.get("force_full_page_ocr", False)),
-                applied,
-                ignored,
-            )
-            set_if_present(
-                ocr_obj,
-                "force_ocr",
-                bool(ocr_cfg.get("force_ocr", False)),
-                applied,
-                ignored,
-            )
+            set_if_present(ocr_obj, "force_full_page_ocr", force_full, applied, ignored)
+            set_if_present(ocr_obj, "force_ocr", force_ocr, applied, ignored)
+            if vertical and engine in ("tesseract", "tesseract_cli"):
+                # Page segmentation mode 5: a single uniform block of vertical text.
+                set_if_present(ocr_obj, "psm", 5, applied, ignored)
             extra = ocr_cfg.get("tesseract_cli_args", "")
             if extra:
                 set_if_present(ocr_obj, "tesseract_args", extra, applied, ignored)
@@ -378,6 +424,158 @@ def has_tableformer_artifacts(artifacts_path: str | None, hf_home: str | None) -
     return False
 
 
+# Model artifacts a pipeline may need: (docling-tools download name, relative paths any
+# of which marks the model as present under an artifacts dir or the HF hub cache).
+MODEL_ARTIFACTS = {
+    "layout": [
+        "model_artifacts/layout",
+        "ds4sd--docling-models/model_artifacts/layout",
+        "ds4sd--docling-layout-heron",
+        "docling-project--docling-layout-heron",
+        "models--ds4sd--docling-models",
+        "models--ds4sd--docling-layout-heron",
+    ],
+    "tableformer": [
+        "accurate/tm_config.json",
+        "fast/tm_config.json",
+        "model_artifacts/tableformer",
+        "ds4sd--docling-models/model_artifacts/tableformer",
+        "models--ds4sd--docling-models",
+    ],
+    "code_formula": ["ds4sd--CodeFormulaV2", "ds4sd--CodeFormula", "models--ds4sd--CodeFormulaV2"],
+    "picture_classifier": [
+        "ds4sd--DocumentFigureClassifier",
+        "models--ds4sd--DocumentFigureClassifier",
+    ],
+    "easyocr": ["EasyOcr"],
+    "rapidocr": ["RapidOcr"],
+}
+
+
+def artifact_bases(cfg):
+    bases = []
+    artifacts = cfg.get("paths", {}).get("docling_artifacts_dir") or os.environ.get(
+        "DOCLING_ARTIFACTS_PATH"
+    )
+    if artifacts:
+        bases.append(Path(artifacts))
+    hf_home = os.environ.get("HF_HOME")
+    if hf_home:
+        bases.append(Path(hf_home) / "hub")
+    bases.append(Path.home() / ".cache" / "huggingface" / "hub")
+    bases.append(Path.home() / ".cache" / "docling" / "models")
+    return bases
+
+
+def models(req, cfg):
+    """Which model artifacts the pipeline for this decision needs, and which are absent."""
+    import shutil
+
+    overrides = req.get("pipeline_overrides") or {}
+    if overrides:
+        cfg = apply_pipeline_overrides(cfg, overrides)
+    docling_cfg = cfg.get("docling", {})
+    pipeline_cfg = docling_cfg.get("pipeline", {})
+    ocr_cfg = docling_cfg.get("ocr", {})
+    vlm_cfg = docling_cfg.get("vlm", {})
+
+    required = ["layout"]
+    if pipeline_cfg.get("do_table_structure", False):
+        required.append("tableformer")
+    if pipeline_cfg.get("do_code_enrichment", False) or pipeline_cfg.get(
+        "do_formula_enrichment", False
+    ):
+        required.append("code_formula")
+    if pipeline_cfg.get("do_picture_classification", False):
+        required.append("picture_classifier")
+    ocr_engine = ocr_cfg.get("engine", "")
+    if bool(req.get("do_ocr", False)):
+        required.append(f"ocr:{ocr_engine}")
+    vlm_model = vlm_cfg.get("model", "") if vlm_cfg.get("enabled", False) else ""
+    if vlm_model and vlm_cfg.get("provider", "local") == "local":
+        required.append(f"vlm:{vlm_model}")
+
+    bases = artifact_bases(cfg)
+
+    def present(paths):
+        return any((base / rel).exists() for base in bases for rel in paths)
+
+    missing = []
+    downloads = []
+    hf_repos = []
+    for name in required:
+        if name.startswith("ocr:"):
+            if ocr_engine in ("tesseract", "tesseract_cli"):
+                if shutil.which("tesseract") is None:
+                    missing.append(
+                        dict(name=name, detail="tesseract binary not found on PATH")
+                    )
+            elif ocr_engine in MODEL_ARTIFACTS and not present(MODEL_ARTIFACTS[ocr_engine]):
+                missing.append(dict(name=name, detail=f"no {ocr_engine} models found"))
+                downloads.append(ocr_engine)
+        elif name.startswith("vlm:"):
+            repo = vlm_model.replace("/", "--")
+            if not present([repo, f"models--{repo}"]):
+                missing.append(dict(name=name, detail=f"no local snapshot of {vlm_model}"))
+                hf_repos.append(vlm_model)
+        elif not present(MODEL_ARTIFACTS[name]):
+            missing.append(dict(name=name, detail=f"no {name} artifacts found"))
+            downloads.append(name)
+
+    artifacts = cfg.get("paths", {}).get("docling_artifacts_dir")
+    out_arg = f" -o {artifacts}" if artifacts else ""
+    commands = []
+    if downloads:
+        commands.append("docling-tools models download " + " ".join(downloads) + out_arg)
+    for repo in hf_repos:
+        commands.append(f"docling-tools models download-hf-repo {repo}{out_arg}")
+    prefetch = " && ".join(commands) or None
+    return {
+        "required": required,
+        "missing": missing,
+        "searched": [str(b) for b in bases],
+        "prefetch_command": prefetch,
+    }
+
+
+def device(req):
+    """Whether the accelerator device can run models now: torch sees it and a small
+    allocation on it succeeds, which a busy or wedged GPU fails."""
+    name = str(req.get("device", "AUTO")).upper()
+    if name in ("AUTO", "CPU"):
+        return {"device": name, "available": True}
+
+    def unavailable(detail):
+        return {"device": name, "available": False, "detail": detail}
+
+    try:
+        import torch
+    except Exception as exc:
+        return unavailable(f"torch is not importable: {exc}")
+    try:
+        if name.startswith("CUDA"):
+            if not torch.cuda.is_available():
+                return unavailable("torch.cuda.is_available() is false")
+        elif name == "MPS":
+            if not torch.backends.mps.is_available():
+                return unavailable("torch.backends.mps.is_available() is false")
+        target = name.lower()
+        (torch.zeros(1, device=target) + 1).cpu()
+    except Exception as exc:
+        lines = str(exc).strip().splitlines()
+        return unavailable(lines[0] if lines else type(exc).__name__)
+    return {"device": name, "available": True}
+
+
+def docling_version():
+    try:
+        from importlib.metadata import version
+
+        return version("docling")
+    except Exception:
+        return None
+
+
 def convert(req, cfg):
     from docling.document_converter import DocumentConverter, PdfFormatOption
     from docling.datamodel.base_models import InputFormat
@@ -389,7 +587,18 @@ def convert(req, cfg):
     end_page = int(req.get("end_page", 1))
     do_ocr = bool(req.get("do_ocr", False))
     pdf_backend = req.get("pdf_backend", "AUTO")
+    require_backend = bool(req.get("require_pdf_backend", False))
     use_page_range = bool(req.get("use_page_range", False))
+    page_break = req.get("page_break_placeholder") or ""
+    split_spreads = bool(req.get("split_spreads", False))
+    tiles = {
+        int(t["page"]): t
+        for t in req.get("tiles") or []
+        if start_page <= int(t["page"]) <= end_page
+    }
+    overrides = req.get("pipeline_overrides") or {}
+    if overrides:
+        cfg = apply_pipeline_overrides(cfg, overrides)
 
     out_dir.mkdir(parents=True, exist_ok=True)
 
@@ -400,12 +609,19 @@ def convert(req, cfg):
     pipeline_options, applied, ignored = build_pipeline_options(cfg, do_ocr)
 
     backend_cls = resolve_backend_class(pdf_backend)
+    if backend_cls is None and require_backend and pdf_backend not in ("", "AUTO"):
+        raise RuntimeError(f"pdf_backend {pdf_backend} is not available in this docling install")
     if backend_cls is None:
         pdf_opt = PdfFormatOption(pipeline_options=pipeline_options)
     else:
         pdf_opt = PdfFormatOption(pipeline_options=pipeline_options, backend=backend_cls)
 
     converter = DocumentConverter(format_options={InputFormat.PDF: pdf_opt})
+    init_started = time.monotonic()
+    if hasattr(converter, "initialize_pipeline"):
+        converter.initialize_pipeline(InputFormat.PDF)
+    init_seconds = round(time.monotonic() - init_started, 3)
+    print(READY_MARKER, file=sys.stderr, flush=True)
 
     kwargs = {
         "raises_on_error": bool(cfg["docling"].get("raises_on_error", False)),
@@ -417,14 +633,30 @@ def convert(req, cfg):
     if max_file_size > 0:
         kwargs["max_file_size"] = max_file_size
 
-    if use_page_range:
+    source_pdf = input_pdf
+    # Chunk page (from 1) that each page of a cut-up PDF came from.
+    sources = None
+    if split_spreads:
+        source_pdf = str(
+            split_halves(input_pdf, start_page, end_page, use_page_range, out_dir, chunk_index)
+        )
+        sources = [p for p in range(1, end_page - start_page + 2) for _ in range(2)]
+    elif tiles:
+        path, layout = split_tiles(
+            input_pdf, start_page, end_page, use_page_range, tiles, out_dir, chunk_index
+        )
+        source_pdf = str(path)
+        sources = [page for page, _ in layout]
+    elif use_page_range:
         sig = inspect.signature(converter.convert)
         if "page_range" in sig.parameters:
             kwargs["page_range"] = (start_page, end_page)
         else:
             ignored.append("page_range")
 
-    res = converter.convert(input_pdf, **kwargs)
+    convert_started = time.monotonic()
+    res = converter.convert(source_pdf, **kwargs)
+    timings = {"init": init_seconds, "convert": round(time.monotonic() - convert_started, 3)}
 
     warnings = []
     ok = True
@@ -437,12 +669,29 @@ def convert(req, cfg):
         "ignored_flags": ignored,
         "pdf_backend": pdf_backend,
         "use_page_range": use_page_range,
+        "pipeline_overrides": overrides,
+        "ocr_applied": bool(do_ocr),
+        "timings": timings,
+        "docling_version": docling_version(),
     }
 
+    export_started = time.monotonic()
     try:
         doc = res.document
-        if hasattr(doc, "export_to_markdown"):
-            md = doc.export_to_markdown()
+        meta.update(document_counts(doc))
+        if split_spreads and hasattr(doc, "export_to_markdown"):
+            md = export_halves(doc, page_break)
+        elif tiles and hasattr(doc, "export_to_markdown"):
+            md = export_tiles(doc, layout, start_page, page_break)
+        elif hasattr(doc, "export_to_markdown"):
+            md_kwargs = {}
+            if page_break:
+                md_sig = inspect.signature(doc.export_to_markdown)
+                if "page_break_placeholder" in md_sig.parameters:
+                    md_kwargs["page_break_placeholder"] = page_break
+                else:
+                    ignored.append("page_break_placeholder")
+            md = doc.export_to_markdown(**md_kwargs)
         elif hasattr(doc, "export_to_text"):
             md = doc.export_to_text()
             warnings.append("export_to_markdown missing; used export_to_text")
@@ -452,11 +701,246 @@ def convert(req, cfg):
     except Exception as e:
         ok = False
         warnings.append(f"export failed: {e}")
+    timings["export"] = round(time.monotonic() - export_started, 3)
+
+    if split_spreads:
+        meta["split_spreads"] = True
+    if tiles:
+        meta["tiled_pages"] = sorted(tiles)
+    if sources is not None:
+        meta["page_errors"] = merge_pages(
+            collect_page_errors(res, 1, len(sources), True), sources, start_page, use_page_range
+        )
+        meta["page_confidence"] = merge_pages(
+            collect_page_confidence(res), sources, start_page, use_page_range
+        )
+    else:
+        meta["page_errors"] = collect_page_errors(res, start_page, end_page, use_page_range)
+        meta["page_confidence"] = collect_page_confidence(res)
+    if do_ocr and bool(cfg["docling"].get("ocr", {}).get("hybrid", False)):
+        page_ocr = collect_page_ocr(res)
+        if sources is not None:
+            page_ocr = merge_pages(page_ocr, sources, start_page, use_page_range)
+        meta["page_ocr"] = page_ocr
+    for err in getattr(res, "errors", None) or []:
+        warnings.append(f"docling error: {getattr(err, 'error_message', err)}")
 
     out = {"ok": ok, "markdown": md, "warnings": warnings, "meta": meta}
     return out
 
 
+def split_halves(input_pdf, start_page, end_page, use_page_range, out_dir, chunk_index):
+    """Write a PDF with the left and right half of each page as pages of their own, for
+    two-up scans (input.split_spreads)."""
+    from pypdf import PdfReader, PdfWriter
+
+    reader = PdfReader(input_pdf)
+    if use_page_range:
+        pages = range(start_page - 1, end_page)
+    else:
+        pages = range(len(reader.pages))
+    writer = PdfWriter()
+    for i in pages:
+        page = reader.pages[i]
+        box = page.mediabox
+        left, bottom = float(box.left), float(box.bottom)
+        half = float(box.width) / 2
+        for offset in (left, left + half):
+            out = writer.add_blank_page(width=half, height=float(box.height))
+            out.merge_translated_page(page, -offset, -bottom)
+    path = Path(out_dir) / f"chunk_{chunk_index:05d}_halves.pdf"
+    with open(path, "wb") as f:
+        writer.write(f)
+    return path
+
+
+def export_halves(doc, page_break: str) -> str:
+    """Markdown for a document of split halves: each pair of pages becomes one page, with
+    HALF_PAGE_BREAK between its halves."""
+    n_pages = len(getattr(doc, "pages", None) or {})
+    if "page_no" in inspect.signature(doc.export_to_markdown).parameters and n_pages:
+        halves = [doc.export_to_markdown(page_no=n) for n in range(1, n_pages + 1)]
+    else:
+        # Older docling: fall back to its page breaks, which skip empty pages.
+        md = doc.export_to_markdown(page_break_placeholder=HALF_PAGE_BREAK)
+        halves = md.split(HALF_PAGE_BREAK)
+    pages = []
+    for i in range(0, len(halves), 2):
+        pair = halves[i : i + 2]
+        pages.append(f"\n\n{HALF_PAGE_BREAK}\n\n".join(h.strip() for h in pair))
+    return f"\n\n{page_break}\n\n".join(pages)
+
+
+def split_tiles(input_pdf, start_page, end_page, use_page_range, tiles, out_dir, chunk_index):
+    """Write a PDF with each page in `tiles` cut into its grid of tiles, row by row from
+    the top, and the other pages as they are (input.tile_oversized_pages). Returns the
+    path and, per written page, its chunk page and (row, col, rows, cols) or None."""
+    from pypdf import PdfReader, PdfWriter
+
+    reader = PdfReader(input_pdf)
+    first = start_page - 1 if use_page_range else 0
+    writer = PdfWriter()
+    layout = []
+    for n in range(1, end_page - start_page + 2):
+        page = reader.pages[first + n - 1]
+        tile = tiles.get(start_page + n - 1)
+        if tile is None:
+            writer.add_page(page)
+            layout.append((n, None))
+            continue
+        if int(page.get("/Rotate", 0) or 0) % 360 and hasattr(page, "transfer_rotation_to_content"):
+            page.transfer_rotation_to_content()
+        box = page.mediabox
+        rows, cols = int(tile["rows"]), int(tile["cols"])
+        width, height = float(box.width) / cols, float(box.height) / rows
+        for row in range(1, rows + 1):
+            for col in range(1, cols + 1):
+                x = float(box.left) + (col - 1) * width
+                y = float(box.bottom) + (rows - row) * height
+                out = writer.add_blank_page(width=width, height=height)
+                out.merge_translated_page(page, -x, -y)
+                layout.append((n, (row, col, rows, cols)))
+    path = Path(out_dir) / f"chunk_{chunk_index:05d}_tiles.pdf"
+    with open(path, "wb") as f:
+        writer.write(f)
+    return path, layout
+
+
+def export_tiles(doc, layout, start_page: int, page_break: str) -> str:
+    """Markdown for a document with tiled pages: each page's tiles become one page again,
+    each tile opened with a marker giving its place in the grid (postprocess::tile_marker).
+    """
+    n_pages = len(getattr(doc, "pages", None) or {})
+    if "page_no" in inspect.signature(doc.export_to_markdown).parameters and n_pages:
+        parts = [doc.export_to_markdown(page_no=n) for n in range(1, n_pages + 1)]
+    else:
+        # Older docling: fall back to its page breaks, which skip empty pages.
+        parts = doc.export_to_markdown(page_break_placeholder=page_break).split(page_break)
+    pages = {}
+    for (page, tile), part in zip(layout, parts):
+        text = part.strip()
+        if tile is not None:
+            row, col, rows, cols = tile
+            marker = TILE_MARKER.format(
+                row=row, col=col, rows=rows, cols=cols, page=start_page + page - 1
+            )
+            text = f"{marker}\n\n{text}" if text else marker
+        pages.setdefault(page, []).append(text)
+    return f"\n\n{page_break}\n\n".join("\n\n".join(p) for p in pages.values())
+
+
+def merge_pages(entries, sources, start_page: int, use_page_range: bool):
+    """Entries for the pages of a cut-up PDF renumbered to the pages they were cut from
+    (`sources`), first entry per page kept. Pages are then numbered like
+    collect_page_errors."""
+    out = {}
+    for entry in entries:
+        n = int(entry["page"])
+        if not 1 <= n <= len(sources):
+            continue
+        page = sources[n - 1]
+        if use_page_range:
+            page += start_page - 1
+        out.setdefault(page, dict(entry, page=page))
+    return [out[p] for p in sorted(out)]
+
+
+def document_counts(doc):
+    """Page, table and element counts for the meta block; whatever this docling exposes."""
+    out = {}
+    pages = getattr(doc, "pages", None)
+    if pages is not None:
+        out["pages_processed"] = len(pages)
+        try:
+            numbers = [int(p) for p in pages.keys()]
+        except Exception:
+            numbers = []
+        if numbers:
+            out["page_span"] = [min(numbers), max(numbers)]
+    counts = {}
+    for kind in ("texts", "tables", "pictures", "groups", "key_value_items", "form_items"):
+        items = getattr(doc, kind, None)
+        if isinstance(items, (list, tuple)):
+            counts[kind] = len(items)
+    if counts:
+        out["element_counts"] = counts
+    if "tables" in counts:
+        out["table_count"] = counts["tables"]
+    return out
+
+
+def collect_page_errors(res, start_page: int, end_page: int, use_page_range: bool):
+    """Pages docling dropped from a partially successful conversion.
+
+    Page numbers are relative to the PDF docling opened, matching what pdf_text.py reports.
+    """
+    status = str(getattr(res, "status", ""))
+    if "PARTIAL" not in status.upper():
+        return []
+    reasons = [str(getattr(e, "error_message", e)) for e in (getattr(res, "errors", None) or [])]
+    reason = "; ".join(reasons) or "page dropped by docling"
+    try:
+        present = set(res.document.pages.keys())
+    except Exception:
+        return []
+    if use_page_range:
+        expected = range(start_page, end_page + 1)
+    else:
+        expected = range(1, end_page - start_page + 2)
+    return [{"page": p, "reason": reason} for p in expected if p not in present]
+
+
+def collect_page_confidence(res):
+    """Docling's per-page confidence scores, when this docling computes them.
+
+    Pages are numbered within the PDF docling opened, like collect_page_errors. NaN
+    scores (not computed) are dropped so the output stays valid JSON.
+    """
+    pages = getattr(getattr(res, "confidence", None), "pages", None)
+    if not pages:
+        return []
+    out = []
+    for page_no, scores in sorted(pages.items()):
+        entry = {"page": int(page_no) + 1}
+        for name in ("ocr_score", "parse_score", "layout_score", "mean_score"):
+            value = getattr(scores, name, None)
+            try:
+                value = float(value)
+            except (TypeError, ValueError):
+                continue
+            if math.isfinite(value):
+                entry[name] = round(value, 4)
+        out.append(entry)
+    return out
+
+
+def collect_page_ocr(res):
+    """Where each page's text came from under hybrid OCR: "text" when every cell is
+    from the text layer, "ocr" when every cell is from OCR, "mixed", or "empty".
+
+    Pages are numbered within the PDF docling opened, like collect_page_errors.
+    """
+    out = []
+    for page in getattr(res, "pages", None) or []:
+        try:
+            page_no = int(page.page_no) + 1
+            cells = list(getattr(page, "cells", None) or [])
+        except Exception:
+            continue
+        ocr = sum(1 for c in cells if getattr(c, "from_ocr", False))
+        text = len(cells) - ocr
+        if ocr and text:
+            mode = "mixed"
+        elif ocr:
+            mode = "ocr"
+        elif text:
+            mode = "text"
+        else:
+            mode = "empty"
+        out.append({"page": page_no, "mode": mode})
+    return out
+
+
 def emit_and_exit(payload, code=0):
     # Avoid Python 3.14 multiprocessing teardown hangs by exiting immediately
     # after flushing the JSON response that Rust expects on stdout.
@@ -475,6 +959,10 @@ def main():
             emit_and_exit(doctor(), 0)
         if cmd == "convert":
             emit_and_exit(convert(payload.get("req", {}), payload.get("cfg", {})), 0)
+        if cmd == "models":
+            emit_and_exit(models(payload.get("req", {}), payload.get("cfg", {})), 0)
+        if cmd == "device":
+            emit_and_exit(device(payload.get("req", {})), 0)
 
         emit_and_exit({"ok": False, "error": f"unknown cmd: {cmd}"}, 1)
     except Exception as e:
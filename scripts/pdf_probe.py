@@ -1,5 +1,8 @@
 #!/usr/bin/env python3
+import hashlib
 import json
+import logging
+import math
 import re
 import sys
 from pathlib import Path
@@ -16,18 +19,393 @@ except Exception:
     pdfium = None
 
 GARBAGE_RE = re.compile(r"[\uFFFD]")
+CELL_GAP_RE = re.compile(r"\S(?: {2,}|\t+)(?=\S)")
+
+# Lines inspected at each page edge when looking for tables that cross a page break.
+EDGE_LINES = 4
+
+# Width/height above which a page may hold two book pages side by side.
+SPREAD_MIN_ASPECT = 1.2
+# A gutter column carries at most this fraction of the ink of the columns either side.
+GUTTER_MAX_INK = 0.25
+# Characters of each sampled page sent back for quack-check's garbage scoring.
+SAMPLE_TEXT_CHARS = 4000
+# Render width used to look for the gutter.
+GUTTER_RENDER_PX = 200
+# Pages with a larger area than A3 (in points) are reported as large pages.
+LARGE_PAGE_AREA_PT = 842 * 1191
+
+# pypdf warnings that mean it had to rebuild a broken cross-reference table.
+XREF_WARNING_RE = re.compile(r"xref", re.I)
+
+
+class CollectWarnings(logging.Handler):
+    def __init__(self):
+        super().__init__(logging.WARNING)
+        self.messages = []
+
+    def emit(self, record):
+        self.messages.append(record.getMessage())
+
+
+def resolve(obj):
+    try:
+        return obj.get_object()
+    except Exception:
+        return obj
+
+
+def page_structure(page):
+    """Fonts, image XObjects, and annotation count for one pypdf page."""
+    fonts = set()
+    n_images = 0
+    n_annots = 0
+    try:
+        resources = resolve(page.get("/Resources")) or {}
+        font_dict = resolve(resources.get("/Font")) or {}
+        for ref in font_dict.values():
+            font = resolve(ref)
+            name = str(font.get("/BaseFont", "")).lstrip("/")
+            # Drop subset prefixes such as "ABCDEF+Times-Roman".
+            if len(name) > 7 and name[6] == "+":
+                name = name[7:]
+            if name:
+                fonts.add(name)
+        xobjects = resolve(resources.get("/XObject")) or {}
+        for ref in xobjects.values():
+            if resolve(ref).get("/Subtype") == "/Image":
+                n_images += 1
+    except Exception:
+        pass
+    try:
+        n_annots = len(resolve(page.get("/Annots")) or [])
+    except Exception:
+        pass
+    return fonts, n_images, n_annots
+
+
+def open_reader(input_pdf: Path):
+    """A pypdf reader, and whether pypdf had to repair the cross-reference table."""
+    handler = CollectWarnings()
+    log = logging.getLogger("pypdf")
+    log.addHandler(handler)
+    try:
+        reader = PdfReader(str(input_pdf))
+        len(reader.pages)
+    finally:
+        log.removeHandler(handler)
+    return reader, any(XREF_WARNING_RE.search(m) for m in handler.messages)
+
+
+def is_javascript_action(action) -> bool:
+    """A /JavaScript action, or one chained after another action via /Next."""
+    seen = 0
+    while action is not None and seen < 32:
+        action = resolve(action)
+        if isinstance(action, list):
+            return any(is_javascript_action(a) for a in action)
+        try:
+            if action.get("/S") == "/JavaScript" or action.get("/JS") is not None:
+                return True
+            action = action.get("/Next")
+        except Exception:
+            return False
+        seen += 1
+    return False
+
+
+def has_javascript_triggers(obj) -> bool:
+    """An /A action or /AA additional-actions dictionary that runs JavaScript."""
+    try:
+        if is_javascript_action(obj.get("/A")):
+            return True
+        triggers = resolve(obj.get("/AA")) or {}
+        return any(is_javascript_action(a) for a in triggers.values())
+    except Exception:
+        return False
+
+
+def risky_features(reader, xref_repaired: bool):
+    """Features security-conscious deployments may not want parsed: XFA forms, form
+    fields, embedded JavaScript, file attachments, and a repaired xref table."""
+    found = set()
+    if xref_repaired:
+        found.add("xref_repaired")
+    try:
+        root = resolve(reader.trailer["/Root"])
+        acro = resolve(root.get("/AcroForm")) or {}
+        if acro.get("/XFA") is not None:
+            found.add("xfa")
+        if resolve(acro.get("/Fields")):
+            found.add("forms")
+        names = resolve(root.get("/Names")) or {}
+        if names.get("/JavaScript") is not None:
+            found.add("javascript")
+        if names.get("/EmbeddedFiles") is not None:
+            found.add("attachments")
+        if is_javascript_action(root.get("/OpenAction")) or has_javascript_triggers(root):
+            found.add("javascript")
+    except Exception:
+        pass
+    for page in reader.pages:
+        try:
+            if has_javascript_triggers(page):
+                found.add("javascript")
+            for ref in resolve(page.get("/Annots")) or []:
+                annot = resolve(ref)
+                if annot.get("/Subtype") == "/FileAttachment":
+                    found.add("attachments")
+                if has_javascript_triggers(annot):
+                    found.add("javascript")
+        except Exception:
+            continue
+    return sorted(found)
+
+
+def strip_javascript(obj) -> None:
+    try:
+        if is_javascript_action(obj.get("/A")):
+            del obj["/A"]
+        if "/AA" in obj:
+            del obj["/AA"]
+    except Exception:
+        pass
+
+
+def strip_features(input_pdf: Path, out_pdf: Path, features) -> dict:
+    """Write a copy of the PDF without the given features."""
+    try:
+        from pypdf import PdfWriter
+        from pypdf.generic import NameObject
+
+        writer = PdfWriter(clone_from=str(input_pdf))
+        root = writer._root_object
+        names = resolve(root.get("/Names")) or {}
+        if "javascript" in features:
+            if "/JavaScript" in names:
+                del names[NameObject("/JavaScript")]
+            if is_javascript_action(root.get("/OpenAction")):
+                del root[NameObject("/OpenAction")]
+            strip_javascript(root)
+        if "attachments" in features and "/EmbeddedFiles" in names:
+            del names[NameObject("/EmbeddedFiles")]
+        if "xfa" in features:
+            acro = resolve(root.get("/AcroForm"))
+            if acro is not None and "/XFA" in acro:
+                del acro[NameObject("/XFA")]
+        for page in writer.pages:
+            if "javascript" in features:
+                strip_javascript(page)
+            annots = resolve(page.get("/Annots"))
+            if not annots:
+                continue
+            kept = []
+            for ref in annots:
+                annot = resolve(ref)
+                if "attachments" in features and annot.get("/Subtype") == "/FileAttachment":
+                    continue
+                if "javascript" in features:
+                    strip_javascript(annot)
+                kept.append(ref)
+            annots.clear()
+            annots.extend(kept)
+        with open(out_pdf, "wb") as f:
+            writer.write(f)
+    except Exception as e:
+        return dict(ok=False, error=f"failed to strip {', '.join(features)}: {e}")
+    return dict(ok=True)
+
+
+def count_pdfium_images(page) -> int:
+    try:
+        import pypdfium2.raw as pdfium_c
+
+        return sum(1 for _ in page.get_objects(filter=[pdfium_c.FPDF_PAGEOBJ_IMAGE]))
+    except Exception:
+        return 0
+
+
+def page_size(reader, doc, i: int):
+    """Width and height in points as displayed, after /Rotate; None when unreadable."""
+    try:
+        if reader is not None:
+            box = reader.pages[i].mediabox
+            width, height = float(box.width), float(box.height)
+            if int(reader.pages[i].get("/Rotate", 0) or 0) % 180:
+                width, height = height, width
+        else:
+            width, height = doc[i].get_size()
+        return width, height
+    except Exception:
+        return None
+
+
+def page_aspect(reader, doc, i: int) -> float:
+    size = page_size(reader, doc, i)
+    if size is None or size[1] <= 0:
+        return 0.0
+    return size[0] / size[1]
+
+
+def large_pages(reader, doc, n_pages: int):
+    """Every page larger than A3, for quack-check to tile the ones too large to OCR."""
+    out = []
+    for i in range(n_pages):
+        size = page_size(reader, doc, i)
+        if size is not None and size[0] * size[1] > LARGE_PAGE_AREA_PT:
+            out.append(dict(page=i + 1, width_pt=round(size[0], 1), height_pt=round(size[1], 1)))
+    return out
+
+
+def has_center_gutter(doc, i: int):
+    """Whether a rendered page has a vertical band near the middle with far less ink
+    than the two halves; None when the page cannot be rendered."""
+    if doc is None:
+        return None
+    try:
+        page = doc[i]
+        width, _ = page.get_size()
+        bitmap = page.render(scale=GUTTER_RENDER_PX / max(1.0, width), grayscale=True)
+        w, h, stride = bitmap.width, bitmap.height, bitmap.stride
+        channels = getattr(bitmap, "n_channels", 1) or 1
+        buf = bytes(bitmap.buffer)
+        page.close()
+    except Exception:
+        return None
+    if w < 20 or h < 20:
+        return None
+    ink = []
+    for x in range(w):
+        dark = 0
+        for y in range(0, h, 2):
+            if buf[y * stride + x * channels] < 128:
+                dark += 1
+        ink.append(dark)
+    center = ink[int(w * 0.45) : int(w * 0.55) + 1]
+    sides = sorted(ink[int(w * 0.1) : int(w * 0.4)] + ink[int(w * 0.6) : int(w * 0.9)])
+    typical = sides[len(sides) // 2] if sides else 0
+    if typical == 0:
+        return False
+    return min(center) <= typical * GUTTER_MAX_INK
+
+
+def looks_like_spread(reader, doc, i: int) -> bool:
+    """A landscape page with a gutter down the middle; aspect alone when it cannot be
+    rendered."""
+    if page_aspect(reader, doc, i) < SPREAD_MIN_ASPECT:
+        return False
+    gutter = has_center_gutter(doc, i)
+    return True if gutter is None else gutter
+
+
+def count_form_fields(reader) -> int:
+    if reader is None:
+        return 0
+    try:
+        return len(reader.get_fields() or {})
+    except Exception:
+        return 0
+
+
+def sample_count(n_pages: int, floor: int, percent: float, cap: int) -> int:
+    """`floor` pages, raised to `percent` of the document (at most `cap`) for long PDFs."""
+    scaled = math.ceil(n_pages * percent / 100.0) if percent > 0 else 0
+    return max(1, floor, min(scaled, cap))
+
+
+def is_table_line(line: str) -> bool:
+    """A line with at least three whitespace-separated columns in layout-mode text."""
+    return len(CELL_GAP_RE.findall(line)) >= 2
+
+
+def edge_is_tabular(lines) -> bool:
+    edge = lines[:EDGE_LINES]
+    return bool(edge) and sum(1 for l in edge if is_table_line(l)) * 2 > len(edge)
+
+
+def page_layout(input_pdf: Path, pages) -> dict:
+    """Whether tables touch the top/bottom of each requested 1-based page."""
+    if PdfReader is None:
+        return dict(pages=[])
+    try:
+        reader = PdfReader(str(input_pdf))
+    except Exception as e:
+        return dict(pages=[], error=f"failed to read pdf: {e}")
+    out = []
+    for n in pages:
+        if n < 1 or n > len(reader.pages):
+            continue
+        try:
+            txt = reader.pages[n - 1].extract_text(extraction_mode="layout") or ""
+        except Exception:
+            txt = ""
+        lines = [l for l in txt.splitlines() if l.strip()]
+        out.append(
+            dict(
+                page=n,
+                table_at_top=edge_is_tabular(lines),
+                table_at_bottom=edge_is_tabular(lines[::-1]),
+            )
+        )
+    return dict(pages=out)
+
+
+def page_hashes(input_pdf: Path) -> dict:
+    """sha256 per page over its whitespace-normalized text and the data of its images,
+    so a page matches across two files only when both its text and its scan do."""
+    if PdfReader is None:
+        return dict(pages=[], error="missing pypdf import")
+    try:
+        reader = PdfReader(str(input_pdf))
+    except Exception as e:
+        return dict(pages=[], error=f"failed to read pdf: {e}")
+    out = []
+    for page in reader.pages:
+        h = hashlib.sha256()
+        try:
+            text = page.extract_text() or ""
+        except Exception:
+            text = ""
+        h.update(" ".join(text.split()).encode("utf-8"))
+        try:
+            resources = resolve(page.get("/Resources")) or {}
+            xobjects = resolve(resources.get("/XObject")) or {}
+            for name in sorted(xobjects.keys()):
+                obj = resolve(xobjects[name])
+                if obj.get("/Subtype") == "/Image":
+                    h.update(b"\0image\0")
+                    h.update(obj.get_data())
+        except Exception:
+            pass
+        out.append(h.hexdigest())
+    return dict(pages=out)
 
 
 def main() -> None:
     req = json.loads(sys.stdin.read().strip() or "{}")
     input_pdf = Path(req["input_pdf"])
+    if "layout_pages" in req:
+        print(json.dumps(page_layout(input_pdf, [int(p) for p in req["layout_pages"]])))
+        return
+    if req.get("page_hashes"):
+        print(json.dumps(page_hashes(input_pdf)))
+        return
+    if "strip_features" in req:
+        if PdfReader is None:
+            print(json.dumps(dict(ok=False, error="missing pypdf import")))
+            return
+        print(json.dumps(strip_features(input_pdf, Path(req["out_pdf"]), req["strip_features"])))
+        return
     sample_pages = int(req.get("sample_pages", 12))
+    sample_pages_percent = float(req.get("sample_pages_percent", 0.0))
+    max_sample_pages = int(req.get("max_sample_pages", sample_pages))
 
     reader = None
     doc = None
+    xref_repaired = False
     if PdfReader is not None:
         try:
-            reader = PdfReader(str(input_pdf))
+            reader, xref_repaired = open_reader(input_pdf)
         except Exception as e:
             print(
                 json.dumps(
@@ -86,7 +464,17 @@ def main() -> None:
         print(json.dumps(out))
         return
 
-    k = min(sample_pages, n_pages)
+    k = min(
+        sample_count(n_pages, sample_pages, sample_pages_percent, max_sample_pages),
+        n_pages,
+    )
+    # Rendering for the gutter check needs pypdfium2 even when pypdf reads the text.
+    render_doc = doc
+    if render_doc is None and pdfium is not None:
+        try:
+            render_doc = pdfium.PdfDocument(str(input_pdf))
+        except Exception:
+            render_doc = None
     idxs = []
     if k == 1:
         idxs = [0]
@@ -97,34 +485,70 @@ def main() -> None:
     total_chars = 0
     total_ws = 0
     total_garbage = 0
+    pages_with_text = 0
+    fonts = set()
+    images_total = 0
+    max_images = 0
+    annotations = 0
+    spread_pages = 0
+    sample_texts = []
 
     for i in idxs:
+        if looks_like_spread(reader, render_doc, i):
+            spread_pages += 1
         if reader is not None:
-            txt = reader.pages[i].extract_text() or ""
+            page = reader.pages[i]
+            txt = page.extract_text() or ""
+            page_fonts, n_images, n_annots = page_structure(page)
+            fonts.update(page_fonts)
         else:
             page = doc[i]
             text_page = page.get_textpage()
             txt = text_page.get_text_range() or ""
             text_page.close()
+            n_images = count_pdfium_images(page)
+            n_annots = 0
             page.close()
         total_chars += len(txt)
+        sample_texts.append(txt[:SAMPLE_TEXT_CHARS])
         total_ws += sum(1 for c in txt if c.isspace())
         total_garbage += len(GARBAGE_RE.findall(txt))
+        if txt.strip():
+            pages_with_text += 1
+        images_total += n_images
+        max_images = max(max_images, n_images)
+        annotations += n_annots
 
     avg = int(total_chars / max(1, len(idxs)))
     garbage_ratio = float(total_garbage / max(1, total_chars))
     whitespace_ratio = float(total_ws / max(1, total_chars))
 
+    structure = dict(
+        has_text_layer=pages_with_text > 0,
+        pages_with_text=pages_with_text,
+        fonts=sorted(fonts),
+        font_count=len(fonts),
+        images_per_page=float(images_total / max(1, len(idxs))),
+        max_images_on_page=max_images,
+        annotation_count=annotations,
+        form_field_count=count_form_fields(reader),
+        risky_features=risky_features(reader, xref_repaired) if reader is not None else [],
+        spread_pages=spread_pages,
+        large_pages=large_pages(reader, doc, n_pages),
+    )
+
     out = dict(
         page_count=n_pages,
         sampled_pages=len(idxs),
         avg_chars_per_page=avg,
         garbage_ratio=garbage_ratio,
         whitespace_ratio=whitespace_ratio,
+        structure=structure,
+        sample_texts=sample_texts,
     )
     print(json.dumps(out))
-    if doc is not None:
-        doc.close()
+    if render_doc is not None:
+        render_doc.close()
 
 
 if __name__ == "__main__":
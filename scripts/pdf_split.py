@@ -15,6 +15,7 @@ def main() -> None:
     input_pdf = Path(req["input_pdf"])
     out_dir = Path(req["out_dir"])
     chunks = req.get("chunks", [])
+    on_page_error = req.get("on_page_error", "fail")
 
     try:
         reader = PdfReader(str(input_pdf))
@@ -41,8 +42,17 @@ def main() -> None:
             return
 
         w = PdfWriter()
+        page_errors = []
         for p in range(s - 1, e):
-            w.add_page(reader.pages[p])
+            if on_page_error == "fail":
+                w.add_page(reader.pages[p])
+                continue
+            try:
+                w.add_page(reader.pages[p])
+            except Exception as ex:
+                # Keep a blank stand-in so page numbering inside the chunk stays aligned.
+                w.add_blank_page(width=612, height=792)
+                page_errors.append({"page": p + 1, "reason": str(ex)})
 
         out_path = out_dir / f"chunk_{i:05d}_p{s:05d}-p{e:05d}.pdf"
         with out_path.open("wb") as f:
@@ -54,6 +64,7 @@ def main() -> None:
                 "start_page": s,
                 "end_page": e,
                 "path": str(out_path),
+                "page_errors": page_errors,
             }
         )
 
@@ -2,6 +2,7 @@
 import json
 import re
 import sys
+import time
 import unicodedata
 from pathlib import Path
 
@@ -18,6 +19,37 @@ except Exception:
 
 HYPHEN_RE = re.compile(r"(\w)-\n(\w)")
 SPACE_RE = re.compile(r"[\t\f\r ]+")
+# Han, kana, and CJK/fullwidth punctuation; mirrors `script::is_cjk` on the Rust side.
+CJK = "\u3000-\u30ff\u31f0-\u31ff\u3400-\u4dbf\u4e00-\u9fff\uf900-\ufaff\uff01-\uff9f"
+CJK_SPACE_RE = re.compile(rf"(?<=[{CJK}]) +(?=[{CJK}])")
+LTR_CLASSES = ("L", "EN", "AN")
+
+
+def visual_to_logical(line: str) -> str:
+    """Reorder a line stored in visual order: reverse it, then turn runs of
+    left-to-right text (Latin words, numbers) back around."""
+    if not any(unicodedata.bidirectional(c) in ("R", "AL") for c in line):
+        return line
+    rev = line[::-1]
+    out = []
+    i = 0
+    while i < len(rev):
+        if unicodedata.bidirectional(rev[i]) not in LTR_CLASSES:
+            out.append(rev[i])
+            i += 1
+            continue
+        # Extend over spaces and punctuation only while more LTR text follows.
+        j = end = i
+        while j < len(rev):
+            cls = unicodedata.bidirectional(rev[j])
+            if cls in LTR_CLASSES:
+                end = j + 1
+            elif cls not in ("WS", "CS", "ES", "ET"):
+                break
+            j += 1
+        out.append(rev[i:end][::-1])
+        i = end
+    return "".join(out)
 
 
 def normalize_text(text: str, cfg: dict) -> str:
@@ -25,15 +57,29 @@ def normalize_text(text: str, cfg: dict) -> str:
         text = unicodedata.normalize("NFKC", text)
     if cfg.get("fix_hyphenation", False):
         text = HYPHEN_RE.sub(r"\1\2", text)
+    if cfg.get("rtl_visual_order", False):
+        text = "\n".join(visual_to_logical(line) for line in text.splitlines())
     if cfg.get("collapse_whitespace", False):
         lines = []
         for line in text.splitlines():
             line = SPACE_RE.sub(" ", line).strip()
+            line = CJK_SPACE_RE.sub("", line)
             lines.append(line)
         text = "\n".join(lines)
     return text
 
 
+def extract_page(reader, doc, page_index: int) -> str:
+    if reader is not None:
+        return reader.pages[page_index].extract_text() or ""
+    page = doc[page_index]
+    text_page = page.get_textpage()
+    text = text_page.get_text_range() or ""
+    text_page.close()
+    page.close()
+    return text
+
+
 def convert(req: dict, cfg: dict) -> None:
     input_pdf = Path(req["input_pdf"])
     start_page = int(req.get("start_page", 1))
@@ -86,28 +132,43 @@ def convert(req: dict, cfg: dict) -> None:
             )
             return
 
+    on_page_error = req.get("on_page_error", "fail")
+    page_errors = []
     parts = []
+    extract_started = time.monotonic()
     for page_index in range(start_page - 1, end_page):
-        if reader is not None:
-            text = reader.pages[page_index].extract_text() or ""
-        else:
-            page = doc[page_index]
-            text_page = page.get_textpage()
-            text = text_page.get_text_range() or ""
-            text_page.close()
-            page.close()
+        try:
+            text = extract_page(reader, doc, page_index)
+        except Exception as e:
+            if on_page_error == "fail":
+                raise
+            # Keep an empty page so page breaks stay aligned; Rust applies the policy.
+            page_errors.append({"page": page_index + 1, "reason": str(e)})
+            text = ""
         text = normalize_text(text, native_cfg)
         if light_md:
             parts.append(f"## Page {page_index + 1}\n\n{text}")
         else:
             parts.append(text)
 
-    markdown = "\n\n".join(parts)
+    page_break = req.get("page_break_placeholder") or ""
+    if page_break:
+        markdown = f"\n\n{page_break}\n\n".join(parts)
+    else:
+        markdown = "\n\n".join(parts)
     out = {
         "ok": True,
         "markdown": markdown,
         "warnings": warnings,
-        "meta": {"start_page": start_page, "end_page": end_page, "engine": "native_text"},
+        "meta": {
+            "start_page": start_page,
+            "end_page": end_page,
+            "engine": "native_text",
+            "pages_processed": end_page - start_page + 1,
+            "ocr_applied": False,
+            "timings": {"extract": round(time.monotonic() - extract_started, 3)},
+            "page_errors": page_errors,
+        },
     }
     print(json.dumps(out))
     if doc is not None:
@@ -0,0 +1,128 @@
+use crate::{cancel::JobControl, config::Config, engine::Engine, policy::PolicyDecision};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// First and longest wait between device checks with `on_unavailable = "wait"`.
+const FIRST_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// How often a waiting job checks whether it was cancelled.
+const POLL: Duration = Duration::from_millis(200);
+
+/// The accelerator a docling job ran on, recorded under `accelerator` in the report
+/// when `docling.accelerator.device` names a specific device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AcceleratorUse {
+    /// `docling.accelerator.device`.
+    pub requested: String,
+    /// The device chunks were converted on: `requested`, or "CPU" after a fallback.
+    pub used: String,
+    /// Time spent waiting for the device to become available.
+    #[serde(default)]
+    pub waited_seconds: f64,
+    /// Why the requested device was not used, or the last reason it was waited for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl AcceleratorUse {
+    pub fn fell_back(&self) -> bool {
+        self.used != self.requested
+    }
+}
+
+/// Check that the configured accelerator can run a docling job before any chunk is
+/// converted, and handle an unavailable one per `docling.accelerator.on_unavailable`:
+/// fail, wait with exponential backoff up to `wait_max_seconds`, or switch the job to
+/// CPU through an `accelerator.device` override in `decision`. Jobs not routed to
+/// docling, and AUTO or CPU devices, are not checked.
+pub fn preflight<E: Engine + ?Sized>(
+    cfg: &Config,
+    engine: &E,
+    decision: &mut PolicyDecision,
+    control: &JobControl,
+) -> Result<Option<AcceleratorUse>> {
+    let acc = &cfg.docling.accelerator;
+    let mode = acc.on_unavailable.as_str();
+    let requested = acc.device.to_uppercase();
+    if decision.chosen_engine != "docling" || matches!(requested.as_str(), "AUTO" | "CPU" | "") {
+        return Ok(None);
+    }
+
+    let started = Instant::now();
+    let max_wait = Duration::from_secs(acc.wait_max_seconds);
+    let mut backoff = FIRST_BACKOFF;
+    loop {
+        let detail = match engine.check_device(&requested) {
+            Ok(check) if check.available => {
+                info!("accelerator {requested} available");
+                return Ok(Some(AcceleratorUse {
+                    used: requested.clone(),
+                    requested,
+                    waited_seconds: started.elapsed().as_secs_f64(),
+                    reason: None,
+                }));
+            }
+            Ok(check) => check.detail.unwrap_or_else(|| "unavailable".into()),
+            Err(err) => format!("device check failed: {err:#}"),
+        };
+        match mode {
+            "cpu" => {
+                warn!("accelerator {requested} unavailable ({detail}); converting on CPU");
+                decision
+                    .pipeline_overrides
+                    .insert("accelerator.device".into(), "CPU".into());
+                return Ok(Some(AcceleratorUse {
+                    requested,
+                    used: "CPU".into(),
+                    waited_seconds: started.elapsed().as_secs_f64(),
+                    reason: Some(detail),
+                }));
+            }
+            "wait" if started.elapsed() + backoff <= max_wait => {
+                warn!("accelerator {requested} unavailable ({detail}); retrying in {backoff:?}");
+                let until = Instant::now() + backoff;
+                while Instant::now() < until {
+                    control.check()?;
+                    std::thread::sleep(POLL.min(until.saturating_duration_since(Instant::now())));
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            "wait" => {
+                return Err(anyhow!(
+                    "accelerator {requested} still unavailable after {}s: {detail}",
+                    started.elapsed().as_secs()
+                ));
+            }
+            _ => return Err(anyhow!("accelerator {requested} is unavailable: {detail}")),
+        }
+    }
+}
+
+pub fn validate(cfg: &Config) -> Result<()> {
+    let mode = cfg.docling.accelerator.on_unavailable.as_str();
+    if !matches!(mode, "fail" | "wait" | "cpu") {
+        return Err(anyhow!(
+            "unknown docling.accelerator.on_unavailable: {mode} (expected fail, wait or cpu)"
+        ));
+    }
+    Ok(())
+}
+
+/// `cfg` with chunks capped at `docling.accelerator.cpu_max_pages_per_chunk`, for
+/// planning a job that fell back to CPU.
+pub fn cpu_chunking(cfg: &Config) -> Config {
+    let mut cfg = cfg.clone();
+    let cap = cfg.docling.accelerator.cpu_max_pages_per_chunk;
+    if cap > 0 {
+        let chunking = &mut cfg.chunking;
+        chunking.max_pages_per_chunk = chunking.max_pages_per_chunk.min(cap);
+        chunking.target_pages_per_chunk = chunking.target_pages_per_chunk.min(cap);
+        chunking.min_pages_per_chunk = chunking.min_pages_per_chunk.min(cap);
+        // Inputs longer than one CPU chunk are chunked whatever their size.
+        let limits = &mut cfg.limits;
+        limits.require_chunking_over_pages = limits.require_chunking_over_pages.min(cap);
+    }
+    cfg
+}
@@ -0,0 +1,169 @@
+use crate::{config::Config, util::ensure_dir};
+use anyhow::{anyhow, Context, Result};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Whether `path` names a compressed input: `*.gz` (usually `*.pdf.gz`) or `*.zip`.
+pub fn is_compressed(path: &Path) -> bool {
+    compression(path).is_some()
+}
+
+fn compression(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "gz" => Some("gzip"),
+        "zip" => Some("zip"),
+        _ => None,
+    }
+}
+
+/// Whether `path` is something quack-check can convert: a PDF or a compressed one.
+pub fn is_supported_input(path: &Path) -> bool {
+    is_compressed(path)
+        || path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+/// The PDF a job actually reads. For compressed inputs this is a decompressed copy
+/// under `paths.work_dir`, removed again when the value is dropped.
+#[derive(Debug)]
+pub struct PreparedInput {
+    pub path: PathBuf,
+    temp_dir: Option<PathBuf>,
+}
+
+impl Drop for PreparedInput {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.temp_dir
+            && let Err(err) = std::fs::remove_dir_all(dir)
+        {
+            warn!(
+                "failed to remove decompressed input {}: {err}",
+                dir.display()
+            );
+        }
+    }
+}
+
+/// Decompress `input` into `work_dir/inputs/` if it is a `.gz` or a `.zip` holding
+/// exactly one PDF; plain PDFs are used in place. The decompressed size is capped at
+/// `limits.max_input_file_bytes`, and the content must start like a PDF.
+pub fn prepare_input(cfg: &Config, input: &Path) -> Result<PreparedInput> {
+    let Some(kind) = compression(input) else {
+        return Ok(PreparedInput {
+            path: input.to_path_buf(),
+            temp_dir: None,
+        });
+    };
+    let temp_dir = Path::new(&cfg.paths.work_dir).join("inputs").join(format!(
+        "{}-{}",
+        std::process::id(),
+        unique_suffix(input)
+    ));
+    ensure_dir(&temp_dir)?;
+    // Own the directory before anything can fail so it is cleaned up either way.
+    let mut prepared = PreparedInput {
+        path: PathBuf::new(),
+        temp_dir: Some(temp_dir.clone()),
+    };
+
+    let file = std::fs::File::open(input).with_context(|| format!("open {}", input.display()))?;
+    let limit = cfg.limits.max_input_file_bytes;
+    let path = match kind {
+        "gzip" => {
+            let name = input
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "input".into());
+            let out = temp_dir.join(with_pdf_extension(&name));
+            let reader = flate2::read::MultiGzDecoder::new(std::io::BufReader::new(file));
+            write_limited(reader, &out, limit)
+                .with_context(|| format!("decompress {}", input.display()))?;
+            out
+        }
+        _ => {
+            let mut archive = zip::ZipArchive::new(file)
+                .with_context(|| format!("read zip archive {}", input.display()))?;
+            let pdfs: Vec<usize> = (0..archive.len())
+                .filter(|&i| {
+                    archive.by_index(i).is_ok_and(|entry| {
+                        let name = entry.name();
+                        entry.is_file()
+                            && !name.starts_with("__MACOSX/")
+                            && name.to_ascii_lowercase().ends_with(".pdf")
+                    })
+                })
+                .collect();
+            let index = match pdfs.as_slice() {
+                [one] => *one,
+                [] => return Err(anyhow!("zip archive contains no PDF: {}", input.display())),
+                many => {
+                    return Err(anyhow!(
+                        "zip archive contains {} PDFs; expected exactly one: {}",
+                        many.len(),
+                        input.display()
+                    ));
+                }
+            };
+            let entry = archive.by_index(index)?;
+            let name = Path::new(entry.name())
+                .file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "input.pdf".into());
+            let out = temp_dir.join(name);
+            write_limited(entry, &out, limit)
+                .with_context(|| format!("extract {}", input.display()))?;
+            out
+        }
+    };
+
+    let mut magic = [0u8; 5];
+    let n = std::fs::File::open(&path)?.read(&mut magic)?;
+    if &magic[..n] != b"%PDF-" {
+        return Err(anyhow!(
+            "decompressed {} is not a PDF: {}",
+            kind,
+            input.display()
+        ));
+    }
+    info!(
+        "decompressed {} input {} to {}",
+        kind,
+        input.display(),
+        path.display()
+    );
+    prepared.path = path;
+    Ok(prepared)
+}
+
+fn with_pdf_extension(name: &str) -> String {
+    if name.to_ascii_lowercase().ends_with(".pdf") {
+        name.to_string()
+    } else {
+        format!("{name}.pdf")
+    }
+}
+
+/// Distinguishes concurrent preparations of different inputs within one process.
+fn unique_suffix(input: &Path) -> String {
+    crate::util::sha256_hex(input.display().to_string().as_bytes())
+        .chars()
+        .take(12)
+        .collect()
+}
+
+fn write_limited(reader: impl Read, out: &Path, limit: u64) -> Result<()> {
+    let mut file =
+        std::fs::File::create(out).with_context(|| format!("create {}", out.display()))?;
+    let written = std::io::copy(&mut reader.take(limit.saturating_add(1)), &mut file)?;
+    if written > limit {
+        return Err(anyhow!(
+            "decompressed input exceeds max_input_file_bytes: more than {limit} bytes"
+        ));
+    }
+    file.flush()?;
+    Ok(())
+}
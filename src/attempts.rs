@@ -0,0 +1,172 @@
+use crate::{
+    engine::limits,
+    exit_code::{self, ErrorClass},
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Lines of stderr kept per attempt.
+const STDERR_EXCERPT_LINES: usize = 20;
+
+/// One failed try at converting (part of) a chunk, so a chunk left as placeholders can
+/// be remedied by hand without rerunning it to see what went wrong.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    /// "docling" or "native_text".
+    pub engine: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_backend: Option<String>,
+    /// Pages tried, as "6-10"; a chunk shrunk after running out of memory tries less
+    /// than all of its pages.
+    pub pages: String,
+    /// "timeout", "out_of_memory", "engine_error" (the engine could not run or
+    /// crashed), or "conversion_failed" (it ran but reported failure).
+    pub error_class: String,
+    /// The first line of the error.
+    pub error: String,
+    /// The end of the engine's stderr for this attempt: from the chunk's stderr log
+    /// with `debug.keep_python_stderr`, otherwise from the error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_excerpt: Option<String>,
+}
+
+impl Attempt {
+    /// An attempt whose engine call returned an error.
+    pub fn from_error(engine: &str, pages: (u32, u32), err: &anyhow::Error) -> Self {
+        let text = format!("{err:#}");
+        let class = if exit_code::classify(err) == ErrorClass::Timeout {
+            "timeout"
+        } else if limits::looks_like_oom(&text) {
+            "out_of_memory"
+        } else {
+            "engine_error"
+        };
+        Self::new(engine, pages, class, &text)
+    }
+
+    /// An attempt whose engine ran but answered `ok: false`.
+    pub fn from_warnings(engine: &str, pages: (u32, u32), warnings: &[String]) -> Self {
+        let text = warnings.join("\n");
+        let class = if limits::looks_like_oom(&text) {
+            "out_of_memory"
+        } else {
+            "conversion_failed"
+        };
+        Self::new(engine, pages, class, &text)
+    }
+
+    fn new(engine: &str, (start, end): (u32, u32), class: &str, text: &str) -> Self {
+        let mut lines = text.lines();
+        let error = lines.next().unwrap_or_default().trim().to_string();
+        let rest: Vec<&str> = lines.filter(|l| !l.trim().is_empty()).collect();
+        Self {
+            engine: engine.to_string(),
+            pdf_backend: None,
+            pages: format!("{start}-{end}"),
+            error_class: class.to_string(),
+            error,
+            stderr_excerpt: tail(&rest),
+        }
+    }
+
+    pub fn with_backend(mut self, backend: &str) -> Self {
+        self.pdf_backend = Some(backend.to_string());
+        self
+    }
+
+    /// Take the excerpt from what the attempt appended to `log` past `offset` bytes.
+    pub fn with_stderr_log(mut self, log: Option<&Path>, offset: u64) -> Self {
+        let appended = log
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|raw| raw.get(offset as usize..).map(<[u8]>::to_vec));
+        if let Some(appended) = appended {
+            let text = String::from_utf8_lossy(&appended);
+            let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+            if let Some(excerpt) = tail(&lines) {
+                self.stderr_excerpt = Some(excerpt);
+            }
+        }
+        self
+    }
+}
+
+fn tail(lines: &[&str]) -> Option<String> {
+    let start = lines.len().saturating_sub(STDERR_EXCERPT_LINES);
+    (!lines.is_empty()).then(|| lines[start..].join("\n"))
+}
+
+/// The size of a stderr log before an attempt, to excerpt what the attempt adds.
+pub fn log_offset(log: Option<&Path>) -> u64 {
+    log.and_then(|p| std::fs::metadata(p).ok())
+        .map_or(0, |m| m.len())
+}
+
+/// The failed attempts of one chunk, shared by the requests an attempt is cloned into
+/// (backend fallbacks, out-of-memory halves).
+#[derive(Debug, Clone, Default)]
+pub struct AttemptLog(Arc<Mutex<Vec<Attempt>>>);
+
+impl AttemptLog {
+    pub fn push(&self, attempt: Attempt) {
+        self.0.lock().unwrap().push(attempt);
+    }
+
+    pub fn snapshot(&self) -> Vec<Attempt> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// A chunk's conversion error with the attempts behind it. Displays as `message`, so
+/// it can stand in for a plain error message.
+#[derive(Debug)]
+pub struct ChunkFailed {
+    pub message: String,
+    pub attempts: Vec<Attempt>,
+}
+
+impl std::fmt::Display for ChunkFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ChunkFailed {}
+
+/// The attempts recorded on `err`, if it came from a failed chunk.
+pub fn trail(err: &anyhow::Error) -> Vec<Attempt> {
+    err.downcast_ref::<ChunkFailed>()
+        .map(|f| f.attempts.clone())
+        .unwrap_or_default()
+}
+
+/// An HTML comment listing `attempts`, written under the first placeholder of a chunk
+/// that failed.
+pub fn trail_comment(attempts: &[Attempt]) -> String {
+    let mut out = format!("<!-- {} failed conversion attempt(s):", attempts.len());
+    for (n, a) in attempts.iter().enumerate() {
+        let backend = a
+            .pdf_backend
+            .as_deref()
+            .map(|b| format!(", pdf_backend {b}"))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "\n{}. {}{backend}, pages {}: {}: {}",
+            n + 1,
+            a.engine,
+            a.pages,
+            a.error_class,
+            a.error
+        ));
+        if let Some(excerpt) = &a.stderr_excerpt {
+            for line in excerpt.lines() {
+                out.push_str("\n   | ");
+                out.push_str(line);
+            }
+        }
+    }
+    out.push_str("\n-->");
+    // Nothing inside may close the comment early.
+    let body = &out[4..out.len() - 3];
+    format!("<!--{}-->", body.replace("--", "-"))
+}
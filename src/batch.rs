@@ -0,0 +1,433 @@
+use crate::{
+    config, dedup,
+    exit_code::{self, ErrorClass},
+    template,
+    util::now_rfc3339,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Written to the output root after every `quack-check batch`.
+pub const SUMMARY_FILE: &str = "batch-summary.json";
+
+/// The deepest file a job writes below its job directory (and a mirror below its
+/// destination), measured against `batch.max_path_length`.
+pub const DEEPEST_JOB_FILE: &str = "final/chunks/0001_pages-0001-0040.md";
+
+/// Longest file name most filesystems allow, in bytes.
+const MAX_NAME_BYTES: usize = 255;
+
+/// Hex digits of the path hash appended to a stem.
+const STEM_HASH_LEN: usize = 8;
+
+/// Every `*.pdf`, `*.gz` and `*.zip` under `dir` (recursively, case-insensitive),
+/// sorted by path. Symlinked directories are followed, each real directory once, so
+/// a link back up the tree does not loop.
+pub fn discover_inputs(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Err(anyhow!("batch input is not a directory: {}", dir.display()));
+    }
+    let mut inputs = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let real = std::fs::canonicalize(&current)
+            .with_context(|| format!("resolving {}", current.display()))?;
+        if !seen.insert(real) {
+            warn!("batch: skipping {}: directory already visited", current.display());
+            continue;
+        }
+        for entry in std::fs::read_dir(&current)
+            .with_context(|| format!("read_dir {}", current.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if crate::archive::is_supported_input(&path) {
+                inputs.push(path);
+            }
+        }
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// `inputs` with higher `global.priority` first, usually set in an input's sidecar;
+/// equal priorities keep their order. A sidecar that does not parse is reported when
+/// its job starts, not here.
+pub fn by_priority(cfg: &config::Config, inputs: Vec<PathBuf>) -> Vec<PathBuf> {
+    let sidecar_priority = |input: &Path| {
+        let raw = std::fs::read_to_string(crate::sidecar::sidecar_path(input)).ok()?;
+        let table: toml::Table = raw.parse().ok()?;
+        i32::try_from(table.get("global")?.get("priority")?.as_integer()?).ok()
+    };
+    let mut keyed: Vec<(i32, PathBuf)> = inputs
+        .into_iter()
+        .map(|input| {
+            let priority = cfg
+                .input
+                .sidecar
+                .then(|| sidecar_priority(&input))
+                .flatten()
+                .unwrap_or(cfg.global.priority);
+            (priority, input)
+        })
+        .collect();
+    keyed.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+    keyed.into_iter().map(|(_, input)| input).collect()
+}
+
+/// The `{input_stem}` each input of a batch gets. Inputs whose stems collide (ignoring
+/// case) get a short hash of their path relative to the batch directory appended, as
+/// in `scan-3f2a9c1b`, so outputs named by stem cannot overwrite each other; the hash
+/// does not depend on which other inputs are present. A stem that would make an
+/// output path too long is cut to fit, with the same suffix, or fails the input, per
+/// `batch.long_paths`.
+pub struct OutputNames {
+    shorten: bool,
+    max_path_length: usize,
+    /// Input -> (file stem, path hash, whether the stem collided).
+    inputs: HashMap<PathBuf, (String, String, bool)>,
+}
+
+impl OutputNames {
+    pub fn new(cfg: &config::Batch, input_dir: &Path, inputs: &[PathBuf]) -> Result<Self> {
+        let shorten = match cfg.long_paths.as_str() {
+            "shorten" => true,
+            "fail" => false,
+            other => {
+                return Err(anyhow!(
+                    "unknown batch.long_paths: {other} (expected shorten or fail)"
+                ))
+            }
+        };
+        let mut by_stem: HashMap<String, usize> = HashMap::new();
+        let stems: Vec<String> = inputs
+            .iter()
+            .map(|input| {
+                let stem = input
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                *by_stem.entry(stem.to_lowercase()).or_default() += 1;
+                stem
+            })
+            .collect();
+        let inputs = inputs
+            .iter()
+            .zip(stems)
+            .map(|(input, stem)| {
+                let relative = input.strip_prefix(input_dir).unwrap_or(input);
+                let key = relative.to_string_lossy().replace('\\', "/");
+                let hash = crate::util::sha256_hex(key.as_bytes())[..STEM_HASH_LEN].to_string();
+                let collides = cfg.disambiguate_stems && by_stem[&stem.to_lowercase()] > 1;
+                (input.clone(), (stem, hash, collides))
+            })
+            .collect();
+        Ok(Self {
+            shorten,
+            max_path_length: match cfg.max_path_length {
+                0 => platform_path_limit(),
+                n => n,
+            },
+            inputs,
+        })
+    }
+
+    /// The `{input_stem}` of `input` once its longest output path fits:
+    /// `longest_path` gives that path for a candidate stem. `None` when the input is not
+    /// part of the batch, or keeps its own stem.
+    pub fn stem_for(
+        &self,
+        input: &Path,
+        longest_path: impl Fn(&str) -> Result<PathBuf>,
+    ) -> Result<Option<String>> {
+        let Some((stem, hash, collides)) = self.inputs.get(input) else {
+            return Ok(None);
+        };
+        let candidate = if *collides {
+            format!("{stem}-{hash}")
+        } else {
+            stem.clone()
+        };
+        let path = longest_path(&candidate)?;
+        let excess = self.excess(&path);
+        if excess == 0 {
+            return Ok((*collides).then_some(candidate));
+        }
+        if !self.shorten {
+            return Err(anyhow!(
+                "output path is {excess} byte(s) too long (batch.max_path_length {}): {}",
+                self.max_path_length,
+                path.display()
+            ));
+        }
+        let keep = candidate.len().saturating_sub(excess + STEM_HASH_LEN + 1);
+        let mut cut = stem.len().min(keep);
+        while !stem.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let base = stem[..cut].trim_end();
+        let shortened = if base.is_empty() {
+            hash.clone()
+        } else {
+            format!("{base}-{hash}")
+        };
+        let path = longest_path(&shortened)?;
+        if self.excess(&path) > 0 {
+            return Err(anyhow!(
+                "output path is too long even with {{input_stem}} shortened to {shortened} \
+                 (batch.max_path_length {}): {}",
+                self.max_path_length,
+                path.display()
+            ));
+        }
+        warn!(
+            "batch: shortened {{input_stem}} of {} to {shortened} to fit the path length limit",
+            input.display()
+        );
+        Ok(Some(shortened))
+    }
+
+    /// Bytes `path` is over the length limit, or its longest component over the file
+    /// name limit, whichever is more.
+    fn excess(&self, path: &Path) -> usize {
+        let longest_name = path
+            .components()
+            .map(|c| c.as_os_str().len())
+            .max()
+            .unwrap_or(0);
+        path.as_os_str()
+            .len()
+            .saturating_sub(self.max_path_length)
+            .max(longest_name.saturating_sub(MAX_NAME_BYTES))
+    }
+}
+
+/// The longest path a job of `input` writes with `cfg` expanded for it: below its job
+/// directory, and below each mirror destination. `{tier}`, `{engine}` and `{pages}`
+/// are only known once the job has run and stay unexpanded in mirror paths.
+pub fn longest_output_path(
+    cfg: &config::Config,
+    input: &Path,
+    job_id: &str,
+    stem: &str,
+) -> PathBuf {
+    let job_dir = Path::new(&cfg.paths.out_dir).join(job_id);
+    let mut paths = vec![job_dir.join(DEEPEST_JOB_FILE)];
+    for name in [
+        &cfg.output.markdown_filename,
+        &cfg.output.text_filename,
+        &cfg.output.report_filename,
+    ] {
+        paths.push(job_dir.join("final").join(name));
+    }
+    let mut vars = template::job_vars(input, job_id);
+    template::rename_input(&mut vars, stem);
+    vars.insert("date", now_rfc3339().chars().take(10).collect());
+    let in_final = DEEPEST_JOB_FILE.trim_start_matches("final/");
+    let deferred = ["tier", "engine", "pages"];
+    for mirror in &cfg.output.mirrors {
+        if let Ok(dest) = template::expand_except(&mirror.path, &vars, &deferred) {
+            paths.push(Path::new(&dest).join(in_final));
+        }
+    }
+    paths
+        .into_iter()
+        .max_by_key(|p| p.as_os_str().len())
+        .unwrap_or(job_dir)
+}
+
+/// `MAX_PATH` less the terminator on Windows, `PATH_MAX` less it elsewhere.
+fn platform_path_limit() -> usize {
+    if cfg!(windows) {
+        259
+    } else {
+        4095
+    }
+}
+
+/// Tracks what a batch has seen so repeated inputs and near-identical transcripts
+/// are flagged instead of reported as independent documents.
+pub struct Deduplicator {
+    mode: String,
+    shingle_words: usize,
+    permutations: usize,
+    threshold: f32,
+    by_hash: HashMap<String, PathBuf>,
+    signatures: Vec<(PathBuf, Vec<u64>)>,
+}
+
+impl Deduplicator {
+    pub fn new(cfg: &config::Batch) -> Result<Self> {
+        if !matches!(cfg.dedup.as_str(), "off" | "input_hash" | "minhash") {
+            return Err(anyhow!(
+                "unknown batch.dedup: {} (expected off, input_hash or minhash)",
+                cfg.dedup
+            ));
+        }
+        if cfg.dedup == "minhash" && cfg.minhash_permutations == 0 {
+            return Err(anyhow!("batch.minhash_permutations must be > 0"));
+        }
+        Ok(Self {
+            mode: cfg.dedup.clone(),
+            shingle_words: cfg.shingle_words,
+            permutations: cfg.minhash_permutations,
+            threshold: cfg.near_duplicate_threshold,
+            by_hash: HashMap::new(),
+            signatures: Vec::new(),
+        })
+    }
+
+    /// The first input seen with the same content hash, if any.
+    pub fn exact_duplicate(&self, input_hash: &str) -> Option<PathBuf> {
+        if self.mode == "off" {
+            return None;
+        }
+        self.by_hash.get(input_hash).cloned()
+    }
+
+    pub fn record_input(&mut self, input_hash: &str, input: &Path) {
+        self.by_hash
+            .entry(input_hash.to_string())
+            .or_insert_with(|| input.to_path_buf());
+    }
+
+    /// The most similar earlier transcript at or above the threshold, if any. Only
+    /// transcripts that are not themselves near-duplicates are kept for comparison, so
+    /// every member of a cluster points at the same original.
+    pub fn near_duplicate(&mut self, input: &Path, text: &str) -> Option<(PathBuf, f32)> {
+        if self.mode != "minhash" {
+            return None;
+        }
+        let sig = dedup::signature(text, self.shingle_words, self.permutations);
+        let best = self
+            .signatures
+            .iter()
+            .map(|(path, other)| (path, dedup::similarity(&sig, other)))
+            .filter(|(_, s)| *s >= self.threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(path, s)| (path.clone(), s));
+        if best.is_none() {
+            self.signatures.push((input.to_path_buf(), sig));
+        }
+        best
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchEntry {
+    pub input: PathBuf,
+    /// ok | partial | degraded | failed | paused | duplicate | near_duplicate
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_dir: Option<PathBuf>,
+    /// `{input_stem}` when it differs from the file stem (see [`OutputNames`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_stem: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// For a failed input, what kind of failure it was (see [`crate::exit_code`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_class: Option<ErrorClass>,
+}
+
+impl BatchEntry {
+    fn new(input: &Path, status: &str) -> Self {
+        Self {
+            input: input.to_path_buf(),
+            status: status.to_string(),
+            job_id: None,
+            job_dir: None,
+            input_stem: None,
+            duplicate_of: None,
+            similarity: None,
+            error: None,
+            error_class: None,
+        }
+    }
+
+    pub fn ok(input: &Path, job_id: &str, job_dir: &Path) -> Self {
+        Self {
+            job_id: Some(job_id.to_string()),
+            job_dir: Some(job_dir.to_path_buf()),
+            ..Self::new(input, "ok")
+        }
+    }
+
+    pub fn failed(input: &Path, job_id: Option<&str>, err: &anyhow::Error) -> Self {
+        Self {
+            job_id: job_id.map(str::to_string),
+            error: Some(format!("{err:#}")),
+            error_class: Some(exit_code::classify(err)),
+            ..Self::new(input, "failed")
+        }
+    }
+
+    /// Stopped by `jobs pause`; `jobs resume` and another batch run continue it.
+    pub fn paused(input: &Path, job_id: &str, job_dir: &Path) -> Self {
+        Self {
+            job_id: Some(job_id.to_string()),
+            job_dir: Some(job_dir.to_path_buf()),
+            ..Self::new(input, "paused")
+        }
+    }
+
+    /// Same bytes as an earlier input; no job is run and the original's job is reported.
+    pub fn duplicate(input: &Path, job_id: &str, job_dir: &Path, original: &Path) -> Self {
+        Self {
+            job_id: Some(job_id.to_string()),
+            job_dir: Some(job_dir.to_path_buf()),
+            duplicate_of: Some(original.to_path_buf()),
+            similarity: Some(1.0),
+            ..Self::new(input, "duplicate")
+        }
+    }
+
+    /// Converted, but the time budget ran out before every chunk was.
+    pub fn mark_partial(&mut self) {
+        self.status = "partial".into();
+    }
+
+    /// Converted, with placeholders for chunks that failed within
+    /// `limits.allow_failed_chunk_fraction`.
+    pub fn mark_degraded(&mut self) {
+        self.status = "degraded".into();
+    }
+
+    pub fn mark_near_duplicate(&mut self, original: &Path, similarity: f32) {
+        self.status = "near_duplicate".into();
+        self.duplicate_of = Some(original.to_path_buf());
+        self.similarity = Some(similarity);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchSummary {
+    pub input_dir: PathBuf,
+    pub counts: BTreeMap<String, usize>,
+    pub jobs: Vec<BatchEntry>,
+}
+
+impl BatchSummary {
+    pub fn new(input_dir: &Path, jobs: Vec<BatchEntry>) -> Self {
+        let mut counts = BTreeMap::new();
+        for job in &jobs {
+            *counts.entry(job.status.clone()).or_insert(0) += 1;
+        }
+        Self {
+            input_dir: input_dir.to_path_buf(),
+            counts,
+            jobs,
+        }
+    }
+}
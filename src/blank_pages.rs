@@ -0,0 +1,129 @@
+use crate::postprocess::{is_marker_line, parse_page_marker};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Blank and near-blank pages found in the transcript, recorded under `blank_pages` in
+/// the report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlankPageStats {
+    /// `postprocess.blank_pages`.
+    pub mode: String,
+    pub pages: Vec<u32>,
+}
+
+/// `<!-- blank page N -->`, or `<!-- blank pages N-M -->` for a collapsed run.
+pub fn blank_page_comment(first: u32, last: u32) -> String {
+    if first == last {
+        format!("<!-- blank page {first} -->")
+    } else {
+        format!("<!-- blank pages {first}-{last} -->")
+    }
+}
+
+/// Whether a page's text is blank: no image and at most `max_chars` letters and
+/// digits outside HTML comments, which leaves room for a lone page number.
+pub fn is_blank(text: &str, max_chars: usize) -> bool {
+    let mut chars = 0;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("<!--") && line.ends_with("-->") {
+            // Docling's placeholder for a picture.
+            if line == "<!-- image -->" {
+                return false;
+            }
+            continue;
+        }
+        if line.contains("![") {
+            return false;
+        }
+        chars += line.chars().filter(|c| c.is_alphanumeric()).count();
+        if chars > max_chars {
+            return false;
+        }
+    }
+    true
+}
+
+/// Find blank pages in page-marked markdown and drop, mark, or collapse them according
+/// to `mode` ("keep" | "drop" | "marker" | "collapse"). Only pages in `measurable` are
+/// considered, so a chunk whose page breaks the engine did not mark is never mistaken
+/// for blank pages. Page markers, chunk markers and `---` separators on a blank page
+/// are kept.
+pub fn handle_blank_pages(
+    md: &str,
+    mode: &str,
+    max_chars: usize,
+    measurable: &BTreeSet<u32>,
+) -> Result<(String, BlankPageStats)> {
+    if !matches!(mode, "keep" | "drop" | "marker" | "collapse") {
+        return Err(anyhow!("unknown postprocess.blank_pages: {mode}"));
+    }
+    let segments = segments(md);
+    let blank: BTreeSet<u32> = segments
+        .iter()
+        .filter(|s| s.page.is_some_and(|p| measurable.contains(&p)))
+        .filter(|s| is_blank(&s.lines.join("\n"), max_chars))
+        .filter_map(|s| s.page)
+        .collect();
+    let stats = BlankPageStats {
+        mode: mode.to_string(),
+        pages: blank.iter().copied().collect(),
+    };
+    if mode == "keep" || blank.is_empty() {
+        return Ok((md.to_string(), stats));
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    for segment in &segments {
+        let Some(page) = segment.page.filter(|p| blank.contains(p)) else {
+            out.extend(segment.lines.iter().map(|l| l.to_string()));
+            continue;
+        };
+        let mut lines = segment.lines.iter();
+        if let Some(marker) = lines.next() {
+            out.push(marker.to_string());
+        }
+        let comment = match mode {
+            "marker" => Some(blank_page_comment(page, page)),
+            "collapse" if page == 1 || !blank.contains(&(page - 1)) => {
+                let last = (page..)
+                    .take_while(|p| blank.contains(p))
+                    .last()
+                    .unwrap_or(page);
+                Some(blank_page_comment(page, last))
+            }
+            _ => None,
+        };
+        let kept = lines.filter(|l| is_marker_line(l) || l.trim() == "---");
+        for line in comment.into_iter().chain(kept.map(|l| l.to_string())) {
+            out.push(String::new());
+            out.push(line);
+        }
+        out.push(String::new());
+    }
+    Ok((out.join("\n"), stats))
+}
+
+/// A page marker and the lines up to the next one; `page` is `None` for the text
+/// before the first marker.
+pub(crate) struct Segment<'a> {
+    pub page: Option<u32>,
+    pub lines: Vec<&'a str>,
+}
+
+pub(crate) fn segments(md: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![Segment {
+        page: None,
+        lines: Vec::new(),
+    }];
+    for line in md.lines() {
+        if let Some(page) = parse_page_marker(line) {
+            segments.push(Segment {
+                page: Some(page),
+                lines: Vec::new(),
+            });
+        }
+        segments.last_mut().expect("segment").lines.push(line);
+    }
+    segments
+}
@@ -0,0 +1,223 @@
+use crate::{
+    config::Classification,
+    policy::{classify_tier, QualityTier},
+    probe::ProbeSampleStats,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Upper bound on candidate values tried per threshold; keeps the grid at most
+/// `MAX_CANDIDATES^4` combinations regardless of corpus size.
+const MAX_CANDIDATES: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub path: PathBuf,
+    pub tier: QualityTier,
+}
+
+#[derive(Debug, Clone)]
+pub struct LabeledSample {
+    pub path: PathBuf,
+    pub expected: QualityTier,
+    pub sample: ProbeSampleStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Misclassified {
+    pub path: PathBuf,
+    pub expected: QualityTier,
+    pub predicted: QualityTier,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Calibration {
+    pub total: usize,
+    pub baseline_errors: usize,
+    pub errors: usize,
+    pub min_avg_chars_per_page_for_high_text: u32,
+    pub max_avg_chars_per_page_for_scan: u32,
+    pub max_garbage_ratio_for_high_text: f32,
+    pub max_whitespace_ratio_for_high_text: f32,
+    pub misclassified: Vec<Misclassified>,
+}
+
+/// Read a `path,tier` CSV. Blank lines, `#` comments, and a `path,tier` header are
+/// skipped; relative paths resolve against the CSV's directory.
+pub fn read_labels(path: &Path) -> Result<Vec<Label>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("read labels: {}", path.display()))?;
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut labels = Vec::new();
+
+    for (idx, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (file, tier) = line
+            .rsplit_once(',')
+            .ok_or_else(|| anyhow!("{}:{}: expected `path,tier`", path.display(), idx + 1))?;
+        let file = file.trim().trim_matches('"');
+        let tier = tier.trim().trim_matches('"');
+        if idx == 0 && file.eq_ignore_ascii_case("path") {
+            continue;
+        }
+        let tier = QualityTier::from_label(tier).ok_or_else(|| {
+            anyhow!(
+                "{}:{}: unknown tier {tier:?} (expected HIGH_TEXT, MIXED_TEXT or SCAN)",
+                path.display(),
+                idx + 1
+            )
+        })?;
+        let file = PathBuf::from(file);
+        let file = if file.is_relative() { base.join(file) } else { file };
+        labels.push(Label { path: file, tier });
+    }
+
+    if labels.is_empty() {
+        return Err(anyhow!("no labels in {}", path.display()));
+    }
+    Ok(labels)
+}
+
+/// Grid-search the four tier thresholds for the fewest misclassified samples.
+///
+/// Candidates for each threshold are the observed sample values plus the current
+/// setting. Ties go to the combination closest to the current config, so a corpus
+/// that is already classified correctly gets its thresholds back unchanged.
+pub fn calibrate(current: &Classification, samples: &[LabeledSample]) -> Calibration {
+    let avg: Vec<f64> = samples.iter().map(|s| s.sample.avg_chars_per_page as f64).collect();
+    let garbage: Vec<f64> = samples.iter().map(|s| s.sample.garbage_ratio as f64).collect();
+    let ws: Vec<f64> = samples.iter().map(|s| s.sample.whitespace_ratio as f64).collect();
+
+    let cur_high = current.min_avg_chars_per_page_for_high_text as f64;
+    let cur_scan = current.max_avg_chars_per_page_for_scan as f64;
+    let cur_garbage = current.max_garbage_ratio_for_high_text as f64;
+    let cur_ws = current.max_whitespace_ratio_for_high_text as f64;
+
+    let high_cands = candidates(&avg, cur_high);
+    let scan_cands = candidates(&avg, cur_scan);
+    let garbage_cands = candidates(&garbage, cur_garbage);
+    let ws_cands = candidates(&ws, cur_ws);
+
+    let mut best = current.clone();
+    let mut best_key = (errors(current, samples), 0.0);
+    let baseline_errors = best_key.0;
+
+    for &high in &high_cands {
+        for &scan in &scan_cands {
+            if scan >= high {
+                continue;
+            }
+            for &g in &garbage_cands {
+                for &w in &ws_cands {
+                    let mut c = current.clone();
+                    c.min_avg_chars_per_page_for_high_text = high as u32;
+                    c.max_avg_chars_per_page_for_scan = scan as u32;
+                    c.max_garbage_ratio_for_high_text = g as f32;
+                    c.max_whitespace_ratio_for_high_text = w as f32;
+                    let errs = errors(&c, samples);
+                    if errs > best_key.0 {
+                        continue;
+                    }
+                    let dist = rel_dist(high, cur_high)
+                        + rel_dist(scan, cur_scan)
+                        + rel_dist(g, cur_garbage)
+                        + rel_dist(w, cur_ws);
+                    if (errs, dist) < best_key {
+                        best_key = (errs, dist);
+                        best = c;
+                    }
+                }
+            }
+        }
+    }
+
+    let misclassified = samples
+        .iter()
+        .filter_map(|s| {
+            let predicted = classify_tier(&best, &s.sample);
+            (predicted != s.expected).then(|| Misclassified {
+                path: s.path.clone(),
+                expected: s.expected,
+                predicted,
+            })
+        })
+        .collect();
+
+    Calibration {
+        total: samples.len(),
+        baseline_errors,
+        errors: best_key.0,
+        min_avg_chars_per_page_for_high_text: best.min_avg_chars_per_page_for_high_text,
+        max_avg_chars_per_page_for_scan: best.max_avg_chars_per_page_for_scan,
+        max_garbage_ratio_for_high_text: best.max_garbage_ratio_for_high_text,
+        max_whitespace_ratio_for_high_text: best.max_whitespace_ratio_for_high_text,
+        misclassified,
+    }
+}
+
+impl Calibration {
+    /// A `[classification]` block ready to paste into quack-check.toml.
+    pub fn to_toml(&self) -> String {
+        let mut out = format!(
+            "# calibrated on {} labeled files: {} misclassified (current thresholds: {})\n",
+            self.total, self.errors, self.baseline_errors
+        );
+        for m in &self.misclassified {
+            out.push_str(&format!(
+                "# still wrong: {} (labeled {:?}, classified {:?})\n",
+                m.path.display(),
+                m.expected,
+                m.predicted
+            ));
+        }
+        out.push_str("[classification]\n");
+        out.push_str(&format!(
+            "min_avg_chars_per_page_for_high_text = {}\n",
+            self.min_avg_chars_per_page_for_high_text
+        ));
+        out.push_str(&format!(
+            "max_avg_chars_per_page_for_scan = {}\n",
+            self.max_avg_chars_per_page_for_scan
+        ));
+        out.push_str(&format!(
+            "max_garbage_ratio_for_high_text = {}\n",
+            self.max_garbage_ratio_for_high_text
+        ));
+        out.push_str(&format!(
+            "max_whitespace_ratio_for_high_text = {}\n",
+            self.max_whitespace_ratio_for_high_text
+        ));
+        out
+    }
+}
+
+fn errors(c: &Classification, samples: &[LabeledSample]) -> usize {
+    samples
+        .iter()
+        .filter(|s| classify_tier(c, &s.sample) != s.expected)
+        .count()
+}
+
+fn candidates(values: &[f64], current: f64) -> Vec<f64> {
+    let mut v: Vec<f64> = values.to_vec();
+    v.sort_by(|a, b| a.total_cmp(b));
+    v.dedup();
+    if v.len() > MAX_CANDIDATES {
+        let step = (v.len() - 1) as f64 / (MAX_CANDIDATES - 1) as f64;
+        v = (0..MAX_CANDIDATES)
+            .map(|i| v[(i as f64 * step).round() as usize])
+            .collect();
+    }
+    v.push(current);
+    v.sort_by(|a, b| a.total_cmp(b));
+    v.dedup();
+    v
+}
+
+fn rel_dist(value: f64, current: f64) -> f64 {
+    (value - current).abs() / current.abs().max(1e-6)
+}
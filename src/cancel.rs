@@ -0,0 +1,77 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Cancels a running job from another thread. Clones share one flag, so an embedding
+/// application keeps a clone and calls [`CancellationToken::cancel`] while the job runs.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Why a job stopped early. Job errors carry it in their chain; see [`interrupted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupted {
+    Cancelled,
+    DeadlineExceeded,
+    /// `jobs pause`: chunks already converted are kept for the next run.
+    Paused,
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interrupted::Cancelled => write!(f, "job cancelled"),
+            Interrupted::DeadlineExceeded => write!(f, "job deadline exceeded"),
+            Interrupted::Paused => write!(f, "job paused"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Whether `err` stopped a job because it was cancelled, paused, or ran past its
+/// deadline.
+pub fn interrupted(err: &anyhow::Error) -> Option<Interrupted> {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<Interrupted>())
+        .copied()
+}
+
+/// A job's cancellation token and deadline. The pipeline checks it between stages and
+/// before each chunk; engines check it while waiting on a conversion and stop the
+/// conversion when it trips.
+#[derive(Debug, Clone, Default)]
+pub struct JobControl {
+    cancel: CancellationToken,
+    deadline: Option<Instant>,
+}
+
+impl JobControl {
+    pub fn new(cancel: CancellationToken, deadline: Option<Instant>) -> Self {
+        Self { cancel, deadline }
+    }
+
+    pub fn check(&self) -> Result<(), Interrupted> {
+        if self.cancel.is_cancelled() {
+            return Err(Interrupted::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Interrupted::DeadlineExceeded),
+            _ => Ok(()),
+        }
+    }
+}
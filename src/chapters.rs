@@ -0,0 +1,212 @@
+use crate::postprocess::{is_marker_line, parse_page_marker};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Documents with at least one heading per this many pages already have usable
+/// structure and are left alone.
+const PAGES_PER_HEADING: usize = 25;
+const MAX_TITLE_CHARS: usize = 60;
+const MAX_TITLE_WORDS: usize = 8;
+/// Blank lines before a line that count as a large vertical gap.
+const GAP_LINES: usize = 2;
+
+const NUMBERED_TITLE: &str = r"(?i)^(chapter|chap\.|part|book)\s+([0-9]+|[ivxlcdm]+|one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve|thirteen|fourteen|fifteen|sixteen|seventeen|eighteen|nineteen|twenty)\b[.:]?\s*(.*)$";
+
+/// Whether `md` has too few headings for its length to be navigable.
+pub fn has_few_headings(md: &str, page_count: u32) -> bool {
+    let mut in_code = false;
+    let mut headings = 0;
+    for line in md.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code && line.starts_with('#') {
+            headings += 1;
+        }
+    }
+    headings * PAGES_PER_HEADING < page_count.max(1) as usize
+}
+
+/// A run of non-blank lines, with the layout context that hints at a title.
+struct Block {
+    start: usize,
+    end: usize,
+    gap_before: usize,
+    page_start: bool,
+}
+
+/// Promote likely chapter titles to `#` headings: standalone "Chapter N" / "Part N" /
+/// "Book N" lines (joined with a short title line right after them), and short
+/// all-caps lines that open a page or follow a large gap. Caps lines that occur more
+/// than once are running headers, not titles. Returns the rewritten markdown and the
+/// number of headings added.
+pub fn promote_chapter_titles(md: &str) -> (String, usize) {
+    let numbered = Regex::new(NUMBERED_TITLE).expect("numbered title regex");
+    let lines: Vec<&str> = md.lines().collect();
+    let blocks = blocks(&lines);
+
+    let mut caps_seen: HashMap<&str, usize> = HashMap::new();
+    for b in &blocks {
+        if b.end - b.start == 1 && is_caps_title(lines[b.start].trim()) {
+            *caps_seen.entry(lines[b.start].trim()).or_default() += 1;
+        }
+    }
+
+    let mut replace: HashMap<usize, String> = HashMap::new();
+    let mut drop: Vec<usize> = Vec::new();
+    let mut i = 0;
+    while i < blocks.len() {
+        let b = &blocks[i];
+        let line = lines[b.start].trim();
+        if b.end - b.start != 1 {
+            i += 1;
+            continue;
+        }
+        if let Some(caps) = numbered.captures(line) {
+            let rest = caps.get(3).map_or("", |m| m.as_str());
+            if is_numbered_title(line, rest) {
+                let mut title = line.to_string();
+                // "CHAPTER IV" on one line and "THE STORM" on the next.
+                if rest.is_empty()
+                    && let Some(next) = blocks.get(i + 1)
+                    && next.end - next.start == 1
+                    && !next.page_start
+                    && is_title_line(lines[next.start].trim())
+                {
+                    title = format!(
+                        "{}: {}",
+                        line.trim_end_matches(['.', ':']),
+                        lines[next.start].trim()
+                    );
+                    drop.push(next.start);
+                    i += 1;
+                }
+                replace.insert(b.start, format!("# {title}"));
+            }
+        } else if (b.page_start || b.gap_before >= GAP_LINES)
+            && is_caps_title(line)
+            && caps_seen.get(line) == Some(&1)
+        {
+            replace.insert(b.start, format!("# {line}"));
+        }
+        i += 1;
+    }
+
+    let promoted = replace.len();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    for (idx, line) in lines.iter().enumerate() {
+        if drop.contains(&idx) {
+            // Also drop the blank line that separated the title from its number.
+            while out.last().is_some_and(|l| l.trim().is_empty()) {
+                out.pop();
+            }
+            continue;
+        }
+        match replace.remove(&idx) {
+            Some(heading) => out.push(heading),
+            None => out.push(line.to_string()),
+        }
+    }
+    let mut joined = out.join("\n");
+    if md.ends_with('\n') {
+        joined.push('\n');
+    }
+    (joined, promoted)
+}
+
+fn blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut in_code = false;
+    let mut gap = usize::MAX;
+    let mut page_start = true;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            page_start = false;
+            gap = 0;
+            i += 1;
+            continue;
+        }
+        if in_code {
+            i += 1;
+            continue;
+        }
+        if parse_page_marker(line).is_some() || line.trim() == "---" {
+            page_start = true;
+            gap = usize::MAX;
+            i += 1;
+            continue;
+        }
+        if line.trim().is_empty() || is_marker_line(line) {
+            gap = gap.saturating_add(1);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < lines.len()
+            && !lines[i].trim().is_empty()
+            && !is_marker_line(lines[i])
+            && !lines[i].trim_start().starts_with("```")
+        {
+            i += 1;
+        }
+        blocks.push(Block {
+            start,
+            end: i,
+            gap_before: gap,
+            page_start,
+        });
+        page_start = false;
+        gap = 0;
+    }
+    blocks
+}
+
+fn is_structural(line: &str) -> bool {
+    line.starts_with('#')
+        || line.starts_with('|')
+        || line.starts_with("- ")
+        || line.starts_with("* ")
+        || line.starts_with('>')
+        || line.starts_with("![")
+        || line.starts_with("<!--")
+}
+
+fn is_short(line: &str) -> bool {
+    line.chars().count() <= MAX_TITLE_CHARS && line.split_whitespace().count() <= MAX_TITLE_WORDS
+}
+
+fn ends_like_prose(line: &str) -> bool {
+    line.ends_with(['.', ',', ';']) && !line.ends_with("..")
+}
+
+fn is_numbered_title(line: &str, rest: &str) -> bool {
+    !is_structural(line)
+        && line.chars().count() <= MAX_TITLE_CHARS + 20
+        && !line.ends_with([',', ';'])
+        && !rest.chars().next().is_some_and(char::is_lowercase)
+}
+
+/// A short line with at least three letters, all of them upper case.
+fn is_caps_title(line: &str) -> bool {
+    let letters: Vec<char> = line.chars().filter(|c| c.is_alphabetic()).collect();
+    !is_structural(line)
+        && is_short(line)
+        && !ends_like_prose(line)
+        && letters.len() >= 3
+        && letters.iter().all(|c| c.is_uppercase())
+}
+
+/// A short caps or title-case line without sentence punctuation.
+fn is_title_line(line: &str) -> bool {
+    if is_structural(line) || !is_short(line) || ends_like_prose(line) {
+        return false;
+    }
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let capitalized = words
+        .iter()
+        .filter(|w| w.chars().next().is_some_and(|c| !c.is_lowercase()))
+        .count();
+    !words.is_empty() && capitalized * 2 > words.len()
+}
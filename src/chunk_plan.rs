@@ -1,6 +1,14 @@
-use crate::{config::Config, probe::ProbeResult};
-use anyhow::Result;
+use crate::{
+    config::Config,
+    engine::{Engine, PageLayout},
+    exclusions::{self, PageExclusion},
+    probe::ProbeResult,
+};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkPlan {
@@ -21,6 +29,16 @@ impl ChunkPlan {
         Ok(Self::from_page_count(cfg, page_count))
     }
 
+    /// The plan a job starts from, before boundary refinement: one chunk unless the
+    /// input is over `limits.require_chunking_over_pages` or `_bytes`.
+    pub fn for_job(cfg: &Config, probe: &ProbeResult) -> ChunkPlan {
+        let plan = Self::from_page_count(cfg, probe.input.page_count);
+        if plan.chunks.len() > 1 && !requires_chunking(cfg, probe) {
+            return Self::single(plan.page_count, &cfg.chunking.strategy);
+        }
+        plan
+    }
+
     pub fn single(page_count: u32, strategy: &str) -> ChunkPlan {
         ChunkPlan {
             page_count,
@@ -33,36 +51,219 @@ impl ChunkPlan {
     }
 
     pub fn from_page_count(cfg: &Config, page_count: u32) -> ChunkPlan {
-        let target = cfg.chunking.target_pages_per_chunk.max(1);
-        let maxp = cfg.chunking.max_pages_per_chunk.max(1);
-        let minp = cfg.chunking.min_pages_per_chunk.max(1).min(maxp);
+        ChunkPlan {
+            page_count,
+            chunks: split_run(cfg, 1, page_count),
+            strategy: cfg.chunking.strategy.clone(),
+        }
+    }
 
-        let mut chunks = Vec::new();
-        let mut p = 1u32;
+    /// The plan without `excluded` pages: each run of kept pages is planned on its own,
+    /// so no chunk spans an exclusion. A single-chunk plan keeps one chunk per run.
+    pub fn without_pages(self, cfg: &Config, excluded: &[PageExclusion]) -> ChunkPlan {
+        if excluded.is_empty() {
+            return self;
+        }
+        let chunked = self.chunks.len() > 1;
+        let chunks = exclusions::kept_runs(self.page_count, excluded)
+            .into_iter()
+            .flat_map(|(start, end)| {
+                if chunked {
+                    split_run(cfg, start, end)
+                } else {
+                    vec![PageRange {
+                        start_page: start,
+                        end_page: end,
+                    }]
+                }
+            })
+            .collect();
+        ChunkPlan { chunks, ..self }
+    }
 
-        while p <= page_count {
-            let mut end = (p + target - 1).min(page_count);
-            let span = end - p + 1;
-            if span > maxp {
-                end = p + maxp - 1;
-            }
+    /// Check a hand-edited plan (`run --plan`) against the document: chunks must be
+    /// non-empty, in order, and cover pages 1..=`page_count` without gaps or overlaps.
+    pub fn validate(&self, page_count: u32) -> Result<()> {
+        self.validate_excluding(page_count, &[])
+    }
 
-            let remaining = page_count.saturating_sub(end);
-            if remaining > 0 && remaining < minp && !chunks.is_empty() {
-                end = page_count;
+    /// Like [`ChunkPlan::validate`], for a document with `excluded` pages: those must be
+    /// left out, and every other page covered.
+    pub fn validate_excluding(&self, page_count: u32, excluded: &[PageExclusion]) -> Result<()> {
+        let skip = |mut page: u32| {
+            while let Some(range) = exclusions::excluded_range(excluded, page) {
+                page = range.end_page + 1;
+            }
+            page
+        };
+        if self.page_count != page_count {
+            return Err(anyhow!(
+                "plan is for {} page(s), but the input has {page_count}",
+                self.page_count
+            ));
+        }
+        if self.chunks.is_empty() {
+            return Err(anyhow!("plan has no chunks"));
+        }
+        let mut next = skip(1);
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if chunk.start_page > chunk.end_page {
+                return Err(anyhow!(
+                    "chunk {i} ends (page {}) before it starts (page {})",
+                    chunk.end_page,
+                    chunk.start_page
+                ));
+            }
+            if let Some(range) = excluded
+                .iter()
+                .find(|r| r.start_page <= chunk.end_page && chunk.start_page <= r.end_page)
+            {
+                return Err(anyhow!(
+                    "chunk {i} (pages {}-{}) includes excluded pages {}-{}",
+                    chunk.start_page,
+                    chunk.end_page,
+                    range.start_page,
+                    range.end_page
+                ));
+            }
+            if chunk.start_page != next {
+                let problem = if chunk.start_page > next { "gap" } else { "overlap" };
+                return Err(anyhow!(
+                    "chunk {i} starts at page {}, expected {next} ({problem} between chunks)",
+                    chunk.start_page
+                ));
             }
+            next = skip(chunk.end_page + 1);
+        }
+        if next != page_count + 1 {
+            return Err(anyhow!(
+                "plan ends at page {}, but the input has {page_count}",
+                next - 1
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pages whose layout decides where each seam may go: `slack` pages either side of
+    /// every chunk end, plus the page after.
+    pub fn seam_window_pages(&self, slack: u32) -> Vec<u32> {
+        let mut pages = Vec::new();
+        for chunk in self.chunks.iter().take(self.chunks.len().saturating_sub(1)) {
+            let lo = chunk.end_page.saturating_sub(slack).max(1);
+            let hi = (chunk.end_page + slack + 1).min(self.page_count);
+            pages.extend(lo..=hi);
+        }
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
 
-            chunks.push(PageRange {
-                start_page: p,
-                end_page: end,
+    /// Move every seam that falls inside a table (tabular lines at the bottom of one
+    /// page and the top of the next) to the nearest clean page break within
+    /// `chunking.boundary_slack_pages`, keeping chunks non-empty and within
+    /// `max_pages_per_chunk`. Seams whose neighbourhood has no clean break, or whose
+    /// layout is unknown, stay put. Returns the `(old_end, new_end)` of each move.
+    pub fn avoid_table_splits(&mut self, cfg: &Config, layouts: &[PageLayout]) -> Vec<(u32, u32)> {
+        let slack = cfg.chunking.boundary_slack_pages;
+        let maxp = cfg.chunking.max_pages_per_chunk.max(1);
+        let by_page: HashMap<u32, &PageLayout> = layouts.iter().map(|l| (l.page, l)).collect();
+        // Some(true) when the break after `page` cuts a table, None when unknown.
+        let cuts_table = |page: u32| -> Option<bool> {
+            let (a, b) = (by_page.get(&page)?, by_page.get(&(page + 1))?);
+            Some(a.table_at_bottom && b.table_at_top)
+        };
+
+        let mut moved = Vec::new();
+        for i in 0..self.chunks.len().saturating_sub(1) {
+            let end = self.chunks[i].end_page;
+            // Chunks either side of excluded pages share no seam.
+            if self.chunks[i + 1].start_page != end + 1 || cuts_table(end) != Some(true) {
+                continue;
+            }
+            let start = self.chunks[i].start_page;
+            let next_end = self.chunks[i + 1].end_page;
+            let candidates = (1..=slack).flat_map(|d| [end.checked_sub(d), Some(end + d)]);
+            let new_end = candidates.flatten().find(|&e| {
+                e >= start
+                    && e < next_end
+                    && e - start < maxp
+                    && next_end - e <= maxp
+                    && cuts_table(e) == Some(false)
             });
-            p = end + 1;
+            if let Some(new_end) = new_end {
+                self.chunks[i].end_page = new_end;
+                self.chunks[i + 1].start_page = new_end + 1;
+                moved.push((end, new_end));
+            }
         }
+        moved
+    }
 
-        ChunkPlan {
-            page_count,
-            chunks,
-            strategy: cfg.chunking.strategy.clone(),
+    /// Run the engine's layout pre-pass around the seams and apply
+    /// [`ChunkPlan::avoid_table_splits`]. A failed pre-pass only costs the refinement.
+    pub fn refine_boundaries(&mut self, cfg: &Config, engine: &dyn Engine, input: &Path) {
+        let slack = cfg.chunking.boundary_slack_pages;
+        if slack == 0 || self.chunks.len() < 2 {
+            return;
+        }
+        let layouts = match engine.page_layout(input, &self.seam_window_pages(slack)) {
+            Ok(layouts) => layouts,
+            Err(err) => {
+                warn!("layout pre-pass failed; keeping planned chunk boundaries: {err:#}");
+                return;
+            }
+        };
+        for (old_end, new_end) in self.avoid_table_splits(cfg, &layouts) {
+            info!("chunk seam moved from after page {old_end} to after page {new_end} to keep a table whole");
+        }
+    }
+}
+
+/// Chunk pages `start..=end` by `chunking.target_pages_per_chunk`, never over
+/// `max_pages_per_chunk`, folding a short tail into the last chunk.
+fn split_run(cfg: &Config, start: u32, end: u32) -> Vec<PageRange> {
+    let target = cfg.chunking.target_pages_per_chunk.max(1);
+    let maxp = cfg.chunking.max_pages_per_chunk.max(1);
+    let minp = cfg.chunking.min_pages_per_chunk.max(1).min(maxp);
+
+    let mut chunks = Vec::new();
+    let mut p = start;
+
+    while p <= end {
+        let mut last = (p + target - 1).min(end);
+        let span = last - p + 1;
+        if span > maxp {
+            last = p + maxp - 1;
+        }
+
+        let remaining = end.saturating_sub(last);
+        if remaining > 0 && remaining < minp && !chunks.is_empty() {
+            last = end;
         }
+
+        chunks.push(PageRange {
+            start_page: p,
+            end_page: last,
+        });
+        p = last + 1;
+    }
+    chunks
+}
+
+/// Whether the input is large enough that `limits` require it to be split.
+pub fn requires_chunking(cfg: &Config, probe: &ProbeResult) -> bool {
+    probe.input.page_count > cfg.limits.require_chunking_over_pages
+        || probe.input.file_bytes > cfg.limits.require_chunking_over_bytes
+}
+
+/// Read a plan written by `plan --write`, or the plan of a job's `job.json`.
+pub fn read_plan_json(path: &Path) -> Result<ChunkPlan> {
+    let raw =
+        std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut value: serde_json::Value =
+        serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+    if let Some(plan) = value.get_mut("plan") {
+        value = plan.take();
     }
+    serde_json::from_value(value).with_context(|| format!("not a chunk plan: {}", path.display()))
 }
@@ -0,0 +1,96 @@
+use crate::{config::ChunkStage, postprocess::is_marker_line};
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+
+/// The chunk a stage runs on, for its filters and replacement placeholders.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkInfo<'a> {
+    pub index: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+    pub engine: &'a str,
+    pub content_type: Option<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+enum Action {
+    Replace(String),
+    DropLines,
+}
+
+/// A `[[postprocess.chunk_stages]]` entry with its pattern compiled.
+#[derive(Debug, Clone)]
+pub struct CompiledStage {
+    pattern: Regex,
+    action: Action,
+    engines: Vec<String>,
+    content_types: Vec<String>,
+}
+
+impl CompiledStage {
+    fn applies_to(&self, chunk: &ChunkInfo) -> bool {
+        (self.engines.is_empty() || self.engines.iter().any(|e| e == chunk.engine))
+            && (self.content_types.is_empty()
+                || chunk
+                    .content_type
+                    .is_some_and(|c| self.content_types.iter().any(|t| t == c)))
+    }
+}
+
+/// Check and compile `postprocess.chunk_stages`.
+pub fn compile(stages: &[ChunkStage]) -> Result<Vec<CompiledStage>> {
+    stages
+        .iter()
+        .enumerate()
+        .map(|(i, stage)| {
+            let action = match stage.kind.as_str() {
+                "replace" => Action::Replace(stage.replacement.clone()),
+                "drop_lines" => Action::DropLines,
+                other => {
+                    return Err(anyhow!(
+                        "unknown postprocess.chunk_stages[{i}].kind: {other}"
+                    ))
+                }
+            };
+            let pattern = Regex::new(&stage.pattern)
+                .with_context(|| format!("postprocess.chunk_stages[{i}].pattern"))?;
+            Ok(CompiledStage {
+                pattern,
+                action,
+                engines: stage.engines.clone(),
+                content_types: stage.content_types.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Run the stages that apply to `chunk` over its markdown, in order.
+pub fn apply(stages: &[CompiledStage], part: &str, chunk: &ChunkInfo) -> String {
+    let mut part = part.to_string();
+    for stage in stages.iter().filter(|s| s.applies_to(chunk)) {
+        let lines = part.lines().filter_map(|line| {
+            if is_marker_line(line) || !stage.pattern.is_match(line) {
+                return Some(line.to_string());
+            }
+            match &stage.action {
+                Action::Replace(replacement) => Some(
+                    stage
+                        .pattern
+                        .replace_all(line, expand(replacement, chunk).as_str())
+                        .into_owned(),
+                ),
+                Action::DropLines => None,
+            }
+        });
+        part = lines.collect::<Vec<_>>().join("\n");
+    }
+    part
+}
+
+fn expand(replacement: &str, chunk: &ChunkInfo) -> String {
+    replacement
+        .replace("{index}", &chunk.index.to_string())
+        .replace("{start_page}", &chunk.start_page.to_string())
+        .replace("{end_page}", &chunk.end_page.to_string())
+        .replace("{engine}", chunk.engine)
+}
@@ -1,15 +1,39 @@
 use crate::{
+    archive::PreparedInput,
+    batch::{self, BatchEntry},
+    cancel::{self, Interrupted},
+    collections::{self, Registry},
+    completions,
     config::Config,
+    config_hash,
     engine::{python::PythonEngine, Engine},
-    pipeline::Pipeline,
-    util::{ensure_dir, now_rfc3339, sha256_hex},
+    exit_code::ErrorClass,
+    failure,
+    hooks::CommandHooks,
+    inspect,
+    jobs::{self, JobState},
+    lock::{self, InputLock},
+    logrotate::{self, RotatingFile},
+    metrics::Recorder,
+    pipeline::{JobOutput, Pipeline, StreamedChunk},
+    redact::{effective_config_toml, RedactingMakeWriter, Redactor},
+    replay::{self, RecordingEngine},
+    retention,
+    reuse::ReuseSource,
+    systemd::Notifier,
+    template,
+    util::{ensure_dir, now_rfc3339},
 };
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::{info, warn};
 use tracing_appender::non_blocking::WorkerGuard;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt::writer::BoxMakeWriter, layer::SubscriberExt,
+    util::SubscriberInitExt, EnvFilter, Layer,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "quack-check")]
@@ -22,52 +46,564 @@ pub struct Args {
     #[arg(long)]
     pub config: Option<PathBuf>,
 
+    /// Use the config registered under this name (see `collections add`) instead of
+    /// `--config`.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub collection: Option<String>,
+
+    /// Resolve relative `paths.*` against this directory instead of the config file's.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub workdir: Option<PathBuf>,
+
     /// Override log level (trace/debug/info/warn/error).
     #[arg(long)]
     pub log_level: Option<String>,
+
+    /// Print only errors (on stderr) and each command's final result.
+    #[arg(long, global = true)]
+    pub quiet: bool,
+
+    /// Print each command's result as a single JSON document on stdout; logs other than
+    /// errors are kept off the console.
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Never create or write anything: for `classify`, `plan` and `inspect` on read-only
+    /// mounts. Caches are bypassed and logs go to stderr only.
+    #[arg(long, global = true)]
+    pub read_only: bool,
+}
+
+/// Where command output goes: results always reach stdout (as one compact JSON
+/// document with `--json`), progress lines only in the default mode.
+#[derive(Debug, Clone, Copy)]
+struct Console {
+    quiet: bool,
+    json: bool,
+}
+
+impl Console {
+    fn from_args(args: &Args) -> Self {
+        Self {
+            quiet: args.quiet,
+            json: args.json,
+        }
+    }
+
+    /// Print a command's final result: `value` as JSON with `--json`, `human` otherwise.
+    fn result<T: serde::Serialize>(&self, value: &T, human: impl FnOnce() -> String) -> Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string(value)?);
+        } else {
+            let text = human();
+            if text.ends_with('\n') {
+                print!("{text}");
+            } else {
+                println!("{text}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a command's final result, shown as pretty JSON outside `--json` mode.
+    fn json_result<T: serde::Serialize>(&self, value: &T) -> Result<()> {
+        let pretty = serde_json::to_string_pretty(value)?;
+        self.result(value, || pretty)
+    }
+
+    /// Progress and per-item detail; dropped by `--quiet` and `--json`.
+    fn line(&self, text: impl std::fmt::Display) {
+        if !self.quiet && !self.json {
+            println!("{text}");
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
-    Doctor {},
+    Doctor {
+        /// Create the venv from `[docling.setup]`, install the pinned packages, and
+        /// point `docling.python_exe` in the config at it before checking.
+        #[arg(long)]
+        fix: bool,
+    },
     Classify {
         #[arg(long)]
         input: PathBuf,
+        /// Sample exactly N pages when probing, overriding classification.sample_pages
+        /// and its auto-scaling.
+        #[arg(long, value_name = "N")]
+        sample_pages: Option<u32>,
     },
     Plan {
+        #[arg(long, required_unless_present = "probe_json")]
+        input: Option<PathBuf>,
+        /// Plan from the output of a prior `classify` (or a bare probe) instead of
+        /// probing again. `--input` defaults to the probed path.
+        #[arg(long, value_name = "FILE")]
+        probe_json: Option<PathBuf>,
+        /// Also write the plan to FILE, to edit and pass to `run --plan`.
+        #[arg(long, value_name = "FILE")]
+        write: Option<PathBuf>,
+    },
+    /// Probe labeled PDFs and suggest `[classification]` thresholds.
+    Calibrate {
+        /// CSV of `path,tier` rows (tier: HIGH_TEXT, MIXED_TEXT or SCAN).
         #[arg(long)]
-        input: PathBuf,
+        labels: PathBuf,
     },
     Run {
         #[arg(long)]
         input: PathBuf,
         #[arg(long)]
         out_dir: Option<PathBuf>,
+        /// Sample exactly N pages when probing, overriding classification.sample_pages
+        /// and its auto-scaling.
+        #[arg(long, value_name = "N")]
+        sample_pages: Option<u32>,
+        /// Job id (or unique prefix, or directory) of an earlier job on a previous
+        /// version of this input; chunks whose pages are unchanged are reused.
+        #[arg(long, value_name = "JOB")]
+        reuse_from: Option<String>,
+        /// Convert exactly the chunks in this plan (from `plan --write`, possibly
+        /// hand-edited) instead of planning them.
+        #[arg(long, value_name = "FILE")]
+        plan: Option<PathBuf>,
+        /// Name this job, overriding global.job_name; may use the same variables,
+        /// e.g. `{input_stem}-{date}`.
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Run a generated fixture PDF through every stage to verify the installation.
+    SelfTest {},
+    /// Run every PDF under a directory, flagging duplicate inputs/transcripts.
+    Batch {
+        #[arg(long)]
+        input_dir: PathBuf,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Run one input under every combination of `--vary` settings and compare the
+    /// transcripts.
+    Matrix {
+        #[arg(long)]
+        input: PathBuf,
+        /// A dotted config key and the values to try, e.g.
+        /// `docling.pipeline.do_table_structure=true,false`. `engine=` sets every tier's
+        /// engine. Repeat for a cross-product.
+        #[arg(long, value_name = "KEY=V1,V2", required = true)]
+        vary: Vec<String>,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Run only the merge and cleanup stages on markdown or text from another tool,
+    /// writing the usual final outputs.
+    Postprocess {
+        /// Markdown or plain text; `<!-- quack:page N -->` markers or form feeds
+        /// separate its pages.
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Summarize a finished or partial job: report, chunk statuses, grouped warnings.
+    Inspect {
+        /// Job id, unique job id prefix, or job directory path.
+        #[arg(long)]
+        job: String,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+        /// Dump this chunk's metadata (or markdown, with --markdown) instead.
+        #[arg(long, value_name = "N")]
+        chunk: Option<u32>,
+        /// With --chunk, print the chunk's markdown instead of its metadata.
+        #[arg(long, requires = "chunk")]
+        markdown: bool,
+        /// Print the full report JSON instead of the summary.
+        #[arg(long, conflicts_with = "chunk")]
+        report: bool,
+    },
+    /// Run a job's pipeline and postprocessing again from the engine I/O it recorded
+    /// (`debug.record_engine_io`), without the PDF or Python, into `<job>/replay/`.
+    Replay {
+        /// Job id, unique job id prefix, or job directory path.
+        #[arg(long)]
+        job: String,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Inspect and maintain job directories.
+    Jobs {
+        #[command(subcommand)]
+        cmd: JobsCommand,
+    },
+    /// Work with config files.
+    Config {
+        #[command(subcommand)]
+        cmd: ConfigCommand,
+    },
+    /// Keep a registry of named configs for `--collection`.
+    Collections {
+        #[command(subcommand)]
+        cmd: CollectionsCommand,
+    },
+    /// Work with every finished job under the output directory at once.
+    Corpus {
+        #[command(subcommand)]
+        cmd: CorpusCommand,
+    },
+    /// Print a shell completion script; registered collection names are included.
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    /// Print the man page, or write one per subcommand into a directory.
+    Manpage {
+        /// Write `quack-check.1`, `quack-check-run.1`, ... here instead of printing.
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CorpusCommand {
+    /// Write documents, chunks, pages, warnings, and quality tables as NDJSON, with a
+    /// DuckDB script that loads them.
+    Export {
+        /// Directory for the tables and `load.sql`.
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+        /// Also load the tables into this DuckDB database (needs `duckdb` on PATH).
+        #[arg(long, value_name = "FILE")]
+        duckdb: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CollectionsCommand {
+    /// Register a config under a name.
+    Add {
+        name: String,
+        /// The config file; its absolute path is recorded.
+        #[arg(long)]
+        config: PathBuf,
+        /// A note shown by `collections list`.
+        #[arg(long)]
+        description: Option<String>,
+        /// Re-point an existing name.
+        #[arg(long)]
+        replace: bool,
+    },
+    /// List registered collections and whether their config changed since.
+    List {},
+    /// Forget a collection; its config file is left alone.
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Upgrade a config written for an earlier release: apply renamed, moved, and
+    /// reshaped keys, print the changes, and warn about deprecated and unknown keys.
+    Migrate {
+        /// The config to upgrade.
+        path: PathBuf,
+        /// Rewrite the file in place, keeping the original as `<path>.bak`.
+        #[arg(long)]
+        write: bool,
+        /// Write the upgraded config here instead.
+        #[arg(long, value_name = "FILE", conflicts_with = "write")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JobsCommand {
+    /// Find job directories in inconsistent states and repair, complete, or delete them.
+    Doctor {
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+        /// Only print what would be done.
+        #[arg(long)]
+        dry_run: bool,
+        /// Apply every proposed action without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Delete the intermediate artifacts the current `[retention]` rules no longer keep.
+    Clean {
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+        /// Only print what would be deleted.
+        #[arg(long)]
+        dry_run: bool,
+        /// Delete without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// List the jobs whose name (`global.job_name` or `run --name`) matches a pattern.
+    Find {
+        /// Job name; `*` matches any run of characters and `?` any one.
+        #[arg(long, value_name = "PATTERN")]
+        name: String,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Stop a job from starting more chunks; a run in progress stops after the chunks
+    /// it has started.
+    Pause {
+        /// Job id, unique job id prefix, or job directory path.
+        #[arg(long)]
+        job: String,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Let a paused job run again, keeping the chunks it converted before the pause.
+    Resume {
+        /// Job id, unique job id prefix, or job directory path.
+        #[arg(long)]
+        job: String,
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
     },
 }
 
 pub fn dispatch(args: Args) -> Result<()> {
-    let cfg_path = resolve_config_path(args.config.as_deref())?;
-    let cfg = Config::load(&cfg_path)?;
+    // Migration reads the file it is given and must not need a config that parses.
+    if let Command::Config {
+        cmd: ConfigCommand::Migrate { path, write, out },
+    } = &args.cmd
+    {
+        return config_migrate(Console::from_args(&args), path, *write, out.as_deref());
+    }
+    // Likewise the registry, whose `add` loads the config it registers.
+    if let Command::Collections { cmd } = &args.cmd {
+        return collections_cmd(Console::from_args(&args), cmd);
+    }
+    // And the generated docs, which only describe the command line.
+    match &args.cmd {
+        Command::Completions { shell } => {
+            completions::write_completions(*shell, &mut std::io::stdout());
+            return Ok(());
+        }
+        Command::Manpage { out_dir: None } => {
+            return completions::write_manpage(&mut std::io::stdout());
+        }
+        Command::Manpage { out_dir: Some(dir) } => {
+            completions::write_manpages(dir)?;
+            return Console::from_args(&args).result(
+                &serde_json::json!({ "out_dir": dir }),
+                || format!("wrote man pages to {}", dir.display()),
+            );
+        }
+        _ => {}
+    }
+
+    let (cfg_path, mut cfg) = load_config(&args).context(ErrorClass::Config)?;
+    if let Command::Classify {
+        sample_pages: Some(n),
+        ..
+    }
+    | Command::Run {
+        sample_pages: Some(n),
+        ..
+    } = &args.cmd
+    {
+        cfg.classification.pin_sample_pages(*n);
+    }
+    if args.read_only {
+        make_read_only(&args.cmd, &mut cfg).context(ErrorClass::Config)?;
+    }
 
+    let console = Console::from_args(&args);
     match &args.cmd {
-        Command::Doctor {} => {
+        Command::Doctor { fix } => {
             let log_path = resolve_log_path(&cfg, None);
             let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
-            doctor(&cfg)
+            if *fix {
+                doctor_fix(&mut cfg, &cfg_path, console)?;
+            }
+            doctor(&cfg, console)
         }
-        Command::Classify { input } => {
+        Command::SelfTest {} => {
             let log_path = resolve_log_path(&cfg, None);
             let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
-            classify(&cfg, input)
+            self_test(&cfg, console)
         }
-        Command::Plan { input } => {
+        Command::Classify { input, .. } => {
             let log_path = resolve_log_path(&cfg, None);
             let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
-            plan(&cfg, input)
+            classify(&cfg, console, input)
+        }
+        Command::Plan {
+            input,
+            probe_json,
+            write,
+        } => {
+            let log_path = resolve_log_path(&cfg, None);
+            let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
+            plan(
+                &cfg,
+                console,
+                input.as_deref(),
+                probe_json.as_deref(),
+                write.as_deref(),
+            )
+        }
+        Command::Calibrate { labels } => {
+            let log_path = resolve_log_path(&cfg, None);
+            let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
+            calibrate(&cfg, console, labels)
+        }
+        Command::Run {
+            input,
+            out_dir,
+            reuse_from,
+            plan,
+            ..
+        } => run(
+            &args,
+            &cfg,
+            input,
+            out_dir.as_deref(),
+            reuse_from.as_deref(),
+            plan.as_deref(),
+        ),
+        Command::Batch { input_dir, out_dir } => {
+            batch(&args, &cfg, input_dir, out_dir.as_deref())
+        }
+        Command::Matrix {
+            input,
+            vary,
+            out_dir,
+        } => matrix(&args, &cfg, input, vary, out_dir.as_deref()),
+        Command::Postprocess { input, out_dir } => {
+            postprocess_cmd(&args, &cfg, input, out_dir.as_deref())
+        }
+        Command::Inspect {
+            job,
+            out_dir,
+            chunk,
+            markdown,
+            report,
+        } => inspect(&cfg, console, job, out_dir.as_deref(), *chunk, *markdown, *report),
+        Command::Replay { job, out_dir } => {
+            let job_dir = inspect::resolve_job_dir(&out_root(&cfg, out_dir.as_deref()), job)?;
+            console.json_result(&replay::replay_job(&cfg, &job_dir)?)
+        }
+        Command::Jobs {
+            cmd: JobsCommand::Doctor {
+                out_dir,
+                dry_run,
+                yes,
+            },
+        } => {
+            let log_path = resolve_log_path(&cfg, None);
+            let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
+            jobs_doctor(&cfg, console, out_dir.as_deref(), *dry_run, *yes)
+        }
+        Command::Jobs {
+            cmd: JobsCommand::Clean {
+                out_dir,
+                dry_run,
+                yes,
+            },
+        } => {
+            let log_path = resolve_log_path(&cfg, None);
+            let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
+            jobs_clean(&cfg, console, out_dir.as_deref(), *dry_run, *yes)
+        }
+        Command::Jobs {
+            cmd: JobsCommand::Find { name, out_dir },
+        } => jobs_find(&cfg, console, name, out_dir.as_deref()),
+        Command::Jobs {
+            cmd: JobsCommand::Pause { job, out_dir },
+        } => jobs_pause(&cfg, console, job, out_dir.as_deref(), true),
+        Command::Jobs {
+            cmd: JobsCommand::Resume { job, out_dir },
+        } => jobs_pause(&cfg, console, job, out_dir.as_deref(), false),
+        Command::Corpus {
+            cmd:
+                CorpusCommand::Export {
+                    out,
+                    out_dir,
+                    duckdb,
+                },
+        } => {
+            let log_path = resolve_log_path(&cfg, None);
+            let _guard = init_logging(&args, &cfg, log_path.as_deref())?;
+            corpus_export(&cfg, console, out, out_dir.as_deref(), duckdb.as_deref())
+        }
+        Command::Config { .. }
+        | Command::Collections { .. }
+        | Command::Completions { .. }
+        | Command::Manpage { .. } => {
+            unreachable!("handled before loading the config")
+        }
+    }
+}
+
+/// `--read-only`: refuse commands that would write, and turn off everything else that
+/// does. The probe cache is bypassed, logs go to stderr only, and Python is told not to
+/// write bytecode next to the scripts.
+fn make_read_only(cmd: &Command, cfg: &mut Config) -> Result<()> {
+    let input = match cmd {
+        Command::Classify { input, .. } => Some(input),
+        Command::Plan { write: Some(_), .. } => {
+            return Err(anyhow!("--read-only cannot write a plan (--write)"));
+        }
+        Command::Plan { input, .. } => input.as_ref(),
+        Command::Inspect { .. } => None,
+        _ => return Err(anyhow!("--read-only only applies to classify, plan and inspect")),
+    };
+    if let Some(input) = input
+        && crate::archive::is_compressed(input)
+    {
+        return Err(anyhow!(
+            "--read-only cannot decompress {} into paths.work_dir",
+            input.display()
+        ));
+    }
+    cfg.classification.probe_cache_ttl_seconds = 0;
+    cfg.logging.write_to_file = false;
+    cfg.docling
+        .env
+        .insert("PYTHONDONTWRITEBYTECODE".into(), "1".into());
+    Ok(())
+}
+
+/// The directory relative `paths.*` in the config at `path` are resolved against.
+/// The config named by `--collection` or `--config` (or the default), with its paths
+/// resolved and its templates expanded.
+fn load_config(args: &Args) -> Result<(PathBuf, Config)> {
+    let cfg_path = match &args.collection {
+        Some(_) if args.config.is_some() => {
+            return Err(anyhow!("--collection and --config are mutually exclusive"));
         }
-        Command::Run { input, out_dir } => run(&args, &cfg, input, out_dir.as_deref()),
+        Some(name) => Registry::load(&collections::registry_path())?
+            .resolve(name)?
+            .to_path_buf(),
+        None => resolve_config_path(args.config.as_deref())?,
+    };
+    let mut cfg = Config::load(&cfg_path)?;
+    if let Some(dir) = &args.workdir {
+        cfg.paths.root = std::path::absolute(dir)?.display().to_string();
+    }
+    cfg.resolve_paths(config_dir(&cfg_path))?;
+    if let Command::Run {
+        name: Some(name), ..
+    } = &args.cmd
+    {
+        cfg.global.job_name = name.clone();
     }
+    template::expand_config(&mut cfg)?;
+    Ok((cfg_path, cfg))
+}
+
+fn config_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
 }
 
 fn resolve_config_path(user: Option<&Path>) -> Result<PathBuf> {
@@ -90,25 +626,50 @@ fn init_logging(args: &Args, cfg: &Config, file_path: Option<&Path>) -> Result<O
 
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
 
+    let redactor = Arc::new(Redactor::from_config(cfg));
+    // `--quiet` and `--json` keep stdout for results: only errors reach the console,
+    // on stderr. The log file keeps the configured level either way.
+    let (console, console_level) = if args.quiet || args.json {
+        (
+            BoxMakeWriter::new(RedactingMakeWriter::new(std::io::stderr, redactor.clone())),
+            LevelFilter::ERROR,
+        )
+    } else {
+        (
+            BoxMakeWriter::new(RedactingMakeWriter::new(std::io::stdout, redactor.clone())),
+            LevelFilter::TRACE,
+        )
+    };
+
     let stdout_layer = if cfg.logging.json {
         tracing_subscriber::fmt::layer()
             .json()
+            .with_writer(console)
             .with_target(true)
+            .with_filter(console_level)
             .boxed()
     } else {
         tracing_subscriber::fmt::layer()
+            .with_writer(console)
             .with_target(true)
+            .with_filter(console_level)
             .boxed()
     };
 
     let (file_layer, guard) = if let Some(path) = file_path {
         let parent = path.parent().unwrap_or_else(|| Path::new("."));
         ensure_dir(parent)?;
-        let file = std::fs::File::create(path)
-            .with_context(|| format!("create log file: {}", path.display()))?;
-        let (non_blocking, guard) = tracing_appender::non_blocking(file);
+        let (non_blocking, guard) = if is_shared_log(cfg, path)
+            && logrotate::enabled(&cfg.logging.rotate)
+        {
+            tracing_appender::non_blocking(RotatingFile::open(path, &cfg.logging.rotate)?)
+        } else {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("create log file: {}", path.display()))?;
+            tracing_appender::non_blocking(file)
+        };
         let layer = tracing_subscriber::fmt::layer()
-            .with_writer(non_blocking)
+            .with_writer(RedactingMakeWriter::new(non_blocking, redactor))
             .with_ansi(false)
             .with_target(true)
             .boxed();
@@ -117,60 +678,905 @@ fn init_logging(args: &Args, cfg: &Config, file_path: Option<&Path>) -> Result<O
         (None, None)
     };
 
+    let journald_layer = if cfg.logging.journald {
+        Some(journald_layer()?)
+    } else {
+        None
+    };
+
     tracing_subscriber::registry()
         .with(filter)
         .with(stdout_layer)
         .with(file_layer)
+        .with(journald_layer)
         .try_init()
         .map_err(|e| anyhow!("failed to init logging: {e}"))?;
 
     Ok(guard)
 }
 
-fn doctor(cfg: &Config) -> Result<()> {
+/// Events with their fields as `QUACK_*` journal fields, e.g. `QUACK_JOB_ID` from the
+/// job span, under the `quack-check` syslog identifier.
+#[cfg(unix)]
+fn journald_layer<S>() -> Result<Box<dyn Layer<S> + Send + Sync>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let layer = tracing_journald::layer()
+        .context("logging.journald: connecting to journald")?
+        .with_field_prefix(Some("QUACK".into()))
+        .with_syslog_identifier("quack-check".into());
+    Ok(layer.boxed())
+}
+
+#[cfg(not(unix))]
+fn journald_layer<S>() -> Result<Box<dyn Layer<S> + Send + Sync>>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    Err(anyhow!("logging.journald is only supported on Unix"))
+}
+
+fn doctor(cfg: &Config, console: Console) -> Result<()> {
     let engine = PythonEngine::new(cfg)?;
     let diag = engine.doctor()?;
-    println!("{}", serde_json::to_string_pretty(&diag)?);
+    console.json_result(&diag)
+}
+
+/// Bootstrap the Python environment, then record it in the config file.
+fn doctor_fix(cfg: &mut Config, cfg_path: &Path, console: Console) -> Result<()> {
+    let plan = crate::setup::plan(cfg)?;
+    crate::setup::run(&plan, |step| {
+        console.line(format!("{}: {}", step.description, step.argv.join(" ")))
+    })?;
+
+    let raw = std::fs::read_to_string(cfg_path)
+        .with_context(|| format!("reading config: {}", cfg_path.display()))?;
+    let updated = crate::setup::set_python_exe(&raw, &plan.python_exe)?;
+    if updated != raw {
+        std::fs::write(cfg_path, updated)
+            .with_context(|| format!("writing config: {}", cfg_path.display()))?;
+        console.line(format!(
+            "set docling.python_exe = {} in {}",
+            plan.python_exe.display(),
+            cfg_path.display()
+        ));
+    }
+    cfg.docling.python_exe = plan.python_exe.display().to_string();
     Ok(())
 }
 
-fn classify(cfg: &Config, input: &Path) -> Result<()> {
+fn self_test(cfg: &Config, console: Console) -> Result<()> {
     let engine = PythonEngine::new(cfg)?;
-    let probe = crate::probe::probe_pdf(cfg, &engine, input)?;
-    let decision = crate::policy::decide(cfg, &probe);
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&serde_json::json!({
-            "input": input,
-            "probe": probe,
-            "decision": decision,
-        }))?
-    );
+    let work_dir = Path::new(&cfg.paths.work_dir).join("self-test");
+    if work_dir.exists() {
+        std::fs::remove_dir_all(&work_dir)?;
+    }
+    let report = crate::selftest::run(cfg, engine, &work_dir)?;
+
+    console.result(&report, || {
+        let lines: Vec<String> = report
+            .stages
+            .iter()
+            .map(|stage| {
+                let status = if stage.ok { "PASS" } else { "FAIL" };
+                format!("{status} {:<20} {}", stage.stage, stage.detail)
+            })
+            .collect();
+        lines.join("\n")
+    })?;
+    if !cfg.global.keep_intermediates {
+        std::fs::remove_dir_all(&work_dir)?;
+    }
+    if !report.passed() {
+        let failed = report.stages.iter().filter(|s| !s.ok).count();
+        return Err(anyhow!("self-test failed: {failed} stage(s)"));
+    }
     Ok(())
 }
 
-fn plan(cfg: &Config, input: &Path) -> Result<()> {
+fn classify(cfg: &Config, console: Console, input: &Path) -> Result<()> {
+    console.json_result(&classify_summary(cfg, input)?)
+}
+
+fn classify_summary(cfg: &Config, input: &Path) -> Result<serde_json::Value> {
+    let engine = PythonEngine::new(cfg)?;
+    let prepared = crate::archive::prepare_input(cfg, input).context(ErrorClass::Input)?;
+    let mut probe = probe_input(cfg, &engine, &prepared.path).context(ErrorClass::Probe)?;
+    // Point at the user's file, not a decompressed copy, for `plan --probe-json`.
+    probe.input.path = input.display().to_string();
+    let decision = crate::policy::decide(cfg, &probe);
+    Ok(serde_json::json!({
+        "input": input,
+        "probe": probe,
+        "decision": decision,
+    }))
+}
+
+fn plan(
+    cfg: &Config,
+    console: Console,
+    input: Option<&Path>,
+    probe_json: Option<&Path>,
+    write: Option<&Path>,
+) -> Result<()> {
     let engine = PythonEngine::new(cfg)?;
-    let probe = crate::probe::probe_pdf(cfg, &engine, input)?;
-    let plan = crate::chunk_plan::ChunkPlan::from_probe(cfg, &probe)?;
-    println!("{}", serde_json::to_string_pretty(&plan)?);
+    let given = probe_json.map(crate::probe::read_probe_json).transpose()?;
+    let input = match (input, &given) {
+        (Some(input), _) => input.to_path_buf(),
+        (None, Some(probe)) => PathBuf::from(&probe.input.path),
+        (None, None) => return Err(anyhow!("plan needs --input or --probe-json")),
+    };
+    let cfg = &crate::sidecar::overlay(cfg, &input)?;
+    let prepared = crate::archive::prepare_input(cfg, &input).context(ErrorClass::Input)?;
+    let probe = match given {
+        Some(probe) => probe,
+        None => probe_input(cfg, &engine, &prepared.path).context(ErrorClass::Probe)?,
+    };
+    let excluded = crate::exclusions::resolve(cfg, probe.input.page_count)?;
+    let mut plan =
+        crate::chunk_plan::ChunkPlan::from_probe(cfg, &probe)?.without_pages(cfg, &excluded);
+    plan.refine_boundaries(cfg, &engine, &prepared.path);
+    if let Some(path) = write {
+        std::fs::write(path, serde_json::to_string_pretty(&plan)?)
+            .with_context(|| format!("write {}", path.display()))?;
+        info!("wrote chunk plan to {}", path.display());
+    }
+    console.json_result(&plan)
+}
+
+/// Probe through the probe cache unless it is disabled.
+fn probe_input(cfg: &Config, engine: &dyn Engine, input: &Path) -> Result<crate::probe::ProbeResult> {
+    match crate::probe::ProbeCache::from_config(cfg) {
+        Some(cache) => cache.probe(cfg, engine, input),
+        None => crate::probe::probe_pdf(cfg, engine, input),
+    }
+}
+
+fn calibrate(cfg: &Config, console: Console, labels: &Path) -> Result<()> {
+    let engine = PythonEngine::new(cfg)?;
+    let labels = crate::calibrate::read_labels(labels)?;
+    let mut samples = Vec::with_capacity(labels.len());
+    for label in labels {
+        let probe = crate::probe::probe_pdf(cfg, &engine, &label.path)
+            .with_context(|| format!("probe {}", label.path.display()))?;
+        info!(
+            "probed {}: labeled={:?} avg_chars={} garbage={:.4} whitespace={:.4}",
+            label.path.display(),
+            label.tier,
+            probe.sample.avg_chars_per_page,
+            probe.sample.garbage_ratio,
+            probe.sample.whitespace_ratio
+        );
+        samples.push(crate::calibrate::LabeledSample {
+            path: label.path,
+            expected: label.tier,
+            sample: probe.sample,
+        });
+    }
+    let result = crate::calibrate::calibrate(&cfg.classification, &samples);
+    console.result(&result, || result.to_toml())
+}
+
+fn config_migrate(console: Console, path: &Path, write: bool, out: Option<&Path>) -> Result<()> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("reading config: {}", path.display()))?;
+    let migration = crate::migrate::migrate(&raw)
+        .with_context(|| format!("migrating {}", path.display()))?;
+    let dest = if write { Some(path) } else { out };
+    if let Some(dest) = dest {
+        if dest == path {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".bak");
+            std::fs::copy(path, &backup)
+                .with_context(|| format!("backing up {}", path.display()))?;
+        }
+        std::fs::write(dest, &migration.migrated)
+            .with_context(|| format!("writing {}", dest.display()))?;
+    }
+    console.result(&migration, || {
+        let mut text = migration.summary();
+        match dest {
+            Some(dest) => text.push_str(&format!("wrote {}\n", dest.display())),
+            None if !migration.changes.is_empty() => {
+                text.push_str("\n(dry run; pass --write or --out to save the upgraded config)\n")
+            }
+            None => {}
+        }
+        text
+    })
+}
+
+fn collections_cmd(console: Console, cmd: &CollectionsCommand) -> Result<()> {
+    let path = collections::registry_path();
+    let mut registry = Registry::load(&path)?;
+    match cmd {
+        CollectionsCommand::Add {
+            name,
+            config,
+            description,
+            replace,
+        } => {
+            let added = registry
+                .add(name, config, description.as_deref(), *replace)?
+                .clone();
+            registry.save(&path)?;
+            console.result(&added, || {
+                format!("registered {name} -> {}", added.config.display())
+            })
+        }
+        CollectionsCommand::List {} => {
+            let statuses = registry.statuses();
+            console.result(&statuses, || {
+                if statuses.is_empty() {
+                    return format!("no collections registered in {}", path.display());
+                }
+                let mut text = String::new();
+                for status in &statuses {
+                    text.push_str(&format!(
+                        "{}\t{}\t{}",
+                        status.name,
+                        status.state,
+                        status.collection.config.display()
+                    ));
+                    if let Some(description) = &status.collection.description {
+                        text.push_str(&format!("\t{description}"));
+                    }
+                    text.push('\n');
+                }
+                text
+            })
+        }
+        CollectionsCommand::Remove { name } => {
+            let removed = registry.remove(name)?;
+            registry.save(&path)?;
+            console.result(&removed, || format!("removed {name}"))
+        }
+    }
+}
+
+fn corpus_export(
+    cfg: &Config,
+    console: Console,
+    out: &Path,
+    out_override: Option<&Path>,
+    duckdb: Option<&Path>,
+) -> Result<()> {
+    let summary = crate::corpus::export(cfg, &out_root(cfg, out_override), out)?;
+    if let Some(database) = duckdb {
+        crate::corpus::load_into_duckdb(&summary.out, database)?;
+        console.line(format!("loaded into {}", database.display()));
+    }
+    console.result(&summary, || {
+        let mut text = format!(
+            "exported {} document(s) to {}\n",
+            summary.documents,
+            summary.out.display()
+        );
+        for (table, rows) in &summary.rows {
+            text.push_str(&format!("  {table}: {rows} row(s)\n"));
+        }
+        if !summary.skipped_jobs.is_empty() {
+            text.push_str(&format!(
+                "skipped {} job(s) without a final report\n",
+                summary.skipped_jobs.len()
+            ));
+        }
+        text
+    })
+}
+
+fn jobs_doctor(
+    cfg: &Config,
+    console: Console,
+    out_override: Option<&Path>,
+    dry_run: bool,
+    yes: bool,
+) -> Result<()> {
+    if console.json && !dry_run && !yes {
+        return Err(anyhow!("jobs doctor --json needs --dry-run or --yes"));
+    }
+    let checks = jobs::scan(cfg, &out_root(cfg, out_override))?;
+    let mut applied = 0;
+    let mut outcomes = Vec::new();
+
+    for check in &checks {
+        let state = serde_json::to_value(check.state)?;
+        console.line(format!(
+            "{} [{}] {}",
+            check.job_id,
+            state.as_str().unwrap_or("?"),
+            check.detail
+        ));
+        let mut actions = Vec::new();
+        if check.state != JobState::Running {
+            for action in &check.actions {
+                let status = if dry_run {
+                    console.line(format!("  would {}", action.describe()));
+                    "planned"
+                } else if !yes && !confirm(&format!("  {}?", action.describe()))? {
+                    console.line("  skipped");
+                    "skipped"
+                } else {
+                    jobs::apply(cfg, check, action)
+                        .with_context(|| format!("{}: {}", check.job_id, action.describe()))?;
+                    console.line(format!("  done: {}", action.describe()));
+                    applied += 1;
+                    "applied"
+                };
+                actions.push(serde_json::json!({
+                    "action": action.describe(),
+                    "status": status,
+                }));
+            }
+        }
+        outcomes.push(serde_json::json!({
+            "job_id": check.job_id,
+            "state": state,
+            "detail": check.detail,
+            "actions": actions,
+        }));
+    }
+
+    info!("jobs doctor: {} job(s) checked, {} action(s) applied", checks.len(), applied);
+    console.result(
+        &serde_json::json!({
+            "checked": checks.len(),
+            "applied": applied,
+            "jobs": outcomes,
+        }),
+        || format!("{} job(s) checked, {} action(s) applied", checks.len(), applied),
+    )
+}
+
+fn jobs_find(
+    cfg: &Config,
+    console: Console,
+    pattern: &str,
+    out_override: Option<&Path>,
+) -> Result<()> {
+    let found = jobs::find_by_name(&out_root(cfg, out_override), pattern)?;
+    console.result(&found, || {
+        if found.is_empty() {
+            return format!("no job named {pattern}");
+        }
+        found
+            .iter()
+            .map(|job| {
+                format!(
+                    "{}  {}  {}  {}",
+                    job.job_name,
+                    job.job_id,
+                    job.finished.as_deref().unwrap_or("unfinished"),
+                    job.job_dir.display()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+/// `jobs pause` (`pause = true`) and `jobs resume`.
+fn jobs_pause(
+    cfg: &Config,
+    console: Console,
+    job: &str,
+    out_override: Option<&Path>,
+    pause: bool,
+) -> Result<()> {
+    let job_dir = inspect::resolve_job_dir(&out_root(cfg, out_override), job)?;
+    let state = if pause {
+        Some(crate::pause::pause(&job_dir)?)
+    } else {
+        crate::pause::resume(&job_dir)?
+    };
+    let value = serde_json::json!({ "job_dir": job_dir, "pause": state });
+    console.result(&value, || match (&state, pause) {
+        (Some(_), true) => format!("paused {}", job_dir.display()),
+        (Some(_), false) => format!(
+            "resumed {}; run the job again to continue",
+            job_dir.display()
+        ),
+        (None, _) => format!("{} is not paused", job_dir.display()),
+    })
+}
+
+fn jobs_clean(
+    cfg: &Config,
+    console: Console,
+    out_override: Option<&Path>,
+    dry_run: bool,
+    yes: bool,
+) -> Result<()> {
+    if console.json && !dry_run && !yes {
+        return Err(anyhow!("jobs clean --json needs --dry-run or --yes"));
+    }
+    retention::validate(cfg)?;
+    let active = lock::active_job_ids(cfg)?;
+    let now = std::time::SystemTime::now();
+    let mut freed = 0u64;
+    let mut deleted = 0usize;
+    let mut outcomes = Vec::new();
+
+    for job_dir in jobs::job_dirs(&out_root(cfg, out_override))? {
+        let job_id = job_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if active.contains(&job_id) {
+            console.line(format!("{job_id} [running] skipped"));
+            continue;
+        }
+        let artifacts = retention::expired(cfg, &job_dir, now)?;
+        if artifacts.is_empty() {
+            continue;
+        }
+        let bytes: u64 = artifacts.iter().map(|a| a.bytes).sum();
+        console.line(format!("{job_id}: {} file(s), {bytes} bytes", artifacts.len()));
+        let status = if dry_run {
+            for a in &artifacts {
+                console.line(format!("  would delete {} {}", a.class, a.path.display()));
+            }
+            "planned"
+        } else if !yes && !confirm("  delete them?")? {
+            console.line("  skipped");
+            "skipped"
+        } else {
+            for a in &artifacts {
+                std::fs::remove_file(&a.path)
+                    .with_context(|| format!("remove {}", a.path.display()))?;
+            }
+            deleted += artifacts.len();
+            freed += bytes;
+            "applied"
+        };
+        outcomes.push(serde_json::json!({
+            "job_id": job_id,
+            "status": status,
+            "bytes": bytes,
+            "artifacts": artifacts,
+        }));
+    }
+
+    info!("jobs clean: {deleted} file(s) deleted, {freed} bytes freed");
+    console.result(
+        &serde_json::json!({
+            "deleted": deleted,
+            "freed_bytes": freed,
+            "jobs": outcomes,
+        }),
+        || format!("{deleted} file(s) deleted, {freed} bytes freed"),
+    )
+}
+
+fn inspect(
+    cfg: &Config,
+    console: Console,
+    job: &str,
+    out_override: Option<&Path>,
+    chunk: Option<u32>,
+    markdown: bool,
+    report: bool,
+) -> Result<()> {
+    let job_dir = inspect::resolve_job_dir(&out_root(cfg, out_override), job)?;
+    let view = inspect::load(cfg, &job_dir)?;
+
+    if let Some(index) = chunk {
+        let record = view.record(index)?;
+        if markdown {
+            let value = serde_json::json!({
+                "chunk_index": record.chunk_index,
+                "markdown": record.out.markdown,
+            });
+            return console.result(&value, || record.out.markdown.clone());
+        }
+        return console.json_result(&serde_json::json!({
+            "chunk_index": record.chunk_index,
+            "start_page": record.start_page,
+            "end_page": record.end_page,
+            "ok": record.out.ok,
+            "warnings": record.out.warnings,
+            "page_errors": record.page_errors,
+            "stderr_log": record.stderr_log,
+            "meta": record.out.meta,
+        }));
+    }
+    if report {
+        let report = view
+            .report
+            .as_ref()
+            .ok_or_else(|| anyhow!("job {} has no final report", view.job_id))?;
+        return console.json_result(report);
+    }
+    console.result(&view.summary_json(), || view.summary())
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    use std::io::Write;
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn run(
+    args: &Args,
+    cfg: &Config,
+    input: &Path,
+    out_override: Option<&Path>,
+    reuse_from: Option<&str>,
+    plan: Option<&Path>,
+) -> Result<()> {
+    let target = run_target(cfg, input, out_override, reuse_from, plan)?;
+    let cfg = &target.cfg;
+
+    // Hold the input lock for the rest of the run so concurrent invocations on the same
+    // PDF cannot race on the same job_dir.
+    let _lock = InputLock::acquire(cfg, &target.input_hash, &target.job_id)?;
+    open_job_dir(cfg, &target).context(ErrorClass::Output)?;
+
+    let log_path = resolve_log_path(cfg, Some(&target.job_dir));
+    let _guard = init_logging(args, cfg, log_path.as_deref())?;
+
+    let metrics = Recorder::from_config(cfg);
+    let notifier = Notifier::from_env();
+    if let Some(notifier) = &notifier {
+        notifier.ready(&format!("starting job {}", short_id(&target.job_id)));
+    }
+    let rerun = rerun_command(args, input, out_override);
+    let outcome = execute_job(cfg, input, &target, &rerun, metrics.as_ref(), notifier.as_ref());
+    if let Some(notifier) = &notifier {
+        let status = outcome.as_ref().map_or("failed", |r| r.report.completeness.status());
+        notifier.stopping(&format!("job {} {status}", short_id(&target.job_id)));
+    }
+    let result = outcome?;
+
+    if cfg.global.print_summary || args.json {
+        Console::from_args(args).json_result(&run_summary(&target, &result))?;
+    }
+
     Ok(())
 }
 
-fn run(args: &Args, cfg: &Config, input: &Path, out_override: Option<&Path>) -> Result<()> {
-    validate_input(cfg, input)?;
+fn run_target(
+    cfg: &Config,
+    input: &Path,
+    out_override: Option<&Path>,
+    reuse_from: Option<&str>,
+    plan: Option<&Path>,
+) -> Result<JobTarget> {
+    let mut target = job_target(cfg, input, out_override, None)?;
+    if let Some(job) = reuse_from {
+        target.reuse_from = Some(inspect::resolve_job_dir(&out_root(cfg, out_override), job)?);
+    }
+    if let Some(path) = plan {
+        target.plan = Some(crate::chunk_plan::read_plan_json(path)?);
+    }
+    Ok(target)
+}
 
-    let cfg_norm = cfg.normalized_for_hash();
-    let cfg_hash = sha256_hex(cfg_norm.as_bytes());
+fn run_summary(target: &JobTarget, result: &JobOutput) -> serde_json::Value {
+    serde_json::json!({
+        "job_id": target.job_id,
+        "job_dir": target.job_dir,
+        "status": result.report.completeness.status(),
+        "completeness": result.report.completeness,
+    })
+}
+
+/// `run` or `classify` for a caller embedding the crate (the `ffi` feature): the
+/// result `--json` would print, without logging setup, metrics, or service
+/// notifications, so it can be called any number of times in one process.
+pub fn embedded(args: &Args) -> Result<serde_json::Value> {
+    let (_, cfg) = load_config(args).context(ErrorClass::Config)?;
+    match &args.cmd {
+        Command::Classify { input, .. } => classify_summary(&cfg, input),
+        Command::Run {
+            input,
+            out_dir,
+            reuse_from,
+            plan,
+            ..
+        } => {
+            let target =
+                run_target(&cfg, input, out_dir.as_deref(), reuse_from.as_deref(), plan.as_deref())?;
+            let cfg = &target.cfg;
+            let _lock = InputLock::acquire(cfg, &target.input_hash, &target.job_id)?;
+            open_job_dir(cfg, &target).context(ErrorClass::Output)?;
+            let rerun = rerun_command(args, input, out_dir.as_deref());
+            let result = execute_job(cfg, input, &target, &rerun, None, None)?;
+            Ok(run_summary(&target, &result))
+        }
+        _ => Err(anyhow!("only run and classify can be embedded")),
+    }
+}
+
+/// Clean up a transcript made elsewhere into a job directory of its own, keyed by the
+/// text's hash.
+fn postprocess_cmd(
+    args: &Args,
+    cfg: &Config,
+    input: &Path,
+    out_override: Option<&Path>,
+) -> Result<()> {
+    let text = std::fs::read_to_string(input)
+        .with_context(|| format!("reading input: {}", input.display()))
+        .context(ErrorClass::Input)?;
     let input_hash = crate::util::hash_file(cfg, input)
-        .with_context(|| format!("hashing input: {}", input.display()))?;
-    let job_id = sha256_hex(format!("{}:{}", cfg_hash, input_hash).as_bytes());
+        .with_context(|| format!("hashing input: {}", input.display()))
+        .context(ErrorClass::Input)?;
+    let job_id = config_hash::job_id(cfg, &input_hash);
+    let mut cfg = cfg.clone();
+    if let Some(dir) = out_override {
+        cfg.paths.out_dir = dir.display().to_string();
+    }
+    let cfg = &template::for_job(&cfg, input, &job_id).context(ErrorClass::Config)?;
+    let job_dir = PathBuf::from(&cfg.paths.out_dir).join(&job_id);
+    if job_dir.exists() && !cfg.global.resume {
+        return Err(anyhow!(
+            "job_dir already exists and resume=false: {}",
+            job_dir.display()
+        ))
+        .context(ErrorClass::Output);
+    }
+    ensure_dir(&job_dir.join("final")).context(ErrorClass::Output)?;
+    ensure_dir(&job_dir.join("logs")).context(ErrorClass::Output)?;
+
+    let log_path = resolve_log_path(cfg, Some(&job_dir));
+    let _guard = init_logging(args, cfg, log_path.as_deref())?;
+    info!("job_id={job_id} out={}", job_dir.display());
 
-    let out_root = out_override
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from(&cfg.paths.out_dir));
-    let job_dir = out_root.join(&job_id);
+    let started = now_rfc3339();
+    let result = crate::pipeline::postprocess_only(cfg, input, &text)?;
+    jobs::write_final_outputs(cfg, &job_dir, &result).context(ErrorClass::Output)?;
+    jobs::write_index(cfg, &job_dir, &job_id, Some(&started)).context(ErrorClass::Output)?;
+    if !cfg.output.mirrors.is_empty() {
+        let vars = crate::mirror::template_vars(&job_id, input, &result.report);
+        crate::mirror::mirror_final_outputs(cfg, &job_dir, &vars).context(ErrorClass::Output)?;
+    }
 
+    if cfg.global.print_summary || args.json {
+        Console::from_args(args).json_result(&serde_json::json!({
+            "job_id": job_id,
+            "job_dir": job_dir,
+            "status": "ok",
+            "pages": result.report.input.page_count,
+        }))?;
+    }
+    Ok(())
+}
+
+fn batch(args: &Args, cfg: &Config, input_dir: &Path, out_override: Option<&Path>) -> Result<()> {
+    let out_root = out_root(cfg, out_override);
+    ensure_dir(&out_root)?;
+    let log_path = resolve_log_path(cfg, None);
+    let _guard = init_logging(args, cfg, log_path.as_deref())?;
+
+    let inputs = batch::by_priority(cfg, batch::discover_inputs(input_dir)?);
+    info!("batch: {} PDF(s) under {}", inputs.len(), input_dir.display());
+    let mut dedup = batch::Deduplicator::new(&cfg.batch)?;
+    let names = batch::OutputNames::new(&cfg.batch, input_dir, &inputs)?;
+    let mut entries = Vec::new();
+    let metrics = Recorder::from_config(cfg);
+    let total = inputs.len();
+    let notifier = Notifier::from_env();
+    if let Some(notifier) = &notifier {
+        notifier.ready(&format!("batch of {total} input(s)"));
+    }
+
+    for (n, input) in inputs.into_iter().enumerate() {
+        if let Some(metrics) = &metrics {
+            metrics.set_queue_depth(total - n - 1);
+        }
+        if let Some(notifier) = &notifier {
+            notifier.status(&format!("input {}/{total}: {}", n + 1, input.display()));
+        }
+        let target = match job_target(cfg, &input, out_override, Some(&names)) {
+            Ok(t) => t,
+            Err(err) => {
+                warn!("batch: skipping {}: {err:#}", input.display());
+                entries.push(BatchEntry::failed(&input, None, &err));
+                continue;
+            }
+        };
+
+        if let Some(first) = dedup.exact_duplicate(&target.input_hash) {
+            info!("batch: {} duplicates {}", input.display(), first.display());
+            entries.push(BatchEntry::duplicate(&input, &target.job_id, &target.job_dir, &first));
+            continue;
+        }
+
+        let outcome =
+            InputLock::acquire(&target.cfg, &target.input_hash, &target.job_id).and_then(|_lock| {
+                open_job_dir(&target.cfg, &target).context(ErrorClass::Output)?;
+                let rerun = rerun_command(args, &input, out_override);
+                let (metrics, notifier) = (metrics.as_ref(), notifier.as_ref());
+                execute_job(&target.cfg, &input, &target, &rerun, metrics, notifier)
+            });
+
+        match outcome {
+            Ok(result) => {
+                let mut entry = BatchEntry::ok(&input, &target.job_id, &target.job_dir);
+                match result.report.completeness.status() {
+                    "partial" => entry.mark_partial(),
+                    "degraded" => entry.mark_degraded(),
+                    _ => {}
+                }
+                if let Some((first, similarity)) = dedup.near_duplicate(&input, &result.text) {
+                    info!(
+                        "batch: {} near-duplicates {} (similarity {similarity:.3})",
+                        input.display(),
+                        first.display()
+                    );
+                    entry.mark_near_duplicate(&first, similarity);
+                }
+                entries.push(entry);
+            }
+            Err(err) if cancel::interrupted(&err) == Some(Interrupted::Paused) => {
+                info!("batch: {} is paused; moving on", input.display());
+                entries.push(BatchEntry::paused(&input, &target.job_id, &target.job_dir));
+            }
+            Err(err) => {
+                warn!("batch: {} failed: {err:#}", input.display());
+                entries.push(BatchEntry::failed(&input, Some(&target.job_id), &err));
+            }
+        }
+        if let Some(entry) = entries.last_mut() {
+            entry.input_stem = target.input_stem.clone();
+        }
+        dedup.record_input(&target.input_hash, &input);
+    }
+
+    if let Some(notifier) = &notifier {
+        notifier.stopping(&format!("batch of {total} input(s) finished"));
+    }
+    let summary = batch::BatchSummary::new(input_dir, entries);
+    std::fs::write(
+        out_root.join(batch::SUMMARY_FILE),
+        serde_json::to_string_pretty(&summary)?,
+    )?;
+    if cfg.global.print_summary || args.json {
+        Console::from_args(args).json_result(&summary)?;
+    }
+    Ok(())
+}
+
+fn matrix(
+    args: &Args,
+    cfg: &Config,
+    input: &Path,
+    vary: &[String],
+    out_override: Option<&Path>,
+) -> Result<()> {
+    let out_root = out_root(cfg, out_override);
+    ensure_dir(&out_root)?;
+    let log_path = resolve_log_path(cfg, None);
+    let _guard = init_logging(args, cfg, log_path.as_deref())?;
+
+    let axes = vary
+        .iter()
+        .map(|spec| crate::matrix::parse_vary(spec))
+        .collect::<Result<Vec<_>>>()?;
+    // Resolve every variant up front so a bad key fails before the first conversion.
+    let variants = crate::matrix::variants(&axes)
+        .into_iter()
+        .map(|settings| Ok((crate::matrix::apply(cfg, &settings)?, settings)))
+        .collect::<Result<Vec<_>>>()?;
+    info!("matrix: {} variant(s) of {}", variants.len(), input.display());
+
+    let console = Console::from_args(args);
+    let mut runs = Vec::new();
+    for (variant_cfg, settings) in variants {
+        let name = crate::matrix::label(&settings);
+        console.line(format!("running {name}"));
+        let started = std::time::Instant::now();
+        let outcome = job_target(&variant_cfg, input, out_override, None).and_then(|target| {
+            let _lock = InputLock::acquire(&target.cfg, &target.input_hash, &target.job_id)?;
+            open_job_dir(&target.cfg, &target).context(ErrorClass::Output)?;
+            let rerun = rerun_command(args, input, out_override);
+            let output = execute_job(&target.cfg, input, &target, &rerun, None, None)?;
+            Ok(crate::matrix::MatrixJob {
+                job_id: target.job_id.clone(),
+                job_dir: target.job_dir.clone(),
+                output,
+            })
+        });
+        if let Err(err) = &outcome {
+            warn!("matrix: {name} failed: {err:#}");
+        }
+        runs.push(crate::matrix::MatrixRun {
+            settings,
+            duration: started.elapsed(),
+            outcome,
+        });
+    }
+
+    let report = crate::matrix::MatrixReport::new(cfg, input, runs);
+    let path = crate::matrix::report_path(&out_root, input);
+    std::fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+    info!("matrix report: {}", path.display());
+    console.result(&report, || report.table())
+}
+
+/// The directory every job directory lives under: `paths.out_dir` or the override, up
+/// to its first per-input placeholder.
+fn out_root(cfg: &Config, out_override: Option<&Path>) -> PathBuf {
+    match out_override {
+        Some(dir) => template::static_root(&dir.display().to_string()),
+        None => template::static_root(&cfg.paths.out_dir),
+    }
+}
+
+struct JobTarget {
+    /// `cfg` with the output directory and filenames expanded for this input.
+    cfg: Config,
+    /// The PDF the pipeline reads; a decompressed copy for `.gz`/`.zip` inputs.
+    pdf: PreparedInput,
+    input_hash: String,
+    job_id: String,
+    job_dir: PathBuf,
+    /// The input with symlinks resolved.
+    canonical: PathBuf,
+    /// `{input_stem}` in place of the file stem, for a batch input whose stem collided
+    /// or made an output path too long (`batch::OutputNames`).
+    input_stem: Option<String>,
+    /// Earlier job whose unchanged chunks are reused (`run --reuse-from`).
+    reuse_from: Option<PathBuf>,
+    /// Chunks to convert instead of planning them (`run --plan`).
+    plan: Option<crate::chunk_plan::ChunkPlan>,
+}
+
+fn job_target(
+    cfg: &Config,
+    input: &Path,
+    out_override: Option<&Path>,
+    names: Option<&batch::OutputNames>,
+) -> Result<JobTarget> {
+    validate_input(cfg, input).context(ErrorClass::Input)?;
+    let resolved = crate::input_path::resolve(cfg, input).context(ErrorClass::Input)?;
+    // Before hashing: the sidecar can change what the job converts.
+    let cfg = &crate::sidecar::overlay(cfg, input).context(ErrorClass::Config)?;
+    let pdf = crate::archive::prepare_input(cfg, input).context(ErrorClass::Input)?;
+
+    // Hash the decompressed PDF so `x.pdf` and `x.pdf.gz` share a job.
+    let input_hash = crate::util::hash_file(cfg, &pdf.path)
+        .with_context(|| format!("hashing input: {}", input.display()))
+        .context(ErrorClass::Input)?;
+    let identity = crate::input_path::identity_hash(cfg, &input_hash, &resolved)?;
+    let job_id = config_hash::job_id(cfg, &identity);
+    let mut cfg = cfg.clone();
+    if let Some(dir) = out_override {
+        cfg.paths.out_dir = dir.display().to_string();
+    }
+    let input_stem = match names {
+        Some(names) => names
+            .stem_for(input, |stem| {
+                let cfg = template::for_job_as(&cfg, input, &job_id, Some(stem))?;
+                Ok(batch::longest_output_path(&cfg, input, &job_id, stem))
+            })
+            .context(ErrorClass::Output)?,
+        None => None,
+    };
+    let cfg = template::for_job_as(&cfg, input, &job_id, input_stem.as_deref())
+        .context(ErrorClass::Config)?;
+    let job_dir = PathBuf::from(&cfg.paths.out_dir).join(&job_id);
+
+    Ok(JobTarget {
+        cfg,
+        pdf,
+        input_hash,
+        job_id,
+        job_dir,
+        canonical: resolved.canonical,
+        input_stem,
+        reuse_from: None,
+        plan: None,
+    })
+}
+
+fn open_job_dir(cfg: &Config, target: &JobTarget) -> Result<()> {
+    let job_dir = &target.job_dir;
     if job_dir.exists() && !cfg.global.resume {
         return Err(anyhow!(
             "job_dir already exists and resume=false: {}",
@@ -178,18 +1584,145 @@ fn run(args: &Args, cfg: &Config, input: &Path, out_override: Option<&Path>) ->
         ));
     }
 
-    ensure_dir(&job_dir)?;
+    ensure_dir(job_dir)?;
     ensure_dir(&job_dir.join("final"))?;
     ensure_dir(&job_dir.join("logs"))?;
     ensure_dir(&job_dir.join("chunks"))?;
+    Ok(())
+}
 
-    let log_path = resolve_log_path(cfg, Some(&job_dir));
-    let _guard = init_logging(args, cfg, log_path.as_deref())?;
+/// The local Python engine, with docling conversions sent to docling-serve when
+/// `[engine.remote]` is enabled.
+fn conversion_engine(cfg: &Config) -> Result<Box<dyn Engine>> {
+    let local = PythonEngine::new(cfg)?;
+    if !cfg.engine.remote.enabled {
+        return Ok(Box::new(local));
+    }
+    #[cfg(feature = "remote")]
+    {
+        let remote = crate::engine::remote::RemoteEngine::new(cfg, local)?;
+        info!("docling conversions go to {}", cfg.engine.remote.url);
+        Ok(Box::new(remote))
+    }
+    #[cfg(not(feature = "remote"))]
+    Err(anyhow!(
+        "engine.remote is enabled, but this build lacks the `remote` feature"
+    ))
+}
 
-    info!("job_id={job_id} out={}", job_dir.display());
+/// Run a job; on failure, write `failure.json` before returning the error. `rerun` is
+/// the command line suggested there to run the job again.
+fn execute_job(
+    cfg: &Config,
+    input: &Path,
+    target: &JobTarget,
+    rerun: &str,
+    metrics: Option<&Recorder>,
+    notifier: Option<&Notifier>,
+) -> Result<JobOutput> {
+    let started = std::time::Instant::now();
+    if let Some(metrics) = metrics {
+        metrics.job_started();
+    }
+    // Journald keeps the span's fields with every event logged on this thread.
+    let span = cfg
+        .logging
+        .journald
+        .then(|| tracing::info_span!("job", job_id = %target.job_id, input = %input.display()));
+    let _entered = span.as_ref().map(|s| s.enter());
+    let outcome = run_job_stages(cfg, input, target, metrics, notifier);
+    if let Some(metrics) = metrics {
+        match &outcome {
+            Ok(result) => {
+                let completeness = &result.report.completeness;
+                let pages = completeness.converted_pages;
+                metrics.job_finished(completeness.status(), pages, started.elapsed());
+            }
+            Err(_) => metrics.job_finished("failed", 0, started.elapsed()),
+        }
+    }
+    match outcome {
+        Ok(result) => {
+            failure::clear(&target.job_dir);
+            Ok(result)
+        }
+        Err(err) if cancel::interrupted(&err) == Some(Interrupted::Paused) => {
+            info!(
+                "job paused; continue with quack-check jobs resume --job {}, then run it again",
+                target.job_id
+            );
+            Err(err)
+        }
+        Err(err) => {
+            let report = failure::describe(cfg, &target.job_dir, &target.job_id, input, &err, rerun);
+            match failure::write(&target.job_dir, &report) {
+                Ok(path) => warn!("job failed at stage {}; see {}", report.stage, path.display()),
+                Err(write_err) => warn!("could not write {}: {write_err:#}", failure::FAILURE_FILE),
+            }
+            Err(err)
+        }
+    }
+}
+
+/// A job id shortened for status lines, like `{job_id_short}`.
+fn short_id(job_id: &str) -> String {
+    format!("{}…", job_id.chars().take(12).collect::<String>())
+}
+
+/// The `run` command line that repeats a job.
+fn rerun_command(args: &Args, input: &Path, out_override: Option<&Path>) -> String {
+    let mut words = vec!["quack-check".to_string()];
+    if let Some(config) = &args.config {
+        words.extend(["--config".to_string(), shell_word(&config.display().to_string())]);
+    }
+    words.extend(["run".to_string(), "--input".to_string()]);
+    words.push(shell_word(&input.display().to_string()));
+    if let Some(dir) = out_override {
+        words.extend(["--out-dir".to_string(), shell_word(&dir.display().to_string())]);
+    }
+    if let Command::Run {
+        reuse_from,
+        plan,
+        name,
+        ..
+    } = &args.cmd
+    {
+        if let Some(job) = reuse_from {
+            words.extend(["--reuse-from".to_string(), shell_word(job)]);
+        }
+        if let Some(path) = plan {
+            words.extend(["--plan".to_string(), shell_word(&path.display().to_string())]);
+        }
+        if let Some(name) = name {
+            words.extend(["--name".to_string(), shell_word(name)]);
+        }
+    }
+    words.join(" ")
+}
+
+fn shell_word(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+=:,@{}".contains(c))
+    {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+fn run_job_stages(
+    cfg: &Config,
+    input: &Path,
+    target: &JobTarget,
+    metrics: Option<&Recorder>,
+    notifier: Option<&Notifier>,
+) -> Result<JobOutput> {
+    let job_dir = &target.job_dir;
+    info!("job_id={} out={}", target.job_id, job_dir.display());
 
     if cfg.debug.dump_effective_config {
-        let raw = toml::to_string(cfg).unwrap_or_default();
+        let raw = effective_config_toml(cfg, &Redactor::from_config(cfg));
         std::fs::write(job_dir.join("effective-config.toml"), raw)?;
     }
 
@@ -197,57 +1730,56 @@ fn run(args: &Args, cfg: &Config, input: &Path, out_override: Option<&Path>) ->
     ensure_dir(Path::new(&cfg.paths.cache_dir))?;
     ensure_dir(Path::new(&cfg.paths.docling_artifacts_dir))?;
 
-    let engine = PythonEngine::new(cfg)?;
-    let pipeline = Pipeline::new(cfg, engine);
-
-    let started = now_rfc3339();
-    let result = pipeline.run_job(input, &job_dir)?;
-
-    if cfg.output.write_markdown {
-        std::fs::write(
-            job_dir.join("final").join(&cfg.output.markdown_filename),
-            &result.markdown,
-        )?;
-    }
-
-    if cfg.output.write_text {
-        std::fs::write(
-            job_dir.join("final").join(&cfg.output.text_filename),
-            &result.text,
-        )?;
-    }
-
-    if cfg.output.write_report_json {
-        std::fs::write(
-            job_dir.join("final").join(&cfg.output.report_filename),
-            serde_json::to_string_pretty(&result.report)?,
-        )?;
+    let reuse = match &target.reuse_from {
+        Some(dir) => Some(ReuseSource::load(dir)?),
+        None => None,
+    };
+    let mut engine = conversion_engine(cfg)?;
+    if cfg.debug.record_engine_io {
+        let recording = job_dir.join("logs").join(replay::RECORDING_FILE);
+        engine = Box::new(RecordingEngine::create(engine, &recording)?);
     }
+    let pipeline = Pipeline::new_with_hooks(cfg, engine, CommandHooks::new(cfg))
+        .with_probe_cache(crate::probe::ProbeCache::from_config(cfg))
+        .with_reuse_from(reuse)
+        .with_plan(target.plan.clone());
 
-    if cfg.output.write_index_json {
-        let index = serde_json::json!({
-            "job_id": job_id,
-            "started": started,
-            "finished": now_rfc3339(),
-            "final_markdown": format!("final/{}", cfg.output.markdown_filename),
-            "final_text": format!("final/{}", cfg.output.text_filename),
-            "report": format!("final/{}", cfg.output.report_filename),
-        });
-        std::fs::write(job_dir.join("index.json"), serde_json::to_string_pretty(&index)?)?;
+    let started = now_rfc3339();
+    let mut result = if metrics.is_some() || notifier.is_some() {
+        let mut sink = |chunk: StreamedChunk| -> Result<()> {
+            if let Some(metrics) = metrics {
+                metrics.chunk_converted(chunk.end_page - chunk.start_page + 1);
+            }
+            if let Some(notifier) = notifier {
+                notifier.status(&format!(
+                    "chunk {}/{} of job {}",
+                    chunk.chunk_index + 1,
+                    chunk.chunk_count,
+                    short_id(&target.job_id)
+                ));
+            }
+            Ok(())
+        };
+        pipeline.run_with_sink(&target.pdf.path, job_dir, &mut sink)?
+    } else {
+        pipeline.run_job(&target.pdf.path, job_dir)?
+    };
+    if target.pdf.path != input {
+        result.report.input.path = input.display().to_string();
     }
+    result.report.input.canonical_path = Some(target.canonical.display().to_string());
 
-    if cfg.global.print_summary {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&serde_json::json!({
-                "job_id": job_id,
-                "job_dir": job_dir,
-                "status": "ok"
-            }))?
-        );
+    jobs::write_final_outputs(cfg, job_dir, &result).context(ErrorClass::Output)?;
+    jobs::write_index(cfg, job_dir, &target.job_id, Some(&started)).context(ErrorClass::Output)?;
+    if !cfg.output.mirrors.is_empty() {
+        let mut vars = crate::mirror::template_vars(&target.job_id, input, &result.report);
+        if let Some(stem) = &target.input_stem {
+            template::rename_input(&mut vars, stem);
+        }
+        crate::mirror::mirror_final_outputs(cfg, job_dir, &vars).context(ErrorClass::Output)?;
     }
 
-    Ok(())
+    Ok(result)
 }
 
 fn validate_input(cfg: &Config, input: &Path) -> Result<()> {
@@ -261,9 +1793,12 @@ fn validate_input(cfg: &Config, input: &Path) -> Result<()> {
         return Err(anyhow!("input does not exist: {}", input.display()));
     }
 
-    if let Some(ext) = input.extension().and_then(|s| s.to_str()) {
-        if ext.to_ascii_lowercase() != "pdf" {
-            return Err(anyhow!("input is not a PDF: {}", input.display()));
+    if input.extension().is_some() {
+        if !crate::archive::is_supported_input(input) {
+            return Err(anyhow!(
+                "input is not a PDF (or .gz/.zip of one): {}",
+                input.display()
+            ));
         }
     } else {
         warn!("input has no extension; assuming PDF: {}", input.display());
@@ -277,6 +1812,12 @@ fn looks_like_url(s: &str) -> bool {
     s.starts_with("http://") || s.starts_with("https://") || s.starts_with("file://")
 }
 
+/// Whether `path` is the log every run writes to rather than one job's own log, so
+/// `logging.rotate` applies to it.
+fn is_shared_log(cfg: &Config, path: &Path) -> bool {
+    !cfg.logging.file_path.is_empty() || path == out_root(cfg, None).join("quack-check.log")
+}
+
 fn resolve_log_path(cfg: &Config, job_dir: Option<&Path>) -> Option<PathBuf> {
     if !cfg.logging.write_to_file {
         return None;
@@ -290,5 +1831,5 @@ fn resolve_log_path(cfg: &Config, job_dir: Option<&Path>) -> Option<PathBuf> {
         return Some(job_dir.join("logs").join("quack-check.log"));
     }
 
-    Some(PathBuf::from(&cfg.paths.out_dir).join("quack-check.log"))
+    Some(out_root(cfg, None).join("quack-check.log"))
 }
@@ -0,0 +1,174 @@
+use crate::{
+    config::Config,
+    config_hash::config_hash,
+    util::{now_rfc3339, sha256_hex},
+};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Registry file used when `QUACK_CHECK_COLLECTIONS` is unset, relative to the working
+/// directory.
+pub const DEFAULT_REGISTRY: &str = ".quack-check-collections.json";
+
+/// Environment variable naming the registry file.
+pub const REGISTRY_ENV: &str = "QUACK_CHECK_COLLECTIONS";
+
+/// Named configs, so `run --collection scans-1920s` replaces `--config <path>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub collections: BTreeMap<String, Collection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    /// Absolute path of the config file.
+    pub config: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub added_at: String,
+    /// SHA-256 of the config file when it was registered.
+    pub config_sha256: String,
+    /// `config_hash` of the config when it was registered.
+    pub config_hash: String,
+}
+
+/// A registered collection as `collections list` reports it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectionStatus {
+    pub name: String,
+    #[serde(flatten)]
+    pub collection: Collection,
+    /// "ok", "modified" (the file changed since it was registered) or "missing".
+    pub state: String,
+}
+
+pub fn registry_path() -> PathBuf {
+    std::env::var_os(REGISTRY_ENV)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_REGISTRY))
+}
+
+impl Registry {
+    /// The registry at `path`; empty if the file does not exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading collections registry: {}", path.display()))?;
+        serde_json::from_str(&raw)
+            .with_context(|| format!("parsing collections registry: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing collections registry: {}", path.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("writing collections registry: {}", path.display()))
+    }
+
+    /// Register `config` as `name`. The config must load; `replace` allows re-pointing an
+    /// existing name.
+    pub fn add(
+        &mut self,
+        name: &str,
+        config: &Path,
+        description: Option<&str>,
+        replace: bool,
+    ) -> Result<&Collection> {
+        validate_name(name)?;
+        if !replace && self.collections.contains_key(name) {
+            return Err(anyhow!(
+                "collection {name:?} already exists; pass --replace to re-point it"
+            ));
+        }
+        let config = std::fs::canonicalize(config)
+            .with_context(|| format!("config not found: {}", config.display()))?;
+        let raw = std::fs::read(&config)
+            .with_context(|| format!("reading config: {}", config.display()))?;
+        let cfg = Config::load(&config)?;
+        self.collections.insert(
+            name.to_string(),
+            Collection {
+                config,
+                description: description.map(str::to_string),
+                added_at: now_rfc3339(),
+                config_sha256: sha256_hex(&raw),
+                config_hash: config_hash(&cfg),
+            },
+        );
+        Ok(&self.collections[name])
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Collection> {
+        self.collections
+            .remove(name)
+            .ok_or_else(|| anyhow!("no collection named {name:?}"))
+    }
+
+    /// The config path registered as `name`.
+    pub fn resolve(&self, name: &str) -> Result<&Path> {
+        let collection = self.collections.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.collections.keys().map(String::as_str).collect();
+            if known.is_empty() {
+                anyhow!("no collection named {name:?}; none are registered")
+            } else {
+                anyhow!(
+                    "no collection named {name:?}; registered: {}",
+                    known.join(", ")
+                )
+            }
+        })?;
+        if !collection.config.is_file() {
+            return Err(anyhow!(
+                "collection {name:?} points at a missing config: {}",
+                collection.config.display()
+            ));
+        }
+        Ok(&collection.config)
+    }
+
+    pub fn statuses(&self) -> Vec<CollectionStatus> {
+        self.collections
+            .iter()
+            .map(|(name, collection)| {
+                let state = match std::fs::read(&collection.config) {
+                    Ok(raw) if sha256_hex(&raw) == collection.config_sha256 => "ok",
+                    Ok(_) => "modified",
+                    Err(_) => "missing",
+                };
+                CollectionStatus {
+                    name: name.clone(),
+                    collection: collection.clone(),
+                    state: state.into(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Names are typed on command lines: letters, digits, `.`, `_` and
+/// `-`, not starting with `.` or `-`.
+fn validate_name(name: &str) -> Result<()> {
+    let ok = !name.is_empty()
+        && !name.starts_with(['.', '-'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if ok {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "invalid collection name {name:?}: use letters, digits, '.', '_' and '-'"
+        ))
+    }
+}
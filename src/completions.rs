@@ -0,0 +1,65 @@
+use crate::{
+    cli::Args,
+    collections::{registry_path, Registry},
+    policy::ENGINES,
+};
+use anyhow::{Context, Result};
+use clap::{builder::PossibleValuesParser, CommandFactory};
+use clap_complete::Shell;
+use std::io::Write;
+use std::path::Path;
+
+/// Values `--log-level` accepts.
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+/// The CLI definition with the values the parser leaves open filled in, for
+/// completions and man pages only: the collections registered when it is generated
+/// (for `--collection` and `collections remove`), log levels, and `engine=<name>` for
+/// `matrix --vary`. Parsing still uses [`Args`] as is, so a collection registered later
+/// works without completing.
+pub fn command() -> clap::Command {
+    let collections: Vec<String> = Registry::load(&registry_path())
+        .map(|r| r.collections.into_keys().collect())
+        .unwrap_or_default();
+    let engines: Vec<String> = ENGINES.iter().map(|e| format!("engine={e}")).collect();
+
+    let mut cmd = Args::command()
+        .mut_arg("log_level", |a| a.value_parser(LOG_LEVELS))
+        .mut_subcommand("matrix", |s| {
+            s.mut_arg("vary", |a| a.value_parser(PossibleValuesParser::new(engines)))
+        });
+    if !collections.is_empty() {
+        cmd = cmd
+            .mut_arg("collection", |a| {
+                a.value_parser(PossibleValuesParser::new(collections.clone()))
+            })
+            .mut_subcommand("collections", |s| {
+                s.mut_subcommand("remove", |r| {
+                    r.mut_arg("name", |a| {
+                        a.value_parser(PossibleValuesParser::new(collections))
+                    })
+                })
+            });
+    }
+    cmd
+}
+
+/// Write the completion script for `shell` to `out`.
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    clap_complete::generate(shell, &mut command(), "quack-check", out);
+}
+
+/// Write the `quack-check(1)` man page to `out`.
+pub fn write_manpage(out: &mut dyn Write) -> Result<()> {
+    clap_mangen::Man::new(command())
+        .render(out)
+        .context("rendering man page")
+}
+
+/// Write `quack-check.1` and a page per subcommand (`quack-check-run.1`, ...) into
+/// `dir`.
+pub fn write_manpages(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    clap_mangen::generate_to(command(), dir)
+        .with_context(|| format!("writing man pages to {}", dir.display()))
+}
@@ -1,8 +1,9 @@
+use crate::xdg;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub global: Global,
@@ -13,6 +14,8 @@ pub struct Config {
     #[serde(default)]
     pub limits: Limits,
     #[serde(default)]
+    pub input: Input,
+    #[serde(default)]
     pub classification: Classification,
     #[serde(default)]
     pub chunking: Chunking,
@@ -32,6 +35,14 @@ pub struct Config {
     pub debug: Debug,
     #[serde(default)]
     pub security: Security,
+    #[serde(default)]
+    pub batch: Batch,
+    #[serde(default)]
+    pub retention: Retention,
+    #[serde(default)]
+    pub metrics: Metrics,
+    #[serde(default)]
+    pub hooks: Hooks,
 }
 
 impl Config {
@@ -42,31 +53,70 @@ impl Config {
         Ok(cfg)
     }
 
-    /// A stable, normalization-friendly string for hashing.
+    /// Make the relative `paths.*` entries absolute so the tool behaves the same from any
+    /// working directory. They are resolved against `paths.root`, itself relative to
+    /// `base` (the config file's directory), or `base` when it is empty; `paths.root`
+    /// is then set to that directory. Empty `work_dir` and `cache_dir` take their XDG
+    /// defaults, as does an empty `docling_artifacts_dir` once its default exists; other
+    /// empty entries stay empty, and a bare `pdfium_library` file name is left to the
+    /// system library search.
+    pub fn resolve_paths(&mut self, base: &Path) -> Result<()> {
+        let base = std::path::absolute(base)
+            .with_context(|| format!("resolving config directory: {}", base.display()))?;
+        let root = normalize_path(&base.join(&self.paths.root));
+        let p = &mut self.paths;
+        if p.work_dir.is_empty() {
+            p.work_dir = default_work_dir();
+        }
+        if p.cache_dir.is_empty() {
+            p.cache_dir = default_cache_dir();
+        }
+        if p.docling_artifacts_dir.is_empty()
+            && let Some(dir) = xdg::artifacts_dir()
+        {
+            p.docling_artifacts_dir = dir.display().to_string();
+        }
+        for value in [
+            &mut p.out_dir,
+            &mut p.work_dir,
+            &mut p.cache_dir,
+            &mut p.docling_artifacts_dir,
+            &mut p.scripts_dir,
+        ] {
+            if !value.is_empty() && Path::new(value.as_str()).is_relative() {
+                *value = normalize_path(&root.join(&*value)).display().to_string();
+            }
+        }
+        let pdfium = Path::new(&p.pdfium_library);
+        if pdfium.is_relative() && pdfium.components().count() > 1 {
+            p.pdfium_library = normalize_path(&root.join(pdfium)).display().to_string();
+        }
+        p.root = root.display().to_string();
+        Ok(())
+    }
+
+    /// The whole config as TOML. Job ids hash [`crate::config_hash::canonical`]
+    /// instead, which leaves out defaults and settings that do not affect conversion.
     pub fn normalized_for_hash(&self) -> String {
         toml::to_string(self).unwrap_or_default()
     }
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            global: Default::default(),
-            paths: Default::default(),
-            hashing: Default::default(),
-            limits: Default::default(),
-            classification: Default::default(),
-            chunking: Default::default(),
-            engine: Default::default(),
-            native_text: Default::default(),
-            docling: Default::default(),
-            postprocess: Default::default(),
-            output: Default::default(),
-            logging: Default::default(),
-            debug: Default::default(),
-            security: Default::default(),
+/// `path` with `.` and `..` components folded away, without touching the filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for part in path.components() {
+        match part {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() && !out.has_root() {
+                    out.push(part);
+                }
+            }
+            other => out.push(other),
         }
     }
+    out
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +127,17 @@ pub struct Global {
     pub resume: bool,
     pub max_parallel_chunks: usize,
     pub print_summary: bool,
+    #[serde(default = "default_on_input_locked")]
+    pub on_input_locked: String,
+    /// Hash every page into `job.json`, so a later job on a replacement scan can reuse
+    /// this job's unchanged chunks (`run --reuse-from`).
+    #[serde(default = "default_record_page_hashes")]
+    pub record_page_hashes: bool,
+    #[serde(default)]
+    pub governor: ParallelGovernor,
+    /// `batch` runs inputs with higher priorities first; usually set in a sidecar.
+    #[serde(default)]
+    pub priority: i32,
 }
 impl Default for Global {
     fn default() -> Self {
@@ -87,44 +148,134 @@ impl Default for Global {
             resume: true,
             max_parallel_chunks: 1,
             print_summary: true,
+            on_input_locked: default_on_input_locked(),
+            record_page_hashes: default_record_page_hashes(),
+            governor: Default::default(),
+            priority: 0,
         }
     }
 }
 
+fn default_on_input_locked() -> String {
+    "fail".into()
+}
+
+fn default_record_page_hashes() -> bool {
+    true
+}
+
+/// Adjusts how many chunks convert at once, within `max_parallel_chunks`, from the
+/// host's free memory and load average.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParallelGovernor {
+    #[serde(default = "default_governor_enabled")]
+    pub enabled: bool,
+    /// No new conversion starts while available memory is below this.
+    #[serde(default = "default_min_free_memory_bytes")]
+    pub min_free_memory_bytes: u64,
+    /// Memory headroom reserved for each new conversion; 0 disables the estimate.
+    #[serde(default = "default_memory_per_chunk_bytes")]
+    pub memory_per_chunk_bytes: u64,
+    /// No new conversion starts while the 1-minute load average per CPU is above this;
+    /// 0 disables the check.
+    #[serde(default = "default_max_load_per_cpu")]
+    pub max_load_per_cpu: f64,
+}
+impl Default for ParallelGovernor {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_free_memory_bytes: default_min_free_memory_bytes(),
+            memory_per_chunk_bytes: default_memory_per_chunk_bytes(),
+            max_load_per_cpu: default_max_load_per_cpu(),
+        }
+    }
+}
+
+fn default_governor_enabled() -> bool {
+    true
+}
+
+fn default_min_free_memory_bytes() -> u64 {
+    2 * 1024 * 1024 * 1024
+}
+
+fn default_memory_per_chunk_bytes() -> u64 {
+    3 * 1024 * 1024 * 1024
+}
+
+fn default_max_load_per_cpu() -> f64 {
+    1.5
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Paths {
     pub out_dir: String,
+    /// Empty or unset: `$XDG_CACHE_HOME/quack-check/work` (see [`crate::xdg`]).
+    #[serde(default = "default_work_dir")]
     pub work_dir: String,
+    /// Empty or unset: `$XDG_CACHE_HOME/quack-check/cache`.
+    #[serde(default = "default_cache_dir")]
     pub cache_dir: String,
+    /// Empty or unset: `$XDG_DATA_HOME/quack-check/docling-artifacts` if that exists,
+    /// otherwise left empty for docling's Hugging Face cache.
+    #[serde(default)]
     pub docling_artifacts_dir: String,
     pub scripts_dir: String,
+    /// The pdfium shared library for page rendering (`pdfium` cargo feature); empty
+    /// searches the system library path.
+    #[serde(default)]
+    pub pdfium_library: String,
+    /// Directory the other relative `paths.*` are resolved against; empty means the
+    /// config file's directory. Set to the absolute directory used when loading.
+    #[serde(default)]
+    pub root: String,
 }
 impl Default for Paths {
     fn default() -> Self {
         Self {
             out_dir: "out".into(),
-            work_dir: ".quack-check-work".into(),
-            cache_dir: ".quack-check-cache".into(),
+            work_dir: default_work_dir(),
+            cache_dir: default_cache_dir(),
             docling_artifacts_dir: "".into(),
             scripts_dir: "scripts".into(),
+            pdfium_library: "".into(),
+            root: "".into(),
         }
     }
 }
 
+fn default_work_dir() -> String {
+    xdg::work_dir().display().to_string()
+}
+
+fn default_cache_dir() -> String {
+    xdg::cache_dir().display().to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hashing {
     pub mode: String,
     pub fast_window_bytes: u64,
+    /// What a job id is keyed on besides the config: "content" | "content_and_path"
+    /// (the canonical input path as well).
+    #[serde(default = "default_identity")]
+    pub identity: String,
 }
 impl Default for Hashing {
     fn default() -> Self {
         Self {
             mode: "fast_2x16mb".into(),
             fast_window_bytes: 16 * 1024 * 1024,
+            identity: default_identity(),
         }
     }
 }
 
+fn default_identity() -> String {
+    "content".into()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Limits {
     pub max_input_file_bytes: u64,
@@ -132,6 +283,19 @@ pub struct Limits {
     pub require_chunking_over_pages: u32,
     pub require_chunking_over_bytes: u64,
     pub job_timeout_seconds: u64,
+    /// Soft budget per page of the input; 0 disables it.
+    #[serde(default)]
+    pub max_seconds_per_page: f64,
+    /// Soft budget for the whole job; 0 disables it.
+    #[serde(default)]
+    pub soft_budget_seconds: u64,
+    #[serde(default = "default_on_page_error")]
+    pub on_page_error: String,
+    /// Share of a job's chunks that may fail conversion, after fallbacks, before the job
+    /// fails; failed chunks are kept as placeholders and the job is degraded. 0 fails the
+    /// job on the first failed chunk.
+    #[serde(default)]
+    pub allow_failed_chunk_fraction: f64,
 }
 impl Default for Limits {
     fn default() -> Self {
@@ -141,34 +305,92 @@ impl Default for Limits {
             require_chunking_over_pages: 200,
             require_chunking_over_bytes: 200_000_000,
             job_timeout_seconds: 0,
+            max_seconds_per_page: 0.0,
+            soft_budget_seconds: 0,
+            on_page_error: default_on_page_error(),
+            allow_failed_chunk_fraction: 0.0,
+        }
+    }
+}
+
+impl Limits {
+    /// The soft time budget for a job of `page_count` pages: the tighter of
+    /// `soft_budget_seconds` and `max_seconds_per_page * page_count`, if either is set.
+    pub fn soft_budget(&self, page_count: u32) -> Option<f64> {
+        let per_page = (self.max_seconds_per_page > 0.0)
+            .then(|| self.max_seconds_per_page * f64::from(page_count));
+        let whole = (self.soft_budget_seconds > 0).then_some(self.soft_budget_seconds as f64);
+        match (per_page, whole) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
         }
     }
+
+    /// How many of `chunk_count` chunks may fail under `allow_failed_chunk_fraction`.
+    pub fn allowed_chunk_failures(&self, chunk_count: usize) -> usize {
+        (self.allow_failed_chunk_fraction.clamp(0.0, 1.0) * chunk_count as f64).floor() as usize
+    }
+}
+
+fn default_on_page_error() -> String {
+    "fail".into()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Classification {
     pub sample_pages: u32,
+    #[serde(default = "default_sample_pages_percent")]
+    pub sample_pages_percent: f32,
+    #[serde(default = "default_max_sample_pages")]
+    pub max_sample_pages: u32,
     pub enable_render_probe: bool,
     pub min_avg_chars_per_page_for_high_text: u32,
     pub max_avg_chars_per_page_for_scan: u32,
     pub max_garbage_ratio_for_high_text: f32,
     pub max_whitespace_ratio_for_high_text: f32,
     pub forced_tier: String,
+    /// Reuse a probe of the same input and sample settings for this long; 0 disables
+    /// the probe cache.
+    #[serde(default = "default_probe_cache_ttl_seconds")]
+    pub probe_cache_ttl_seconds: u64,
 }
 impl Default for Classification {
     fn default() -> Self {
         Self {
             sample_pages: 12,
+            sample_pages_percent: default_sample_pages_percent(),
+            max_sample_pages: default_max_sample_pages(),
             enable_render_probe: false,
             min_avg_chars_per_page_for_high_text: 1200,
             max_avg_chars_per_page_for_scan: 80,
             max_garbage_ratio_for_high_text: 0.02,
             max_whitespace_ratio_for_high_text: 0.55,
             forced_tier: "AUTO".into(),
+            probe_cache_ttl_seconds: default_probe_cache_ttl_seconds(),
         }
     }
 }
 
+impl Classification {
+    /// Sample exactly `pages` pages (capped at the page count), disabling auto-scaling.
+    pub fn pin_sample_pages(&mut self, pages: u32) {
+        self.sample_pages = pages;
+        self.sample_pages_percent = 0.0;
+    }
+}
+
+fn default_sample_pages_percent() -> f32 {
+    3.0
+}
+
+fn default_max_sample_pages() -> u32 {
+    50
+}
+
+fn default_probe_cache_ttl_seconds() -> u64 {
+    3600
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chunking {
     pub strategy: String,
@@ -178,7 +400,21 @@ pub struct Chunking {
     pub cap_chunk_bytes: bool,
     pub max_chunk_bytes: u64,
     pub split_backend: String,
+    /// Deprecated: `false` reads as `retention.split_pdfs = "delete"`.
+    #[serde(default = "default_keep_split_pdfs")]
     pub keep_split_pdfs: bool,
+    /// How far a chunk seam may move to avoid cutting a table in half; 0 disables the
+    /// layout pre-pass.
+    #[serde(default = "default_boundary_slack_pages")]
+    pub boundary_slack_pages: u32,
+    /// A docling chunk that runs out of memory is retried as two halves, recursively,
+    /// until it is this many pages; 0 fails the job on the first OOM instead.
+    #[serde(default = "default_oom_min_chunk_pages")]
+    pub oom_min_chunk_pages: u32,
+    /// What to do when a `page_range` conversion came back with pages outside the
+    /// requested range: `"fail"` the chunk, `"warn"`, or `"off"`.
+    #[serde(default = "default_page_range_check")]
+    pub page_range_check: String,
 }
 impl Default for Chunking {
     fn default() -> Self {
@@ -190,16 +426,37 @@ impl Default for Chunking {
             cap_chunk_bytes: true,
             max_chunk_bytes: 50_000_000,
             split_backend: "python_pypdf".into(),
-            keep_split_pdfs: true,
+            keep_split_pdfs: default_keep_split_pdfs(),
+            boundary_slack_pages: default_boundary_slack_pages(),
+            oom_min_chunk_pages: default_oom_min_chunk_pages(),
+            page_range_check: default_page_range_check(),
         }
     }
 }
 
+fn default_keep_split_pdfs() -> bool {
+    true
+}
+
+fn default_boundary_slack_pages() -> u32 {
+    2
+}
+
+fn default_oom_min_chunk_pages() -> u32 {
+    5
+}
+
+fn default_page_range_check() -> String {
+    "fail".into()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Engine {
     pub high_text_engine: String,
     pub mixed_text_engine: String,
     pub scan_engine: String,
+    #[serde(default)]
+    pub remote: Remote,
 }
 impl Default for Engine {
     fn default() -> Self {
@@ -207,10 +464,60 @@ impl Default for Engine {
             high_text_engine: "native_text".into(),
             mixed_text_engine: "docling".into(),
             scan_engine: "docling".into(),
+            remote: Default::default(),
+        }
+    }
+}
+
+/// Docling conversions sent to a docling-serve instance instead of the local runner.
+/// Probing, splitting, native text and postprocessing stay local.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Remote {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Base URL, e.g. "http://gpu-box:5001".
+    #[serde(default)]
+    pub url: String,
+    /// Environment variable holding the API key; unset or empty sends none.
+    #[serde(default = "default_remote_api_key_env")]
+    pub api_key_env: String,
+    #[serde(default = "default_remote_api_key_header")]
+    pub api_key_header: String,
+    #[serde(default = "default_remote_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// Give up on a chunk after this long, upload and polling included; 0 waits forever.
+    #[serde(default = "default_remote_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+impl Default for Remote {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            api_key_env: default_remote_api_key_env(),
+            api_key_header: default_remote_api_key_header(),
+            poll_interval_seconds: default_remote_poll_interval_seconds(),
+            timeout_seconds: default_remote_timeout_seconds(),
         }
     }
 }
 
+fn default_remote_api_key_env() -> String {
+    "DOCLING_SERVE_API_KEY".into()
+}
+
+fn default_remote_api_key_header() -> String {
+    "X-Api-Key".into()
+}
+
+fn default_remote_poll_interval_seconds() -> u64 {
+    2
+}
+
+fn default_remote_timeout_seconds() -> u64 {
+    1800
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NativeText {
     pub backend: String,
@@ -218,6 +525,14 @@ pub struct NativeText {
     pub collapse_whitespace: bool,
     pub fix_hyphenation: bool,
     pub light_markdown: bool,
+    /// Reorder right-to-left lines that the PDF stores in visual (left-to-right glyph)
+    /// order back into logical order.
+    #[serde(default)]
+    pub rtl_visual_order: bool,
+    /// Interpreter for the pypdf scripts (probe, split, native text); empty uses
+    /// `docling.python_exe`. Resolved like it, including "auto".
+    #[serde(default)]
+    pub python_exe: String,
 }
 impl Default for NativeText {
     fn default() -> Self {
@@ -227,6 +542,8 @@ impl Default for NativeText {
             collapse_whitespace: true,
             fix_hyphenation: true,
             light_markdown: false,
+            rtl_visual_order: false,
+            python_exe: String::new(),
         }
     }
 }
@@ -240,6 +557,18 @@ pub struct Docling {
     pub process_isolation: bool,
     pub doctor_timeout_seconds: u64,
     pub chunk_timeout_seconds: u64,
+    /// With `global.offline_only`, check before converting that every model the chosen
+    /// pipeline needs is on disk.
+    #[serde(default = "default_preflight_models")]
+    pub preflight_models: bool,
+    /// Least time between starting two docling processes, so chunks converted in
+    /// parallel do not all load their models at once.
+    #[serde(default)]
+    pub startup_stagger_seconds: f64,
+    /// Docling processes allowed to be loading models at the same time (0: no limit),
+    /// apart from how many conversions run at once.
+    #[serde(default)]
+    pub max_concurrent_startups: usize,
     #[serde(default)]
     pub env: std::collections::BTreeMap<String, String>,
     #[serde(default)]
@@ -252,6 +581,17 @@ pub struct Docling {
     pub accelerator: DoclingAccelerator,
     #[serde(default)]
     pub vlm: DoclingVlm,
+    /// Per-tier replacements for `[docling.pipeline]` keys (or `[docling.ocr]` keys
+    /// prefixed with `ocr.`), keyed by tier name.
+    #[serde(default)]
+    pub tier_overrides:
+        std::collections::BTreeMap<String, std::collections::BTreeMap<String, serde_json::Value>>,
+    #[serde(default)]
+    pub table_chunks: DoclingTableChunks,
+    #[serde(default)]
+    pub ocr_retry: DoclingOcrRetry,
+    #[serde(default)]
+    pub setup: DoclingSetup,
 }
 impl Default for Docling {
     fn default() -> Self {
@@ -263,28 +603,181 @@ impl Default for Docling {
             process_isolation: true,
             doctor_timeout_seconds: 120,
             chunk_timeout_seconds: 600,
+            preflight_models: default_preflight_models(),
+            startup_stagger_seconds: 0.0,
+            max_concurrent_startups: 0,
             env: Default::default(),
             backend: Default::default(),
             pipeline: Default::default(),
             ocr: Default::default(),
             accelerator: Default::default(),
             vlm: Default::default(),
+            tier_overrides: Default::default(),
+            table_chunks: Default::default(),
+            ocr_retry: Default::default(),
+            setup: Default::default(),
         }
     }
 }
 
+fn default_preflight_models() -> bool {
+    true
+}
+
+/// Per-chunk content type and the settings table-dominant chunks are converted with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoclingTableChunks {
+    /// "off" | "detect" | "route"
+    #[serde(default = "default_table_chunks_mode")]
+    pub mode: String,
+    /// Tables per page at or above which a chunk counts as table-dominant.
+    #[serde(default = "default_min_tables_per_page")]
+    pub min_tables_per_page: f32,
+    /// `[docling.pipeline]` (or `ocr.`-prefixed) keys applied on top of the tier's
+    /// overrides when a table-dominant chunk is converted again.
+    #[serde(default = "default_table_chunk_overrides")]
+    pub overrides: std::collections::BTreeMap<String, serde_json::Value>,
+}
+impl Default for DoclingTableChunks {
+    fn default() -> Self {
+        Self {
+            mode: default_table_chunks_mode(),
+            min_tables_per_page: default_min_tables_per_page(),
+            overrides: default_table_chunk_overrides(),
+        }
+    }
+}
+
+fn default_table_chunks_mode() -> String {
+    "detect".into()
+}
+
+fn default_min_tables_per_page() -> f32 {
+    0.5
+}
+
+fn default_table_chunk_overrides() -> std::collections::BTreeMap<String, serde_json::Value> {
+    [
+        ("do_table_structure".to_string(), serde_json::Value::Bool(true)),
+        ("table_batch_size".to_string(), serde_json::Value::from(32)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Converting OCR'd chunks again with more languages when too many words look wrong.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoclingOcrRetry {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Share of unknown words above which a chunk is retried.
+    #[serde(default = "default_max_unknown_word_ratio")]
+    pub max_unknown_word_ratio: f32,
+    /// Added to the chunk's `ocr.langs` for the retry.
+    #[serde(default)]
+    pub secondary_langs: Vec<String>,
+    /// Also add the Tesseract language of each non-Latin script found in the chunk.
+    #[serde(default = "default_ocr_retry_auto_detect")]
+    pub auto_detect: bool,
+    /// Word list, one word per line, that defines unknown words; empty uses a
+    /// word-shape heuristic.
+    #[serde(default)]
+    pub dictionary: String,
+}
+impl Default for DoclingOcrRetry {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_unknown_word_ratio: default_max_unknown_word_ratio(),
+            secondary_langs: Vec::new(),
+            auto_detect: default_ocr_retry_auto_detect(),
+            dictionary: String::new(),
+        }
+    }
+}
+
+fn default_max_unknown_word_ratio() -> f32 {
+    0.3
+}
+
+fn default_ocr_retry_auto_detect() -> bool {
+    true
+}
+
+/// The Python environment `doctor --fix` creates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoclingSetup {
+    /// Where the venv is created.
+    #[serde(default = "default_setup_venv_dir")]
+    pub venv_dir: String,
+    /// Interpreter the venv is created from.
+    #[serde(default = "default_setup_base_python")]
+    pub base_python: String,
+    /// Pinned pip requirements.
+    #[serde(default = "default_setup_packages")]
+    pub packages: Vec<String>,
+    /// Directory of wheels installed from with `global.offline_only`.
+    #[serde(default)]
+    pub wheel_dir: String,
+}
+impl Default for DoclingSetup {
+    fn default() -> Self {
+        Self {
+            venv_dir: default_setup_venv_dir(),
+            base_python: default_setup_base_python(),
+            packages: default_setup_packages(),
+            wheel_dir: String::new(),
+        }
+    }
+}
+
+fn default_setup_venv_dir() -> String {
+    ".venv-docling".into()
+}
+
+fn default_setup_base_python() -> String {
+    "python3".into()
+}
+
+fn default_setup_packages() -> Vec<String> {
+    vec![
+        "docling==2.31.0".into(),
+        "pypdf==5.4.0".into(),
+        "pypdfium2==4.30.1".into(),
+    ]
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DoclingBackend {
-    pub pdf_backend: String,
+    /// Backends to try in order; a chunk that fails under one is retried with the next.
+    #[serde(deserialize_with = "string_or_list")]
+    pub pdf_backend: Vec<String>,
 }
 impl Default for DoclingBackend {
     fn default() -> Self {
         Self {
-            pdf_backend: "AUTO".into(),
+            pdf_backend: vec!["AUTO".into()],
         }
     }
 }
 
+/// Accept either `key = "X"` or `key = ["X", "Y"]`.
+fn string_or_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DoclingPipeline {
     pub do_ocr: bool,
@@ -349,6 +842,15 @@ pub struct DoclingOcr {
     pub bitmap_area_threshold: f32,
     pub force_ocr: bool,
     pub tesseract_cli_args: String,
+    /// Read CJK pages as vertical text: tesseract gets the `*_vert` models of the CJK
+    /// languages in `langs` and page segmentation mode 5.
+    #[serde(default)]
+    pub vertical_text: bool,
+    /// Decide OCR page by page: pages with a usable text layer keep the backend's text
+    /// and only pages mostly covered by images (`bitmap_area_threshold`) are OCRed,
+    /// whatever the tier. Overrides `force_full_page_ocr` and `force_ocr`.
+    #[serde(default)]
+    pub hybrid: bool,
 }
 impl Default for DoclingOcr {
     fn default() -> Self {
@@ -359,6 +861,8 @@ impl Default for DoclingOcr {
             bitmap_area_threshold: 0.25,
             force_ocr: false,
             tesseract_cli_args: "".into(),
+            vertical_text: false,
+            hybrid: false,
         }
     }
 }
@@ -368,6 +872,15 @@ pub struct DoclingAccelerator {
     pub device: String,
     pub inference_threads: u32,
     pub use_fp16: bool,
+    /// When `device` (other than AUTO or CPU) is unavailable at the start of a docling
+    /// job: "fail" | "wait" (retry with backoff up to `wait_max_seconds`) | "cpu".
+    #[serde(default = "default_on_unavailable")]
+    pub on_unavailable: String,
+    #[serde(default = "default_wait_max_seconds")]
+    pub wait_max_seconds: u64,
+    /// Chunk size cap for jobs that fell back to CPU; 0 keeps `[chunking]` as is.
+    #[serde(default)]
+    pub cpu_max_pages_per_chunk: u32,
 }
 impl Default for DoclingAccelerator {
     fn default() -> Self {
@@ -375,10 +888,21 @@ impl Default for DoclingAccelerator {
             device: "AUTO".into(),
             inference_threads: 0,
             use_fp16: true,
+            on_unavailable: default_on_unavailable(),
+            wait_max_seconds: default_wait_max_seconds(),
+            cpu_max_pages_per_chunk: 0,
         }
     }
 }
 
+fn default_on_unavailable() -> String {
+    "fail".into()
+}
+
+fn default_wait_max_seconds() -> u64 {
+    600
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DoclingVlm {
     pub enabled: bool,
@@ -409,9 +933,74 @@ pub struct Postprocess {
     pub remove_repeated_lines: bool,
     pub repeated_line_min_occurrences: u32,
     pub repeated_line_max_length: u32,
+    /// "document" | "chunk_window": count repeated lines across the whole transcript,
+    /// or only within each chunk and `repeated_line_chunk_window` chunks either side.
+    #[serde(default = "default_repeated_line_scope")]
+    pub repeated_line_scope: String,
+    #[serde(default = "default_repeated_line_chunk_window")]
+    pub repeated_line_chunk_window: u32,
     pub remove_by_regex: bool,
+    #[serde(default = "default_embedded_images")]
+    pub embedded_images: String,
+    #[serde(default)]
+    pub keep_page_markers: bool,
+    #[serde(default)]
+    pub resolve_cross_references: bool,
+    #[serde(default = "default_verse")]
+    pub verse: String,
+    #[serde(default = "default_verse_max_line_chars")]
+    pub verse_max_line_chars: u32,
+    #[serde(default = "default_verse_min_lines")]
+    pub verse_min_lines: u32,
+    /// "off" | "scan" | "always": promote likely chapter titles to headings when a
+    /// document has few of them.
+    #[serde(default = "default_infer_chapter_titles")]
+    pub infer_chapter_titles: String,
+    /// "off" | "detect" | "markers": map PDF pages to printed page numbers in the
+    /// report, and with "markers" also label kept page markers with them.
+    #[serde(default = "default_printed_page_numbers")]
+    pub printed_page_numbers: String,
+    /// "off" | "extract" | "replace": parse back-of-book index and glossary sections
+    /// into `final/terms.json`, and with "replace" drop their raw lines from the
+    /// transcript.
+    #[serde(default = "default_extract_terms")]
+    pub extract_terms: String,
+    /// "rule" | "comment" | "both": separate merged chunks with `---`, with a
+    /// `<!-- quack:chunk ... -->` provenance comment at the start of each chunk, or both.
+    #[serde(default = "default_chunk_separator")]
+    pub chunk_separator: String,
+    /// Drop spaces that extraction or OCR left between CJK characters.
+    #[serde(default = "default_cjk_spacing")]
+    pub cjk_spacing: bool,
+    /// Start mostly right-to-left lines with a right-to-left mark (U+200F).
+    #[serde(default)]
+    pub rtl_line_marks: bool,
+    /// "keep" | "drop" | "marker" | "collapse": what to do with blank and near-blank
+    /// pages. "marker" leaves a `<!-- blank page N -->` comment per page, "collapse" one
+    /// per run of consecutive blank pages.
+    #[serde(default = "default_blank_pages")]
+    pub blank_pages: String,
+    /// A page with no image and at most this many letters and digits counts as blank.
+    #[serde(default = "default_blank_page_max_chars")]
+    pub blank_page_max_chars: u32,
+    /// "off" | "report" | "drop": what to do with pages repeating an earlier page of
+    /// the same or the previous chunk. "drop" leaves a `<!-- duplicate of page N -->`
+    /// comment in their place.
+    #[serde(default = "default_duplicate_pages")]
+    pub duplicate_pages: String,
+    /// Estimated shingle similarity at or above which a page counts as a duplicate.
+    #[serde(default = "default_duplicate_page_threshold")]
+    pub duplicate_page_threshold: f32,
+    /// Pages with fewer words are never compared.
+    #[serde(default = "default_duplicate_page_min_words")]
+    pub duplicate_page_min_words: u32,
     #[serde(default)]
     pub regex: PostprocessRegex,
+    #[serde(default)]
+    pub lint: PostprocessLint,
+    /// Cleanup steps run on each chunk before merging, in order.
+    #[serde(default)]
+    pub chunk_stages: Vec<ChunkStage>,
 }
 impl Default for Postprocess {
     fn default() -> Self {
@@ -423,12 +1012,116 @@ impl Default for Postprocess {
             remove_repeated_lines: true,
             repeated_line_min_occurrences: 6,
             repeated_line_max_length: 120,
+            repeated_line_scope: default_repeated_line_scope(),
+            repeated_line_chunk_window: default_repeated_line_chunk_window(),
             remove_by_regex: true,
+            embedded_images: default_embedded_images(),
+            keep_page_markers: false,
+            resolve_cross_references: false,
+            verse: default_verse(),
+            verse_max_line_chars: default_verse_max_line_chars(),
+            verse_min_lines: default_verse_min_lines(),
+            infer_chapter_titles: default_infer_chapter_titles(),
+            printed_page_numbers: default_printed_page_numbers(),
+            extract_terms: default_extract_terms(),
+            chunk_separator: default_chunk_separator(),
+            cjk_spacing: default_cjk_spacing(),
+            rtl_line_marks: false,
+            blank_pages: default_blank_pages(),
+            blank_page_max_chars: default_blank_page_max_chars(),
+            duplicate_pages: default_duplicate_pages(),
+            duplicate_page_threshold: default_duplicate_page_threshold(),
+            duplicate_page_min_words: default_duplicate_page_min_words(),
             regex: Default::default(),
+            lint: Default::default(),
+            chunk_stages: Vec::new(),
         }
     }
 }
 
+/// A `[[postprocess.chunk_stages]]` entry. Patterns match line by line, and the
+/// `<!-- quack:... -->` marker lines are left alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkStage {
+    /// "replace" | "drop_lines"
+    pub kind: String,
+    pub pattern: String,
+    /// For "replace": `$1` names a capture group, and `{index}`, `{start_page}`,
+    /// `{end_page}` and `{engine}` the chunk.
+    #[serde(default)]
+    pub replacement: String,
+    /// Only chunks converted by one of these engines; empty for all.
+    #[serde(default)]
+    pub engines: Vec<String>,
+    /// Only chunks with one of these content types ("table", "prose"); empty for all.
+    #[serde(default)]
+    pub content_types: Vec<String>,
+}
+
+fn default_repeated_line_scope() -> String {
+    "document".into()
+}
+
+fn default_repeated_line_chunk_window() -> u32 {
+    1
+}
+
+fn default_embedded_images() -> String {
+    "keep".into()
+}
+
+fn default_verse() -> String {
+    "off".into()
+}
+
+fn default_verse_max_line_chars() -> u32 {
+    60
+}
+
+fn default_verse_min_lines() -> u32 {
+    4
+}
+
+fn default_infer_chapter_titles() -> String {
+    "scan".into()
+}
+
+fn default_printed_page_numbers() -> String {
+    "detect".into()
+}
+
+fn default_extract_terms() -> String {
+    "off".into()
+}
+
+fn default_cjk_spacing() -> bool {
+    true
+}
+
+fn default_blank_pages() -> String {
+    "keep".into()
+}
+
+fn default_blank_page_max_chars() -> u32 {
+    3
+}
+
+fn default_duplicate_pages() -> String {
+    "report".into()
+}
+
+fn default_duplicate_page_threshold() -> f32 {
+    0.9
+}
+
+fn default_duplicate_page_min_words() -> u32 {
+    30
+}
+
+fn default_chunk_separator() -> String {
+    "rule".into()
+}
+
 fn default_control_chars_to_sanitize() -> Vec<u8> {
     let mut out: Vec<u8> = (0u8..=31u8).collect();
     out.extend(127u8..=159u8);
@@ -450,6 +1143,33 @@ impl Default for PostprocessRegex {
     }
 }
 
+/// Structural checks on the final markdown, recorded under `lint` in the report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostprocessLint {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Longer lines are flagged; 0 disables the check.
+    #[serde(default = "default_lint_max_line_chars")]
+    pub max_line_chars: u32,
+    /// Fail the job, before anything is written to `final/`, when a check finds an
+    /// error.
+    #[serde(default)]
+    pub fail_on_error: bool,
+}
+impl Default for PostprocessLint {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_line_chars: default_lint_max_line_chars(),
+            fail_on_error: false,
+        }
+    }
+}
+
+fn default_lint_max_line_chars() -> u32 {
+    2000
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Output {
     pub write_markdown: bool,
@@ -460,6 +1180,50 @@ pub struct Output {
     pub text_filename: String,
     pub report_filename: String,
     pub write_index_json: bool,
+    #[serde(default = "default_write_decisions_json")]
+    pub write_decisions_json: bool,
+    #[serde(default)]
+    pub split_final_by: String,
+    #[serde(default = "default_tokenizer")]
+    pub tokenizer: String,
+    /// Write `final/page_quality.json` with a per-page quality score.
+    #[serde(default = "default_write_page_quality")]
+    pub write_page_quality: bool,
+    /// Also render the scores as `final/page_quality.svg`.
+    #[serde(default)]
+    pub page_quality_svg: bool,
+    /// Write `final/pagemap.json`, mapping transcript offsets and paragraphs back to
+    /// their chunk, PDF page, and printed page.
+    #[serde(default = "default_write_pagemap")]
+    pub write_pagemap: bool,
+    /// Write `final/checksums.json` with the sha256 of every final artifact.
+    #[serde(default)]
+    pub write_checksums: bool,
+    /// Also write each chunk's stretch of the transcript to
+    /// `final/chunks/0001_pages-0001-0040.md`.
+    #[serde(default)]
+    pub write_chunk_markdown: bool,
+    /// End the transcript with a "Processing provenance" section listing each chunk's
+    /// pages, engine, conversion time, and warnings.
+    #[serde(default)]
+    pub provenance_appendix: bool,
+    /// Write a thumbnail of every page to `final/thumbnails/page-0001.png` (`pdfium`
+    /// cargo feature).
+    #[serde(default)]
+    pub thumbnails: bool,
+    #[serde(default = "default_thumbnail_width_px")]
+    pub thumbnail_width_px: u32,
+    /// Render this many of the worst pages by quality score to
+    /// `final/flagged/page-0042.png` for proofreading (`pdfium` cargo feature).
+    #[serde(default)]
+    pub flagged_page_images: u32,
+    #[serde(default = "default_flagged_page_width_px")]
+    pub flagged_page_width_px: u32,
+    /// Extra destinations that receive a copy of `final/` after a successful run.
+    #[serde(default)]
+    pub mirrors: Vec<Mirror>,
+    #[serde(default)]
+    pub encoding: OutputEncoding,
 }
 impl Default for Output {
     fn default() -> Self {
@@ -472,16 +1236,108 @@ impl Default for Output {
             text_filename: "transcript.txt".into(),
             report_filename: "report.json".into(),
             write_index_json: true,
+            write_decisions_json: true,
+            split_final_by: "".into(),
+            tokenizer: default_tokenizer(),
+            write_page_quality: default_write_page_quality(),
+            page_quality_svg: false,
+            write_pagemap: default_write_pagemap(),
+            write_checksums: false,
+            write_chunk_markdown: false,
+            provenance_appendix: false,
+            thumbnails: false,
+            thumbnail_width_px: default_thumbnail_width_px(),
+            flagged_page_images: 0,
+            flagged_page_width_px: default_flagged_page_width_px(),
+            mirrors: Vec::new(),
+            encoding: OutputEncoding::default(),
         }
     }
 }
 
+/// How the text files under `final/` (transcript, plain text, sections, chunk
+/// markdown) are written. JSON artifacts are always LF, UTF-8 without a BOM.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputEncoding {
+    /// "lf" | "crlf"
+    #[serde(default = "default_newline")]
+    pub newline: String,
+    /// Start each file with a UTF-8 byte order mark.
+    #[serde(default)]
+    pub bom: bool,
+    /// Unicode normalization applied as the file is written: "none" | "nfc" | "nfkc".
+    #[serde(default = "default_normalization")]
+    pub normalization: String,
+}
+impl Default for OutputEncoding {
+    fn default() -> Self {
+        Self {
+            newline: default_newline(),
+            bom: false,
+            normalization: default_normalization(),
+        }
+    }
+}
+
+fn default_newline() -> String {
+    "lf".into()
+}
+
+fn default_normalization() -> String {
+    "none".into()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mirror {
+    /// Destination directory; `{job_id}`, `{job_id_short}`, `{input_stem}`,
+    /// `{input_name}`, `{tier}`, `{engine}`, `{pages}` and `{date}` are expanded.
+    pub path: String,
+    /// "copy" | "hardlink" (falls back to copying across filesystems).
+    #[serde(default = "default_mirror_mode")]
+    pub mode: String,
+}
+
+fn default_mirror_mode() -> String {
+    "copy".into()
+}
+
+fn default_write_decisions_json() -> bool {
+    true
+}
+
+fn default_write_page_quality() -> bool {
+    true
+}
+
+fn default_write_pagemap() -> bool {
+    true
+}
+
+fn default_tokenizer() -> String {
+    "approx".into()
+}
+
+fn default_thumbnail_width_px() -> u32 {
+    200
+}
+
+fn default_flagged_page_width_px() -> u32 {
+    1200
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Logging {
     pub level: String,
     pub json: bool,
     pub write_to_file: bool,
     pub file_path: String,
+    #[serde(default)]
+    pub redact: Vec<String>,
+    /// Also send events to the systemd journal with their fields as `QUACK_*` fields.
+    #[serde(default)]
+    pub journald: bool,
+    #[serde(default)]
+    pub rotate: Rotate,
 }
 impl Default for Logging {
     fn default() -> Self {
@@ -490,20 +1346,61 @@ impl Default for Logging {
             json: false,
             write_to_file: true,
             file_path: "".into(),
+            redact: vec![],
+            journald: false,
+            rotate: Default::default(),
+        }
+    }
+}
+
+/// Rotation of the shared log file (`logging.file_path`, or `quack-check.log` in the
+/// output root), which long `batch` runs keep appending to. Per-job logs in
+/// `job_dir/logs/` are never rotated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rotate {
+    /// Rotate once the file reaches this size; 0 never rotates by size.
+    #[serde(default)]
+    pub max_mb: u64,
+    /// Also rotate at the start of each period (UTC): "never", "hourly", or "daily".
+    #[serde(default = "default_rotate_every")]
+    pub every: String,
+    /// Rotated files kept next to the log, as `<file>.1` (newest) to `<file>.N`.
+    #[serde(default = "default_rotate_keep_files")]
+    pub keep_files: u32,
+}
+impl Default for Rotate {
+    fn default() -> Self {
+        Self {
+            max_mb: 0,
+            every: default_rotate_every(),
+            keep_files: default_rotate_keep_files(),
         }
     }
 }
 
+fn default_rotate_every() -> String {
+    "never".into()
+}
+
+fn default_rotate_keep_files() -> u32 {
+    5
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Debug {
     pub keep_python_stderr: bool,
     pub dump_effective_config: bool,
+    /// Record every engine request and response to `logs/engine-io.jsonl`, for
+    /// `quack-check replay`.
+    #[serde(default)]
+    pub record_engine_io: bool,
 }
 impl Default for Debug {
     fn default() -> Self {
         Self {
             keep_python_stderr: true,
             dump_effective_config: true,
+            record_engine_io: false,
         }
     }
 }
@@ -512,12 +1409,313 @@ impl Default for Debug {
 pub struct Security {
     pub reject_url_inputs: bool,
     pub pin_scripts_dir: bool,
+    #[serde(default)]
+    pub child_limits: ChildLimits,
+    #[serde(default)]
+    pub sandbox: Sandbox,
+    #[serde(default)]
+    pub pdf_features: PdfFeatures,
+    /// ed25519 key that signs `final/checksums.json`; empty disables signing.
+    #[serde(default)]
+    pub signing_key_path: String,
+    /// Directories inputs must resolve into, symlinks followed; empty allows any.
+    #[serde(default)]
+    pub allowed_input_roots: Vec<String>,
 }
 impl Default for Security {
     fn default() -> Self {
         Self {
             reject_url_inputs: true,
             pin_scripts_dir: true,
+            child_limits: Default::default(),
+            sandbox: Default::default(),
+            pdf_features: Default::default(),
+            signing_key_path: "".into(),
+            allowed_input_roots: Vec::new(),
+        }
+    }
+}
+
+/// What to do when the probe finds a risky PDF feature: "allow" | "warn" | "strip" |
+/// "refuse". "strip" converts a copy without the feature; it is not available for
+/// `forms` and `xref_repaired`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfFeatures {
+    #[serde(default = "default_feature_warn")]
+    pub xfa: String,
+    #[serde(default = "default_feature_warn")]
+    pub javascript: String,
+    #[serde(default = "default_feature_warn")]
+    pub attachments: String,
+    #[serde(default = "default_feature_warn")]
+    pub xref_repaired: String,
+    #[serde(default = "default_feature_allow")]
+    pub forms: String,
+}
+impl Default for PdfFeatures {
+    fn default() -> Self {
+        Self {
+            xfa: default_feature_warn(),
+            javascript: default_feature_warn(),
+            attachments: default_feature_warn(),
+            xref_repaired: default_feature_warn(),
+            forms: default_feature_allow(),
         }
     }
 }
+
+impl PdfFeatures {
+    /// The configured action for a feature name the probe reports.
+    pub fn action(&self, feature: &str) -> Option<&str> {
+        Some(match feature {
+            "xfa" => &self.xfa,
+            "javascript" => &self.javascript,
+            "attachments" => &self.attachments,
+            "xref_repaired" => &self.xref_repaired,
+            "forms" => &self.forms,
+            _ => return None,
+        })
+    }
+}
+
+fn default_feature_warn() -> String {
+    "warn".into()
+}
+
+fn default_feature_allow() -> String {
+    "allow".into()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sandbox {
+    pub mode: String,
+    #[serde(default = "default_bwrap_exe")]
+    pub bwrap_exe: String,
+    #[serde(default)]
+    pub wrapper: Vec<String>,
+    #[serde(default)]
+    pub extra_ro_paths: Vec<String>,
+}
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            mode: "off".into(),
+            bwrap_exe: default_bwrap_exe(),
+            wrapper: vec![],
+            extra_ro_paths: vec![],
+        }
+    }
+}
+
+fn default_bwrap_exe() -> String {
+    "bwrap".into()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChildLimits {
+    pub max_rss_bytes: u64,
+    pub max_cpu_seconds: u64,
+    pub nice: i32,
+    pub cgroup: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    pub dedup: String,
+    #[serde(default = "default_minhash_permutations")]
+    pub minhash_permutations: usize,
+    #[serde(default = "default_shingle_words")]
+    pub shingle_words: usize,
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub near_duplicate_threshold: f32,
+    /// Append a short hash of the input's path to `{input_stem}` and `{input_name}`
+    /// when inputs share a file stem (`scan.pdf` in several subfolders).
+    #[serde(default = "default_disambiguate_stems")]
+    pub disambiguate_stems: bool,
+    /// Longest output path allowed, in bytes; 0 uses the platform's limit.
+    #[serde(default)]
+    pub max_path_length: usize,
+    /// "shorten" (`{input_stem}` is cut to fit, with a hash suffix) | "fail".
+    #[serde(default = "default_long_paths")]
+    pub long_paths: String,
+}
+impl Default for Batch {
+    fn default() -> Self {
+        Self {
+            dedup: "minhash".into(),
+            minhash_permutations: default_minhash_permutations(),
+            shingle_words: default_shingle_words(),
+            near_duplicate_threshold: default_near_duplicate_threshold(),
+            disambiguate_stems: default_disambiguate_stems(),
+            max_path_length: 0,
+            long_paths: default_long_paths(),
+        }
+    }
+}
+
+fn default_disambiguate_stems() -> bool {
+    true
+}
+
+fn default_long_paths() -> String {
+    "shorten".into()
+}
+
+fn default_minhash_permutations() -> usize {
+    64
+}
+
+fn default_shingle_words() -> usize {
+    5
+}
+
+fn default_near_duplicate_threshold() -> f32 {
+    0.9
+}
+
+/// What happens to each class of intermediate artifact in a job directory, applied
+/// when a job finishes (unless `global.keep_intermediates`) and by `jobs clean`. Each
+/// rule is "keep" | "delete" | "failed" (keep only for chunks that did not convert
+/// cleanly) | "<N>d" (keep for N days after the file was written).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Retention {
+    /// `chunks/chunk_*.pdf` and `input.stripped.pdf`.
+    #[serde(default = "default_retain_keep")]
+    pub split_pdfs: String,
+    /// `chunks/chunk_NNNNN.json`; needed to resume and to re-merge with `jobs doctor`.
+    #[serde(default = "default_retain_keep")]
+    pub chunk_json: String,
+    /// `logs/chunk_NNNNN.stderr.log`.
+    #[serde(default = "default_retain_keep")]
+    pub stderr_logs: String,
+    /// Rendered page images left under `chunks/`.
+    #[serde(default = "default_retain_delete")]
+    pub page_images: String,
+}
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            split_pdfs: default_retain_keep(),
+            chunk_json: default_retain_keep(),
+            stderr_logs: default_retain_keep(),
+            page_images: default_retain_delete(),
+        }
+    }
+}
+
+fn default_retain_keep() -> String {
+    "keep".into()
+}
+
+fn default_retain_delete() -> String {
+    "delete".into()
+}
+
+/// Settings about the input document itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Input {
+    /// Overlay `<input>.quack.toml`, when present, on the config for that input.
+    #[serde(default = "default_input_sidecar")]
+    pub sidecar: bool,
+    #[serde(default)]
+    pub exclusions: Exclusions,
+    /// "off" | "auto" | "always": convert each PDF page as its left and right halves,
+    /// for two-up scans. "auto" splits when the probe finds most sampled pages are
+    /// spreads.
+    #[serde(default = "default_split_spreads")]
+    pub split_spreads: String,
+    /// Cut pages larger than `max_page_megapixels` (rendered for OCR) into tiles, convert
+    /// each tile as a page, and join their text under tile markers.
+    #[serde(default)]
+    pub tile_oversized_pages: bool,
+    /// Largest page, in megapixels at docling's 216 dpi OCR rendering, converted whole.
+    #[serde(default = "default_max_page_megapixels")]
+    pub max_page_megapixels: f64,
+}
+impl Default for Input {
+    fn default() -> Self {
+        Self {
+            sidecar: default_input_sidecar(),
+            exclusions: Exclusions::default(),
+            split_spreads: default_split_spreads(),
+            tile_oversized_pages: false,
+            max_page_megapixels: default_max_page_megapixels(),
+        }
+    }
+}
+
+fn default_input_sidecar() -> bool {
+    true
+}
+
+fn default_split_spreads() -> String {
+    "off".into()
+}
+
+fn default_max_page_megapixels() -> f64 {
+    40.0
+}
+
+/// Pages to leave out of conversion, such as ads, plates, or duplicated scans.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Exclusions {
+    #[serde(default)]
+    pub ranges: Vec<ExcludedRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedRange {
+    /// "12" or "201-240", 1-based and inclusive.
+    pub pages: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Commands run around each pipeline stage by `run` and `batch`
+/// ([`crate::hooks::CommandHooks`]). Each entry is a list of commands, each an argv
+/// list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hooks {
+    #[serde(default)]
+    pub probe: Vec<Vec<String>>,
+    #[serde(default)]
+    pub decision: Vec<Vec<String>>,
+    #[serde(default)]
+    pub before_chunk: Vec<Vec<String>>,
+    #[serde(default)]
+    pub after_chunk: Vec<Vec<String>>,
+    #[serde(default)]
+    pub before_merge: Vec<Vec<String>>,
+    #[serde(default)]
+    pub after_merge: Vec<Vec<String>>,
+    /// Kill a hook command and fail the job after this long; 0 waits forever.
+    #[serde(default = "default_hook_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            probe: Vec::new(),
+            decision: Vec::new(),
+            before_chunk: Vec::new(),
+            after_chunk: Vec::new(),
+            before_merge: Vec::new(),
+            after_merge: Vec::new(),
+            timeout_seconds: default_hook_timeout_seconds(),
+        }
+    }
+}
+
+fn default_hook_timeout_seconds() -> u64 {
+    60
+}
+
+/// Prometheus metrics for fleets of `run` and `batch` invocations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metrics {
+    /// node-exporter textfile collector file (`*.prom`), rewritten as jobs progress;
+    /// empty disables metrics.
+    #[serde(default)]
+    pub textfile_path: String,
+}
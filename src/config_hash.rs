@@ -0,0 +1,136 @@
+use crate::{config::Config, util::sha256_hex};
+use serde_json::{Map, Value};
+
+/// Version of the canonical form below, appended to every job id. Bump it whenever
+/// `HASHED` or `UNHASHED` changes for existing keys or the canonical form is computed
+/// differently, so old and new job directories never collide. Listing a newly added key
+/// in `UNHASHED` needs no bump: no older config sets it.
+pub const VERSION: u32 = 1;
+
+/// Dotted config paths that decide what the chunk conversions produce. A section name
+/// covers every key under it. Postprocessing and output settings are left out: a
+/// resumed job re-merges its chunk records with the current config anyway.
+const HASHED: &[&str] = &[
+    "limits.require_chunking_over_pages",
+    "limits.require_chunking_over_bytes",
+    "limits.on_page_error",
+    "input.exclusions.ranges",
+    "input.split_spreads",
+    "input.tile_oversized_pages",
+    "input.max_page_megapixels",
+    "classification",
+    "chunking",
+    "engine",
+    "native_text",
+    "docling",
+    "security.pdf_features",
+];
+
+/// Keys inside `HASHED` sections that do not change conversion output.
+const UNHASHED: &[&str] = &[
+    "classification.probe_cache_ttl_seconds",
+    "chunking.keep_split_pdfs",
+    "engine.remote.api_key_env",
+    "engine.remote.api_key_header",
+    "engine.remote.poll_interval_seconds",
+    "engine.remote.timeout_seconds",
+    "docling.python_exe",
+    "native_text.python_exe",
+    "docling.doctor_timeout_seconds",
+    "docling.chunk_timeout_seconds",
+    "docling.preflight_models",
+    "docling.startup_stagger_seconds",
+    "docling.max_concurrent_startups",
+    "docling.accelerator.wait_max_seconds",
+    "docling.setup",
+];
+
+/// The config as it takes part in the job id: only the `HASHED` paths, without the
+/// `UNHASHED` keys, and without any key still at its default, as compact JSON with
+/// sorted keys. A new key with a default, a reordered file, or a changed log or output
+/// setting leaves it unchanged.
+pub fn canonical(cfg: &Config) -> String {
+    let current = serde_json::to_value(cfg).unwrap_or_default();
+    let defaults = serde_json::to_value(Config::default()).unwrap_or_default();
+    let mut out = Value::Object(Map::new());
+    for path in HASHED {
+        let Some(mut value) = lookup(&current, path).cloned() else {
+            continue;
+        };
+        for key in UNHASHED {
+            if let Some(rest) = key
+                .strip_prefix(path)
+                .and_then(|rest| rest.strip_prefix('.'))
+            {
+                remove(&mut value, rest);
+            }
+        }
+        if let Some(default) = lookup(&defaults, path)
+            && !strip_defaults(&mut value, default)
+        {
+            continue;
+        }
+        insert(&mut out, path, value);
+    }
+    // serde_json's default map is ordered by key.
+    serde_json::to_string(&out).unwrap_or_default()
+}
+
+/// sha256 of [`canonical`].
+pub fn config_hash(cfg: &Config) -> String {
+    sha256_hex(canonical(cfg).as_bytes())
+}
+
+/// `<sha256 of config hash and input hash>-v<VERSION>`.
+pub fn job_id(cfg: &Config, input_hash: &str) -> String {
+    let digest = sha256_hex(format!("{}:{}", config_hash(cfg), input_hash).as_bytes());
+    format!("{digest}-v{VERSION}")
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, key| v.get(key))
+}
+
+fn remove(value: &mut Value, path: &str) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            if let Some(child) = value.get_mut(head) {
+                remove(child, rest);
+            }
+        }
+        None => {
+            if let Some(map) = value.as_object_mut() {
+                map.remove(path);
+            }
+        }
+    }
+}
+
+fn insert(out: &mut Value, path: &str, value: Value) {
+    let mut node = out;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Some(map) = node.as_object_mut() else {
+            return;
+        };
+        if keys.peek().is_none() {
+            map.insert(key.to_string(), value);
+            return;
+        }
+        node = map
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+}
+
+/// Drop everything in `value` equal to `default`. Returns whether anything is left.
+fn strip_defaults(value: &mut Value, default: &Value) -> bool {
+    if let (Some(map), Some(default_map)) = (value.as_object_mut(), default.as_object()) {
+        map.retain(|key, v| match default_map.get(key) {
+            Some(d) => strip_defaults(v, d),
+            None => true,
+        });
+        return !map.is_empty();
+    }
+    value != default
+}
@@ -0,0 +1,21 @@
+use crate::{config::Config, engine::ConvertMeta};
+
+pub const TABLE: &str = "table";
+pub const PROSE: &str = "prose";
+
+/// "table" when docling found at least `docling.table_chunks.min_tables_per_page`
+/// tables per converted page, "prose" otherwise. `None` when detection is off or the
+/// engine did not count tables (native text).
+pub fn chunk_content_type(cfg: &Config, meta: &ConvertMeta, pages: u32) -> Option<&'static str> {
+    let settings = &cfg.docling.table_chunks;
+    if settings.mode == "off" {
+        return None;
+    }
+    let tables = meta.table_count?;
+    let pages = meta.pages_processed.unwrap_or(pages).max(1);
+    if tables as f32 / pages as f32 >= settings.min_tables_per_page {
+        Some(TABLE)
+    } else {
+        Some(PROSE)
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    config::Config,
+    encoding,
+    inspect::{self, JobView},
+    jobs,
+    page_quality::{PageQualityReport, PAGE_QUALITY_FILE},
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// DuckDB script written next to the tables; `duckdb corpus.duckdb < load.sql` loads them.
+pub const LOAD_SCRIPT: &str = "load.sql";
+
+/// The exported tables and their columns, with DuckDB types. Rows are written as
+/// newline-delimited JSON objects with exactly these keys.
+pub const TABLES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "documents",
+        &[
+            ("job_id", "VARCHAR"),
+            ("input_path", "VARCHAR"),
+            ("canonical_path", "VARCHAR"),
+            ("file_bytes", "UBIGINT"),
+            ("page_count", "UINTEGER"),
+            ("tier", "VARCHAR"),
+            ("engine", "VARCHAR"),
+            ("do_ocr", "BOOLEAN"),
+            ("complete", "BOOLEAN"),
+            ("converted_pages", "UINTEGER"),
+            ("chunk_count", "UINTEGER"),
+            ("tokenizer", "VARCHAR"),
+            ("tokens", "UBIGINT"),
+            ("markdown", "VARCHAR"),
+        ],
+    ),
+    (
+        "chunks",
+        &[
+            ("job_id", "VARCHAR"),
+            ("chunk_index", "UINTEGER"),
+            ("start_page", "UINTEGER"),
+            ("end_page", "UINTEGER"),
+            ("ok", "BOOLEAN"),
+            ("skipped", "VARCHAR"),
+            ("pdf_backend", "VARCHAR"),
+            ("ocr_applied", "BOOLEAN"),
+            ("content_type", "VARCHAR"),
+            ("script", "VARCHAR"),
+            ("tokens", "UBIGINT"),
+            ("seconds", "DOUBLE"),
+            ("warning_count", "UINTEGER"),
+            ("page_error_count", "UINTEGER"),
+        ],
+    ),
+    (
+        "pages",
+        &[
+            ("job_id", "VARCHAR"),
+            ("page", "UINTEGER"),
+            ("chars", "UBIGINT"),
+            ("garbage_ratio", "FLOAT"),
+            ("ocr_confidence", "FLOAT"),
+            ("score", "FLOAT"),
+            ("warnings", "VARCHAR[]"),
+        ],
+    ),
+    (
+        "warnings",
+        &[
+            ("job_id", "VARCHAR"),
+            ("chunk_index", "UINTEGER"),
+            ("code", "VARCHAR"),
+            ("warning", "VARCHAR"),
+        ],
+    ),
+    (
+        "quality",
+        &[
+            ("job_id", "VARCHAR"),
+            ("sampled_pages", "UINTEGER"),
+            ("avg_chars_per_page", "UINTEGER"),
+            ("garbage_ratio", "FLOAT"),
+            ("whitespace_ratio", "FLOAT"),
+            ("pages_scored", "UINTEGER"),
+            ("mean_page_score", "FLOAT"),
+            ("min_page_score", "FLOAT"),
+            ("blank_pages", "UINTEGER"),
+            ("page_errors", "UINTEGER"),
+            ("warnings", "UINTEGER"),
+        ],
+    ),
+];
+
+/// What `corpus export` wrote.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    pub out: PathBuf,
+    pub documents: usize,
+    /// Job directories without a final report (still running, failed, or incomplete).
+    pub skipped_jobs: Vec<String>,
+    /// Rows written per table.
+    pub rows: Vec<(String, usize)>,
+}
+
+/// Write one `<table>.ndjson` per table in [`TABLES`] under `out`, covering every job
+/// under `out_root` that has a final report, plus [`LOAD_SCRIPT`].
+pub fn export(cfg: &Config, out_root: &Path, out: &Path) -> Result<ExportSummary> {
+    std::fs::create_dir_all(out).with_context(|| format!("create_dir_all {}", out.display()))?;
+    let out = std::fs::canonicalize(out)?;
+    let mut tables: Vec<Table> = TABLES
+        .iter()
+        .map(|(name, _)| Table::create(&out, name))
+        .collect::<Result<_>>()?;
+    let mut documents = 0;
+    let mut skipped_jobs = Vec::new();
+
+    for job_dir in jobs::job_dirs(out_root)? {
+        let view = inspect::load(cfg, &job_dir)?;
+        if view.report.is_none() {
+            skipped_jobs.push(view.job_id);
+            continue;
+        }
+        for (table, rows) in tables.iter_mut().zip(job_rows(cfg, &view)?) {
+            for row in rows {
+                table.write(&row)?;
+            }
+        }
+        documents += 1;
+    }
+
+    let mut rows = Vec::new();
+    for table in tables {
+        rows.push((table.name.to_string(), table.finish()?));
+    }
+    std::fs::write(out.join(LOAD_SCRIPT), load_script(&out))?;
+    for job_id in &skipped_jobs {
+        warn!("corpus: skipping {job_id}: no final report");
+    }
+    info!(
+        "corpus: exported {documents} document(s) to {}",
+        out.display()
+    );
+    Ok(ExportSummary {
+        out,
+        documents,
+        skipped_jobs,
+        rows,
+    })
+}
+
+/// Load the exported tables into a DuckDB database with the `duckdb` command.
+pub fn load_into_duckdb(out: &Path, database: &Path) -> Result<()> {
+    let script = std::fs::File::open(out.join(LOAD_SCRIPT))?;
+    let status = std::process::Command::new("duckdb")
+        .arg(database)
+        .stdin(script)
+        .status()
+        .map_err(|err| anyhow!("running duckdb (is it on PATH?): {err}"))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "duckdb failed loading {}: {status}",
+            database.display()
+        ));
+    }
+    Ok(())
+}
+
+/// `CREATE OR REPLACE TABLE` statements reading the NDJSON files with fixed column
+/// types, so empty tables and all-null columns load the same as full ones.
+pub fn load_script(out: &Path) -> String {
+    let mut sql = String::from("-- Generated by `quack-check corpus export`.\n");
+    for (name, columns) in TABLES {
+        let columns: Vec<String> = columns
+            .iter()
+            .map(|(column, ty)| format!("{column}: '{ty}'"))
+            .collect();
+        let path = out.join(format!("{name}.ndjson"));
+        let _ = writeln!(
+            sql,
+            "CREATE OR REPLACE TABLE {name} AS SELECT * FROM read_ndjson('{}', columns = {{{}}});",
+            path.display().to_string().replace('\'', "''"),
+            columns.join(", ")
+        );
+    }
+    sql
+}
+
+/// One job's rows, per table in [`TABLES`] order.
+fn job_rows(cfg: &Config, view: &JobView) -> Result<Vec<Vec<Value>>> {
+    let Some(report) = &view.report else {
+        return Ok(vec![Vec::new(); TABLES.len()]);
+    };
+    let job_id = &view.job_id;
+    let job_cfg = jobs::job_config(cfg, &view.job_dir);
+    let final_dir = view.job_dir.join("final");
+    // index.json names the file with any filename template expanded.
+    let markdown_file = std::fs::read_to_string(view.job_dir.join("index.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+        .and_then(|index| {
+            index["final_markdown"]
+                .as_str()
+                .map(|f| view.job_dir.join(f))
+        })
+        .unwrap_or_else(|| final_dir.join(&job_cfg.output.markdown_filename));
+    let markdown = std::fs::read_to_string(markdown_file)
+        .ok()
+        .map(|raw| encoding::decode(&raw));
+    let page_quality: Option<PageQualityReport> =
+        std::fs::read_to_string(final_dir.join(PAGE_QUALITY_FILE))
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok());
+    let chunks = view.chunks();
+
+    let document = json!({
+        "job_id": job_id,
+        "input_path": report.input.path,
+        "canonical_path": report.input.canonical_path,
+        "file_bytes": report.input.file_bytes,
+        "page_count": report.input.page_count,
+        "tier": report.decision.tier,
+        "engine": report.decision.chosen_engine,
+        "do_ocr": report.decision.do_ocr,
+        "complete": report.completeness.complete,
+        "converted_pages": report.completeness.converted_pages,
+        "chunk_count": chunks.len(),
+        "tokenizer": report.tokens.tokenizer,
+        "tokens": report.tokens.total,
+        "markdown": markdown,
+    });
+
+    let chunk_rows = chunks
+        .iter()
+        .map(|c| {
+            json!({
+                "job_id": job_id,
+                "chunk_index": c.chunk_index,
+                "start_page": c.start_page,
+                "end_page": c.end_page,
+                "ok": c.ok,
+                "skipped": c.skipped,
+                "pdf_backend": c.pdf_backend,
+                "ocr_applied": c.meta.ocr_applied,
+                "content_type": c.content_type,
+                "script": c.script,
+                "tokens": c.tokens,
+                "seconds": c.meta.timings.values().sum::<f64>(),
+                "warning_count": c.warnings.len(),
+                "page_error_count": c.page_errors.len(),
+            })
+        })
+        .collect();
+
+    let pages = page_quality
+        .as_ref()
+        .map(|q| q.pages.as_slice())
+        .unwrap_or_default();
+    let page_rows = pages
+        .iter()
+        .map(|p| {
+            json!({
+                "job_id": job_id,
+                "page": p.page,
+                "chars": p.chars,
+                "garbage_ratio": p.garbage_ratio,
+                "ocr_confidence": p.ocr_confidence,
+                "score": p.score,
+                "warnings": p.warnings,
+            })
+        })
+        .collect();
+
+    let warning_rows: Vec<Value> = chunks
+        .iter()
+        .flat_map(|c| {
+            c.warnings.iter().map(move |w| {
+                json!({
+                    "job_id": job_id,
+                    "chunk_index": c.chunk_index,
+                    "code": inspect::warning_code(w),
+                    "warning": w,
+                })
+            })
+        })
+        .collect();
+
+    let scores: Vec<f32> = pages.iter().filter_map(|p| p.score).collect();
+    let quality = json!({
+        "job_id": job_id,
+        "sampled_pages": report.sample.sampled_pages,
+        "avg_chars_per_page": report.sample.avg_chars_per_page,
+        "garbage_ratio": report.sample.garbage_ratio,
+        "whitespace_ratio": report.sample.whitespace_ratio,
+        "pages_scored": scores.len(),
+        "mean_page_score": (!scores.is_empty())
+            .then(|| scores.iter().sum::<f32>() / scores.len() as f32),
+        "min_page_score": scores.iter().copied().reduce(f32::min),
+        "blank_pages": report.blank_pages.pages.len(),
+        "page_errors": chunks.iter().map(|c| c.page_errors.len()).sum::<usize>(),
+        "warnings": warning_rows.len(),
+    });
+
+    Ok(vec![
+        vec![document],
+        chunk_rows,
+        page_rows,
+        warning_rows,
+        vec![quality],
+    ])
+}
+
+struct Table {
+    name: &'static str,
+    file: std::io::BufWriter<std::fs::File>,
+    rows: usize,
+}
+
+impl Table {
+    fn create(out: &Path, name: &'static str) -> Result<Self> {
+        let path = out.join(format!("{name}.ndjson"));
+        let file =
+            std::fs::File::create(&path).with_context(|| format!("create {}", path.display()))?;
+        Ok(Self {
+            name,
+            file: std::io::BufWriter::new(file),
+            rows: 0,
+        })
+    }
+
+    fn write(&mut self, row: &Value) -> Result<()> {
+        serde_json::to_writer(&mut self.file, row)?;
+        self.file.write_all(b"\n")?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<usize> {
+        self.file.flush()?;
+        Ok(self.rows)
+    }
+}
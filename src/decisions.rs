@@ -0,0 +1,146 @@
+use crate::{config::Config, policy::QualityTier, report::JobReport, tiles::PageTiles};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const DECISIONS_FILE: &str = "decisions.json";
+
+/// Every runtime decision a job made, without timings, paths, or tool versions, so the
+/// file can be committed for reference documents and diffed after upgrades.
+#[derive(Debug, Clone, Serialize)]
+pub struct Decisions {
+    pub page_count: u32,
+    pub tier: QualityTier,
+    pub engine: String,
+    pub do_ocr: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub split_spreads: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tiled_pages: Vec<PageTiles>,
+    pub pipeline_overrides: BTreeMap<String, serde_json::Value>,
+    pub on_page_error: String,
+    pub chunks: Vec<ChunkDecision>,
+    pub postprocess: Vec<String>,
+    pub split_final_by: String,
+    pub sections: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkDecision {
+    pub index: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+    pub engine: String,
+    pub pdf_backend: Option<String>,
+    pub content_type: Option<String>,
+    pub fallbacks: Vec<String>,
+    pub unreadable_pages: Vec<u32>,
+}
+
+pub fn effective_decisions(cfg: &Config, report: &JobReport, sections: usize) -> Decisions {
+    let decision = &report.decision;
+    let chunks = report
+        .chunk_reports
+        .iter()
+        .map(|c| {
+            let fallbacks: Vec<String> = c
+                .warnings
+                .iter()
+                .filter(|w| w.contains("fell back") || w.contains("fallback"))
+                .cloned()
+                .collect();
+            let engine = if fallbacks.iter().any(|w| w.contains("fell back to docling")) {
+                "docling".to_string()
+            } else {
+                decision.chosen_engine.clone()
+            };
+            let mut unreadable_pages: Vec<u32> = c.page_errors.iter().map(|e| e.page).collect();
+            unreadable_pages.sort_unstable();
+            unreadable_pages.dedup();
+            ChunkDecision {
+                index: c.chunk_index,
+                start_page: c.start_page,
+                end_page: c.end_page,
+                engine,
+                pdf_backend: c.pdf_backend.clone(),
+                content_type: c.content_type.clone(),
+                fallbacks,
+                unreadable_pages,
+            }
+        })
+        .collect();
+
+    Decisions {
+        page_count: report.input.page_count,
+        tier: decision.tier,
+        engine: decision.chosen_engine.clone(),
+        do_ocr: decision.do_ocr,
+        split_spreads: decision.split_spreads,
+        tiled_pages: decision.tiled_pages.clone(),
+        pipeline_overrides: decision.pipeline_overrides.clone(),
+        on_page_error: cfg.limits.on_page_error.clone(),
+        chunks,
+        postprocess: postprocess_stages(cfg),
+        split_final_by: cfg.output.split_final_by.clone(),
+        sections,
+    }
+}
+
+/// The postprocess stages that ran, in pipeline order.
+fn postprocess_stages(cfg: &Config) -> Vec<String> {
+    let pp = &cfg.postprocess;
+    let mut stages: Vec<String> = pp
+        .chunk_stages
+        .iter()
+        .map(|s| format!("chunk_stage:{}", s.kind))
+        .collect();
+    if pp.embedded_images != "keep" {
+        stages.push(format!("embedded_images:{}", pp.embedded_images));
+    }
+    if pp.printed_page_numbers != "off" {
+        stages.push(format!("printed_page_numbers:{}", pp.printed_page_numbers));
+    }
+    if pp.normalize_newlines {
+        stages.push("normalize_newlines".into());
+    }
+    if pp.normalize_unicode {
+        stages.push("normalize_unicode".into());
+    }
+    if !pp.control_chars_to_sanitize.is_empty() {
+        stages.push("sanitize_control_chars".into());
+    }
+    if pp.trim_trailing_whitespace {
+        stages.push("trim_trailing_whitespace".into());
+    }
+    if pp.infer_chapter_titles != "off" {
+        stages.push(format!("infer_chapter_titles:{}", pp.infer_chapter_titles));
+    }
+    if pp.verse != "off" {
+        stages.push(format!("verse:{}", pp.verse));
+    }
+    if pp.remove_repeated_lines {
+        stages.push("remove_repeated_lines".into());
+    }
+    if pp.remove_by_regex {
+        stages.push(format!("remove_by_regex:{}", pp.regex.patterns.len()));
+    }
+    if pp.extract_terms != "off" {
+        stages.push(format!("extract_terms:{}", pp.extract_terms));
+    }
+    if pp.resolve_cross_references {
+        stages.push("resolve_cross_references".into());
+    }
+    if !pp.keep_page_markers {
+        stages.push("strip_page_markers".into());
+    }
+    stages
+}
+
+/// Canonical JSON: object keys sorted, two-space indent, trailing newline.
+pub fn to_canonical_json(decisions: &Decisions) -> serde_json::Result<String> {
+    // serde_json's default map is ordered by key, so a round trip through `Value`
+    // sorts every object.
+    let value = serde_json::to_value(decisions)?;
+    let mut out = serde_json::to_string_pretty(&value)?;
+    out.push('\n');
+    Ok(out)
+}
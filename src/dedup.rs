@@ -0,0 +1,66 @@
+/// A MinHash signature over word shingles; two signatures estimate the Jaccard
+/// similarity of the underlying shingle sets.
+///
+/// Hashing is FNV-1a with a fixed per-permutation seed, so signatures are stable
+/// across runs and platforms.
+pub fn signature(text: &str, shingle_words: usize, permutations: usize) -> Vec<u64> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let k = shingle_words.max(1);
+    let seeds: Vec<u64> = (0..permutations as u64).map(splitmix64).collect();
+    let mut sig = vec![u64::MAX; permutations];
+
+    if words.is_empty() {
+        return sig;
+    }
+    let windows: Vec<&[String]> = if words.len() < k {
+        vec![&words[..]]
+    } else {
+        words.windows(k).collect()
+    };
+    for shingle in windows {
+        let base = fnv1a(shingle);
+        for (slot, seed) in sig.iter_mut().zip(&seeds) {
+            let h = splitmix64(base ^ seed);
+            if h < *slot {
+                *slot = h;
+            }
+        }
+    }
+    sig
+}
+
+/// Estimated Jaccard similarity: the fraction of matching signature slots.
+pub fn similarity(a: &[u64], b: &[u64]) -> f32 {
+    let n = a.len().min(b.len());
+    if n == 0 {
+        return 0.0;
+    }
+    // Empty texts produce all-MAX signatures; don't call them duplicates of each other.
+    if a.iter().all(|&h| h == u64::MAX) || b.iter().all(|&h| h == u64::MAX) {
+        return 0.0;
+    }
+    let same = a.iter().zip(b).take(n).filter(|(x, y)| x == y).count();
+    same as f32 / n as f32
+}
+
+fn fnv1a(words: &[String]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for w in words {
+        for b in w.bytes().chain(std::iter::once(b' ')) {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    h
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,150 @@
+use crate::{blank_pages::segments, dedup, postprocess::is_marker_line};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Words per shingle and MinHash slots per page signature.
+const SHINGLE_WORDS: usize = 3;
+const PERMUTATIONS: usize = 128;
+
+/// Pages whose text repeats an earlier page, recorded under `duplicate_pages` in the
+/// report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicatePageStats {
+    /// `postprocess.duplicate_pages`.
+    pub mode: String,
+    pub ranges: Vec<DuplicateRange>,
+}
+
+impl DuplicatePageStats {
+    /// Every duplicate page, in order.
+    pub fn pages(&self) -> Vec<u32> {
+        self.ranges
+            .iter()
+            .flat_map(|r| r.start_page..=r.end_page)
+            .collect()
+    }
+}
+
+/// A run of pages repeating a run of earlier pages page for page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateRange {
+    pub start_page: u32,
+    pub end_page: u32,
+    pub original_start_page: u32,
+    pub original_end_page: u32,
+    /// Lowest estimated similarity of a page in the run to its original.
+    pub min_similarity: f32,
+}
+
+/// `<!-- duplicate of page N -->`, left in place of a dropped duplicate.
+pub fn duplicate_page_comment(original: u32) -> String {
+    format!("<!-- duplicate of page {original} -->")
+}
+
+/// Find pages in page-marked markdown whose word shingles match an earlier page of
+/// the same or the previous chunk (`chunks` holds each chunk's page range) with at
+/// least `threshold` estimated similarity, and report or drop them according to `mode`
+/// ("off" | "report" | "drop"). Only pages in `measurable` with at least `min_words`
+/// words are compared, so blank pages and bare running heads never match. A dropped
+/// page keeps its page marker, chunk markers, and `---` separators, plus a
+/// [`duplicate_page_comment`].
+pub fn handle_duplicate_pages(
+    md: &str,
+    mode: &str,
+    threshold: f32,
+    min_words: usize,
+    chunks: &[(u32, u32)],
+    measurable: &BTreeSet<u32>,
+) -> Result<(String, DuplicatePageStats)> {
+    if !matches!(mode, "off" | "report" | "drop") {
+        return Err(anyhow!("unknown postprocess.duplicate_pages: {mode}"));
+    }
+    let mut stats = DuplicatePageStats {
+        mode: mode.to_string(),
+        ranges: Vec::new(),
+    };
+    if mode == "off" {
+        return Ok((md.to_string(), stats));
+    }
+    let chunk_of = |page: u32| chunks.iter().position(|&(s, e)| (s..=e).contains(&page));
+
+    let segments = segments(md);
+    let mut signatures: Vec<(u32, Option<usize>, Vec<u64>)> = Vec::new();
+    // Duplicate page -> (original page, similarity).
+    let mut originals: BTreeMap<u32, (u32, f32)> = BTreeMap::new();
+    for segment in &segments {
+        let Some(page) = segment.page.filter(|p| measurable.contains(p)) else {
+            continue;
+        };
+        let text: Vec<&str> = segment
+            .lines
+            .iter()
+            .copied()
+            .filter(|l| !is_marker_line(l) && !l.trim_start().starts_with("<!--"))
+            .collect();
+        let text = text.join("\n");
+        if text.split_whitespace().count() < min_words {
+            continue;
+        }
+        let signature = dedup::signature(&text, SHINGLE_WORDS, PERMUTATIONS);
+        let chunk = chunk_of(page);
+        let best = signatures
+            .iter()
+            .filter(|(earlier, earlier_chunk, _)| {
+                *earlier < page
+                    && match (chunk, earlier_chunk) {
+                        (Some(c), Some(e)) => *e + 1 >= c,
+                        _ => true,
+                    }
+            })
+            .map(|(earlier, _, sig)| (*earlier, dedup::similarity(&signature, sig)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((earlier, similarity)) = best.filter(|(_, s)| *s >= threshold) {
+            // A repeat of a repeat points at the first occurrence.
+            let original = originals.get(&earlier).map_or(earlier, |(o, _)| *o);
+            originals.insert(page, (original, similarity));
+        }
+        signatures.push((page, chunk, signature));
+    }
+
+    for (&page, &(original, similarity)) in &originals {
+        match stats.ranges.last_mut() {
+            Some(run) if run.end_page + 1 == page && run.original_end_page + 1 == original => {
+                run.end_page = page;
+                run.original_end_page = original;
+                run.min_similarity = run.min_similarity.min(similarity);
+            }
+            _ => stats.ranges.push(DuplicateRange {
+                start_page: page,
+                end_page: page,
+                original_start_page: original,
+                original_end_page: original,
+                min_similarity: similarity,
+            }),
+        }
+    }
+    if mode == "report" || originals.is_empty() {
+        return Ok((md.to_string(), stats));
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    for segment in &segments {
+        let Some((original, _)) = segment.page.and_then(|p| originals.get(&p)) else {
+            out.extend(segment.lines.iter().map(|l| l.to_string()));
+            continue;
+        };
+        let mut lines = segment.lines.iter();
+        if let Some(marker) = lines.next() {
+            out.push(marker.to_string());
+        }
+        let kept = lines.filter(|l| is_marker_line(l) || l.trim() == "---");
+        let comment = duplicate_page_comment(*original);
+        for line in std::iter::once(comment).chain(kept.map(|l| l.to_string())) {
+            out.push(String::new());
+            out.push(line);
+        }
+        out.push(String::new());
+    }
+    Ok((out.join("\n"), stats))
+}
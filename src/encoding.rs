@@ -0,0 +1,42 @@
+use crate::config::{Config, OutputEncoding};
+use anyhow::{anyhow, Result};
+use unicode_normalization::UnicodeNormalization;
+
+const BOM: &str = "\u{feff}";
+
+pub fn validate(cfg: &Config) -> Result<()> {
+    let enc = &cfg.output.encoding;
+    if !matches!(enc.newline.as_str(), "lf" | "crlf") {
+        return Err(anyhow!("unknown output.encoding.newline: {}", enc.newline));
+    }
+    if !matches!(enc.normalization.as_str(), "none" | "nfc" | "nfkc") {
+        return Err(anyhow!(
+            "unknown output.encoding.normalization: {}",
+            enc.normalization
+        ));
+    }
+    Ok(())
+}
+
+/// `text` as `[output.encoding]` says to write it. Line endings are made uniform
+/// first, so text that already has some CRLFs does not end up with `\r\r\n`.
+pub fn encode(enc: &OutputEncoding, text: &str) -> String {
+    let mut out = match enc.normalization.as_str() {
+        "nfc" => text.nfc().collect::<String>(),
+        "nfkc" => text.nfkc().collect::<String>(),
+        _ => text.to_string(),
+    };
+    if enc.newline == "crlf" {
+        out = out.replace("\r\n", "\n").replace('\n', "\r\n");
+    }
+    if enc.bom && !out.starts_with(BOM) {
+        out.insert_str(0, BOM);
+    }
+    out
+}
+
+/// A final text file read back for tools that consume it (`corpus export`), with the
+/// BOM and CRLFs `[output.encoding]` may have added taken off again.
+pub fn decode(raw: &str) -> String {
+    raw.strip_prefix(BOM).unwrap_or(raw).replace("\r\n", "\n")
+}
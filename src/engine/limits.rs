@@ -0,0 +1,196 @@
+use crate::config::ChildLimits;
+use anyhow::Result;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+/// Extra seconds between the soft CPU limit (SIGXCPU) and the hard one (SIGKILL).
+#[cfg(unix)]
+const CPU_HARD_LIMIT_GRACE_SECONDS: u64 = 5;
+
+/// Apply `[security.child_limits]` to a command before it is spawned.
+///
+/// Memory is enforced as RLIMIT_AS because Linux ignores RLIMIT_RSS; address space
+/// counts virtual reservations, so leave generous headroom for model runtimes. The
+/// cgroup assignment happens in the child before exec, so the limit covers the whole
+/// Python process tree from the start.
+#[cfg(unix)]
+pub fn apply(cmd: &mut Command, limits: &ChildLimits) -> Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::process::CommandExt;
+
+    if limits.max_rss_bytes == 0
+        && limits.max_cpu_seconds == 0
+        && limits.nice == 0
+        && limits.cgroup.is_empty()
+    {
+        return Ok(());
+    }
+
+    let max_as = limits.max_rss_bytes;
+    let max_cpu = limits.max_cpu_seconds;
+    let nice = limits.nice;
+    let cgroup_procs = if limits.cgroup.is_empty() {
+        None
+    } else {
+        let path = Path::new(&limits.cgroup).join("cgroup.procs");
+        Some(CString::new(path.as_os_str().as_bytes())?)
+    };
+
+    // SAFETY: the closure only issues raw syscalls (getrlimit/setrlimit/setpriority/
+    // open/write/close) on data prepared before fork; it neither allocates nor locks.
+    unsafe {
+        cmd.pre_exec(move || {
+            if max_as > 0 {
+                set_rlimit(libc::RLIMIT_AS, max_as, max_as)?;
+            }
+            if max_cpu > 0 {
+                set_rlimit(
+                    libc::RLIMIT_CPU,
+                    max_cpu,
+                    max_cpu + CPU_HARD_LIMIT_GRACE_SECONDS,
+                )?;
+            }
+            if nice != 0 && libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if let Some(path) = &cgroup_procs {
+                let fd = libc::open(path.as_ptr(), libc::O_WRONLY);
+                if fd < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+                libc::close(fd);
+                if written != 1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+/// Lower soft/hard limits, never raising them above the inherited hard limit.
+#[cfg(unix)]
+unsafe fn set_rlimit(resource: RlimitResource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let cap = current.rlim_max;
+    let limit = libc::rlimit {
+        rlim_cur: (soft as libc::rlim_t).min(cap),
+        rlim_max: (hard as libc::rlim_t).min(cap),
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn apply(_cmd: &mut Command, limits: &ChildLimits) -> Result<()> {
+    if limits.max_rss_bytes > 0
+        || limits.max_cpu_seconds > 0
+        || limits.nice != 0
+        || !limits.cgroup.is_empty()
+    {
+        tracing::warn!("security.child_limits is only supported on Unix; ignoring");
+    }
+    Ok(())
+}
+
+/// The cgroup's `oom_kill` counter, to tell cgroup OOM kills apart from other signals.
+pub fn cgroup_oom_kills(limits: &ChildLimits) -> Option<u64> {
+    if limits.cgroup.is_empty() {
+        return None;
+    }
+    let raw = std::fs::read_to_string(Path::new(&limits.cgroup).join("memory.events")).ok()?;
+    raw.lines()
+        .find_map(|l| l.strip_prefix("oom_kill "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Which configured limits a finished child ran into, as human-readable notes.
+pub fn detect_hits(
+    limits: &ChildLimits,
+    status: &ExitStatus,
+    stderr: &str,
+    oom_kills_before: Option<u64>,
+) -> Vec<String> {
+    let mut hits = Vec::new();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        match status.signal() {
+            Some(libc::SIGXCPU) => hits.push(format!(
+                "cpu limit hit: exceeded max_cpu_seconds={}",
+                limits.max_cpu_seconds
+            )),
+            Some(libc::SIGKILL) if limits.max_cpu_seconds > 0 => {
+                let oom = matches!(
+                    (oom_kills_before, cgroup_oom_kills(limits)),
+                    (Some(before), Some(after)) if after > before
+                );
+                if !oom {
+                    hits.push(format!(
+                        "cpu limit hit: killed past max_cpu_seconds={}",
+                        limits.max_cpu_seconds
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = status;
+
+    if let (Some(before), Some(after)) = (oom_kills_before, cgroup_oom_kills(limits))
+        && after > before
+    {
+        hits.push(format!("memory limit hit: cgroup {} OOM-killed a process", limits.cgroup));
+    }
+
+    if limits.max_rss_bytes > 0
+        && ["MemoryError", "Cannot allocate memory", "std::bad_alloc"]
+            .iter()
+            .any(|needle| stderr.contains(needle))
+    {
+        hits.push(format!(
+            "memory limit hit: allocation failed under max_rss_bytes={}",
+            limits.max_rss_bytes
+        ));
+    }
+
+    hits
+}
+
+/// Whether a failed conversion looks like it ran out of memory: a Python/C++ allocation
+/// failure, a CUDA OOM, a memory-limit note from `detect_hits`, or a SIGKILL that was
+/// not a CPU limit (the kernel OOM killer leaves no other trace).
+pub fn looks_like_oom(failure: &str) -> bool {
+    let text = failure.to_ascii_lowercase();
+    let allocation = [
+        "memoryerror",
+        "cannot allocate memory",
+        "can't allocate memory",
+        "std::bad_alloc",
+        "out of memory",
+        "memory limit hit",
+    ]
+    .iter()
+    .any(|needle| text.contains(needle));
+    let killed = (text.contains("signal: 9") || text.contains("sigkill"))
+        && !text.contains("cpu limit hit");
+    allocation || killed
+}
@@ -1,16 +1,108 @@
+pub mod limits;
 pub mod python;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod sandbox;
+pub mod startup;
 pub mod types;
 
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::path::Path;
 
-pub use types::{ConvertIn, ConvertOut, DocDiag, ProbeOut, SplitChunk};
+pub use types::{
+    ConvertIn, ConvertMeta, ConvertOut, DeviceCheck, DocDiag, MissingModel, ModelCheck,
+    PageConfidence, PageError, PageLayout, PageOcr, ProbeOut, SplitChunk,
+};
 
-pub trait Engine {
+/// Boxed engines, for callers that pick an implementation at runtime.
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn doctor(&self) -> Result<DocDiag> {
+        (**self).doctor()
+    }
+    fn probe_pdf(&self, input: &Path, sample: &crate::config::Classification) -> Result<ProbeOut> {
+        (**self).probe_pdf(input, sample)
+    }
+    fn split_pdf(&self, input: &Path, out_dir: &Path, ranges: &[crate::chunk_plan::PageRange])
+        -> Result<Vec<SplitChunk>> {
+        (**self).split_pdf(input, out_dir, ranges)
+    }
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        (**self).convert_docling(req)
+    }
+    fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        (**self).convert_native_text(req)
+    }
+    fn page_layout(&self, input: &Path, pages: &[u32]) -> Result<Vec<PageLayout>> {
+        (**self).page_layout(input, pages)
+    }
+    fn strip_pdf_features(&self, input: &Path, output: &Path, features: &[String]) -> Result<()> {
+        (**self).strip_pdf_features(input, output, features)
+    }
+    fn page_hashes(&self, input: &Path) -> Result<Vec<String>> {
+        (**self).page_hashes(input)
+    }
+    fn check_models(
+        &self,
+        do_ocr: bool,
+        pipeline_overrides: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<ModelCheck> {
+        (**self).check_models(do_ocr, pipeline_overrides)
+    }
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        (**self).check_device(device)
+    }
+    fn script_versions(&self) -> BTreeMap<String, String> {
+        (**self).script_versions()
+    }
+}
+
+pub trait Engine: Sync {
     fn doctor(&self) -> Result<DocDiag>;
-    fn probe_pdf(&self, input: &Path, sample_pages: u32) -> Result<ProbeOut>;
+    fn probe_pdf(&self, input: &Path, sample: &crate::config::Classification) -> Result<ProbeOut>;
     fn split_pdf(&self, input: &Path, out_dir: &Path, ranges: &[crate::chunk_plan::PageRange])
         -> Result<Vec<SplitChunk>>;
     fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut>;
     fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut>;
+    /// Layout of the given 1-based pages. Engines without a layout pre-pass report
+    /// nothing, which leaves chunk boundaries where the plan put them.
+    fn page_layout(&self, _input: &Path, _pages: &[u32]) -> Result<Vec<PageLayout>> {
+        Ok(Vec::new())
+    }
+    /// Write a copy of `input` without the given risky features
+    /// (`security.pdf_features` set to "strip").
+    fn strip_pdf_features(&self, _input: &Path, _output: &Path, features: &[String]) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "this engine cannot strip PDF features: {}",
+            features.join(", ")
+        ))
+    }
+    /// A content hash per page, in page order, for reusing chunks of an earlier job
+    /// (`run --reuse-from`). Engines that cannot hash pages report none.
+    fn page_hashes(&self, _input: &Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+    /// Model artifacts the docling pipeline needs for a decision (`do_ocr` plus tier
+    /// overrides). Engines that do not run docling need none.
+    fn check_models(
+        &self,
+        _do_ocr: bool,
+        _pipeline_overrides: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<ModelCheck> {
+        Ok(ModelCheck::default())
+    }
+    /// Whether the accelerator `device` can run docling now. Engines that do not run
+    /// docling locally can always run.
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        Ok(DeviceCheck {
+            device: device.to_string(),
+            available: true,
+            detail: None,
+        })
+    }
+    /// A version (or content hash) per helper script the engine runs, recorded in each
+    /// chunk's provenance. Engines without scripts report none.
+    fn script_versions(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
 }
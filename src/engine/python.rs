@@ -1,49 +1,81 @@
-use super::{types::*, Engine};
-use crate::config::Config;
+use super::{
+    limits,
+    sandbox::{self, SandboxPaths},
+    startup::{self, StartupGate, StartupPermit},
+    types::*,
+    Engine,
+};
+use crate::{
+    cancel::{Interrupted, JobControl},
+    config::{Classification, Config},
+    exit_code::ErrorClass,
+    util::sha256_hex,
+};
 use anyhow::{anyhow, Context, Result};
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Output, Stdio};
+use std::process::{Child, Output, Stdio};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
+const SCRIPTS: [&str; 4] = [
+    "docling_runner.py",
+    "pdf_probe.py",
+    "pdf_split.py",
+    "pdf_text.py",
+];
+
 pub struct PythonEngine {
     cfg: Config,
     scripts_dir: PathBuf,
     python_exe: PathBuf,
+    /// Runs the pypdf scripts; `docling.python_exe` unless `native_text.python_exe` is set.
+    pypdf_python_exe: PathBuf,
+    /// Shared by the conversions of a job, which may run in parallel.
+    startup: Arc<StartupGate>,
 }
 
 impl PythonEngine {
     pub fn new(cfg: &Config) -> Result<Self> {
         let scripts_dir = PathBuf::from(&cfg.paths.scripts_dir);
         if cfg.security.pin_scripts_dir {
-            let cwd = std::env::current_dir().with_context(|| "current_dir")?;
+            // The config's directory (`paths.root`), or the working directory for a
+            // config that was not loaded from a file.
+            let root = match cfg.paths.root.as_str() {
+                "" => std::env::current_dir().with_context(|| "current_dir")?,
+                root => PathBuf::from(root)
+                    .canonicalize()
+                    .with_context(|| format!("canonicalize paths.root: {root}"))?,
+            };
             let canon = scripts_dir
                 .canonicalize()
                 .with_context(|| format!("canonicalize scripts_dir: {}", scripts_dir.display()))?;
-            if !canon.starts_with(&cwd) {
+            if !canon.starts_with(&root) {
                 return Err(anyhow!(
-                    "scripts_dir is outside cwd while pin_scripts_dir=true: {}",
+                    "scripts_dir is outside {} while pin_scripts_dir=true: {}",
+                    root.display(),
                     canon.display()
                 ));
             }
         }
-        for script in [
-            "docling_runner.py",
-            "pdf_probe.py",
-            "pdf_split.py",
-            "pdf_text.py",
-        ] {
+        for script in SCRIPTS {
             let path = scripts_dir.join(script);
             if !path.exists() {
                 return Err(anyhow!("missing script: {}", path.display()));
             }
         }
         let python_exe = resolve_python_exe(&cfg.docling.python_exe)?;
+        let pypdf_python_exe = match cfg.native_text.python_exe.trim() {
+            "" => python_exe.clone(),
+            raw => resolve_python_exe(raw)?,
+        };
         Ok(Self {
             cfg: cfg.clone(),
             scripts_dir,
             python_exe,
+            pypdf_python_exe,
+            startup: StartupGate::new(cfg),
         })
     }
 
@@ -51,20 +83,55 @@ impl PythonEngine {
         self.scripts_dir.join(name)
     }
 
+    /// The Docling runner needs the Docling venv; the probe, split, and text scripts only
+    /// need pypdf.
+    fn python_for(&self, script: &Path) -> &Path {
+        if script.file_name().is_some_and(|n| n == "docling_runner.py") {
+            &self.python_exe
+        } else {
+            &self.pypdf_python_exe
+        }
+    }
+
     fn run_json<I: serde::Serialize, O: for<'de> serde::Deserialize<'de>>(
         &self,
         script: &Path,
         input: &I,
         timeout_seconds: Option<u64>,
         extra_env: &[(&str, &str)],
+        paths: &SandboxPaths,
     ) -> Result<O> {
+        let wait = Wait {
+            timeout_seconds,
+            control: None,
+            startup: None,
+        };
+        let (out, limit_hits) =
+            self.run_json_limited(script, input, wait, extra_env, paths, None)?;
+        for hit in &limit_hits {
+            warn!("{}: {hit}", script.display());
+        }
+        Ok(out)
+    }
+
+    /// Like `run_json`, also returning warnings: which `[security.child_limits]` the
+    /// child hit, and any stray output printed around its JSON reply.
+    /// Non-empty stderr is appended to `stderr_log` when given, even if the child failed.
+    fn run_json_limited<I: serde::Serialize, O: for<'de> serde::Deserialize<'de>>(
+        &self,
+        script: &Path,
+        input: &I,
+        wait: Wait<'_>,
+        extra_env: &[(&str, &str)],
+        paths: &SandboxPaths,
+        stderr_log: Option<&Path>,
+    ) -> Result<(O, Vec<String>)> {
         debug!(
             "python run {} timeout={:?}",
             script.display(),
-            timeout_seconds
+            wait.timeout_seconds
         );
-        let mut cmd = Command::new(&self.python_exe);
-        cmd.arg(script);
+        let mut cmd = sandbox::command(&self.cfg, self.python_for(script), script, paths)?;
         cmd.stdin(Stdio::piped());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
@@ -79,6 +146,14 @@ impl PythonEngine {
             cmd.env("DOCLING_ARTIFACTS_PATH", artifacts_dir);
         }
 
+        let limits = &self.cfg.security.child_limits;
+        limits::apply(&mut cmd, limits)?;
+        let oom_kills_before = limits::cgroup_oom_kills(limits);
+
+        let permit = match wait.startup {
+            Some(gate) => Some(gate.acquire(wait.control)?),
+            None => None,
+        };
         let mut child = cmd
             .spawn()
             .with_context(|| format!("spawning python: {}", script.display()))?;
@@ -91,32 +166,132 @@ impl PythonEngine {
             stdin.flush().ok();
         }
 
-        let output = if let Some(secs) = timeout_seconds {
-            wait_with_timeout(&mut child, Duration::from_secs(secs))?
+        let (output, killed) = if wait.timeout_seconds.is_some() || wait.control.is_some() {
+            wait_with_timeout(&mut child, wait, permit)?
         } else {
-            child
+            let output = child
                 .wait_with_output()
-                .with_context(|| "waiting for python")?
+                .with_context(|| "waiting for python")?;
+            (output, None)
         };
 
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(path) = stderr_log
+            && !output.stderr.is_empty()
+            && let Err(err) = append_stderr_log(path, script, &output.status, &output.stderr)
+        {
+            warn!("could not write {}: {err:#}", path.display());
+        }
+        match killed {
+            Some(Killed::Timeout) => {
+                return Err(anyhow::Error::new(ErrorClass::Timeout).context(format!(
+                    "python process exceeded timeout ({}s); stderr: {}",
+                    wait.timeout_seconds.unwrap_or_default(),
+                    stderr
+                )));
+            }
+            Some(Killed::Interrupted(why)) => {
+                return Err(anyhow::Error::new(why).context(format!(
+                    "python process stopped: {}",
+                    script.display()
+                )));
+            }
+            None => {}
+        }
+        let mut limit_hits =
+            limits::detect_hits(limits, &output.status, &stderr, oom_kills_before);
+
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(anyhow!(
-                "python script failed: {}\n{}",
+                "python script failed: {} ({}){}\n{}",
                 script.display(),
+                output.status,
+                if limit_hits.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", limit_hits.join("; "))
+                },
                 stderr
             ));
         }
 
         if self.cfg.debug.keep_python_stderr && !output.stderr.is_empty() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
             debug!("python stderr {}: {}", script.display(), stderr.trim());
         }
 
-        let out: O = serde_json::from_slice(&output.stdout)
+        let (out, stray): (O, _) = parse_json_output(&output.stdout)
             .with_context(|| format!("parsing python JSON output: {}", script.display()))?;
-        Ok(out)
+        if let Some(stray) = stray {
+            let name = script.file_name().unwrap_or_default().to_string_lossy();
+            limit_hits.push(format!("stray stdout from {name}: {stray}"));
+        }
+        Ok((out, limit_hits))
+    }
+}
+
+/// Longest stray output quoted in a warning.
+const MAX_STRAY_CHARS: usize = 500;
+
+/// The JSON reply in a script's stdout, plus whatever else was printed around it.
+/// Libraries the scripts import sometimes print to stdout; rather than failing on the
+/// mixed output, the last JSON value that parses as `O` is taken as the reply and the
+/// rest is returned (shortened) for a warning.
+pub fn parse_json_output<O: for<'de> serde::Deserialize<'de>>(
+    stdout: &[u8],
+) -> Result<(O, Option<String>)> {
+    let first_err = match serde_json::from_slice(stdout) {
+        Ok(out) => return Ok((out, None)),
+        Err(err) => err,
+    };
+    let text = String::from_utf8_lossy(stdout);
+    let starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    // From the last line up, since the reply is printed last.
+    for start in starts.into_iter().rev() {
+        let line = &text[start..];
+        let offset = line.len() - line.trim_start().len();
+        if !line.trim_start().starts_with(['{', '[']) {
+            continue;
+        }
+        let mut values =
+            serde_json::Deserializer::from_str(&line[offset..]).into_iter::<serde_json::Value>();
+        let Some(Ok(value)) = values.next() else {
+            continue;
+        };
+        let end = start + offset + values.byte_offset();
+        let Ok(out) = O::deserialize(value) else {
+            continue;
+        };
+        let stray = format!("{} {}", text[..start].trim(), text[end..].trim());
+        let stray = stray.trim();
+        let shortened = match stray.char_indices().nth(MAX_STRAY_CHARS) {
+            Some((cut, _)) => format!("{}...", &stray[..cut]),
+            None => stray.to_string(),
+        };
+        return Ok((out, Some(shortened)));
+    }
+    Err(first_err.into())
+}
+
+fn append_stderr_log(
+    path: &Path,
+    script: &Path,
+    status: &std::process::ExitStatus,
+    stderr: &[u8],
+) -> Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let name = script.file_name().unwrap_or(script.as_os_str());
+    writeln!(file, "==> {} ({status}) <==", name.to_string_lossy())?;
+    file.write_all(stderr)?;
+    if !stderr.ends_with(b"\n") {
+        writeln!(file)?;
     }
+    Ok(())
 }
 
 fn resolve_python_exe(raw: &str) -> Result<PathBuf> {
@@ -139,10 +314,10 @@ fn resolve_python_exe(raw: &str) -> Result<PathBuf> {
 }
 
 fn expand_tilde(path: &str) -> PathBuf {
-    if let Some(rest) = path.strip_prefix("~/") {
-        if let Ok(home) = std::env::var("HOME") {
-            return PathBuf::from(home).join(rest);
-        }
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Ok(home) = std::env::var("HOME")
+    {
+        return PathBuf::from(home).join(rest);
     }
     PathBuf::from(path)
 }
@@ -161,22 +336,133 @@ impl Engine for PythonEngine {
             &serde_json::json!({"cmd":"doctor"}),
             Some(self.cfg.docling.doctor_timeout_seconds),
             &[],
+            &SandboxPaths::none(),
         )
     }
 
-    fn probe_pdf(&self, input: &Path, sample_pages: u32) -> Result<ProbeOut> {
+    fn probe_pdf(&self, input: &Path, sample: &Classification) -> Result<ProbeOut> {
         let script = self.script("pdf_probe.py");
         let req = serde_json::json!({
             "input_pdf": input,
-            "sample_pages": sample_pages,
+            "sample_pages": sample.sample_pages,
+            "sample_pages_percent": sample.sample_pages_percent,
+            "max_sample_pages": sample.max_sample_pages,
         });
-        let out: ProbeOut = self.run_json(&script, &req, Some(120), &[])?;
+        let paths = SandboxPaths::none().read(input);
+        let out: ProbeOut = self.run_json(&script, &req, Some(120), &[], &paths)?;
         if let Some(err) = out.error.as_deref() {
             return Err(anyhow!("pdf_probe error: {err}"));
         }
         Ok(out)
     }
 
+    fn check_models(
+        &self,
+        do_ocr: bool,
+        pipeline_overrides: &std::collections::BTreeMap<String, serde_json::Value>,
+    ) -> Result<ModelCheck> {
+        let script = self.script("docling_runner.py");
+        self.run_json(
+            &script,
+            &serde_json::json!({
+                "cmd": "models",
+                "req": { "do_ocr": do_ocr, "pipeline_overrides": pipeline_overrides },
+                "cfg": &self.cfg,
+            }),
+            Some(self.cfg.docling.doctor_timeout_seconds),
+            &[],
+            &SandboxPaths::none(),
+        )
+    }
+
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        let script = self.script("docling_runner.py");
+        self.run_json(
+            &script,
+            &serde_json::json!({ "cmd": "device", "req": { "device": device } }),
+            Some(self.cfg.docling.doctor_timeout_seconds),
+            &[],
+            &SandboxPaths::none(),
+        )
+    }
+
+    /// `sha256:<first 12 hex digits>` of each script, which have no version of their own.
+    fn script_versions(&self) -> std::collections::BTreeMap<String, String> {
+        SCRIPTS
+            .iter()
+            .filter_map(|name| {
+                let bytes = std::fs::read(self.script(name)).ok()?;
+                Some((name.to_string(), format!("sha256:{}", &sha256_hex(&bytes)[..12])))
+            })
+            .collect()
+    }
+
+    fn page_layout(&self, input: &Path, pages: &[u32]) -> Result<Vec<PageLayout>> {
+        #[derive(serde::Deserialize)]
+        struct LayoutOut {
+            pages: Vec<PageLayout>,
+            #[serde(default)]
+            error: Option<String>,
+        }
+        let script = self.script("pdf_probe.py");
+        let req = serde_json::json!({
+            "input_pdf": input,
+            "layout_pages": pages,
+        });
+        let paths = SandboxPaths::none().read(input);
+        let out: LayoutOut = self.run_json(&script, &req, Some(120), &[], &paths)?;
+        if let Some(err) = out.error.as_deref() {
+            return Err(anyhow!("pdf_probe layout error: {err}"));
+        }
+        Ok(out.pages)
+    }
+
+    fn page_hashes(&self, input: &Path) -> Result<Vec<String>> {
+        #[derive(serde::Deserialize)]
+        struct HashesOut {
+            pages: Vec<String>,
+            #[serde(default)]
+            error: Option<String>,
+        }
+        let script = self.script("pdf_probe.py");
+        let req = serde_json::json!({
+            "input_pdf": input,
+            "page_hashes": true,
+        });
+        let paths = SandboxPaths::none().read(input);
+        let out: HashesOut = self.run_json(&script, &req, Some(300), &[], &paths)?;
+        if let Some(err) = out.error.as_deref() {
+            return Err(anyhow!("pdf_probe page hash error: {err}"));
+        }
+        Ok(out.pages)
+    }
+
+    fn strip_pdf_features(&self, input: &Path, output: &Path, features: &[String]) -> Result<()> {
+        #[derive(serde::Deserialize)]
+        struct StripOut {
+            ok: bool,
+            #[serde(default)]
+            error: Option<String>,
+        }
+        let script = self.script("pdf_probe.py");
+        let req = serde_json::json!({
+            "input_pdf": input,
+            "out_pdf": output,
+            "strip_features": features,
+        });
+        let mut paths = SandboxPaths::none().read(input);
+        if let Some(dir) = output.parent() {
+            paths = paths.write(dir);
+        }
+        let out: StripOut = self.run_json(&script, &req, Some(300), &[], &paths)?;
+        if !out.ok {
+            return Err(anyhow!(out
+                .error
+                .unwrap_or_else(|| "pdf_probe strip failed".to_string())));
+        }
+        Ok(())
+    }
+
     fn split_pdf(
         &self,
         input: &Path,
@@ -188,8 +474,10 @@ impl Engine for PythonEngine {
             "input_pdf": input,
             "out_dir": out_dir,
             "chunks": ranges,
+            "on_page_error": self.cfg.limits.on_page_error,
         });
-        let out: SplitOut = self.run_json(&script, &req, Some(300), &[])?;
+        let paths = SandboxPaths::none().read(input).write(out_dir);
+        let out: SplitOut = self.run_json(&script, &req, Some(300), &[], &paths)?;
         if !out.ok {
             let msg = out
                 .error
@@ -206,12 +494,20 @@ impl Engine for PythonEngine {
         } else {
             None
         };
-        let out: ConvertOut = self.run_json(
+        let wait = Wait {
+            timeout_seconds: timeout,
+            control: Some(&req.control),
+            startup: Some(&self.startup),
+        };
+        let (mut out, limit_hits): (ConvertOut, _) = self.run_json_limited(
             &script,
             &serde_json::json!({"cmd":"convert","req":req, "cfg": &self.cfg}),
-            timeout,
+            wait,
             &[],
+            &convert_paths(req),
+            req.stderr_log.as_deref(),
         )?;
+        out.warnings.extend(limit_hits);
         if !out.ok {
             warn!("docling convert returned ok=false for chunk {}", req.chunk_index);
         }
@@ -225,12 +521,20 @@ impl Engine for PythonEngine {
         } else {
             None
         };
-        let out: ConvertOut = self.run_json(
+        let wait = Wait {
+            timeout_seconds: timeout,
+            control: Some(&req.control),
+            startup: None,
+        };
+        let (mut out, limit_hits): (ConvertOut, _) = self.run_json_limited(
             &script,
             &serde_json::json!({"cmd":"convert","req":req, "cfg": &self.cfg}),
-            timeout,
+            wait,
             &[],
+            &convert_paths(req),
+            req.stderr_log.as_deref(),
         )?;
+        out.warnings.extend(limit_hits);
         if !out.ok {
             warn!("native text convert returned ok=false for chunk {}", req.chunk_index);
         }
@@ -238,7 +542,35 @@ impl Engine for PythonEngine {
     }
 }
 
-fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<Output> {
+fn convert_paths(req: &ConvertIn) -> SandboxPaths {
+    SandboxPaths::none()
+        .read(&req.input_pdf)
+        .write(&req.out_dir)
+}
+
+/// When to stop waiting for a child: after its own timeout, or as soon as the job it
+/// works for is cancelled or past its deadline. With `startup`, the child is started
+/// through the gate and holds its permit until it reports its models loaded.
+#[derive(Clone, Copy)]
+struct Wait<'a> {
+    timeout_seconds: Option<u64>,
+    control: Option<&'a JobControl>,
+    startup: Option<&'a Arc<StartupGate>>,
+}
+
+/// Why `wait_with_timeout` killed the child.
+enum Killed {
+    Timeout,
+    Interrupted(Interrupted),
+}
+
+/// Wait for the child, killing it when `wait` says to stop; reports why it was killed.
+fn wait_with_timeout(
+    child: &mut Child,
+    wait: Wait<'_>,
+    mut permit: Option<StartupPermit>,
+) -> Result<(Output, Option<Killed>)> {
+    let timeout = wait.timeout_seconds.map(Duration::from_secs);
     // Drain pipes while waiting so verbose python logging can't deadlock the child
     // on a full stdout/stderr buffer.
     let stdout_reader = child.stdout.take();
@@ -252,10 +584,25 @@ fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<Output> {
         Ok(buf)
     });
 
+    // Line by line, to release the startup permit as soon as the models are loaded.
     let stderr_thread = std::thread::spawn(move || -> Result<Vec<u8>> {
         let mut buf = Vec::new();
-        if let Some(mut err) = stderr_reader {
-            err.read_to_end(&mut buf).with_context(|| "read stderr")?;
+        if let Some(err) = stderr_reader {
+            let mut err = BufReader::new(err);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                if err.read_until(b'\n', &mut line).with_context(|| "read stderr")? == 0 {
+                    break;
+                }
+                if line.trim_ascii() == startup::READY_MARKER.as_bytes() {
+                    if let Some(permit) = permit.as_mut() {
+                        permit.release();
+                    }
+                    continue;
+                }
+                buf.extend_from_slice(&line);
+            }
         }
         Ok(buf)
     });
@@ -269,15 +616,27 @@ fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<Output> {
             let stderr = stderr_thread
                 .join()
                 .map_err(|_| anyhow!("stderr reader thread panicked"))??;
-            return Ok(Output {
-                status,
-                stdout,
-                stderr,
-            });
+            return Ok((
+                Output {
+                    status,
+                    stdout,
+                    stderr,
+                },
+                None,
+            ));
         }
 
-        if start.elapsed() > timeout {
-            warn!("python process timed out after {:?}", timeout);
+        let killed = match timeout {
+            Some(timeout) if start.elapsed() > timeout => {
+                warn!("python process timed out after {:?}", timeout);
+                Some(Killed::Timeout)
+            }
+            _ => wait
+                .control
+                .and_then(|c| c.check().err())
+                .map(Killed::Interrupted),
+        };
+        if let Some(killed) = killed {
             let _ = child.kill();
             let status = child.wait().with_context(|| "wait after kill")?;
             let stdout = stdout_thread
@@ -286,15 +645,13 @@ fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<Output> {
             let stderr = stderr_thread
                 .join()
                 .map_err(|_| anyhow!("stderr reader thread panicked"))??;
-            let output = Output {
-                status,
-                stdout,
-                stderr,
-            };
-            return Err(anyhow!(
-                "python process exceeded timeout ({:?}); stderr: {}",
-                timeout,
-                String::from_utf8_lossy(&output.stderr)
+            return Ok((
+                Output {
+                    status,
+                    stdout,
+                    stderr,
+                },
+                Some(killed),
             ));
         }
 
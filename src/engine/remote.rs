@@ -0,0 +1,382 @@
+use super::{types::*, Engine};
+use crate::{
+    config::{Classification, Config},
+    exit_code::ErrorClass,
+    util::sha256_hex,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Name recorded in `meta.engine` (and chunk provenance) for remote conversions.
+pub const REMOTE_ENGINE_NAME: &str = "docling_remote";
+
+/// Sends docling conversions to a docling-serve instance (`[engine.remote]`): the chunk
+/// PDF is uploaded as a multipart form, the task is polled until it finishes, and its
+/// markdown is fetched. Probing, splitting, native text and everything after conversion
+/// stay with `local`.
+pub struct RemoteEngine<E: Engine> {
+    local: E,
+    cfg: Config,
+    base_url: String,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+}
+
+#[derive(Deserialize)]
+struct TaskStatus {
+    task_id: String,
+    task_status: String,
+}
+
+#[derive(Deserialize)]
+struct ConvertResult {
+    #[serde(default)]
+    document: Option<ResultDocument>,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    errors: Vec<ResultError>,
+    #[serde(default)]
+    processing_time: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ResultDocument {
+    #[serde(default)]
+    md_content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ResultError {
+    #[serde(default)]
+    component_type: String,
+    #[serde(default)]
+    error_message: String,
+}
+
+impl<E: Engine> RemoteEngine<E> {
+    /// Requires `docling.pipeline.enable_remote_services = true` and an http(s) URL. The
+    /// API key is read from `engine.remote.api_key_env` once, here.
+    pub fn new(cfg: &Config, local: E) -> Result<Self> {
+        let remote = &cfg.engine.remote;
+        if !cfg.docling.pipeline.enable_remote_services {
+            return Err(anyhow!(
+                "engine.remote is enabled, but docling.pipeline.enable_remote_services = false"
+            ));
+        }
+        let base_url = remote.url.trim().trim_end_matches('/').to_string();
+        if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+            return Err(anyhow!(
+                "engine.remote.url must be an http:// or https:// URL, got {:?}",
+                remote.url
+            ));
+        }
+        let api_key = std::env::var(&remote.api_key_env)
+            .ok()
+            .filter(|k| !k.is_empty());
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_connect(Some(Duration::from_secs(30)))
+            .build()
+            .new_agent();
+        Ok(Self {
+            local,
+            cfg: cfg.clone(),
+            base_url,
+            api_key,
+            agent,
+        })
+    }
+
+    fn deadline_passed(&self, started: Instant) -> bool {
+        let timeout = self.cfg.engine.remote.timeout_seconds;
+        timeout > 0 && started.elapsed().as_secs() >= timeout
+    }
+
+    fn get(&self, path: &str) -> Result<String> {
+        let mut req = self.agent.get(format!("{}{path}", self.base_url));
+        if let Some(key) = &self.api_key {
+            req = req.header(&self.cfg.engine.remote.api_key_header, key);
+        }
+        let resp = req.call().with_context(|| format!("GET {path}"))?;
+        self.body(resp, "GET", path)
+    }
+
+    fn post_form(&self, path: &str, boundary: &str, body: &[u8]) -> Result<String> {
+        let mut req = self.agent.post(format!("{}{path}", self.base_url)).header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={boundary}"),
+        );
+        if let Some(key) = &self.api_key {
+            req = req.header(&self.cfg.engine.remote.api_key_header, key);
+        }
+        let resp = req.send(body).with_context(|| format!("POST {path}"))?;
+        self.body(resp, "POST", path)
+    }
+
+    fn body(
+        &self,
+        mut resp: ureq::http::Response<ureq::Body>,
+        method: &str,
+        path: &str,
+    ) -> Result<String> {
+        let status = resp.status().as_u16();
+        let body = resp
+            .body_mut()
+            .with_config()
+            .limit(u64::MAX)
+            .read_to_string()
+            .with_context(|| format!("reading {method} {path} response"))?;
+        match status {
+            200..=299 => Ok(body),
+            401 | 403 => Err(anyhow!(
+                "docling-serve rejected {method} {path} ({status}); check the API key in ${}",
+                self.cfg.engine.remote.api_key_env
+            )),
+            _ => Err(anyhow!(
+                "docling-serve returned {status} for {method} {path}: {}",
+                body.chars().take(500).collect::<String>()
+            )),
+        }
+    }
+}
+
+impl<E: Engine> Engine for RemoteEngine<E> {
+    fn doctor(&self) -> Result<DocDiag> {
+        self.local.doctor()
+    }
+
+    fn probe_pdf(&self, input: &Path, sample: &Classification) -> Result<ProbeOut> {
+        self.local.probe_pdf(input, sample)
+    }
+
+    fn split_pdf(
+        &self,
+        input: &Path,
+        out_dir: &Path,
+        ranges: &[crate::chunk_plan::PageRange],
+    ) -> Result<Vec<SplitChunk>> {
+        self.local.split_pdf(input, out_dir, ranges)
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let started = Instant::now();
+        let input = Path::new(&req.input_pdf);
+        let pdf = std::fs::read(input).with_context(|| format!("reading {}", input.display()))?;
+        let file_name = input
+            .file_name()
+            .map_or("chunk.pdf".into(), |n| n.to_string_lossy().into_owned());
+        // Derived from the content, so the boundary cannot occur inside it.
+        let boundary = format!("quack-check-{}", &sha256_hex(&pdf)[..32]);
+        let body = multipart_body(&boundary, &form_fields(&self.cfg, req), &file_name, &pdf);
+
+        let submitted: TaskStatus =
+            serde_json::from_str(&self.post_form("/v1/convert/file/async", &boundary, &body)?)
+                .context("parsing docling-serve task")?;
+        let task_id = submitted.task_id;
+        debug!(
+            "chunk {} submitted to docling-serve as task {task_id}",
+            req.chunk_index
+        );
+
+        let mut status = submitted.task_status;
+        while !matches!(status.as_str(), "success" | "failure") {
+            // docling-serve has no way to abort a task; it is left to finish unread.
+            req.control.check().with_context(|| {
+                format!("docling-serve task {task_id} for chunk {}", req.chunk_index)
+            })?;
+            if self.deadline_passed(started) {
+                return Err(anyhow::Error::new(ErrorClass::Timeout).context(format!(
+                    "docling-serve task {task_id} for chunk {} ran past {}s (status {status})",
+                    req.chunk_index,
+                    self.cfg.engine.remote.timeout_seconds
+                )));
+            }
+            std::thread::sleep(Duration::from_secs(
+                self.cfg.engine.remote.poll_interval_seconds,
+            ));
+            let polled: TaskStatus =
+                serde_json::from_str(&self.get(&format!("/v1/status/poll/{task_id}"))?)
+                    .context("parsing docling-serve task status")?;
+            status = polled.task_status;
+        }
+
+        let result: ConvertResult =
+            serde_json::from_str(&self.get(&format!("/v1/result/{task_id}"))?)
+                .context("parsing docling-serve result")?;
+        let mut warnings: Vec<String> = result
+            .errors
+            .iter()
+            .map(|e| format!("docling-serve {}: {}", e.component_type, e.error_message))
+            .collect();
+        if result.status == "partial_success" {
+            warnings.push("docling-serve reported partial_success".into());
+        }
+        let markdown = result.document.and_then(|d| d.md_content);
+        let ok = status == "success" && result.status != "failure" && markdown.is_some();
+        if !ok {
+            warn!(
+                "docling-serve task {task_id} failed for chunk {} (status {})",
+                req.chunk_index, result.status
+            );
+        }
+        let mut timings = BTreeMap::new();
+        if let Some(seconds) = result.processing_time {
+            timings.insert("convert".to_string(), seconds);
+        }
+        timings.insert("remote_total".to_string(), started.elapsed().as_secs_f64());
+        Ok(ConvertOut {
+            ok,
+            markdown: markdown.unwrap_or_default(),
+            warnings,
+            meta: ConvertMeta {
+                engine: Some(REMOTE_ENGINE_NAME.to_string()),
+                chunk_index: Some(req.chunk_index),
+                start_page: Some(req.start_page),
+                end_page: Some(req.end_page),
+                use_page_range: Some(req.use_page_range),
+                pdf_backend: Some(req.pdf_backend.clone()),
+                timings,
+                pipeline_overrides: req.pipeline_overrides.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.local.convert_native_text(req)
+    }
+
+    fn page_layout(&self, input: &Path, pages: &[u32]) -> Result<Vec<PageLayout>> {
+        self.local.page_layout(input, pages)
+    }
+
+    fn strip_pdf_features(&self, input: &Path, output: &Path, features: &[String]) -> Result<()> {
+        self.local.strip_pdf_features(input, output, features)
+    }
+
+    fn page_hashes(&self, input: &Path) -> Result<Vec<String>> {
+        self.local.page_hashes(input)
+    }
+
+    /// The models live on the server, so there is nothing to check locally.
+    fn check_models(
+        &self,
+        _do_ocr: bool,
+        _pipeline_overrides: &BTreeMap<String, Value>,
+    ) -> Result<ModelCheck> {
+        Ok(ModelCheck::default())
+    }
+}
+
+/// docling-serve form options for a chunk: `[docling.pipeline]` and `[docling.ocr]`
+/// with the tier's overrides applied, the chunk's page range when the whole input is
+/// uploaded, and the page-break placeholder the pipeline turns into page markers.
+pub fn form_fields(cfg: &Config, req: &ConvertIn) -> Vec<(String, String)> {
+    let mut pipeline = to_map(&cfg.docling.pipeline);
+    let mut ocr = to_map(&cfg.docling.ocr);
+    for (key, value) in &req.pipeline_overrides {
+        match key.strip_prefix("ocr.") {
+            Some(key) => ocr.insert(key.to_string(), value.clone()),
+            None => pipeline.insert(key.clone(), value.clone()),
+        };
+    }
+
+    let mut fields: Vec<(String, String)> = vec![
+        ("to_formats".into(), "md".into()),
+        ("image_export_mode".into(), "placeholder".into()),
+        ("do_ocr".into(), req.do_ocr.to_string()),
+        ("pdf_backend".into(), req.pdf_backend.clone()),
+        (
+            "md_page_break_placeholder".into(),
+            req.page_break_placeholder.clone(),
+        ),
+    ];
+    if pipeline.get("generate_picture_images") == Some(&Value::Bool(true)) {
+        fields[1].1 = "embedded".into();
+    }
+    if req.use_page_range {
+        fields.push(("page_range".into(), req.start_page.to_string()));
+        fields.push(("page_range".into(), req.end_page.to_string()));
+    }
+    for key in [
+        "do_table_structure",
+        "do_code_enrichment",
+        "do_formula_enrichment",
+        "do_picture_classification",
+        "do_picture_description",
+        "images_scale",
+        "table_mode",
+    ] {
+        if let Some(value) = pipeline.get(key) {
+            fields.push((key.into(), form_value(value)));
+        }
+    }
+    if let Some(seconds) = pipeline
+        .get("document_timeout_seconds")
+        .and_then(Value::as_u64)
+        .filter(|s| *s > 0)
+    {
+        fields.push(("document_timeout".into(), seconds.to_string()));
+    }
+    if let Some(engine) = ocr.get("engine") {
+        fields.push(("ocr_engine".into(), form_value(engine)));
+    }
+    if let Some(Value::Array(langs)) = ocr.get("langs") {
+        fields.extend(langs.iter().map(|l| ("ocr_lang".into(), form_value(l))));
+    }
+    // docling-serve OCRs only bitmap areas unless forced, which is what hybrid OCR wants.
+    let hybrid = ocr.get("hybrid") == Some(&Value::Bool(true));
+    let force = !hybrid
+        && ["force_ocr", "force_full_page_ocr"]
+            .iter()
+            .any(|k| ocr.get(*k) == Some(&Value::Bool(true)));
+    fields.push(("force_ocr".into(), force.to_string()));
+    fields
+}
+
+fn to_map<T: serde::Serialize>(value: &T) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+fn form_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn multipart_body(
+    boundary: &str,
+    fields: &[(String, String)],
+    file_name: &str,
+    pdf: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(pdf.len() + 4096);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    let file_name = file_name.replace(['"', '\r', '\n'], "_");
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"files\"; filename=\"{file_name}\"\r\nContent-Type: application/pdf\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(pdf);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    body
+}
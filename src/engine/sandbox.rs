@@ -0,0 +1,171 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// System locations every sandboxed Python needs to see (read-only, skipped if absent).
+const SYSTEM_RO_PATHS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc"];
+
+/// Paths one Python invocation is allowed to touch, beyond the system/runtime defaults.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxPaths {
+    pub read_only: Vec<PathBuf>,
+    pub writable: Vec<PathBuf>,
+}
+
+impl SandboxPaths {
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn read(mut self, path: impl Into<PathBuf>) -> Self {
+        self.read_only.push(path.into());
+        self
+    }
+
+    pub fn write(mut self, path: impl Into<PathBuf>) -> Self {
+        self.writable.push(path.into());
+        self
+    }
+}
+
+/// Build the command that runs `python script`, wrapped per `security.sandbox.mode`.
+pub fn command(
+    cfg: &Config,
+    python_exe: &Path,
+    script: &Path,
+    paths: &SandboxPaths,
+) -> Result<Command> {
+    let sandbox = &cfg.security.sandbox;
+    match sandbox.mode.as_str() {
+        "off" => {
+            let mut cmd = Command::new(python_exe);
+            cmd.arg(script);
+            Ok(cmd)
+        }
+        "bwrap" => {
+            let mut cmd = Command::new(&sandbox.bwrap_exe);
+            cmd.args(bwrap_args(cfg, python_exe, script, paths)?);
+            Ok(cmd)
+        }
+        "wrapper" => {
+            let (program, prefix) = sandbox.wrapper.split_first().ok_or_else(|| {
+                anyhow!("security.sandbox.mode = \"wrapper\" needs a wrapper command")
+            })?;
+            let (ro, rw) = resolved_paths(cfg, python_exe, script, paths)?;
+            let mut cmd = Command::new(program);
+            cmd.args(prefix);
+            cmd.arg(python_exe);
+            cmd.arg(absolute(script)?);
+            cmd.env("QUACK_SANDBOX_RO", std::env::join_paths(&ro)?);
+            cmd.env("QUACK_SANDBOX_RW", std::env::join_paths(&rw)?);
+            cmd.env(
+                "QUACK_SANDBOX_NET",
+                if cfg.global.offline_only { "0" } else { "1" },
+            );
+            Ok(cmd)
+        }
+        other => Err(anyhow!("unknown security.sandbox.mode: {other}")),
+    }
+}
+
+/// bubblewrap arguments: a fresh namespace with only the system dirs, the Python
+/// runtime, scripts, artifacts, work dir and call inputs mounted read-only, the call's
+/// output dirs writable, a private /tmp, and no network when `offline_only = true`.
+pub fn bwrap_args(
+    cfg: &Config,
+    python_exe: &Path,
+    script: &Path,
+    paths: &SandboxPaths,
+) -> Result<Vec<OsString>> {
+    let (ro, rw) = resolved_paths(cfg, python_exe, script, paths)?;
+    let cwd = std::env::current_dir().with_context(|| "current_dir")?;
+
+    let mut args: Vec<OsString> = ["--die-with-parent", "--new-session", "--unshare-all"]
+        .map(OsString::from)
+        .to_vec();
+    if !cfg.global.offline_only {
+        args.push("--share-net".into());
+    }
+    for p in SYSTEM_RO_PATHS {
+        args.extend(["--ro-bind-try", p, p].map(OsString::from));
+    }
+    args.extend(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"].map(OsString::from));
+    // Keep the working directory so relative paths in requests still resolve; only
+    // the paths bound below are visible inside it.
+    args.extend(["--dir".into(), OsString::from(&cwd)]);
+    // Mount parents before children so a nested bind is not hidden by its parent.
+    let mut binds: Vec<(&PathBuf, &str)> = ro
+        .iter()
+        .filter(|p| !rw.contains(p))
+        .map(|p| (p, "--ro-bind-try"))
+        .chain(rw.iter().map(|p| (p, "--bind")))
+        .collect();
+    binds.sort();
+    for (p, flag) in binds {
+        args.extend([flag.into(), OsString::from(p), OsString::from(p)]);
+    }
+    args.extend([
+        "--chdir".into(),
+        OsString::from(&cwd),
+        "--".into(),
+        OsString::from(python_exe),
+        OsString::from(absolute(script)?),
+    ]);
+    Ok(args)
+}
+
+fn resolved_paths(
+    cfg: &Config,
+    python_exe: &Path,
+    script: &Path,
+    paths: &SandboxPaths,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut ro = Vec::new();
+    // A venv interpreter lives at <venv>/bin/python; expose the whole venv.
+    if python_exe.is_absolute()
+        && let Some(prefix) = python_exe.parent().and_then(Path::parent)
+    {
+        ro.push(prefix.to_path_buf());
+    }
+    if let Some(dir) = absolute(script)?.parent() {
+        ro.push(dir.to_path_buf());
+    }
+    for p in [&cfg.paths.docling_artifacts_dir, &cfg.paths.work_dir] {
+        if !p.is_empty() {
+            ro.push(absolute(Path::new(p))?);
+        }
+    }
+    if let Ok(hf_home) = std::env::var("HF_HOME") {
+        ro.push(PathBuf::from(hf_home));
+    } else if let Ok(home) = std::env::var("HOME") {
+        ro.push(Path::new(&home).join(".cache").join("huggingface"));
+    }
+    for p in &cfg.security.sandbox.extra_ro_paths {
+        ro.push(absolute(Path::new(p))?);
+    }
+    for p in &paths.read_only {
+        ro.push(absolute(p)?);
+    }
+
+    let mut rw = Vec::new();
+    for p in &paths.writable {
+        rw.push(absolute(p)?);
+    }
+    ro.sort();
+    ro.dedup();
+    rw.sort();
+    rw.dedup();
+    Ok((ro, rw))
+}
+
+fn absolute(p: &Path) -> Result<PathBuf> {
+    if p.is_absolute() {
+        Ok(p.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()
+            .with_context(|| "current_dir")?
+            .join(p))
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{cancel::JobControl, config::Config};
+use anyhow::Result;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Line `docling_runner.py` prints to stderr once its models are loaded.
+pub const READY_MARKER: &str = "quack:models-ready";
+
+/// How often a waiting startup checks whether its job was cancelled.
+const POLL: Duration = Duration::from_millis(100);
+
+/// Spaces out and limits docling process startups, so chunks converted in parallel do
+/// not all load their models at once. A [`StartupPermit`] is held from starting a
+/// process until it reports [`READY_MARKER`] or exits; `docling.max_concurrent_startups`
+/// bounds how many are held at once, and `docling.startup_stagger_seconds` is the least
+/// time between two startups.
+#[derive(Debug)]
+pub struct StartupGate {
+    max_concurrent: usize,
+    stagger: Duration,
+    state: Mutex<GateState>,
+    released: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    starting: usize,
+    last_start: Option<Instant>,
+}
+
+impl StartupGate {
+    pub fn new(cfg: &Config) -> Arc<Self> {
+        Arc::new(Self {
+            max_concurrent: cfg.docling.max_concurrent_startups,
+            stagger: Duration::from_secs_f64(cfg.docling.startup_stagger_seconds.max(0.0)),
+            state: Mutex::new(GateState::default()),
+            released: Condvar::new(),
+        })
+    }
+
+    /// Wait for a startup slot, then until the stagger since the previous startup has
+    /// passed. Stops waiting with an error when `control` is cancelled or past its
+    /// deadline.
+    pub fn acquire(self: &Arc<Self>, control: Option<&JobControl>) -> Result<StartupPermit> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while self.max_concurrent > 0 && state.starting >= self.max_concurrent {
+            if let Some(control) = control {
+                control.check()?;
+            }
+            state = self
+                .released
+                .wait_timeout(state, POLL)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        let now = Instant::now();
+        // Claim the next start time before sleeping, so startups queue up behind it.
+        let start = match state.last_start {
+            Some(last) if !self.stagger.is_zero() => (last + self.stagger).max(now),
+            _ => now,
+        };
+        state.last_start = Some(start);
+        state.starting += 1;
+        drop(state);
+        let permit = StartupPermit {
+            gate: Some(self.clone()),
+        };
+
+        while Instant::now() < start {
+            if let Some(control) = control {
+                control.check()?;
+            }
+            std::thread::sleep(POLL.min(start.saturating_duration_since(Instant::now())));
+        }
+        if start > now {
+            debug!("docling startup staggered by {:?}", start - now);
+        }
+        Ok(permit)
+    }
+
+    /// Startups holding a permit.
+    pub fn starting(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .starting
+    }
+}
+
+/// A docling process still loading its models; released on drop.
+#[derive(Debug)]
+pub struct StartupPermit {
+    gate: Option<Arc<StartupGate>>,
+}
+
+impl StartupPermit {
+    pub fn release(&mut self) {
+        if let Some(gate) = self.gate.take() {
+            let mut state = gate.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.starting -= 1;
+            gate.released.notify_one();
+        }
+    }
+}
+
+impl Drop for StartupPermit {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
@@ -1,4 +1,6 @@
+use crate::{probe::ProbeStructure, tiles::PageTiles};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocDiag {
@@ -18,9 +20,51 @@ pub struct ProbeOut {
     pub garbage_ratio: f32,
     pub whitespace_ratio: f32,
     #[serde(default)]
+    pub structure: ProbeStructure,
+    /// The start of each sampled page's text, for scoring it with [`crate::textstats`].
+    /// Older runners do not send it; their `garbage_ratio` is used as is.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sample_texts: Vec<String>,
+    #[serde(default)]
     pub error: Option<String>,
 }
 
+/// Whether tabular lines run into a page's top or bottom edge, from the layout pre-pass
+/// used to keep chunk seams out of tables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageLayout {
+    pub page: u32,
+    pub table_at_top: bool,
+    pub table_at_bottom: bool,
+}
+
+/// Models a docling pipeline needs and which of them are not on disk.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCheck {
+    pub required: Vec<String>,
+    pub missing: Vec<MissingModel>,
+    #[serde(default)]
+    pub searched: Vec<String>,
+    #[serde(default)]
+    pub prefetch_command: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingModel {
+    pub name: String,
+    pub detail: String,
+}
+
+/// Whether an accelerator device can run docling right now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceCheck {
+    pub device: String,
+    pub available: bool,
+    /// Why it is unavailable (driver error, out of memory, not built in).
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConvertIn {
     pub input_pdf: String,
@@ -30,7 +74,38 @@ pub struct ConvertIn {
     pub end_page: u32,
     pub do_ocr: bool,
     pub pdf_backend: String,
+    /// Fail instead of silently using docling's default when `pdf_backend` is not
+    /// available, so the next backend in the fallback chain gets a turn.
+    #[serde(default)]
+    pub require_pdf_backend: bool,
     pub use_page_range: bool,
+    pub page_break_placeholder: String,
+    /// Convert each page as its left and right halves, with
+    /// `postprocess::HALF_PAGE_BREAK_PLACEHOLDER` between them. Only docling splits.
+    #[serde(default)]
+    pub split_spreads: bool,
+    /// This chunk's pages to cut into tiles, each tile converted as a page and opened
+    /// with a `postprocess::tile_marker`. Only docling tiles.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<PageTiles>,
+    pub on_page_error: String,
+    /// Tier-specific replacements for `[docling.pipeline]` keys (`ocr.`-prefixed keys
+    /// target `[docling.ocr]`, `accelerator.`-prefixed keys `[docling.accelerator]`),
+    /// applied by the runner before building pipeline options.
+    #[serde(default)]
+    pub pipeline_overrides: std::collections::BTreeMap<String, serde_json::Value>,
+    /// Where the engine appends the child's stderr (`debug.keep_python_stderr`); not
+    /// sent to Python.
+    #[serde(skip)]
+    pub stderr_log: Option<std::path::PathBuf>,
+    /// The job's cancellation token and deadline; engines stop the conversion when it
+    /// trips. Not sent to Python.
+    #[serde(skip)]
+    pub control: crate::cancel::JobControl,
+    /// Failed tries at this chunk so far, across backend fallbacks and out-of-memory
+    /// halves. Not sent to Python.
+    #[serde(skip)]
+    pub attempts: crate::attempts::AttemptLog,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,7 +113,99 @@ pub struct ConvertOut {
     pub ok: bool,
     pub markdown: String,
     pub warnings: Vec<String>,
-    pub meta: serde_json::Value,
+    #[serde(default)]
+    pub meta: ConvertMeta,
+}
+
+/// What an engine reports about a conversion besides the markdown. Every field is
+/// optional on the wire: older runners and the native-text engine send only some of
+/// them, and keys this build does not know about are kept in `extra` and written back
+/// out unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConvertMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_page: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_page: Option<u32>,
+    /// Whether the engine converted `start_page..=end_page` of the input it was given;
+    /// page errors are then absolute rather than relative to the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_page_range: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_backend: Option<String>,
+    /// Pages present in the converted document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages_processed: Option<u32>,
+    /// Lowest and highest page number in the converted document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_span: Option<[u32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_applied: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_count: Option<u32>,
+    /// Document elements by kind (`texts`, `tables`, `pictures`, ...).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub element_counts: BTreeMap<String, u32>,
+    /// Seconds spent per step (`convert`, `export`, ...).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub timings: BTreeMap<String, f64>,
+    /// Pipeline options the installed docling accepted and ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pipeline_overrides: BTreeMap<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub page_errors: Vec<PageError>,
+    /// Docling's per-page confidence, numbered like `page_errors`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub page_confidence: Vec<PageConfidence>,
+    /// Where each page's text came from under `docling.ocr.hybrid`, numbered like
+    /// `page_errors`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub page_ocr: Vec<PageOcr>,
+    /// The earlier job this chunk was taken from unchanged (`run --reuse-from`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reused_from: Option<String>,
+    /// The retry with more OCR languages (`docling.ocr_retry`), when one ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_retry: Option<crate::ocr_retry::OcrLangRetry>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Docling's 0-1 confidence scores for one page; each is absent when docling did not
+/// compute it (no OCR ran, or an older docling).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageConfidence {
+    pub page: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean_score: Option<f32>,
+}
+
+/// How hybrid OCR read one page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageOcr {
+    pub page: u32,
+    /// "text" (text layer only), "ocr" (OCR only), "mixed", or "empty".
+    pub mode: String,
+}
+
+impl ConvertMeta {
+    pub fn ignored(&self, flag: &str) -> bool {
+        self.ignored_flags.iter().any(|f| f == flag)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +214,16 @@ pub struct SplitChunk {
     pub start_page: u32,
     pub end_page: u32,
     pub path: String,
+    #[serde(default)]
+    pub page_errors: Vec<PageError>,
+}
+
+/// A single page an engine could not read. `page` is 1-based; split outputs report
+/// absolute pages, convert outputs report pages relative to the PDF the engine opened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageError {
+    pub page: u32,
+    pub reason: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,103 @@
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Pages left out of a job, as recorded in the job manifest and report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageExclusion {
+    pub start_page: u32,
+    pub end_page: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+}
+
+impl PageExclusion {
+    pub fn pages(&self) -> u32 {
+        self.end_page - self.start_page + 1
+    }
+}
+
+/// Parse `input.exclusions.ranges` against a document of `page_count` pages: sorted,
+/// with overlapping and adjacent ranges merged. Ranges past the end are clipped or
+/// dropped with a warning, since one config serves documents of every length.
+pub fn resolve(cfg: &Config, page_count: u32) -> Result<Vec<PageExclusion>> {
+    let mut ranges = Vec::new();
+    for range in &cfg.input.exclusions.ranges {
+        let (start_page, end_page) = parse_pages(&range.pages)?;
+        if start_page > page_count {
+            warn!(
+                "input.exclusions range {} is past the last page ({page_count}); ignoring it",
+                range.pages
+            );
+            continue;
+        }
+        ranges.push(PageExclusion {
+            start_page,
+            end_page: end_page.min(page_count),
+            reason: range.reason.trim().to_string(),
+        });
+    }
+    ranges.sort_by_key(|r| (r.start_page, r.end_page));
+
+    let mut merged: Vec<PageExclusion> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start_page <= last.end_page + 1 => {
+                last.end_page = last.end_page.max(range.end_page);
+                if !range.reason.is_empty() && !last.reason.contains(&range.reason) {
+                    if !last.reason.is_empty() {
+                        last.reason.push_str("; ");
+                    }
+                    last.reason.push_str(&range.reason);
+                }
+            }
+            _ => merged.push(range),
+        }
+    }
+    let excluded: u32 = merged.iter().map(PageExclusion::pages).sum();
+    if page_count > 0 && excluded >= page_count {
+        return Err(anyhow!(
+            "input.exclusions exclude every page of the input ({page_count})"
+        ));
+    }
+    Ok(merged)
+}
+
+/// The runs of pages in 1..=`page_count` that are not excluded.
+pub fn kept_runs(page_count: u32, excluded: &[PageExclusion]) -> Vec<(u32, u32)> {
+    let mut runs = Vec::new();
+    let mut next = 1;
+    for range in excluded {
+        if range.start_page > next {
+            runs.push((next, range.start_page - 1));
+        }
+        next = next.max(range.end_page + 1);
+    }
+    if next <= page_count {
+        runs.push((next, page_count));
+    }
+    runs
+}
+
+pub fn excluded_range(excluded: &[PageExclusion], page: u32) -> Option<&PageExclusion> {
+    excluded
+        .iter()
+        .find(|r| (r.start_page..=r.end_page).contains(&page))
+}
+
+/// `"12"` or `"201-240"`, 1-based and inclusive.
+fn parse_pages(pages: &str) -> Result<(u32, u32)> {
+    let invalid =
+        || anyhow!("invalid input.exclusions pages: {pages:?} (expected \"N\" or \"N-M\")");
+    let (start, end) = match pages.split_once('-') {
+        Some((a, b)) => (a.trim(), b.trim()),
+        None => (pages.trim(), pages.trim()),
+    };
+    let start: u32 = start.parse().map_err(|_| invalid())?;
+    let end: u32 = end.parse().map_err(|_| invalid())?;
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
@@ -0,0 +1,97 @@
+use crate::cancel::{self, Interrupted};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What kind of failure ended a command. Errors carry their class as context (or, for
+/// timeouts and lock contention, as their root cause); [`classify`] finds it, and the
+/// binary exits with [`ErrorClass::code`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Anything not classified below.
+    Other,
+    /// The config did not load, resolve, or validate.
+    Config,
+    /// The input is missing, not a PDF, or could not be decompressed or hashed.
+    Input,
+    /// The PDF could not be probed.
+    Probe,
+    /// A chunk failed to convert.
+    Conversion,
+    /// A conversion ran past its timeout, or the job past its deadline.
+    Timeout,
+    /// Another run holds the input's lock and `global.on_input_locked = "fail"`.
+    Locked,
+    /// The job directory, final outputs, index, or mirrors could not be written.
+    Output,
+    /// The job was cancelled.
+    Cancelled,
+}
+
+impl ErrorClass {
+    pub const ALL: [ErrorClass; 9] = [
+        ErrorClass::Other,
+        ErrorClass::Config,
+        ErrorClass::Input,
+        ErrorClass::Probe,
+        ErrorClass::Conversion,
+        ErrorClass::Timeout,
+        ErrorClass::Locked,
+        ErrorClass::Output,
+        ErrorClass::Cancelled,
+    ];
+
+    /// The process exit code. 2 is also what clap exits with on a command-line usage
+    /// error, and 130 what a shell reports for a process stopped by Ctrl-C.
+    pub fn code(self) -> i32 {
+        match self {
+            ErrorClass::Other => 1,
+            ErrorClass::Config => 2,
+            ErrorClass::Input => 3,
+            ErrorClass::Probe => 4,
+            ErrorClass::Conversion => 5,
+            ErrorClass::Timeout => 6,
+            ErrorClass::Locked => 7,
+            ErrorClass::Output => 8,
+            ErrorClass::Cancelled => 130,
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            ErrorClass::Other => "failed",
+            ErrorClass::Config => "invalid configuration",
+            ErrorClass::Input => "invalid input",
+            ErrorClass::Probe => "probe failed",
+            ErrorClass::Conversion => "conversion failed",
+            ErrorClass::Timeout => "timed out",
+            ErrorClass::Locked => "input locked",
+            ErrorClass::Output => "writing outputs failed",
+            ErrorClass::Cancelled => "cancelled",
+        };
+        f.write_str(text)
+    }
+}
+
+impl std::error::Error for ErrorClass {}
+
+/// The class of `err`. A cancellation or timeout anywhere in the chain wins over the
+/// stage it interrupted; otherwise the outermost class attached to the error counts.
+pub fn classify(err: &anyhow::Error) -> ErrorClass {
+    match cancel::interrupted(err) {
+        Some(Interrupted::Cancelled | Interrupted::Paused) => return ErrorClass::Cancelled,
+        Some(Interrupted::DeadlineExceeded) => return ErrorClass::Timeout,
+        None => {}
+    }
+    if err
+        .chain()
+        .any(|e| e.downcast_ref::<ErrorClass>() == Some(&ErrorClass::Timeout))
+    {
+        return ErrorClass::Timeout;
+    }
+    err.downcast_ref::<ErrorClass>()
+        .copied()
+        .unwrap_or(ErrorClass::Other)
+}
@@ -0,0 +1,160 @@
+use crate::{
+    attempts::{self, Attempt},
+    config::Config,
+    exit_code::{self, ErrorClass},
+    jobs,
+    util::now_rfc3339,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Written to the job directory when a job fails; removed when it next succeeds.
+pub const FAILURE_FILE: &str = "failure.json";
+
+/// Lines of Python stderr kept in the report.
+const STDERR_TAIL_LINES: usize = 40;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureReport {
+    pub job_id: String,
+    pub input: String,
+    pub failed_at: String,
+    /// How far the job got, from what is on disk: "prepare" (probe, policy, split),
+    /// "convert", "merge", or "publish" (index and mirrors).
+    pub stage: String,
+    /// What kind of failure it was; the process exits with its code.
+    #[serde(default = "default_class")]
+    pub class: ErrorClass,
+    /// The error, outermost context first.
+    pub error_chain: Vec<String>,
+    pub completed_chunks: Vec<u32>,
+    /// Chunks in the plan, once `job.json` was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planned_chunks: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<StderrExcerpt>,
+    /// The failed chunk's tries: engine, backend, error class, and stderr excerpt each.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<Attempt>,
+    pub next_actions: Vec<String>,
+}
+
+/// The end of the most recently written chunk stderr log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StderrExcerpt {
+    /// Job-relative path of the log.
+    pub log: String,
+    pub tail: String,
+}
+
+/// Describe a failed job from the error and what it left in `job_dir`. `rerun` is the
+/// command line that runs the job again.
+pub fn describe(
+    cfg: &Config,
+    job_dir: &Path,
+    job_id: &str,
+    input: &Path,
+    err: &anyhow::Error,
+    rerun: &str,
+) -> FailureReport {
+    let completed_chunks: Vec<u32> = jobs::load_records(job_dir)
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.chunk_index)
+        .collect();
+    let planned_chunks = jobs::read_manifest(job_dir).map(|m| m.plan.chunks.len());
+    let final_dir = job_dir.join("final");
+    let stage = if final_dir.join(&cfg.output.report_filename).is_file()
+        || final_dir.join(&cfg.output.markdown_filename).is_file()
+    {
+        "publish"
+    } else if planned_chunks.is_some_and(|n| completed_chunks.len() >= n) {
+        "merge"
+    } else if planned_chunks.is_some() {
+        "convert"
+    } else {
+        "prepare"
+    };
+    let stderr = last_stderr(job_dir);
+
+    let mut next_actions = Vec::new();
+    match stage {
+        "prepare" => next_actions.push(format!(
+            "check how the input is classified: quack-check classify --input {}",
+            input.display()
+        )),
+        "merge" | "publish" => {
+            next_actions.push("re-merge the converted chunks: quack-check jobs doctor".into())
+        }
+        _ => {}
+    }
+    if stage == "convert" && cfg.global.resume && !completed_chunks.is_empty() {
+        next_actions.push(format!(
+            "resume after the {} converted chunk(s): {rerun}",
+            completed_chunks.len()
+        ));
+    } else {
+        next_actions.push(format!("run the job again: {rerun}"));
+    }
+    if let Some(stderr) = &stderr {
+        next_actions.push(format!("read the Python stderr in {}", stderr.log));
+    }
+    if !cfg.global.resume {
+        next_actions
+            .push("global.resume = false: delete the job directory before running it again".into());
+    }
+
+    FailureReport {
+        job_id: job_id.to_string(),
+        input: input.display().to_string(),
+        failed_at: now_rfc3339(),
+        stage: stage.to_string(),
+        class: exit_code::classify(err),
+        error_chain: err.chain().map(|e| e.to_string()).collect(),
+        completed_chunks,
+        planned_chunks,
+        stderr,
+        attempts: attempts::trail(err),
+        next_actions,
+    }
+}
+
+fn default_class() -> ErrorClass {
+    ErrorClass::Other
+}
+
+/// Write `failure.json` for a failed job; returns its path.
+pub fn write(job_dir: &Path, report: &FailureReport) -> Result<PathBuf> {
+    let path = job_dir.join(FAILURE_FILE);
+    std::fs::write(&path, serde_json::to_string_pretty(report)?)?;
+    Ok(path)
+}
+
+/// Drop a stale report once the job has succeeded.
+pub fn clear(job_dir: &Path) {
+    let _ = std::fs::remove_file(job_dir.join(FAILURE_FILE));
+}
+
+fn last_stderr(job_dir: &Path) -> Option<StderrExcerpt> {
+    let logs_dir = job_dir.join("logs");
+    let (_, path) = std::fs::read_dir(&logs_dir)
+        .ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(".stderr.log"))
+        })
+        .filter_map(|p| Some((std::fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .max()?;
+    let raw = std::fs::read_to_string(&path).ok()?;
+    let lines: Vec<&str> = raw.lines().collect();
+    let tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+    let log = path
+        .strip_prefix(job_dir)
+        .unwrap_or(&path)
+        .display()
+        .to_string();
+    Some(StderrExcerpt { log, tail })
+}
@@ -0,0 +1,124 @@
+use crate::{
+    cli::{self, Args},
+    exit_code,
+};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::AssertUnwindSafe;
+
+/// Run one job, as `quack-check run --input <input_path>`. Returns a JSON string to
+/// release with `quack_free_result`: `{"ok": true, "result": ...}` with what `--json`
+/// prints, or `{"ok": false, "error": "...", "error_class": "...", "exit_code": N}`.
+///
+/// `config_path` may be NULL for the default config lookup (`quack-check.toml` in the
+/// working directory), and `out_dir` NULL for the config's `paths.out_dir`. The result
+/// holds the job id, job directory, status, and completeness.
+///
+/// # Safety
+///
+/// Each non-NULL argument must point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn quack_run_job(
+    config_path: *const c_char,
+    input_path: *const c_char,
+    out_dir: *const c_char,
+) -> *mut c_char {
+    let (config, input, out_dir) =
+        unsafe { (string(config_path), string(input_path), string(out_dir)) };
+    respond(|| {
+        let mut words = vec![
+            "run".into(),
+            "--input".into(),
+            required(input, "input_path")?,
+        ];
+        if let Some(dir) = out_dir? {
+            words.extend(["--out-dir".into(), dir]);
+        }
+        embedded(config?, words)
+    })
+}
+
+/// Probe and classify one input, as `quack-check classify --input <input_path>`. Returns
+/// JSON like `quack_run_job`, whose result holds the probe and the policy decision.
+///
+/// # Safety
+///
+/// Each non-NULL argument must point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn quack_classify(
+    config_path: *const c_char,
+    input_path: *const c_char,
+) -> *mut c_char {
+    let (config, input) = unsafe { (string(config_path), string(input_path)) };
+    respond(|| {
+        let words = vec![
+            "classify".into(),
+            "--input".into(),
+            required(input, "input_path")?,
+        ];
+        embedded(config?, words)
+    })
+}
+
+/// Release a string returned by this library. NULL is ignored.
+///
+/// # Safety
+///
+/// `result` must be NULL or a pointer returned by this library, not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn quack_free_result(result: *mut c_char) {
+    if !result.is_null() {
+        drop(unsafe { CString::from_raw(result) });
+    }
+}
+
+fn embedded(config: Option<String>, words: Vec<String>) -> Result<serde_json::Value> {
+    let mut argv = vec!["quack-check".to_string(), "--json".to_string()];
+    if let Some(config) = config {
+        argv.extend(["--config".to_string(), config]);
+    }
+    argv.extend(words);
+    let args = Args::try_parse_from(argv).map_err(|e| anyhow!("{e}"))?;
+    cli::embedded(&args)
+}
+
+/// The JSON envelope of `call`'s outcome, as a string the caller owns. A panic is
+/// reported as an error rather than unwinding into the caller.
+fn respond(call: impl FnOnce() -> Result<serde_json::Value>) -> *mut c_char {
+    let outcome = std::panic::catch_unwind(AssertUnwindSafe(call))
+        .unwrap_or_else(|_| Err(anyhow!("quack-check panicked")));
+    let envelope = match outcome {
+        Ok(result) => serde_json::json!({ "ok": true, "result": result }),
+        Err(err) => {
+            let class = exit_code::classify(&err);
+            serde_json::json!({
+                "ok": false,
+                "error": format!("{err:#}"),
+                "error_class": class,
+                "exit_code": class.code(),
+            })
+        }
+    };
+    // serde_json escapes control characters, so the text holds no NUL.
+    CString::new(envelope.to_string())
+        .unwrap_or_default()
+        .into_raw()
+}
+
+fn required(arg: Result<Option<String>>, name: &str) -> Result<String> {
+    arg?.ok_or_else(|| anyhow!("{name} is NULL"))
+}
+
+/// # Safety
+///
+/// `ptr` must be NULL or point to a NUL-terminated string.
+unsafe fn string(ptr: *const c_char) -> Result<Option<String>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    let text = unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| anyhow!("argument is not valid UTF-8"))?;
+    Ok(Some(text.to_string()))
+}
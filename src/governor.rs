@@ -0,0 +1,97 @@
+use crate::config::ParallelGovernor;
+
+/// Host pressure signals sampled between chunks. Signals the platform does not expose
+/// are `None` and do not constrain parallelism.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HostLoad {
+    pub mem_available_bytes: Option<u64>,
+    pub load_avg_1m: Option<f64>,
+    pub cpus: usize,
+}
+
+impl HostLoad {
+    /// Read `MemAvailable` from /proc/meminfo and the 1-minute load average from
+    /// /proc/loadavg.
+    pub fn sample() -> Self {
+        let mem_available_bytes = std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|s| parse_mem_available(&s));
+        let load_avg_1m = std::fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|s| s.split_whitespace().next()?.parse().ok());
+        let cpus = std::thread::available_parallelism().map_or(1, usize::from);
+        Self {
+            mem_available_bytes,
+            load_avg_1m,
+            cpus,
+        }
+    }
+}
+
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Decides how many chunk conversions may be in flight, between 1 and
+/// `global.max_parallel_chunks`, from free memory and load average. Running
+/// conversions are never interrupted; a lower limit only holds back new ones.
+#[derive(Debug, Clone)]
+pub struct Governor {
+    max: usize,
+    cfg: ParallelGovernor,
+    limit: usize,
+}
+
+impl Governor {
+    pub fn new(max_parallel_chunks: usize, cfg: &ParallelGovernor) -> Self {
+        let max = max_parallel_chunks.max(1);
+        Self {
+            max,
+            cfg: cfg.clone(),
+            limit: max,
+        }
+    }
+
+    /// The limit from the last call to [`Governor::limit`].
+    pub fn current(&self) -> usize {
+        self.limit
+    }
+
+    /// The in-flight limit given the host's load and the conversions already running.
+    /// Below `min_free_memory_bytes`, or above `max_load_per_cpu`, the limit drops
+    /// below `in_flight` so nothing new starts until one finishes. Otherwise one new
+    /// conversion is allowed per `memory_per_chunk_bytes` of headroom, and the limit
+    /// grows by at most one per call.
+    pub fn limit(&mut self, load: &HostLoad, in_flight: usize) -> usize {
+        if !self.cfg.enabled || self.max == 1 {
+            self.limit = self.max;
+            return self.limit;
+        }
+        let shrink = in_flight.saturating_sub(1);
+        let mut target = self.max;
+
+        if let Some(available) = load.mem_available_bytes {
+            let cap = match available.checked_sub(self.cfg.min_free_memory_bytes) {
+                None => shrink,
+                Some(headroom) => headroom
+                    .checked_div(self.cfg.memory_per_chunk_bytes)
+                    .map_or(self.max, |fits| {
+                        in_flight.saturating_add(usize::try_from(fits).unwrap_or(usize::MAX))
+                    }),
+            };
+            target = target.min(cap);
+        }
+
+        if let Some(load_avg) = load.load_avg_1m
+            && self.cfg.max_load_per_cpu > 0.0
+            && load_avg > self.cfg.max_load_per_cpu * load.cpus.max(1) as f64
+        {
+            target = target.min(shrink);
+        }
+
+        self.limit = target.min(self.limit + 1).clamp(1, self.max);
+        self.limit
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    config::Config,
+    engine::ConvertIn,
+    pipeline::{ChunkRecord, JobOutput},
+    policy::PolicyDecision,
+    probe::ProbeResult,
+};
+use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Callbacks around each pipeline stage, for validation, external logging, or editing
+/// what a stage produced. Every method defaults to doing nothing; an error fails the
+/// job. `before_chunk` runs on the conversion threads, the others on the job's thread
+/// in order.
+pub trait PipelineHooks: Send + Sync {
+    /// After probing, before the policy sees the probe.
+    fn on_probe(&self, _probe: &mut ProbeResult) -> Result<()> {
+        Ok(())
+    }
+
+    /// After the policy decision, before chunks are planned around it.
+    fn on_decision(&self, _probe: &ProbeResult, _decision: &mut PolicyDecision) -> Result<()> {
+        Ok(())
+    }
+
+    /// Before a chunk is sent to its engine.
+    fn before_chunk(&self, _req: &mut ConvertIn) -> Result<()> {
+        Ok(())
+    }
+
+    /// After a chunk converted (or was reused), before its record is written.
+    fn after_chunk(&self, _record: &mut ChunkRecord) -> Result<()> {
+        Ok(())
+    }
+
+    /// Before the chunk records are merged into the transcript.
+    fn before_merge(&self, _records: &mut Vec<ChunkRecord>) -> Result<()> {
+        Ok(())
+    }
+
+    /// After merging, before the outputs are returned to be written.
+    fn after_merge(&self, _output: &mut JobOutput) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The hooks [`crate::pipeline::Pipeline::new`] runs: none.
+pub struct NoHooks;
+
+impl PipelineHooks for NoHooks {}
+
+/// Runs the commands in `[hooks]` at each stage. Each gets the stage's data as JSON on
+/// stdin and the stage name in `QUACK_HOOK`; a non-zero exit or running past
+/// `hooks.timeout_seconds` fails the job. Commands only observe: their output is
+/// ignored.
+pub struct CommandHooks {
+    cfg: crate::config::Hooks,
+}
+
+impl CommandHooks {
+    pub fn new(cfg: &Config) -> Self {
+        Self {
+            cfg: cfg.hooks.clone(),
+        }
+    }
+
+    fn run(
+        &self,
+        stage: &str,
+        commands: &[Vec<String>],
+        payload: impl FnOnce() -> serde_json::Value,
+    ) -> Result<()> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+        let input = serde_json::to_vec(&payload())?;
+        for argv in commands {
+            run_command(stage, argv, &input, self.cfg.timeout_seconds)?;
+        }
+        Ok(())
+    }
+}
+
+impl PipelineHooks for CommandHooks {
+    fn on_probe(&self, probe: &mut ProbeResult) -> Result<()> {
+        self.run("probe", &self.cfg.probe, || json!(probe))
+    }
+
+    fn on_decision(&self, probe: &ProbeResult, decision: &mut PolicyDecision) -> Result<()> {
+        self.run(
+            "decision",
+            &self.cfg.decision,
+            || json!({ "probe": probe, "decision": decision }),
+        )
+    }
+
+    fn before_chunk(&self, req: &mut ConvertIn) -> Result<()> {
+        self.run("before_chunk", &self.cfg.before_chunk, || json!(req))
+    }
+
+    fn after_chunk(&self, record: &mut ChunkRecord) -> Result<()> {
+        self.run("after_chunk", &self.cfg.after_chunk, || json!(record))
+    }
+
+    fn before_merge(&self, records: &mut Vec<ChunkRecord>) -> Result<()> {
+        self.run(
+            "before_merge",
+            &self.cfg.before_merge,
+            || json!({ "chunks": records }),
+        )
+    }
+
+    fn after_merge(&self, output: &mut JobOutput) -> Result<()> {
+        self.run(
+            "after_merge",
+            &self.cfg.after_merge,
+            || json!({ "report": output.report, "markdown": output.markdown }),
+        )
+    }
+}
+
+fn run_command(stage: &str, argv: &[String], input: &[u8], timeout_seconds: u64) -> Result<()> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow!("hooks.{stage} has an empty command"))?;
+    debug!("hook {stage}: {}", argv.join(" "));
+    let mut child = Command::new(program)
+        .args(args)
+        .env("QUACK_HOOK", stage)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("starting hook {stage}: {program}"))?;
+
+    let mut stdin = child.stdin.take();
+    let input = input.to_vec();
+    // A hook may exit without reading its input; a broken pipe is not its failure.
+    let writer = std::thread::spawn(move || {
+        if let Some(stdin) = stdin.as_mut() {
+            let _ = stdin.write_all(&input);
+        }
+    });
+    let mut stderr_pipe = child.stderr.take();
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(err) = stderr_pipe.as_mut() {
+            let _ = err.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    let started = Instant::now();
+    let timeout = Duration::from_secs(timeout_seconds);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout_seconds > 0 && started.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = writer.join();
+            return Err(anyhow!(
+                "hook {stage} ({program}) timed out after {timeout_seconds}s"
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let _ = writer.join();
+    let stderr = reader.join().unwrap_or_default();
+    let stderr = String::from_utf8_lossy(&stderr);
+    if !status.success() {
+        return Err(anyhow!(
+            "hook {stage} ({program}) failed ({status}): {}",
+            stderr.trim()
+        ));
+    }
+    if !stderr.trim().is_empty() {
+        info!("hook {stage} ({program}): {}", stderr.trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use crate::{config::Config, util::sha256_hex};
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Links followed before giving up, as the kernel's `SYMLOOP_MAX`.
+const MAX_LINKS: usize = 40;
+
+/// Where an input really lives.
+#[derive(Debug, Clone)]
+pub struct ResolvedInput {
+    /// The input with every symlink resolved.
+    pub canonical: PathBuf,
+    /// The symlinks followed from the path as given, in order; empty for a plain file.
+    pub links: Vec<PathBuf>,
+    /// Hard links to the file, 1 when it has no others (always 1 off Unix).
+    pub hard_links: u64,
+}
+
+/// Follow `input`'s symlinks, failing on a cycle or a dangling link, and check the
+/// result against `security.allowed_input_roots`.
+pub fn resolve(cfg: &Config, input: &Path) -> Result<ResolvedInput> {
+    let mut current = absolute(input)?;
+    let mut links: Vec<PathBuf> = Vec::new();
+    loop {
+        let meta = std::fs::symlink_metadata(&current).with_context(|| match links.last() {
+            Some(link) => format!(
+                "dangling symlink {} -> {}",
+                link.display(),
+                current.display()
+            ),
+            None => format!("input does not exist: {}", current.display()),
+        })?;
+        if !meta.file_type().is_symlink() {
+            break;
+        }
+        if links.contains(&current) || links.len() >= MAX_LINKS {
+            links.push(current);
+            let chain: Vec<String> = links.iter().map(|p| p.display().to_string()).collect();
+            return Err(anyhow!(
+                "symlink cycle resolving {}: {}",
+                input.display(),
+                chain.join(" -> ")
+            ));
+        }
+        let target = std::fs::read_link(&current)
+            .with_context(|| format!("reading symlink: {}", current.display()))?;
+        let next = current.parent().unwrap_or(Path::new("/")).join(target);
+        links.push(current);
+        current = absolute(&next).with_context(|| {
+            format!(
+                "dangling symlink {} -> {}",
+                links[links.len() - 1].display(),
+                next.display()
+            )
+        })?;
+    }
+    let canonical = std::fs::canonicalize(&current)
+        .with_context(|| format!("resolving input: {}", input.display()))?;
+    check_allowed(cfg, input, &canonical)?;
+
+    let hard_links = hard_links(&canonical);
+    if !links.is_empty() {
+        info!(
+            "input {} resolves to {}",
+            input.display(),
+            canonical.display()
+        );
+    }
+    if hard_links > 1 && cfg.hashing.identity == "content_and_path" {
+        warn!(
+            "{} has {hard_links} hard links; each linked path gets its own job under \
+             hashing.identity = \"content_and_path\"",
+            canonical.display()
+        );
+    }
+    Ok(ResolvedInput {
+        canonical,
+        links,
+        hard_links,
+    })
+}
+
+/// What the job id is computed from: the content hash alone (`hashing.identity =
+/// "content"`), or the content hash and the canonical path (`"content_and_path"`).
+/// Symlinks to one file share a job either way.
+pub fn identity_hash(cfg: &Config, input_hash: &str, input: &ResolvedInput) -> Result<String> {
+    match cfg.hashing.identity.as_str() {
+        "content" => Ok(input_hash.to_string()),
+        "content_and_path" => Ok(sha256_hex(
+            format!("{input_hash}:{}", input.canonical.display()).as_bytes(),
+        )),
+        other => Err(anyhow!(
+            "unknown hashing.identity {other:?} (expected \"content\" or \"content_and_path\")"
+        )),
+    }
+}
+
+/// Refuse inputs outside `security.allowed_input_roots` (when set). Roots are compared
+/// after resolving symlinks on both sides, so a link cannot lead out of them.
+fn check_allowed(cfg: &Config, input: &Path, canonical: &Path) -> Result<()> {
+    let roots = &cfg.security.allowed_input_roots;
+    if roots.is_empty() {
+        return Ok(());
+    }
+    for root in roots {
+        let root = std::fs::canonicalize(root)
+            .with_context(|| format!("security.allowed_input_roots entry not found: {root}"))?;
+        if canonical.starts_with(&root) {
+            return Ok(());
+        }
+    }
+    Err(anyhow!(
+        "input {} ({}) is outside security.allowed_input_roots",
+        input.display(),
+        canonical.display()
+    ))
+}
+
+/// `path` made absolute with its directory resolved, its last component kept as is so
+/// a symlink there can still be followed.
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => (dir, name),
+        _ => return Ok(std::fs::canonicalize(path)?),
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    Ok(std::fs::canonicalize(dir)?.join(name))
+}
+
+#[cfg(unix)]
+fn hard_links(path: &Path) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).map(|m| m.nlink()).unwrap_or(1)
+}
+
+#[cfg(not(unix))]
+fn hard_links(_path: &Path) -> u64 {
+    1
+}
@@ -0,0 +1,247 @@
+use crate::{
+    config::Config,
+    jobs,
+    pipeline::ChunkRecord,
+    report::{ChunkReport, JobReport},
+    script,
+};
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// A job directory's report and chunk records, as far as they exist.
+pub struct JobView {
+    pub job_id: String,
+    pub job_dir: PathBuf,
+    pub report: Option<JobReport>,
+    pub records: Vec<ChunkRecord>,
+}
+
+/// Resolve `--job` to a job directory: a path, a full job id under `out_root`, or a
+/// unique job id prefix.
+pub fn resolve_job_dir(out_root: &Path, job: &str) -> Result<PathBuf> {
+    let as_path = Path::new(job);
+    if as_path.is_dir() && as_path.components().count() > 1 {
+        return Ok(as_path.to_path_buf());
+    }
+    let exact = out_root.join(job);
+    if exact.is_dir() {
+        return Ok(exact);
+    }
+    let mut matches: Vec<PathBuf> = jobs::job_dirs(out_root)?
+        .into_iter()
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(job))
+        })
+        .collect();
+    match matches.len() {
+        0 => Err(anyhow!(
+            "no job matching {job:?} under {}",
+            out_root.display()
+        )),
+        1 => Ok(matches.remove(0)),
+        n => Err(anyhow!("{n} jobs match {job:?}; use a longer prefix")),
+    }
+}
+
+pub fn load(cfg: &Config, job_dir: &Path) -> Result<JobView> {
+    let job_cfg = jobs::job_config(cfg, job_dir);
+    let report_path = job_dir.join("final").join(&job_cfg.output.report_filename);
+    let report = if report_path.is_file() {
+        let raw = std::fs::read_to_string(&report_path)?;
+        Some(
+            serde_json::from_str(&raw)
+                .with_context(|| format!("parse report: {}", report_path.display()))?,
+        )
+    } else {
+        None
+    };
+    Ok(JobView {
+        job_id: job_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        job_dir: job_dir.to_path_buf(),
+        report,
+        records: jobs::load_records(job_dir)?,
+    })
+}
+
+impl JobView {
+    /// Per-chunk reports, rebuilt from chunk records when the job has no final report.
+    pub fn chunks(&self) -> Vec<ChunkReport> {
+        if let Some(report) = &self.report {
+            return report.chunk_reports.clone();
+        }
+        self.records
+            .iter()
+            .map(|r| {
+                let detected = script::detect(&r.out.markdown);
+                ChunkReport {
+                    chunk_index: r.chunk_index,
+                    start_page: r.start_page,
+                    end_page: r.end_page,
+                    ok: r.out.ok,
+                    pdf_backend: r.out.meta.pdf_backend.clone(),
+                    tokens: 0,
+                    page_errors: r.page_errors.clone(),
+                    stderr_log: r.stderr_log.clone(),
+                    content_type: r.content_type.clone(),
+                    script: detected.as_ref().map(|d| d.script.clone()),
+                    direction: detected.map(|d| d.direction),
+                    warnings: r.out.warnings.clone(),
+                    meta: r.out.meta.clone(),
+                    skipped: None,
+                    attempts: r.attempts.clone(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn record(&self, chunk: u32) -> Result<&ChunkRecord> {
+        self.records
+            .iter()
+            .find(|r| r.chunk_index == chunk)
+            .ok_or_else(|| {
+                anyhow!(
+                    "job {} has no chunk record {chunk} (was output.write_chunk_json disabled?)",
+                    self.job_id
+                )
+            })
+    }
+
+    /// The overview as JSON, for `--json`.
+    pub fn summary_json(&self) -> serde_json::Value {
+        let chunks = self.chunks();
+        let warnings: BTreeMap<String, Vec<serde_json::Value>> = group_warnings(&chunks)
+            .into_iter()
+            .map(|(code, hits)| {
+                let hits = hits
+                    .into_iter()
+                    .map(|(chunk, warning)| serde_json::json!({ "chunk": chunk, "warning": warning }))
+                    .collect();
+                (code, hits)
+            })
+            .collect();
+        let chunks: Vec<serde_json::Value> = chunks
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "chunk_index": c.chunk_index,
+                    "start_page": c.start_page,
+                    "end_page": c.end_page,
+                    "ok": c.ok,
+                    "tokens": c.tokens,
+                    "pdf_backend": c.pdf_backend,
+                    "pages_processed": c.meta.pages_processed,
+                    "ocr_applied": c.meta.ocr_applied,
+                    "table_count": c.meta.table_count,
+                    "seconds": c.meta.timings.values().sum::<f64>(),
+                    "warnings": c.warnings.len(),
+                    "page_errors": c.page_errors.len(),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "job_id": self.job_id,
+            "job_dir": self.job_dir,
+            "input": self.report.as_ref().map(|r| &r.input),
+            "decision": self.report.as_ref().map(|r| &r.decision),
+            "tokens": self.report.as_ref().map(|r| &r.tokens),
+            "chunks": chunks,
+            "warnings": warnings,
+        })
+    }
+
+    /// Human-readable overview: job summary, chunk table, and warnings grouped by code.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "job     {}", self.job_id);
+        let _ = writeln!(out, "dir     {}", self.job_dir.display());
+        match &self.report {
+            Some(r) => {
+                let _ = writeln!(out, "input   {}", r.input.path);
+                let _ = writeln!(
+                    out,
+                    "pages   {} ({} bytes)",
+                    r.input.page_count, r.input.file_bytes
+                );
+                let _ = writeln!(
+                    out,
+                    "policy  tier={:?} engine={} do_ocr={}",
+                    r.decision.tier, r.decision.chosen_engine, r.decision.do_ocr
+                );
+                let _ = writeln!(out, "tokens  {} ({})", r.tokens.total, r.tokens.tokenizer);
+            }
+            None => {
+                let _ = writeln!(out, "status  no final report; showing chunk records");
+            }
+        }
+
+        let chunks = self.chunks();
+        let _ = writeln!(out, "\nchunks ({})", chunks.len());
+        for c in &chunks {
+            let _ = writeln!(
+                out,
+                "  {:>5}  pages {:>5}-{:<5}  {:<6} tokens={:<7} warnings={} page_errors={}{}{}",
+                c.chunk_index,
+                c.start_page,
+                c.end_page,
+                if c.ok { "ok" } else { "FAILED" },
+                c.tokens,
+                c.warnings.len(),
+                c.page_errors.len(),
+                c.pdf_backend
+                    .as_deref()
+                    .map(|b| format!(" backend={b}"))
+                    .unwrap_or_default(),
+                c.meta
+                    .table_count
+                    .map(|n| format!(" tables={n}"))
+                    .unwrap_or_default()
+            );
+        }
+
+        let groups = group_warnings(&chunks);
+        if !groups.is_empty() {
+            let _ = writeln!(out, "\nwarnings");
+            for (code, hits) in &groups {
+                let mut list: Vec<String> = hits.iter().map(|(c, _)| c.to_string()).collect();
+                list.dedup();
+                let _ = writeln!(out, "  {code} ({}): chunks {}", hits.len(), list.join(","));
+                let _ = writeln!(out, "    e.g. {}", hits[0].1);
+            }
+        }
+        out
+    }
+}
+
+/// Warnings keyed by `warning_code`, each with the chunk it came from.
+pub fn group_warnings(chunks: &[ChunkReport]) -> BTreeMap<String, Vec<(u32, String)>> {
+    let mut groups: BTreeMap<String, Vec<(u32, String)>> = BTreeMap::new();
+    for c in chunks {
+        for w in &c.warnings {
+            groups
+                .entry(warning_code(w))
+                .or_default()
+                .push((c.chunk_index, w.clone()));
+        }
+    }
+    groups
+}
+
+/// A stable grouping key for a free-form warning: the text before the first `:`, `;`
+/// or `(`, with numbers replaced by `N`.
+pub fn warning_code(warning: &str) -> String {
+    let head = warning
+        .split([':', ';', '('])
+        .next()
+        .unwrap_or(warning)
+        .trim();
+    let digits = Regex::new(r"\d+").expect("digits regex");
+    digits.replace_all(head, "N").into_owned()
+}
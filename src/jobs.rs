@@ -0,0 +1,510 @@
+use crate::{
+    config::Config,
+    decisions, encoding, failure, lock, page_quality, pagemap,
+    pipeline::{self, ChunkRecord, JobManifest, JobOutput, JOB_MANIFEST_FILE},
+    report::PARTIAL_REPORT_FILE,
+    retention, signing, terms,
+    util::{ensure_dir, now_rfc3339},
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::warn;
+
+/// Write everything under `final/` for a finished job.
+pub fn write_final_outputs(cfg: &Config, job_dir: &Path, result: &JobOutput) -> Result<()> {
+    let final_dir = job_dir.join("final");
+    ensure_dir(&final_dir)?;
+    let text = |s: &str| encoding::encode(&cfg.output.encoding, s);
+
+    if cfg.output.write_markdown {
+        std::fs::write(
+            final_dir.join(&cfg.output.markdown_filename),
+            text(&result.markdown),
+        )?;
+    }
+
+    if !result.images.is_empty() {
+        let images_dir = final_dir.join("images");
+        ensure_dir(&images_dir)?;
+        for image in &result.images {
+            std::fs::write(images_dir.join(&image.file_name), &image.bytes)?;
+        }
+    }
+
+    for image in &result.page_images {
+        let path = final_dir.join(&image.file);
+        if let Some(dir) = path.parent() {
+            ensure_dir(dir)?;
+        }
+        std::fs::write(path, &image.bytes)?;
+    }
+
+    if !result.sections.is_empty() {
+        let sections_dir = final_dir.join("sections");
+        ensure_dir(&sections_dir)?;
+        for section in &result.sections {
+            std::fs::write(sections_dir.join(&section.file), text(&section.markdown))?;
+        }
+        std::fs::write(
+            sections_dir.join("sections.json"),
+            serde_json::to_string_pretty(&serde_json::json!({
+                "split_by": cfg.output.split_final_by,
+                "sections": result.sections,
+            }))?,
+        )?;
+    }
+
+    if !result.chunks.is_empty() {
+        let chunks_dir = final_dir.join("chunks");
+        ensure_dir(&chunks_dir)?;
+        for chunk in &result.chunks {
+            std::fs::write(chunks_dir.join(&chunk.file), text(&chunk.markdown))?;
+        }
+    }
+
+    if cfg.output.write_text {
+        std::fs::write(final_dir.join(&cfg.output.text_filename), text(&result.text))?;
+    }
+
+    if cfg.output.write_report_json {
+        // Over the running job's partial report, then renamed into place.
+        let partial = final_dir.join(PARTIAL_REPORT_FILE);
+        std::fs::write(&partial, serde_json::to_string_pretty(&result.report)?)?;
+        std::fs::rename(&partial, final_dir.join(&cfg.output.report_filename))?;
+    }
+
+    if cfg.output.write_page_quality {
+        std::fs::write(
+            final_dir.join(page_quality::PAGE_QUALITY_FILE),
+            serde_json::to_string_pretty(&result.page_quality)?,
+        )?;
+        if cfg.output.page_quality_svg {
+            std::fs::write(
+                final_dir.join(page_quality::PAGE_QUALITY_SVG),
+                page_quality::render_svg(&result.page_quality),
+            )?;
+        }
+    }
+
+    if cfg.output.write_pagemap {
+        std::fs::write(
+            final_dir.join(pagemap::PAGEMAP_FILE),
+            serde_json::to_string_pretty(&result.pagemap)?,
+        )?;
+    }
+
+    if !result.terms.sections.is_empty() {
+        std::fs::write(
+            final_dir.join(terms::TERMS_FILE),
+            serde_json::to_string_pretty(&result.terms)?,
+        )?;
+    }
+
+    if cfg.output.write_decisions_json {
+        let decisions = decisions::effective_decisions(cfg, &result.report, result.sections.len());
+        std::fs::write(
+            final_dir.join(decisions::DECISIONS_FILE),
+            decisions::to_canonical_json(&decisions)?,
+        )?;
+    }
+
+    // Last, so every other artifact is covered.
+    signing::write_checksums(cfg, &final_dir)?;
+
+    Ok(())
+}
+
+/// Write `index.json` if enabled. `started` is unknown for jobs completed by `jobs doctor`.
+pub fn write_index(
+    cfg: &Config,
+    job_dir: &Path,
+    job_id: &str,
+    started: Option<&str>,
+) -> Result<()> {
+    if !cfg.output.write_index_json {
+        return Ok(());
+    }
+    let index = serde_json::json!({
+        "job_id": job_id,
+        "job_name": cfg.global.job_name,
+        "started": started,
+        "finished": now_rfc3339(),
+        "final_markdown": format!("final/{}", cfg.output.markdown_filename),
+        "final_text": format!("final/{}", cfg.output.text_filename),
+        "report": format!("final/{}", cfg.output.report_filename),
+    });
+    std::fs::write(
+        job_dir.join("index.json"),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Final outputs and index present.
+    Complete,
+    /// Final outputs present but no index.json.
+    MissingIndex,
+    /// Every planned chunk converted but the final outputs were never written.
+    Unmerged,
+    /// Some chunks missing, or chunks that cannot be re-merged.
+    Incomplete,
+    /// Nothing worth keeping.
+    Empty,
+    /// Another process holds the input lock for this job.
+    Running,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobAction {
+    Remerge,
+    WriteIndex,
+    RemoveOrphans(Vec<PathBuf>),
+    Delete,
+}
+
+impl JobAction {
+    pub fn describe(&self) -> String {
+        match self {
+            JobAction::Remerge => "re-merge final outputs from chunk records".to_string(),
+            JobAction::WriteIndex => "write index.json".to_string(),
+            JobAction::RemoveOrphans(paths) => {
+                format!("remove {} orphaned temp file(s)", paths.len())
+            }
+            JobAction::Delete => "delete job directory".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobCheck {
+    pub job_id: String,
+    pub job_dir: PathBuf,
+    pub state: JobState,
+    pub detail: String,
+    pub actions: Vec<JobAction>,
+}
+
+/// Inspect every job directory under `out_dir`.
+pub fn scan(cfg: &Config, out_dir: &Path) -> Result<Vec<JobCheck>> {
+    let active = lock::active_job_ids(cfg)?;
+    let mut checks = Vec::new();
+    for dir in job_dirs(out_dir)? {
+        checks.push(inspect_job(cfg, &dir, &active)?);
+    }
+    Ok(checks)
+}
+
+/// Job directories under `out_dir`, sorted. Other directories are searched too, for
+/// output directories templated per input (`out/{input_stem}/<job_id>`).
+pub fn job_dirs(out_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    let mut pending = vec![out_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("read_dir {}", dir.display())),
+        };
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            if is_job_dir(&path) {
+                dirs.push(path);
+            } else if path.is_dir() {
+                pending.push(path);
+            }
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn is_job_dir(p: &Path) -> bool {
+    p.is_dir()
+        && (p.join("chunks").is_dir()
+            || p.join("final").is_dir()
+            || p.join(JOB_MANIFEST_FILE).is_file())
+}
+
+pub fn inspect_job(cfg: &Config, job_dir: &Path, active: &[String]) -> Result<JobCheck> {
+    let job_id = job_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let job_cfg = job_config(cfg, job_dir);
+    let check = |state, detail: String, actions| JobCheck {
+        job_id: job_id.clone(),
+        job_dir: job_dir.to_path_buf(),
+        state,
+        detail,
+        actions,
+    };
+
+    if active.contains(&job_id) {
+        return Ok(check(
+            JobState::Running,
+            "input lock is held".into(),
+            vec![],
+        ));
+    }
+
+    let final_dir = job_dir.join("final");
+    let has_final = final_dir.join(&job_cfg.output.report_filename).is_file()
+        || final_dir.join(&job_cfg.output.markdown_filename).is_file();
+    let has_index = job_dir.join("index.json").is_file();
+    let orphans = orphaned_files(&job_cfg, job_dir)?;
+
+    if has_final {
+        let mut actions = Vec::new();
+        if !has_index && job_cfg.output.write_index_json {
+            actions.push(JobAction::WriteIndex);
+        }
+        if !orphans.is_empty() {
+            actions.push(JobAction::RemoveOrphans(orphans));
+        }
+        let state = if actions.contains(&JobAction::WriteIndex) {
+            JobState::MissingIndex
+        } else {
+            JobState::Complete
+        };
+        return Ok(check(state, "final outputs present".into(), actions));
+    }
+
+    let records = chunk_record_paths(job_dir)?;
+    let manifest = read_manifest(job_dir);
+
+    match manifest {
+        _ if records.is_empty() => Ok(check(
+            JobState::Empty,
+            "no chunk records and no final outputs".into(),
+            vec![JobAction::Delete],
+        )),
+        None => Ok(check(
+            JobState::Incomplete,
+            format!(
+                "{} chunk record(s) but no {JOB_MANIFEST_FILE}; cannot re-merge",
+                records.len()
+            ),
+            vec![JobAction::Delete],
+        )),
+        Some(manifest) => {
+            let planned = manifest.plan.chunks.len();
+            if records.len() >= planned && load_records(job_dir).is_ok() {
+                let mut actions = vec![JobAction::Remerge];
+                if job_cfg.output.write_index_json {
+                    actions.push(JobAction::WriteIndex);
+                }
+                if !orphans.is_empty() {
+                    actions.push(JobAction::RemoveOrphans(orphans));
+                }
+                Ok(check(
+                    JobState::Unmerged,
+                    format!("all {planned} chunk(s) converted; final outputs missing"),
+                    actions,
+                ))
+            } else {
+                Ok(check(
+                    JobState::Incomplete,
+                    format!(
+                        "{}/{planned} chunk(s) converted; re-run `quack-check run --input {}` to complete{}",
+                        records.len(),
+                        manifest.probe.input.path,
+                        failure_note(job_dir)
+                    ),
+                    vec![JobAction::Delete],
+                ))
+            }
+        }
+    }
+}
+
+/// The error recorded in `failure.json`, if the last run left one.
+fn failure_note(job_dir: &Path) -> String {
+    std::fs::read_to_string(job_dir.join(failure::FAILURE_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<failure::FailureReport>(&raw).ok())
+        .map(|f| {
+            format!(
+                " (last run failed at {}: {})",
+                f.stage,
+                f.error_chain.first().map_or("", String::as_str)
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Apply one action to a job directory.
+pub fn apply(cfg: &Config, check: &JobCheck, action: &JobAction) -> Result<()> {
+    let job_cfg = job_config(cfg, &check.job_dir);
+    match action {
+        JobAction::Remerge => {
+            let manifest = read_manifest(&check.job_dir)
+                .with_context(|| format!("missing {JOB_MANIFEST_FILE}"))?;
+            let records = load_records(&check.job_dir)?;
+            let output = pipeline::assemble_job(&job_cfg, manifest, records)?;
+            write_final_outputs(&job_cfg, &check.job_dir, &output)
+        }
+        JobAction::WriteIndex => write_index(&job_cfg, &check.job_dir, &check.job_id, None),
+        JobAction::RemoveOrphans(paths) => {
+            for p in paths {
+                std::fs::remove_file(p).with_context(|| format!("remove {}", p.display()))?;
+            }
+            Ok(())
+        }
+        JobAction::Delete => std::fs::remove_dir_all(&check.job_dir)
+            .with_context(|| format!("remove {}", check.job_dir.display())),
+    }
+}
+
+/// A job located by name with `jobs find`.
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedJob {
+    pub job_id: String,
+    pub job_name: String,
+    pub job_dir: PathBuf,
+    /// From index.json; `None` while the job is running or when it did not finish.
+    pub finished: Option<String>,
+}
+
+/// Jobs under `out_dir` whose name matches `pattern`, where `*` matches any run of
+/// characters and `?` any one. The name comes from index.json, or from the dumped
+/// effective config of a job that has not finished.
+pub fn find_by_name(out_dir: &Path, pattern: &str) -> Result<Vec<NamedJob>> {
+    let mut found = Vec::new();
+    for job_dir in job_dirs(out_dir)? {
+        let index: Option<serde_json::Value> = std::fs::read_to_string(job_dir.join("index.json"))
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok());
+        let indexed_name = index
+            .as_ref()
+            .and_then(|i| i["job_name"].as_str())
+            .map(str::to_string);
+        let job_name = match indexed_name {
+            Some(name) => name,
+            None if job_dir.join("effective-config.toml").is_file() => {
+                job_config(&Config::default(), &job_dir).global.job_name
+            }
+            None => continue,
+        };
+        if !wildcard_match(pattern, &job_name) {
+            continue;
+        }
+        found.push(NamedJob {
+            job_id: job_dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            job_name,
+            finished: index
+                .as_ref()
+                .and_then(|i| i["finished"].as_str())
+                .map(str::to_string),
+            job_dir,
+        });
+    }
+    Ok(found)
+}
+
+/// Shell-style matching of `*` and `?`; everything else matches itself.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    // Where the last `*` was, and the text position it is currently matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// The config the job ran with, if it was dumped; the current config otherwise.
+pub fn job_config(cfg: &Config, job_dir: &Path) -> Config {
+    let path = job_dir.join("effective-config.toml");
+    if path.is_file() {
+        match Config::load(&path) {
+            Ok(c) => return c,
+            Err(err) => warn!("ignoring unreadable {}: {err:#}", path.display()),
+        }
+    }
+    cfg.clone()
+}
+
+pub(crate) fn read_manifest(job_dir: &Path) -> Option<JobManifest> {
+    let raw = std::fs::read_to_string(job_dir.join(JOB_MANIFEST_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn chunk_record_paths(job_dir: &Path) -> Result<Vec<PathBuf>> {
+    let chunks_dir = job_dir.join("chunks");
+    if !chunks_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&chunks_dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension().and_then(|e| e.to_str()) == Some("json")
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("chunk_"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+pub fn load_records(job_dir: &Path) -> Result<Vec<ChunkRecord>> {
+    let mut records = Vec::new();
+    for path in chunk_record_paths(job_dir)? {
+        let raw = std::fs::read_to_string(&path)?;
+        let record: ChunkRecord = serde_json::from_str(&raw)
+            .with_context(|| format!("parse chunk record: {}", path.display()))?;
+        records.push(record);
+    }
+    records.sort_by_key(|r| r.chunk_index);
+    Ok(records)
+}
+
+/// Artifacts the job's cleanup stage would delete under its `[retention]` rules, plus
+/// `*.tmp` leftovers.
+fn orphaned_files(cfg: &Config, job_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut orphans = Vec::new();
+    if !cfg.global.keep_intermediates {
+        orphans.extend(
+            retention::expired(cfg, job_dir, SystemTime::now())?
+                .into_iter()
+                .map(|a| a.path),
+        );
+    }
+    for dir in [job_dir.to_path_buf(), job_dir.join("chunks"), job_dir.join("final")] {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("tmp") {
+                orphans.push(path);
+            }
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
@@ -1,10 +1,72 @@
+pub mod accelerator;
+pub mod archive;
+pub mod attempts;
+pub mod batch;
+pub mod blank_pages;
+pub mod calibrate;
+pub mod cancel;
+pub mod chapters;
 pub mod chunk_plan;
+pub mod chunk_stage;
 pub mod cli;
+pub mod collections;
+pub mod completions;
 pub mod config;
+pub mod config_hash;
+pub mod content;
+pub mod corpus;
+pub mod decisions;
+pub mod dedup;
+pub mod duplicate_pages;
+pub mod encoding;
 pub mod engine;
+pub mod exclusions;
+pub mod exit_code;
+pub mod failure;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod governor;
+pub mod hooks;
+pub mod input_path;
+pub mod inspect;
+pub mod jobs;
+pub mod lint;
+pub mod lock;
+pub mod logrotate;
+pub mod matrix;
+pub mod metrics;
+pub mod migrate;
+pub mod mirror;
+pub mod ocr_retry;
+pub mod page_quality;
+pub mod pagemap;
+pub mod pagenum;
+pub mod pause;
 pub mod pipeline;
 pub mod policy;
 pub mod postprocess;
 pub mod probe;
+pub mod provenance;
+pub mod redact;
+pub mod render;
+pub mod replay;
 pub mod report;
+pub mod retention;
+pub mod reuse;
+pub mod script;
+pub mod sections;
+pub mod sidecar;
+pub mod selftest;
+pub mod signing;
+pub mod spreads;
+pub mod systemd;
+pub mod setup;
+pub mod template;
+pub mod terms;
+pub mod textstats;
+pub mod tiles;
+pub mod tokens;
 pub mod util;
+pub mod verse;
+pub mod xdg;
+pub mod xref;
@@ -0,0 +1,239 @@
+use crate::{
+    config::PostprocessLint,
+    postprocess::{is_marker_line, parse_page_marker},
+};
+use serde::{Deserialize, Serialize};
+
+pub const ERROR: &str = "error";
+pub const WARNING: &str = "warning";
+
+/// One problem in the final markdown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintFinding {
+    /// unbalanced_fence | broken_table_row | heading_jump | empty_section | long_line
+    pub rule: String,
+    /// "error" or "warning".
+    pub severity: String,
+    /// 1-based line in the final markdown.
+    pub line: usize,
+    /// PDF page, when page markers are kept in the transcript.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub message: String,
+}
+
+/// `lint` in the report (`postprocess.lint.enabled`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintReport {
+    pub errors: usize,
+    pub warnings: usize,
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// The first few errors on one line, for a failed job's message.
+    pub fn error_summary(&self, limit: usize) -> String {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == ERROR)
+            .take(limit)
+            .map(|f| format!("line {}: {}", f.line, f.message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+struct Line<'a> {
+    number: usize,
+    page: Option<u32>,
+    text: &'a str,
+    in_fence: bool,
+}
+
+/// Check merged markdown for structural problems. Unclosed code fences and table rows
+/// whose cell count differs from the header are errors; heading levels that skip a
+/// level, headings with nothing under them, and lines longer than `max_line_chars`
+/// are warnings. Page markers and other `<!-- quack:... -->` lines are ignored.
+pub fn lint_markdown(md: &str, cfg: &PostprocessLint) -> LintReport {
+    let mut lines = Vec::new();
+    let mut page = None;
+    let mut fence: Option<(&str, usize)> = None;
+    let mut findings = Vec::new();
+    for (i, text) in md.lines().enumerate() {
+        if let Some(p) = parse_page_marker(text) {
+            page = Some(p);
+        }
+        if is_marker_line(text) {
+            continue;
+        }
+        let trimmed = text.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        let in_fence = fence.is_some();
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some((m, lines.len())),
+            (Some((open, _)), Some(m)) if m == open && trimmed.trim_end() == m => fence = None,
+            _ => {}
+        }
+        lines.push(Line {
+            number: i + 1,
+            page,
+            text,
+            in_fence: in_fence || marker.is_some(),
+        });
+    }
+    if let Some((_, at)) = fence {
+        findings.push(finding(
+            &lines[at],
+            "unbalanced_fence",
+            ERROR,
+            "code fence is never closed".into(),
+        ));
+    }
+
+    check_long_lines(&lines, cfg.max_line_chars as usize, &mut findings);
+    check_headings(&lines, &mut findings);
+    check_tables(&lines, &mut findings);
+
+    findings.sort_by_key(|f| f.line);
+    LintReport {
+        errors: findings.iter().filter(|f| f.severity == ERROR).count(),
+        warnings: findings.iter().filter(|f| f.severity == WARNING).count(),
+        findings,
+    }
+}
+
+fn finding(line: &Line, rule: &str, severity: &str, message: String) -> LintFinding {
+    LintFinding {
+        rule: rule.into(),
+        severity: severity.into(),
+        line: line.number,
+        page: line.page,
+        message,
+    }
+}
+
+fn check_long_lines(lines: &[Line], max: usize, out: &mut Vec<LintFinding>) {
+    if max == 0 {
+        return;
+    }
+    for line in lines {
+        let chars = line.text.chars().count();
+        if chars > max {
+            out.push(finding(
+                line,
+                "long_line",
+                WARNING,
+                format!("line is {chars} characters long (max {max})"),
+            ));
+        }
+    }
+}
+
+fn heading_level(text: &str) -> Option<usize> {
+    let hashes = text.bytes().take_while(|b| *b == b'#').count();
+    let rest = &text[hashes..];
+    ((1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' '))).then_some(hashes)
+}
+
+fn check_headings(lines: &[Line], out: &mut Vec<LintFinding>) {
+    let headings: Vec<(usize, usize)> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !l.in_fence)
+        .filter_map(|(i, l)| heading_level(l.text).map(|level| (i, level)))
+        .collect();
+    let mut previous: Option<usize> = None;
+    for (k, &(i, level)) in headings.iter().enumerate() {
+        if let Some(prev) = previous
+            && level > prev + 1
+        {
+            out.push(finding(
+                &lines[i],
+                "heading_jump",
+                WARNING,
+                format!("heading level jumps from {prev} to {level}"),
+            ));
+        }
+        previous = Some(level);
+
+        // Empty: nothing but blank lines before the next heading at this level or
+        // above (a subheading counts as content).
+        let end = headings[k + 1..]
+            .iter()
+            .find(|(_, l)| *l <= level)
+            .map_or(lines.len(), |(j, _)| *j);
+        let has_content = lines[i + 1..end].iter().any(|l| !l.text.trim().is_empty());
+        if !has_content {
+            out.push(finding(
+                &lines[i],
+                "empty_section",
+                WARNING,
+                format!(
+                    "section \"{}\" is empty",
+                    lines[i].text.trim_start_matches('#').trim()
+                ),
+            ));
+        }
+    }
+}
+
+fn table_cells(text: &str) -> Option<usize> {
+    let t = text.trim();
+    if !t.starts_with('|') {
+        return None;
+    }
+    let inner = t.strip_prefix('|').unwrap_or(t);
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    let mut cells = 1;
+    let mut escaped = false;
+    for c in inner.chars() {
+        match c {
+            '\\' if !escaped => {
+                escaped = true;
+                continue;
+            }
+            '|' if !escaped => cells += 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    Some(cells)
+}
+
+fn is_separator_row(text: &str) -> bool {
+    let t = text.trim();
+    t.starts_with('|')
+        && t.contains('-')
+        && t.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+fn check_tables(lines: &[Line], out: &mut Vec<LintFinding>) {
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        let header = &lines[i];
+        let Some(columns) = table_cells(header.text).filter(|_| !header.in_fence) else {
+            i += 1;
+            continue;
+        };
+        if !is_separator_row(lines[i + 1].text) {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        while j < lines.len() {
+            let Some(cells) = table_cells(lines[j].text) else {
+                break;
+            };
+            if cells != columns {
+                out.push(finding(
+                    &lines[j],
+                    "broken_table_row",
+                    ERROR,
+                    format!("table row has {cells} cells, the header has {columns}"),
+                ));
+            }
+            j += 1;
+        }
+        i = j;
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    config::Config,
+    exit_code::ErrorClass,
+    util::{ensure_dir, now_rfc3339},
+};
+use anyhow::{anyhow, Context, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use tracing::info;
+
+/// An exclusive lock on one input file, keyed by the input hash.
+///
+/// The lock is held for as long as the value lives; the lockfile itself is left in
+/// place so that a waiting process never ends up locking an unlinked inode.
+pub struct InputLock {
+    file: File,
+}
+
+impl InputLock {
+    pub fn acquire(cfg: &Config, input_hash: &str, job_id: &str) -> Result<Self> {
+        let locks_dir = Path::new(&cfg.paths.cache_dir).join("locks");
+        ensure_dir(&locks_dir)?;
+        let path = locks_dir.join(format!("{input_hash}.lock"));
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("open lockfile: {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let holder = read_holder(&mut file);
+                match cfg.global.on_input_locked.as_str() {
+                    "wait" => {
+                        info!("input is locked by {holder}; waiting");
+                        file.lock()
+                            .with_context(|| format!("lock: {}", path.display()))?;
+                    }
+                    "fail" => {
+                        return Err(anyhow::Error::new(ErrorClass::Locked).context(format!(
+                            "input is already being processed by {holder} (lockfile: {})",
+                            path.display()
+                        )));
+                    }
+                    other => return Err(anyhow!("unknown global.on_input_locked: {other}")),
+                }
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(err).with_context(|| format!("lock: {}", path.display()));
+            }
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        let holder = serde_json::json!({
+            "pid": std::process::id(),
+            "job_id": job_id,
+            "since": now_rfc3339(),
+        });
+        file.write_all(serde_json::to_string(&holder)?.as_bytes())?;
+        file.flush()?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for InputLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+/// Job ids whose input lock is currently held by some process.
+pub fn active_job_ids(cfg: &Config) -> Result<Vec<String>> {
+    let locks_dir = Path::new(&cfg.paths.cache_dir).join("locks");
+    let entries = match std::fs::read_dir(&locks_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("read_dir {}", locks_dir.display()));
+        }
+    };
+
+    let mut active = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("lock") {
+            continue;
+        }
+        let Ok(mut file) = OpenOptions::new().read(true).write(true).open(&path) else {
+            continue;
+        };
+        match file.try_lock() {
+            Ok(()) => {
+                let _ = file.unlock();
+            }
+            Err(TryLockError::WouldBlock) => {
+                let mut raw = String::new();
+                if file.read_to_string(&mut raw).is_ok()
+                    && let Ok(v) = serde_json::from_str::<serde_json::Value>(&raw)
+                    && let Some(job_id) = v["job_id"].as_str()
+                {
+                    active.push(job_id.to_string());
+                }
+            }
+            Err(TryLockError::Error(_)) => {}
+        }
+    }
+    Ok(active)
+}
+
+fn read_holder(file: &mut File) -> String {
+    let mut raw = String::new();
+    if file.seek(SeekFrom::Start(0)).is_err() || file.read_to_string(&mut raw).is_err() {
+        return "another process".to_string();
+    }
+    match serde_json::from_str::<serde_json::Value>(&raw) {
+        Ok(v) => format!(
+            "PID {} / job {}",
+            v["pid"].as_u64().map(|p| p.to_string()).unwrap_or_else(|| "?".into()),
+            v["job_id"].as_str().unwrap_or("?")
+        ),
+        Err(_) => "another process".to_string(),
+    }
+}
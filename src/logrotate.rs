@@ -0,0 +1,138 @@
+use crate::{config::Rotate, util::ensure_dir};
+use anyhow::{anyhow, Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A log file that moves itself aside to `<file>.1` once it is too big or its period
+/// is over, shifting older files up to `<file>.<keep_files>` and deleting the rest.
+/// Rotation is checked before each write, so a log line goes whole into one file.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Seconds per period, from `every`; 0 when rotating by size only.
+    period_seconds: u64,
+    keep_files: u32,
+    file: File,
+    written: u64,
+    period: u64,
+}
+
+impl RotatingFile {
+    /// Open `path` for appending, rotating it first if it is already due (a previous
+    /// run left it too big, or wrote it in an earlier period).
+    pub fn open(path: &Path, cfg: &Rotate) -> Result<Self> {
+        let period_seconds = match cfg.every.as_str() {
+            "never" => 0,
+            "hourly" => 3600,
+            "daily" => 86_400,
+            other => {
+                return Err(anyhow!(
+                    "unknown logging.rotate.every: {other} (expected never, hourly, or daily)"
+                ));
+            }
+        };
+        if let Some(parent) = path.parent() {
+            ensure_dir(parent)?;
+        }
+        let max_bytes = cfg.max_mb.saturating_mul(1024 * 1024);
+        let now = period_of(SystemTime::now(), period_seconds);
+        if let Ok(meta) = std::fs::metadata(path) {
+            let written_in = meta
+                .modified()
+                .map_or(now, |t| period_of(t, period_seconds));
+            let too_big = max_bytes > 0 && meta.len() >= max_bytes;
+            if too_big || written_in != now {
+                shift(path, cfg.keep_files)
+                    .with_context(|| format!("rotate log file: {}", path.display()))?;
+            }
+        }
+        let file = append(path)?;
+        let written = file.metadata().map_or(0, |m| m.len());
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            period_seconds,
+            keep_files: cfg.keep_files,
+            file,
+            written,
+            period: now,
+        })
+    }
+
+    fn rotate_if_due(&mut self) -> io::Result<()> {
+        let now = period_of(SystemTime::now(), self.period_seconds);
+        let too_big = self.max_bytes > 0 && self.written >= self.max_bytes;
+        if !too_big && now == self.period {
+            return Ok(());
+        }
+        self.file.flush()?;
+        shift(&self.path, self.keep_files)?;
+        self.file = append(&self.path).map_err(io::Error::other)?;
+        self.written = 0;
+        self.period = now;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.rotate_if_due()?;
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Whether `cfg` rotates at all.
+pub fn enabled(cfg: &Rotate) -> bool {
+    cfg.max_mb > 0 || cfg.every != "never"
+}
+
+/// The rotated file `n` places back: `<file>.<n>`.
+pub fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+fn append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("open log file: {}", path.display()))
+}
+
+/// Move `path` to `<path>.1`, each `<path>.N` to `<path>.N+1`, and drop what falls
+/// past `keep`. With `keep` 0 the log is simply removed.
+fn shift(path: &Path, keep: u32) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let _ = std::fs::remove_file(rotated_path(path, keep.max(1)));
+    for n in (1..keep).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    if keep == 0 {
+        std::fs::remove_file(path)
+    } else {
+        std::fs::rename(path, rotated_path(path, 1))
+    }
+}
+
+fn period_of(time: SystemTime, period_seconds: u64) -> u64 {
+    if period_seconds == 0 {
+        return 0;
+    }
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    seconds / period_seconds
+}
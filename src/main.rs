@@ -1,13 +1,18 @@
 use anyhow::Result;
 use clap::Parser;
-use quack_check::cli;
+use quack_check::{cli, exit_code};
 use tracing::error;
 
 fn main() -> Result<()> {
     let args = cli::Args::parse();
     if let Err(err) = cli::dispatch(args) {
-        error!("{:#}", err);
-        std::process::exit(1);
+        // Errors from before logging is set up (config, flags) would otherwise vanish.
+        if tracing::dispatcher::has_been_set() {
+            error!("{:#}", err);
+        } else {
+            eprintln!("error: {err:#}");
+        }
+        std::process::exit(exit_code::classify(&err).code());
     }
     Ok(())
 }
@@ -0,0 +1,324 @@
+use crate::{config::Config, dedup, pipeline::JobOutput, textstats};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Written to the output root after every `quack-check matrix`, suffixed with the
+/// input's file stem.
+pub const REPORT_PREFIX: &str = "matrix-";
+
+/// `--vary engine=...` sets the engine for every tier.
+const ENGINE_KEYS: [&str; 3] = [
+    "engine.high_text_engine",
+    "engine.mixed_text_engine",
+    "engine.scan_engine",
+];
+
+/// One `--vary key=v1,v2` axis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Axis {
+    pub key: String,
+    pub values: Vec<String>,
+}
+
+pub fn parse_vary(spec: &str) -> Result<Axis> {
+    let (key, values) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow!("--vary expects key=value1,value2: {spec}"))?;
+    let values: Vec<String> = values
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if key.trim().is_empty() || values.is_empty() {
+        return Err(anyhow!("--vary expects key=value1,value2: {spec}"));
+    }
+    Ok(Axis {
+        key: key.trim().to_string(),
+        values,
+    })
+}
+
+/// The cross-product of every axis, first axis varying slowest.
+pub fn variants(axes: &[Axis]) -> Vec<Vec<(String, String)>> {
+    let mut out: Vec<Vec<(String, String)>> = vec![Vec::new()];
+    for axis in axes {
+        out = out
+            .into_iter()
+            .flat_map(|prefix| {
+                axis.values.iter().map(move |value| {
+                    let mut next = prefix.clone();
+                    next.push((axis.key.clone(), value.clone()));
+                    next
+                })
+            })
+            .collect();
+    }
+    out
+}
+
+/// `cfg` with each dotted `key=value` setting applied. Keys must already exist in the
+/// config, and values are parsed as TOML literals of the existing value's type, so a
+/// typo'd key or a `true` given to a string setting fails before anything runs.
+pub fn apply(cfg: &Config, settings: &[(String, String)]) -> Result<Config> {
+    let mut root = toml::Value::try_from(cfg).context("serialize config")?;
+    for (key, raw) in settings {
+        let keys: Vec<&str> = if key == "engine" {
+            ENGINE_KEYS.to_vec()
+        } else {
+            vec![key.as_str()]
+        };
+        for key in keys {
+            set_path(&mut root, key, raw)?;
+        }
+    }
+    root.try_into()
+        .with_context(|| format!("invalid config after applying {}", label(settings)))
+}
+
+fn set_path(root: &mut toml::Value, key: &str, raw: &str) -> Result<()> {
+    let mut node = root;
+    for part in key.split('.') {
+        node = node
+            .get_mut(part)
+            .ok_or_else(|| anyhow!("unknown config key in --vary: {key}"))?;
+    }
+    let parsed = parse_literal(raw);
+    *node = match (&*node, parsed) {
+        (toml::Value::String(_), _) => toml::Value::String(raw.to_string()),
+        (toml::Value::Float(_), Some(toml::Value::Integer(i))) => toml::Value::Float(i as f64),
+        (existing, Some(value)) if existing.same_type(&value) => value,
+        (existing, _) => {
+            return Err(anyhow!(
+                "--vary {key}={raw}: expected a value of type {}",
+                existing.type_str()
+            ));
+        }
+    };
+    Ok(())
+}
+
+fn parse_literal(raw: &str) -> Option<toml::Value> {
+    let mut table: toml::Table = toml::from_str(&format!("v = {raw}")).ok()?;
+    table.remove("v")
+}
+
+/// `key=value` pairs joined with spaces.
+pub fn label(settings: &[(String, String)]) -> String {
+    settings
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A converted variant, as far as the comparison needs it.
+pub struct MatrixJob {
+    pub job_id: String,
+    pub job_dir: PathBuf,
+    pub output: JobOutput,
+}
+
+/// One variant's outcome.
+pub struct MatrixRun {
+    pub settings: Vec<(String, String)>,
+    pub duration: Duration,
+    pub outcome: Result<MatrixJob>,
+}
+
+/// Cheap, engine-independent signals for judging a transcript.
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityScore {
+    pub chars: usize,
+    pub words: usize,
+    pub tokens: usize,
+    pub headings: usize,
+    pub table_rows: usize,
+    /// Estimated share of the text that is OCR noise ([`crate::textstats`]).
+    pub garbage_ratio: f32,
+    pub warnings: usize,
+    pub page_errors: usize,
+}
+
+pub fn quality(output: &JobOutput) -> QualityScore {
+    let md = &output.markdown;
+    let chars = output.text.chars().count();
+    let chunks = &output.report.chunk_reports;
+    QualityScore {
+        chars,
+        words: output.text.split_whitespace().count(),
+        tokens: output.report.tokens.total,
+        headings: md.lines().filter(|l| l.starts_with('#')).count(),
+        table_rows: md
+            .lines()
+            .filter(|l| l.trim_start().starts_with('|'))
+            .count(),
+        garbage_ratio: textstats::garbage_ratio(&output.text),
+        warnings: chunks.iter().map(|c| c.warnings.len()).sum(),
+        page_errors: chunks.iter().map(|c| c.page_errors.len()).sum(),
+    }
+}
+
+/// How a variant's markdown differs from the baseline's: lines only in one of them
+/// (compared as multisets, ignoring blank lines) and MinHash similarity of the text.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffStats {
+    pub baseline: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub similarity: f32,
+}
+
+pub fn diff_stats(cfg: &Config, baseline: &JobOutput, variant: &JobOutput) -> DiffStats {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for line in baseline
+        .markdown
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+    {
+        *remaining.entry(line).or_default() += 1;
+    }
+    let mut lines_added = 0;
+    for line in variant
+        .markdown
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+    {
+        match remaining.get_mut(line) {
+            Some(n) if *n > 0 => *n -= 1,
+            _ => lines_added += 1,
+        }
+    }
+    let (words, perms) = (cfg.batch.shingle_words, cfg.batch.minhash_permutations);
+    DiffStats {
+        baseline: String::new(),
+        lines_added,
+        lines_removed: remaining.values().sum(),
+        similarity: dedup::similarity(
+            &dedup::signature(&baseline.text, words, perms),
+            &dedup::signature(&variant.text, words, perms),
+        ),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixEntry {
+    pub label: String,
+    pub settings: BTreeMap<String, String>,
+    /// ok | failed
+    pub status: String,
+    pub duration_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<QualityScore>,
+    /// Against the first variant that succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<DiffStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixReport {
+    pub input: PathBuf,
+    pub variants: Vec<MatrixEntry>,
+}
+
+impl MatrixReport {
+    pub fn new(cfg: &Config, input: &Path, runs: Vec<MatrixRun>) -> Self {
+        let baseline = runs.iter().enumerate().find_map(|(i, r)| {
+            r.outcome
+                .as_ref()
+                .ok()
+                .map(|job| (i, label(&r.settings), &job.output))
+        });
+        let variants =
+            runs.iter()
+                .enumerate()
+                .map(|(i, run)| {
+                    let mut entry = MatrixEntry {
+                        label: label(&run.settings),
+                        settings: run.settings.iter().cloned().collect(),
+                        status: "ok".into(),
+                        duration_seconds: run.duration.as_secs_f64(),
+                        job_id: None,
+                        job_dir: None,
+                        quality: None,
+                        diff: None,
+                        error: None,
+                    };
+                    match &run.outcome {
+                        Ok(job) => {
+                            entry.job_id = Some(job.job_id.clone());
+                            entry.job_dir = Some(job.job_dir.clone());
+                            entry.quality = Some(quality(&job.output));
+                            entry.diff = baseline.as_ref().filter(|(b, _, _)| *b != i).map(
+                                |(_, name, base)| DiffStats {
+                                    baseline: name.clone(),
+                                    ..diff_stats(cfg, base, &job.output)
+                                },
+                            );
+                        }
+                        Err(err) => {
+                            entry.status = "failed".into();
+                            entry.error = Some(format!("{err:#}"));
+                        }
+                    }
+                    entry
+                })
+                .collect();
+        Self {
+            input: input.to_path_buf(),
+            variants,
+        }
+    }
+
+    /// One row per variant, for the terminal.
+    pub fn table(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "matrix for {}", self.input.display());
+        for v in &self.variants {
+            let _ = write!(
+                out,
+                "\n{}\n  {:<6} {:>8.1}s",
+                v.label, v.status, v.duration_seconds
+            );
+            if let Some(q) = &v.quality {
+                let _ = write!(
+                    out,
+                    "  tokens={} headings={} table_rows={} garbage={:.4} warnings={} page_errors={}",
+                    q.tokens, q.headings, q.table_rows, q.garbage_ratio, q.warnings, q.page_errors
+                );
+            }
+            if let Some(d) = &v.diff {
+                let _ = write!(
+                    out,
+                    "\n  vs baseline: +{} -{} lines, similarity {:.3}",
+                    d.lines_added, d.lines_removed, d.similarity
+                );
+            }
+            if let Some(err) = &v.error {
+                let _ = write!(out, "\n  error: {err}");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// `out_root/matrix-<input stem>.json`.
+pub fn report_path(out_root: &Path, input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "input".into());
+    out_root.join(format!("{REPORT_PREFIX}{stem}.json"))
+}
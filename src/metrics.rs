@@ -0,0 +1,165 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Job outcomes counted by `quack_check_jobs_total`.
+const OUTCOMES: [&str; 4] = ["ok", "partial", "degraded", "failed"];
+
+/// Keeps process-wide counters and rewrites them to `metrics.textfile_path` in the
+/// Prometheus text format after every change, for node-exporter's textfile collector.
+pub struct Recorder {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    jobs_in_progress: u64,
+    jobs: [u64; OUTCOMES.len()],
+    chunks_converted: u64,
+    pages_converted: u64,
+    pages_per_second: f64,
+    queue_depth: u64,
+    last_progress: f64,
+}
+
+impl Recorder {
+    /// `None` when `metrics.textfile_path` is empty.
+    pub fn from_config(cfg: &Config) -> Option<Self> {
+        let path = cfg.metrics.textfile_path.trim();
+        if path.is_empty() {
+            return None;
+        }
+        let recorder = Self {
+            path: PathBuf::from(path),
+            state: Mutex::default(),
+        };
+        recorder.update(|s| s.last_progress = now());
+        Some(recorder)
+    }
+
+    /// Inputs waiting behind the current one (batch mode).
+    pub fn set_queue_depth(&self, depth: usize) {
+        self.update(|s| s.queue_depth = depth as u64);
+    }
+
+    pub fn job_started(&self) {
+        self.update(|s| {
+            s.jobs_in_progress += 1;
+            s.last_progress = now();
+        });
+    }
+
+    pub fn chunk_converted(&self, pages: u32) {
+        self.update(|s| {
+            s.chunks_converted += 1;
+            s.pages_converted += u64::from(pages);
+            s.last_progress = now();
+        });
+    }
+
+    /// `outcome` is "ok", "partial", "degraded", or "failed"; `pages` were converted in `elapsed`.
+    pub fn job_finished(&self, outcome: &str, pages: u32, elapsed: Duration) {
+        self.update(|s| {
+            s.jobs_in_progress = s.jobs_in_progress.saturating_sub(1);
+            if let Some(i) = OUTCOMES.iter().position(|o| *o == outcome) {
+                s.jobs[i] += 1;
+            }
+            if pages > 0 && !elapsed.is_zero() {
+                s.pages_per_second = f64::from(pages) / elapsed.as_secs_f64();
+            }
+            s.last_progress = now();
+        });
+    }
+
+    /// The current metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let s = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP quack_check_{name} {help}");
+            let _ = writeln!(out, "# TYPE quack_check_{name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "quack_check_{name}{labels} {value}");
+            }
+        };
+        let plain = |value: String| vec![(String::new(), value)];
+        metric(
+            "jobs_in_progress",
+            "gauge",
+            "Jobs currently running.",
+            &plain(s.jobs_in_progress.to_string()),
+        );
+        let jobs: Vec<(String, String)> = OUTCOMES
+            .iter()
+            .zip(s.jobs)
+            .map(|(o, n)| (format!("{{outcome=\"{o}\"}}"), n.to_string()))
+            .collect();
+        metric("jobs_total", "counter", "Finished jobs by outcome.", &jobs);
+        metric(
+            "chunks_converted_total",
+            "counter",
+            "Chunks converted.",
+            &plain(s.chunks_converted.to_string()),
+        );
+        metric(
+            "pages_converted_total",
+            "counter",
+            "Pages in converted chunks.",
+            &plain(s.pages_converted.to_string()),
+        );
+        metric(
+            "pages_per_second",
+            "gauge",
+            "Conversion rate of the last finished job.",
+            &plain(format!("{:.3}", s.pages_per_second)),
+        );
+        metric(
+            "queue_depth",
+            "gauge",
+            "Inputs waiting to be converted.",
+            &plain(s.queue_depth.to_string()),
+        );
+        metric(
+            "last_progress_timestamp_seconds",
+            "gauge",
+            "Unix time a job last started, converted a chunk, or finished.",
+            &plain(format!("{:.0}", s.last_progress)),
+        );
+        out
+    }
+
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        change(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()));
+        if let Err(err) = self.write() {
+            warn!(
+                "could not write metrics to {}: {err:#}",
+                self.path.display()
+            );
+        }
+    }
+
+    /// Write to a temporary file and rename it over the old one, so the collector never
+    /// reads a half-written file.
+    fn write(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, self.render())?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
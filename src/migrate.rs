@@ -0,0 +1,258 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// One upgrade step for configs written against an earlier layout. Keys are dotted
+/// paths from the top of the file.
+#[derive(Debug, Clone, Copy)]
+pub enum Rule {
+    /// `from` moved to `to`; the value is kept. A value already at `to` wins.
+    Rename {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// A single string where a list is now expected.
+    StringToList { key: &'static str },
+    /// Still parsed, but ignored or scheduled for removal; the key is left in place.
+    Deprecated {
+        key: &'static str,
+        note: &'static str,
+    },
+    /// The default changed; configs that never set the key now get `new` where they
+    /// used to get `old`.
+    DefaultChanged {
+        key: &'static str,
+        old: &'static str,
+        new: &'static str,
+    },
+}
+
+/// Every layout change since the first release, oldest first. Add an entry whenever a
+/// key is renamed, moved, reshaped, retired, or gets a new default.
+pub const RULES: &[Rule] = &[
+    Rule::StringToList {
+        key: "docling.backend.pdf_backend",
+    },
+    Rule::Deprecated {
+        key: "chunking.keep_split_pdfs",
+        note: "use retention.split_pdfs",
+    },
+];
+
+/// Tables whose keys are user-chosen, so they are not checked for unknown keys.
+const FREE_FORM: [&str; 3] = [
+    "docling.env",
+    "docling.tier_overrides",
+    "docling.table_chunks.overrides",
+];
+
+/// A key whose value the migration changed. `None` means absent on that side.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub key: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Migration {
+    pub changes: Vec<Change>,
+    pub warnings: Vec<String>,
+    /// The upgraded config as TOML. Comments from the original are not carried over.
+    pub migrated: String,
+}
+
+impl Migration {
+    /// `-`/`+` lines per changed key.
+    pub fn diff(&self) -> String {
+        let mut out = String::new();
+        for change in &self.changes {
+            let _ = writeln!(out, "# {}", change.reason);
+            if let Some(before) = &change.before {
+                let _ = writeln!(out, "- {} = {before}", change.key);
+            }
+            if let Some(after) = &change.after {
+                let _ = writeln!(out, "+ {} = {after}", change.key);
+            }
+        }
+        out
+    }
+
+    /// Diff and warnings, for the terminal.
+    pub fn summary(&self) -> String {
+        let mut out = if self.changes.is_empty() {
+            "config is up to date\n".to_string()
+        } else {
+            self.diff()
+        };
+        for warning in &self.warnings {
+            let _ = writeln!(out, "warning: {warning}");
+        }
+        out
+    }
+}
+
+pub fn migrate(raw: &str) -> Result<Migration> {
+    migrate_with(raw, RULES)
+}
+
+/// Apply `rules` to a config's TOML, then check the result against the current schema.
+pub fn migrate_with(raw: &str, rules: &[Rule]) -> Result<Migration> {
+    let mut root: toml::Table = toml::from_str(raw).context("parsing TOML")?;
+    let mut changes = Vec::new();
+    let mut warnings = Vec::new();
+
+    for rule in rules {
+        match *rule {
+            Rule::Rename { from, to } => {
+                let Some(value) = remove(&mut root, from) else {
+                    continue;
+                };
+                if let Some(existing) = get(&root, to) {
+                    warnings.push(format!(
+                        "{from} was renamed to {to}; both are set, keeping {to} = {existing}"
+                    ));
+                    changes.push(Change {
+                        key: from.into(),
+                        before: Some(value.to_string()),
+                        after: None,
+                        reason: format!("{from} was renamed to {to}"),
+                    });
+                    continue;
+                }
+                let shown = value.to_string();
+                insert(&mut root, to, value)?;
+                changes.push(Change {
+                    key: from.into(),
+                    before: Some(shown.clone()),
+                    after: None,
+                    reason: format!("{from} was renamed to {to}"),
+                });
+                changes.push(Change {
+                    key: to.into(),
+                    before: None,
+                    after: Some(shown),
+                    reason: format!("{from} was renamed to {to}"),
+                });
+            }
+            Rule::StringToList { key } => {
+                if let Some(toml::Value::String(s)) = get(&root, key) {
+                    let list = toml::Value::Array(vec![toml::Value::String(s.clone())]);
+                    changes.push(Change {
+                        key: key.into(),
+                        before: Some(toml::Value::String(s.clone()).to_string()),
+                        after: Some(list.to_string()),
+                        reason: format!("{key} is a list now"),
+                    });
+                    insert(&mut root, key, list)?;
+                }
+            }
+            Rule::Deprecated { key, note } => {
+                if get(&root, key).is_some() {
+                    warnings.push(format!("{key} is deprecated: {note}"));
+                }
+            }
+            Rule::DefaultChanged { key, old, new } => {
+                if get(&root, key).is_none() {
+                    warnings.push(format!(
+                        "{key} is not set; its default changed from {old} to {new} (set it to {old} to keep the old behavior)"
+                    ));
+                }
+            }
+        }
+    }
+
+    let known = toml::Value::try_from(Config::default()).context("serialize default config")?;
+    unknown_keys(&toml::Value::Table(root.clone()), &known, "", &mut warnings);
+
+    let migrated = toml::to_string_pretty(&root).context("serialize migrated config")?;
+    toml::from_str::<Config>(&migrated).context("the migrated config does not parse")?;
+    Ok(Migration {
+        changes,
+        warnings,
+        migrated,
+    })
+}
+
+/// Keys the current schema does not know; serde ignores them, so a stale or misspelled
+/// key otherwise has no effect without any notice.
+fn unknown_keys(value: &toml::Value, known: &toml::Value, prefix: &str, out: &mut Vec<String>) {
+    let (Some(table), Some(known)) = (value.as_table(), known.as_table()) else {
+        return;
+    };
+    for (key, child) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        if FREE_FORM.contains(&path.as_str()) {
+            continue;
+        }
+        match known.get(key) {
+            Some(known_child) => unknown_keys(child, known_child, &path, out),
+            None => out.push(format!("unknown key {path} is ignored")),
+        }
+    }
+}
+
+fn get<'a>(root: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let mut parts = key.split('.');
+    let mut node = root.get(parts.next()?)?;
+    for part in parts {
+        node = node.as_table()?.get(part)?;
+    }
+    Some(node)
+}
+
+fn remove(root: &mut toml::Table, key: &str) -> Option<toml::Value> {
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (Some(parent), last),
+        None => (None, key),
+    };
+    let table = match parent {
+        Some(parent) => table_mut(root, parent, false).ok()??,
+        None => root,
+    };
+    table.remove(last)
+}
+
+fn insert(root: &mut toml::Table, key: &str, value: toml::Value) -> Result<()> {
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (Some(parent), last),
+        None => (None, key),
+    };
+    let table = match parent {
+        Some(parent) => {
+            table_mut(root, parent, true)?.ok_or_else(|| anyhow!("cannot create table {parent}"))?
+        }
+        None => root,
+    };
+    table.insert(last.to_string(), value);
+    Ok(())
+}
+
+/// The table at `path`, created along the way when `create` is set. Errors when a
+/// segment exists but is not a table.
+fn table_mut<'a>(
+    root: &'a mut toml::Table,
+    path: &str,
+    create: bool,
+) -> Result<Option<&'a mut toml::Table>> {
+    let mut node = root;
+    for part in path.split('.') {
+        if !node.contains_key(part) {
+            if !create {
+                return Ok(None);
+            }
+            node.insert(part.to_string(), toml::Value::Table(toml::Table::new()));
+        }
+        node = node
+            .get_mut(part)
+            .and_then(toml::Value::as_table_mut)
+            .ok_or_else(|| anyhow!("{part} in {path} is not a table"))?;
+    }
+    Ok(Some(node))
+}
@@ -0,0 +1,87 @@
+use crate::{
+    config::{Config, Mirror},
+    report::JobReport,
+    template,
+    util::{ensure_dir, now_rfc3339},
+};
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+pub use crate::template::expand;
+
+/// Job metadata available to `output.mirrors` path templates as `{name}`.
+pub fn template_vars(
+    job_id: &str,
+    input: &Path,
+    report: &JobReport,
+) -> BTreeMap<&'static str, String> {
+    let mut vars = template::job_vars(input, job_id);
+    vars.extend([
+        ("tier", report.decision.tier.config_key().to_string()),
+        ("engine", report.decision.chosen_engine.clone()),
+        ("pages", report.input.page_count.to_string()),
+        ("date", now_rfc3339().chars().take(10).collect()),
+    ]);
+    vars
+}
+
+/// Copy or hardlink `job_dir/final/` into every configured mirror. Returns the
+/// destination directories.
+pub fn mirror_final_outputs(
+    cfg: &Config,
+    job_dir: &Path,
+    vars: &BTreeMap<&'static str, String>,
+) -> Result<Vec<PathBuf>> {
+    let final_dir = job_dir.join("final");
+    let mut dests = Vec::new();
+    for mirror in &cfg.output.mirrors {
+        let dest = PathBuf::from(expand(&mirror.path, vars)?);
+        mirror_dir(mirror, &final_dir, &dest)
+            .with_context(|| format!("mirror final outputs to {}", dest.display()))?;
+        info!(
+            "mirrored final outputs to {} ({})",
+            dest.display(),
+            mirror.mode
+        );
+        dests.push(dest);
+    }
+    Ok(dests)
+}
+
+fn mirror_dir(mirror: &Mirror, src: &Path, dest: &Path) -> Result<()> {
+    if !matches!(mirror.mode.as_str(), "copy" | "hardlink") {
+        return Err(anyhow!(
+            "unknown output.mirrors mode: {} (expected copy or hardlink)",
+            mirror.mode
+        ));
+    }
+    ensure_dir(dest)?;
+    for entry in std::fs::read_dir(src).with_context(|| format!("read_dir {}", src.display()))? {
+        let path = entry?.path();
+        let target = dest.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            mirror_dir(mirror, &path, &target)?;
+            continue;
+        }
+        if target.exists() {
+            std::fs::remove_file(&target)
+                .with_context(|| format!("replace {}", target.display()))?;
+        }
+        if mirror.mode == "hardlink" {
+            match std::fs::hard_link(&path, &target) {
+                Ok(()) => continue,
+                // Typically a different filesystem; a copy still publishes the file.
+                Err(err) => warn!(
+                    "hardlink {} -> {} failed ({err}); copying instead",
+                    path.display(),
+                    target.display()
+                ),
+            }
+        }
+        std::fs::copy(&path, &target)
+            .with_context(|| format!("copy {} -> {}", path.display(), target.display()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use crate::{config::Config, script};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Chunks with fewer words than this are not scored; the ratio would be noise.
+pub const MIN_WORDS: usize = 20;
+
+/// Tesseract language for each script the OCR could be missing, used when
+/// `docling.ocr_retry.auto_detect` is on. Latin has none: which Latin-script language
+/// a passage is in cannot be told from its letters.
+pub const SCRIPT_LANGS: &[(&str, &str)] = &[
+    ("greek", "ell"),
+    ("cyrillic", "rus"),
+    ("hebrew", "heb"),
+    ("arabic", "ara"),
+    ("devanagari", "hin"),
+    ("thai", "tha"),
+    ("han", "chi_sim"),
+    ("kana", "jpn"),
+    ("hangul", "kor"),
+];
+
+/// Share of a chunk's letters a script needs before its language is added.
+const MIN_SCRIPT_SHARE: f32 = 0.02;
+
+/// Both attempts of a chunk retried with other OCR languages, recorded in its meta.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrLangRetry {
+    pub first_langs: Vec<String>,
+    pub first_unknown_word_ratio: f32,
+    pub retry_langs: Vec<String>,
+    /// `None` when the retry failed.
+    pub retry_unknown_word_ratio: Option<f32>,
+    /// "first" | "retry"
+    pub kept: String,
+}
+
+/// Words of a word list, one per line, lowercased.
+pub fn load_dictionary(path: &Path) -> Result<HashSet<String>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("reading docling.ocr_retry.dictionary {}", path.display()))?;
+    Ok(raw
+        .lines()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// Share of the words in `text` that are not in `dictionary`, or that do not look like
+/// words at all when there is no dictionary. `None` below [`MIN_WORDS`] words.
+pub fn unknown_word_ratio(text: &str, dictionary: Option<&HashSet<String>>) -> Option<f32> {
+    let mut words = 0usize;
+    let mut unknown = 0usize;
+    for token in text.split_whitespace() {
+        if token.starts_with("<!--") || token.starts_with('|') {
+            continue;
+        }
+        let word = token.trim_matches(|c: char| !c.is_alphanumeric());
+        if word.chars().filter(|c| c.is_alphabetic()).count() < 2 {
+            continue;
+        }
+        words += 1;
+        let known = match dictionary {
+            Some(dict) => in_dictionary(dict, word),
+            None => looks_like_a_word(word),
+        };
+        if !known {
+            unknown += 1;
+        }
+    }
+    (words >= MIN_WORDS).then(|| unknown as f32 / words as f32)
+}
+
+fn in_dictionary(dict: &HashSet<String>, word: &str) -> bool {
+    let lower = word.to_lowercase();
+    let base = lower
+        .strip_suffix("'s")
+        .or_else(|| lower.strip_suffix("’s"))
+        .unwrap_or(&lower);
+    dict.contains(base) || base.split('-').all(|part| dict.contains(part))
+}
+
+/// Whether OCR output plausibly is a word: letters of one script, no digits inside,
+/// a vowel in Latin words, no letter repeated three times running, and no case
+/// switching mid-word. Misread text in the wrong language fails most of these.
+pub fn looks_like_a_word(word: &str) -> bool {
+    if word.chars().count() > 30 {
+        return false;
+    }
+    let mut scripts = HashSet::new();
+    let mut run = 0;
+    let mut prev = None;
+    let mut case_switches = 0;
+    let mut prev_lower = false;
+    for c in word.chars() {
+        if c.is_alphabetic() {
+            if let Some(s) = script::script_of(c) {
+                scripts.insert(s);
+            }
+            if c.is_uppercase() && prev_lower {
+                case_switches += 1;
+            }
+            prev_lower = c.is_lowercase();
+        } else if !matches!(c, '\'' | '’' | '-') {
+            return false;
+        }
+        run = if prev == Some(c) { run + 1 } else { 1 };
+        if run >= 3 && c.is_alphabetic() {
+            return false;
+        }
+        prev = Some(c);
+    }
+    if scripts.len() > 1 || case_switches > 1 {
+        return false;
+    }
+    if scripts.contains("latin") && word.chars().count() >= 3 {
+        return word
+            .to_lowercase()
+            .chars()
+            .any(|c| "aeiouyàáâãäåæèéêëìíîïòóôõöøœùúûüýÿ".contains(c));
+    }
+    true
+}
+
+/// The languages to retry a chunk with: `current`, then `secondary_langs`, then (with
+/// `auto_detect` and a Tesseract engine) the language of each script in `text` that
+/// none of them covers.
+pub fn retry_langs(cfg: &Config, current: &[String], text: &str) -> Vec<String> {
+    let retry = &cfg.docling.ocr_retry;
+    let mut langs: Vec<String> = current.to_vec();
+    for lang in &retry.secondary_langs {
+        if !langs.contains(lang) {
+            langs.push(lang.clone());
+        }
+    }
+    let tesseract = matches!(
+        cfg.docling.ocr.engine.as_str(),
+        "tesseract" | "tesseract_cli"
+    );
+    if retry.auto_detect && tesseract {
+        for lang in detected_langs(text) {
+            if !langs.iter().any(|l| l == lang) {
+                langs.push(lang.to_string());
+            }
+        }
+    }
+    langs
+}
+
+fn detected_langs(text: &str) -> Vec<&'static str> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for s in text.chars().filter_map(script::script_of) {
+        *counts.entry(s).or_default() += 1;
+    }
+    let total: usize = counts.values().sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    SCRIPT_LANGS
+        .iter()
+        .filter(|(s, _)| {
+            counts.get(s).copied().unwrap_or(0) as f32 / total as f32 >= MIN_SCRIPT_SHARE
+        })
+        .map(|(_, lang)| *lang)
+        .collect()
+}
@@ -0,0 +1,283 @@
+use crate::{
+    engine::{ConvertMeta, PageError},
+    postprocess::{is_marker_line, parse_page_marker},
+    textstats::{self, Vocabulary},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+pub const PAGE_QUALITY_FILE: &str = "page_quality.json";
+pub const PAGE_QUALITY_SVG: &str = "page_quality.svg";
+
+/// Pages with less text than this fraction of the document's median are scored down.
+const LOW_TEXT_FRACTION: f32 = 0.5;
+/// Garbage ratio at which a page's text counts as entirely unreadable.
+const GARBAGE_CEILING: f32 = 0.1;
+/// Garbage ratio clean text gets from the noise model's prior; not held against a page.
+const GARBAGE_FLOOR: f32 = 0.01;
+/// Pages listed under `worst` in the report.
+const WORST_PAGES: usize = 20;
+
+/// How trustworthy one page of the transcript looks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageQuality {
+    pub page: u32,
+    /// Non-whitespace characters, when the engine marked this page's extent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chars: Option<usize>,
+    /// Estimated share of the characters that are OCR noise ([`textstats`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub garbage_ratio: Option<f32>,
+    /// Docling's OCR confidence, or its mean confidence when no OCR score exists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_confidence: Option<f32>,
+    /// Where the text came from under hybrid OCR: "text", "ocr", "mixed", or "empty".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 0 (worst) to 1; absent when the page was not converted or cannot be measured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+/// `final/page_quality.json`: one entry per PDF page, for heatmaps and proofreading.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageQualityReport {
+    pub page_count: u32,
+    pub median_chars: usize,
+    /// Lowest-scoring pages, worst first.
+    pub worst: Vec<u32>,
+    pub pages: Vec<PageQuality>,
+}
+
+/// What hybrid OCR (`docling.ocr.hybrid`) did across the document, recorded under
+/// `hybrid_ocr` in the report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HybridOcrStats {
+    /// Pages read from the text layer alone.
+    pub text_pages: u32,
+    pub ocr_pages: u32,
+    pub mixed_pages: u32,
+    pub empty_pages: u32,
+    /// Every page OCR contributed text to, in order.
+    pub ocr_applied: Vec<u32>,
+}
+
+/// Tally the pages' hybrid OCR modes; `None` when no engine reported any.
+pub fn hybrid_ocr_stats(report: &PageQualityReport) -> Option<HybridOcrStats> {
+    let mut stats = HybridOcrStats::default();
+    let mut any = false;
+    for page in &report.pages {
+        let Some(mode) = &page.ocr_mode else {
+            continue;
+        };
+        any = true;
+        match mode.as_str() {
+            "text" => stats.text_pages += 1,
+            "ocr" => stats.ocr_pages += 1,
+            "mixed" => stats.mixed_pages += 1,
+            _ => stats.empty_pages += 1,
+        }
+        if matches!(mode.as_str(), "ocr" | "mixed") {
+            stats.ocr_applied.push(page.page);
+        }
+    }
+    any.then_some(stats)
+}
+
+/// Raw per-page measurements for one converted chunk. `markdown` carries page markers;
+/// pages are only measured individually when the engine marked every page break.
+pub fn measure_chunk(
+    start_page: u32,
+    end_page: u32,
+    markdown: &str,
+    meta: &ConvertMeta,
+    page_errors: &[PageError],
+) -> Vec<PageQuality> {
+    let segments = page_texts(markdown);
+    let measured = segments.len() as u32 == end_page - start_page + 1;
+    // The whole chunk's words; a page alone has too few to tell a misreading.
+    let vocabulary = Vocabulary::train(markdown);
+    // Confidence and OCR mode pages are numbered within the PDF the engine opened.
+    let offset = if meta.use_page_range == Some(true) {
+        0
+    } else {
+        start_page - 1
+    };
+    (start_page..=end_page)
+        .map(|page| {
+            let text = segments
+                .iter()
+                .find(|(p, _)| *p == page)
+                .filter(|_| measured)
+                .map(|(_, text)| text.as_str());
+            let confidence = meta
+                .page_confidence
+                .iter()
+                .find(|c| c.page + offset == page)
+                .and_then(|c| c.ocr_score.or(c.mean_score));
+            let ocr_mode = meta
+                .page_ocr
+                .iter()
+                .find(|o| o.page + offset == page)
+                .map(|o| o.mode.clone());
+            let warnings = page_errors
+                .iter()
+                .filter(|e| e.page == page)
+                .map(|e| format!("unreadable: {}", e.reason))
+                .collect();
+            PageQuality {
+                page,
+                chars: text.map(|t| t.chars().filter(|c| !c.is_whitespace()).count()),
+                garbage_ratio: text
+                    .map(|t| round(textstats::analyze_with(t, &vocabulary).garbage_ratio())),
+                ocr_confidence: confidence,
+                ocr_mode,
+                warnings,
+                score: None,
+            }
+        })
+        .collect()
+}
+
+/// Score measured pages against the document's median and fill in pages that were
+/// never converted.
+pub fn score_pages(mut measured: Vec<PageQuality>, page_count: u32) -> PageQualityReport {
+    measured.sort_by_key(|p| p.page);
+    let mut chars: Vec<usize> = measured.iter().filter_map(|p| p.chars).collect();
+    chars.sort_unstable();
+    let median_chars = chars.get(chars.len() / 2).copied().unwrap_or(0);
+
+    let mut pages: Vec<PageQuality> = Vec::with_capacity(page_count as usize);
+    let mut measured = measured.into_iter().peekable();
+    for page in 1..=page_count {
+        let mut entry = match measured.next_if(|p| p.page == page) {
+            Some(entry) => entry,
+            None => PageQuality {
+                page,
+                chars: None,
+                garbage_ratio: None,
+                ocr_confidence: None,
+                ocr_mode: None,
+                warnings: vec!["not converted".into()],
+                score: None,
+            },
+        };
+        entry.score = score(&mut entry, median_chars);
+        pages.push(entry);
+    }
+
+    let mut ranked: Vec<(u32, f32)> = pages
+        .iter()
+        .filter_map(|p| p.score.map(|s| (p.page, s)))
+        .filter(|(_, s)| *s < 1.0)
+        .collect();
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+    PageQualityReport {
+        page_count,
+        median_chars,
+        worst: ranked.iter().take(WORST_PAGES).map(|(p, _)| *p).collect(),
+        pages,
+    }
+}
+
+fn score(page: &mut PageQuality, median_chars: usize) -> Option<f32> {
+    if page.warnings.iter().any(|w| w.starts_with("unreadable")) {
+        return Some(0.0);
+    }
+    let confidence = page.ocr_confidence.map(|c| c.clamp(0.0, 1.0));
+    let Some(chars) = page.chars else {
+        return confidence.map(round);
+    };
+    let expected = median_chars as f32 * LOW_TEXT_FRACTION;
+    let text = if expected > 0.0 {
+        (chars as f32 / expected).min(1.0)
+    } else {
+        1.0
+    };
+    if text < 1.0 {
+        page.warnings.push(format!(
+            "little text: {chars} chars (median {median_chars})"
+        ));
+    }
+    let garbage = page.garbage_ratio.unwrap_or(0.0);
+    if garbage > GARBAGE_FLOOR {
+        page.warnings
+            .push(format!("garbage characters: {:.1}%", garbage * 100.0));
+    }
+    let clean =
+        1.0 - ((garbage - GARBAGE_FLOOR).max(0.0) / (GARBAGE_CEILING - GARBAGE_FLOOR)).min(1.0);
+    Some(round(text * clean * confidence.unwrap_or(1.0)))
+}
+
+fn round(v: f32) -> f32 {
+    (v * 1000.0).round() / 1000.0
+}
+
+/// Each marked page's text, without marker and placeholder comment lines.
+fn page_texts(md: &str) -> Vec<(u32, String)> {
+    let mut out: Vec<(u32, String)> = Vec::new();
+    for line in md.lines() {
+        if let Some(page) = parse_page_marker(line) {
+            out.push((page, String::new()));
+        } else if let Some((_, text)) = out.last_mut()
+            && !is_marker_line(line)
+        {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    out
+}
+
+/// A grid of page cells from red (score 0) through yellow to green (1); grey cells
+/// could not be scored. Each cell's tooltip names the page, score and warnings.
+pub fn render_svg(report: &PageQualityReport) -> String {
+    const COLUMNS: u32 = 50;
+    const CELL: u32 = 12;
+    let rows = report.page_count.div_ceil(COLUMNS).max(1);
+    let (width, height) = (COLUMNS * CELL, rows * CELL);
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    for page in &report.pages {
+        let index = page.page - 1;
+        let (x, y) = ((index % COLUMNS) * CELL, (index / COLUMNS) * CELL);
+        let fill = page.score.map(color).unwrap_or_else(|| "#bbbbbb".into());
+        let mut title = match page.score {
+            Some(score) => format!("page {}: {score:.2}", page.page),
+            None => format!("page {}: no score", page.page),
+        };
+        for warning in &page.warnings {
+            title.push_str("; ");
+            title.push_str(warning);
+        }
+        let _ = writeln!(
+            out,
+            r##"  <rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" fill="{fill}" stroke="#ffffff"><title>{}</title></rect>"##,
+            escape(&title)
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn color(score: f32) -> String {
+    let s = score.clamp(0.0, 1.0);
+    let (r, g) = if s < 0.5 {
+        (255.0, 510.0 * s)
+    } else {
+        (510.0 * (1.0 - s), 255.0)
+    };
+    format!("#{:02x}{:02x}40", r.round() as u8, g.round() as u8)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
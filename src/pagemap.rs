@@ -0,0 +1,129 @@
+use crate::{
+    pagenum::PageNumberStats,
+    postprocess::{is_marker_line, parse_half_page_marker, parse_page_marker},
+};
+use serde::{Deserialize, Serialize};
+
+pub const PAGEMAP_FILE: &str = "pagemap.json";
+
+/// Where each paragraph of the merged transcript came from, for quoting it with page
+/// references. Offsets count characters (Unicode scalar values) from the start of the
+/// transcript, end exclusive, as it stands before `[output.encoding]` is applied.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageMap {
+    pub paragraphs: Vec<MappedParagraph>,
+    pub pages: Vec<MappedPage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedParagraph {
+    /// Position among the mapped paragraphs, from 0.
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    /// Index of the chunk that converted the page.
+    pub chunk: Option<u32>,
+    pub page: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub printed_page: Option<String>,
+    /// The book page, when two-up pages were split (`input.split_spreads`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_page: Option<u32>,
+}
+
+/// A page's stretch of the transcript, from its first paragraph to its last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedPage {
+    pub page: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub printed_page: Option<String>,
+    pub chunk: Option<u32>,
+    pub start: usize,
+    pub end: usize,
+    pub first_paragraph: usize,
+    pub last_paragraph: usize,
+}
+
+/// Map `transcript` by the page markers in `marked`, the page-marked markdown it was
+/// finished from. Every postprocess stage keeps the markers in place, and finishing
+/// only drops or relabels marker lines and adds a notice up front, so each paragraph
+/// of `marked` is found verbatim, in order, in `transcript`. `chunks` holds each
+/// chunk's index and page range. Text before the first page marker is not mapped.
+pub fn build(
+    marked: &str,
+    transcript: &str,
+    chunks: &[(u32, u32, u32)],
+    page_numbers: &PageNumberStats,
+) -> PageMap {
+    let mut map = PageMap::default();
+    let mut page: Option<u32> = None;
+    let mut half_page: Option<u32> = None;
+    let mut lines: Vec<&str> = Vec::new();
+    // Byte and character position just past the last paragraph found.
+    let mut cursor = (0usize, 0usize);
+    let mut flush = |lines: &mut Vec<&str>, page: Option<u32>, half_page, map: &mut PageMap| {
+        let text = lines.join("\n");
+        lines.clear();
+        let Some(page) = page else { return };
+        let Some(found) = transcript[cursor.0..].find(&text) else {
+            return;
+        };
+        let start = cursor.1 + transcript[cursor.0..cursor.0 + found].chars().count();
+        let end = start + text.chars().count();
+        cursor = (cursor.0 + found + text.len(), end);
+        map.paragraphs.push(MappedParagraph {
+            index: map.paragraphs.len(),
+            start,
+            end,
+            chunk: chunks
+                .iter()
+                .find(|&&(_, a, b)| (a..=b).contains(&page))
+                .map(|&(index, _, _)| index),
+            page,
+            printed_page: page_numbers.printed(page).map(str::to_string),
+            half_page,
+        });
+    };
+    for line in marked.lines() {
+        if line.trim().is_empty() || is_marker_line(line) {
+            if !lines.is_empty() {
+                flush(&mut lines, page, half_page, &mut map);
+            }
+            if let Some(p) = parse_page_marker(line) {
+                page = Some(p);
+                half_page = None;
+            } else if let Some((half, _)) = parse_half_page_marker(line) {
+                half_page = Some(half);
+            }
+            continue;
+        }
+        lines.push(line);
+    }
+    if !lines.is_empty() {
+        flush(&mut lines, page, half_page, &mut map);
+    }
+    map.pages = pages(&map.paragraphs);
+    map
+}
+
+fn pages(paragraphs: &[MappedParagraph]) -> Vec<MappedPage> {
+    let mut pages: Vec<MappedPage> = Vec::new();
+    for p in paragraphs {
+        match pages.last_mut() {
+            Some(last) if last.page == p.page => {
+                last.end = p.end;
+                last.last_paragraph = p.index;
+            }
+            _ => pages.push(MappedPage {
+                page: p.page,
+                printed_page: p.printed_page.clone(),
+                chunk: p.chunk,
+                start: p.start,
+                end: p.end,
+                first_paragraph: p.index,
+                last_paragraph: p.index,
+            }),
+        }
+    }
+    pages
+}
@@ -0,0 +1,302 @@
+use crate::postprocess::{is_marker_line, parse_page_marker};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Pages on each side whose numbers must agree before a candidate is trusted.
+const SUPPORT_WINDOW: u32 = 6;
+/// Non-blank lines at the top and bottom of a page searched for a page number.
+const EDGE_LINES: usize = 3;
+
+/// The printed page number found (or inferred) for one PDF page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrintedPage {
+    pub pdf_page: u32,
+    pub printed: String,
+    /// "detected" from a header/footer line, or "inferred" from the numbered pages
+    /// around it.
+    pub source: String,
+}
+
+/// The `pdf_page → printed_page` table, recorded under `page_numbers` in the report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageNumberStats {
+    pub detected: usize,
+    pub inferred: usize,
+    pub unnumbered: usize,
+    pub pages: Vec<PrintedPage>,
+}
+
+impl PageNumberStats {
+    pub fn printed(&self, pdf_page: u32) -> Option<&str> {
+        self.pages
+            .binary_search_by_key(&pdf_page, |p| p.pdf_page)
+            .ok()
+            .map(|i| self.pages[i].printed.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Number {
+    value: u32,
+    roman: bool,
+}
+
+impl Number {
+    fn offset(self, pdf_page: u32) -> i64 {
+        i64::from(self.value) - i64::from(pdf_page)
+    }
+
+    fn label(self) -> String {
+        if self.roman {
+            to_roman(self.value)
+        } else {
+            self.value.to_string()
+        }
+    }
+}
+
+/// Find printed page numbers in page-marked markdown (before repeated-line removal
+/// strips the headers and footers they live in).
+///
+/// Each page's first and last few lines are searched for a bare number, `- 12 -`,
+/// `Page 12`, a lower-case roman numeral, or a number at either end of a running
+/// header. A candidate only counts when a nearby page carries a number with the same
+/// offset from its PDF index, which filters out numbers that happen to end a
+/// paragraph. Pages between two detected pages with the same offset (chapter openers,
+/// blank versos) are filled in as inferred. Pages are only considered when the engine
+/// marked every page break, so a chunk without breaks is never mistaken for one page.
+pub fn detect_printed_page_numbers(md: &str, page_count: u32) -> PageNumberStats {
+    let patterns = Patterns::new();
+    let segments = page_segments(md, page_count);
+    let candidates: BTreeMap<u32, Vec<Number>> = segments
+        .iter()
+        .map(|(page, lines)| (*page, patterns.candidates(lines)))
+        .filter(|(_, c)| !c.is_empty())
+        .collect();
+
+    let mut detected: BTreeMap<u32, Number> = BTreeMap::new();
+    for (&page, cands) in &candidates {
+        let best = cands
+            .iter()
+            .map(|c| (support(&candidates, page, *c), *c))
+            .filter(|(s, _)| *s > 0)
+            .max_by_key(|(s, _)| *s);
+        if let Some((_, number)) = best {
+            detected.insert(page, number);
+        }
+    }
+
+    let mut pages = Vec::new();
+    let mut inferred = 0;
+    let mut prev: Option<(u32, Number)> = None;
+    let numbered: Vec<(u32, Number)> = detected.iter().map(|(p, n)| (*p, *n)).collect();
+    let mut next_idx = 0;
+    for page in 1..=page_count {
+        while next_idx < numbered.len() && numbered[next_idx].0 < page {
+            next_idx += 1;
+        }
+        if let Some(number) = detected.get(&page) {
+            pages.push(PrintedPage {
+                pdf_page: page,
+                printed: number.label(),
+                source: "detected".into(),
+            });
+            prev = Some((page, *number));
+            continue;
+        }
+        let Some((p, before)) = prev else { continue };
+        let Some(&(_, after)) = numbered.get(next_idx) else {
+            continue;
+        };
+        let offset = before.offset(p);
+        let value = i64::from(page) + offset;
+        if before.roman == after.roman && after.offset(numbered[next_idx].0) == offset && value > 0
+        {
+            let number = Number {
+                value: value as u32,
+                roman: before.roman,
+            };
+            pages.push(PrintedPage {
+                pdf_page: page,
+                printed: number.label(),
+                source: "inferred".into(),
+            });
+            inferred += 1;
+        }
+    }
+
+    PageNumberStats {
+        detected: detected.len(),
+        inferred,
+        unnumbered: page_count as usize - pages.len(),
+        pages,
+    }
+}
+
+/// How many other pages within `SUPPORT_WINDOW` carry a number of the same kind at the
+/// same offset.
+fn support(candidates: &BTreeMap<u32, Vec<Number>>, page: u32, number: Number) -> usize {
+    let lo = page.saturating_sub(SUPPORT_WINDOW);
+    candidates
+        .range(lo..=page + SUPPORT_WINDOW)
+        .filter(|(p, _)| **p != page)
+        .filter(|(p, cands)| {
+            cands
+                .iter()
+                .any(|c| c.roman == number.roman && c.offset(**p) == number.offset(page))
+        })
+        .count()
+}
+
+/// The lines of every page whose extent is known: its marker is followed by the next
+/// page's marker, or it is the document's last page.
+fn page_segments(md: &str, page_count: u32) -> Vec<(u32, Vec<&str>)> {
+    let mut segments: Vec<(u32, Vec<&str>)> = Vec::new();
+    for line in md.lines() {
+        if let Some(page) = parse_page_marker(line) {
+            segments.push((page, Vec::new()));
+        } else if let Some((_, lines)) = segments.last_mut() {
+            lines.push(line);
+        }
+    }
+    let mut whole = Vec::with_capacity(segments.len());
+    for i in 0..segments.len() {
+        let page = segments[i].0;
+        let complete = match segments.get(i + 1) {
+            Some((next, _)) => *next == page + 1,
+            None => page == page_count,
+        };
+        if complete {
+            whole.push((page, std::mem::take(&mut segments[i].1)));
+        }
+    }
+    whole
+}
+
+struct Patterns {
+    bare: Regex,
+    labeled: Regex,
+    header_leading: Regex,
+    header_trailing: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        Self {
+            bare: Regex::new(r"^[-–—]?\s*(\d{1,4}|[ivxlcdm]{1,7})\s*[-–—]?$").expect("bare regex"),
+            labeled: Regex::new(r"(?i)^(?:page|p\.)\s*(\d{1,4}|[ivxlcdm]{1,7})$")
+                .expect("labeled regex"),
+            header_leading: Regex::new(r"^(\d{1,4})\s*(?:[|·•]\s*\S.*|\s[A-Z][A-Z .,'’:-]{3,})$")
+                .expect("leading header regex"),
+            header_trailing: Regex::new(r"^(?:\S.*[|·•]|[A-Z][A-Z .,'’:-]{3,}\s)\s*(\d{1,4})$")
+                .expect("trailing header regex"),
+        }
+    }
+
+    fn candidates(&self, lines: &[&str]) -> Vec<Number> {
+        let content: Vec<&str> = lines
+            .iter()
+            .map(|l| l.trim().trim_matches(['*', '_', '#', ' ']))
+            .filter(|l| !l.is_empty() && !is_marker_line(l))
+            .collect();
+        let head = content.iter().take(EDGE_LINES);
+        let tail = content.iter().rev().take(EDGE_LINES);
+        let mut out: Vec<Number> = Vec::new();
+        for line in head.chain(tail) {
+            if let Some(number) = self.parse(line)
+                && !out.contains(&number)
+            {
+                out.push(number);
+            }
+        }
+        out
+    }
+
+    fn parse(&self, line: &str) -> Option<Number> {
+        let token = [
+            &self.bare,
+            &self.labeled,
+            &self.header_leading,
+            &self.header_trailing,
+        ]
+        .iter()
+        .find_map(|re| re.captures(line))?
+        .get(1)?
+        .as_str()
+        .to_ascii_lowercase();
+        match token.parse::<u32>() {
+            Ok(0) => None,
+            Ok(value) => Some(Number {
+                value,
+                roman: false,
+            }),
+            Err(_) => from_roman(&token).map(|value| Number { value, roman: true }),
+        }
+    }
+}
+
+const ROMAN: [(u32, &str); 13] = [
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+fn to_roman(mut value: u32) -> String {
+    let mut out = String::new();
+    for (n, s) in ROMAN {
+        while value >= n {
+            out.push_str(s);
+            value -= n;
+        }
+    }
+    out
+}
+
+/// Parse a lower-case roman numeral, rejecting non-canonical spellings ("iiii", "mid").
+fn from_roman(s: &str) -> Option<u32> {
+    let mut rest = s;
+    let mut value = 0;
+    for (n, sym) in ROMAN {
+        let mut repeats = 0;
+        while let Some(r) = rest.strip_prefix(sym) {
+            rest = r;
+            value += n;
+            repeats += 1;
+            if repeats > 3 {
+                return None;
+            }
+        }
+    }
+    (rest.is_empty() && value > 0 && to_roman(value) == s).then_some(value)
+}
+
+/// Rewrite each `<!-- quack:page N -->` as `<!-- quack:page <printed> (pdf N) -->`,
+/// with `-` for pages that have no printed number.
+pub fn relabel_page_markers(md: &str, stats: &PageNumberStats) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in md.lines() {
+        match parse_page_marker(line) {
+            Some(page) => out.push(crate::postprocess::printed_page_marker(
+                stats.printed(page).unwrap_or("-"),
+                page,
+            )),
+            None => out.push(line.to_string()),
+        }
+    }
+    let mut joined = out.join("\n");
+    if md.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
@@ -0,0 +1,62 @@
+use crate::util::now_rfc3339;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Written to the job directory by `jobs pause`; removed when the job next finishes.
+pub const PAUSE_FILE: &str = "pause.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseState {
+    /// "paused": the job starts no more chunks, and runs of it stop at once.
+    /// "resumed": the next run keeps the chunks converted before the pause.
+    pub state: String,
+    pub since: String,
+}
+
+pub fn load(job_dir: &Path) -> Option<PauseState> {
+    let raw = std::fs::read_to_string(job_dir.join(PAUSE_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn is_paused(job_dir: &Path) -> bool {
+    load(job_dir).is_some_and(|p| p.state == "paused")
+}
+
+pub fn is_resuming(job_dir: &Path) -> bool {
+    load(job_dir).is_some_and(|p| p.state == "resumed")
+}
+
+/// Pause the job in `job_dir`. A run in progress finishes the chunks it has started
+/// and stops before the next.
+pub fn pause(job_dir: &Path) -> Result<PauseState> {
+    if !job_dir.join(crate::pipeline::JOB_MANIFEST_FILE).is_file() {
+        return Err(anyhow!("not a started job: {}", job_dir.display()));
+    }
+    write(job_dir, "paused")
+}
+
+/// Let a paused job run again; `None` if it was not paused.
+pub fn resume(job_dir: &Path) -> Result<Option<PauseState>> {
+    if !is_paused(job_dir) {
+        return Ok(None);
+    }
+    write(job_dir, "resumed").map(Some)
+}
+
+/// Drop the state once the job has finished.
+pub fn clear(job_dir: &Path) {
+    let _ = std::fs::remove_file(job_dir.join(PAUSE_FILE));
+}
+
+fn write(job_dir: &Path, state: &str) -> Result<PauseState> {
+    let pause = PauseState {
+        state: state.to_string(),
+        since: now_rfc3339(),
+    };
+    std::fs::write(
+        job_dir.join(PAUSE_FILE),
+        serde_json::to_string_pretty(&pause)?,
+    )?;
+    Ok(pause)
+}
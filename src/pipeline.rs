@@ -1,43 +1,242 @@
 use crate::{
-    chunk_plan::ChunkPlan,
-    config::Config,
-    engine::{ConvertIn, Engine},
-    policy,
-    postprocess,
-    probe,
-    report::{ChunkReport, JobReport},
-    util::ensure_dir,
+    accelerator::{self, AcceleratorUse},
+    attempts::{self, Attempt, AttemptLog, ChunkFailed},
+    blank_pages,
+    cancel::{self, CancellationToken, JobControl},
+    chunk_plan::{ChunkPlan, PageRange},
+    chunk_stage::ChunkInfo,
+    config::{Config, DoclingOcr},
+    config_hash, content,
+    duplicate_pages::{self, DuplicatePageStats},
+    encoding,
+    engine::{limits, ConvertIn, ConvertMeta, ConvertOut, Engine, PageError},
+    exclusions::{self, PageExclusion},
+    exit_code::ErrorClass,
+    governor::{Governor, HostLoad},
+    hooks::{NoHooks, PipelineHooks},
+    lint,
+    ocr_retry::{self, OcrLangRetry},
+    page_quality::{self, PageQualityReport},
+    pagemap::{self, PageMap},
+    pagenum::{self, PageNumberStats},
+    pause,
+    policy::{self, PolicyDecision, QualityTier},
+    postprocess::{self, EmbeddedImage, EmbeddedImageStats, PostprocessContext},
+    probe::{self, ProbeCache, ProbeResult},
+    provenance,
+    render::{self, PageImage, Renderer},
+    report::{
+        ChunkReport, Completeness, JobReport, CONVERSION_FAILED, PARTIAL_REPORT_FILE,
+        SKIPPED_DUE_TO_BUDGET,
+    },
+    retention,
+    reuse::ReuseSource,
+    script,
+    sections::{self, Section, SplitSpec},
+    spreads,
+    terms::{self, TermsReport},
+    tiles,
+    tokens::{TokenCounter, TokenStats},
+    util::{ensure_dir, hash_file, now_rfc3339},
+    xref::{self, XrefStats},
 };
 use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::{mpsc, OnceLock};
+use std::time::{Instant, SystemTime};
 use tracing::{debug, info, warn};
 
 pub struct Pipeline<E: Engine> {
     cfg: Config,
     engine: E,
+    probe_cache: Option<ProbeCache>,
+    reuse_from: Option<ReuseSource>,
+    plan: Option<ChunkPlan>,
+    /// quack-check's and the engine scripts' versions, for chunk provenance.
+    versions: BTreeMap<String, String>,
+    hooks: Box<dyn PipelineHooks>,
+    /// `docling.ocr_retry.dictionary`, read on first use.
+    ocr_dictionary: OnceLock<HashSet<String>>,
 }
 
 pub struct JobOutput {
     pub markdown: String,
     pub text: String,
     pub report: JobReport,
+    pub images: Vec<EmbeddedImage>,
+    pub sections: Vec<Section>,
+    pub page_quality: PageQualityReport,
+    pub terms: TermsReport,
+    /// Each converted chunk's stretch of the transcript, with `output.write_chunk_markdown`.
+    pub chunks: Vec<ChunkMarkdown>,
+    /// Thumbnails and flagged pages (`output.thumbnails`, `output.flagged_page_images`).
+    pub page_images: Vec<PageImage>,
+    /// Transcript offsets back to pages, with `output.write_pagemap`.
+    pub pagemap: PageMap,
+}
+
+/// One chunk's part of the final transcript, written to `final/chunks/`.
+#[derive(Debug, Clone)]
+pub struct ChunkMarkdown {
+    /// `0001_pages-0001-0040.md`, numbered from 1.
+    pub file: String,
+    pub markdown: String,
 }
 
 impl<E: Engine> Pipeline<E> {
     pub fn new(cfg: &Config, engine: E) -> Self {
+        Self::new_with_hooks(cfg, engine, NoHooks)
+    }
+
+    /// A pipeline that calls `hooks` around each stage.
+    pub fn new_with_hooks(cfg: &Config, engine: E, hooks: impl PipelineHooks + 'static) -> Self {
+        let mut versions = engine.script_versions();
+        versions.insert("quack-check".into(), env!("CARGO_PKG_VERSION").into());
         Self {
             cfg: cfg.clone(),
             engine,
+            probe_cache: None,
+            reuse_from: None,
+            plan: None,
+            versions,
+            hooks: Box::new(hooks),
+            ocr_dictionary: OnceLock::new(),
         }
     }
 
+    /// Reuse cached probes (e.g. from a preceding `classify`) instead of probing again.
+    pub fn with_probe_cache(mut self, cache: Option<ProbeCache>) -> Self {
+        self.probe_cache = cache;
+        self
+    }
+
+    /// Take over the unchanged chunks of an earlier job on a previous version of the
+    /// input instead of converting them again.
+    pub fn with_reuse_from(mut self, source: Option<ReuseSource>) -> Self {
+        self.reuse_from = source;
+        self
+    }
+
+    /// Convert exactly these chunks instead of planning them. The plan is checked
+    /// against the probed page count and used as given: it is neither collapsed for
+    /// small inputs nor moved around tables.
+    pub fn with_plan(mut self, plan: Option<ChunkPlan>) -> Self {
+        self.plan = plan;
+        self
+    }
+
     pub fn run_job(&self, input: &Path, job_dir: &Path) -> Result<JobOutput> {
+        self.run(input, job_dir, None, &JobControl::default())
+    }
+
+    /// Like [`Pipeline::run_job`], but stops early when `cancel` is cancelled or
+    /// `deadline` passes: between stages, before each chunk, and by killing conversions
+    /// in progress. The error then carries a [`cancel::Interrupted`] (see
+    /// [`cancel::interrupted`]). Nothing is written to `final/`.
+    pub fn run_job_with(
+        &self,
+        input: &Path,
+        job_dir: &Path,
+        cancel: CancellationToken,
+        deadline: Option<Instant>,
+    ) -> Result<JobOutput> {
+        self.run(input, job_dir, None, &JobControl::new(cancel, deadline))
+    }
+
+    /// Like [`Pipeline::run_job`], but each chunk is handed to `sink` as soon as it is
+    /// converted, while later chunks are still pending. An error from the sink fails the
+    /// job.
+    pub fn run_with_sink<S: ChunkSink>(
+        &self,
+        input: &Path,
+        job_dir: &Path,
+        sink: &mut S,
+    ) -> Result<JobOutput> {
+        self.run(input, job_dir, Some(sink), &JobControl::default())
+    }
+
+    fn run(
+        &self,
+        input: &Path,
+        job_dir: &Path,
+        mut sink: Option<&mut dyn ChunkSink>,
+        control: &JobControl,
+    ) -> Result<JobOutput> {
         let started = Instant::now();
+        let (split_spec, counter, renderer) = self.checked_config().context(ErrorClass::Config)?;
+        let on_page_error = self.cfg.limits.on_page_error.as_str();
+        if pause::is_paused(job_dir) {
+            return Err(cancel::Interrupted::Paused.into());
+        }
 
-        let probe_res = probe::probe_pdf(&self.cfg, &self.engine, input)?;
-        let decision = policy::decide(&self.cfg, &probe_res);
-        let mut plan = ChunkPlan::from_probe(&self.cfg, &probe_res)?;
+        let mut probe_res = match &self.probe_cache {
+            Some(cache) => cache.probe(&self.cfg, &self.engine, input),
+            None => probe::probe_pdf(&self.cfg, &self.engine, input),
+        }
+        .context(ErrorClass::Probe)?;
+        self.hooks.on_probe(&mut probe_res)?;
+        control.check()?;
+        let mut decision = policy::decide(&self.cfg, &probe_res);
+        self.hooks.on_decision(&probe_res, &mut decision)?;
+        if let Some(mismatch) = &decision.tier_mismatch {
+            warn!("{mismatch}");
+        }
+        if decision.split_spreads {
+            info!("converting each page as two half-pages (input.split_spreads)");
+        } else if spreads::looks_two_up(&probe_res) {
+            warn!(
+                "{} of {} sampled pages look like two-up spreads; \
+                 set input.split_spreads = \"auto\" to convert each as two pages",
+                probe_res.structure.spread_pages, probe_res.sample.sampled_pages
+            );
+        }
+        let oversized = tiles::oversized(&self.cfg, &probe_res);
+        for t in &decision.tiled_pages {
+            info!("cutting page {} into {}x{} tiles for OCR", t.page, t.rows, t.cols);
+        }
+        if decision.tiled_pages.is_empty()
+            && let Some(&(page, mp)) = oversized.iter().max_by(|a, b| a.1.total_cmp(&b.1))
+        {
+            warn!(
+                "{} page(s) exceed input.max_page_megapixels = {} when rendered for OCR \
+                 (largest: page {page} at {mp:.0} MP) and may be cut off or run out of \
+                 memory; set input.tile_oversized_pages = true to convert them as tiles",
+                oversized.len(),
+                self.cfg.input.max_page_megapixels
+            );
+        }
+        let excluded_pages = exclusions::resolve(&self.cfg, probe_res.input.page_count)?;
+        for range in &excluded_pages {
+            info!(
+                "excluding pages {}-{}{}",
+                range.start_page,
+                range.end_page,
+                if range.reason.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", range.reason)
+                }
+            );
+        }
+        let accelerator = accelerator::preflight(&self.cfg, &self.engine, &mut decision, control)?;
+        // A job moved to CPU is planned in smaller chunks.
+        let cpu_cfg = accelerator
+            .as_ref()
+            .filter(|a| a.fell_back())
+            .map(|_| accelerator::cpu_chunking(&self.cfg));
+        let plan_cfg = cpu_cfg.as_ref().unwrap_or(&self.cfg);
+        let mut plan = match &self.plan {
+            Some(given) => {
+                given
+                    .validate_excluding(probe_res.input.page_count, &excluded_pages)
+                    .context("invalid chunk plan")?;
+                given.clone()
+            }
+            None => ChunkPlan::for_job(plan_cfg, &probe_res)
+                .without_pages(plan_cfg, &excluded_pages),
+        };
 
         info!(
             "probe page_count={} file_bytes={} avg_chars={} garbage_ratio={} whitespace_ratio={}",
@@ -47,10 +246,21 @@ impl<E: Engine> Pipeline<E> {
             probe_res.sample.garbage_ratio,
             probe_res.sample.whitespace_ratio
         );
+        info!(
+            "probe structure text_layer={} fonts={} images_per_page={:.2} annotations={} form_fields={}",
+            probe_res.structure.has_text_layer,
+            probe_res.structure.font_count,
+            probe_res.structure.images_per_page,
+            probe_res.structure.annotation_count,
+            probe_res.structure.form_field_count
+        );
         info!(
             "policy tier={:?} engine={} do_ocr={}",
             decision.tier, decision.chosen_engine, decision.do_ocr
         );
+        if !decision.pipeline_overrides.is_empty() {
+            info!("policy pipeline_overrides={:?}", decision.pipeline_overrides);
+        }
         debug!(?plan, "chunk plan");
 
         if decision.chosen_engine == "native_text" && self.cfg.native_text.backend != "python_pypdf"
@@ -60,18 +270,23 @@ impl<E: Engine> Pipeline<E> {
                 self.cfg.native_text.backend
             ));
         }
+        self.preflight_models(&decision)?;
+        let stripped = self.apply_pdf_features(input, job_dir, &decision)?;
+        let input = stripped.as_deref().unwrap_or(input);
 
-        let require_chunking = probe_res.input.page_count > self.cfg.limits.require_chunking_over_pages
-            || probe_res.input.file_bytes > self.cfg.limits.require_chunking_over_bytes;
-
-        if !require_chunking && plan.chunks.len() > 1 {
-            plan = ChunkPlan::single(plan.page_count, &self.cfg.chunking.strategy);
-        }
-
-        if self.cfg.global.max_parallel_chunks > 1 {
-            warn!(
-                "max_parallel_chunks > 1 is configured, but pipeline runs sequentially in this build"
-            );
+        if self.plan.is_some() {
+            let maxp = self.cfg.chunking.max_pages_per_chunk;
+            info!("using the given chunk plan: {} chunk(s)", plan.chunks.len());
+            for (i, chunk) in plan.chunks.iter().enumerate() {
+                if chunk.end_page - chunk.start_page + 1 > maxp {
+                    warn!(
+                        "chunk {i} (pages {}-{}) exceeds chunking.max_pages_per_chunk={maxp}",
+                        chunk.start_page, chunk.end_page
+                    );
+                }
+            }
+        } else {
+            plan.refine_boundaries(&self.cfg, &self.engine, input);
         }
 
         let chunks_dir = job_dir.join("chunks");
@@ -91,106 +306,852 @@ impl<E: Engine> Pipeline<E> {
             }
         };
 
-        let mut chunk_reports = Vec::new();
-        let mut markdown_parts = Vec::new();
-
-        for (i, ch) in chunk_inputs.iter().enumerate() {
-            if self.cfg.limits.job_timeout_seconds > 0
-                && started.elapsed().as_secs() > self.cfg.limits.job_timeout_seconds
-            {
-                return Err(anyhow!(
-                    "job timeout exceeded: {}s",
-                    self.cfg.limits.job_timeout_seconds
-                ));
+        control.check()?;
+        let page_hashes = if self.cfg.global.record_page_hashes || self.reuse_from.is_some() {
+            match self.engine.page_hashes(input) {
+                Ok(hashes) => hashes,
+                Err(err) if self.reuse_from.is_some() => return Err(err.context("hash pages")),
+                Err(err) => {
+                    warn!("could not hash pages: {err:#}");
+                    Vec::new()
+                }
             }
+        } else {
+            Vec::new()
+        };
+        let mut reused = match &self.reuse_from {
+            Some(source) => {
+                let ranges: Vec<PageRange> = chunk_inputs
+                    .iter()
+                    .map(|ch| PageRange {
+                        start_page: ch.start_page,
+                        end_page: ch.end_page,
+                    })
+                    .collect();
+                let reused = source.reusable(&decision, &page_hashes, &ranges);
+                info!(
+                    "reuse: {} of {} chunk(s) unchanged since job {}",
+                    reused.len(),
+                    chunk_inputs.len(),
+                    source.job_id
+                );
+                reused
+            }
+            None => BTreeMap::new(),
+        };
+        if pause::is_resuming(job_dir) {
+            resume_records(job_dir, &chunk_inputs, &mut reused);
+        }
 
-            info!(
-                "chunk {} pages {}-{} input={}",
-                i,
-                ch.start_page,
-                ch.end_page,
-                ch.input_pdf.display()
-            );
+        ensure_dir(job_dir)?;
+        let manifest = JobManifest {
+            probe: probe_res,
+            decision,
+            plan,
+            page_hashes,
+            excluded_pages,
+            accelerator,
+        };
+        std::fs::write(
+            job_dir.join(JOB_MANIFEST_FILE),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+        let decision = &manifest.decision;
+        self.write_partial_report(&counter, job_dir, &manifest, &[], chunk_inputs.len());
 
-            let req = ConvertIn {
-                input_pdf: ch.input_pdf.display().to_string(),
-                out_dir: chunks_dir.display().to_string(),
-                chunk_index: i as u32,
-                start_page: ch.start_page,
-                end_page: ch.end_page,
-                do_ocr: decision.do_ocr,
-                pdf_backend: self.cfg.docling.backend.pdf_backend.clone(),
-                use_page_range: ch.use_page_range,
-            };
+        let mut records = Vec::new();
+        let mut skipped = Vec::new();
+        let allowed_failures = self.cfg.limits.allowed_chunk_failures(chunk_inputs.len());
+        let mut failed = 0;
+        let budget = self.cfg.limits.soft_budget(manifest.probe.input.page_count);
+        let converting = Instant::now();
+        let mut streamed_images = EmbeddedImageStats::default();
+        let postprocess_ctx = PostprocessContext::new(&self.cfg)?;
+        let mut paused = false;
 
-            let mut used_fallback = false;
-            let mut out = match decision.chosen_engine.as_str() {
-                "docling" => self.engine.convert_docling(&req),
-                "native_text" => self.engine.convert_native_text(&req),
-                other => Err(anyhow!("unknown engine: {other}")),
-            };
+        let mut governor = Governor::new(
+            self.cfg.global.max_parallel_chunks,
+            &self.cfg.global.governor,
+        );
+        let convert = |i: usize, ch: &ChunkInput| match reused.get(&i) {
+            Some(record) => Ok(record.clone()),
+            None => {
+                control.check()?;
+                self.convert_chunk(i, ch, input, decision, job_dir, control)
+            }
+        };
 
-            if matches!(decision.chosen_engine.as_str(), "native_text") {
-                let needs_fallback = match &out {
-                    Ok(o) => !o.ok
-                        || o.warnings.iter().any(|w| w.contains("missing pypdf import")),
-                    Err(e) => e.to_string().contains("missing pypdf import"),
-                };
-
-                if needs_fallback {
-                    warn!("native_text failed; falling back to docling for chunk {}", i);
-                    out = self.engine.convert_docling(&req);
-                    used_fallback = true;
+        // Conversions run on scoped threads, up to the governor's limit at a time, and
+        // their results are taken in chunk order. With one chunk in flight this is the
+        // plain sequential loop.
+        std::thread::scope(|scope| -> Result<()> {
+            let (tx, rx) = mpsc::channel();
+            let mut pending = BTreeMap::new();
+            let mut next = 0;
+            let mut in_flight = 0;
+            let mut stopped = false;
+            loop {
+                if !stopped && next < chunk_inputs.len() {
+                    let before = governor.current();
+                    let limit = if in_flight == 0 && before == 1 {
+                        1
+                    } else {
+                        let load = HostLoad::sample();
+                        let limit = governor.limit(&load, in_flight);
+                        if limit != before {
+                            info!(
+                                "parallel chunk limit {before} -> {limit} (mem_available={:?} load_1m={:?} cpus={})",
+                                load.mem_available_bytes, load.load_avg_1m, load.cpus
+                            );
+                        }
+                        limit
+                    };
+                    while next < chunk_inputs.len() && in_flight < limit {
+                        if self.cfg.limits.job_timeout_seconds > 0
+                            && started.elapsed().as_secs() > self.cfg.limits.job_timeout_seconds
+                        {
+                            return Err(anyhow!(
+                                "job timeout exceeded: {}s",
+                                self.cfg.limits.job_timeout_seconds
+                            ));
+                        }
+                        if pause::is_paused(job_dir) {
+                            info!("job paused; starting no more chunks after chunk {next}");
+                            paused = true;
+                            stopped = true;
+                            break;
+                        }
+                        let (i, ch) = (next, &chunk_inputs[next]);
+                        if governor.current() == 1 {
+                            let _ = tx.send((i, convert(i, ch)));
+                        } else {
+                            let tx = tx.clone();
+                            let convert = &convert;
+                            scope.spawn(move || {
+                                let _ = tx.send((i, convert(i, ch)));
+                            });
+                        }
+                        next += 1;
+                        in_flight += 1;
+                    }
                 }
-            }
+                if in_flight == 0 {
+                    return Ok(());
+                }
+
+                let (k, result) = rx.recv().context("chunk conversion thread vanished")?;
+                in_flight -= 1;
+                pending.insert(k, result);
+                while let Some(result) = pending.remove(&records.len()) {
+                    let i = records.len();
+                    let mut record = match result {
+                        Ok(record) => record,
+                        // Cancellation and deadlines stop the job whatever the allowance.
+                        Err(err)
+                            if failed < allowed_failures
+                                && cancel::interrupted(&err).is_none() =>
+                        {
+                            failed += 1;
+                            warn!(
+                                "chunk {i} failed ({failed} of {allowed_failures} allowed); keeping placeholders: {err:#}"
+                            );
+                            failed_chunk_record(i, &chunk_inputs[i], &err)
+                        }
+                        Err(err) => return Err(err.context(ErrorClass::Conversion)),
+                    };
+                    self.hooks.after_chunk(&mut record)?;
+
+                    if self.cfg.output.write_chunk_json {
+                        std::fs::write(
+                            chunks_dir.join(chunk_record_file(i as u32)),
+                            serde_json::to_string_pretty(&record)?,
+                        )?;
+                    }
+
+                    if !record.page_errors.is_empty() && on_page_error == "fail" {
+                        return Err(anyhow!(
+                            "chunk {} has unreadable pages: {:?}",
+                            i,
+                            record.page_errors.iter().map(|e| e.page).collect::<Vec<_>>()
+                        ));
+                    }
 
-            let mut out = out.with_context(|| format!("convert failed for chunk {}", i))?;
+                    if let Some(sink) = sink.as_deref_mut() {
+                        let chunk = streamed_chunk(
+                            &self.cfg,
+                            &postprocess_ctx,
+                            decision,
+                            manifest.probe.input.page_count,
+                            chunk_inputs.len() as u32,
+                            &record,
+                            &mut streamed_images,
+                        )?;
+                        sink.chunk(chunk)
+                            .with_context(|| format!("chunk sink failed on chunk {i}"))?;
+                    }
+
+                    records.push(record);
+                    self.write_partial_report(
+                        &counter,
+                        job_dir,
+                        &manifest,
+                        &records,
+                        chunk_inputs.len(),
+                    );
 
-            if !out.ok {
-                return Err(anyhow!("chunk {} failed; warnings={:?}", i, out.warnings));
+                    // Soft budget: stop starting chunks once the pace so far projects past
+                    // it, and emit what was converted rather than failing the job.
+                    let remaining = &chunk_inputs[next..];
+                    if let Some(budget) = budget
+                        && !stopped
+                        && !remaining.is_empty()
+                    {
+                        let done: u32 = chunk_inputs[..=i].iter().map(ChunkInput::pages).sum();
+                        let left: u32 = remaining.iter().map(ChunkInput::pages).sum();
+                        let per_page = converting.elapsed().as_secs_f64() / f64::from(done.max(1));
+                        let projected = started.elapsed().as_secs_f64() + per_page * f64::from(left);
+                        if projected > budget {
+                            warn!(
+                                "time budget {budget:.0}s would be exceeded (projected {projected:.0}s); skipping {} chunk(s), pages {}-{}",
+                                remaining.len(),
+                                remaining[0].start_page,
+                                remaining[remaining.len() - 1].end_page
+                            );
+                            skipped = remaining
+                                .iter()
+                                .enumerate()
+                                .map(|(k, ch)| skipped_chunk_report((next + k) as u32, ch))
+                                .collect();
+                            stopped = true;
+                        }
+                    }
+                }
             }
+        })?;
+        if paused {
+            return Err(cancel::Interrupted::Paused.into());
+        }
+        // A table-chunk rerun that was stopped keeps its first conversion; don't
+        // assemble a job that was interrupted meanwhile.
+        control.check()?;
 
-            if used_fallback {
-                out.warnings
-                    .push("native_text failed; fell back to docling".to_string());
+        self.hooks.before_merge(&mut records)?;
+        let mut output = assemble(
+            &self.cfg,
+            &counter,
+            split_spec.as_ref(),
+            manifest,
+            records,
+            skipped,
+        )?;
+        if let Some(renderer) = &renderer {
+            // The transcript is done; missing page images only warrant a warning.
+            match render::page_images(&self.cfg, renderer, input, &output.page_quality) {
+                Ok(images) => output.page_images = images,
+                Err(err) => warn!("could not render page images: {err:#}"),
             }
+        }
+        self.hooks.after_merge(&mut output)?;
+
+        if !self.cfg.global.keep_intermediates {
+            self.cleanup_intermediates(job_dir)?;
+        }
+        pause::clear(job_dir);
 
-            if self.cfg.output.write_chunk_json {
-                let chunk_json_path = chunks_dir.join(format!("chunk_{:05}.json", i));
-                std::fs::write(&chunk_json_path, serde_json::to_string_pretty(&out)?)?;
+        Ok(output)
+    }
+
+    /// Check the settings the job reads as it goes, before it starts, and build what
+    /// they name: the final split, the tokenizer, and the page renderer.
+    fn checked_config(&self) -> Result<(Option<SplitSpec>, TokenCounter, Option<Renderer>)> {
+        let split_spec = SplitSpec::parse(&self.cfg.output.split_final_by)?;
+        let counter = TokenCounter::new(&self.cfg.output.tokenizer)?;
+        let on_page_error = self.cfg.limits.on_page_error.as_str();
+        if !matches!(on_page_error, "fail" | "skip" | "placeholder") {
+            return Err(anyhow!("unknown limits.on_page_error: {on_page_error}"));
+        }
+        if self.cfg.docling.backend.pdf_backend.is_empty() {
+            return Err(anyhow!("docling.backend.pdf_backend must name at least one backend"));
+        }
+        policy::validate_tier_overrides(&self.cfg)?;
+        policy::validate_pdf_features(&self.cfg)?;
+        retention::validate(&self.cfg)?;
+        accelerator::validate(&self.cfg)?;
+        encoding::validate(&self.cfg)?;
+        spreads::validate(&self.cfg)?;
+        tiles::validate(&self.cfg)?;
+        // Up front, so a build without pdfium fails before converting anything.
+        let renderer = if render::wants_page_images(&self.cfg) {
+            Some(Renderer::new(&self.cfg)?)
+        } else {
+            None
+        };
+        Ok((split_spec, counter, renderer))
+    }
+
+    /// Refresh `final/report.partial.json` with the chunks converted so far, when
+    /// `output.write_report_json` is on. A monitor may read it at any time, so it is
+    /// written beside and renamed over; failures only warn.
+    fn write_partial_report(
+        &self,
+        counter: &TokenCounter,
+        job_dir: &Path,
+        manifest: &JobManifest,
+        records: &[ChunkRecord],
+        chunk_count: usize,
+    ) {
+        if !self.cfg.output.write_report_json {
+            return;
+        }
+        let report = progress_report(counter, manifest, records, chunk_count);
+        let final_dir = job_dir.join("final");
+        let path = final_dir.join(PARTIAL_REPORT_FILE);
+        let tmp = final_dir.join(format!("{PARTIAL_REPORT_FILE}.tmp"));
+        let written = ensure_dir(&final_dir)
+            .and_then(|_| Ok(serde_json::to_string_pretty(&report)?))
+            .and_then(|json| Ok(std::fs::write(&tmp, json)?))
+            .and_then(|_| Ok(std::fs::rename(&tmp, &path)?));
+        if let Err(err) = written {
+            warn!("could not write {}: {err:#}", path.display());
+        }
+    }
+
+    /// Convert one chunk with the engine the policy chose, falling back to docling
+    /// when native text extraction fails and re-running table-dominant chunks when
+    /// `docling.table_chunks.mode = "route"`. Safe to call from several threads.
+    fn convert_chunk(
+        &self,
+        i: usize,
+        ch: &ChunkInput,
+        input: &Path,
+        decision: &PolicyDecision,
+        job_dir: &Path,
+        control: &JobControl,
+    ) -> Result<ChunkRecord> {
+        let on_page_error = self.cfg.limits.on_page_error.as_str();
+        let chunks_dir = job_dir.join("chunks");
+        let started_at = now_rfc3339();
+        info!(
+            "chunk {} pages {}-{} input={}",
+            i,
+            ch.start_page,
+            ch.end_page,
+            ch.input_pdf.display()
+        );
+
+        let stderr_log = if self.cfg.debug.keep_python_stderr {
+            let logs_dir = job_dir.join("logs");
+            ensure_dir(&logs_dir)?;
+            let path = logs_dir.join(chunk_stderr_file(i as u32));
+            // Start fresh on resume; attempts within this run append.
+            let _ = std::fs::remove_file(&path);
+            Some(path)
+        } else {
+            None
+        };
+
+        let mut req = ConvertIn {
+            input_pdf: ch.input_pdf.display().to_string(),
+            out_dir: chunks_dir.display().to_string(),
+            chunk_index: i as u32,
+            start_page: ch.start_page,
+            end_page: ch.end_page,
+            do_ocr: decision.do_ocr,
+            pdf_backend: self.cfg.docling.backend.pdf_backend[0].clone(),
+            require_pdf_backend: false,
+            use_page_range: ch.use_page_range,
+            page_break_placeholder: postprocess::PAGE_BREAK_PLACEHOLDER.to_string(),
+            split_spreads: decision.split_spreads,
+            tiles: decision
+                .tiled_pages
+                .iter()
+                .filter(|t| (ch.start_page..=ch.end_page).contains(&t.page))
+                .copied()
+                .collect(),
+            on_page_error: on_page_error.to_string(),
+            pipeline_overrides: decision.pipeline_overrides.clone(),
+            stderr_log: stderr_log.clone(),
+            control: control.clone(),
+            attempts: AttemptLog::default(),
+        };
+        self.hooks.before_chunk(&mut req)?;
+
+        let mut used_fallback = false;
+        let offset = attempts::log_offset(req.stderr_log.as_deref());
+        let mut out = match decision.chosen_engine.as_str() {
+            "docling" => self.convert_docling_shrinking(input, &req),
+            "native_text" => self.engine.convert_native_text(&req),
+            other => Err(anyhow!("unknown engine: {other}")),
+        };
+
+        if decision.chosen_engine == "native_text" && policy::native_text_needs_fallback(&out) {
+            warn!("native_text failed; falling back to docling for chunk {}", i);
+            let pages = (req.start_page, req.end_page);
+            let attempt = match &out {
+                Ok(out) => Attempt::from_warnings("native_text", pages, &out.warnings),
+                Err(err) => Attempt::from_error("native_text", pages, err),
+            };
+            req.attempts
+                .push(attempt.with_stderr_log(req.stderr_log.as_deref(), offset));
+            out = self.convert_docling_shrinking(input, &req);
+            used_fallback = true;
+        }
+
+        let mut out = out.with_context(|| ChunkFailed {
+            message: match &stderr_log {
+                Some(p) if p.is_file() => {
+                    format!("convert failed for chunk {} (stderr: {})", i, p.display())
+                }
+                _ => format!("convert failed for chunk {}", i),
+            },
+            attempts: req.attempts.snapshot(),
+        })?;
+
+        if !out.ok {
+            return Err(anyhow::Error::new(ChunkFailed {
+                message: format!("chunk {} failed; warnings={:?}", i, out.warnings),
+                attempts: req.attempts.snapshot(),
+            }));
+        }
+
+        if used_fallback {
+            out.warnings
+                .push("native_text failed; fell back to docling".to_string());
+            out.meta.engine = Some("docling".to_string());
+        }
+
+        let mut content_type = content::chunk_content_type(&self.cfg, &out.meta, ch.pages());
+        if content_type == Some(content::TABLE)
+            && self.cfg.docling.table_chunks.mode == "route"
+            && (decision.chosen_engine == "docling" || used_fallback)
+        {
+            out = self.convert_table_chunk(input, &req, out);
+            content_type = content::chunk_content_type(&self.cfg, &out.meta, ch.pages())
+                .or(content_type);
+        }
+
+        if self.cfg.docling.ocr_retry.enabled
+            && (decision.chosen_engine == "docling" || used_fallback)
+            && out.meta.ocr_applied.unwrap_or(req.do_ocr)
+        {
+            out = self.retry_ocr_langs(input, &req, out)?;
+        }
+
+        let page_errors = collect_page_errors(ch, &out.meta);
+        let provenance = self.provenance(ch, &req, &out.meta, decision, started_at);
+        Ok(ChunkRecord {
+            chunk_index: i as u32,
+            start_page: ch.start_page,
+            end_page: ch.end_page,
+            page_errors,
+            stderr_log: stderr_log
+                .filter(|p| p.is_file())
+                .map(|_| format!("logs/{}", chunk_stderr_file(i as u32))),
+            content_type: content_type.map(str::to_string),
+            provenance: Some(provenance),
+            attempts: req.attempts.snapshot(),
+            out,
+        })
+    }
+
+    fn provenance(
+        &self,
+        ch: &ChunkInput,
+        req: &ConvertIn,
+        meta: &ConvertMeta,
+        decision: &PolicyDecision,
+        started_at: String,
+    ) -> ChunkProvenance {
+        let engine = meta
+            .engine
+            .clone()
+            .unwrap_or_else(|| decision.chosen_engine.clone());
+        let do_ocr = engine == "docling" && meta.ocr_applied.unwrap_or(req.do_ocr);
+        let mut versions = self.versions.clone();
+        if let Some(docling) = meta.extra.get("docling_version").and_then(|v| v.as_str()) {
+            versions.insert("docling".into(), docling.into());
+        }
+        ChunkProvenance {
+            chunk_id: format!(
+                "{}:{}-{}",
+                &ch.input_hash[..ch.input_hash.len().min(16)],
+                ch.start_page,
+                ch.end_page
+            ),
+            input_hash: ch.input_hash.clone(),
+            hash_mode: self.cfg.hashing.mode.clone(),
+            pdf_backend: meta.pdf_backend.clone().filter(|_| engine == "docling"),
+            engine,
+            do_ocr,
+            ocr: do_ocr.then(|| self.cfg.docling.ocr.clone()),
+            config_hash: config_hash::config_hash(&self.cfg),
+            versions,
+            started_at,
+            finished_at: now_rfc3339(),
+        }
+    }
+
+    /// Act on the risky features the probe found: refuse the job, log a warning, or
+    /// have the engine write a copy without them to `input.stripped.pdf`, which is
+    /// then converted in place of the input.
+    fn apply_pdf_features(
+        &self,
+        input: &Path,
+        job_dir: &Path,
+        decision: &PolicyDecision,
+    ) -> Result<Option<PathBuf>> {
+        let with_action = |action: &str| -> Vec<String> {
+            decision
+                .pdf_features
+                .iter()
+                .filter(|f| f.action == action)
+                .map(|f| f.feature.clone())
+                .collect()
+        };
+        let refused = with_action("refuse");
+        if !refused.is_empty() {
+            return Err(anyhow!(
+                "input has PDF features refused by security.pdf_features: {}",
+                refused.join(", ")
+            ));
+        }
+        let warned = with_action("warn");
+        if !warned.is_empty() {
+            warn!("input has risky PDF features: {}", warned.join(", "));
+        }
+        let strip = with_action("strip");
+        if strip.is_empty() {
+            return Ok(None);
+        }
+        ensure_dir(job_dir)?;
+        let out = job_dir.join("input.stripped.pdf");
+        self.engine
+            .strip_pdf_features(input, &out, &strip)
+            .with_context(|| format!("stripping PDF features: {}", strip.join(", ")))?;
+        info!("stripped PDF features {} into {}", strip.join(", "), out.display());
+        Ok(Some(out))
+    }
+
+    /// Offline jobs routed to docling fail here, before any splitting, when a model the
+    /// pipeline needs is not on disk, rather than on the first chunk.
+    fn preflight_models(&self, decision: &PolicyDecision) -> Result<()> {
+        if !self.cfg.global.offline_only
+            || !self.cfg.docling.preflight_models
+            || decision.chosen_engine != "docling"
+        {
+            return Ok(());
+        }
+        let check = self
+            .engine
+            .check_models(decision.do_ocr, &decision.pipeline_overrides)
+            .context("docling model preflight")?;
+        debug!("model preflight required={:?}", check.required);
+        if check.missing.is_empty() {
+            return Ok(());
+        }
+        let missing: Vec<String> = check
+            .missing
+            .iter()
+            .map(|m| format!("  - {}: {}", m.name, m.detail))
+            .collect();
+        let mut msg = format!(
+            "offline_only=true but {} required docling model(s) are missing:\n{}",
+            check.missing.len(),
+            missing.join("\n")
+        );
+        if !check.searched.is_empty() {
+            msg.push_str(&format!("\nsearched: {}", check.searched.join(", ")));
+        }
+        if let Some(cmd) = &check.prefetch_command {
+            msg.push_str(&format!("\nprefetch them with: {cmd}"));
+        }
+        Err(anyhow!(msg))
+    }
+
+    /// Convert a table-dominant chunk again with `docling.table_chunks.overrides` on top
+    /// of the tier's. Keeps the first conversion when the second one fails.
+    fn convert_table_chunk(&self, input: &Path, req: &ConvertIn, first: ConvertOut) -> ConvertOut {
+        let mut table_req = req.clone();
+        table_req.pipeline_overrides.extend(
+            self.cfg
+                .docling
+                .table_chunks
+                .overrides
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        info!(
+            "chunk {} is table-dominant; converting again with table settings",
+            req.chunk_index
+        );
+        match self.convert_docling_shrinking(input, &table_req) {
+            Ok(mut out) if out.ok => {
+                out.warnings.splice(0..0, first.warnings);
+                out.warnings.push(
+                    "table-dominant chunk; converted with docling.table_chunks.overrides".into(),
+                );
+                out
             }
+            Ok(out) => self.keep_first_conversion(req, first, &format!("{:?}", out.warnings)),
+            Err(err) => self.keep_first_conversion(req, first, &format!("{err:#}")),
+        }
+    }
 
-            chunk_reports.push(ChunkReport {
-                chunk_index: i as u32,
-                start_page: ch.start_page,
-                end_page: ch.end_page,
-                ok: out.ok,
-                warnings: out.warnings.clone(),
-                meta: out.meta.clone(),
-            });
+    fn keep_first_conversion(
+        &self,
+        req: &ConvertIn,
+        mut first: ConvertOut,
+        why: &str,
+    ) -> ConvertOut {
+        warn!(
+            "chunk {}: conversion with table settings failed; keeping the first result: {why}",
+            req.chunk_index
+        );
+        first
+            .warnings
+            .push("table-dominant chunk; conversion with table settings failed".into());
+        first
+    }
 
-            markdown_parts.push(out.markdown);
+    /// Convert an OCR'd chunk again with more languages (`docling.ocr_retry`) when too
+    /// many of its words are unknown, and keep the attempt with fewer. A Latin quotation
+    /// read with English models comes out as gibberish that the retry can fix.
+    fn retry_ocr_langs(
+        &self,
+        input: &Path,
+        req: &ConvertIn,
+        first: ConvertOut,
+    ) -> Result<ConvertOut> {
+        let settings = &self.cfg.docling.ocr_retry;
+        let dictionary = self.ocr_dictionary()?;
+        let Some(first_ratio) = ocr_retry::unknown_word_ratio(&first.markdown, dictionary) else {
+            return Ok(first);
+        };
+        if first_ratio <= settings.max_unknown_word_ratio {
+            return Ok(first);
         }
+        let first_langs: Vec<String> = req
+            .pipeline_overrides
+            .get("ocr.langs")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_else(|| self.cfg.docling.ocr.langs.clone());
+        let retry_langs = ocr_retry::retry_langs(&self.cfg, &first_langs, &first.markdown);
+        if retry_langs == first_langs {
+            debug!(
+                "chunk {}: {first_ratio:.2} unknown words but no other OCR languages to try",
+                req.chunk_index
+            );
+            return Ok(first);
+        }
+        info!(
+            "chunk {}: {first_ratio:.2} unknown words; converting again with OCR languages {}",
+            req.chunk_index,
+            retry_langs.join("+")
+        );
+        let mut retry_req = req.clone();
+        retry_req
+            .pipeline_overrides
+            .insert("ocr.langs".into(), serde_json::json!(retry_langs));
+        let retried = match self.convert_docling_shrinking(input, &retry_req) {
+            Ok(out) if out.ok => Some(out),
+            Ok(out) => {
+                warn!(
+                    "chunk {}: OCR language retry failed: {:?}",
+                    req.chunk_index, out.warnings
+                );
+                None
+            }
+            Err(err) if cancel::interrupted(&err).is_some() => return Err(err),
+            Err(err) => {
+                warn!("chunk {}: OCR language retry failed: {err:#}", req.chunk_index);
+                None
+            }
+        };
+        let retry_ratio = retried
+            .as_ref()
+            .and_then(|out| ocr_retry::unknown_word_ratio(&out.markdown, dictionary));
+        let keep_retry = retry_ratio.is_some_and(|r| r < first_ratio);
+        let (mut out, kept) = match retried {
+            Some(mut retried) if keep_retry => {
+                retried.warnings.splice(0..0, first.warnings);
+                (retried, "retry")
+            }
+            _ => (first, "first"),
+        };
+        out.warnings.push(format!(
+            "OCR retried with {}: unknown words {first_ratio:.2} -> {}; kept the {kept} attempt",
+            retry_langs.join("+"),
+            retry_ratio.map_or("failed".to_string(), |r| format!("{r:.2}"))
+        ));
+        out.meta.ocr_retry = Some(OcrLangRetry {
+            first_langs,
+            first_unknown_word_ratio: first_ratio,
+            retry_langs,
+            retry_unknown_word_ratio: retry_ratio,
+            kept: kept.into(),
+        });
+        Ok(out)
+    }
 
-        let merged_md = postprocess::merge_markdown(&self.cfg, markdown_parts)?;
-        let merged_txt = postprocess::markdown_to_text(&self.cfg, &merged_md)?;
+    fn ocr_dictionary(&self) -> Result<Option<&HashSet<String>>> {
+        let path = &self.cfg.docling.ocr_retry.dictionary;
+        if path.is_empty() {
+            return Ok(None);
+        }
+        if let Some(words) = self.ocr_dictionary.get() {
+            return Ok(Some(words));
+        }
+        let words = ocr_retry::load_dictionary(Path::new(path))?;
+        Ok(Some(self.ocr_dictionary.get_or_init(|| words)))
+    }
 
-        if !self.cfg.global.keep_intermediates {
-            self.cleanup_intermediates(&chunk_inputs)?;
+    /// Convert with docling; when the chunk runs out of memory, convert its two halves
+    /// from the original input instead, recursing down to `chunking.oom_min_chunk_pages`.
+    /// Art books with huge page images often OOM at 40 pages but fit at 10.
+    fn convert_docling_shrinking(&self, input: &Path, req: &ConvertIn) -> Result<ConvertOut> {
+        let failure = match self.convert_docling(req) {
+            Ok(out) if out.ok || !limits::looks_like_oom(&out.warnings.join("\n")) => {
+                return Ok(out);
+            }
+            Ok(out) => out.warnings.join("; "),
+            Err(err) => {
+                let text = format!("{err:#}");
+                if !limits::looks_like_oom(&text) {
+                    return Err(err);
+                }
+                text
+            }
+        };
+
+        let min_pages = self.cfg.chunking.oom_min_chunk_pages;
+        let pages = req.end_page - req.start_page + 1;
+        if min_pages == 0 || pages <= min_pages {
+            return Err(anyhow!(
+                "chunk {} ran out of memory converting pages {}-{} (chunking.oom_min_chunk_pages={min_pages}): {failure}",
+                req.chunk_index,
+                req.start_page,
+                req.end_page
+            ));
         }
 
-        let report = JobReport {
-            input: probe_res.input,
-            sample: probe_res.sample,
-            decision,
-            chunk_reports,
+        let mid = req.start_page + pages.div_ceil(2) - 1;
+        warn!(
+            "chunk {} ran out of memory on pages {}-{}; retrying as {}-{} and {}-{}",
+            req.chunk_index,
+            req.start_page,
+            req.end_page,
+            req.start_page,
+            mid,
+            mid + 1,
+            req.end_page
+        );
+        let half = |start_page: u32, end_page: u32| -> Result<ConvertOut> {
+            let mut sub = req.clone();
+            sub.input_pdf = input.display().to_string();
+            sub.use_page_range = true;
+            sub.start_page = start_page;
+            sub.end_page = end_page;
+            let out = self.convert_docling_shrinking(input, &sub)?;
+            if out.meta.ignored("page_range") {
+                return Err(anyhow!(
+                    "chunk {} ran out of memory and cannot be shrunk: the installed docling ignores page_range",
+                    req.chunk_index
+                ));
+            }
+            Ok(out)
         };
+        let first = half(req.start_page, mid)?;
+        let second = half(mid + 1, req.end_page)?;
+        Ok(join_halves(req, first, second))
+    }
 
-        Ok(JobOutput {
-            markdown: merged_md,
-            text: merged_txt,
-            report,
-        })
+    /// Convert with each `docling.backend.pdf_backend` in turn until one succeeds; some
+    /// malformed PDFs only parse under a specific backend.
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let backends = &self.cfg.docling.backend.pdf_backend;
+        let mut failures = Vec::new();
+        for (n, backend) in backends.iter().enumerate() {
+            let last = n + 1 == backends.len();
+            let mut attempt = req.clone();
+            attempt.pdf_backend = backend.clone();
+            attempt.require_pdf_backend = !last;
+
+            let offset = attempts::log_offset(req.stderr_log.as_deref());
+            let result = self.engine.convert_docling(&attempt);
+            let pages = (req.start_page, req.end_page);
+            let failed = match &result {
+                Ok(out) if !out.ok => Some(Attempt::from_warnings("docling", pages, &out.warnings)),
+                Err(err) if cancel::interrupted(err).is_none() => {
+                    Some(Attempt::from_error("docling", pages, err))
+                }
+                _ => None,
+            };
+            if let Some(failed) = failed {
+                req.attempts.push(
+                    failed
+                        .with_backend(backend)
+                        .with_stderr_log(req.stderr_log.as_deref(), offset),
+                );
+            }
+            let failure = match result {
+                Ok(mut out) if out.ok => {
+                    self.check_page_range(&attempt, &mut out)?;
+                    if !failures.is_empty() {
+                        out.warnings.push(format!(
+                            "pdf_backend fallback: converted with {backend} after {}",
+                            failures.join("; ")
+                        ));
+                    }
+                    return Ok(out);
+                }
+                Ok(out) if last => return Ok(out),
+                Err(err) if last || cancel::interrupted(&err).is_some() => return Err(err),
+                Ok(out) => format!("{:?}", out.warnings),
+                Err(err) => format!("{err:#}"),
+            };
+            warn!(
+                "chunk {} failed with pdf_backend {backend}; trying {}: {failure}",
+                req.chunk_index,
+                backends[n + 1]
+            );
+            failures.push(format!("{backend} failed ({failure})"));
+        }
+        Err(anyhow!("docling.backend.pdf_backend is empty"))
+    }
+
+    /// Make sure a `page_range` conversion covered only the requested pages. Some
+    /// docling versions and backends drop the range and convert the whole document,
+    /// which would repeat it once per chunk in the transcript.
+    fn check_page_range(&self, req: &ConvertIn, out: &mut ConvertOut) -> Result<()> {
+        let mode = self.cfg.chunking.page_range_check.as_str();
+        if !req.use_page_range || mode == "off" {
+            return Ok(());
+        }
+        let Some(problem) = page_range_problem(req, &out.meta) else {
+            return Ok(());
+        };
+        match mode {
+            "warn" => {
+                warn!(
+                    "chunk {}: page_range {}-{} not honored: {problem}",
+                    req.chunk_index, req.start_page, req.end_page
+                );
+                out.warnings.push(format!(
+                    "page_range {}-{} not honored: {problem}",
+                    req.start_page, req.end_page
+                ));
+                Ok(())
+            }
+            "fail" => Err(anyhow!(
+                "chunk {}: page_range {}-{} not honored: {problem} (chunking.page_range_check = \"fail\"; use chunking.strategy = \"physical_split\")",
+                req.chunk_index,
+                req.start_page,
+                req.end_page
+            )),
+            other => Err(anyhow!(
+                "unknown chunking.page_range_check: {other} (expected fail, warn, or off)"
+            )),
+        }
     }
 
     fn prepare_chunks(
@@ -208,60 +1169,1076 @@ impl<E: Engine> Pipeline<E> {
             let mut out = Vec::new();
             for c in split_outputs {
                 let path = PathBuf::from(c.path);
-                if self.cfg.chunking.cap_chunk_bytes && self.cfg.chunking.max_chunk_bytes > 0 {
-                    if let Ok(meta) = std::fs::metadata(&path) {
-                        if meta.len() > self.cfg.chunking.max_chunk_bytes {
-                            warn!(
-                                "chunk {} exceeds max_chunk_bytes ({} > {})",
-                                c.chunk_index,
-                                meta.len(),
-                                self.cfg.chunking.max_chunk_bytes
-                            );
-                        }
-                    }
+                let input_hash = hash_file(&self.cfg, &path)
+                    .with_context(|| format!("hash chunk {}", path.display()))?;
+                if self.cfg.chunking.cap_chunk_bytes
+                    && self.cfg.chunking.max_chunk_bytes > 0
+                    && let Ok(meta) = std::fs::metadata(&path)
+                    && meta.len() > self.cfg.chunking.max_chunk_bytes
+                {
+                    warn!(
+                        "chunk {} exceeds max_chunk_bytes ({} > {})",
+                        c.chunk_index,
+                        meta.len(),
+                        self.cfg.chunking.max_chunk_bytes
+                    );
                 }
                 out.push(ChunkInput {
                     input_pdf: path,
+                    input_hash,
                     start_page: c.start_page,
                     end_page: c.end_page,
                     use_page_range: false,
-                    temp_file: true,
+                    page_errors: c.page_errors,
                 });
             }
             return Ok(out);
         }
 
         let use_page_range = strategy == "page_range" && plan.chunks.len() > 1;
+        let input_hash = hash_file(&self.cfg, input)
+            .with_context(|| format!("hash input {}", input.display()))?;
         Ok(plan
             .chunks
             .iter()
             .map(|r| ChunkInput {
                 input_pdf: input.to_path_buf(),
+                input_hash: input_hash.clone(),
                 start_page: r.start_page,
                 end_page: r.end_page,
                 use_page_range,
-                temp_file: false,
+                page_errors: Vec::new(),
             })
             .collect())
     }
 
-    fn cleanup_intermediates(&self, chunks: &[ChunkInput]) -> Result<()> {
-        if self.cfg.chunking.keep_split_pdfs {
-            return Ok(());
+    /// Delete what `[retention]` does not keep once the job has finished; chunks that
+    /// were skipped or lost pages count as failed.
+    fn cleanup_intermediates(&self, job_dir: &Path) -> Result<()> {
+        for artifact in retention::expired(&self.cfg, job_dir, SystemTime::now())? {
+            debug!("retention: removing {} {}", artifact.class, artifact.path.display());
+            let _ = std::fs::remove_file(&artifact.path);
         }
-        for ch in chunks {
-            if ch.temp_file {
-                let _ = std::fs::remove_file(&ch.input_pdf);
+        Ok(())
+    }
+}
+
+/// Name of the per-job manifest written before conversion starts.
+pub const JOB_MANIFEST_FILE: &str = "job.json";
+
+/// What the pipeline decided about a job up front; together with the chunk records it
+/// is enough to rebuild the final outputs without the input PDF.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobManifest {
+    pub probe: ProbeResult,
+    pub decision: PolicyDecision,
+    pub plan: ChunkPlan,
+    /// Content hash of every page (`global.record_page_hashes`), for `run --reuse-from`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub page_hashes: Vec<String>,
+    /// Pages left out by `input.exclusions`; the plan does not cover them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_pages: Vec<PageExclusion>,
+    /// The device check, when `docling.accelerator.device` names a device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accelerator: Option<AcceleratorUse>,
+}
+
+/// One converted chunk as written to `chunks/chunk_NNNNN.json`: the engine output plus
+/// the page range it covers and its absolute page errors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    pub chunk_index: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+    #[serde(default)]
+    pub page_errors: Vec<PageError>,
+    /// Job-relative path of the chunk's captured Python stderr, if any was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_log: Option<String>,
+    /// "table" or "prose", from the engine's table count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Missing from records written before provenance was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ChunkProvenance>,
+    /// Failed tries before the chunk converted, or before it was given up on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<Attempt>,
+    #[serde(flatten)]
+    pub out: ConvertOut,
+}
+
+/// What produced a chunk record, so a `chunk_NNNNN.json` copied out of its job
+/// directory can still be interpreted on its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkProvenance {
+    /// `<input hash prefix>:<start>-<end>`; the same pages of the same PDF get the same id.
+    pub chunk_id: String,
+    /// Hash of the PDF the engine was given: the chunk's own PDF with `physical_split`,
+    /// the whole input otherwise.
+    pub input_hash: String,
+    /// `hashing.mode` of `input_hash`.
+    pub hash_mode: String,
+    /// The engine that produced the markdown, after any fallback.
+    pub engine: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_backend: Option<String>,
+    pub do_ocr: bool,
+    /// `docling.ocr` as used, when the chunk was OCRed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr: Option<DoclingOcr>,
+    /// Hash of the settings that affect conversion (`config_hash`).
+    pub config_hash: String,
+    /// quack-check, docling, and engine script versions.
+    pub versions: BTreeMap<String, String>,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+pub fn chunk_record_file(chunk_index: u32) -> String {
+    format!("chunk_{:05}.json", chunk_index)
+}
+
+pub fn chunk_stderr_file(chunk_index: u32) -> String {
+    format!("chunk_{:05}.stderr.log", chunk_index)
+}
+
+/// Rebuild the final outputs from a manifest and its converted chunks.
+pub fn assemble_job(
+    cfg: &Config,
+    manifest: JobManifest,
+    records: Vec<ChunkRecord>,
+) -> Result<JobOutput> {
+    let split_spec = SplitSpec::parse(&cfg.output.split_final_by)?;
+    let counter = TokenCounter::new(&cfg.output.tokenizer)?;
+    assemble(cfg, &counter, split_spec.as_ref(), manifest, records, Vec::new())
+}
+
+/// The engine named in the decision of a [`postprocess_only`] run.
+pub const EXTERNAL_ENGINE: &str = "external";
+
+/// Run only the merge and cleanup stages on a transcript made by another tool, as one
+/// chunk. Pages are split at its `<!-- quack:page N -->` markers, else at form feeds;
+/// text with neither is one page. The report's probe and decision are stand-ins: the
+/// tier is `classification.forced_tier` (HIGH_TEXT for AUTO) and the engine
+/// [`EXTERNAL_ENGINE`].
+pub fn postprocess_only(cfg: &Config, input: &Path, text: &str) -> Result<JobOutput> {
+    let split_spec = SplitSpec::parse(&cfg.output.split_final_by)?;
+    let counter = TokenCounter::new(&cfg.output.tokenizer)?;
+    let (start_page, pages) = external_pages(text);
+    let end_page = start_page + pages.len() as u32 - 1;
+    let chars: usize = pages.iter().map(|p| p.chars().count()).sum();
+
+    let tier = policy::forced_tier(cfg).unwrap_or(QualityTier::HighText);
+    let mut decision = policy::decision_for(cfg, tier);
+    decision.chosen_engine = EXTERNAL_ENGINE.to_string();
+    decision.do_ocr = false;
+    let manifest = JobManifest {
+        probe: ProbeResult {
+            input: probe::ProbeInput {
+                path: input.display().to_string(),
+                canonical_path: None,
+                file_bytes: text.len() as u64,
+                page_count: end_page,
+            },
+            sample: probe::ProbeSampleStats {
+                sampled_pages: pages.len() as u32,
+                avg_chars_per_page: (chars / pages.len()) as u32,
+                garbage_ratio: 0.0,
+                whitespace_ratio: 0.0,
+            },
+            structure: Default::default(),
+            render: None,
+        },
+        decision,
+        plan: ChunkPlan {
+            page_count: end_page,
+            chunks: vec![PageRange {
+                start_page,
+                end_page,
+            }],
+            strategy: EXTERNAL_ENGINE.to_string(),
+        },
+        page_hashes: Vec::new(),
+        excluded_pages: Vec::new(),
+        accelerator: None,
+    };
+    let page_break = format!("\n\n{}\n\n", postprocess::PAGE_BREAK_PLACEHOLDER);
+    let record = ChunkRecord {
+        chunk_index: 0,
+        start_page,
+        end_page,
+        page_errors: Vec::new(),
+        stderr_log: None,
+        content_type: None,
+        provenance: None,
+        attempts: Vec::new(),
+        out: ConvertOut {
+            ok: true,
+            markdown: pages.join(&page_break),
+            warnings: Vec::new(),
+            meta: ConvertMeta::default(),
+        },
+    };
+    let records = vec![record];
+    let mut output = assemble(cfg, &counter, split_spec.as_ref(), manifest, records, Vec::new())?;
+    output.report.source = Some("postprocess".to_string());
+    Ok(output)
+}
+
+/// The first page number and the pages of an external transcript. Other quack-check
+/// markers are dropped, so a quack-check transcript can go through again; the empty
+/// page after a trailing form feed (as `pdftotext` writes) is dropped too.
+fn external_pages(text: &str) -> (u32, Vec<String>) {
+    let has_markers = text.lines().any(|l| postprocess::parse_page_marker(l).is_some());
+    let mut first_page = None;
+    let mut pages: Vec<Vec<&str>> = vec![Vec::new()];
+    for line in text.lines() {
+        if let Some(page) = postprocess::parse_page_marker(line) {
+            // Text before the first marker stays on the first page.
+            if first_page.is_some() {
+                pages.push(Vec::new());
             }
+            first_page.get_or_insert(page);
+            continue;
         }
-        Ok(())
+        if postprocess::is_marker_line(line) {
+            continue;
+        }
+        if has_markers {
+            pages.last_mut().unwrap().push(line);
+            continue;
+        }
+        let mut pieces = line.split('\x0c');
+        pages.last_mut().unwrap().extend(pieces.next());
+        for piece in pieces {
+            pages.push(vec![piece]);
+        }
+    }
+    let mut pages: Vec<String> = pages.iter().map(|p| p.join("\n").trim().to_string()).collect();
+    if pages.len() > 1 && !has_markers && pages.last().is_some_and(|p| p.is_empty()) {
+        pages.pop();
+    }
+    (first_page.unwrap_or(1), pages)
+}
+
+fn assemble(
+    cfg: &Config,
+    counter: &TokenCounter,
+    split_spec: Option<&SplitSpec>,
+    manifest: JobManifest,
+    records: Vec<ChunkRecord>,
+    skipped: Vec<ChunkReport>,
+) -> Result<JobOutput> {
+    let on_page_error = cfg.limits.on_page_error.as_str();
+    let postprocess_ctx = PostprocessContext::new(cfg)?;
+    let mut chunk_reports = Vec::new();
+    let mut markdown_parts = Vec::new();
+    let mut images = Vec::new();
+    let mut image_stats = EmbeddedImageStats::default();
+    let mut measured_pages = Vec::new();
+    let mut converted_at = BTreeMap::new();
+
+    for record in records {
+        let ChunkRecord {
+            chunk_index,
+            start_page,
+            end_page,
+            page_errors,
+            stderr_log,
+            content_type,
+            attempts,
+            provenance,
+            mut out,
+        } = record;
+        if let Some(provenance) = provenance {
+            converted_at.insert(chunk_index, provenance.finished_at);
+        }
+
+        for err in &page_errors {
+            warn!("page {} unreadable ({}): {}", err.page, on_page_error, err.reason);
+        }
+        let marked = mark_chunk(cfg, start_page, &out.markdown, &page_errors, &mut out.warnings);
+
+        let part = postprocess_ctx.handle_embedded_images(&marked, &mut images, &mut image_stats);
+        let chunk = ChunkInfo {
+            index: chunk_index,
+            start_page,
+            end_page,
+            engine: chunk_engine(&manifest.decision, &out.meta),
+            content_type: content_type.as_deref(),
+        };
+        let part = postprocess_ctx.run_chunk_stages(&part, &chunk);
+        measured_pages.extend(page_quality::measure_chunk(
+            start_page,
+            end_page,
+            &part,
+            &out.meta,
+            &page_errors,
+        ));
+
+        let unmarked = postprocess::strip_page_markers(&part);
+        let detected = script::detect(&unmarked);
+        chunk_reports.push(ChunkReport {
+            chunk_index,
+            start_page,
+            end_page,
+            ok: out.ok,
+            pdf_backend: out.meta.pdf_backend.clone(),
+            tokens: counter.count(&unmarked),
+            page_errors,
+            stderr_log,
+            content_type,
+            script: detected.as_ref().map(|d| d.script.clone()),
+            direction: detected.map(|d| d.direction),
+            warnings: out.warnings,
+            meta: out.meta,
+            skipped: (!out.ok).then(|| CONVERSION_FAILED.to_string()),
+            attempts,
+        });
+
+        markdown_parts.push(with_chunk_marker(
+            cfg,
+            &manifest.decision,
+            chunk_index,
+            start_page,
+            end_page,
+            &chunk_reports[chunk_reports.len() - 1].meta,
+            part,
+        ));
+    }
+
+    if image_stats.found > 0 {
+        info!(
+            "embedded images found={} stripped={} externalized={} bytes_removed={}",
+            image_stats.found,
+            image_stats.stripped,
+            image_stats.externalized,
+            image_stats.markdown_bytes_removed
+        );
+    }
+
+    let page_count = manifest.probe.input.page_count;
+    // Pages the engine marked out individually and read without errors.
+    let measurable: BTreeSet<u32> = measured_pages
+        .iter()
+        .filter(|p| p.chars.is_some() && p.warnings.is_empty())
+        .map(|p| p.page)
+        .collect();
+    for range in &manifest.excluded_pages {
+        measured_pages.extend((range.start_page..=range.end_page).map(|page| {
+            page_quality::PageQuality {
+                page,
+                chars: None,
+                garbage_ratio: None,
+                ocr_confidence: None,
+                ocr_mode: None,
+                warnings: vec![match range.reason.as_str() {
+                    "" => "excluded".to_string(),
+                    reason => format!("excluded: {reason}"),
+                }],
+                score: None,
+            }
+        }));
+    }
+    let page_quality = page_quality::score_pages(measured_pages, page_count);
+    let hybrid_ocr = page_quality::hybrid_ocr_stats(&page_quality);
+    if let Some(stats) = &hybrid_ocr {
+        info!(
+            "hybrid ocr text={} ocr={} mixed={} empty={}",
+            stats.text_pages, stats.ocr_pages, stats.mixed_pages, stats.empty_pages
+        );
+    }
+    let relabel_markers = match cfg.postprocess.printed_page_numbers.as_str() {
+        "off" | "detect" => false,
+        "markers" => true,
+        other => return Err(anyhow!("unknown postprocess.printed_page_numbers: {other}")),
+    };
+    // Before merging: repeated-line removal strips the headers and footers that carry
+    // the printed numbers.
+    let page_numbers = if cfg.postprocess.printed_page_numbers == "off" {
+        PageNumberStats::default()
+    } else {
+        let stats = pagenum::detect_printed_page_numbers(&markdown_parts.join("\n"), page_count);
+        info!(
+            "printed page numbers detected={} inferred={} unnumbered={}",
+            stats.detected, stats.inferred, stats.unnumbered
+        );
+        stats
+    };
+
+    let merge_opts = merge_options(cfg, &manifest.decision, page_count)?;
+    let plain_md = postprocess_ctx.merge(markdown_parts, merge_opts)?;
+    // After merging, so the page-number lines it removes don't count as text.
+    let (plain_md, blank_pages) = blank_pages::handle_blank_pages(
+        &plain_md,
+        &cfg.postprocess.blank_pages,
+        cfg.postprocess.blank_page_max_chars as usize,
+        &measurable,
+    )?;
+    if !blank_pages.pages.is_empty() {
+        info!(
+            "blank pages found={} mode={}",
+            blank_pages.pages.len(),
+            blank_pages.mode
+        );
+    }
+    let chunk_ranges: Vec<(u32, u32)> = chunk_reports
+        .iter()
+        .map(|c| (c.start_page, c.end_page))
+        .collect();
+    let (plain_md, duplicate_pages) = duplicate_pages::handle_duplicate_pages(
+        &plain_md,
+        &cfg.postprocess.duplicate_pages,
+        cfg.postprocess.duplicate_page_threshold,
+        cfg.postprocess.duplicate_page_min_words as usize,
+        &chunk_ranges,
+        &measurable,
+    )?;
+    for range in &duplicate_pages.ranges {
+        warn!(
+            "pages {}-{} repeat pages {}-{} (similarity >= {:.2}; mode={})",
+            range.start_page,
+            range.end_page,
+            range.original_start_page,
+            range.original_end_page,
+            range.min_similarity,
+            duplicate_pages.mode
+        );
+    }
+    let (plain_md, terms) = match cfg.postprocess.extract_terms.as_str() {
+        "off" => (plain_md, TermsReport::default()),
+        "extract" => {
+            let terms = terms::extract_terms(&plain_md, &page_numbers);
+            (plain_md, terms)
+        }
+        "replace" => terms::replace_terms(&plain_md, &page_numbers),
+        other => return Err(anyhow!("unknown postprocess.extract_terms: {other}")),
+    };
+    if !terms.sections.is_empty() {
+        info!(
+            "index/glossary sections={} entries={}",
+            terms.sections.len(),
+            terms.entry_count()
+        );
+    }
+    let (marked_md, xref_stats) = if cfg.postprocess.resolve_cross_references {
+        let (linked, stats) = xref::resolve_cross_references(&plain_md);
+        info!(
+            "cross references targets={} resolved={} unresolved={}",
+            stats.targets, stats.resolved, stats.unresolved
+        );
+        (linked, stats)
+    } else {
+        (plain_md.clone(), XrefStats::default())
+    };
+    let mut sections = match split_spec {
+        Some(spec) => {
+            sections::split_sections(spec, &marked_md, cfg.postprocess.keep_page_markers, counter)
+        }
+        None => Vec::new(),
+    };
+    let chunks = if cfg.output.write_chunk_markdown {
+        let ranges: Vec<(u32, u32)> = chunk_reports
+            .iter()
+            .map(|c| (c.start_page, c.end_page))
+            .collect();
+        postprocess::split_by_chunks(&marked_md, &ranges)
+            .into_iter()
+            .zip(&chunk_reports)
+            .map(|(markdown, c)| ChunkMarkdown {
+                file: format!(
+                    "{:04}_pages-{:04}-{:04}.md",
+                    c.chunk_index + 1,
+                    c.start_page,
+                    c.end_page
+                ),
+                markdown: match (cfg.postprocess.keep_page_markers, relabel_markers) {
+                    (true, true) => pagenum::relabel_page_markers(&markdown, &page_numbers),
+                    (true, false) => markdown,
+                    (false, _) => postprocess::strip_page_markers(&markdown),
+                },
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let merged_md = if cfg.postprocess.keep_page_markers {
+        if relabel_markers {
+            for section in &mut sections {
+                section.markdown = pagenum::relabel_page_markers(&section.markdown, &page_numbers);
+            }
+            pagenum::relabel_page_markers(&marked_md, &page_numbers)
+        } else {
+            marked_md.clone()
+        }
+    } else {
+        postprocess::strip_page_markers(&marked_md)
+    };
+    // Plain text is derived from the transcript before links were added.
+    let mut merged_txt =
+        postprocess::markdown_to_text(cfg, &postprocess::strip_page_markers(&plain_md))?;
+
+    let completeness = completeness(
+        manifest.probe.input.page_count,
+        &chunk_reports,
+        &skipped,
+        &manifest.excluded_pages,
+    );
+    let mut merged_md = merged_md;
+    if let Some(reason) = &completeness.reason {
+        // Up front, so nobody mistakes a partial transcript for the whole book.
+        merged_md = format!("> **Incomplete transcript:** {reason}.\n\n{merged_md}");
+        merged_txt = format!("[Incomplete transcript: {reason}.]\n\n{merged_txt}");
+    }
+    let pagemap = if cfg.output.write_pagemap {
+        let chunk_pages: Vec<(u32, u32, u32)> = chunk_reports
+            .iter()
+            .map(|c| (c.chunk_index, c.start_page, c.end_page))
+            .collect();
+        pagemap::build(&marked_md, &merged_md, &chunk_pages, &page_numbers)
+    } else {
+        PageMap::default()
+    };
+    chunk_reports.extend(skipped);
+    if cfg.output.provenance_appendix {
+        let appendix = provenance::appendix(
+            &chunk_reports,
+            &manifest.decision.chosen_engine,
+            &converted_at,
+        );
+        merged_txt = format!(
+            "{}\n\n{}",
+            merged_txt.trim_end(),
+            postprocess::markdown_to_text(cfg, &appendix)?
+        );
+        merged_md = format!("{}\n\n{appendix}", merged_md.trim_end());
+    }
+
+    let lint_report = if cfg.postprocess.lint.enabled {
+        let report = lint::lint_markdown(&merged_md, &cfg.postprocess.lint);
+        if report.errors > 0 || report.warnings > 0 {
+            warn!(
+                "markdown lint errors={} warnings={}",
+                report.errors, report.warnings
+            );
+        }
+        if report.errors > 0 && cfg.postprocess.lint.fail_on_error {
+            return Err(anyhow!(
+                "markdown lint found {} error(s): {}",
+                report.errors,
+                report.error_summary(5)
+            ));
+        }
+        Some(report)
+    } else {
+        None
+    };
+
+    let report = JobReport {
+        completeness,
+        source: None,
+        input: manifest.probe.input,
+        sample: manifest.probe.sample,
+        structure: manifest.probe.structure,
+        render: manifest.probe.render,
+        decision: manifest.decision,
+        chunk_reports,
+        embedded_images: image_stats,
+        xref: xref_stats,
+        page_numbers,
+        blank_pages,
+        duplicate_pages,
+        excluded_pages: manifest.excluded_pages,
+        accelerator: manifest.accelerator,
+        hybrid_ocr,
+        lint: lint_report,
+        tokens: TokenStats {
+            tokenizer: counter.name().to_string(),
+            total: counter.count(&postprocess::strip_page_markers(&merged_md)),
+        },
+    };
+
+    Ok(JobOutput {
+        markdown: merged_md,
+        text: merged_txt,
+        report,
+        images,
+        sections,
+        page_quality,
+        terms,
+        chunks,
+        page_images: Vec::new(),
+        pagemap,
+    })
+}
+
+fn merge_options(
+    cfg: &Config,
+    decision: &PolicyDecision,
+    page_count: u32,
+) -> Result<postprocess::MergeOptions> {
+    let infer_chapter_titles = match cfg.postprocess.infer_chapter_titles.as_str() {
+        "off" => false,
+        "scan" => decision.tier == QualityTier::Scan,
+        "always" => true,
+        other => return Err(anyhow!("unknown postprocess.infer_chapter_titles: {other}")),
+    };
+    Ok(postprocess::MergeOptions {
+        infer_chapter_titles,
+        page_count,
+    })
+}
+
+/// A chunk's markdown with absolute page markers, and its unreadable pages blanked or
+/// replaced per `limits.on_page_error`. Adds a warning per unreadable page.
+fn mark_chunk(
+    cfg: &Config,
+    start_page: u32,
+    markdown: &str,
+    page_errors: &[PageError],
+    warnings: &mut Vec<String>,
+) -> String {
+    let mut marked = postprocess::mark_pages(markdown, start_page);
+    for err in page_errors {
+        warnings.push(format!("page {} unreadable: {}", err.page, err.reason));
+        let replacement = if cfg.limits.on_page_error == "placeholder" {
+            postprocess::unreadable_page_placeholder(err.page, &err.reason)
+        } else {
+            String::new()
+        };
+        marked = postprocess::replace_page_content(&marked, err.page, &replacement);
+    }
+    marked
+}
+
+/// One finished chunk, postprocessed on its own. Steps that need the whole document
+/// (repeated lines across chunks, cross-reference links, printed page number
+/// relabeling, sections) only apply to the final transcript, so a chunk's markdown can
+/// differ from its stretch of `final/`.
+#[derive(Debug, Clone)]
+pub struct StreamedChunk {
+    pub chunk_index: u32,
+    /// Chunks in the job's plan.
+    pub chunk_count: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+    /// Kept page markers follow `postprocess.keep_page_markers`.
+    pub markdown: String,
+    pub page_errors: Vec<PageError>,
+    pub content_type: Option<String>,
+    pub warnings: Vec<String>,
+    pub meta: ConvertMeta,
+    /// Images externalized from this chunk, named as they will be in `final/images/`.
+    pub images: Vec<EmbeddedImage>,
+}
+
+/// Receives chunks from [`Pipeline::run_with_sink`] in page order.
+pub trait ChunkSink {
+    fn chunk(&mut self, chunk: StreamedChunk) -> Result<()>;
+}
+
+impl<F: FnMut(StreamedChunk) -> Result<()>> ChunkSink for F {
+    fn chunk(&mut self, chunk: StreamedChunk) -> Result<()> {
+        self(chunk)
+    }
+}
+
+/// `image_stats` runs across the job, so externalized image names match the final ones.
+fn streamed_chunk(
+    cfg: &Config,
+    ctx: &PostprocessContext,
+    decision: &PolicyDecision,
+    page_count: u32,
+    chunk_count: u32,
+    record: &ChunkRecord,
+    image_stats: &mut EmbeddedImageStats,
+) -> Result<StreamedChunk> {
+    let mut warnings = record.out.warnings.clone();
+    let marked = mark_chunk(
+        cfg,
+        record.start_page,
+        &record.out.markdown,
+        &record.page_errors,
+        &mut warnings,
+    );
+    let mut images = Vec::new();
+    let part = ctx.handle_embedded_images(&marked, &mut images, image_stats);
+    let chunk = ChunkInfo {
+        index: record.chunk_index,
+        start_page: record.start_page,
+        end_page: record.end_page,
+        engine: chunk_engine(decision, &record.out.meta),
+        content_type: record.content_type.as_deref(),
+    };
+    let part = ctx.run_chunk_stages(&part, &chunk);
+    let part = with_chunk_marker(
+        cfg,
+        decision,
+        record.chunk_index,
+        record.start_page,
+        record.end_page,
+        &record.out.meta,
+        part,
+    );
+    let merged = ctx.merge(vec![part], merge_options(cfg, decision, page_count)?)?;
+    let markdown = if cfg.postprocess.keep_page_markers {
+        merged
+    } else {
+        postprocess::strip_page_markers(&merged)
+    };
+    Ok(StreamedChunk {
+        chunk_index: record.chunk_index,
+        chunk_count,
+        start_page: record.start_page,
+        end_page: record.end_page,
+        markdown,
+        page_errors: record.page_errors.clone(),
+        content_type: record.content_type.clone(),
+        warnings,
+        meta: record.out.meta.clone(),
+        images,
+    })
+}
+
+/// `part` opened with its `<!-- quack:chunk ... -->` provenance comment when
+/// `postprocess.chunk_separator` asks for one. The engine is the one that produced the
+/// chunk, which differs from the policy's after a native-text fallback.
+fn with_chunk_marker(
+    cfg: &Config,
+    decision: &PolicyDecision,
+    index: u32,
+    start_page: u32,
+    end_page: u32,
+    meta: &ConvertMeta,
+    part: String,
+) -> String {
+    if !matches!(cfg.postprocess.chunk_separator.as_str(), "comment" | "both") {
+        return part;
     }
+    let marker = postprocess::chunk_marker(&postprocess::ChunkMarker {
+        index,
+        start_page,
+        end_page,
+        engine: chunk_engine(decision, meta).to_string(),
+    });
+    format!("{marker}\n\n{part}")
+}
+
+/// The engine that converted a chunk: the one it reports, else the policy's choice.
+fn chunk_engine<'a>(decision: &'a PolicyDecision, meta: &'a ConvertMeta) -> &'a str {
+    meta.engine.as_deref().unwrap_or(&decision.chosen_engine)
 }
 
 struct ChunkInput {
     input_pdf: PathBuf,
+    /// `hashing.mode` hash of `input_pdf`.
+    input_hash: String,
     start_page: u32,
     end_page: u32,
     use_page_range: bool,
-    temp_file: bool,
+    page_errors: Vec<PageError>,
+}
+
+impl ChunkInput {
+    fn pages(&self) -> u32 {
+        self.end_page - self.start_page + 1
+    }
+}
+
+/// The report of a job still converting: the probe and decision, and a report for each
+/// chunk converted so far with its warnings and page errors. Whole-document results
+/// (tokens, page numbers, lint, ...) are left empty until the job is assembled.
+fn progress_report(
+    counter: &TokenCounter,
+    manifest: &JobManifest,
+    records: &[ChunkRecord],
+    chunk_count: usize,
+) -> JobReport {
+    let chunk_reports: Vec<ChunkReport> = records
+        .iter()
+        .map(|record| {
+            let unmarked = postprocess::strip_page_markers(&record.out.markdown);
+            let detected = script::detect(&unmarked);
+            let mut warnings = record.out.warnings.clone();
+            warnings.extend(
+                record
+                    .page_errors
+                    .iter()
+                    .map(|err| format!("page {} unreadable: {}", err.page, err.reason)),
+            );
+            ChunkReport {
+                chunk_index: record.chunk_index,
+                start_page: record.start_page,
+                end_page: record.end_page,
+                ok: record.out.ok,
+                pdf_backend: record.out.meta.pdf_backend.clone(),
+                tokens: counter.count(&unmarked),
+                page_errors: record.page_errors.clone(),
+                stderr_log: record.stderr_log.clone(),
+                content_type: record.content_type.clone(),
+                script: detected.as_ref().map(|d| d.script.clone()),
+                direction: detected.map(|d| d.direction),
+                warnings,
+                meta: record.out.meta.clone(),
+                skipped: (!record.out.ok).then(|| CONVERSION_FAILED.to_string()),
+                attempts: record.attempts.clone(),
+            }
+        })
+        .collect();
+    let total_pages = manifest.probe.input.page_count;
+    JobReport {
+        completeness: Completeness {
+            complete: false,
+            converted_pages: chunk_reports
+                .iter()
+                .map(|c| c.end_page - c.start_page + 1)
+                .sum(),
+            total_pages,
+            skipped_chunks: Vec::new(),
+            failed_chunks: Vec::new(),
+            reason: Some(format!(
+                "in progress: {} of {chunk_count} chunk(s) converted",
+                chunk_reports.len()
+            )),
+        },
+        source: None,
+        input: manifest.probe.input.clone(),
+        sample: manifest.probe.sample.clone(),
+        structure: manifest.probe.structure.clone(),
+        render: manifest.probe.render.clone(),
+        decision: manifest.decision.clone(),
+        chunk_reports,
+        embedded_images: EmbeddedImageStats::default(),
+        xref: XrefStats::default(),
+        page_numbers: PageNumberStats::default(),
+        blank_pages: blank_pages::BlankPageStats::default(),
+        duplicate_pages: DuplicatePageStats::default(),
+        excluded_pages: manifest.excluded_pages.clone(),
+        accelerator: manifest.accelerator.clone(),
+        hybrid_ocr: None,
+        lint: None,
+        tokens: TokenStats {
+            tokenizer: counter.name().to_string(),
+            total: 0,
+        },
+    }
+}
+
+/// A job resumed after `jobs pause` keeps the chunks it converted before: records in
+/// `job_dir` that converted cleanly and cover the same pages as a planned chunk.
+fn resume_records(
+    job_dir: &Path,
+    chunk_inputs: &[ChunkInput],
+    reused: &mut BTreeMap<usize, ChunkRecord>,
+) {
+    let records = match crate::jobs::load_records(job_dir) {
+        Ok(records) => records,
+        Err(err) => {
+            warn!("resume: could not read converted chunks: {err:#}");
+            return;
+        }
+    };
+    let mut kept = 0;
+    for record in records.into_iter().filter(|r| r.out.ok) {
+        let i = record.chunk_index as usize;
+        if chunk_inputs
+            .get(i)
+            .is_some_and(|ch| (ch.start_page, ch.end_page) == (record.start_page, record.end_page))
+            && !reused.contains_key(&i)
+        {
+            reused.insert(i, record);
+            kept += 1;
+        }
+    }
+    info!("resume: keeping {kept} of {} chunk(s) converted before the pause", chunk_inputs.len());
+}
+
+/// Stand-in for a chunk that failed under `limits.allow_failed_chunk_fraction`: an
+/// unreadable-page placeholder per page, and `ok: false`, which marks it failed.
+fn failed_chunk_record(i: usize, ch: &ChunkInput, err: &anyhow::Error) -> ChunkRecord {
+    let reason = format!("conversion failed: {err}");
+    let trail = attempts::trail(err);
+    let markdown = (ch.start_page..=ch.end_page)
+        .map(|page| {
+            let placeholder = postprocess::unreadable_page_placeholder(page, &reason);
+            if page == ch.start_page && !trail.is_empty() {
+                format!("{placeholder}\n\n{}", attempts::trail_comment(&trail))
+            } else {
+                placeholder
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("\n\n{}\n\n", postprocess::PAGE_BREAK_PLACEHOLDER));
+    ChunkRecord {
+        chunk_index: i as u32,
+        start_page: ch.start_page,
+        end_page: ch.end_page,
+        page_errors: Vec::new(),
+        stderr_log: None,
+        content_type: None,
+        provenance: None,
+        attempts: trail,
+        out: ConvertOut {
+            ok: false,
+            markdown,
+            warnings: vec![format!("conversion failed: {err:#}")],
+            meta: ConvertMeta::default(),
+        },
+    }
+}
+
+fn skipped_chunk_report(chunk_index: u32, ch: &ChunkInput) -> ChunkReport {
+    ChunkReport {
+        chunk_index,
+        start_page: ch.start_page,
+        end_page: ch.end_page,
+        ok: false,
+        pdf_backend: None,
+        tokens: 0,
+        page_errors: Vec::new(),
+        stderr_log: None,
+        warnings: vec!["not converted: time budget exhausted".into()],
+        content_type: None,
+        script: None,
+        direction: None,
+        meta: ConvertMeta::default(),
+        skipped: Some(SKIPPED_DUE_TO_BUDGET.into()),
+        attempts: Vec::new(),
+    }
+}
+
+/// Excluded pages are neither converted nor missing; pages of failed chunks are
+/// missing.
+fn completeness(
+    total_pages: u32,
+    converted: &[ChunkReport],
+    skipped: &[ChunkReport],
+    excluded: &[PageExclusion],
+) -> Completeness {
+    let failed: Vec<&ChunkReport> = converted
+        .iter()
+        .filter(|c| c.skipped.as_deref() == Some(CONVERSION_FAILED))
+        .collect();
+    let pages = |c: &ChunkReport| c.end_page - c.start_page + 1;
+    let skipped_pages: u32 = skipped.iter().map(pages).sum::<u32>()
+        + failed.iter().map(|c| pages(c)).sum::<u32>()
+        + excluded.iter().map(PageExclusion::pages).sum::<u32>();
+    let mut reasons = Vec::new();
+    if !failed.is_empty() {
+        let ranges: Vec<String> = failed
+            .iter()
+            .map(|c| format!("{}-{}", c.start_page, c.end_page))
+            .collect();
+        reasons.push(format!(
+            "{} chunk(s) failed to convert; pages {} hold placeholders",
+            failed.len(),
+            ranges.join(", ")
+        ));
+    }
+    if let (Some(first), Some(last)) = (skipped.first(), skipped.last()) {
+        reasons.push(format!(
+            "the time budget ran out; pages {}-{} of {total_pages} were not converted",
+            first.start_page, last.end_page
+        ));
+    }
+    Completeness {
+        complete: skipped.is_empty() && failed.is_empty(),
+        converted_pages: total_pages.saturating_sub(skipped_pages),
+        total_pages,
+        skipped_chunks: skipped.iter().map(|c| c.chunk_index).collect(),
+        failed_chunks: failed.iter().map(|c| c.chunk_index).collect(),
+        reason: (!reasons.is_empty()).then(|| reasons.join("; ")),
+    }
+}
+
+/// One chunk's output rebuilt from its two separately converted halves. Page errors
+/// from the halves are already absolute, since halves convert with `page_range`.
+fn join_halves(req: &ConvertIn, first: ConvertOut, second: ConvertOut) -> ConvertOut {
+    let mut warnings = vec![format!(
+        "out of memory on pages {}-{}; converted in smaller pieces",
+        req.start_page, req.end_page
+    )];
+    warnings.extend(first.warnings);
+    warnings.extend(second.warnings);
+    let mut meta = first.meta;
+    let rest = second.meta;
+    meta.start_page = Some(req.start_page);
+    meta.end_page = Some(req.end_page);
+    meta.use_page_range = Some(true);
+    meta.page_errors.extend(rest.page_errors);
+    meta.pages_processed = sum(meta.pages_processed, rest.pages_processed);
+    meta.page_span = match (meta.page_span, rest.page_span) {
+        (Some([a, b]), Some([c, d])) => Some([a.min(c), b.max(d)]),
+        (a, b) => a.or(b),
+    };
+    meta.table_count = sum(meta.table_count, rest.table_count);
+    meta.ocr_applied = match (meta.ocr_applied, rest.ocr_applied) {
+        (Some(a), Some(b)) => Some(a || b),
+        (a, b) => a.or(b),
+    };
+    for (kind, n) in rest.element_counts {
+        *meta.element_counts.entry(kind).or_default() += n;
+    }
+    for (step, secs) in rest.timings {
+        *meta.timings.entry(step).or_default() += secs;
+    }
+    ConvertOut {
+        ok: first.ok && second.ok,
+        markdown: format!(
+            "{}\n\n{}\n\n{}",
+            first.markdown,
+            postprocess::PAGE_BREAK_PLACEHOLDER,
+            second.markdown
+        ),
+        warnings,
+        meta,
+    }
+}
+
+/// Why a `page_range` conversion's meta shows more than the requested pages, if it does.
+/// Missing meta is not a mismatch: older runners do not report page numbers.
+fn page_range_problem(req: &ConvertIn, meta: &ConvertMeta) -> Option<String> {
+    if meta.ignored("page_range") {
+        return Some("the installed docling ignores page_range".into());
+    }
+    if meta.use_page_range == Some(false) {
+        return Some("the engine converted without a page range".into());
+    }
+    let expected = req.end_page - req.start_page + 1;
+    if let Some(pages) = meta.pages_processed
+        && pages > expected
+    {
+        return Some(format!("{pages} pages converted, expected at most {expected}"));
+    }
+    if let Some([first, last]) = meta.page_span
+        && (first < req.start_page || last > req.end_page)
+    {
+        return Some(format!("returned pages {first}-{last}"));
+    }
+    None
+}
+
+fn sum(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Split-time page errors plus the engine's, mapped to absolute page numbers.
+fn collect_page_errors(ch: &ChunkInput, meta: &ConvertMeta) -> Vec<PageError> {
+    let mut errors = ch.page_errors.clone();
+    // A chunk converted in halves reports absolute pages even when it was split.
+    let absolute = meta.use_page_range.unwrap_or(ch.use_page_range);
+    for mut err in meta.page_errors.iter().cloned() {
+        if !absolute {
+            err.page = ch.start_page + err.page.saturating_sub(1);
+        }
+        errors.push(err);
+    }
+    errors.sort_by_key(|e| e.page);
+    errors.dedup_by_key(|e| e.page);
+    errors
 }
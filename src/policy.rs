@@ -1,82 +1,268 @@
-use crate::{config::Config, probe::ProbeResult};
+use crate::{
+    config::{Classification, Config},
+    engine::ConvertOut,
+    probe::{ProbeResult, ProbeSampleStats},
+    spreads,
+    tiles::{self, PageTiles},
+};
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QualityTier {
     HighText,
     MixedText,
     Scan,
 }
 
+impl QualityTier {
+    /// Parse a tier label as written in config (`HIGH_TEXT`) or reports (`HighText`).
+    pub fn from_label(label: &str) -> Option<Self> {
+        let norm: String = label
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        match norm.as_str() {
+            "HIGHTEXT" => Some(Self::HighText),
+            "MIXEDTEXT" => Some(Self::MixedText),
+            "SCAN" => Some(Self::Scan),
+            _ => None,
+        }
+    }
+
+    /// The tier's name in config (`forced_tier`, `[docling.tier_overrides.*]`).
+    pub fn config_key(self) -> &'static str {
+        match self {
+            Self::HighText => "HIGH_TEXT",
+            Self::MixedText => "MIXED_TEXT",
+            Self::Scan => "SCAN",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolicyDecision {
     pub tier: QualityTier,
     pub chosen_engine: String,
     pub do_ocr: bool,
+    /// Docling pipeline/OCR settings that replace the global ones for this tier, from
+    /// `[docling.tier_overrides.<TIER>]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pipeline_overrides: BTreeMap<String, serde_json::Value>,
+    /// Risky features the probe found and what `[security.pdf_features]` does about each.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pdf_features: Vec<FeatureAction>,
+    /// With `classification.forced_tier` set: the tier the probe's thresholds would
+    /// have chosen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_tier: Option<QualityTier>,
+    /// Set when `auto_tier` differs from the forced tier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier_mismatch: Option<String>,
+    /// Each PDF page is converted as its left and right halves (`input.split_spreads`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub split_spreads: bool,
+    /// Pages cut into tiles for OCR (`input.tile_oversized_pages`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiled_pages: Vec<PageTiles>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureAction {
+    /// xfa | forms | javascript | attachments | xref_repaired
+    pub feature: String,
+    /// allow | warn | strip | refuse
+    pub action: String,
 }
 
 pub fn decide(cfg: &Config, probe: &ProbeResult) -> PolicyDecision {
-    if cfg.classification.forced_tier != "AUTO" {
-        return forced(cfg);
+    let auto = classify_tier(&cfg.classification, &probe.sample);
+    let forced = forced_tier(cfg);
+    let mut decision = decision_for(cfg, forced.unwrap_or(auto));
+    if let Some(forced) = forced {
+        decision.auto_tier = Some(auto);
+        decision.tier_mismatch = (forced != auto).then(|| {
+            format!(
+                "classification.forced_tier = {} but the probe suggests {}",
+                forced.config_key(),
+                auto.config_key()
+            )
+        });
     }
+    decision.pdf_features = feature_actions(cfg, &probe.structure.risky_features);
+    decision.split_spreads = spreads::should_split(cfg, probe);
+    decision.tiled_pages = tiles::plan(cfg, probe, decision.split_spreads);
+    decision
+}
 
-    let avg = probe.sample.avg_chars_per_page;
-    let garbage = probe.sample.garbage_ratio;
-    let ws = probe.sample.whitespace_ratio;
+/// Pair each reported feature with its configured action. Names this build does not
+/// know (from a newer runner) are warned about.
+pub fn feature_actions(cfg: &Config, features: &[String]) -> Vec<FeatureAction> {
+    features
+        .iter()
+        .map(|feature| FeatureAction {
+            feature: feature.clone(),
+            action: cfg
+                .security
+                .pdf_features
+                .action(feature)
+                .unwrap_or("warn")
+                .to_string(),
+        })
+        .collect()
+}
 
-    let tier = if avg >= cfg.classification.min_avg_chars_per_page_for_high_text
-        && garbage <= cfg.classification.max_garbage_ratio_for_high_text
-        && ws <= cfg.classification.max_whitespace_ratio_for_high_text
+/// The threshold rules alone, without engine selection.
+pub fn classify_tier(c: &Classification, sample: &ProbeSampleStats) -> QualityTier {
+    let avg = sample.avg_chars_per_page;
+    if avg >= c.min_avg_chars_per_page_for_high_text
+        && sample.garbage_ratio <= c.max_garbage_ratio_for_high_text
+        && sample.whitespace_ratio <= c.max_whitespace_ratio_for_high_text
     {
         QualityTier::HighText
-    } else if avg <= cfg.classification.max_avg_chars_per_page_for_scan {
+    } else if avg <= c.max_avg_chars_per_page_for_scan {
         QualityTier::Scan
     } else {
         QualityTier::MixedText
-    };
+    }
+}
 
-    match tier {
-        QualityTier::HighText => PolicyDecision {
-            tier,
-            chosen_engine: cfg.engine.high_text_engine.clone(),
-            do_ocr: false,
-        },
-        QualityTier::MixedText => PolicyDecision {
-            tier,
-            chosen_engine: cfg.engine.mixed_text_engine.clone(),
-            do_ocr: cfg.docling.pipeline.do_ocr,
-        },
-        QualityTier::Scan => PolicyDecision {
-            tier,
-            chosen_engine: cfg.engine.scan_engine.clone(),
-            do_ocr: true,
-        },
+/// The tier set by `classification.forced_tier`, or `None` for "AUTO". Unknown labels
+/// fall back to `MIXED_TEXT`.
+pub fn forced_tier(cfg: &Config) -> Option<QualityTier> {
+    match cfg.classification.forced_tier.as_str() {
+        "AUTO" => None,
+        "HIGH_TEXT" => Some(QualityTier::HighText),
+        "MIXED_TEXT" => Some(QualityTier::MixedText),
+        "SCAN" => Some(QualityTier::Scan),
+        _ => Some(QualityTier::MixedText),
     }
 }
 
-fn forced(cfg: &Config) -> PolicyDecision {
-    let tier = match cfg.classification.forced_tier.as_str() {
-        "HIGH_TEXT" => QualityTier::HighText,
-        "MIXED_TEXT" => QualityTier::MixedText,
-        "SCAN" => QualityTier::Scan,
-        _ => QualityTier::MixedText,
+/// Engine selection and Docling overrides for a tier, without the probe's PDF features.
+pub fn decision_for(cfg: &Config, tier: QualityTier) -> PolicyDecision {
+    let (chosen_engine, do_ocr) = match tier {
+        QualityTier::HighText => (&cfg.engine.high_text_engine, false),
+        QualityTier::MixedText => (&cfg.engine.mixed_text_engine, cfg.docling.pipeline.do_ocr),
+        QualityTier::Scan => (&cfg.engine.scan_engine, true),
     };
+    let pipeline_overrides = cfg
+        .docling
+        .tier_overrides
+        .get(tier.config_key())
+        .cloned()
+        .unwrap_or_default();
+    // Hybrid OCR picks pages itself, so the runner needs OCR available for every tier.
+    let hybrid = pipeline_overrides
+        .get("ocr.hybrid")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(cfg.docling.ocr.hybrid);
+    // `do_ocr` travels on the request rather than in the pipeline config.
+    let do_ocr = pipeline_overrides
+        .get("do_ocr")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(do_ocr || hybrid);
+    PolicyDecision {
+        tier,
+        chosen_engine: chosen_engine.clone(),
+        do_ocr,
+        pipeline_overrides,
+        pdf_features: Vec::new(),
+        auto_tier: None,
+        tier_mismatch: None,
+        split_spreads: false,
+        tiled_pages: Vec::new(),
+    }
+}
+
+/// Engine names `[engine]` accepts for each tier.
+pub const ENGINES: [&str; 2] = ["docling", "native_text"];
+
+/// The conversions a chunk is tried with, in order: the chosen engine, then Docling for
+/// native-text chunks that fail, with each Docling attempt walking
+/// `docling.backend.pdf_backend`. Entries read `native_text` or `docling:<backend>`.
+pub fn fallback_chain(cfg: &Config, decision: &PolicyDecision) -> Vec<String> {
+    let docling = cfg
+        .docling
+        .backend
+        .pdf_backend
+        .iter()
+        .map(|backend| format!("docling:{backend}"));
+    match decision.chosen_engine.as_str() {
+        "native_text" => std::iter::once("native_text".to_string())
+            .chain(docling)
+            .collect(),
+        "docling" => docling.collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+/// Whether a native-text conversion failed in a way Docling may recover from.
+pub fn native_text_needs_fallback(out: &Result<ConvertOut>) -> bool {
+    match out {
+        Ok(o) => !o.ok || o.warnings.iter().any(|w| w.contains("missing pypdf import")),
+        Err(e) => e.to_string().contains("missing pypdf import"),
+    }
+}
+
+/// Reject `[docling.tier_overrides.*]` tables that name no known tier, or keys (there
+/// or in `[docling.table_chunks.overrides]`) outside the `[docling.pipeline]` / `ocr.`
+/// namespaces the runner understands.
+pub fn validate_tier_overrides(cfg: &Config) -> Result<()> {
+    for (tier, overrides) in &cfg.docling.tier_overrides {
+        if QualityTier::from_label(tier).map(QualityTier::config_key) != Some(tier.as_str()) {
+            return Err(anyhow!(
+                "unknown tier in docling.tier_overrides: {tier} (expected HIGH_TEXT, MIXED_TEXT or SCAN)"
+            ));
+        }
+        check_override_keys(&format!("docling.tier_overrides.{tier}"), overrides)?;
+    }
+    let table_chunks = &cfg.docling.table_chunks;
+    if !matches!(table_chunks.mode.as_str(), "off" | "detect" | "route") {
+        return Err(anyhow!(
+            "unsupported docling.table_chunks.mode: {} (expected off, detect or route)",
+            table_chunks.mode
+        ));
+    }
+    check_override_keys("docling.table_chunks.overrides", &table_chunks.overrides)
+}
+
+/// Reject unknown `[security.pdf_features]` actions, and "strip" for features that
+/// cannot be removed from a copy.
+pub fn validate_pdf_features(cfg: &Config) -> Result<()> {
+    let features = &cfg.security.pdf_features;
+    for feature in ["xfa", "javascript", "attachments", "xref_repaired", "forms"] {
+        let action = features.action(feature).unwrap_or_default();
+        let strippable = !matches!(feature, "forms" | "xref_repaired");
+        match action {
+            "allow" | "warn" | "refuse" => {}
+            "strip" if strippable => {}
+            "strip" => {
+                return Err(anyhow!(
+                    "security.pdf_features.{feature} cannot be \"strip\" (expected allow, warn or refuse)"
+                ));
+            }
+            other => {
+                return Err(anyhow!(
+                    "unsupported security.pdf_features.{feature}: {other} (expected allow, warn, strip or refuse)"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
 
-    match tier {
-        QualityTier::HighText => PolicyDecision {
-            tier,
-            chosen_engine: cfg.engine.high_text_engine.clone(),
-            do_ocr: false,
-        },
-        QualityTier::MixedText => PolicyDecision {
-            tier,
-            chosen_engine: cfg.engine.mixed_text_engine.clone(),
-            do_ocr: cfg.docling.pipeline.do_ocr,
-        },
-        QualityTier::Scan => PolicyDecision {
-            tier,
-            chosen_engine: cfg.engine.scan_engine.clone(),
-            do_ocr: true,
-        },
+fn check_override_keys(
+    table: &str,
+    overrides: &BTreeMap<String, serde_json::Value>,
+) -> Result<()> {
+    for key in overrides.keys() {
+        let name = key.strip_prefix("ocr.").unwrap_or(key);
+        if name.is_empty() || name.contains('.') {
+            return Err(anyhow!("invalid {table} key: {key}"));
+        }
     }
+    Ok(())
 }
@@ -1,11 +1,403 @@
-use crate::config::Config;
-use anyhow::Result;
+use crate::{
+    chunk_stage::{self, ChunkInfo, CompiledStage},
+    config::Config,
+    spreads,
+};
+use anyhow::{anyhow, Result};
+use base64::Engine as _;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use unicode_normalization::UnicodeNormalization;
 
+/// An image decoded from a data-URI in converted markdown, destined for `final/images/`.
+#[derive(Debug, Clone)]
+pub struct EmbeddedImage {
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddedImageStats {
+    pub mode: String,
+    pub found: u32,
+    pub stripped: u32,
+    pub externalized: u32,
+    pub decode_failures: u32,
+    pub markdown_bytes_removed: u64,
+    pub image_bytes_written: u64,
+}
+
+/// Placeholder the engines emit between pages. The pipeline rewrites it into absolute
+/// page markers right after conversion.
+pub const PAGE_BREAK_PLACEHOLDER: &str = "<!-- quack:page-break -->";
+
+/// Placeholder docling emits between the left and right halves of a page with
+/// `input.split_spreads`; `mark_pages` turns it into half-page markers.
+pub const HALF_PAGE_BREAK_PLACEHOLDER: &str = "<!-- quack:half-page-break -->";
+
+const MARKER_PREFIX: &str = "<!-- quack:";
+
+pub fn page_marker(page: u32) -> String {
+    format!("<!-- quack:page {page} -->")
+}
+
+/// A page marker relabeled with the printed page number (`postprocess.printed_page_numbers
+/// = "markers"`). `parse_page_marker` still reads the PDF page from it.
+pub fn printed_page_marker(printed: &str, page: u32) -> String {
+    format!("<!-- quack:page {printed} (pdf {page}) -->")
+}
+
+pub fn parse_page_marker(line: &str) -> Option<u32> {
+    let body = line
+        .trim()
+        .strip_prefix("<!-- quack:page ")?
+        .strip_suffix(" -->")?;
+    match body.split_once(" (pdf ") {
+        Some((_, pdf)) => pdf.strip_suffix(')')?.parse().ok(),
+        None => body.parse().ok(),
+    }
+}
+
+/// A marker opening one half of a split two-up page, numbered as a book page:
+/// `<!-- quack:half-page 11 (pdf 6 left) -->`. It sits under the page's own marker, so
+/// everything counted per PDF page is unchanged.
+pub fn half_page_marker(half: u32, page: u32, side: &str) -> String {
+    format!("<!-- quack:half-page {half} (pdf {page} {side}) -->")
+}
+
+/// Read a half-page marker: the book page and the PDF page it is on.
+pub fn parse_half_page_marker(line: &str) -> Option<(u32, u32)> {
+    let body = line
+        .trim()
+        .strip_prefix("<!-- quack:half-page ")?
+        .strip_suffix(") -->")?;
+    let (half, pdf) = body.split_once(" (pdf ")?;
+    let (page, _side) = pdf.split_once(' ')?;
+    Some((half.parse().ok()?, page.parse().ok()?))
+}
+
+/// A marker opening one tile of a page cut up for OCR (`input.tile_oversized_pages`),
+/// with its place in the grid: `<!-- quack:tile row 1 col 2 of 2x3 (pdf 14) -->`.
+/// Docling writes it; tiles follow each other row by row under the page's own marker.
+pub fn tile_marker(page: u32, row: u32, col: u32, rows: u32, cols: u32) -> String {
+    format!("<!-- quack:tile row {row} col {col} of {rows}x{cols} (pdf {page}) -->")
+}
+
+/// Read a tile marker: the PDF page, row, and column.
+pub fn parse_tile_marker(line: &str) -> Option<(u32, u32, u32)> {
+    let body = line
+        .trim()
+        .strip_prefix("<!-- quack:tile row ")?
+        .strip_suffix(") -->")?;
+    let (grid, page) = body.split_once(" (pdf ")?;
+    let (row, rest) = grid.split_once(" col ")?;
+    let (col, _) = rest.split_once(" of ")?;
+    Some((page.parse().ok()?, row.parse().ok()?, col.parse().ok()?))
+}
+
+/// A chunk's provenance, written as `<!-- quack:chunk index=3 pages=81-120 engine=docling -->`
+/// at the start of each chunk with `postprocess.chunk_separator = "comment"` or `"both"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkMarker {
+    pub index: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+    pub engine: String,
+}
+
+pub fn chunk_marker(m: &ChunkMarker) -> String {
+    format!(
+        "<!-- quack:chunk index={} pages={}-{} engine={} -->",
+        m.index, m.start_page, m.end_page, m.engine
+    )
+}
+
+/// Read a chunk marker line. Fields may come in any order; unknown ones are ignored.
+pub fn parse_chunk_marker(line: &str) -> Option<ChunkMarker> {
+    let body = line
+        .trim()
+        .strip_prefix("<!-- quack:chunk ")?
+        .strip_suffix(" -->")?;
+    let (mut index, mut pages, mut engine) = (None, None, None);
+    for field in body.split_whitespace() {
+        match field.split_once('=')? {
+            ("index", v) => index = v.parse().ok(),
+            ("pages", v) => {
+                let (a, b) = v.split_once('-')?;
+                pages = Some((a.parse().ok()?, b.parse().ok()?));
+            }
+            ("engine", v) => engine = Some(v.to_string()),
+            _ => {}
+        }
+    }
+    let (start_page, end_page) = pages?;
+    Some(ChunkMarker {
+        index: index?,
+        start_page,
+        end_page,
+        engine: engine?,
+    })
+}
+
+/// Internal `<!-- quack:... -->` lines survive every cleanup stage untouched.
+pub fn is_marker_line(line: &str) -> bool {
+    line.trim_start().starts_with(MARKER_PREFIX)
+}
+
+/// Prefix a chunk's markdown with a marker for `start_page` and turn each engine page
+/// break into a marker for the following page. A page split into halves gets a
+/// half-page marker before each half.
+pub fn mark_pages(md: &str, start_page: u32) -> String {
+    let mut page = start_page;
+    let mut out = page_marker(page);
+    out.push_str("\n\n");
+    let mut pieces = md.split(PAGE_BREAK_PLACEHOLDER);
+    if let Some(first) = pieces.next() {
+        out.push_str(&mark_halves(first, page));
+    }
+    for piece in pieces {
+        page += 1;
+        out.push_str(&page_marker(page));
+        out.push_str(&mark_halves(piece, page));
+    }
+    out
+}
+
+fn mark_halves(piece: &str, page: u32) -> String {
+    let Some((left, right)) = piece.split_once(HALF_PAGE_BREAK_PLACEHOLDER) else {
+        return piece.to_string();
+    };
+    let (left_page, right_page) = spreads::half_pages(page);
+    let body = left.trim_start();
+    format!(
+        "{}{}\n\n{body}{}{right}",
+        &left[..left.len() - body.len()],
+        half_page_marker(left_page, page, "left"),
+        half_page_marker(right_page, page, "right"),
+    )
+}
+
+/// Split a merged transcript back into the stretches of the chunks covering
+/// `ranges` (1-based inclusive page ranges), by its page markers. A chunk comment goes
+/// with the chunk it opens; the `---` separator between chunks is dropped. Pages
+/// outside every range are left out.
+pub fn split_by_chunks(md: &str, ranges: &[(u32, u32)]) -> Vec<String> {
+    let mut out: Vec<Vec<&str>> = vec![Vec::new(); ranges.len()];
+    let mut current: Option<usize> = None;
+    let mut opening: Option<&str> = None;
+    for line in md.lines() {
+        if parse_chunk_marker(line).is_some() {
+            opening = Some(line);
+            continue;
+        }
+        if let Some(page) = parse_page_marker(line) {
+            let chunk = ranges.iter().position(|&(a, b)| (a..=b).contains(&page));
+            if chunk != current {
+                if let Some(prev) = current {
+                    let lines = &mut out[prev];
+                    while lines
+                        .last()
+                        .is_some_and(|l| l.trim().is_empty() || l.trim() == "---")
+                    {
+                        lines.pop();
+                    }
+                }
+                current = chunk;
+                if let (Some(c), Some(marker)) = (current, opening.take()) {
+                    out[c].extend([marker, ""]);
+                }
+            }
+        } else if let Some(marker) = opening.take_if(|_| !line.trim().is_empty())
+            && let Some(c) = current
+        {
+            out[c].push(marker);
+        }
+        if let Some(c) = current {
+            out[c].push(line);
+        }
+    }
+    out.into_iter()
+        .map(|lines| {
+            let mut text = lines.join("\n").trim().to_string();
+            text.push('\n');
+            text
+        })
+        .collect()
+}
+
+/// Remove page, half-page, and tile markers, along with the blank line they leave
+/// behind.
+pub fn strip_page_markers(md: &str) -> String {
+    strip_lines(md, |line| {
+        parse_page_marker(line).is_some()
+            || parse_half_page_marker(line).is_some()
+            || parse_tile_marker(line).is_some()
+    })
+}
+
+fn strip_lines(md: &str, strip: impl Fn(&str) -> bool) -> String {
+    let lines: Vec<&str> = md.lines().collect();
+    let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut skip_blank = false;
+    for line in lines {
+        if strip(line) {
+            skip_blank = out.last().is_none_or(|l| l.trim().is_empty());
+            continue;
+        }
+        if skip_blank && line.trim().is_empty() {
+            skip_blank = false;
+            continue;
+        }
+        skip_blank = false;
+        out.push(line);
+    }
+    out.join("\n")
+}
+
+/// Replace the content of `page` (between its marker and the next one) in page-marked
+/// markdown. The marker itself is kept so page accounting stays intact.
+pub fn replace_page_content(md: &str, page: u32, replacement: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    let mut in_page = false;
+    for line in md.lines() {
+        if let Some(p) = parse_page_marker(line) {
+            in_page = p == page;
+            out.push(line);
+            if in_page && !replacement.is_empty() {
+                out.push("");
+                out.push(replacement);
+                out.push("");
+            }
+            continue;
+        }
+        if !in_page {
+            out.push(line);
+        }
+    }
+    out.join("\n")
+}
+
+/// `<!-- page N unreadable: reason -->`, with the reason flattened to one safe line.
+pub fn unreadable_page_placeholder(page: u32, reason: &str) -> String {
+    let reason = reason
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("--", "-");
+    format!("<!-- page {page} unreadable: {reason} -->")
+}
+
+/// Per-document facts merging needs beyond the config.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeOptions {
+    /// Promote likely chapter titles when the document has few headings.
+    pub infer_chapter_titles: bool,
+    pub page_count: u32,
+}
+
+/// The postprocess settings checked, and their regexes compiled, once. A job builds
+/// one and reuses it for every streamed chunk and the final merge.
+#[derive(Debug, Clone)]
+pub struct PostprocessContext<'a> {
+    cfg: &'a Config,
+    separator: &'static str,
+    /// `repeated_line_scope = "chunk_window"`: the parts either side counted with each.
+    window: Option<usize>,
+    /// `postprocess.regex.patterns`, when `remove_by_regex` is on.
+    removal_patterns: Vec<Regex>,
+    /// A `![alt](data:image/...;base64,...)` reference.
+    data_uri: Regex,
+    chunk_stages: Vec<CompiledStage>,
+}
+
+impl<'a> PostprocessContext<'a> {
+    pub fn new(cfg: &'a Config) -> Result<Self> {
+        let pp = &cfg.postprocess;
+        let separator = match pp.chunk_separator.as_str() {
+            "rule" | "both" => "\n\n---\n\n",
+            "comment" => "\n\n",
+            other => return Err(anyhow!("unknown postprocess.chunk_separator: {other}")),
+        };
+        let window = match pp.repeated_line_scope.as_str() {
+            "document" => None,
+            "chunk_window" => Some(pp.repeated_line_chunk_window as usize),
+            other => return Err(anyhow!("unknown postprocess.repeated_line_scope: {other}")),
+        };
+        if !matches!(pp.verse.as_str(), "off" | "protect" | "hard_breaks") {
+            return Err(anyhow!("unknown postprocess.verse: {}", pp.verse));
+        }
+        if !matches!(pp.embedded_images.as_str(), "keep" | "strip" | "externalize") {
+            return Err(anyhow!("unknown postprocess.embedded_images: {}", pp.embedded_images));
+        }
+        let removal_patterns = if pp.remove_by_regex {
+            pp.regex
+                .patterns
+                .iter()
+                .map(|p| Regex::new(p))
+                .collect::<std::result::Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+        let data_uri = Regex::new(
+            r"!\[([^\]]*)\]\(\s*data:(image/[A-Za-z0-9.+-]+);base64,([A-Za-z0-9+/=\s]+?)\s*\)",
+        )?;
+        let chunk_stages = chunk_stage::compile(&pp.chunk_stages)?;
+        Ok(Self {
+            cfg,
+            separator,
+            window,
+            removal_patterns,
+            data_uri,
+            chunk_stages,
+        })
+    }
+
+    /// Join converted parts and run the cleanup stages over them.
+    pub fn merge(&self, parts: Vec<String>, opts: MergeOptions) -> Result<String> {
+        merge_parts(self, parts, opts)
+    }
+
+    /// Run `postprocess.chunk_stages` over one chunk's markdown, before merging.
+    pub fn run_chunk_stages(&self, part: &str, chunk: &ChunkInfo) -> String {
+        chunk_stage::apply(&self.chunk_stages, part, chunk)
+    }
+
+    /// [`handle_embedded_images`] with the compiled data-URI regex.
+    pub fn handle_embedded_images(
+        &self,
+        md: &str,
+        images: &mut Vec<EmbeddedImage>,
+        stats: &mut EmbeddedImageStats,
+    ) -> String {
+        replace_data_uris(self, md, images, stats)
+    }
+}
+
 pub fn merge_markdown(cfg: &Config, parts: Vec<String>) -> Result<String> {
-    let mut merged = parts.join("\n\n---\n\n");
+    merge_markdown_with(cfg, parts, MergeOptions::default())
+}
+
+/// [`PostprocessContext::merge`] with a context built for this call alone.
+pub fn merge_markdown_with(cfg: &Config, parts: Vec<String>, opts: MergeOptions) -> Result<String> {
+    PostprocessContext::new(cfg)?.merge(parts, opts)
+}
+
+fn merge_parts(ctx: &PostprocessContext, parts: Vec<String>, opts: MergeOptions) -> Result<String> {
+    let (cfg, separator, window) = (ctx.cfg, ctx.separator, ctx.window);
+    // Repeated lines are counted per chunk neighbourhood, so each chunk is tagged until
+    // they are removed.
+    let tag_parts = window.is_some() && cfg.postprocess.remove_repeated_lines;
+    let mut merged = if tag_parts {
+        parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| format!("{}\n{part}", part_marker(i)))
+            .collect::<Vec<_>>()
+            .join(separator)
+    } else {
+        parts.join(separator)
+    };
 
     if cfg.postprocess.normalize_newlines {
         merged = merged.replace("\r\n", "\n");
@@ -25,17 +417,71 @@ pub fn merge_markdown(cfg: &Config, parts: Vec<String>) -> Result<String> {
             .join("\n");
     }
 
+    if cfg.postprocess.cjk_spacing {
+        merged = crate::script::join_cjk_spaces(&merged);
+    }
+
+    if cfg.postprocess.rtl_line_marks {
+        merged = crate::script::mark_rtl_lines(&merged);
+    }
+
+    // Before line removal: the default regexes drop long all-caps lines, which is what
+    // OCR'd chapter titles look like.
+    if opts.infer_chapter_titles
+        && crate::chapters::has_few_headings(&merged, opts.page_count)
+    {
+        let (promoted, count) = crate::chapters::promote_chapter_titles(&merged);
+        tracing::info!("chapter titles promoted to headings: {count}");
+        merged = promoted;
+    }
+
+    let verse_mode = cfg.postprocess.verse.as_str();
+    let mut verse = match verse_mode {
+        "off" => vec![false; merged.lines().count()],
+        _ => crate::verse::detect_verse(
+            &merged,
+            cfg.postprocess.verse_max_line_chars as usize,
+            cfg.postprocess.verse_min_lines as usize,
+        ),
+    };
+
     if cfg.postprocess.remove_repeated_lines {
-        merged = remove_repeated_lines(cfg, &merged);
+        merged = remove_repeated_lines(cfg, &merged, &mut verse, window);
     }
 
     if cfg.postprocess.remove_by_regex {
-        merged = remove_by_regex(cfg, &merged)?;
+        merged = remove_by_regex(&ctx.removal_patterns, &merged, &mut verse);
+    }
+
+    if verse_mode == "hard_breaks" {
+        merged = crate::verse::add_hard_breaks(&merged, &verse);
+    }
+
+    if tag_parts {
+        merged = merged
+            .lines()
+            .filter(|l| parse_part_marker(l).is_none())
+            .collect::<Vec<_>>()
+            .join("\n");
     }
 
     Ok(merged)
 }
 
+/// Tags the start of each merged part for `repeated_line_scope = "chunk_window"`; never
+/// left in the output.
+fn part_marker(index: usize) -> String {
+    format!("<!-- quack:part {index} -->")
+}
+
+fn parse_part_marker(line: &str) -> Option<usize> {
+    line.trim()
+        .strip_prefix("<!-- quack:part ")?
+        .strip_suffix(" -->")?
+        .parse()
+        .ok()
+}
+
 fn sanitize_control_chars(s: &str, codes: &[u8]) -> String {
     if codes.is_empty() {
         return s.to_string();
@@ -64,50 +510,83 @@ fn sanitize_control_chars(s: &str, codes: &[u8]) -> String {
         .collect()
 }
 
-fn remove_repeated_lines(cfg: &Config, s: &str) -> String {
-    let mut counts: HashMap<&str, u32> = HashMap::new();
+/// Both removal passes leave `protected` (verse) lines alone and keep the mask aligned
+/// with the lines they return.
+/// Drop short lines that occur at least `repeated_line_min_occurrences` times. With a
+/// `window`, occurrences are counted only within a line's own part (tagged by
+/// `part_marker`) and the `window` parts either side of it.
+fn remove_repeated_lines(
+    cfg: &Config,
+    s: &str,
+    protected: &mut Vec<bool>,
+    window: Option<usize>,
+) -> String {
     let lines: Vec<&str> = s.lines().collect();
+    let mut part = 0;
+    let parts: Vec<usize> = lines
+        .iter()
+        .map(|l| {
+            part = parse_part_marker(l).unwrap_or(part);
+            part
+        })
+        .collect();
+    let part_count = parts.last().map_or(1, |p| p + 1);
+    let mut counts: Vec<HashMap<&str, u32>> = vec![HashMap::new(); part_count];
 
-    for &l in &lines {
+    for (idx, &l) in lines.iter().enumerate() {
         let l2 = l.trim();
-        if l2.is_empty() {
+        if l2.is_empty() || is_marker_line(l2) || protected[idx] {
             continue;
         }
         if l2.len() > cfg.postprocess.repeated_line_max_length as usize {
             continue;
         }
-        *counts.entry(l2).or_insert(0) += 1;
+        let bucket = if window.is_some() { parts[idx] } else { 0 };
+        *counts[bucket].entry(l2).or_insert(0) += 1;
     }
 
+    let count = |idx: usize, line: &str| -> u32 {
+        let Some(window) = window else {
+            return counts[0].get(line).copied().unwrap_or(0);
+        };
+        let p = parts[idx];
+        let hi = (p + window).min(part_count - 1);
+        counts[p.saturating_sub(window)..=hi]
+            .iter()
+            .filter_map(|c| c.get(line))
+            .sum()
+    };
+
     let min = cfg.postprocess.repeated_line_min_occurrences;
     let mut out = Vec::with_capacity(lines.len());
-    for &l in &lines {
+    let mut mask = Vec::with_capacity(lines.len());
+    for (idx, &l) in lines.iter().enumerate() {
         let l2 = l.trim();
-        let keep = if l2.is_empty() {
+        let keep = if l2.is_empty() || protected[idx] {
             true
         } else {
-            counts.get(l2).copied().unwrap_or(0) < min
+            count(idx, l2) < min
         };
         if keep {
             out.push(l);
+            mask.push(protected[idx]);
         }
     }
+    *protected = mask;
     out.join("\n")
 }
 
-fn remove_by_regex(cfg: &Config, s: &str) -> Result<String> {
-    let regs: Vec<Regex> = cfg
-        .postprocess
-        .regex
-        .patterns
-        .iter()
-        .map(|p| Regex::new(p))
-        .collect::<std::result::Result<Vec<_>, _>>()?;
-
+fn remove_by_regex(regs: &[Regex], s: &str, protected: &mut Vec<bool>) -> String {
     let mut out = Vec::new();
-    for line in s.lines() {
+    let mut mask = Vec::new();
+    for (idx, line) in s.lines().enumerate() {
+        if is_marker_line(line) || protected[idx] {
+            out.push(line);
+            mask.push(protected[idx]);
+            continue;
+        }
         let mut matched = false;
-        for r in &regs {
+        for r in regs {
             if r.is_match(line.trim()) {
                 matched = true;
                 break;
@@ -115,15 +594,110 @@ fn remove_by_regex(cfg: &Config, s: &str) -> Result<String> {
         }
         if !matched {
             out.push(line);
+            mask.push(false);
         }
     }
-    Ok(out.join("\n"))
+    *protected = mask;
+    out.join("\n")
 }
 
-pub fn markdown_to_text(_cfg: &Config, md: &str) -> Result<String> {
-    let mut s = md.replace("**", "");
+pub fn markdown_to_text(cfg: &Config, md: &str) -> Result<String> {
+    let mut s = strip_lines(md, |line| parse_chunk_marker(line).is_some()).replace("**", "");
     s = s.replace("# ", "");
     s = s.replace("## ", "");
     s = s.replace("### ", "");
+    if cfg.postprocess.verse == "hard_breaks" {
+        // Undo the verse hard breaks and indentation added by `verse::add_hard_breaks`.
+        s = s
+            .lines()
+            .map(|l| {
+                let l = l.strip_suffix('\\').filter(|r| !r.ends_with('\\')).unwrap_or(l);
+                l.replace('\u{a0}', " ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
     Ok(s)
 }
+
+/// Strip or externalize `![alt](data:image/...;base64,...)` references according to
+/// `postprocess.embedded_images` ("keep" | "strip" | "externalize").
+///
+/// Externalized images are appended to `images` and the reference is rewritten to
+/// `images/<file>`, which resolves relative to the final markdown transcript.
+pub fn handle_embedded_images(
+    cfg: &Config,
+    md: &str,
+    images: &mut Vec<EmbeddedImage>,
+    stats: &mut EmbeddedImageStats,
+) -> Result<String> {
+    Ok(PostprocessContext::new(cfg)?.handle_embedded_images(md, images, stats))
+}
+
+fn replace_data_uris(
+    ctx: &PostprocessContext,
+    md: &str,
+    images: &mut Vec<EmbeddedImage>,
+    stats: &mut EmbeddedImageStats,
+) -> String {
+    let mode = ctx.cfg.postprocess.embedded_images.as_str();
+    stats.mode = mode.to_string();
+    if mode == "keep" {
+        return md.to_string();
+    }
+    let re = &ctx.data_uri;
+
+    let mut out = String::with_capacity(md.len());
+    let mut last = 0;
+    for caps in re.captures_iter(md) {
+        let whole = caps.get(0).expect("capture 0");
+        out.push_str(&md[last..whole.start()]);
+        last = whole.end();
+        stats.found += 1;
+
+        let alt = &caps[1];
+        let replacement = if mode == "externalize" {
+            let payload: String = caps[3].chars().filter(|c| !c.is_whitespace()).collect();
+            match base64::engine::general_purpose::STANDARD.decode(payload.as_bytes()) {
+                Ok(bytes) => {
+                    let file_name = format!(
+                        "image_{:05}.{}",
+                        stats.externalized + 1,
+                        image_extension(&caps[2])
+                    );
+                    stats.externalized += 1;
+                    stats.image_bytes_written += bytes.len() as u64;
+                    let r = format!("![{alt}](images/{file_name})");
+                    images.push(EmbeddedImage { file_name, bytes });
+                    r
+                }
+                Err(_) => {
+                    stats.decode_failures += 1;
+                    stats.stripped += 1;
+                    "<!-- image -->".to_string()
+                }
+            }
+        } else {
+            stats.stripped += 1;
+            "<!-- image -->".to_string()
+        };
+
+        stats.markdown_bytes_removed += (whole.len() as u64).saturating_sub(replacement.len() as u64);
+        out.push_str(&replacement);
+    }
+    out.push_str(&md[last..]);
+    out
+}
+
+fn image_extension(mime: &str) -> &'static str {
+    match mime.to_ascii_lowercase().as_str() {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/tiff" => "tiff",
+        "image/bmp" => "bmp",
+        _ => "bin",
+    }
+}
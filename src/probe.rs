@@ -1,17 +1,32 @@
-use crate::{config::Config, engine::Engine};
+use crate::{
+    config::Config,
+    engine::Engine,
+    render::{self, RenderProbeStats},
+    textstats,
+    util::{ensure_dir, hash_file, sha256_hex},
+};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProbeResult {
     pub input: ProbeInput,
     pub sample: ProbeSampleStats,
+    pub structure: ProbeStructure,
+    /// Ink and image coverage of the rendered sample (`classification.enable_render_probe`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render: Option<RenderProbeStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProbeInput {
     pub path: String,
+    /// `path` with symlinks resolved, when a job was run on it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_path: Option<String>,
     pub file_bytes: u64,
     pub page_count: u32,
 }
@@ -24,6 +39,38 @@ pub struct ProbeSampleStats {
     pub whitespace_ratio: f32,
 }
 
+/// Structural signals counted over the sampled pages (form fields cover the whole
+/// document). Not used by the tier thresholds yet, but reported so decisions can be
+/// audited against them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeStructure {
+    pub has_text_layer: bool,
+    pub pages_with_text: u32,
+    pub fonts: Vec<String>,
+    pub font_count: u32,
+    pub images_per_page: f32,
+    pub max_images_on_page: u32,
+    pub annotation_count: u32,
+    pub form_field_count: u32,
+    /// Features `[security.pdf_features]` acts on, from the whole document: `xfa`,
+    /// `forms`, `javascript`, `attachments`, `xref_repaired`.
+    pub risky_features: Vec<String>,
+    /// Sampled pages that look like two-up scans: landscape, with a gutter down the
+    /// middle when the page can be rendered.
+    pub spread_pages: u32,
+    /// Every page larger than A3, from the whole document; `tiles` decides which are
+    /// too large to OCR whole.
+    pub large_pages: Vec<LargePage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LargePage {
+    pub page: u32,
+    pub width_pt: f64,
+    pub height_pt: f64,
+}
+
 pub fn probe_pdf(cfg: &Config, engine: &dyn Engine, input: &Path) -> Result<ProbeResult> {
     let meta = std::fs::metadata(input).with_context(|| "stat input")?;
     let file_bytes = meta.len();
@@ -32,27 +79,152 @@ pub fn probe_pdf(cfg: &Config, engine: &dyn Engine, input: &Path) -> Result<Prob
     }
 
     let probe = engine
-        .probe_pdf(input, cfg.classification.sample_pages)
+        .probe_pdf(input, &cfg.classification)
         .with_context(|| "engine probe_pdf failed")?;
-
-    if probe.page_count > cfg.limits.max_input_pages {
-        anyhow::bail!("input exceeds max_input_pages: {}", probe.page_count);
-    }
-    if probe.page_count == 0 {
-        anyhow::bail!("input has zero pages");
-    }
+    check_page_limits(cfg, probe.page_count)?;
+    let render = if cfg.classification.enable_render_probe {
+        Some(render::probe(cfg, input, probe.page_count).context("render probe failed")?)
+    } else {
+        None
+    };
 
     Ok(ProbeResult {
         input: ProbeInput {
             path: input.display().to_string(),
+            canonical_path: None,
             file_bytes,
             page_count: probe.page_count,
         },
         sample: ProbeSampleStats {
             sampled_pages: probe.sampled_pages,
             avg_chars_per_page: probe.avg_chars_per_page,
-            garbage_ratio: probe.garbage_ratio,
+            garbage_ratio: if probe.sample_texts.is_empty() {
+                probe.garbage_ratio
+            } else {
+                textstats::garbage_ratio(&probe.sample_texts.join("\n\n"))
+            },
             whitespace_ratio: probe.whitespace_ratio,
         },
+        structure: probe.structure,
+        render,
     })
 }
+
+/// A probe saved from `classify` output (its `probe` field) or a bare `ProbeResult`.
+pub fn read_probe_json(path: &Path) -> Result<ProbeResult> {
+    let raw =
+        std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut value: serde_json::Value =
+        serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+    if let Some(probe) = value.get_mut("probe") {
+        value = probe.take();
+    }
+    serde_json::from_value(value).with_context(|| format!("not a probe result: {}", path.display()))
+}
+
+fn check_page_limits(cfg: &Config, page_count: u32) -> Result<()> {
+    if page_count > cfg.limits.max_input_pages {
+        anyhow::bail!("input exceeds max_input_pages: {}", page_count);
+    }
+    if page_count == 0 {
+        anyhow::bail!("input has zero pages");
+    }
+    Ok(())
+}
+
+/// Probe results in `cache_dir/probes/`, keyed by input content hash and the sampling
+/// settings, so `classify`, `plan` and `run` on the same PDF probe it once.
+pub struct ProbeCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedProbe {
+    created_unix: u64,
+    probe: ProbeResult,
+}
+
+impl ProbeCache {
+    /// `None` when `classification.probe_cache_ttl_seconds` is 0.
+    pub fn from_config(cfg: &Config) -> Option<Self> {
+        let ttl = cfg.classification.probe_cache_ttl_seconds;
+        (ttl > 0).then(|| Self {
+            dir: Path::new(&cfg.paths.cache_dir).join("probes"),
+            ttl: Duration::from_secs(ttl),
+        })
+    }
+
+    /// A fresh cached probe of `input`, or a new probe that is then cached. Size and
+    /// page limits are checked either way.
+    pub fn probe(&self, cfg: &Config, engine: &dyn Engine, input: &Path) -> Result<ProbeResult> {
+        let key = probe_key(cfg, input)?;
+        let path = self.dir.join(format!("{key}.json"));
+        if let Some(mut probe) = self.load(&path) {
+            debug!("probe cache hit: {}", path.display());
+            let file_bytes = std::fs::metadata(input).with_context(|| "stat input")?.len();
+            if file_bytes > cfg.limits.max_input_file_bytes {
+                anyhow::bail!("input exceeds max_input_file_bytes: {}", file_bytes);
+            }
+            check_page_limits(cfg, probe.input.page_count)?;
+            probe.input.path = input.display().to_string();
+            return Ok(probe);
+        }
+
+        let probe = probe_pdf(cfg, engine, input)?;
+        if let Err(err) = self.store(&path, &probe) {
+            warn!("could not cache probe in {}: {err:#}", path.display());
+        }
+        Ok(probe)
+    }
+
+    fn load(&self, path: &Path) -> Option<ProbeResult> {
+        let raw = std::fs::read_to_string(path).ok()?;
+        let cached: CachedProbe = match serde_json::from_str(&raw) {
+            Ok(c) => c,
+            Err(err) => {
+                warn!("ignoring unreadable probe cache {}: {err}", path.display());
+                return None;
+            }
+        };
+        let age = unix_now().saturating_sub(cached.created_unix);
+        (age < self.ttl.as_secs()).then_some(cached.probe)
+    }
+
+    fn store(&self, path: &Path, probe: &ProbeResult) -> Result<()> {
+        ensure_dir(&self.dir)?;
+        let cached = CachedProbe {
+            created_unix: unix_now(),
+            probe: probe.clone(),
+        };
+        // Write then rename so a concurrent reader never sees half a file.
+        let tmp = path.with_extension(format!("json.tmp{}", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&cached)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Input content plus every setting that changes what a probe samples.
+fn probe_key(cfg: &Config, input: &Path) -> Result<String> {
+    let input_hash =
+        hash_file(cfg, input).with_context(|| format!("hashing input: {}", input.display()))?;
+    let c = &cfg.classification;
+    let key = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "input_hash": input_hash,
+        "hashing": cfg.hashing.mode,
+        "sample_pages": c.sample_pages,
+        "sample_pages_percent": c.sample_pages_percent,
+        "max_sample_pages": c.max_sample_pages,
+        "enable_render_probe": c.enable_render_probe,
+    });
+    Ok(sha256_hex(key.to_string().as_bytes()))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
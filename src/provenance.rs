@@ -0,0 +1,54 @@
+use crate::report::ChunkReport;
+use std::collections::BTreeMap;
+
+/// Heading of the section [`appendix`] writes.
+pub const APPENDIX_HEADING: &str = "## Processing provenance";
+
+/// A section listing how each chunk was converted: pages, engine and PDF backend, when
+/// it finished, and its warnings. Appended to the transcript with
+/// `output.provenance_appendix`, for archival standards that want processing
+/// provenance to travel with the text rather than beside it in `report.json`.
+/// `default_engine` names the engine of chunks whose metadata does not, and
+/// `converted_at` holds the finish time of each converted chunk by index.
+pub fn appendix(
+    chunks: &[ChunkReport],
+    default_engine: &str,
+    converted_at: &BTreeMap<u32, String>,
+) -> String {
+    let mut chunks: Vec<&ChunkReport> = chunks.iter().collect();
+    chunks.sort_by_key(|c| c.chunk_index);
+    let mut out = format!("{APPENDIX_HEADING}\n");
+    for c in chunks {
+        let engine = c.meta.engine.as_deref().unwrap_or(default_engine);
+        let mut line = format!(
+            "\n- Chunk {}, pages {}-{}: ",
+            c.chunk_index + 1,
+            c.start_page,
+            c.end_page
+        );
+        match c.skipped.as_deref() {
+            Some(reason) if !converted_at.contains_key(&c.chunk_index) => {
+                line.push_str(&format!("not converted ({reason})"));
+            }
+            skipped => {
+                line.push_str(engine);
+                if let Some(backend) = &c.pdf_backend {
+                    line.push_str(&format!(" ({backend})"));
+                }
+                if let Some(at) = converted_at.get(&c.chunk_index) {
+                    line.push_str(&format!(", converted {at}"));
+                }
+                if let Some(reason) = skipped {
+                    line.push_str(&format!(", {reason}"));
+                }
+            }
+        }
+        out.push_str(&line);
+        for warning in &c.warnings {
+            let warning = warning.split_whitespace().collect::<Vec<_>>().join(" ");
+            out.push_str(&format!("\n  - Warning: {warning}"));
+        }
+    }
+    out.push('\n');
+    out
+}
@@ -0,0 +1,132 @@
+use crate::config::Config;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::Arc;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Env values shorter than this are never masked; redacting `"1"` from
+/// `OMP_NUM_THREADS` would shred every log line.
+const MIN_ENV_VALUE_LEN: usize = 4;
+
+/// Masks configured secrets and user paths out of log output.
+///
+/// Each `logging.redact` entry that names a set environment variable masks that
+/// variable's value as `[REDACTED:NAME]`; any other entry is masked literally as
+/// `[REDACTED]`. The value of the variable named by `docling.vlm.api_key_env` is
+/// always masked.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    needles: Vec<(String, String)>,
+}
+
+impl Redactor {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self::with_env(cfg, |name| std::env::var(name).ok())
+    }
+
+    pub fn with_env(cfg: &Config, env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut needles = Vec::new();
+        let api_key_env = cfg.docling.vlm.api_key_env.trim();
+        let entries = cfg
+            .logging
+            .redact
+            .iter()
+            .map(String::as_str)
+            .chain((!api_key_env.is_empty()).then_some(api_key_env));
+
+        for entry in entries {
+            if entry.is_empty() {
+                continue;
+            }
+            match env(entry) {
+                Some(value) if value.len() >= MIN_ENV_VALUE_LEN => {
+                    needles.push((value, format!("[REDACTED:{entry}]")));
+                }
+                Some(_) => {}
+                None if entry != api_key_env => {
+                    needles.push((entry.to_string(), "[REDACTED]".to_string()));
+                }
+                None => {}
+            }
+        }
+
+        // Longest first so `/home/alice/secret` wins over `/home/alice`.
+        needles.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        needles.dedup_by(|a, b| a.0 == b.0);
+        Self { needles }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.needles.is_empty()
+    }
+
+    pub fn redact<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if !self.needles.iter().any(|(needle, _)| s.contains(needle.as_str())) {
+            return Cow::Borrowed(s);
+        }
+        let mut out = s.to_string();
+        for (needle, mask) in &self.needles {
+            if out.contains(needle.as_str()) {
+                out = out.replace(needle.as_str(), mask);
+            }
+        }
+        Cow::Owned(out)
+    }
+}
+
+/// A `MakeWriter` that runs every formatted log line through a [`Redactor`].
+#[derive(Clone)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+    redactor: Arc<Redactor>,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M, redactor: Arc<Redactor>) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+            redactor: self.redactor.clone(),
+        }
+    }
+}
+
+pub struct RedactingWriter<W> {
+    inner: W,
+    redactor: Arc<Redactor>,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The fmt layer hands over one fully formatted event per write, so masking
+        // per call never splits a needle.
+        if self.redactor.is_empty() {
+            return self.inner.write(buf);
+        }
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(self.redactor.redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The config as dumped to effective-config.toml: `docling.env` values are masked
+/// and the result is passed through the redactor.
+pub fn effective_config_toml(cfg: &Config, redactor: &Redactor) -> String {
+    let mut masked = cfg.clone();
+    for value in masked.docling.env.values_mut() {
+        *value = "[REDACTED]".to_string();
+    }
+    let raw = toml::to_string(&masked).unwrap_or_default();
+    redactor.redact(&raw).into_owned()
+}
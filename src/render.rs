@@ -0,0 +1,277 @@
+use crate::{config::Config, page_quality::PageQualityReport};
+use anyhow::{anyhow, Result};
+use flate2::{write::ZlibEncoder, Compression, Crc};
+use serde::{Deserialize, Serialize};
+use std::io::Write as _;
+use std::path::Path;
+use tracing::info;
+
+/// Width of the bitmaps the render probe measures.
+const PROBE_WIDTH_PX: u32 = 600;
+
+/// Pixels darker than this (0-255 luminance, over white) count as ink.
+const INK_THRESHOLD: f32 = 128.0;
+
+/// Rasterization statistics over the sampled pages (`classification.enable_render_probe`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenderProbeStats {
+    pub pages_rendered: u32,
+    /// Mean fraction of dark pixels.
+    pub avg_ink_coverage: f32,
+    /// Mean fraction of the page area covered by image objects.
+    pub avg_image_ratio: f32,
+    pub pages: Vec<PageRenderStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageRenderStats {
+    pub page: u32,
+    pub ink_coverage: f32,
+    pub image_ratio: f32,
+}
+
+/// A rendered page, RGBA with rows packed.
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+pub struct RenderedPage {
+    pub page: u32,
+    pub bitmap: Bitmap,
+    pub image_ratio: f32,
+}
+
+/// A PNG written under `final/`: a thumbnail or a flagged page.
+#[derive(Debug, Clone)]
+pub struct PageImage {
+    /// Relative to `final/`, e.g. `thumbnails/page-0001.png`.
+    pub file: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Renders pages with pdfium, loaded from `paths.pdfium_library` or the system library
+/// path. Without the `pdfium` cargo feature, [`Renderer::new`] fails and nothing renders.
+#[cfg(feature = "pdfium")]
+pub struct Renderer {
+    pdfium: pdfium_render::prelude::Pdfium,
+}
+
+#[cfg(feature = "pdfium")]
+impl Renderer {
+    pub fn new(cfg: &Config) -> Result<Self> {
+        use pdfium_render::prelude::Pdfium;
+        let library = cfg.paths.pdfium_library.trim();
+        let bindings = if library.is_empty() {
+            Pdfium::bind_to_system_library()
+        } else {
+            Pdfium::bind_to_library(library)
+        }
+        .map_err(|err| anyhow!("loading pdfium ({library:?}): {err}"))?;
+        Ok(Self {
+            pdfium: Pdfium::new(bindings),
+        })
+    }
+
+    /// Render 1-based `pages` of `input` `width` pixels wide.
+    pub fn render(&self, input: &Path, pages: &[u32], width: u32) -> Result<Vec<RenderedPage>> {
+        use pdfium_render::prelude::*;
+        let document = self
+            .pdfium
+            .load_pdf_from_file(input, None)
+            .map_err(|err| anyhow!("pdfium could not open {}: {err}", input.display()))?;
+        let mut out = Vec::new();
+        for &page_number in pages {
+            let page = document
+                .pages()
+                .get((page_number - 1) as PdfPageIndex)
+                .map_err(|err| anyhow!("pdfium could not load page {page_number}: {err}"))?;
+            let bitmap = page
+                .render_with_config(&PdfRenderConfig::new().set_target_width(width as Pixels))
+                .map_err(|err| anyhow!("pdfium could not render page {page_number}: {err}"))?;
+            let page_area = page.width().value * page.height().value;
+            let image_area: f32 = page
+                .objects()
+                .iter()
+                .filter(|object| object.object_type() == PdfPageObjectType::Image)
+                .filter_map(|object| object.bounds().ok())
+                .map(|bounds| bounds.width().value * bounds.height().value)
+                .sum();
+            out.push(RenderedPage {
+                page: page_number,
+                bitmap: Bitmap {
+                    width: bitmap.width() as u32,
+                    height: bitmap.height() as u32,
+                    rgba: bitmap.as_rgba_bytes(),
+                },
+                image_ratio: if page_area > 0.0 {
+                    (image_area / page_area).min(1.0)
+                } else {
+                    0.0
+                },
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(not(feature = "pdfium"))]
+pub enum Renderer {}
+
+#[cfg(not(feature = "pdfium"))]
+impl Renderer {
+    pub fn new(_cfg: &Config) -> Result<Self> {
+        Err(anyhow!(
+            "page rendering (classification.enable_render_probe, output.thumbnails, \
+             output.flagged_page_images) requires building quack-check with --features pdfium"
+        ))
+    }
+
+    pub fn render(&self, _input: &Path, _pages: &[u32], _width: u32) -> Result<Vec<RenderedPage>> {
+        match *self {}
+    }
+}
+
+/// Whether `[output]` asks for rendered page images.
+pub fn wants_page_images(cfg: &Config) -> bool {
+    cfg.output.thumbnails || cfg.output.flagged_page_images > 0
+}
+
+/// Render the pages the text probe samples and measure their ink and image coverage.
+pub fn probe(cfg: &Config, input: &Path, page_count: u32) -> Result<RenderProbeStats> {
+    let renderer = Renderer::new(cfg)?;
+    let pages = sample_pages(cfg, page_count);
+    let rendered = renderer.render(input, &pages, PROBE_WIDTH_PX)?;
+    let pages: Vec<PageRenderStats> = rendered
+        .iter()
+        .map(|r| PageRenderStats {
+            page: r.page,
+            ink_coverage: ink_coverage(&r.bitmap),
+            image_ratio: r.image_ratio,
+        })
+        .collect();
+    let n = pages.len().max(1) as f32;
+    let stats = RenderProbeStats {
+        pages_rendered: pages.len() as u32,
+        avg_ink_coverage: pages.iter().map(|p| p.ink_coverage).sum::<f32>() / n,
+        avg_image_ratio: pages.iter().map(|p| p.image_ratio).sum::<f32>() / n,
+        pages,
+    };
+    info!(
+        "render probe pages={} ink_coverage={:.3} image_ratio={:.3}",
+        stats.pages_rendered, stats.avg_ink_coverage, stats.avg_image_ratio
+    );
+    Ok(stats)
+}
+
+/// Thumbnails of every page (`output.thumbnails`) and full-width images of the worst
+/// pages by quality score (`output.flagged_page_images`), as PNGs.
+pub fn page_images(
+    cfg: &Config,
+    renderer: &Renderer,
+    input: &Path,
+    quality: &PageQualityReport,
+) -> Result<Vec<PageImage>> {
+    let mut images = Vec::new();
+    if cfg.output.thumbnails {
+        let pages: Vec<u32> = (1..=quality.page_count).collect();
+        for r in renderer.render(input, &pages, cfg.output.thumbnail_width_px)? {
+            images.push(PageImage {
+                file: format!("thumbnails/page-{:04}.png", r.page),
+                bytes: encode_png(&r.bitmap),
+            });
+        }
+    }
+    let flagged: Vec<u32> = quality
+        .worst
+        .iter()
+        .copied()
+        .take(cfg.output.flagged_page_images as usize)
+        .collect();
+    if !flagged.is_empty() {
+        for r in renderer.render(input, &flagged, cfg.output.flagged_page_width_px)? {
+            images.push(PageImage {
+                file: format!("flagged/page-{:04}.png", r.page),
+                bytes: encode_png(&r.bitmap),
+            });
+        }
+    }
+    Ok(images)
+}
+
+/// The same evenly spread pages `pdf_probe.py` samples.
+pub fn sample_pages(cfg: &Config, page_count: u32) -> Vec<u32> {
+    let c = &cfg.classification;
+    let scaled = if c.sample_pages_percent > 0.0 {
+        (page_count as f32 * c.sample_pages_percent / 100.0).ceil() as u32
+    } else {
+        0
+    };
+    let k = 1
+        .max(c.sample_pages)
+        .max(scaled.min(c.max_sample_pages))
+        .min(page_count);
+    match k {
+        0 => Vec::new(),
+        1 => vec![1],
+        _ => (0..k)
+            .map(|i| {
+                (f64::from(i) * f64::from(page_count - 1) / f64::from(k - 1)).round() as u32 + 1
+            })
+            .collect(),
+    }
+}
+
+/// Fraction of pixels darker than mid-grey once composited over white.
+pub fn ink_coverage(bitmap: &Bitmap) -> f32 {
+    let pixels = bitmap.rgba.chunks_exact(4);
+    let total = pixels.len();
+    if total == 0 {
+        return 0.0;
+    }
+    let ink = pixels
+        .filter(|px| {
+            let luma =
+                0.299 * f32::from(px[0]) + 0.587 * f32::from(px[1]) + 0.114 * f32::from(px[2]);
+            let alpha = f32::from(px[3]) / 255.0;
+            255.0 - alpha * (255.0 - luma) < INK_THRESHOLD
+        })
+        .count();
+    ink as f32 / total as f32
+}
+
+/// An 8-bit RGBA PNG of `bitmap`.
+pub fn encode_png(bitmap: &Bitmap) -> Vec<u8> {
+    let row = bitmap.width as usize * 4;
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    for line in bitmap.rgba.chunks_exact(row.max(1)) {
+        // Filter type 0 (none) per row.
+        let _ = zlib.write_all(&[0]);
+        let _ = zlib.write_all(line);
+    }
+    let data = zlib.finish().unwrap_or_default();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&bitmap.width.to_be_bytes());
+    header.extend_from_slice(&bitmap.height.to_be_bytes());
+    // Bit depth 8, color type 6 (RGBA), default compression, filter and interlace.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &data);
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
@@ -0,0 +1,403 @@
+use crate::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        ConvertIn, ConvertOut, DeviceCheck, DocDiag, Engine, ModelCheck, PageLayout, ProbeOut,
+        SplitChunk,
+    },
+    exit_code::{self, ErrorClass},
+    jobs,
+    pipeline::Pipeline,
+    util::ensure_dir,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Engine requests and responses of a job, one JSON object per line, under `logs/`
+/// (`debug.record_engine_io`).
+pub const RECORDING_FILE: &str = "engine-io.jsonl";
+
+/// Where `quack-check replay` runs a job again, inside the job directory.
+pub const REPLAY_DIR: &str = "replay";
+
+/// Written in place of the PDFs a replay does not have; only ever hashed and stat'ed.
+const STAND_IN_PDF: &[u8] = b"%PDF-1.7\n% quack-check replay stand-in\n";
+
+/// One engine call: the method, its request, and its response or error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// The [`Engine`] method, e.g. "probe_pdf" or "convert_docling".
+    pub call: String,
+    pub request: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_class: Option<ErrorClass>,
+}
+
+/// Which recorded exchange answers a request: conversions by chunk, pages, backend and
+/// OCR, layout by pages, everything else by method alone. Exchanges with the same key
+/// answer in the order they were recorded (retries, fallbacks, a resumed run).
+fn exchange_key(call: &str, request: &serde_json::Value) -> String {
+    let fields: &[&str] = match call {
+        "convert_docling" | "convert_native_text" => &[
+            "chunk_index",
+            "start_page",
+            "end_page",
+            "pdf_backend",
+            "do_ocr",
+        ],
+        "page_layout" => &["pages"],
+        _ => &[],
+    };
+    let mut key = call.to_string();
+    for field in fields {
+        key.push_str(&format!(" {field}={}", request[field]));
+    }
+    key
+}
+
+/// Wraps an engine and appends every call it answers to a [`RECORDING_FILE`], so the
+/// job can be replayed without the PDF or the Python environment.
+pub struct RecordingEngine<E> {
+    inner: E,
+    log: Mutex<std::fs::File>,
+}
+
+impl<E: Engine> RecordingEngine<E> {
+    /// Record to `path`, after whatever earlier runs of the job recorded there.
+    pub fn create(inner: E, path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            ensure_dir(dir)?;
+        }
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        Ok(Self {
+            inner,
+            log: Mutex::new(log),
+        })
+    }
+
+    fn record<T: Serialize>(
+        &self,
+        call: &str,
+        request: serde_json::Value,
+        outcome: Result<T>,
+    ) -> Result<T> {
+        let mut exchange = Exchange {
+            call: call.to_string(),
+            request,
+            response: None,
+            error: None,
+            error_class: None,
+        };
+        match &outcome {
+            Ok(response) => exchange.response = Some(serde_json::to_value(response)?),
+            Err(err) => {
+                exchange.error = Some(format!("{err:#}"));
+                exchange.error_class = Some(exit_code::classify(err));
+            }
+        }
+        let line = serde_json::to_string(&exchange)?;
+        let mut log = self.log.lock().unwrap();
+        writeln!(log, "{line}").context("recording engine I/O")?;
+        outcome
+    }
+}
+
+impl<E: Engine> Engine for RecordingEngine<E> {
+    fn doctor(&self) -> Result<DocDiag> {
+        self.inner.doctor()
+    }
+
+    fn probe_pdf(&self, input: &Path, sample: &Classification) -> Result<ProbeOut> {
+        let request = serde_json::json!({ "sample": sample });
+        self.record("probe_pdf", request, self.inner.probe_pdf(input, sample))
+    }
+
+    fn split_pdf(
+        &self,
+        input: &Path,
+        out_dir: &Path,
+        ranges: &[PageRange],
+    ) -> Result<Vec<SplitChunk>> {
+        let request = serde_json::json!({ "ranges": ranges });
+        self.record(
+            "split_pdf",
+            request,
+            self.inner.split_pdf(input, out_dir, ranges),
+        )
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let request = serde_json::to_value(req)?;
+        self.record("convert_docling", request, self.inner.convert_docling(req))
+    }
+
+    fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let request = serde_json::to_value(req)?;
+        self.record(
+            "convert_native_text",
+            request,
+            self.inner.convert_native_text(req),
+        )
+    }
+
+    fn page_layout(&self, input: &Path, pages: &[u32]) -> Result<Vec<PageLayout>> {
+        let request = serde_json::json!({ "pages": pages });
+        self.record("page_layout", request, self.inner.page_layout(input, pages))
+    }
+
+    fn strip_pdf_features(&self, input: &Path, output: &Path, features: &[String]) -> Result<()> {
+        let request = serde_json::json!({ "features": features });
+        let outcome = self.inner.strip_pdf_features(input, output, features);
+        self.record("strip_pdf_features", request, outcome)
+    }
+
+    fn page_hashes(&self, input: &Path) -> Result<Vec<String>> {
+        let outcome = self.inner.page_hashes(input);
+        self.record("page_hashes", serde_json::json!({}), outcome)
+    }
+
+    fn check_models(
+        &self,
+        do_ocr: bool,
+        pipeline_overrides: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<ModelCheck> {
+        let request = serde_json::json!({
+            "do_ocr": do_ocr,
+            "pipeline_overrides": pipeline_overrides,
+        });
+        let outcome = self.inner.check_models(do_ocr, pipeline_overrides);
+        self.record("check_models", request, outcome)
+    }
+
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        let request = serde_json::json!({ "device": device });
+        self.record("check_device", request, self.inner.check_device(device))
+    }
+
+    fn script_versions(&self) -> BTreeMap<String, String> {
+        let versions = self.inner.script_versions();
+        let _ = self.record(
+            "script_versions",
+            serde_json::json!({}),
+            Ok(versions.clone()),
+        );
+        versions
+    }
+}
+
+/// Answers engine calls from a [`RECORDING_FILE`] instead of running anything. A
+/// request nothing was recorded for fails: the replayed run took a path the recorded
+/// one did not. Clones share what is left of the recording.
+#[derive(Clone)]
+pub struct ReplayEngine {
+    exchanges: Arc<Mutex<HashMap<String, VecDeque<Exchange>>>>,
+    recorded: usize,
+}
+
+impl ReplayEngine {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut exchanges: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+        let mut recorded = 0;
+        for (n, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange: Exchange = serde_json::from_str(line)
+                .with_context(|| format!("{} line {}", path.display(), n + 1))?;
+            exchanges
+                .entry(exchange_key(&exchange.call, &exchange.request))
+                .or_default()
+                .push_back(exchange);
+            recorded += 1;
+        }
+        Ok(Self {
+            exchanges: Arc::new(Mutex::new(exchanges)),
+            recorded,
+        })
+    }
+
+    /// Exchanges recorded, and how many of them the replay has not asked for yet.
+    pub fn usage(&self) -> (usize, usize) {
+        let left = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .values()
+            .map(VecDeque::len)
+            .sum();
+        (self.recorded, left)
+    }
+
+    fn answer<T: DeserializeOwned>(&self, call: &str, request: serde_json::Value) -> Result<T> {
+        let key = exchange_key(call, &request);
+        let exchange = self
+            .exchanges
+            .lock()
+            .unwrap()
+            .get_mut(&key)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| anyhow!("no recorded engine response left for {key}"))?;
+        if let Some(error) = exchange.error {
+            return Err(match exchange.error_class {
+                Some(class) if class != ErrorClass::Other => {
+                    anyhow::Error::new(class).context(error)
+                }
+                _ => anyhow!(error),
+            });
+        }
+        let response = exchange.response.unwrap_or(serde_json::Value::Null);
+        serde_json::from_value(response).with_context(|| format!("recorded response for {key}"))
+    }
+}
+
+impl Engine for ReplayEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        Err(anyhow!("a replayed job has no engine to diagnose"))
+    }
+
+    fn probe_pdf(&self, _input: &Path, sample: &Classification) -> Result<ProbeOut> {
+        self.answer("probe_pdf", serde_json::json!({ "sample": sample }))
+    }
+
+    fn split_pdf(
+        &self,
+        _input: &Path,
+        out_dir: &Path,
+        ranges: &[PageRange],
+    ) -> Result<Vec<SplitChunk>> {
+        let mut chunks: Vec<SplitChunk> =
+            self.answer("split_pdf", serde_json::json!({ "ranges": ranges }))?;
+        ensure_dir(out_dir)?;
+        for chunk in &mut chunks {
+            let name = Path::new(&chunk.path)
+                .file_name()
+                .map(|n| n.to_os_string())
+                .unwrap_or_else(|| format!("chunk_{:05}.pdf", chunk.chunk_index).into());
+            let path = out_dir.join(name);
+            std::fs::write(&path, STAND_IN_PDF)?;
+            chunk.path = path.display().to_string();
+        }
+        Ok(chunks)
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.answer("convert_docling", serde_json::to_value(req)?)
+    }
+
+    fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.answer("convert_native_text", serde_json::to_value(req)?)
+    }
+
+    fn page_layout(&self, _input: &Path, pages: &[u32]) -> Result<Vec<PageLayout>> {
+        self.answer("page_layout", serde_json::json!({ "pages": pages }))
+    }
+
+    fn strip_pdf_features(&self, _input: &Path, output: &Path, features: &[String]) -> Result<()> {
+        self.answer::<()>(
+            "strip_pdf_features",
+            serde_json::json!({ "features": features }),
+        )?;
+        std::fs::write(output, STAND_IN_PDF)?;
+        Ok(())
+    }
+
+    fn page_hashes(&self, _input: &Path) -> Result<Vec<String>> {
+        self.answer("page_hashes", serde_json::json!({}))
+    }
+
+    fn check_models(
+        &self,
+        do_ocr: bool,
+        pipeline_overrides: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<ModelCheck> {
+        let request = serde_json::json!({
+            "do_ocr": do_ocr,
+            "pipeline_overrides": pipeline_overrides,
+        });
+        self.answer("check_models", request)
+    }
+
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        self.answer("check_device", serde_json::json!({ "device": device }))
+    }
+
+    fn script_versions(&self) -> BTreeMap<String, String> {
+        self.answer("script_versions", serde_json::json!({}))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaySummary {
+    pub job_dir: PathBuf,
+    pub replay_dir: PathBuf,
+    pub status: String,
+    pub exchanges_recorded: usize,
+    /// Recorded exchanges the replay never asked for.
+    pub exchanges_unused: usize,
+    /// Whether the replayed transcript is byte for byte the job's; `None` when the job
+    /// has none to compare with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript_matches: Option<bool>,
+}
+
+/// Run the job in `job_dir` again from its [`RECORDING_FILE`], with the config it ran
+/// with, into `job_dir/replay/`. Probing, splitting, and conversion answers come from
+/// the recording and everything after them runs for real, so a pipeline or postprocess
+/// bug reproduces without the PDF or the Python environment. Page rendering (render
+/// probe, thumbnails, flagged pages) needs the PDF and is turned off.
+pub fn replay_job(cfg: &Config, job_dir: &Path) -> Result<ReplaySummary> {
+    let recording = job_dir.join("logs").join(RECORDING_FILE);
+    if !recording.is_file() {
+        return Err(anyhow!(
+            "no recorded engine I/O at {}; run the job with debug.record_engine_io = true",
+            recording.display()
+        ));
+    }
+    let engine = ReplayEngine::load(&recording)?;
+    let mut job_cfg = jobs::job_config(cfg, job_dir);
+    job_cfg.debug.record_engine_io = false;
+    job_cfg.classification.enable_render_probe = false;
+    job_cfg.output.thumbnails = false;
+    job_cfg.output.flagged_page_images = 0;
+
+    let replay_dir = job_dir.join(REPLAY_DIR);
+    if replay_dir.exists() {
+        std::fs::remove_dir_all(&replay_dir)
+            .with_context(|| format!("clearing {}", replay_dir.display()))?;
+    }
+    ensure_dir(&replay_dir.join("final"))?;
+    ensure_dir(&replay_dir.join("logs"))?;
+    let input = replay_dir.join("input.pdf");
+    std::fs::write(&input, STAND_IN_PDF)?;
+
+    let result = Pipeline::new(&job_cfg, engine.clone()).run_job(&input, &replay_dir)?;
+    jobs::write_final_outputs(&job_cfg, &replay_dir, &result)?;
+    let (recorded, unused) = engine.usage();
+
+    let transcript =
+        |dir: &Path| std::fs::read(dir.join("final").join(&job_cfg.output.markdown_filename)).ok();
+    Ok(ReplaySummary {
+        job_dir: job_dir.to_path_buf(),
+        replay_dir: replay_dir.clone(),
+        status: result.report.completeness.status().to_string(),
+        exchanges_recorded: recorded,
+        exchanges_unused: unused,
+        transcript_matches: transcript(job_dir)
+            .map(|original| Some(original) == transcript(&replay_dir)),
+    })
+}
@@ -1,15 +1,59 @@
 use crate::{
+    accelerator::AcceleratorUse,
+    attempts::Attempt,
+    blank_pages::BlankPageStats,
+    duplicate_pages::DuplicatePageStats,
+    engine::{ConvertMeta, PageError},
+    exclusions::PageExclusion,
+    lint::LintReport,
+    page_quality::HybridOcrStats,
+    pagenum::PageNumberStats,
     policy::PolicyDecision,
-    probe::{ProbeInput, ProbeSampleStats},
+    postprocess::EmbeddedImageStats,
+    probe::{ProbeInput, ProbeSampleStats, ProbeStructure},
+    render::RenderProbeStats,
+    tokens::TokenStats,
+    xref::XrefStats,
 };
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobReport {
+    /// Whether every page was converted; first so partial transcripts are obvious.
+    #[serde(default)]
+    pub completeness: Completeness,
+    /// "postprocess" for a `postprocess` run on text from another tool; its probe and
+    /// decision are stand-ins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
     pub input: ProbeInput,
     pub sample: ProbeSampleStats,
+    pub structure: ProbeStructure,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render: Option<RenderProbeStats>,
     pub decision: PolicyDecision,
     pub chunk_reports: Vec<ChunkReport>,
+    pub embedded_images: EmbeddedImageStats,
+    #[serde(default)]
+    pub xref: XrefStats,
+    #[serde(default)]
+    pub page_numbers: PageNumberStats,
+    #[serde(default)]
+    pub blank_pages: BlankPageStats,
+    #[serde(default)]
+    pub duplicate_pages: DuplicatePageStats,
+    /// Pages left out by `input.exclusions`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_pages: Vec<PageExclusion>,
+    /// The accelerator chunks were converted on, when a specific device was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accelerator: Option<AcceleratorUse>,
+    /// Per-page OCR choices under `docling.ocr.hybrid`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hybrid_ocr: Option<HybridOcrStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lint: Option<LintReport>,
+    pub tokens: TokenStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +62,83 @@ pub struct ChunkReport {
     pub start_page: u32,
     pub end_page: u32,
     pub ok: bool,
+    /// The docling PDF backend that produced this chunk, after any fallbacks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_backend: Option<String>,
+    pub tokens: usize,
+    pub page_errors: Vec<PageError>,
+    /// Job-relative path of the chunk's Python stderr (`debug.keep_python_stderr`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_log: Option<String>,
+    /// "table" or "prose" (`docling.table_chunks`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Dominant script of the chunk's text (`script::detect`), e.g. "latin" or "arabic".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    /// "ltr" or "rtl", following `script`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<String>,
     pub warnings: Vec<String>,
-    pub meta: serde_json::Value,
+    #[serde(default)]
+    pub meta: ConvertMeta,
+    /// Why the chunk was not converted (`skipped_due_to_budget`, `conversion_failed`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    /// Failed tries at the chunk: engine, backend, error class, and stderr excerpt each.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<Attempt>,
+}
+
+/// Rewritten in `final/` after every chunk while a job runs, and renamed to
+/// `output.report_filename` when the job finishes.
+pub const PARTIAL_REPORT_FILE: &str = "report.partial.json";
+
+/// Marks chunks the soft time budget left unconverted.
+pub const SKIPPED_DUE_TO_BUDGET: &str = "skipped_due_to_budget";
+
+/// Marks chunks that failed and were replaced by placeholders.
+pub const CONVERSION_FAILED: &str = "conversion_failed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Completeness {
+    pub complete: bool,
+    pub converted_pages: u32,
+    pub total_pages: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_chunks: Vec<u32>,
+    /// Chunks whose conversion failed and that hold placeholders instead
+    /// (`limits.allow_failed_chunk_fraction`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_chunks: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Completeness {
+    /// "ok", "degraded" when chunks failed and hold placeholders, or "partial" when
+    /// the time budget skipped chunks.
+    pub fn status(&self) -> &'static str {
+        if !self.failed_chunks.is_empty() {
+            "degraded"
+        } else if !self.complete {
+            "partial"
+        } else {
+            "ok"
+        }
+    }
+}
+
+/// Reports written before completeness was tracked were all complete.
+impl Default for Completeness {
+    fn default() -> Self {
+        Self {
+            complete: true,
+            converted_pages: 0,
+            total_pages: 0,
+            skipped_chunks: Vec::new(),
+            failed_chunks: Vec::new(),
+            reason: None,
+        }
+    }
 }
@@ -0,0 +1,216 @@
+use crate::{config::Config, jobs};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Artifact classes, in the order of the `[retention]` keys.
+pub const CLASSES: [&str; 4] = ["split_pdfs", "chunk_json", "stderr_logs", "page_images"];
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "ppm", "tif", "tiff"];
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Keep,
+    Delete,
+    /// Keep only for chunks that did not convert cleanly.
+    Failed,
+    /// Keep for this many days after the file was written.
+    Days(u64),
+}
+
+impl Rule {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "keep" => Some(Rule::Keep),
+            "delete" => Some(Rule::Delete),
+            "failed" => Some(Rule::Failed),
+            _ => s.strip_suffix('d')?.parse().ok().map(Rule::Days),
+        }
+    }
+}
+
+/// The configured rule for one class. The deprecated `chunking.keep_split_pdfs = false`
+/// reads as `split_pdfs = "delete"` while `split_pdfs` is left at "keep".
+pub fn rule(cfg: &Config, class: &str) -> Result<Rule> {
+    let r = &cfg.retention;
+    let value = match class {
+        "split_pdfs" => &r.split_pdfs,
+        "chunk_json" => &r.chunk_json,
+        "stderr_logs" => &r.stderr_logs,
+        "page_images" => &r.page_images,
+        _ => return Err(anyhow!("unknown artifact class: {class}")),
+    };
+    let rule = Rule::parse(value).ok_or_else(|| {
+        anyhow!("unknown retention.{class}: {value} (expected keep, delete, failed, or <N>d)")
+    })?;
+    if class == "split_pdfs" && rule == Rule::Keep && !cfg.chunking.keep_split_pdfs {
+        return Ok(Rule::Delete);
+    }
+    Ok(rule)
+}
+
+pub fn validate(cfg: &Config) -> Result<()> {
+    for class in CLASSES {
+        rule(cfg, class)?;
+    }
+    Ok(())
+}
+
+/// One file in a job directory covered by a retention rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    pub class: &'static str,
+    pub path: PathBuf,
+    /// `None` for job-level artifacts such as `input.stripped.pdf`.
+    pub chunk_index: Option<u32>,
+    pub bytes: u64,
+}
+
+/// Every intermediate artifact in `job_dir`, sorted by path.
+pub fn artifacts(job_dir: &Path) -> Result<Vec<Artifact>> {
+    let mut found = Vec::new();
+    let stripped = job_dir.join("input.stripped.pdf");
+    if stripped.is_file() {
+        found.push(artifact("split_pdfs", stripped, None));
+    }
+    let logs_dir = job_dir.join("logs");
+    for path in files_under(&logs_dir)? {
+        let name = file_name(&path);
+        if name.ends_with(".stderr.log") {
+            let index = chunk_index(name);
+            found.push(artifact("stderr_logs", path, index));
+        }
+    }
+    let chunks_dir = job_dir.join("chunks");
+    for path in files_under(&chunks_dir)? {
+        let name = file_name(&path);
+        let index = chunk_index(name);
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        let top_level = path.parent() == Some(chunks_dir.as_path());
+        let class = match ext.as_str() {
+            "pdf" if top_level && index.is_some() => "split_pdfs",
+            "json" if top_level && index.is_some() => "chunk_json",
+            e if IMAGE_EXTENSIONS.contains(&e) => "page_images",
+            _ => continue,
+        };
+        found.push(artifact(class, path, index));
+    }
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(found)
+}
+
+/// The artifacts in `job_dir` the configured rules say to delete at `now`. A chunk
+/// counts as failed while it has no record or its record lists page errors; job-level
+/// artifacts count as failed while any planned chunk does.
+pub fn expired(cfg: &Config, job_dir: &Path, now: SystemTime) -> Result<Vec<Artifact>> {
+    let rules = CLASSES
+        .into_iter()
+        .map(|class| Ok((class, rule(cfg, class)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut outcome: Option<ChunkOutcome> = None;
+    let mut out = Vec::new();
+    for artifact in artifacts(job_dir)? {
+        let rule = rules
+            .iter()
+            .find(|(class, _)| *class == artifact.class)
+            .map_or(Rule::Keep, |(_, rule)| *rule);
+        let delete = match rule {
+            Rule::Keep => false,
+            Rule::Delete => true,
+            Rule::Failed => {
+                let outcome = outcome.get_or_insert_with(|| ChunkOutcome::load(job_dir));
+                !outcome.failed(artifact.chunk_index)
+            }
+            Rule::Days(days) => std::fs::metadata(&artifact.path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|written| now.duration_since(written).ok())
+                .is_some_and(|age| age.as_secs() >= days.saturating_mul(DAY_SECS)),
+        };
+        if delete {
+            out.push(artifact);
+        }
+    }
+    Ok(out)
+}
+
+/// Which chunks of a job converted cleanly, from its records on disk.
+struct ChunkOutcome {
+    clean: BTreeSet<u32>,
+    planned: Option<u32>,
+}
+
+impl ChunkOutcome {
+    fn load(job_dir: &Path) -> Self {
+        let clean = jobs::load_records(job_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| r.page_errors.is_empty())
+            .map(|r| r.chunk_index)
+            .collect();
+        let planned = jobs::read_manifest(job_dir).map(|m| m.plan.chunks.len() as u32);
+        Self { clean, planned }
+    }
+
+    fn failed(&self, chunk_index: Option<u32>) -> bool {
+        match (chunk_index, self.planned) {
+            (Some(i), _) => !self.clean.contains(&i),
+            (None, Some(planned)) => (0..planned).any(|i| !self.clean.contains(&i)),
+            (None, None) => true,
+        }
+    }
+}
+
+fn artifact(class: &'static str, path: PathBuf, chunk_index: Option<u32>) -> Artifact {
+    let bytes = std::fs::metadata(&path).map_or(0, |m| m.len());
+    Artifact {
+        class,
+        path,
+        chunk_index,
+        bytes,
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().and_then(|n| n.to_str()).unwrap_or("")
+}
+
+/// `chunk_00012_p00441-p00480.pdf` and `chunk_00012.json` both belong to chunk 12.
+fn chunk_index(name: &str) -> Option<u32> {
+    let digits: String = name
+        .strip_prefix("chunk_")?
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+fn files_under(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,96 @@
+use crate::{
+    chunk_plan::PageRange,
+    jobs,
+    pipeline::{ChunkRecord, JobManifest},
+    policy::PolicyDecision,
+};
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The converted chunks of an earlier job, for `run --reuse-from`.
+#[derive(Debug, Clone)]
+pub struct ReuseSource {
+    pub job_id: String,
+    pub manifest: JobManifest,
+    pub records: Vec<ChunkRecord>,
+}
+
+impl ReuseSource {
+    /// Load an earlier job. It must have recorded page hashes
+    /// (`global.record_page_hashes`).
+    pub fn load(job_dir: &Path) -> Result<Self> {
+        let job_id = job_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let manifest = jobs::read_manifest(job_dir)
+            .ok_or_else(|| anyhow!("job {job_id} has no readable job.json"))?;
+        if manifest.page_hashes.is_empty() {
+            return Err(anyhow!(
+                "job {job_id} recorded no page hashes (global.record_page_hashes was off or it predates them)"
+            ));
+        }
+        let records = jobs::load_records(job_dir)?;
+        Ok(Self {
+            job_id,
+            manifest,
+            records,
+        })
+    }
+
+    /// Earlier chunk records to use in place of converting the new job's chunks, by
+    /// chunk position. A chunk is reused when the earlier job converted exactly the
+    /// same page range without page errors and every page in it hashes the same in
+    /// both files. Nothing is reused when the policy decided differently, since the
+    /// chunks would have been converted differently.
+    pub fn reusable(
+        &self,
+        decision: &PolicyDecision,
+        page_hashes: &[String],
+        chunks: &[PageRange],
+    ) -> BTreeMap<usize, ChunkRecord> {
+        let old = &self.manifest.decision;
+        if old.tier != decision.tier
+            || old.chosen_engine != decision.chosen_engine
+            || old.do_ocr != decision.do_ocr
+            || old.pipeline_overrides != decision.pipeline_overrides
+        {
+            tracing::warn!(
+                "reuse: job {} was converted as {:?}/{}, this input as {:?}/{}; converting every chunk",
+                self.job_id,
+                old.tier,
+                old.chosen_engine,
+                decision.tier,
+                decision.chosen_engine
+            );
+            return BTreeMap::new();
+        }
+        let old_hashes = &self.manifest.page_hashes;
+        let mut reused = BTreeMap::new();
+        for (i, range) in chunks.iter().enumerate() {
+            let pages = (range.start_page as usize - 1)..(range.end_page as usize);
+            let same_pages = matches!(
+                (old_hashes.get(pages.clone()), page_hashes.get(pages)),
+                (Some(a), Some(b)) if a == b
+            );
+            if !same_pages {
+                continue;
+            }
+            let Some(record) = self.records.iter().find(|r| {
+                r.start_page == range.start_page
+                    && r.end_page == range.end_page
+                    && r.out.ok
+                    && r.page_errors.is_empty()
+            }) else {
+                continue;
+            };
+            let mut record = record.clone();
+            record.chunk_index = i as u32;
+            record.stderr_log = None;
+            record.out.meta.reused_from = Some(self.job_id.clone());
+            reused.insert(i, record);
+        }
+        reused
+    }
+}
@@ -0,0 +1,131 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Right-to-left mark, put in front of RTL lines by `postprocess.rtl_line_marks`.
+pub const RLM: char = '\u{200F}';
+
+/// The writing system a character belongs to, for the scripts the pipeline treats
+/// differently. Digits, punctuation and symbols have none.
+pub fn script_of(c: char) -> Option<&'static str> {
+    Some(match c as u32 {
+        0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => "latin",
+        0x370..=0x3FF | 0x1F00..=0x1FFF => "greek",
+        0x400..=0x52F => "cyrillic",
+        0x590..=0x5FF | 0xFB1D..=0xFB4F => "hebrew",
+        0x600..=0x6FF | 0x750..=0x77F | 0x8A0..=0x8FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => {
+            "arabic"
+        }
+        0x900..=0x97F => "devanagari",
+        0xE00..=0xE7F => "thai",
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => "kana",
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F => "han",
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => "hangul",
+        _ => return None,
+    })
+}
+
+pub fn is_rtl(c: char) -> bool {
+    matches!(script_of(c), Some("hebrew" | "arabic"))
+}
+
+/// Han, kana, and CJK/fullwidth punctuation: characters written without spaces between
+/// them. Hangul is not included; Korean separates words with spaces.
+pub fn is_cjk(c: char) -> bool {
+    matches!(script_of(c), Some("han" | "kana"))
+        || matches!(c as u32, 0x3000..=0x303F | 0xFF01..=0xFF60)
+}
+
+/// The script most letters of a chunk are written in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptInfo {
+    /// latin | greek | cyrillic | hebrew | arabic | devanagari | thai | han | kana | hangul
+    pub script: String,
+    /// "rtl" for hebrew and arabic, "ltr" otherwise.
+    pub direction: String,
+    /// Share of the text's letters in `script`.
+    pub share: f32,
+}
+
+/// The dominant script of `text`; `None` when it has no letters. Japanese text is
+/// reported as "kana" when kana outnumber Han characters, "han" otherwise.
+pub fn detect(text: &str) -> Option<ScriptInfo> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for script in text.chars().filter_map(script_of) {
+        *counts.entry(script).or_default() += 1;
+    }
+    let total: usize = counts.values().sum();
+    let (script, n) = counts.into_iter().max_by_key(|(_, n)| *n)?;
+    Some(ScriptInfo {
+        script: script.to_string(),
+        direction: if matches!(script, "hebrew" | "arabic") {
+            "rtl"
+        } else {
+            "ltr"
+        }
+        .to_string(),
+        share: n as f32 / total as f32,
+    })
+}
+
+/// Drop spaces and tabs between two CJK characters, which OCR and text extraction
+/// insert between glyphs. Spaces next to Latin text or digits are kept.
+pub fn join_cjk_spaces(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if matches!(c, ' ' | '\t') {
+            let end = chars[i..]
+                .iter()
+                .position(|c| !matches!(c, ' ' | '\t'))
+                .map_or(chars.len(), |n| i + n);
+            let between_cjk =
+                i > 0 && is_cjk(chars[i - 1]) && chars.get(end).copied().is_some_and(is_cjk);
+            if !between_cjk {
+                out.extend(&chars[i..end]);
+            }
+            i = end;
+            continue;
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// Put a right-to-left mark at the start of each line whose letters are mostly RTL, so
+/// viewers lay out lines that start with a number or a Latin word right to left. The
+/// mark goes after any heading, list, or quote prefix; code fences, tables, and
+/// `<!-- -->` markers are left alone, as are lines that already start with a mark.
+pub fn mark_rtl_lines(md: &str) -> String {
+    let prefix_re = Regex::new(r"^\s*(?:(?:#{1,6}|[-*+]|\d+[.)])\s+|>\s*)*").expect("prefix regex");
+    let mut in_fence = false;
+    let mut out: Vec<String> = Vec::new();
+    for line in md.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let rtl = line.chars().filter(|c| is_rtl(*c)).count();
+        let letters = line.chars().filter(|c| script_of(*c).is_some()).count();
+        let prefix = prefix_re.find(line).map_or(0, |m| m.end());
+        let skip = in_fence
+            || trimmed.starts_with('|')
+            || trimmed.starts_with("<!--")
+            || rtl == 0
+            || rtl * 2 < letters
+            || line[prefix..].starts_with(RLM);
+        if skip {
+            out.push(line.to_string());
+        } else {
+            out.push(format!("{}{RLM}{}", &line[..prefix], &line[prefix..]));
+        }
+    }
+    let mut joined = out.join("\n");
+    if md.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
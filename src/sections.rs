@@ -0,0 +1,170 @@
+use crate::{postprocess, tokens::TokenCounter};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// How `output.split_final_by` cuts the final transcript into sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitSpec {
+    HeadingLevel1,
+    Pages(u32),
+    Tokens(usize),
+}
+
+impl SplitSpec {
+    /// Parse `"heading_level_1" | "pages:N" | "tokens:N"`; an empty string disables splitting.
+    pub fn parse(raw: &str) -> Result<Option<Self>> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+        if raw == "heading_level_1" {
+            return Ok(Some(Self::HeadingLevel1));
+        }
+        let parse_n = |n: &str| -> Result<u64> {
+            match n.trim().parse::<u64>() {
+                Ok(v) if v > 0 => Ok(v),
+                _ => Err(anyhow!("invalid output.split_final_by: {raw}")),
+            }
+        };
+        if let Some(n) = raw.strip_prefix("pages:") {
+            return Ok(Some(Self::Pages(parse_n(n)? as u32)));
+        }
+        if let Some(n) = raw.strip_prefix("tokens:") {
+            return Ok(Some(Self::Tokens(parse_n(n)? as usize)));
+        }
+        Err(anyhow!("invalid output.split_final_by: {raw}"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Section {
+    pub index: u32,
+    pub title: String,
+    pub file: String,
+    pub start_page: u32,
+    pub end_page: u32,
+    pub chars: usize,
+    pub tokens: usize,
+    #[serde(skip)]
+    pub markdown: String,
+}
+
+/// Split page-marked markdown into sections. Page ranges come from the
+/// `<!-- quack:page N -->` markers, so they are only as precise as the engine's page
+/// breaks (at worst, chunk granularity).
+pub fn split_sections(
+    spec: &SplitSpec,
+    marked_md: &str,
+    keep_page_markers: bool,
+    counter: &TokenCounter,
+) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut current = Builder::default();
+    let mut page = 1u32;
+
+    for line in marked_md.lines() {
+        if let Some(p) = postprocess::parse_page_marker(line) {
+            page = p;
+            if let SplitSpec::Pages(n) = spec
+                && current.has_content()
+                && page >= current.start_page + n
+            {
+                sections.push(current.finish(sections.len(), keep_page_markers, counter));
+                current = Builder::default();
+            }
+            current.lines.push(line.to_string());
+            continue;
+        }
+
+        let starts_section = match spec {
+            SplitSpec::HeadingLevel1 => line.starts_with("# "),
+            SplitSpec::Tokens(n) => line.trim().is_empty() && current.tokens >= *n,
+            SplitSpec::Pages(_) => false,
+        };
+        if starts_section && current.has_content() {
+            sections.push(current.finish(sections.len(), keep_page_markers, counter));
+            current = Builder::default();
+            // Carry the page context into the new section.
+            current.lines.push(postprocess::page_marker(page));
+        }
+
+        current.push_content(line, page, counter);
+    }
+
+    if current.has_content() {
+        sections.push(current.finish(sections.len(), keep_page_markers, counter));
+    }
+    sections
+}
+
+#[derive(Default)]
+struct Builder {
+    lines: Vec<String>,
+    title: Option<String>,
+    start_page: u32,
+    end_page: u32,
+    tokens: usize,
+}
+
+impl Builder {
+    fn has_content(&self) -> bool {
+        self.start_page > 0
+    }
+
+    fn push_content(&mut self, line: &str, page: u32, counter: &TokenCounter) {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            if self.start_page == 0 {
+                self.start_page = page;
+            }
+            self.end_page = page;
+            if self.title.is_none() && trimmed.starts_with('#') {
+                self.title = Some(trimmed.trim_start_matches('#').trim().to_string());
+            }
+            self.tokens += counter.count(line);
+        }
+        self.lines.push(line.to_string());
+    }
+
+    fn finish(self, idx: usize, keep_page_markers: bool, counter: &TokenCounter) -> Section {
+        let mut markdown = self.lines.join("\n");
+        if !keep_page_markers {
+            markdown = postprocess::strip_page_markers(&markdown);
+        }
+        let markdown = markdown.trim_matches('\n').to_string();
+        let title = self
+            .title
+            .unwrap_or_else(|| format!("pages {}-{}", self.start_page, self.end_page));
+        let index = idx as u32 + 1;
+        Section {
+            index,
+            file: format!("{:03}-{}.md", index, slugify(&title)),
+            title,
+            start_page: self.start_page,
+            end_page: self.end_page,
+            chars: markdown.chars().count(),
+            tokens: counter.count(&markdown),
+            markdown,
+        }
+    }
+}
+
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for ch in title.chars().flat_map(|c| c.to_lowercase()) {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.chars().count() >= 48 {
+            break;
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    chunk_plan::ChunkPlan,
+    config::Config,
+    engine::{ConvertIn, ConvertOut, Engine},
+    pipeline::Pipeline,
+    policy, postprocess, probe,
+    util::ensure_dir,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Text on every fixture page; each conversion stage must get it back.
+pub const FIXTURE_PHRASE: &str = "The quick brown fox jumps over the lazy dog";
+pub const FIXTURE_PAGES: u32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageResult {
+    pub stage: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub fixture: PathBuf,
+    pub stages: Vec<StageResult>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.stages.iter().all(|s| s.ok)
+    }
+
+    fn record(&mut self, stage: &str, result: Result<String>) {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(err) => (false, format!("{err:#}")),
+        };
+        self.stages.push(StageResult {
+            stage: stage.to_string(),
+            ok,
+            detail,
+        });
+    }
+}
+
+/// A minimal two-page PDF with a Helvetica text layer, built byte by byte so the
+/// binary needs no bundled assets.
+pub fn fixture_pdf() -> Vec<u8> {
+    let page_stream = |n: u32| {
+        format!(
+            "BT /F1 20 Tf 72 720 Td (Quack Check Self Test Page {n}) Tj ET\n\
+             BT /F1 12 Tf 72 690 Td ({FIXTURE_PHRASE}.) Tj ET\n\
+             BT /F1 12 Tf 72 672 Td (This page exists to verify the installation.) Tj ET\n"
+        )
+    };
+    let objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".into(),
+        "<< /Type /Pages /Kids [3 0 R 5 0 R] /Count 2 >>".into(),
+        page_object(4),
+        stream_object(&page_stream(1)),
+        page_object(6),
+        stream_object(&page_stream(2)),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".into(),
+    ];
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{body}\nendobj\n", i + 1).as_bytes());
+    }
+    let xref_at = pdf.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for off in offsets {
+        xref.push_str(&format!("{off:010} 00000 n \n"));
+    }
+    xref.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_at}\n%%EOF\n",
+        objects.len() + 1
+    ));
+    pdf.extend_from_slice(xref.as_bytes());
+    pdf
+}
+
+fn page_object(contents: u32) -> String {
+    format!(
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+         /Resources << /Font << /F1 7 0 R >> >> /Contents {contents} 0 R >>"
+    )
+}
+
+fn stream_object(content: &str) -> String {
+    format!(
+        "<< /Length {} >>\nstream\n{content}endstream",
+        content.len()
+    )
+}
+
+/// Write the fixture into `work_dir` and run every stage against `engine`: doctor,
+/// probe, plan, a direct conversion with each engine the config routes to, and the
+/// full pipeline. Stages keep running after a failure so the report shows all of them.
+pub fn run<E: Engine>(cfg: &Config, engine: E, work_dir: &Path) -> Result<SelfTestReport> {
+    ensure_dir(work_dir)?;
+    let fixture = work_dir.join("self-test.pdf");
+    std::fs::write(&fixture, fixture_pdf())?;
+    let mut report = SelfTestReport {
+        fixture: fixture.clone(),
+        stages: Vec::new(),
+    };
+
+    report.record(
+        "doctor",
+        engine.doctor().and_then(|diag| match diag.ok {
+            true => Ok(format!(
+                "python {} docling {}",
+                diag.python_version,
+                diag.docling_version.as_deref().unwrap_or("missing")
+            )),
+            false => Err(anyhow!(diag
+                .error
+                .unwrap_or_else(|| "doctor not ok".into()))),
+        }),
+    );
+
+    let probed = probe::probe_pdf(cfg, &engine, &fixture);
+    report.record(
+        "probe",
+        probed.as_ref().map_err(|e| anyhow!("{e:#}")).and_then(|p| {
+            if p.input.page_count != FIXTURE_PAGES {
+                return Err(anyhow!(
+                    "expected {FIXTURE_PAGES} pages, probe saw {}",
+                    p.input.page_count
+                ));
+            }
+            let decision = policy::decide(cfg, p);
+            Ok(format!(
+                "{} pages, avg_chars={}, tier={:?}",
+                p.input.page_count, p.sample.avg_chars_per_page, decision.tier
+            ))
+        }),
+    );
+    if let Ok(p) = &probed {
+        report.record(
+            "plan",
+            ChunkPlan::from_probe(cfg, p).map(|plan| format!("{} chunk(s)", plan.chunks.len())),
+        );
+    }
+
+    let mut engines = vec![
+        cfg.engine.high_text_engine.as_str(),
+        cfg.engine.mixed_text_engine.as_str(),
+        cfg.engine.scan_engine.as_str(),
+    ];
+    engines.sort();
+    engines.dedup();
+    for name in engines {
+        let out_dir = work_dir.join(format!("convert-{name}"));
+        let result = ensure_dir(&out_dir)
+            .and_then(|_| convert_with(cfg, &engine, name, &fixture, &out_dir))
+            .and_then(|out| check_markdown(&out.markdown));
+        report.record(&format!("convert:{name}"), result);
+    }
+
+    let job_dir = work_dir.join("job");
+    let pipeline = Pipeline::new(cfg, engine);
+    report.record(
+        "pipeline",
+        pipeline
+            .run_job(&fixture, &job_dir)
+            .and_then(|out| check_markdown(&out.markdown)),
+    );
+
+    Ok(report)
+}
+
+fn convert_with<E: Engine>(
+    cfg: &Config,
+    engine: &E,
+    name: &str,
+    fixture: &Path,
+    out_dir: &Path,
+) -> Result<ConvertOut> {
+    let req = ConvertIn {
+        input_pdf: fixture.display().to_string(),
+        out_dir: out_dir.display().to_string(),
+        chunk_index: 0,
+        start_page: 1,
+        end_page: FIXTURE_PAGES,
+        do_ocr: false,
+        pdf_backend: cfg
+            .docling
+            .backend
+            .pdf_backend
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "AUTO".into()),
+        require_pdf_backend: false,
+        use_page_range: false,
+        page_break_placeholder: postprocess::PAGE_BREAK_PLACEHOLDER.to_string(),
+        split_spreads: false,
+        tiles: Vec::new(),
+        on_page_error: "fail".into(),
+        pipeline_overrides: Default::default(),
+        stderr_log: None,
+        control: Default::default(),
+        attempts: Default::default(),
+    };
+    let out = match name {
+        "docling" => engine.convert_docling(&req)?,
+        "native_text" => engine.convert_native_text(&req)?,
+        other => return Err(anyhow!("unknown engine: {other}")),
+    };
+    if !out.ok {
+        return Err(anyhow!("conversion not ok; warnings={:?}", out.warnings));
+    }
+    Ok(out)
+}
+
+fn check_markdown(markdown: &str) -> Result<String> {
+    let found = markdown.matches(FIXTURE_PHRASE).count();
+    if found == 0 {
+        return Err(anyhow!(
+            "fixture text missing from output ({} chars)",
+            markdown.len()
+        ));
+    }
+    Ok(format!(
+        "{} chars, fixture text found on {found} page(s)",
+        markdown.len()
+    ))
+}
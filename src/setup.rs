@@ -0,0 +1,149 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// One command `doctor --fix` runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SetupStep {
+    pub description: String,
+    pub argv: Vec<String>,
+}
+
+/// What `doctor --fix` will do, before anything runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupPlan {
+    pub venv_dir: PathBuf,
+    /// The venv's interpreter; written to `docling.python_exe` once setup succeeds.
+    pub python_exe: PathBuf,
+    pub steps: Vec<SetupStep>,
+}
+
+/// The venv's interpreter path on this platform.
+pub fn venv_python(venv_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv_dir.join("Scripts").join("python.exe")
+    } else {
+        venv_dir.join("bin").join("python")
+    }
+}
+
+/// Plan the venv creation and pinned installs from `[docling.setup]`. The venv is only
+/// created when its interpreter is missing; installing again is a no-op for pip when
+/// the pins are already satisfied. With `global.offline_only`, pip may only read
+/// `wheel_dir`.
+pub fn plan(cfg: &Config) -> Result<SetupPlan> {
+    let setup = &cfg.docling.setup;
+    if setup.venv_dir.trim().is_empty() {
+        return Err(anyhow!("docling.setup.venv_dir is empty"));
+    }
+    if setup.packages.is_empty() {
+        return Err(anyhow!("docling.setup.packages is empty"));
+    }
+    let venv_dir = std::path::absolute(&setup.venv_dir)
+        .with_context(|| format!("resolving {}", setup.venv_dir))?;
+    let python_exe = venv_python(&venv_dir);
+    let python = python_exe.display().to_string();
+
+    let mut steps = Vec::new();
+    if !python_exe.exists() {
+        steps.push(SetupStep {
+            description: format!("create venv at {}", venv_dir.display()),
+            argv: vec![
+                setup.base_python.clone(),
+                "-m".into(),
+                "venv".into(),
+                venv_dir.display().to_string(),
+            ],
+        });
+    }
+
+    let mut install: Vec<String> = vec![python, "-m".into(), "pip".into(), "install".into()];
+    if cfg.global.offline_only {
+        if setup.wheel_dir.trim().is_empty() {
+            return Err(anyhow!(
+                "global.offline_only is set, so packages must come from a local wheel directory; set docling.setup.wheel_dir (fill it with `pip download -d <dir> {}`)",
+                setup.packages.join(" ")
+            ));
+        }
+        let wheel_dir = Path::new(&setup.wheel_dir);
+        if !wheel_dir.is_dir() {
+            return Err(anyhow!(
+                "docling.setup.wheel_dir is not a directory: {}",
+                wheel_dir.display()
+            ));
+        }
+        install.extend([
+            "--no-index".into(),
+            "--find-links".into(),
+            wheel_dir.display().to_string(),
+        ]);
+    }
+    install.extend(setup.packages.iter().cloned());
+    steps.push(SetupStep {
+        description: format!("install {}", setup.packages.join(" ")),
+        argv: install,
+    });
+
+    Ok(SetupPlan {
+        venv_dir,
+        python_exe,
+        steps,
+    })
+}
+
+/// Run each step in order, stopping at the first failure.
+pub fn run(plan: &SetupPlan, mut progress: impl FnMut(&SetupStep)) -> Result<()> {
+    for step in &plan.steps {
+        progress(step);
+        let (program, args) = step
+            .argv
+            .split_first()
+            .ok_or_else(|| anyhow!("empty setup command"))?;
+        let status = Command::new(program)
+            .args(args)
+            .status()
+            .with_context(|| format!("running {program}"))?;
+        if !status.success() {
+            return Err(anyhow!("{} failed ({status})", step.description));
+        }
+    }
+    Ok(())
+}
+
+/// `raw` with `python_exe` in the `[docling]` table set to `python_exe`. Everything
+/// else, comments included, is left as written; the key is added under the table
+/// header when missing.
+pub fn set_python_exe(raw: &str, python_exe: &Path) -> Result<String> {
+    let value = toml::Value::String(python_exe.display().to_string()).to_string();
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    let header = lines
+        .iter()
+        .position(|l| l.trim() == "[docling]")
+        .ok_or_else(|| anyhow!("the config has no [docling] table"))?;
+    let end = lines[header + 1..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| header + 1 + i);
+    let existing = (header + 1..end).find(|&i| {
+        lines[i]
+            .split_once('=')
+            .is_some_and(|(key, _)| key.trim() == "python_exe")
+    });
+    let line = format!("python_exe = {value}");
+    match existing {
+        Some(i) => lines[i] = line,
+        None => lines.insert(header + 1, line),
+    }
+    let mut out = lines.join("\n");
+    if raw.ends_with('\n') {
+        out.push('\n');
+    }
+
+    let parsed: Config = toml::from_str(&out).context("the updated config does not parse")?;
+    if Path::new(&parsed.docling.python_exe) != python_exe {
+        return Err(anyhow!("could not set docling.python_exe in the config"));
+    }
+    Ok(out)
+}
@@ -0,0 +1,108 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Appended to an input's file name for its sidecar: `book.pdf.quack.toml`.
+pub const SIDECAR_SUFFIX: &str = ".quack.toml";
+
+/// Sections and keys a sidecar may set. Everything else (paths, security, logging,
+/// batch, ...) belongs to whoever runs quack-check, not to a file that travels with a PDF.
+const ALLOWED: &[&str] = &[
+    "global.job_name",
+    "global.priority",
+    "limits",
+    "input",
+    "classification",
+    "chunking",
+    "engine",
+    "native_text",
+    "docling",
+    "postprocess",
+    "output",
+];
+
+/// Keys inside `ALLOWED` sections a sidecar may not set: interpreters, environment and
+/// remote endpoints would let a file next to a PDF run other code or send it elsewhere.
+const DENIED: &[&str] = &[
+    "input.sidecar",
+    "engine.remote",
+    "native_text.python_exe",
+    "docling.python_exe",
+    "docling.env",
+    "docling.setup",
+    "output.mirrors",
+];
+
+pub fn sidecar_path(input: &Path) -> PathBuf {
+    let mut name = input.as_os_str().to_owned();
+    name.push(SIDECAR_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// `cfg` overlaid with the input's sidecar file, when `input.sidecar` is on and the file
+/// exists. Tables merge key by key; any other value, arrays included, replaces the
+/// config's.
+pub fn overlay(cfg: &Config, input: &Path) -> Result<Config> {
+    let path = sidecar_path(input);
+    if !cfg.input.sidecar || !path.is_file() {
+        return Ok(cfg.clone());
+    }
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading sidecar: {}", path.display()))?;
+    let sidecar: toml::Table =
+        toml::from_str(&raw).with_context(|| format!("parsing sidecar: {}", path.display()))?;
+    let mut keys = Vec::new();
+    leaf_keys("", &sidecar, &mut keys);
+    let covers = |prefix: &str, key: &str| {
+        key == prefix
+            || key
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('.'))
+    };
+    for key in &keys {
+        let allowed = ALLOWED.iter().any(|a| covers(a, key));
+        if !allowed || DENIED.iter().any(|d| covers(d, key)) {
+            return Err(anyhow!(
+                "{} cannot be set in a sidecar: {}",
+                key,
+                path.display()
+            ));
+        }
+    }
+
+    let mut table = toml::Table::try_from(cfg).context("serializing config")?;
+    merge(&mut table, sidecar);
+    let cfg: Config = table
+        .try_into()
+        .with_context(|| format!("applying sidecar: {}", path.display()))?;
+    crate::template::check_filenames(&cfg, false)
+        .with_context(|| format!("applying sidecar: {}", path.display()))?;
+    info!("applied sidecar {}: {}", path.display(), keys.join(", "));
+    Ok(cfg)
+}
+
+fn leaf_keys(prefix: &str, table: &toml::Table, out: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            toml::Value::Table(inner) => leaf_keys(&path, inner, out),
+            _ => out.push(path),
+        }
+    }
+}
+
+fn merge(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge(b, o),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
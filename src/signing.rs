@@ -0,0 +1,282 @@
+use crate::{config::Config, util::sha256_hex};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// sha256 of every other file under `final/`.
+pub const CHECKSUMS_FILE: &str = "checksums.json";
+/// minisign detached signature of `checksums.json`.
+pub const SIGNATURE_FILE: &str = "checksums.json.minisig";
+/// minisign public key matching the signature.
+pub const PUBLIC_KEY_FILE: &str = "minisign.pub";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checksums {
+    pub algorithm: String,
+    /// `final/`-relative path (with `/` separators) -> hex digest.
+    pub files: BTreeMap<String, String>,
+}
+
+/// Hash every file under `final_dir` except the checksum and signature files.
+pub fn checksums(final_dir: &Path) -> Result<Checksums> {
+    let mut files = BTreeMap::new();
+    collect(final_dir, final_dir, &mut files)?;
+    Ok(Checksums {
+        algorithm: "sha256".into(),
+        files,
+    })
+}
+
+fn collect(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(root, &path, files)?;
+            continue;
+        }
+        let rel = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        if [CHECKSUMS_FILE, SIGNATURE_FILE, PUBLIC_KEY_FILE].contains(&rel.as_str()) {
+            continue;
+        }
+        let bytes = std::fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+        files.insert(rel, sha256_hex(&bytes));
+    }
+    Ok(())
+}
+
+/// Write `final/checksums.json` when `output.write_checksums` is on or a signing key
+/// is configured, and sign it with `security.signing_key_path`.
+pub fn write_checksums(cfg: &Config, final_dir: &Path) -> Result<()> {
+    let key_path = cfg.security.signing_key_path.trim();
+    if !cfg.output.write_checksums && key_path.is_empty() {
+        return Ok(());
+    }
+    let raw = serde_json::to_string_pretty(&checksums(final_dir)?)?;
+    std::fs::write(final_dir.join(CHECKSUMS_FILE), &raw)?;
+    // A signature from an earlier run no longer matches.
+    let _ = std::fs::remove_file(final_dir.join(SIGNATURE_FILE));
+    let _ = std::fs::remove_file(final_dir.join(PUBLIC_KEY_FILE));
+    if key_path.is_empty() {
+        return Ok(());
+    }
+    sign(Path::new(key_path), final_dir, raw.as_bytes())
+        .with_context(|| format!("sign {CHECKSUMS_FILE} with {key_path}"))
+}
+
+/// Recompute the checksums of `final_dir` and compare them with `checksums.json`,
+/// then check its signature if there is one. Returns whether it was signed.
+pub fn verify(final_dir: &Path) -> Result<bool> {
+    let raw = std::fs::read_to_string(final_dir.join(CHECKSUMS_FILE))
+        .with_context(|| format!("read {CHECKSUMS_FILE}"))?;
+    let recorded: Checksums = serde_json::from_str(&raw)?;
+    let current = checksums(final_dir)?;
+    let mut problems = Vec::new();
+    for (file, digest) in &recorded.files {
+        match current.files.get(file) {
+            Some(d) if d == digest => {}
+            Some(_) => problems.push(format!("{file}: changed")),
+            None => problems.push(format!("{file}: missing")),
+        }
+    }
+    for file in current.files.keys() {
+        if !recorded.files.contains_key(file) {
+            problems.push(format!("{file}: not in {CHECKSUMS_FILE}"));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(anyhow!("checksum mismatch: {}", problems.join("; ")));
+    }
+    if !final_dir.join(SIGNATURE_FILE).is_file() {
+        return Ok(false);
+    }
+    verify_signature(final_dir, raw.as_bytes())?;
+    Ok(true)
+}
+
+#[cfg(not(feature = "signing"))]
+fn sign(_key_path: &Path, _final_dir: &Path, _message: &[u8]) -> Result<()> {
+    Err(anyhow!(
+        "security.signing_key_path is set, but this build lacks the `signing` feature"
+    ))
+}
+
+#[cfg(not(feature = "signing"))]
+fn verify_signature(_final_dir: &Path, _message: &[u8]) -> Result<()> {
+    Err(anyhow!(
+        "{SIGNATURE_FILE} found, but this build lacks the `signing` feature"
+    ))
+}
+
+#[cfg(feature = "signing")]
+fn sign(key_path: &Path, final_dir: &Path, message: &[u8]) -> Result<()> {
+    let (key_id, key) = minisign::load_key(key_path)?;
+    std::fs::write(
+        final_dir.join(SIGNATURE_FILE),
+        minisign::signature(&key_id, &key, message, CHECKSUMS_FILE),
+    )?;
+    std::fs::write(
+        final_dir.join(PUBLIC_KEY_FILE),
+        minisign::public_key(&key_id, &key.verifying_key()),
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "signing")]
+fn verify_signature(final_dir: &Path, message: &[u8]) -> Result<()> {
+    let public_key = std::fs::read_to_string(final_dir.join(PUBLIC_KEY_FILE))
+        .with_context(|| format!("read {PUBLIC_KEY_FILE}"))?;
+    let signature = std::fs::read_to_string(final_dir.join(SIGNATURE_FILE))
+        .with_context(|| format!("read {SIGNATURE_FILE}"))?;
+    minisign::verify(&public_key, &signature, message)
+}
+
+/// The minisign file formats (<https://jedisct1.github.io/minisign/>), with prehashed
+/// ("ED") signatures as minisign itself writes them.
+#[cfg(feature = "signing")]
+mod minisign {
+    use anyhow::{anyhow, Context, Result};
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use blake2::{Blake2b512, Digest};
+    use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+    use sha2::Sha256;
+    use std::path::Path;
+
+    /// Length of an unencrypted minisign secret key: algorithm ids, KDF salt and
+    /// limits, key id, secret key, and checksum.
+    const SECRET_KEY_LEN: usize = 158;
+
+    /// Read an ed25519 key: an unencrypted minisign secret key (`minisign -G -W`), or
+    /// base64 of a raw 32-byte seed or 64-byte seed and public key. `untrusted comment:`
+    /// lines are skipped. Raw keys get the first 8 bytes of the public key's sha256 as
+    /// their key id.
+    pub fn load_key(path: &Path) -> Result<([u8; 8], SigningKey)> {
+        let raw =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let line = raw
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with("untrusted comment:"))
+            .ok_or_else(|| anyhow!("no key in {}", path.display()))?;
+        let bytes = STANDARD
+            .decode(line)
+            .map_err(|e| anyhow!("key is not base64: {e}"))?;
+        let (key_id, seed) = match bytes.len() {
+            32 | 64 => (None, &bytes[..32]),
+            SECRET_KEY_LEN if &bytes[..2] != b"Ed" => {
+                return Err(anyhow!("not an ed25519 minisign key"));
+            }
+            SECRET_KEY_LEN if bytes[2..4] != [0, 0] => {
+                return Err(anyhow!(
+                    "encrypted minisign keys are not supported; create one with `minisign -G -W`"
+                ));
+            }
+            SECRET_KEY_LEN => (Some(&bytes[54..62]), &bytes[62..94]),
+            n => return Err(anyhow!("unrecognized key: {n} bytes")),
+        };
+        let key = SigningKey::from_bytes(seed.try_into().expect("32-byte seed"));
+        if bytes.len() == 64 && bytes[32..] != key.verifying_key().to_bytes() {
+            return Err(anyhow!(
+                "the public half of the key does not match its seed"
+            ));
+        }
+        let key_id = match key_id {
+            Some(id) => id.try_into().expect("8-byte key id"),
+            None => Sha256::digest(key.verifying_key().to_bytes())[..8]
+                .try_into()
+                .expect("8-byte key id"),
+        };
+        Ok((key_id, key))
+    }
+
+    pub fn signature(key_id: &[u8; 8], key: &SigningKey, message: &[u8], file: &str) -> String {
+        let sig = key.sign(&Blake2b512::digest(message));
+        let mut blob = b"ED".to_vec();
+        blob.extend_from_slice(key_id);
+        blob.extend_from_slice(&sig.to_bytes());
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let trusted = format!("timestamp:{timestamp}\tfile:{file}");
+        let mut global = sig.to_bytes().to_vec();
+        global.extend_from_slice(trusted.as_bytes());
+        format!(
+            "untrusted comment: signature from quack-check key {}\n{}\ntrusted comment: {trusted}\n{}\n",
+            key_id_hex(key_id),
+            STANDARD.encode(blob),
+            STANDARD.encode(key.sign(&global).to_bytes()),
+        )
+    }
+
+    pub fn public_key(key_id: &[u8; 8], key: &VerifyingKey) -> String {
+        let mut blob = b"Ed".to_vec();
+        blob.extend_from_slice(key_id);
+        blob.extend_from_slice(&key.to_bytes());
+        format!(
+            "untrusted comment: minisign public key {}\n{}\n",
+            key_id_hex(key_id),
+            STANDARD.encode(blob)
+        )
+    }
+
+    pub fn verify(public_key: &str, signature: &str, message: &[u8]) -> Result<()> {
+        let pk = decode_line(public_key, 0)?;
+        if pk.len() != 42 || &pk[..2] != b"Ed" {
+            return Err(anyhow!("not a minisign public key"));
+        }
+        let key = VerifyingKey::from_bytes(pk[10..].try_into().expect("32-byte key"))?;
+
+        let sig = decode_line(signature, 0)?;
+        if sig.len() != 74 {
+            return Err(anyhow!("not a minisign signature"));
+        }
+        if sig[2..10] != pk[2..10] {
+            return Err(anyhow!(
+                "signed by key {}, not by the public key given",
+                key_id_hex(sig[2..10].try_into().expect("8-byte key id"))
+            ));
+        }
+        let file_sig = Signature::from_bytes(sig[10..].try_into().expect("64-byte signature"));
+        match &sig[..2] {
+            b"ED" => key.verify(&Blake2b512::digest(message), &file_sig),
+            b"Ed" => key.verify(message, &file_sig),
+            _ => return Err(anyhow!("unknown minisign signature algorithm")),
+        }
+        .map_err(|_| anyhow!("signature does not match the signed file"))?;
+
+        let trusted = signature
+            .lines()
+            .find_map(|l| l.strip_prefix("trusted comment: "))
+            .ok_or_else(|| anyhow!("signature has no trusted comment"))?;
+        let global = decode_line(signature, 1)?;
+        let global = Signature::from_slice(&global).map_err(|_| anyhow!("bad global signature"))?;
+        let mut signed = sig[10..].to_vec();
+        signed.extend_from_slice(trusted.as_bytes());
+        key.verify(&signed, &global)
+            .map_err(|_| anyhow!("trusted comment signature does not match"))
+    }
+
+    /// The `n`th base64 line of a minisign file (comment lines skipped).
+    fn decode_line(text: &str, n: usize) -> Result<Vec<u8>> {
+        let line = text
+            .lines()
+            .filter(|l| !l.is_empty() && !l.contains("comment: "))
+            .nth(n)
+            .ok_or_else(|| anyhow!("truncated minisign file"))?;
+        STANDARD
+            .decode(line.trim())
+            .map_err(|e| anyhow!("minisign file is not base64: {e}"))
+    }
+
+    /// minisign prints key ids as a little-endian u64.
+    fn key_id_hex(key_id: &[u8; 8]) -> String {
+        format!("{:016X}", u64::from_le_bytes(*key_id))
+    }
+}
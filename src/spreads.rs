@@ -0,0 +1,33 @@
+use crate::{config::Config, probe::ProbeResult};
+use anyhow::{anyhow, Result};
+
+pub const SPLIT_MODES: [&str; 3] = ["off", "auto", "always"];
+
+pub fn validate(cfg: &Config) -> Result<()> {
+    let mode = cfg.input.split_spreads.as_str();
+    if !SPLIT_MODES.contains(&mode) {
+        return Err(anyhow!("unknown input.split_spreads: {mode}"));
+    }
+    Ok(())
+}
+
+/// Whether most of the sampled pages look like two-up spreads.
+pub fn looks_two_up(probe: &ProbeResult) -> bool {
+    let spreads = probe.structure.spread_pages;
+    spreads > 0 && spreads * 2 > probe.sample.sampled_pages
+}
+
+/// Whether to convert each PDF page as two half-pages, per `input.split_spreads`.
+pub fn should_split(cfg: &Config, probe: &ProbeResult) -> bool {
+    match cfg.input.split_spreads.as_str() {
+        "always" => true,
+        "auto" => looks_two_up(probe),
+        _ => false,
+    }
+}
+
+/// The book pages on PDF page `pdf_page` of a split document: every page is split, so
+/// its left half is page `2 * pdf_page - 1` and its right half the one after.
+pub fn half_pages(pdf_page: u32) -> (u32, u32) {
+    (2 * pdf_page - 1, 2 * pdf_page)
+}
@@ -0,0 +1,87 @@
+use tracing::debug;
+
+/// Environment variable systemd sets for `Type=notify` units.
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+
+/// Sends `sd_notify` messages to the service manager: readiness, a one-line status for
+/// `systemctl status`, and stopping. Messages are datagrams of `KEY=VALUE` lines sent
+/// to the socket named by `NOTIFY_SOCKET`; a path, or `@name` for an abstract socket.
+/// Failures are only logged, since the job does not depend on them.
+#[derive(Debug)]
+pub struct Notifier {
+    #[cfg(unix)]
+    socket: std::os::unix::net::UnixDatagram,
+    #[cfg(unix)]
+    addr: std::os::unix::net::SocketAddr,
+}
+
+impl Notifier {
+    /// `None` unless running under a unit with `NOTIFY_SOCKET` set.
+    #[cfg(unix)]
+    pub fn from_env() -> Option<Self> {
+        let target = std::env::var_os(NOTIFY_SOCKET_ENV).filter(|s| !s.is_empty())?;
+        Self::connect(&target.to_string_lossy())
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_env() -> Option<Self> {
+        None
+    }
+
+    /// A notifier for the socket `target`, as `NOTIFY_SOCKET` would name it.
+    #[cfg(unix)]
+    pub fn connect(target: &str) -> Option<Self> {
+        use std::os::unix::net::{SocketAddr, UnixDatagram};
+        let addr = match target.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                SocketAddr::from_abstract_name(name.as_bytes())
+            }
+            #[cfg(not(target_os = "linux"))]
+            Some(_) => {
+                debug!("sd_notify: abstract sockets are Linux-only: {target}");
+                return None;
+            }
+            None => SocketAddr::from_pathname(target),
+        };
+        let socket = UnixDatagram::unbound();
+        match (addr, socket) {
+            (Ok(addr), Ok(socket)) => Some(Self { socket, addr }),
+            (Err(err), _) | (_, Err(err)) => {
+                debug!("sd_notify: cannot use {target}: {err}");
+                None
+            }
+        }
+    }
+
+    /// `READY=1`, with an initial status.
+    pub fn ready(&self, status: &str) {
+        self.send(&format!("READY=1\nSTATUS={}", one_line(status)));
+    }
+
+    /// `STATUS=...`, shown by `systemctl status`.
+    pub fn status(&self, status: &str) {
+        self.send(&format!("STATUS={}", one_line(status)));
+    }
+
+    /// `STOPPING=1`, with a final status.
+    pub fn stopping(&self, status: &str) {
+        self.send(&format!("STOPPING=1\nSTATUS={}", one_line(status)));
+    }
+
+    #[cfg(unix)]
+    fn send(&self, message: &str) {
+        if let Err(err) = self.socket.send_to_addr(message.as_bytes(), &self.addr) {
+            debug!("sd_notify failed: {err}");
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn send(&self, _message: &str) {}
+}
+
+/// The protocol is line-based, so a status must not start another field.
+fn one_line(status: &str) -> String {
+    status.replace(['\n', '\r'], " ")
+}
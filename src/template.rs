@@ -0,0 +1,269 @@
+use crate::{
+    config::Config,
+    decisions::DECISIONS_FILE,
+    page_quality::{PAGE_QUALITY_FILE, PAGE_QUALITY_SVG},
+    report::PARTIAL_REPORT_FILE,
+    signing::{CHECKSUMS_FILE, PUBLIC_KEY_FILE, SIGNATURE_FILE},
+    terms::TERMS_FILE,
+    util::now_rfc3339,
+};
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Variables that depend on the input, so they are only known once a job starts. They
+/// may appear in `paths.out_dir` and the output filenames, not in the directories
+/// shared by every job.
+pub const JOB_VARS: [&str; 4] = ["input_stem", "input_name", "job_id", "job_id_short"];
+
+/// Files and directories `final/` holds besides the three configurable outputs.
+const FINAL_NAMES: [&str; 13] = [
+    PARTIAL_REPORT_FILE,
+    DECISIONS_FILE,
+    PAGE_QUALITY_FILE,
+    PAGE_QUALITY_SVG,
+    CHECKSUMS_FILE,
+    SIGNATURE_FILE,
+    PUBLIC_KEY_FILE,
+    TERMS_FILE,
+    "chunks",
+    "flagged",
+    "images",
+    "sections",
+    "thumbnails",
+];
+
+/// Characters Windows does not allow in file names; rejected in the configured output
+/// filenames so a config behaves the same everywhere.
+const RESERVED_CHARS: [char; 7] = ['<', '>', ':', '"', '|', '?', '*'];
+
+/// Device names Windows reserves whatever the extension.
+const RESERVED_STEMS: [&str; 4] = ["con", "prn", "aux", "nul"];
+
+/// `{date}`, and `{job_name}` unless `global.job_name` uses per-input variables: the
+/// variables known as soon as the config is loaded.
+pub fn config_vars(cfg: &Config) -> Result<BTreeMap<&'static str, String>> {
+    let mut vars = BTreeMap::from([("date", now_rfc3339().chars().take(10).collect())]);
+    if !job_name_is_per_input(cfg) {
+        let name = expand(&cfg.global.job_name, &vars).context("global.job_name")?;
+        vars.insert("job_name", name);
+    }
+    Ok(vars)
+}
+
+/// Whether `global.job_name` uses a per-input variable, e.g. `{input_stem}-{date}`, so
+/// it is only known once each job starts.
+pub fn job_name_is_per_input(cfg: &Config) -> bool {
+    JOB_VARS
+        .iter()
+        .any(|var| cfg.global.job_name.contains(&format!("{{{var}}}")))
+}
+
+/// The per-input variables for one job.
+pub fn job_vars(input: &Path, job_id: &str) -> BTreeMap<&'static str, String> {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = input
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    BTreeMap::from([
+        ("input_stem", stem),
+        ("input_name", name),
+        ("job_id", job_id.to_string()),
+        ("job_id_short", job_id.chars().take(12).collect()),
+    ])
+}
+
+/// Give the input `stem` in place of its file stem in `vars`, in `{input_name}` too:
+/// `scan.pdf` becomes `scan-3f2a9c1b.pdf`.
+pub fn rename_input(vars: &mut BTreeMap<&'static str, String>, stem: &str) {
+    let old_stem = vars.get("input_stem").cloned().unwrap_or_default();
+    if let Some(name) = vars.get_mut("input_name") {
+        *name = format!("{stem}{}", name.strip_prefix(&old_stem).unwrap_or_default());
+    }
+    vars.insert("input_stem", stem.to_string());
+}
+
+/// Replace every `{name}` in `template`. Unknown names are an error so a typo does not
+/// write into a literal `{input_sterm}` directory.
+pub fn expand(template: &str, vars: &BTreeMap<&'static str, String>) -> Result<String> {
+    expand_except(template, vars, &[])
+}
+
+/// Like [`expand`], but placeholders named in `deferred` are left as they are.
+pub fn expand_except(
+    template: &str,
+    vars: &BTreeMap<&'static str, String>,
+    deferred: &[&str],
+) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed `{{` in {template}"))?;
+        let name = &rest[open + 1..open + close];
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None if deferred.contains(&name) => out.push_str(&rest[open..=open + close]),
+            None => {
+                let mut known: Vec<&str> = vars.keys().copied().collect();
+                known.extend_from_slice(deferred);
+                return Err(anyhow!(
+                    "unknown placeholder {{{name}}} in {template} (known: {})",
+                    known.join(", ")
+                ));
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Expand `{job_name}` and `{date}` in `paths.*` and the output filenames. Per-input
+/// variables are kept for [`for_job`] in `paths.out_dir` and the filenames, and are an
+/// error in the other paths.
+pub fn expand_config(cfg: &mut Config) -> Result<()> {
+    let vars = config_vars(cfg)?;
+    let mut deferred = JOB_VARS.to_vec();
+    match vars.get("job_name") {
+        Some(name) => cfg.global.job_name = name.clone(),
+        None => deferred.push("job_name"),
+    }
+    let p = &mut cfg.paths;
+    for (key, value) in [
+        ("paths.work_dir", &mut p.work_dir),
+        ("paths.cache_dir", &mut p.cache_dir),
+        ("paths.docling_artifacts_dir", &mut p.docling_artifacts_dir),
+        ("paths.scripts_dir", &mut p.scripts_dir),
+    ] {
+        if value.contains("{job_name}") && !vars.contains_key("job_name") {
+            return Err(anyhow!(
+                "{key} uses {{job_name}}, but global.job_name depends on the input"
+            ));
+        }
+        *value = expand(value, &vars).with_context(|| key.to_string())?;
+    }
+    for (key, value) in job_templates(cfg) {
+        *value = expand_except(value, &vars, &deferred).with_context(|| key.to_string())?;
+    }
+    check_filenames(cfg, false)
+}
+
+/// `cfg` with the per-input variables expanded for one job.
+pub fn for_job(cfg: &Config, input: &Path, job_id: &str) -> Result<Config> {
+    for_job_as(cfg, input, job_id, None)
+}
+
+/// [`for_job`], with `stem` as `{input_stem}` when given (see [`rename_input`]).
+pub fn for_job_as(cfg: &Config, input: &Path, job_id: &str, stem: Option<&str>) -> Result<Config> {
+    let mut vars = config_vars(cfg)?;
+    vars.extend(job_vars(input, job_id));
+    if let Some(stem) = stem {
+        rename_input(&mut vars, stem);
+    }
+    let mut cfg = cfg.clone();
+    if !vars.contains_key("job_name") {
+        let name = expand(&cfg.global.job_name, &vars).context("global.job_name")?;
+        vars.insert("job_name", name);
+    }
+    cfg.global.job_name = vars["job_name"].clone();
+    for (key, value) in job_templates(&mut cfg) {
+        *value = expand(value, &vars).with_context(|| key.to_string())?;
+    }
+    check_filenames(&cfg, true)?;
+    Ok(cfg)
+}
+
+/// Check that the output filenames name files directly inside `final/`: no path
+/// separators or `..`, and no two alike (ignoring case) or alike to a file `final/`
+/// already holds. Templates (`expanded` false, at config load) must also be free of
+/// control and [`RESERVED_CHARS`] outside placeholders and of Windows device names;
+/// once a job has expanded them, those come from the input's own name and are kept.
+pub fn check_filenames(cfg: &Config, expanded: bool) -> Result<()> {
+    let names = [
+        ("output.markdown_filename", &cfg.output.markdown_filename),
+        ("output.text_filename", &cfg.output.text_filename),
+        ("output.report_filename", &cfg.output.report_filename),
+    ];
+    for (i, &(key, value)) in names.iter().enumerate() {
+        if value.contains(['/', '\\']) || value.trim() == ".." {
+            return Err(anyhow!("{key} expands to a path, not a file name: {value}"));
+        }
+        if value.trim().is_empty() || value.trim() == "." {
+            return Err(anyhow!("{key} is not a file name: {value:?}"));
+        }
+        if !expanded {
+            let literal = without_placeholders(value);
+            if let Some(c) = literal
+                .chars()
+                .find(|c| c.is_control() || RESERVED_CHARS.contains(c))
+            {
+                return Err(anyhow!("{key} contains the reserved character {c:?}: {value:?}"));
+            }
+            let stem = value.split('.').next().unwrap_or_default().trim();
+            if RESERVED_STEMS.contains(&stem.to_ascii_lowercase().as_str()) {
+                return Err(anyhow!("{key} uses the reserved device name {stem}: {value}"));
+            }
+        }
+        if let Some(taken) = FINAL_NAMES.iter().find(|n| n.eq_ignore_ascii_case(value)) {
+            return Err(anyhow!("{key} collides with final/{taken}"));
+        }
+        if let Some((other, _)) = names[..i]
+            .iter()
+            .find(|(_, other)| other.eq_ignore_ascii_case(value))
+        {
+            return Err(anyhow!("{key} and {other} are both {value}"));
+        }
+    }
+    Ok(())
+}
+
+/// `value` with every `{name}` placeholder removed.
+fn without_placeholders(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        match rest[open..].find('}') {
+            Some(close) => rest = &rest[open + close + 1..],
+            None => {
+                rest = &rest[open..];
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn job_templates(cfg: &mut Config) -> [(&'static str, &mut String); 4] {
+    [
+        ("paths.out_dir", &mut cfg.paths.out_dir),
+        (
+            "output.markdown_filename",
+            &mut cfg.output.markdown_filename,
+        ),
+        ("output.text_filename", &mut cfg.output.text_filename),
+        ("output.report_filename", &mut cfg.output.report_filename),
+    ]
+}
+
+/// The part of an output directory template before its first per-input placeholder:
+/// where the job directories of every input live, somewhere below.
+pub fn static_root(out_dir: &str) -> PathBuf {
+    let root: PathBuf = Path::new(out_dir)
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains('{'))
+        .collect();
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{
+    pagenum::PageNumberStats,
+    postprocess::{is_marker_line, parse_page_marker},
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const TERMS_FILE: &str = "terms.json";
+
+/// Entries a candidate section needs before it is treated as an index or glossary.
+const MIN_ENTRIES: usize = 5;
+/// Share of a candidate section's lines that must parse as entries.
+const MIN_PARSED_FRACTION: f32 = 0.6;
+/// Longest glossary term; longer "Term: ..." lines are ordinary sentences.
+const MAX_GLOSSARY_TERM_CHARS: usize = 60;
+
+/// One index or glossary term, merged across duplicate lines.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TermEntry {
+    pub term: String,
+    /// Page references as printed: `"12"`, `"45-47"`, `"xii"`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<String>,
+    /// PDF pages of those references, where the printed page numbers are known.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pdf_pages: Vec<u32>,
+    /// `see` / `see also` targets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub see: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+}
+
+/// One detected back-of-book section.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TermSection {
+    /// "index" or "glossary".
+    pub kind: String,
+    pub heading: String,
+    /// PDF page of the heading, when page markers are present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_page: Option<u32>,
+    /// Lines that were neither entries nor continuations.
+    pub unparsed_lines: usize,
+    pub entries: Vec<TermEntry>,
+}
+
+/// `final/terms.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TermsReport {
+    pub sections: Vec<TermSection>,
+}
+
+impl TermsReport {
+    pub fn entry_count(&self) -> usize {
+        self.sections.iter().map(|s| s.entries.len()).sum()
+    }
+}
+
+struct Patterns {
+    heading: Regex,
+    markdown_heading: Regex,
+    letter: Regex,
+    page_ref: Regex,
+    see: Regex,
+    glossary: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        let num = r"(?:\d{1,4}|(?:xc|xl|l?x{0,3})(?:ix|iv|v?i{0,3}))";
+        Self {
+            heading: Regex::new(
+                r"(?i)^(?:#{1,6}\s*)?\**((?:general |subject |name )?index(?: of [\w ]+)?|glossary(?: of [\w ]+)?)\**\s*$",
+            )
+            .expect("heading regex"),
+            markdown_heading: Regex::new(r"^#{1,6}\s").expect("markdown heading regex"),
+            letter: Regex::new(r"^(?:#{1,6}\s*)?\**[A-Z]\**$").expect("letter regex"),
+            page_ref: Regex::new(&format!(
+                r"^({num})(?:\s*[-–—]\s*({num}))?(?:n|f|ff)?\.?$"
+            ))
+            .expect("page ref regex"),
+            see: Regex::new(r"(?i)[,;.]?\s*\(?\bsee(?: also)?\s+([^)]+?)\)?\.?$")
+                .expect("see regex"),
+            glossary: Regex::new(r"^\**([^:*]+?)\**\s*(?::|\s[—–-])\s+(.+)$")
+                .expect("glossary regex"),
+        }
+    }
+
+    /// `"45–47"` normalized to `"45-47"`; roman numerals only in lower case.
+    fn page_ref(&self, token: &str) -> Option<String> {
+        let caps = self.page_ref.captures(token.trim())?;
+        let first = caps.get(1)?.as_str();
+        if first.is_empty() {
+            return None;
+        }
+        Some(match caps.get(2).filter(|m| !m.as_str().is_empty()) {
+            Some(last) => format!("{first}-{}", last.as_str()),
+            None => first.to_string(),
+        })
+    }
+
+    /// `Term, 12, 45-47; see also Other` or `Term 12`.
+    fn index_entry(&self, line: &str) -> Option<TermEntry> {
+        let mut rest = line;
+        let mut see = Vec::new();
+        if let Some(caps) = self.see.captures(line) {
+            see = caps[1]
+                .split(';')
+                .map(|s| s.trim().trim_end_matches('.').to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            rest = &line[..caps.get(0)?.start()];
+        }
+        // A reference list wrapped onto the next line leaves a trailing comma.
+        let mut parts: Vec<&str> = rest.trim().trim_end_matches(',').split(',').collect();
+        let mut pages = Vec::new();
+        while let Some(last) = parts.last() {
+            if let Some(page) = self.page_ref(last) {
+                pages.push(page);
+                parts.pop();
+                continue;
+            }
+            // `Term 12` without a comma before the first reference.
+            if let Some((head, tail)) = last.trim().rsplit_once(' ')
+                && let Some(page) = self.page_ref(tail)
+            {
+                pages.push(page);
+                let n = parts.len();
+                parts[n - 1] = head;
+            }
+            break;
+        }
+        pages.reverse();
+        let term = clean_term(&parts.join(","));
+        if term.is_empty() || (pages.is_empty() && see.is_empty()) {
+            return None;
+        }
+        Some(TermEntry {
+            term,
+            pages,
+            see,
+            ..Default::default()
+        })
+    }
+
+    /// A line of bare page references continuing the previous entry.
+    fn continuation(&self, line: &str) -> Option<Vec<String>> {
+        line.split(',')
+            .map(|token| self.page_ref(token))
+            .collect::<Option<Vec<_>>>()
+            .filter(|pages| !pages.is_empty())
+    }
+
+    fn glossary_entry(&self, line: &str) -> Option<TermEntry> {
+        let caps = self.glossary.captures(line)?;
+        let term = clean_term(&caps[1]);
+        if term.is_empty() || term.chars().count() > MAX_GLOSSARY_TERM_CHARS {
+            return None;
+        }
+        Some(TermEntry {
+            term,
+            definition: Some(caps[2].trim().to_string()),
+            ..Default::default()
+        })
+    }
+}
+
+fn clean_term(raw: &str) -> String {
+    raw.trim()
+        .trim_matches(|c: char| c == '*' || c == '_')
+        .trim_end_matches([',', ';', ':', '.'])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn strip_bullet(line: &str) -> &str {
+    let t = line.trim();
+    t.strip_prefix("- ")
+        .or_else(|| t.strip_prefix("* "))
+        .unwrap_or(t)
+}
+
+/// Where a detected section sits in the transcript, for `replace`.
+struct Span {
+    /// First line after the heading.
+    body_start: usize,
+    /// One past the last line of the section.
+    end: usize,
+}
+
+/// Find index and glossary sections in page-marked markdown and parse their entries.
+///
+/// A section starts at a heading (or a bare line) reading "Index", "Subject Index",
+/// "Index of Names", "Glossary", ... and runs to the next heading that is not a single
+/// letter. Lines are parsed as `term, pages; see also ...` for indexes and
+/// `term: definition` for glossaries; bare page lists continue the previous entry and
+/// other lines continue a glossary definition. A section is only kept when at least
+/// five entries were found and most of its lines parsed, so a stray "Index" line in
+/// body text is ignored. Duplicate terms (OCR'd two-column layouts often repeat lines
+/// across page breaks) are merged.
+pub fn extract_terms(md: &str, page_numbers: &PageNumberStats) -> TermsReport {
+    extract(md, page_numbers).0
+}
+
+/// Like [`extract_terms`], and also replace each detected section's body with a
+/// comment pointing at `terms.json`.
+pub fn replace_terms(md: &str, page_numbers: &PageNumberStats) -> (String, TermsReport) {
+    let (report, spans) = extract(md, page_numbers);
+    if spans.is_empty() {
+        return (md.to_string(), report);
+    }
+    let lines: Vec<&str> = md.lines().collect();
+    let mut out: Vec<String> = Vec::with_capacity(lines.len());
+    let mut next = 0;
+    for (span, section) in spans.iter().zip(&report.sections) {
+        out.extend(lines[next..span.body_start].iter().map(|l| l.to_string()));
+        out.push(String::new());
+        out.push(format!(
+            "<!-- {}: {} entries in {TERMS_FILE} -->",
+            section.kind,
+            section.entries.len()
+        ));
+        out.push(String::new());
+        // Page markers inside the section stay, so page accounting is intact.
+        out.extend(
+            lines[span.body_start..span.end]
+                .iter()
+                .filter(|l| parse_page_marker(l).is_some())
+                .map(|l| format!("{l}\n")),
+        );
+        next = span.end;
+    }
+    out.extend(lines[next..].iter().map(|l| l.to_string()));
+    (out.join("\n"), report)
+}
+
+fn extract(md: &str, page_numbers: &PageNumberStats) -> (TermsReport, Vec<Span>) {
+    let patterns = Patterns::new();
+    let printed_to_pdf: HashMap<&str, u32> = page_numbers
+        .pages
+        .iter()
+        .map(|p| (p.printed.as_str(), p.pdf_page))
+        .collect();
+    let lines: Vec<&str> = md.lines().collect();
+    let mut report = TermsReport::default();
+    let mut spans = Vec::new();
+    let mut page = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(p) = parse_page_marker(line) {
+            page = Some(p);
+        }
+        let Some(caps) = patterns.heading.captures(line.trim()) else {
+            i += 1;
+            continue;
+        };
+        let heading = caps[1].trim().to_string();
+        let kind = if heading.to_lowercase().starts_with("glossary") {
+            "glossary"
+        } else {
+            "index"
+        };
+        let body_start = i + 1;
+        let mut end = body_start;
+        while end < lines.len() {
+            let l = lines[end].trim();
+            if patterns.markdown_heading.is_match(l) && !patterns.letter.is_match(l) {
+                break;
+            }
+            end += 1;
+        }
+        let section = parse_section(
+            &patterns,
+            kind,
+            heading,
+            page,
+            &lines[body_start..end],
+            &printed_to_pdf,
+        );
+        if let Some(section) = section {
+            report.sections.push(section);
+            spans.push(Span { body_start, end });
+            page = lines[body_start..end]
+                .iter()
+                .rev()
+                .find_map(|l| parse_page_marker(l))
+                .or(page);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    (report, spans)
+}
+
+fn parse_section(
+    patterns: &Patterns,
+    kind: &str,
+    heading: String,
+    start_page: Option<u32>,
+    lines: &[&str],
+    printed_to_pdf: &HashMap<&str, u32>,
+) -> Option<TermSection> {
+    let mut entries: Vec<TermEntry> = Vec::new();
+    let (mut parsed, mut unparsed) = (0usize, 0usize);
+    for raw in lines {
+        let line = strip_bullet(raw);
+        if line.is_empty() || is_marker_line(line) || patterns.letter.is_match(line) {
+            continue;
+        }
+        let entry = match kind {
+            "glossary" => patterns.glossary_entry(line),
+            _ => patterns.index_entry(line),
+        };
+        if let Some(entry) = entry {
+            entries.push(entry);
+            parsed += 1;
+            continue;
+        }
+        match (kind, entries.last_mut()) {
+            ("index", Some(prev)) => match patterns.continuation(line) {
+                Some(pages) => {
+                    prev.pages.extend(pages);
+                    parsed += 1;
+                }
+                None => unparsed += 1,
+            },
+            ("glossary", Some(prev)) => {
+                let definition = prev.definition.get_or_insert_with(String::new);
+                definition.push(' ');
+                definition.push_str(line);
+                parsed += 1;
+            }
+            _ => unparsed += 1,
+        }
+    }
+    let total = parsed + unparsed;
+    if entries.len() < MIN_ENTRIES || (parsed as f32) < MIN_PARSED_FRACTION * total as f32 {
+        return None;
+    }
+    let mut entries = dedup(entries);
+    for entry in &mut entries {
+        entry.pdf_pages = entry
+            .pages
+            .iter()
+            .filter_map(|p| {
+                printed_to_pdf
+                    .get(p.split('-').next().unwrap_or(p))
+                    .copied()
+            })
+            .collect();
+    }
+    Some(TermSection {
+        kind: kind.to_string(),
+        heading,
+        start_page,
+        unparsed_lines: unparsed,
+        entries,
+    })
+}
+
+/// Merge entries whose terms match ignoring case and spacing, keeping first-seen
+/// order and the longest definition.
+fn dedup(entries: Vec<TermEntry>) -> Vec<TermEntry> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut out: Vec<TermEntry> = Vec::new();
+    for entry in entries {
+        let key = entry.term.to_lowercase();
+        let Some(&at) = index.get(&key) else {
+            index.insert(key, out.len());
+            out.push(entry);
+            continue;
+        };
+        let existing = &mut out[at];
+        for page in entry.pages {
+            if !existing.pages.contains(&page) {
+                existing.pages.push(page);
+            }
+        }
+        for target in entry.see {
+            if !existing.see.contains(&target) {
+                existing.see.push(target);
+            }
+        }
+        if entry.definition.as_ref().map(String::len)
+            > existing.definition.as_ref().map(String::len)
+        {
+            existing.definition = entry.definition;
+        }
+    }
+    out
+}
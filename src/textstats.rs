@@ -0,0 +1,255 @@
+use crate::script;
+use std::collections::{HashMap, HashSet};
+
+/// Log-odds of a token being noise before any evidence; about 0.25% of clean prose.
+const PRIOR: f32 = -6.0;
+/// Evidence weights, in log-odds. A strong signal alone makes a token likely noise; a
+/// weak one needs company.
+const STRONG: f32 = 8.0;
+const MEDIUM: f32 = 6.0;
+const WEAK: f32 = 4.0;
+
+/// A word that one misreading away becomes a word this much more frequent in the text
+/// is taken for the misreading.
+const MISREAD_FACTOR: u32 = 3;
+/// Occurrences the correctly read word needs, so rare words are not compared.
+const MISREAD_MIN_COUNT: u32 = 5;
+/// Common OCR misreadings, as (read, meant).
+const MISREADINGS: &[(&str, &str)] = &[
+    ("li", "h"),
+    ("rn", "m"),
+    ("cl", "d"),
+    ("vv", "w"),
+    ("ii", "u"),
+    ("c", "e"),
+    ("e", "c"),
+    ("1", "l"),
+    ("0", "o"),
+    ("5", "s"),
+];
+
+/// Letters a Latin word of three or more letters has at least one of.
+const LATIN_VOWELS: &str = "aeiouyàáâãäåæèéêëìíîïòóôõöøœùúûüýÿ";
+/// Punctuation prose puts around words, trimmed before a token is scored.
+const WRAPPING: &[char] = &[
+    '.', ',', ';', ':', '!', '?', '"', '\'', '(', ')', '[', ']', '{', '}', '“', '”', '‘', '’', '«',
+    '»', '„', '—', '–', '-', '*', '_', '`', '#', '>', '…',
+];
+
+/// How much of a text looks like OCR noise.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextStats {
+    /// Non-whitespace characters in scored tokens.
+    pub chars: usize,
+    pub tokens: usize,
+    /// Expected number of noise tokens: the sum of each token's noise probability.
+    pub noise_tokens: f32,
+    /// Expected number of characters in noise tokens.
+    pub noise_chars: f32,
+}
+
+impl TextStats {
+    /// Expected share of the characters that are noise, from 0 to 1.
+    pub fn garbage_ratio(&self) -> f32 {
+        if self.chars == 0 {
+            0.0
+        } else {
+            (self.noise_chars / self.chars as f32).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// Word counts of a text, used as its own dictionary: OCR noise often turns a common
+/// word into a rare one a known misreading away (`tlie` for `the`, `rnore` for `more`),
+/// in whatever language the text is in.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    counts: HashMap<String, u32>,
+}
+
+impl Vocabulary {
+    pub fn train(text: &str) -> Self {
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for word in text.split_whitespace().filter_map(normalized) {
+            *counts.entry(word).or_default() += 1;
+        }
+        Self { counts }
+    }
+
+    fn count(&self, word: &str) -> u32 {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Whether undoing one misreading in `word` gives a word far more common in the text.
+    fn is_misreading(&self, word: &str) -> bool {
+        let Some(word) = normalized(word) else {
+            return false;
+        };
+        let seen = self.count(&word).max(1);
+        MISREADINGS.iter().any(|(read, meant)| {
+            word.match_indices(read).any(|(i, _)| {
+                let candidate = format!("{}{meant}{}", &word[..i], &word[i + read.len()..]);
+                let common = self.count(&candidate);
+                common >= MISREAD_MIN_COUNT && common >= seen * MISREAD_FACTOR
+            })
+        })
+    }
+}
+
+/// [`analyze_with`] against the vocabulary of `text` itself.
+pub fn analyze(text: &str) -> TextStats {
+    analyze_with(text, &Vocabulary::train(text))
+}
+
+/// Score each whitespace-separated token of `text` naive-Bayes style: evidence adds
+/// log-odds to a low prior, and the token counts as noise with the resulting
+/// probability. The evidence is replacement and control characters, runs of mixed
+/// punctuation, digits or symbols inside a word, letters of two scripts in one word
+/// (Cyrillic or Greek lookalikes in Latin text), Latin words without a vowel, long
+/// consonant runs, tripled letters, case switching, and words that are a misreading of
+/// a word common in `vocabulary`. Unlike counting replacement characters, this catches
+/// noise made of ordinary characters (`tlie`, `l1ke`, `.,;'|`). Markdown syntax, URLs,
+/// numbers, and marker comments are skipped.
+pub fn analyze_with(text: &str, vocabulary: &Vocabulary) -> TextStats {
+    let mut stats = TextStats::default();
+    let tokens = text
+        .lines()
+        .filter(|line| !is_comment(line.trim()))
+        .flat_map(str::split_whitespace)
+        .filter(|t| !is_markup(t));
+    for token in tokens {
+        let len = token.chars().count();
+        let odds = PRIOR + evidence(token, vocabulary);
+        let p = 1.0 / (1.0 + (-odds).exp());
+        stats.tokens += 1;
+        stats.chars += len;
+        stats.noise_tokens += p;
+        stats.noise_chars += p * len as f32;
+    }
+    stats
+}
+
+/// [`analyze`]'s garbage ratio.
+pub fn garbage_ratio(text: &str) -> f32 {
+    analyze(text).garbage_ratio()
+}
+
+/// Page markers and other HTML comments on a line of their own.
+fn is_comment(line: &str) -> bool {
+    line.starts_with("<!--") && line.ends_with("-->")
+}
+
+fn is_markup(token: &str) -> bool {
+    token.starts_with("<!--")
+        || token.ends_with("-->")
+        || token.contains("://")
+        || token.contains("](")
+        || token.starts_with("![")
+        || token
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | '=' | '#' | '*' | '>'))
+}
+
+fn evidence(token: &str, vocabulary: &Vocabulary) -> f32 {
+    if token.chars().any(|c| c == '\u{FFFD}' || c.is_control()) {
+        return STRONG;
+    }
+    let word = token.trim_matches(WRAPPING);
+    if word.is_empty() {
+        // A dash or an ellipsis is prose; three kinds of mark in a row are not.
+        let kinds: HashSet<char> = token.chars().collect();
+        return if kinds.len() >= 3 { MEDIUM } else { 0.0 };
+    }
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    let digits = word.chars().filter(|c| c.is_numeric()).count();
+    if letters == 0 {
+        let numeric = word
+            .chars()
+            .all(|c| c.is_numeric() || ",.:/%$€£+-".contains(c));
+        return if numeric { 0.0 } else { MEDIUM };
+    }
+    let mut score = 0.0;
+    if digits > 0 && !is_code_or_ordinal(word) {
+        score += MEDIUM;
+    }
+    let inner = |c: char| {
+        c.is_alphanumeric() || matches!(c, '\'' | '’' | '-' | '.' | '/' | '&' | '_' | '(' | ')')
+    };
+    if !word.chars().all(inner) {
+        score += MEDIUM;
+    }
+    let scripts: HashSet<&str> = word.chars().filter_map(script::script_of).collect();
+    if scripts.len() > 1 {
+        score += STRONG;
+    }
+    if scripts.contains("latin") {
+        score += latin_evidence(word, letters);
+    }
+    if vocabulary.is_misreading(word) {
+        score += MEDIUM;
+    }
+    score
+}
+
+fn latin_evidence(word: &str, letters: usize) -> f32 {
+    let lower = word.to_lowercase();
+    let vowel = |c: char| LATIN_VOWELS.contains(c);
+    let mut score = 0.0;
+    let acronym = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .all(|c| c.is_uppercase());
+    if letters >= 3 && !acronym && !lower.chars().any(vowel) {
+        score += MEDIUM;
+    }
+    let mut run = 0;
+    let mut longest = 0;
+    for c in lower.chars() {
+        run = if c.is_alphabetic() && !vowel(c) {
+            run + 1
+        } else {
+            0
+        };
+        longest = longest.max(run);
+    }
+    if longest >= 5 {
+        score += WEAK;
+    }
+    let chars: Vec<char> = word.chars().collect();
+    if chars
+        .windows(3)
+        .any(|w| w[0] == w[1] && w[1] == w[2] && w[0].is_alphabetic())
+    {
+        score += WEAK;
+    }
+    // Lower to upper twice inside a word: `tHeY`. Once is a name like `McCoy`.
+    let switches = chars
+        .windows(2)
+        .filter(|w| w[0].is_lowercase() && w[1].is_uppercase())
+        .count();
+    if switches > 1 {
+        score += WEAK;
+    }
+    if letters > 30 {
+        score += WEAK;
+    }
+    score
+}
+
+/// `1st`, `19th`, `1920s`, `A4`, `H2O`, `B12`: digits a word may carry.
+fn is_code_or_ordinal(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    let suffix = lower.trim_start_matches(|c: char| c.is_numeric());
+    if suffix.len() < lower.len() && matches!(suffix, "st" | "nd" | "rd" | "th" | "s" | "d") {
+        return true;
+    }
+    word.chars().all(|c| c.is_uppercase() || c.is_numeric())
+}
+
+/// A token lowercased without its wrapping punctuation, when it has a letter.
+fn normalized(token: &str) -> Option<String> {
+    let word = token.trim_matches(WRAPPING);
+    word.chars()
+        .any(char::is_alphabetic)
+        .then(|| word.to_lowercase())
+}
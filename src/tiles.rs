@@ -0,0 +1,79 @@
+use crate::{config::Config, probe::ProbeResult};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Docling renders a page at three times its size in points (216 dpi) for OCR.
+pub const OCR_RENDER_SCALE: f64 = 3.0;
+
+/// How a page is cut for OCR: `rows` by `cols` equal tiles, converted as pages of their
+/// own and read row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageTiles {
+    pub page: u32,
+    pub rows: u32,
+    pub cols: u32,
+}
+
+pub fn validate(cfg: &Config) -> Result<()> {
+    let limit = cfg.input.max_page_megapixels;
+    if limit.is_nan() || limit <= 0.0 {
+        return Err(anyhow!(
+            "input.max_page_megapixels must be positive: {limit}"
+        ));
+    }
+    Ok(())
+}
+
+/// Megapixels of a page of `width` by `height` points rendered for OCR.
+pub fn megapixels(width: f64, height: f64) -> f64 {
+    width * height * OCR_RENDER_SCALE * OCR_RENDER_SCALE / 1e6
+}
+
+/// Pages the probe measured as larger than `input.max_page_megapixels` when rendered
+/// for OCR, as (page, megapixels).
+pub fn oversized(cfg: &Config, probe: &ProbeResult) -> Vec<(u32, f64)> {
+    probe
+        .structure
+        .large_pages
+        .iter()
+        .map(|p| (p.page, megapixels(p.width_pt, p.height_pt)))
+        .filter(|&(_, mp)| mp > cfg.input.max_page_megapixels)
+        .collect()
+}
+
+/// The fewest rows and columns that bring each tile under `max_megapixels`, keeping the
+/// tiles close to square.
+pub fn grid(width: f64, height: f64, max_megapixels: f64) -> (u32, u32) {
+    let (mut rows, mut cols) = (1u32, 1u32);
+    while megapixels(width / cols as f64, height / rows as f64) > max_megapixels {
+        if height / rows as f64 >= width / cols as f64 {
+            rows += 1;
+        } else {
+            cols += 1;
+        }
+    }
+    (rows, cols)
+}
+
+/// The pages to tile, per `input.tile_oversized_pages`. Pages split as spreads are not
+/// tiled as well.
+pub fn plan(cfg: &Config, probe: &ProbeResult, split_spreads: bool) -> Vec<PageTiles> {
+    if !cfg.input.tile_oversized_pages || split_spreads {
+        return Vec::new();
+    }
+    let limit = cfg.input.max_page_megapixels;
+    probe
+        .structure
+        .large_pages
+        .iter()
+        .filter(|p| megapixels(p.width_pt, p.height_pt) > limit)
+        .map(|p| {
+            let (rows, cols) = grid(p.width_pt, p.height_pt, limit);
+            PageTiles {
+                page: p.page,
+                rows,
+                cols,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Counts tokens with the tokenizer named by `output.tokenizer`.
+///
+/// `"approx"` (~4 characters per token) is always available; BPE encodings such as
+/// `"cl100k_base"` and `"o200k_base"` require building with `--features tiktoken`.
+pub struct TokenCounter {
+    name: String,
+    #[cfg(feature = "tiktoken")]
+    bpe: Option<tiktoken_rs::CoreBPE>,
+}
+
+impl TokenCounter {
+    pub fn approx() -> Self {
+        Self {
+            name: "approx".into(),
+            #[cfg(feature = "tiktoken")]
+            bpe: None,
+        }
+    }
+
+    pub fn new(name: &str) -> Result<Self> {
+        if name == "approx" {
+            return Ok(Self::approx());
+        }
+        Self::bpe(name)
+    }
+
+    #[cfg(feature = "tiktoken")]
+    fn bpe(name: &str) -> Result<Self> {
+        let bpe = match name {
+            "cl100k_base" => tiktoken_rs::cl100k_base()?,
+            "o200k_base" => tiktoken_rs::o200k_base()?,
+            "p50k_base" => tiktoken_rs::p50k_base()?,
+            other => return Err(anyhow!("unknown output.tokenizer: {other}")),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            bpe: Some(bpe),
+        })
+    }
+
+    #[cfg(not(feature = "tiktoken"))]
+    fn bpe(name: &str) -> Result<Self> {
+        Err(anyhow!(
+            "output.tokenizer = {name:?} requires building quack-check with --features tiktoken"
+        ))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        #[cfg(feature = "tiktoken")]
+        if let Some(bpe) = &self.bpe {
+            return bpe.encode_ordinary(text).len();
+        }
+        text.chars().count().div_ceil(4)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenStats {
+    pub tokenizer: String,
+    pub total: usize,
+}
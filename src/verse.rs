@@ -0,0 +1,146 @@
+use crate::postprocess::is_marker_line;
+
+/// Minimum share of a stanza's lines (last line excluded) that end well short of its
+/// longest line. Justified or wrapped prose only has a short final line.
+const MIN_RAGGED_FRACTION: f32 = 0.3;
+/// A line counts as "short" below this fraction of the stanza's longest line.
+const RAGGED_LINE_RATIO: f32 = 0.85;
+/// Metered verse is often not ragged, but its lines start with a capital where wrapped
+/// prose mostly continues a sentence in lowercase.
+const MIN_CAPITALIZED_FRACTION: f32 = 0.75;
+
+/// Flag the lines of `md` that belong to verse: runs of stanzas (blank-line separated
+/// blocks of short lines that are ragged or start with capitals). A run needs at least two stanzas, or one stanza of
+/// `min_lines` lines, so short prose paragraphs and address blocks are left alone.
+pub fn detect_verse(md: &str, max_line_chars: usize, min_lines: usize) -> Vec<bool> {
+    let lines: Vec<&str> = md.lines().collect();
+    let mut mask = vec![false; lines.len()];
+
+    // (start, end) line ranges of candidate stanzas, and whether each follows the
+    // previous one with only blank lines in between.
+    let mut stanzas: Vec<(usize, usize, bool)> = Vec::new();
+    let mut adjacent = false;
+    let mut in_code = false;
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim_start().starts_with("```") {
+            in_code = !in_code;
+            adjacent = false;
+            i += 1;
+            continue;
+        }
+        if in_code {
+            i += 1;
+            continue;
+        }
+        if lines[i].trim().is_empty() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < lines.len()
+            && !lines[i].trim().is_empty()
+            && !lines[i].trim_start().starts_with("```")
+        {
+            i += 1;
+        }
+        if is_stanza(&lines[start..i], max_line_chars) {
+            stanzas.push((start, i, adjacent));
+            adjacent = true;
+        } else {
+            adjacent = false;
+        }
+    }
+
+    let mut run: Vec<(usize, usize)> = Vec::new();
+    let mut flush = |run: &mut Vec<(usize, usize)>| {
+        let long_enough = run.len() >= 2 || run.iter().any(|(s, e)| e - s >= min_lines);
+        if long_enough {
+            for &(s, e) in run.iter() {
+                mask[s..e].iter_mut().for_each(|m| *m = true);
+            }
+        }
+        run.clear();
+    };
+    for (start, end, adjacent) in stanzas {
+        if !adjacent {
+            flush(&mut run);
+        }
+        run.push((start, end));
+    }
+    flush(&mut run);
+
+    mask
+}
+
+fn is_stanza(block: &[&str], max_line_chars: usize) -> bool {
+    if block.len() < 2 {
+        return false;
+    }
+    let structural = |l: &str| {
+        let t = l.trim_start();
+        t.starts_with('#')
+            || t.starts_with('|')
+            || t.starts_with("- ")
+            || t.starts_with("* ")
+            || t.starts_with("> ")
+            || t.starts_with("![")
+            || t.starts_with("<!--")
+            || is_marker_line(l)
+            || t.split_once(". ")
+                .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    };
+    if block.iter().any(|l| structural(l)) {
+        return false;
+    }
+    let lens: Vec<usize> = block.iter().map(|l| l.trim().chars().count()).collect();
+    let longest = *lens.iter().max().unwrap_or(&0);
+    if longest > max_line_chars {
+        return false;
+    }
+    let body = &lens[..lens.len() - 1];
+    let short = body
+        .iter()
+        .filter(|&&n| (n as f32) < longest as f32 * RAGGED_LINE_RATIO)
+        .count();
+    let capitalized = block[1..]
+        .iter()
+        .filter(|l| {
+            l.trim_start()
+                .chars()
+                .find(|c| c.is_alphanumeric())
+                .is_some_and(char::is_uppercase)
+        })
+        .count();
+    short as f32 / body.len() as f32 >= MIN_RAGGED_FRACTION
+        || capitalized as f32 / (block.len() - 1) as f32 >= MIN_CAPITALIZED_FRACTION
+}
+
+/// Rewrite flagged verse lines so markdown renderers keep their layout: a trailing
+/// `\` hard break on every line but a stanza's last, and leading indentation as
+/// non-breaking spaces so it is neither dropped nor read as a code block.
+pub fn add_hard_breaks(md: &str, mask: &[bool]) -> String {
+    let lines: Vec<&str> = md.lines().collect();
+    let mut out = Vec::with_capacity(lines.len());
+    for (idx, line) in lines.iter().enumerate() {
+        if !mask.get(idx).copied().unwrap_or(false) {
+            out.push(line.to_string());
+            continue;
+        }
+        let body = line.trim_start();
+        let indent = line.len() - body.len();
+        let mut rewritten = "\u{a0}".repeat(indent);
+        rewritten.push_str(body.trim_end());
+        let stanza_continues = mask.get(idx + 1).copied().unwrap_or(false)
+            && lines.get(idx + 1).is_some_and(|l| !l.trim().is_empty());
+        if stanza_continues {
+            rewritten.push('\\');
+        }
+        out.push(rewritten);
+    }
+    let mut joined = out.join("\n");
+    if md.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+/// Subdirectory of the XDG base directories the tool's defaults live in.
+const APP_DIR: &str = "quack-check";
+
+/// `$XDG_CACHE_HOME`, or `~/.cache`. The spec ignores relative values.
+pub fn cache_home() -> Option<PathBuf> {
+    base_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// `$XDG_DATA_HOME`, or `~/.local/share`.
+pub fn data_home() -> Option<PathBuf> {
+    base_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn base_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(var).map(PathBuf::from)
+        && dir.is_absolute()
+    {
+        return Some(dir);
+    }
+    let home = std::env::var_os("HOME").filter(|h| !h.is_empty())?;
+    Some(PathBuf::from(home).join(fallback))
+}
+
+/// Default `paths.work_dir`: `~/.cache/quack-check/work`, or `.quack-check-work` when
+/// there is no home directory.
+pub fn work_dir() -> PathBuf {
+    cache_home()
+        .map(|d| d.join(APP_DIR).join("work"))
+        .unwrap_or_else(|| PathBuf::from(".quack-check-work"))
+}
+
+/// Default `paths.cache_dir`: `~/.cache/quack-check/cache`, or `.quack-check-cache`.
+pub fn cache_dir() -> PathBuf {
+    cache_home()
+        .map(|d| d.join(APP_DIR).join("cache"))
+        .unwrap_or_else(|| PathBuf::from(".quack-check-cache"))
+}
+
+/// Default `paths.docling_artifacts_dir`: `~/.local/share/quack-check/docling-artifacts`,
+/// only once it exists. Otherwise docling finds its models in the Hugging Face cache.
+pub fn artifacts_dir() -> Option<PathBuf> {
+    data_home()
+        .map(|d| d.join(APP_DIR).join("docling-artifacts"))
+        .filter(|d| d.is_dir())
+}
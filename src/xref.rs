@@ -0,0 +1,238 @@
+use crate::postprocess::{is_marker_line, parse_page_marker};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Counts from the cross-reference pass, recorded under `xref` in the report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct XrefStats {
+    pub targets: usize,
+    pub resolved: usize,
+    pub unresolved: usize,
+}
+
+/// Turn internal references ("see Chapter 5", "Figure 3.2", "page 14") into markdown
+/// links pointing at the matching heading, figure/table caption, or page.
+///
+/// Headings link to their GitHub-style slug; captions and pages get an explicit
+/// `<a id="..."></a>` anchor line. Page references only resolve when the input still
+/// carries `<!-- quack:page N -->` markers. Headings, captions, code blocks, and text
+/// already inside a link are never rewritten.
+pub fn resolve_cross_references(md: &str) -> (String, XrefStats) {
+    let heading_re = Regex::new(r"^(#{1,6})\s+(.+?)\s*#*\s*$").expect("heading regex");
+    let heading_label_re = Regex::new(
+        r"(?i)^(?:(chapter|section|part|appendix)\s+)?([0-9]+(?:\.[0-9]+)*|[A-Z]|[IVXLC]+)\b[.:]?",
+    )
+    .expect("heading label regex");
+    // A caption needs punctuation after the number so "Figure 3 shows..." stays a reference.
+    let caption_re = Regex::new(
+        r"(?i)^[\s*_]*(figure|fig\.|table)\s+([0-9]+(?:\.[0-9]+)*)[*_]*\s*(?:[.:|—–-]|$)",
+    )
+    .expect("caption regex");
+    let ref_re = Regex::new(
+        r"(?i)\b(chapter|section|part|appendix|figure|fig\.|table)\s+([0-9]+(?:\.[0-9]+)*|[A-Z])\b",
+    )
+    .expect("reference regex");
+    let page_ref_re = Regex::new(r"(?i)\b(?:pages?|pp?\.)\s+(\d+)\b").expect("page regex");
+    let link_re = Regex::new(r"\[[^\]]*\]\([^)]*\)").expect("link regex");
+
+    let lines: Vec<&str> = md.lines().collect();
+
+    // Pass 1: collect targets.
+    let mut targets: HashMap<(String, String), String> = HashMap::new();
+    let mut caption_anchors: BTreeMap<usize, String> = BTreeMap::new();
+    let mut pages: BTreeSet<u32> = BTreeSet::new();
+    let mut slugs: HashMap<String, usize> = HashMap::new();
+    let mut in_code = false;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some(page) = parse_page_marker(line) {
+            pages.insert(page);
+            continue;
+        }
+        if let Some(caps) = heading_re.captures(line) {
+            let title = caps[2].to_string();
+            let slug = unique_slug(&mut slugs, &title);
+            if let Some(label) = heading_label_re.captures(&title) {
+                let number = label[2].to_ascii_lowercase();
+                match label.get(1).map(|m| m.as_str().to_ascii_lowercase()) {
+                    Some(kind) => {
+                        targets.entry((kind, number)).or_insert(slug);
+                    }
+                    None if number.chars().all(|c| c.is_ascii_digit() || c == '.') => {
+                        if !number.contains('.') {
+                            targets
+                                .entry(("chapter".into(), number.clone()))
+                                .or_insert_with(|| slug.clone());
+                        }
+                        targets.entry(("section".into(), number)).or_insert(slug);
+                    }
+                    None => {}
+                }
+            }
+            continue;
+        }
+        if let Some(caps) = caption_re.captures(line) {
+            let kind = ref_kind(&caps[1]);
+            let number = caps[2].to_string();
+            let anchor = format!("{kind}-{}", number.replace('.', "-"));
+            if !targets.contains_key(&(kind.to_string(), number.clone())) {
+                targets.insert((kind.to_string(), number), anchor.clone());
+                caption_anchors.insert(idx, anchor);
+            }
+        }
+    }
+
+    let mut stats = XrefStats {
+        targets: targets.len(),
+        ..Default::default()
+    };
+
+    // Pass 2: rewrite references.
+    let mut linked_pages: BTreeSet<u32> = BTreeSet::new();
+    let mut rewritten: Vec<String> = Vec::with_capacity(lines.len());
+    in_code = false;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            rewritten.push(line.to_string());
+            continue;
+        }
+        if in_code
+            || is_marker_line(line)
+            || heading_re.is_match(line)
+            || caption_anchors.contains_key(&idx)
+            || caption_re.is_match(line)
+        {
+            rewritten.push(line.to_string());
+            continue;
+        }
+
+        let links: Vec<(usize, usize)> = link_re
+            .find_iter(line)
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        let inside_link =
+            |start: usize, end: usize| links.iter().any(|&(s, e)| start < e && end > s);
+
+        let mut spans: Vec<(usize, usize, String)> = Vec::new();
+        for caps in ref_re.captures_iter(line) {
+            let whole = caps.get(0).expect("capture 0");
+            if inside_link(whole.start(), whole.end()) {
+                continue;
+            }
+            let kind = ref_kind(&caps[1]);
+            let number = caps[2].to_ascii_lowercase();
+            if kind != "appendix" && !number.starts_with(|c: char| c.is_ascii_digit()) {
+                continue;
+            }
+            match targets.get(&(kind.to_string(), number)) {
+                Some(anchor) => {
+                    spans.push((whole.start(), whole.end(), format!("#{anchor}")));
+                    stats.resolved += 1;
+                }
+                None => stats.unresolved += 1,
+            }
+        }
+        for caps in page_ref_re.captures_iter(line) {
+            let whole = caps.get(0).expect("capture 0");
+            if inside_link(whole.start(), whole.end())
+                || spans
+                    .iter()
+                    .any(|&(s, e, _)| whole.start() < e && whole.end() > s)
+            {
+                continue;
+            }
+            let Ok(page) = caps[1].parse::<u32>() else {
+                continue;
+            };
+            if pages.contains(&page) {
+                spans.push((whole.start(), whole.end(), format!("#page-{page}")));
+                linked_pages.insert(page);
+                stats.resolved += 1;
+            } else if !pages.is_empty() {
+                stats.unresolved += 1;
+            }
+        }
+
+        if spans.is_empty() {
+            rewritten.push(line.to_string());
+            continue;
+        }
+        spans.sort_by_key(|s| s.0);
+        let mut out = String::with_capacity(line.len() + spans.len() * 16);
+        let mut last = 0;
+        for (start, end, href) in spans {
+            out.push_str(&line[last..start]);
+            out.push_str(&format!("[{}]({href})", &line[start..end]));
+            last = end;
+        }
+        out.push_str(&line[last..]);
+        rewritten.push(out);
+    }
+
+    // Pass 3: insert anchors for captions and referenced pages.
+    let mut out = String::with_capacity(md.len());
+    for (idx, line) in rewritten.iter().enumerate() {
+        if let Some(anchor) = caption_anchors.get(&idx) {
+            out.push_str(&format!("<a id=\"{anchor}\"></a>\n"));
+        }
+        out.push_str(line);
+        out.push('\n');
+        if let Some(page) = parse_page_marker(lines[idx])
+            && linked_pages.contains(&page)
+        {
+            out.push_str(&format!("<a id=\"page-{page}\"></a>\n"));
+        }
+    }
+    if !md.ends_with('\n') {
+        out.pop();
+    }
+
+    (out, stats)
+}
+
+fn ref_kind(word: &str) -> &'static str {
+    match word.to_ascii_lowercase().as_str() {
+        "chapter" => "chapter",
+        "section" => "section",
+        "part" => "part",
+        "appendix" => "appendix",
+        "table" => "table",
+        _ => "figure",
+    }
+}
+
+/// GitHub-style heading slug, suffixed `-1`, `-2`, ... on repeats.
+fn unique_slug(seen: &mut HashMap<String, usize>, title: &str) -> String {
+    let base: String = title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c)
+            } else if c == ' ' {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect();
+    let count = seen.entry(base.clone()).or_insert(0);
+    let slug = if *count == 0 {
+        base.clone()
+    } else {
+        format!("{base}-{count}")
+    };
+    *count += 1;
+    slug
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use quack_check::{
+    accelerator::preflight,
+    cancel::{CancellationToken, JobControl},
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DeviceCheck, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    policy::{decision_for, QualityTier},
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A 20-page scan on a machine whose GPU is busy; records each conversion's overrides.
+#[derive(Default)]
+struct BusyGpuEngine {
+    converted: Arc<Mutex<Vec<ConvertIn>>>,
+}
+
+impl Engine for BusyGpuEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.converted.lock().unwrap().push(req.clone());
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        Ok(DeviceCheck {
+            device: device.into(),
+            available: false,
+            detail: Some("CUDA error: all CUDA-capable devices are busy".into()),
+        })
+    }
+}
+
+fn gpu_config(on_unavailable: &str) -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.docling.accelerator.device = "CUDA".into();
+    cfg.docling.accelerator.on_unavailable = on_unavailable.into();
+    cfg
+}
+
+#[test]
+fn an_unavailable_gpu_falls_back_to_cpu_in_smaller_chunks() {
+    let dir = std::env::temp_dir().join(format!("quack-accelerator-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = gpu_config("cpu");
+    cfg.docling.accelerator.cpu_max_pages_per_chunk = 5;
+
+    let engine = BusyGpuEngine::default();
+    let converted = engine.converted.clone();
+    let out = Pipeline::new(&cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let used = out.report.accelerator.unwrap();
+    assert_eq!(
+        (used.requested.as_str(), used.used.as_str()),
+        ("CUDA", "CPU")
+    );
+    assert!(used.reason.unwrap().contains("busy"));
+    let converted = converted.lock().unwrap();
+    assert_eq!(converted.len(), 4);
+    assert!(converted
+        .iter()
+        .all(|req| req.pipeline_overrides.get("accelerator.device") == Some(&"CPU".into())));
+
+    let err = Pipeline::new(&gpu_config("fail"), BusyGpuEngine::default())
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .err()
+        .unwrap();
+    assert!(
+        format!("{err:#}").contains("CUDA is unavailable"),
+        "{err:#}"
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn waiting_gives_up_or_stops_when_cancelled() {
+    let engine = BusyGpuEngine::default();
+    let mut cfg = gpu_config("wait");
+    cfg.docling.accelerator.wait_max_seconds = 0;
+    let mut decision = decision_for(&cfg, QualityTier::Scan);
+    let err = preflight(&cfg, &engine, &mut decision, &JobControl::default()).unwrap_err();
+    assert!(err.to_string().contains("still unavailable"), "{err}");
+
+    cfg.docling.accelerator.wait_max_seconds = 600;
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let control = JobControl::new(cancel, None);
+    let err = preflight(&cfg, &engine, &mut decision, &control).unwrap_err();
+    assert!(err.to_string().contains("cancelled"), "{err}");
+
+    // AUTO leaves the choice to docling, so there is nothing to check.
+    cfg.docling.accelerator.device = "AUTO".into();
+    assert_eq!(
+        preflight(&cfg, &engine, &mut decision, &control).unwrap(),
+        None
+    );
+    assert!(decision.pipeline_overrides.is_empty());
+}
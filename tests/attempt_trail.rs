@@ -0,0 +1,157 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    attempts::{trail_comment, Attempt},
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    failure,
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+
+/// Two five-page chunks. Pages 6-10 fail under every backend: DLPARSE_V4 crashes out of
+/// memory, PYPDFIUM2 reports failure. Pages 1-5 only convert with PYPDFIUM2.
+struct StubbornEngine;
+
+impl Engine for StubbornEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 10,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        if req.pdf_backend == "DLPARSE_V4" {
+            return Err(anyhow!(
+                "python script failed: docling_runner.py (exit status: 1)\n\
+                 Traceback (most recent call last):\n  File \"x.py\"\nMemoryError"
+            ));
+        }
+        let ok = req.start_page == 1;
+        Ok(ConvertOut {
+            ok,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: if ok {
+                vec![]
+            } else {
+                vec!["docling error: page 7 has no content stream".into()]
+            },
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str, fraction: f64) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-attempts-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.chunking.oom_min_chunk_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+    cfg.limits.allow_failed_chunk_fraction = fraction;
+    cfg.docling.backend.pdf_backend = vec!["DLPARSE_V4".into(), "PYPDFIUM2".into()];
+    (cfg, dir)
+}
+
+#[test]
+fn a_failed_chunk_keeps_every_attempt_in_its_placeholder_and_report() {
+    let (cfg, dir) = setup("degraded", 0.5);
+    let out = Pipeline::new(&cfg, StubbornEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // The chunk that recovered lists the backend that failed first.
+    let recovered = &out.report.chunk_reports[0].attempts;
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].pdf_backend.as_deref(), Some("DLPARSE_V4"));
+
+    let attempts = &out.report.chunk_reports[1].attempts;
+    assert_eq!(attempts.len(), 2, "{attempts:?}");
+    assert_eq!(attempts[0].engine, "docling");
+    assert_eq!(attempts[0].pages, "6-10");
+    assert_eq!(attempts[0].error_class, "out_of_memory");
+    assert_eq!(
+        attempts[0].error,
+        "python script failed: docling_runner.py (exit status: 1)"
+    );
+    assert!(attempts[0]
+        .stderr_excerpt
+        .as_deref()
+        .unwrap()
+        .ends_with("MemoryError"));
+    assert_eq!(attempts[1].pdf_backend.as_deref(), Some("PYPDFIUM2"));
+    assert_eq!(attempts[1].error_class, "conversion_failed");
+    assert_eq!(
+        attempts[1].error,
+        "docling error: page 7 has no content stream"
+    );
+
+    let md = &out.markdown;
+    let comment = md.find("<!-- 2 failed conversion attempt(s):").expect(md);
+    assert!(md[comment..].contains(
+        "1. docling, pdf_backend DLPARSE_V4, pages 6-10: out_of_memory: python script failed"
+    ));
+    assert!(md[comment..].contains("   | MemoryError"));
+    assert!(md[comment..].contains("2. docling, pdf_backend PYPDFIUM2, pages 6-10"));
+}
+
+#[test]
+fn a_failed_job_reports_the_attempts() {
+    let (cfg, dir) = setup("failed", 0.0);
+    let input = dir.join("in.pdf");
+    let job_dir = dir.join("job");
+    let err = Pipeline::new(&cfg, StubbornEngine)
+        .run_job(&input, &job_dir)
+        .err()
+        .unwrap();
+    let report = failure::describe(&cfg, &job_dir, "abc", &input, &err, "quack-check run");
+    let _ = std::fs::remove_dir_all(&dir);
+    let classes: Vec<&str> = report
+        .attempts
+        .iter()
+        .map(|a| a.error_class.as_str())
+        .collect();
+    assert_eq!(classes, ["out_of_memory", "conversion_failed"]);
+}
+
+#[test]
+fn trail_comments_cannot_be_closed_early() {
+    let attempt = Attempt {
+        engine: "native_text".into(),
+        pdf_backend: None,
+        pages: "1-5".into(),
+        error_class: "engine_error".into(),
+        error: "pdf_text.py failed --> badly".into(),
+        stderr_excerpt: Some("<!-- nested -->".into()),
+    };
+    let comment = trail_comment(&[attempt]);
+    assert!(comment.starts_with("<!-- 1 failed conversion attempt(s):\n1. native_text, pages"));
+    assert!(comment.ends_with("\n-->"));
+    assert_eq!(comment.matches("-->").count(), 1, "{comment}");
+}
@@ -0,0 +1,119 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Config, DoclingBackend},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+};
+use std::path::Path;
+
+/// Fails every backend except `works_with`.
+struct PickyEngine {
+    works_with: &'static str,
+    last: &'static str,
+}
+
+impl Engine for PickyEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(
+        &self,
+        _input: &Path,
+        _sample: &quack_check::config::Classification,
+    ) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        // Only the last backend may fall back to docling's default when unavailable.
+        assert_eq!(req.require_pdf_backend, req.pdf_backend != self.last);
+        if req.pdf_backend != self.works_with {
+            return Err(anyhow!("{} cannot parse this file", req.pdf_backend));
+        }
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Recovered text.".into(),
+            warnings: vec![],
+            meta: ConvertMeta {
+                pdf_backend: Some(req.pdf_backend.clone()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str, backends: &[&str]) -> (Config, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-backend-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.docling.backend.pdf_backend = backends.iter().map(|b| b.to_string()).collect();
+    (cfg, dir)
+}
+
+#[test]
+fn retries_chunk_with_next_backend() {
+    let (cfg, dir) = setup("retry", &["DLPARSE_V4", "DLPARSE_V2", "PYPDFIUM2"]);
+    let engine = PickyEngine {
+        works_with: "DLPARSE_V2",
+        last: "PYPDFIUM2",
+    };
+    let pipeline = Pipeline::new(&cfg, engine);
+    let out = pipeline
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+
+    let chunk = &out.report.chunk_reports[0];
+    assert_eq!(chunk.pdf_backend.as_deref(), Some("DLPARSE_V2"));
+    assert!(chunk
+        .warnings
+        .iter()
+        .any(|w| w.contains("DLPARSE_V4 failed")));
+    assert!(out.markdown.contains("Recovered text."));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fails_after_last_backend() {
+    let (cfg, dir) = setup("exhausted", &["DLPARSE_V4", "PYPDFIUM2"]);
+    let engine = PickyEngine {
+        works_with: "DLPARSE_V1",
+        last: "PYPDFIUM2",
+    };
+    let pipeline = Pipeline::new(&cfg, engine);
+    let Err(err) = pipeline.run_job(&dir.join("in.pdf"), &dir.join("job")) else {
+        panic!("conversion should fail once every backend has failed");
+    };
+    assert!(format!("{err:#}").contains("PYPDFIUM2 cannot parse"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn accepts_single_backend_string() {
+    let one: DoclingBackend = toml::from_str("pdf_backend = \"DLPARSE_V2\"").unwrap();
+    assert_eq!(one.pdf_backend, vec!["DLPARSE_V2"]);
+    let many: DoclingBackend =
+        toml::from_str("pdf_backend = [\"DLPARSE_V4\", \"PYPDFIUM2\"]").unwrap();
+    assert_eq!(many.pdf_backend, vec!["DLPARSE_V4", "PYPDFIUM2"]);
+}
@@ -0,0 +1,88 @@
+use quack_check::{
+    batch::{discover_inputs, BatchEntry, BatchSummary, Deduplicator},
+    config::Batch,
+    dedup::{signature, similarity},
+};
+use std::path::Path;
+
+const NOTICE: &str = "This notice is provided to all residents of the county regarding the \
+    scheduled maintenance of the water treatment facility on the first Monday of each month. \
+    Service interruptions may occur between the hours of nine and noon. Please store water \
+    in advance and contact the utilities office with any questions about the schedule.";
+
+#[test]
+fn minhash_separates_near_duplicates_from_different_text() {
+    let rescan = NOTICE.replace("nine and noon", "nine and no0n");
+    let other = "Quarterly earnings rose on strong demand for industrial equipment, while \
+        operating margins narrowed as raw material costs climbed through the second half.";
+
+    let a = signature(NOTICE, 5, 128);
+    assert_eq!(a, signature(NOTICE, 5, 128), "signatures are deterministic");
+    assert!(similarity(&a, &signature(&rescan, 5, 128)) > 0.7);
+    assert!(similarity(&a, &signature(other, 5, 128)) < 0.1);
+    assert_eq!(
+        similarity(&signature("", 5, 16), &signature("", 5, 16)),
+        0.0
+    );
+}
+
+#[test]
+fn deduplicator_flags_exact_and_near_duplicates() {
+    let cfg = Batch {
+        near_duplicate_threshold: 0.6,
+        ..Batch::default()
+    };
+    let mut dedup = Deduplicator::new(&cfg).unwrap();
+
+    assert!(dedup.exact_duplicate("h1").is_none());
+    dedup.record_input("h1", Path::new("a.pdf"));
+    assert_eq!(dedup.exact_duplicate("h1").unwrap(), Path::new("a.pdf"));
+
+    assert!(dedup.near_duplicate(Path::new("a.pdf"), NOTICE).is_none());
+    let rescan = NOTICE.replace("Monday", "Mondav");
+    let (original, sim) = dedup.near_duplicate(Path::new("b.pdf"), &rescan).unwrap();
+    assert_eq!(original, Path::new("a.pdf"));
+    assert!(sim >= 0.6);
+
+    let off = Batch {
+        dedup: "off".into(),
+        ..Batch::default()
+    };
+    let mut dedup = Deduplicator::new(&off).unwrap();
+    dedup.record_input("h1", Path::new("a.pdf"));
+    assert!(dedup.exact_duplicate("h1").is_none());
+    assert!(dedup.near_duplicate(Path::new("a.pdf"), NOTICE).is_none());
+
+    let bad = Batch {
+        dedup: "fuzzy".into(),
+        ..Batch::default()
+    };
+    assert!(Deduplicator::new(&bad).is_err());
+}
+
+#[test]
+fn discovers_pdfs_recursively_and_counts_statuses() {
+    let dir = std::env::temp_dir().join(format!("quack-batch-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    std::fs::write(dir.join("b.pdf"), b"%PDF").unwrap();
+    std::fs::write(dir.join("nested").join("a.PDF"), b"%PDF").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"x").unwrap();
+
+    let inputs = discover_inputs(&dir).unwrap();
+    assert_eq!(
+        inputs,
+        vec![dir.join("b.pdf"), dir.join("nested").join("a.PDF")]
+    );
+
+    let summary = BatchSummary::new(
+        &dir,
+        vec![
+            BatchEntry::ok(&inputs[0], "j1", &dir.join("j1")),
+            BatchEntry::duplicate(&inputs[1], "j1", &dir.join("j1"), &inputs[0]),
+        ],
+    );
+    assert_eq!(summary.counts["ok"], 1);
+    assert_eq!(summary.counts["duplicate"], 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,113 @@
+use quack_check::{
+    batch::{longest_output_path, OutputNames},
+    config::{Batch, Config, Mirror},
+    template::{for_job_as, rename_input},
+};
+use std::path::{Path, PathBuf};
+
+fn inputs() -> Vec<PathBuf> {
+    ["in/a/scan.pdf", "in/b/Scan.pdf", "in/b/letter.pdf"]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[test]
+fn colliding_stems_get_a_deterministic_hash_suffix() {
+    let inputs = inputs();
+    let names = OutputNames::new(&Batch::default(), Path::new("in"), &inputs).unwrap();
+    let short = |_: &str| Ok(PathBuf::from("out/x"));
+    let a = names.stem_for(&inputs[0], short).unwrap().unwrap();
+    let b = names.stem_for(&inputs[1], short).unwrap().unwrap();
+    assert!(
+        a.starts_with("scan-") && a.len() == "scan-".len() + 8,
+        "{a}"
+    );
+    assert!(b.starts_with("Scan-") && a[5..] != b[5..], "{a} {b}");
+    assert_eq!(names.stem_for(&inputs[2], short).unwrap(), None);
+    assert_eq!(
+        names.stem_for(Path::new("elsewhere.pdf"), short).unwrap(),
+        None
+    );
+
+    // The suffix hashes the relative path, whatever else the batch holds.
+    let alone = OutputNames::new(
+        &Batch::default(),
+        Path::new("in"),
+        &[inputs[0].clone(), PathBuf::from("in/c/scan.pdf")],
+    )
+    .unwrap();
+    assert_eq!(alone.stem_for(&inputs[0], short).unwrap(), Some(a));
+
+    let off = Batch {
+        disambiguate_stems: false,
+        ..Batch::default()
+    };
+    let names = OutputNames::new(&off, Path::new("in"), &inputs).unwrap();
+    assert_eq!(names.stem_for(&inputs[0], short).unwrap(), None);
+}
+
+#[test]
+fn the_renamed_stem_reaches_paths_and_filenames() {
+    let mut vars = quack_check::template::job_vars(Path::new("in/a/scan.pdf.gz"), "abc");
+    rename_input(&mut vars, "scan.pdf-1234abcd");
+    assert_eq!(vars["input_name"], "scan.pdf-1234abcd.gz");
+
+    let mut cfg = Config::default();
+    cfg.paths.out_dir = "out/{input_stem}".into();
+    cfg.output.markdown_filename = "{input_name}.md".into();
+    let job = for_job_as(
+        &cfg,
+        Path::new("in/a/scan.pdf"),
+        "abc",
+        Some("scan-1234abcd"),
+    )
+    .unwrap();
+    assert_eq!(job.paths.out_dir, "out/scan-1234abcd");
+    assert_eq!(job.output.markdown_filename, "scan-1234abcd.pdf.md");
+}
+
+#[test]
+fn long_stems_are_shortened_to_fit_or_fail() {
+    let stem = "a".repeat(120);
+    let input = PathBuf::from(format!("in/{stem}.pdf"));
+    let mut cfg = Config::default();
+    cfg.paths.out_dir = "/archive/{input_stem}".into();
+    cfg.output.mirrors = vec![Mirror {
+        path: "/mirror/{tier}/{input_stem}/{input_stem}".into(),
+        mode: "copy".into(),
+    }];
+    let longest = |s: &str| {
+        let job = for_job_as(&cfg, &input, "job1", Some(s))?;
+        Ok(longest_output_path(&job, &input, "job1", s))
+    };
+    assert!(longest(&stem)
+        .unwrap()
+        .starts_with(format!("/mirror/{{tier}}/{stem}/{stem}")));
+
+    let batch = Batch {
+        max_path_length: 200,
+        ..Batch::default()
+    };
+    let names = OutputNames::new(&batch, Path::new("in"), std::slice::from_ref(&input)).unwrap();
+    let short = names.stem_for(&input, longest).unwrap().unwrap();
+    assert!(
+        short.starts_with("aaaa") && short.len() < stem.len(),
+        "{short}"
+    );
+    assert!(longest(&short).unwrap().as_os_str().len() <= 200);
+
+    let fail = Batch {
+        long_paths: "fail".into(),
+        ..batch
+    };
+    let names = OutputNames::new(&fail, Path::new("in"), std::slice::from_ref(&input)).unwrap();
+    let err = names.stem_for(&input, longest).unwrap_err().to_string();
+    assert!(err.contains("batch.max_path_length 200"), "{err}");
+
+    let unknown = Batch {
+        long_paths: "truncate".into(),
+        ..Batch::default()
+    };
+    assert!(OutputNames::new(&unknown, Path::new("in"), &[]).is_err());
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use quack_check::{
+    blank_pages::{handle_blank_pages, is_blank},
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+const MD: &str = "<!-- quack:page 1 -->\n\nText one.\n\n<!-- quack:page 2 -->\n\n12\n\n<!-- quack:page 3 -->\n\n<!-- quack:page 4 -->\n\nText four.\n";
+
+#[test]
+fn near_blank_means_no_image_and_few_characters() {
+    assert!(is_blank("", 3));
+    assert!(is_blank("- 12 -\n\n<!-- page footer -->", 3));
+    assert!(is_blank("xii", 3));
+    assert!(!is_blank("Page 12", 3));
+    assert!(!is_blank("<!-- image -->", 3));
+    assert!(!is_blank("![plate](images/plate.png)", 3));
+}
+
+#[test]
+fn blank_pages_are_kept_dropped_marked_or_collapsed() {
+    let all: BTreeSet<u32> = (1..=4).collect();
+    let (kept, stats) = handle_blank_pages(MD, "keep", 3, &all).unwrap();
+    assert_eq!(kept, MD);
+    assert_eq!(stats.pages, [2, 3]);
+
+    let (dropped, _) = handle_blank_pages(MD, "drop", 3, &all).unwrap();
+    assert!(!dropped.contains("12"), "{dropped}");
+    assert!(dropped.contains("<!-- quack:page 3 -->"), "{dropped}");
+
+    let (marked, _) = handle_blank_pages(MD, "marker", 3, &all).unwrap();
+    let expected = "<!-- quack:page 2 -->\n\n<!-- blank page 2 -->\n\n\
+                    <!-- quack:page 3 -->\n\n<!-- blank page 3 -->";
+    assert!(marked.contains(expected), "{marked}");
+
+    let (collapsed, _) = handle_blank_pages(MD, "collapse", 3, &all).unwrap();
+    assert!(
+        collapsed.contains("<!-- blank pages 2-3 -->"),
+        "{collapsed}"
+    );
+    assert!(!collapsed.contains("<!-- blank page 3 -->"), "{collapsed}");
+
+    // Pages the engine did not mark out individually are never blank.
+    let (_, stats) = handle_blank_pages(MD, "drop", 3, &BTreeSet::from([1, 2, 4])).unwrap();
+    assert_eq!(stats.pages, [2]);
+
+    let err = handle_blank_pages(MD, "hide", 3, &all)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("unknown postprocess.blank_pages"), "{err}");
+}
+
+/// Five pages per chunk with a page-numbered blank verso after every text page.
+struct VersoEngine;
+
+impl Engine for VersoEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 10,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| {
+                if p % 2 == 0 {
+                    format!("{p}")
+                } else {
+                    format!("Text of page {p}.")
+                }
+            })
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn the_pipeline_reports_and_marks_blank_versos() {
+    let dir = std::env::temp_dir().join(format!("quack-blank-pages-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+    cfg.postprocess.blank_pages = "marker".into();
+
+    let out = Pipeline::new(&cfg, VersoEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(out.report.blank_pages.pages, [2, 4, 6, 8, 10]);
+    assert!(out.markdown.contains("Text of page 9."), "{}", out.markdown);
+    assert!(
+        out.markdown.contains("<!-- blank page 6 -->"),
+        "{}",
+        out.markdown
+    );
+    assert!(!out.markdown.contains("\n6\n"), "{}", out.markdown);
+    assert!(out.text.contains("<!-- blank page 4 -->"), "{}", out.text);
+}
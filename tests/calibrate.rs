@@ -0,0 +1,74 @@
+use quack_check::{
+    calibrate::{calibrate, read_labels, LabeledSample},
+    config::Config,
+    policy::QualityTier,
+    probe::ProbeSampleStats,
+};
+
+fn mk(avg: u32, garbage: f32, ws: f32, expected: QualityTier) -> LabeledSample {
+    LabeledSample {
+        path: format!("{avg}.pdf").into(),
+        expected,
+        sample: ProbeSampleStats {
+            sampled_pages: 10,
+            avg_chars_per_page: avg,
+            garbage_ratio: garbage,
+            whitespace_ratio: ws,
+        },
+    }
+}
+
+#[test]
+fn finds_thresholds_that_fit_labels() {
+    let cfg = Config::default();
+    // 900 chars/page is high text in this corpus, below the default 1200 cutoff.
+    let samples = vec![
+        mk(3000, 0.0, 0.2, QualityTier::HighText),
+        mk(900, 0.01, 0.3, QualityTier::HighText),
+        mk(400, 0.1, 0.3, QualityTier::MixedText),
+        mk(150, 0.0, 0.5, QualityTier::Scan),
+        mk(5, 0.0, 0.9, QualityTier::Scan),
+    ];
+    let result = calibrate(&cfg.classification, &samples);
+    assert_eq!(result.total, 5);
+    assert_eq!(result.baseline_errors, 2);
+    assert_eq!(result.errors, 0);
+    assert!(result.misclassified.is_empty());
+    assert!(result.to_toml().contains("[classification]"));
+}
+
+#[test]
+fn keeps_current_thresholds_when_already_correct() {
+    let cfg = Config::default();
+    let samples = vec![
+        mk(3000, 0.0, 0.2, QualityTier::HighText),
+        mk(10, 0.0, 0.9, QualityTier::Scan),
+    ];
+    let result = calibrate(&cfg.classification, &samples);
+    assert_eq!(result.errors, 0);
+    assert_eq!(
+        result.min_avg_chars_per_page_for_high_text,
+        cfg.classification.min_avg_chars_per_page_for_high_text
+    );
+    assert_eq!(
+        result.max_avg_chars_per_page_for_scan,
+        cfg.classification.max_avg_chars_per_page_for_scan
+    );
+}
+
+#[test]
+fn reads_labels_csv() {
+    let dir = std::env::temp_dir().join(format!("quack-calibrate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let csv = dir.join("labels.csv");
+    std::fs::write(&csv, "path,tier\n# comment\na.pdf,HIGH_TEXT\n/abs/b.pdf, scan\n").unwrap();
+    let labels = read_labels(&csv).unwrap();
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels[0].path, dir.join("a.pdf"));
+    assert_eq!(labels[0].tier, QualityTier::HighText);
+    assert_eq!(labels[1].tier, QualityTier::Scan);
+
+    std::fs::write(&csv, "a.pdf,SHINY\n").unwrap();
+    assert!(read_labels(&csv).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use quack_check::{
+    cancel::{interrupted, CancellationToken, Interrupted},
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A 20-page scan whose second chunk runs until the job is stopped, the way the
+/// Python engine waits on its child.
+struct SlowEngine {
+    converted: Arc<Mutex<Vec<u32>>>,
+}
+
+impl Engine for SlowEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.converted.lock().unwrap().push(req.chunk_index);
+        if req.chunk_index == 1 {
+            loop {
+                req.control.check()?;
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Text.".into(),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf, SlowEngine, Arc<Mutex<Vec<u32>>>) {
+    let dir = std::env::temp_dir().join(format!("quack-cancel-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    let converted = Arc::<Mutex<Vec<u32>>>::default();
+    let engine = SlowEngine {
+        converted: converted.clone(),
+    };
+    (cfg, dir, engine, converted)
+}
+
+#[test]
+fn a_cancelled_token_stops_the_job_before_converting() {
+    let (cfg, dir, engine, converted) = setup("before");
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let err = Pipeline::new(&cfg, engine)
+        .run_job_with(&dir.join("in.pdf"), &dir.join("job"), cancel, None)
+        .err()
+        .unwrap();
+    assert_eq!(interrupted(&err), Some(Interrupted::Cancelled));
+    assert!(converted.lock().unwrap().is_empty());
+}
+
+#[test]
+fn cancelling_stops_a_conversion_in_progress() {
+    let (cfg, dir, engine, converted) = setup("during");
+    let cancel = CancellationToken::new();
+    let canceller = {
+        let cancel = cancel.clone();
+        let converted = converted.clone();
+        std::thread::spawn(move || {
+            while converted.lock().unwrap().len() < 2 {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            cancel.cancel();
+        })
+    };
+    let err = Pipeline::new(&cfg, engine)
+        .run_job_with(&dir.join("in.pdf"), &dir.join("job"), cancel, None)
+        .err()
+        .unwrap();
+    canceller.join().unwrap();
+    assert_eq!(interrupted(&err), Some(Interrupted::Cancelled), "{err:#}");
+    assert_eq!(*converted.lock().unwrap(), [0, 1]);
+    assert!(!dir.join("job/final/transcript.md").exists());
+}
+
+#[test]
+fn a_deadline_bounds_the_job() {
+    let (cfg, dir, engine, _) = setup("deadline");
+    let started = Instant::now();
+    let err = Pipeline::new(&cfg, engine)
+        .run_job_with(
+            &dir.join("in.pdf"),
+            &dir.join("job"),
+            CancellationToken::new(),
+            Some(started + Duration::from_millis(100)),
+        )
+        .err()
+        .unwrap();
+    assert_eq!(interrupted(&err), Some(Interrupted::DeadlineExceeded));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(
+        format!("{err:#}").contains("job deadline exceeded"),
+        "{err:#}"
+    );
+}
@@ -0,0 +1,85 @@
+use quack_check::{
+    chapters::{has_few_headings, promote_chapter_titles},
+    config::Config,
+    postprocess::{merge_markdown, merge_markdown_with, page_marker, MergeOptions},
+};
+
+fn scanned_book() -> String {
+    [
+        page_marker(1),
+        String::new(),
+        "THE SILENT SEA".into(),
+        String::new(),
+        "A novel in three parts.".into(),
+        String::new(),
+        page_marker(2),
+        String::new(),
+        "CHAPTER IV".into(),
+        String::new(),
+        "The Storm".into(),
+        String::new(),
+        "It rained for a week. Nobody left the house.".into(),
+        String::new(),
+        page_marker(3),
+        String::new(),
+        "Chapter 5 describes how the rain stopped.".into(),
+        String::new(),
+        "NOTE".into(),
+    ]
+    .join("\n")
+}
+
+#[test]
+fn promotes_numbered_and_caps_titles() {
+    let (md, count) = promote_chapter_titles(&scanned_book());
+    assert_eq!(count, 2, "{md}");
+    assert!(md.contains("# THE SILENT SEA\n"), "{md}");
+    assert!(md.contains("# CHAPTER IV: The Storm\n\nIt rained"), "{md}");
+    // Prose that merely mentions a chapter, and a caps word mid-page, stay text.
+    assert!(md.contains("\nChapter 5 describes"), "{md}");
+    assert!(md.contains("\nNOTE"), "{md}");
+}
+
+#[test]
+fn repeated_caps_lines_are_running_headers() {
+    let md = [
+        page_marker(1),
+        String::new(),
+        "THE SILENT SEA".into(),
+        String::new(),
+        "Text.".into(),
+        page_marker(2),
+        String::new(),
+        "THE SILENT SEA".into(),
+        String::new(),
+        "More text.".into(),
+    ]
+    .join("\n");
+    let (out, count) = promote_chapter_titles(&md);
+    assert_eq!(count, 0);
+    assert_eq!(out, md);
+}
+
+#[test]
+fn only_documents_with_few_headings_qualify() {
+    assert!(has_few_headings("# One\n\ntext", 100));
+    assert!(!has_few_headings("# One\n\ntext\n\n# Two", 40));
+    assert!(!has_few_headings("```\n# not a heading\n```\n# Real", 10));
+}
+
+#[test]
+fn merge_infers_titles_before_regex_removal() {
+    let cfg = Config::default();
+    let parts = vec![scanned_book()];
+
+    let plain = merge_markdown(&cfg, parts.clone()).unwrap();
+    assert!(!plain.contains("THE SILENT SEA"), "default regex drops caps lines");
+
+    let opts = MergeOptions {
+        infer_chapter_titles: true,
+        page_count: 3,
+    };
+    let merged = merge_markdown_with(&cfg, parts, opts).unwrap();
+    assert!(merged.contains("# THE SILENT SEA"), "{merged}");
+    assert!(merged.contains("# CHAPTER IV: The Storm"), "{merged}");
+}
@@ -0,0 +1,36 @@
+#![cfg(unix)]
+
+use quack_check::{config::ChildLimits, engine::limits};
+use std::process::Command;
+
+#[test]
+fn cpu_limit_is_applied_and_reported() {
+    let limits = ChildLimits {
+        max_cpu_seconds: 1,
+        ..Default::default()
+    };
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "while :; do :; done"]);
+    limits::apply(&mut cmd, &limits).unwrap();
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+
+    let hits = limits::detect_hits(&limits, &output.status, "", None);
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].starts_with("cpu limit hit"));
+}
+
+#[test]
+fn memory_errors_are_reported_only_when_limited() {
+    let status = Command::new("true").status().unwrap();
+    let stderr = "Traceback ...\nMemoryError";
+    assert!(limits::detect_hits(&ChildLimits::default(), &status, stderr, None).is_empty());
+
+    let limited = ChildLimits {
+        max_rss_bytes: 1 << 30,
+        ..Default::default()
+    };
+    let hits = limits::detect_hits(&limited, &status, stderr, None);
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].starts_with("memory limit hit"));
+}
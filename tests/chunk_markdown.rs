@@ -0,0 +1,107 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+    postprocess::{split_by_chunks, PAGE_BREAK_PLACEHOLDER},
+};
+use std::path::Path;
+
+/// A 20-page scan with one line of text per page.
+struct PagedEngine;
+
+impl Engine for PagedEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| format!("Text of page {p}."))
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn chunk_markdown_is_written_next_to_the_transcript() {
+    let dir = std::env::temp_dir().join(format!("quack-chunk-markdown-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.postprocess.chunk_separator = "both".into();
+    cfg.output.write_chunk_markdown = true;
+
+    let job_dir = dir.join("job");
+    let out = Pipeline::new(&cfg, PagedEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+
+    let chunks = job_dir.join("final/chunks");
+    let first = std::fs::read_to_string(chunks.join("0001_pages-0001-0010.md")).unwrap();
+    let second = std::fs::read_to_string(chunks.join("0002_pages-0011-0020.md")).unwrap();
+    assert!(
+        first.starts_with("<!-- quack:chunk index=0 pages=1-10"),
+        "{first}"
+    );
+    assert!(first.contains("Text of page 10."), "{first}");
+    assert!(
+        !first.contains("page 11") && !first.contains("---"),
+        "{first}"
+    );
+    assert!(
+        second.starts_with("<!-- quack:chunk index=1 pages=11-20"),
+        "{second}"
+    );
+    assert!(second.trim_end().ends_with("Text of page 20."), "{second}");
+    assert!(!second.contains("quack:page"), "{second}");
+}
+
+#[test]
+fn split_by_chunks_follows_page_markers() {
+    let md = "<!-- quack:page 1 -->\n\nOne\n\n<!-- quack:page 2 -->\nTwo\n\n---\n\n<!-- quack:page 3 -->\n\nThree\n<!-- quack:page 9 -->\nStray";
+    let parts = split_by_chunks(md, &[(1, 2), (3, 4)]);
+    assert_eq!(
+        parts,
+        [
+            "<!-- quack:page 1 -->\n\nOne\n\n<!-- quack:page 2 -->\nTwo\n",
+            "<!-- quack:page 3 -->\n\nThree\n",
+        ]
+    );
+}
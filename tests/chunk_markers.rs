@@ -0,0 +1,196 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::{
+        chunk_marker, markdown_to_text, merge_markdown, parse_chunk_marker, ChunkMarker,
+    },
+};
+use std::path::{Path, PathBuf};
+
+fn marker(index: u32, start_page: u32, end_page: u32) -> ChunkMarker {
+    ChunkMarker {
+        index,
+        start_page,
+        end_page,
+        engine: "docling".into(),
+    }
+}
+
+#[test]
+fn chunk_markers_round_trip() {
+    let line = chunk_marker(&marker(3, 81, 120));
+    assert_eq!(
+        line,
+        "<!-- quack:chunk index=3 pages=81-120 engine=docling -->"
+    );
+    assert_eq!(parse_chunk_marker(&line), Some(marker(3, 81, 120)));
+    assert_eq!(
+        parse_chunk_marker("<!-- quack:chunk engine=docling pages=1-9 index=0 tier=SCAN -->"),
+        Some(marker(0, 1, 9))
+    );
+    assert_eq!(parse_chunk_marker("<!-- quack:page 4 -->"), None);
+    assert_eq!(
+        parse_chunk_marker("<!-- quack:chunk index=x pages=1-9 engine=docling -->"),
+        None
+    );
+}
+
+#[test]
+fn comment_separators_replace_rules_and_leave_the_text() {
+    let mut cfg = Config::default();
+    cfg.postprocess.chunk_separator = "comment".into();
+    let parts = vec![
+        format!("{}\n\nFirst.", chunk_marker(&marker(0, 1, 10))),
+        format!("{}\n\nSecond.", chunk_marker(&marker(1, 11, 20))),
+    ];
+    let merged = merge_markdown(&cfg, parts.clone()).unwrap();
+    assert!(!merged.contains("---"));
+    assert_eq!(
+        merged.lines().filter_map(parse_chunk_marker).count(),
+        2,
+        "{merged}"
+    );
+    assert_eq!(
+        markdown_to_text(&cfg, &merged).unwrap(),
+        "First.\n\nSecond."
+    );
+
+    cfg.postprocess.chunk_separator = "both".into();
+    assert!(merge_markdown(&cfg, parts.clone())
+        .unwrap()
+        .contains("\n---\n"));
+
+    cfg.postprocess.chunk_separator = "ruler".into();
+    let err = merge_markdown(&cfg, parts).unwrap_err().to_string();
+    assert!(
+        err.contains("unknown postprocess.chunk_separator: ruler"),
+        "{err}"
+    );
+}
+
+/// Native text fails for the second chunk, which falls back to docling.
+struct FallbackEngine;
+
+impl Engine for FallbackEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 3000,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Docling pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: req.chunk_index == 0,
+            markdown: format!("Native pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta {
+                engine: Some("native_text".into()),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir =
+        std::env::temp_dir().join(format!("quack-chunk-markers-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "HIGH_TEXT".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.postprocess.chunk_separator = "comment".into();
+    (cfg, dir)
+}
+
+#[test]
+fn the_transcript_records_each_chunks_pages_and_engine() {
+    let (cfg, dir) = setup("job");
+    let out = Pipeline::new(&cfg, FallbackEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let markers: Vec<ChunkMarker> = out
+        .markdown
+        .lines()
+        .filter_map(parse_chunk_marker)
+        .collect();
+    assert_eq!(
+        markers,
+        [
+            ChunkMarker {
+                engine: "native_text".into(),
+                ..marker(0, 1, 10)
+            },
+            marker(1, 11, 20),
+        ]
+    );
+    assert!(!out.text.contains("quack:chunk"));
+}
+
+#[test]
+fn the_provenance_appendix_lists_each_chunk_at_the_end() {
+    let (mut cfg, dir) = setup("appendix");
+    cfg.output.provenance_appendix = true;
+    let out = Pipeline::new(&cfg, FallbackEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let (body, appendix) = out
+        .markdown
+        .split_once("## Processing provenance\n")
+        .expect("appendix");
+    assert!(body.contains("Docling pages 11-20."), "{body}");
+    let entries: Vec<&str> = appendix.lines().filter(|l| l.starts_with("- ")).collect();
+    assert_eq!(entries.len(), 2, "{appendix}");
+    assert!(
+        entries[0].starts_with("- Chunk 1, pages 1-10: native_text, converted "),
+        "{appendix}"
+    );
+    assert!(
+        entries[1].starts_with("- Chunk 2, pages 11-20: docling"),
+        "{appendix}"
+    );
+    assert!(out.text.contains("Processing provenance"), "{}", out.text);
+    assert!(
+        out.text.contains("Chunk 2, pages 11-20: docling"),
+        "{}",
+        out.text
+    );
+
+    cfg.output.provenance_appendix = false;
+    let out = Pipeline::new(&cfg, FallbackEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .unwrap();
+    assert!(!out.markdown.contains("Processing provenance"));
+}
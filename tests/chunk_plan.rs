@@ -1,4 +1,8 @@
-use quack_check::{chunk_plan::ChunkPlan, config::Config};
+use quack_check::{
+    chunk_plan::{read_plan_json, ChunkPlan, PageRange},
+    config::Config,
+    engine::PageLayout,
+};
 
 #[test]
 fn chunk_plan_basic() {
@@ -8,3 +12,109 @@ fn chunk_plan_basic() {
     assert_eq!(plan.chunks[0].start_page, 1);
     assert_eq!(plan.chunks.last().unwrap().end_page, 101);
 }
+
+fn layout(page: u32, top: bool, bottom: bool) -> PageLayout {
+    PageLayout {
+        page,
+        table_at_top: top,
+        table_at_bottom: bottom,
+    }
+}
+
+#[test]
+fn seam_moves_off_a_table_break() {
+    let mut cfg = Config::default();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.min_pages_per_chunk = 1;
+    let mut plan = ChunkPlan::from_page_count(&cfg, 20);
+    assert_eq!(plan.seam_window_pages(2), vec![8, 9, 10, 11, 12, 13]);
+
+    // A table runs from page 10 onto 11 and 12; page 9 ends in prose.
+    let layouts: Vec<PageLayout> = (8..=13)
+        .map(|p| layout(p, (11..=12).contains(&p), (10..=11).contains(&p)))
+        .collect();
+    let moved = plan.avoid_table_splits(&cfg, &layouts);
+    assert_eq!(moved, vec![(10, 9)]);
+    assert_eq!(plan.chunks[0].end_page, 9);
+    assert_eq!(plan.chunks[1].start_page, 10);
+}
+
+#[test]
+fn seam_stays_without_clean_break_or_layout() {
+    let mut cfg = Config::default();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.min_pages_per_chunk = 1;
+    cfg.chunking.boundary_slack_pages = 1;
+
+    let mut plan = ChunkPlan::from_page_count(&cfg, 20);
+    let all_table: Vec<PageLayout> = (9..=12).map(|p| layout(p, true, true)).collect();
+    assert!(plan.avoid_table_splits(&cfg, &all_table).is_empty());
+    assert_eq!(plan.chunks[0].end_page, 10);
+
+    assert!(plan.avoid_table_splits(&cfg, &[]).is_empty());
+    assert_eq!(plan.chunks[0].end_page, 10);
+}
+
+fn plan(page_count: u32, ranges: &[(u32, u32)]) -> ChunkPlan {
+    ChunkPlan {
+        page_count,
+        chunks: ranges
+            .iter()
+            .map(|&(start_page, end_page)| PageRange {
+                start_page,
+                end_page,
+            })
+            .collect(),
+        strategy: "page_range".into(),
+    }
+}
+
+#[test]
+fn edited_plans_must_cover_every_page_once() {
+    assert!(plan(30, &[(1, 12), (13, 13), (14, 30)])
+        .validate(30)
+        .is_ok());
+
+    let cases = [
+        (plan(30, &[(1, 30)]), 31, "plan is for 30 page(s)"),
+        (plan(30, &[]), 30, "no chunks"),
+        (plan(30, &[(1, 10), (12, 30)]), 30, "gap between chunks"),
+        (plan(30, &[(1, 10), (10, 30)]), 30, "overlap between chunks"),
+        (
+            plan(30, &[(1, 10), (11, 9)]),
+            30,
+            "ends (page 9) before it starts",
+        ),
+        (plan(30, &[(1, 10), (11, 29)]), 30, "plan ends at page 29"),
+    ];
+    for (plan, page_count, expected) in cases {
+        let err = plan.validate(page_count).unwrap_err().to_string();
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
+#[test]
+fn plans_are_read_from_plan_files_and_job_manifests() {
+    let dir = std::env::temp_dir().join(format!("quack-plan-file-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let written = plan(20, &[(1, 7), (8, 20)]);
+
+    let path = dir.join("plan.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&written).unwrap()).unwrap();
+    let read = read_plan_json(&path).unwrap();
+    assert_eq!(read.chunks.len(), 2);
+    assert_eq!(read.chunks[1].start_page, 8);
+
+    let manifest = dir.join("job.json");
+    std::fs::write(
+        &manifest,
+        serde_json::json!({ "decision": {}, "plan": written }).to_string(),
+    )
+    .unwrap();
+    assert_eq!(read_plan_json(&manifest).unwrap().chunks[0].end_page, 7);
+
+    std::fs::write(&path, "{}").unwrap();
+    let err = format!("{:#}", read_plan_json(&path).unwrap_err());
+    assert!(err.contains("not a chunk plan"), "{err}");
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    config_hash::config_hash,
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::{chunk_record_file, ChunkRecord, Pipeline},
+    util::hash_file,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A 20-page scan converted by a docling that reports its version.
+struct VersionedEngine;
+
+impl Engine for VersionedEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let mut extra = serde_json::Map::new();
+        extra.insert("docling_version".into(), "2.31.0".into());
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta {
+                pdf_backend: Some(req.pdf_backend.clone()),
+                ocr_applied: Some(req.do_ocr),
+                extra,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn script_versions(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([("docling_runner.py".into(), "sha256:0123456789ab".into())])
+    }
+}
+
+#[test]
+fn chunk_records_carry_their_provenance() {
+    let dir = std::env::temp_dir().join(format!("quack-chunk-provenance-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.global.keep_intermediates = true;
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.docling.ocr.langs = vec!["deu".into(), "eng".into()];
+
+    let job_dir = dir.join("job");
+    Pipeline::new(&cfg, VersionedEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+
+    let input_hash = hash_file(&cfg, &dir.join("in.pdf")).unwrap();
+    let read = |i: u32| -> ChunkRecord {
+        let raw = std::fs::read_to_string(job_dir.join("chunks").join(chunk_record_file(i)));
+        serde_json::from_str(&raw.unwrap()).unwrap()
+    };
+    let second = read(1).provenance.unwrap();
+    assert_eq!(second.chunk_id, format!("{}:11-20", &input_hash[..16]));
+    assert_eq!(second.input_hash, input_hash);
+    assert_eq!(second.hash_mode, cfg.hashing.mode);
+    assert_eq!(second.engine, "docling");
+    assert_eq!(second.pdf_backend.as_deref(), Some("AUTO"));
+    assert!(second.do_ocr);
+    assert_eq!(second.ocr.unwrap().langs, ["deu", "eng"]);
+    assert_eq!(second.config_hash, config_hash(&cfg));
+    assert_eq!(second.versions["docling"], "2.31.0");
+    assert_eq!(second.versions["docling_runner.py"], "sha256:0123456789ab");
+    assert_eq!(second.versions["quack-check"], env!("CARGO_PKG_VERSION"));
+    assert!(second.started_at <= second.finished_at);
+    assert_eq!(
+        read(0).provenance.unwrap().chunk_id,
+        format!("{}:1-10", &input_hash[..16])
+    );
+}
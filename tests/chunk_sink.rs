@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, PageError, ProbeOut, SplitChunk,
+    },
+    pipeline::{Pipeline, StreamedChunk},
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Logs each conversion; page 15 is unreadable and chunk 2 carries an inline image.
+struct LoggingEngine {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Engine for LoggingEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 30,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("convert {}", req.chunk_index));
+        let mut markdown = format!("Pages {}-{}.", req.start_page, req.end_page);
+        if req.chunk_index == 2 {
+            markdown.push_str("\n\n![fig](data:image/png;base64,aGVsbG8=)");
+        }
+        let page_errors = (req.start_page..=req.end_page)
+            .filter(|p| *p == 15)
+            .map(|page| PageError {
+                page,
+                reason: "bad image".into(),
+            })
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown,
+            warnings: vec![],
+            meta: ConvertMeta {
+                use_page_range: Some(req.use_page_range),
+                page_errors,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+/// Three 10-page chunks converted by page range.
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-sink-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.limits.on_page_error = "placeholder".into();
+    cfg.postprocess.embedded_images = "externalize".into();
+    (cfg, dir)
+}
+
+#[test]
+fn chunks_reach_the_sink_before_later_chunks_convert() {
+    let (cfg, dir) = setup("order");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let engine = LoggingEngine { log: log.clone() };
+    let mut chunks: Vec<StreamedChunk> = Vec::new();
+    let out = Pipeline::new(&cfg, engine)
+        .run_with_sink(
+            &dir.join("in.pdf"),
+            &dir.join("job"),
+            &mut |chunk: StreamedChunk| {
+                log.lock()
+                    .unwrap()
+                    .push(format!("sink {}", chunk.chunk_index));
+                chunks.push(chunk);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "convert 0",
+            "sink 0",
+            "convert 1",
+            "sink 1",
+            "convert 2",
+            "sink 2"
+        ]
+    );
+    let ranges: Vec<(u32, u32)> = chunks.iter().map(|c| (c.start_page, c.end_page)).collect();
+    assert_eq!(ranges, [(1, 10), (11, 20), (21, 30)]);
+    assert!(chunks[0].markdown.contains("Pages 1-10."));
+
+    // Page errors are applied per chunk, as in the final transcript.
+    assert_eq!(chunks[1].page_errors[0].page, 15);
+    assert!(chunks[1]
+        .warnings
+        .iter()
+        .any(|w| w == "page 15 unreadable: bad image"));
+
+    // Externalized images keep the names they get in the final outputs.
+    assert_eq!(chunks[2].images.len(), 1);
+    assert_eq!(chunks[2].images[0].file_name, out.images[0].file_name);
+    assert!(chunks[2]
+        .markdown
+        .contains(&format!("images/{}", out.images[0].file_name)));
+}
+
+#[test]
+fn a_failing_sink_stops_the_job() {
+    let (cfg, dir) = setup("fail");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let engine = LoggingEngine { log: log.clone() };
+    let mut sink = |chunk: StreamedChunk| {
+        if chunk.chunk_index == 1 {
+            Err(anyhow!("index unavailable"))
+        } else {
+            Ok(())
+        }
+    };
+    let Err(err) =
+        Pipeline::new(&cfg, engine).run_with_sink(&dir.join("in.pdf"), &dir.join("job"), &mut sink)
+    else {
+        panic!("expected the job to fail");
+    };
+    let msg = format!("{err:#}");
+    assert!(msg.contains("chunk sink failed on chunk 1"), "{msg}");
+    assert!(msg.contains("index unavailable"), "{msg}");
+    assert_eq!(*log.lock().unwrap(), ["convert 0", "convert 1"]);
+}
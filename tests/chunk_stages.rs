@@ -0,0 +1,122 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{ChunkStage, Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::path::Path;
+
+/// Ten scanned pages; each chunk starts with a scanner banner and numbers its figures
+/// from 1.
+struct BannerEngine;
+
+impl Engine for BannerEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 10,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| format!("Text of page {p}."))
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!(
+                "SCANNED BY ACME\n\nFigure 1: a map.\n\n{}",
+                pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n"))
+            ),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn config(stages: Vec<ChunkStage>) -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+    cfg.postprocess.remove_by_regex = false;
+    cfg.postprocess.chunk_stages = stages;
+    cfg
+}
+
+fn stage(kind: &str, pattern: &str, replacement: &str) -> ChunkStage {
+    ChunkStage {
+        kind: kind.into(),
+        pattern: pattern.into(),
+        replacement: replacement.into(),
+        engines: vec![],
+        content_types: vec![],
+    }
+}
+
+#[test]
+fn chunk_stages_see_each_chunk_before_merging() {
+    let dir = std::env::temp_dir().join(format!("quack-chunk-stages-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+
+    let mut skipped = stage("drop_lines", "^Text of page", "");
+    skipped.engines = vec!["native_text".into()];
+    let cfg = config(vec![
+        stage("drop_lines", "^SCANNED BY", ""),
+        stage("replace", r"^Figure (\d+):", "Figure {start_page}.$1:"),
+        skipped,
+    ]);
+    let out = Pipeline::new(&cfg, BannerEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let md = &out.markdown;
+    assert!(!md.contains("SCANNED BY"), "{md}");
+    assert!(md.contains("Figure 1.1: a map."), "{md}");
+    assert!(md.contains("Figure 6.1: a map."), "{md}");
+    assert!(md.contains("Text of page 10."), "{md}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bad_chunk_stages_fail_before_converting() {
+    let dir = std::env::temp_dir().join(format!("quack-chunk-stages-bad-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    for (bad, want) in [
+        (stage("rewrite", "x", ""), "chunk_stages[0].kind: rewrite"),
+        (stage("replace", "(", ""), "chunk_stages[0].pattern"),
+    ] {
+        let err = Pipeline::new(&config(vec![bad]), BannerEngine)
+            .run_job(&dir.join("in.pdf"), &dir.join("job"))
+            .err()
+            .unwrap();
+        assert!(format!("{err:#}").contains(want), "{err:#}");
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
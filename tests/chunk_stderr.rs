@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+use quack_check::{config::Config, engine::python::PythonEngine, pipeline::Pipeline};
+use std::path::Path;
+
+/// Stand-in "python" scripts run by /bin/sh: each drains stdin, complains on stderr,
+/// and prints a canned JSON reply.
+fn write_scripts(dir: &Path, text_stderr: &str) {
+    std::fs::create_dir_all(dir).unwrap();
+    let script = |stderr: &str, json: &str| {
+        format!("cat >/dev/null\nprintf '%s\\n' '{stderr}' >&2\nprintf '%s' '{json}'\n")
+    };
+    std::fs::write(
+        dir.join("pdf_probe.py"),
+        script(
+            "probe chatter",
+            r#"{"page_count":1,"sampled_pages":1,"avg_chars_per_page":3000,"garbage_ratio":0.0,"whitespace_ratio":0.1}"#,
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("pdf_text.py"),
+        script(
+            text_stderr,
+            r#"{"ok":true,"markdown":"Hello.","warnings":[],"meta":{}}"#,
+        ),
+    )
+    .unwrap();
+    for name in ["docling_runner.py", "pdf_split.py"] {
+        std::fs::write(dir.join(name), "exit 1\n").unwrap();
+    }
+}
+
+fn config(root: &Path, keep: bool) -> Config {
+    let mut cfg = Config::default();
+    cfg.paths.scripts_dir = root.join("scripts").display().to_string();
+    cfg.security.pin_scripts_dir = false;
+    cfg.docling.python_exe = "/bin/sh".into();
+    cfg.debug.keep_python_stderr = keep;
+    cfg
+}
+
+#[test]
+fn writes_chunk_stderr_and_references_it() {
+    let root = std::env::temp_dir().join(format!("quack-stderr-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    write_scripts(&root.join("scripts"), "UserWarning: font substitution");
+    std::fs::write(root.join("in.pdf"), b"%PDF-1.4").unwrap();
+
+    let cfg = config(&root, true);
+    let pipeline = Pipeline::new(&cfg, PythonEngine::new(&cfg).unwrap());
+    let job_dir = root.join("job");
+    let out = pipeline.run_job(&root.join("in.pdf"), &job_dir).unwrap();
+
+    let chunk = &out.report.chunk_reports[0];
+    assert_eq!(
+        chunk.stderr_log.as_deref(),
+        Some("logs/chunk_00000.stderr.log")
+    );
+    let log = std::fs::read_to_string(job_dir.join("logs/chunk_00000.stderr.log")).unwrap();
+    assert!(log.starts_with("==> pdf_text.py (exit status: 0) <==\n"));
+    assert!(log.contains("UserWarning: font substitution"));
+    assert!(!log.contains("probe chatter"));
+
+    let cfg = config(&root, false);
+    let pipeline = Pipeline::new(&cfg, PythonEngine::new(&cfg).unwrap());
+    let job_dir = root.join("job-quiet");
+    let out = pipeline.run_job(&root.join("in.pdf"), &job_dir).unwrap();
+    assert!(out.report.chunk_reports[0].stderr_log.is_none());
+    assert!(!job_dir.join("logs/chunk_00000.stderr.log").exists());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use quack_check::{
+    chunk_plan::PageRange,
+    cli::{Args, Command},
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+};
+use std::path::Path;
+use std::process::Command as Process;
+
+struct TextEngine;
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 1,
+            sampled_pages: 1,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Body text.".into(),
+            warnings: vec!["page 1: sparse".into()],
+            meta: Default::default(),
+        })
+    }
+}
+
+#[test]
+fn output_flags_are_global() {
+    let args = Args::try_parse_from(["quack-check", "plan", "--input", "a.pdf", "--json"]).unwrap();
+    assert!(args.json && !args.quiet);
+    assert!(matches!(args.cmd, Command::Plan { .. }));
+
+    let args = Args::try_parse_from(["quack-check", "--quiet", "doctor"]).unwrap();
+    assert!(args.quiet && !args.json);
+}
+
+#[test]
+fn json_mode_prints_one_document() {
+    let dir = std::env::temp_dir().join(format!("quack-cli-json-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+
+    let cfg = Config::default();
+    let job_dir = dir.join("out").join("job1");
+    let out = Pipeline::new(&cfg, TextEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+
+    let output = Process::new(env!("CARGO_BIN_EXE_quack-check"))
+        .args(["inspect", "--job", "job1", "--json", "--out-dir"])
+        .arg(dir.join("out"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "{stdout}");
+    let value: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(value["job_id"], "job1");
+    assert_eq!(value["chunks"][0]["ok"], true);
+    assert_eq!(value["warnings"]["page N"][0]["chunk"], 0);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,173 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    collections::{Registry, REGISTRY_ENV},
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct TextEngine;
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 1,
+            sampled_pages: 1,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(serde_json::from_str(
+            r#"{"ok":true,"markdown":"Hello.","warnings":[],"meta":{}}"#,
+        )?)
+    }
+}
+
+/// The example config with `out_dir` changed.
+fn example(out_dir: &str) -> String {
+    include_str!("../quack-check.example.toml")
+        .replace("out_dir = \"out\"", &format!("out_dir = {out_dir:?}"))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-collections-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn the_registry_records_configs_and_notices_edits() {
+    let dir = temp_dir("registry");
+    let config = dir.join("scans.toml");
+    std::fs::write(&config, example("out")).unwrap();
+    let path = dir.join("registry.json");
+
+    let mut registry = Registry::load(&path).unwrap();
+    let added = registry
+        .add(
+            "scans-1920s",
+            &config,
+            Some("faded typewriter scans"),
+            false,
+        )
+        .unwrap();
+    assert!(added.config.is_absolute());
+    registry.save(&path).unwrap();
+
+    let mut registry = Registry::load(&path).unwrap();
+    assert_eq!(
+        registry.resolve("scans-1920s").unwrap(),
+        config.canonicalize().unwrap()
+    );
+    let err = registry
+        .add("scans-1920s", &config, None, false)
+        .unwrap_err();
+    assert!(err.to_string().contains("--replace"), "{err}");
+    assert!(registry.add("../etc", &config, None, false).is_err());
+    let err = registry.resolve("scans-1930s").unwrap_err();
+    assert!(err.to_string().contains("registered: scans-1920s"), "{err}");
+
+    assert_eq!(registry.statuses()[0].state, "ok");
+    std::fs::write(&config, example("scans-out")).unwrap();
+    assert_eq!(registry.statuses()[0].state, "modified");
+    std::fs::remove_file(&config).unwrap();
+    assert_eq!(registry.statuses()[0].state, "missing");
+    assert!(registry.resolve("scans-1920s").is_err());
+
+    registry.remove("scans-1920s").unwrap();
+    assert!(registry.collections.is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn broken_configs_are_not_registered() {
+    let dir = temp_dir("broken");
+    let config = dir.join("broken.toml");
+    std::fs::write(&config, "[chunking\n").unwrap();
+    assert!(Registry::default()
+        .add("broken", &config, None, false)
+        .is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn commands_load_the_named_config() {
+    let dir = temp_dir("cli");
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let out_dir = dir.join("elsewhere");
+    std::fs::write(
+        dir.join("scans.toml"),
+        example(&out_dir.display().to_string()),
+    )
+    .unwrap();
+    let cfg = Config::default();
+    let job_dir = out_dir.join("job1");
+    let out = Pipeline::new(&cfg, TextEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+
+    let quack = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_quack-check"))
+            .args(args)
+            .current_dir(&dir)
+            .env(REGISTRY_ENV, dir.join("registry.json"))
+            .output()
+            .unwrap()
+    };
+    let added = quack(&["collections", "add", "scans", "--config", "scans.toml"]);
+    assert!(
+        added.status.success(),
+        "{}",
+        String::from_utf8_lossy(&added.stderr)
+    );
+
+    // The job is only found under the collection's `paths.out_dir`.
+    let inspected = quack(&[
+        "inspect",
+        "--job",
+        "job1",
+        "--collection",
+        "scans",
+        "--json",
+    ]);
+    assert!(
+        inspected.status.success(),
+        "{}",
+        String::from_utf8_lossy(&inspected.stderr)
+    );
+    let value: serde_json::Value = serde_json::from_slice(&inspected.stdout).unwrap();
+    assert_eq!(value["job_id"], "job1");
+
+    let listed = quack(&["collections", "list", "--json"]);
+    let value: serde_json::Value = serde_json::from_slice(&listed.stdout).unwrap();
+    assert_eq!(value[0]["name"], "scans");
+    assert_eq!(value[0]["state"], "ok");
+
+    let unknown = quack(&["inspect", "--job", "job1", "--collection", "nope"]);
+    assert!(!unknown.status.success());
+    let _ = std::fs::remove_dir_all(&dir);
+}
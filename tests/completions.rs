@@ -0,0 +1,45 @@
+use std::process::Command;
+
+#[test]
+fn completions_include_registered_collections() {
+    let dir = std::env::temp_dir().join(format!("quack-completions-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let registry = dir.join("collections.json");
+    std::fs::write(
+        &registry,
+        r#"{"collections": {"scans-1920s": {"config": "/tmp/a.toml", "added_at": "2026-01-01T00:00:00Z", "config_sha256": "x", "config_hash": "y"}}}"#,
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_quack-check"))
+        .current_dir(&dir)
+        .env("QUACK_CHECK_COLLECTIONS", &registry)
+        .args(["completions", "bash"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let script = String::from_utf8(out.stdout).unwrap();
+    assert!(script.contains("scans-1920s"), "{script}");
+    assert!(script.contains("engine=native_text"));
+    assert!(script.contains("postprocess"));
+
+    let out = Command::new(env!("CARGO_BIN_EXE_quack-check"))
+        .current_dir(&dir)
+        .args(["manpage", "--out-dir", "man"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let page = std::fs::read_to_string(dir.join("man/quack-check-run.1")).unwrap();
+    assert!(page.contains("reuse\\-from"), "{page}");
+    assert!(dir.join("man/quack-check-jobs-doctor.1").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
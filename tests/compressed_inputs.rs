@@ -0,0 +1,128 @@
+use quack_check::{archive::prepare_input, batch::discover_inputs, config::Config};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+const PDF: &[u8] = b"%PDF-1.4\n% fake body\n%%EOF\n";
+
+fn scratch(name: &str) -> (PathBuf, Config) {
+    let dir = std::env::temp_dir().join(format!("quack-compressed-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut cfg = Config::default();
+    cfg.paths.work_dir = dir.join("work").display().to_string();
+    (dir, cfg)
+}
+
+fn write_gz(path: &Path, bytes: &[u8]) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut enc = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    enc.write_all(bytes).unwrap();
+    enc.finish().unwrap();
+}
+
+fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, bytes) in entries {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(bytes).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn gzipped_pdf_is_decompressed_into_work_dir_and_removed_on_drop() {
+    let (dir, cfg) = scratch("gz");
+    let input = dir.join("book.pdf.gz");
+    write_gz(&input, PDF);
+
+    let prepared = prepare_input(&cfg, &input).unwrap();
+    assert_eq!(prepared.path.file_name().unwrap(), "book.pdf");
+    assert!(prepared.path.starts_with(&cfg.paths.work_dir));
+    assert_eq!(std::fs::read(&prepared.path).unwrap(), PDF);
+
+    let decompressed = prepared.path.clone();
+    drop(prepared);
+    assert!(!decompressed.exists());
+    assert!(!decompressed.parent().unwrap().exists());
+}
+
+#[test]
+fn zip_with_one_pdf_is_extracted() {
+    let (dir, cfg) = scratch("zip");
+    let input = dir.join("scan.zip");
+    write_zip(
+        &input,
+        &[
+            ("readme.txt", b"not a pdf"),
+            ("__MACOSX/docs/._scan.pdf", b"resource fork"),
+            ("docs/scan.pdf", PDF),
+        ],
+    );
+
+    let prepared = prepare_input(&cfg, &input).unwrap();
+    assert_eq!(prepared.path.file_name().unwrap(), "scan.pdf");
+    assert_eq!(std::fs::read(&prepared.path).unwrap(), PDF);
+}
+
+#[test]
+fn zip_with_several_or_no_pdfs_is_rejected_and_cleaned_up() {
+    let (dir, cfg) = scratch("zip-bad");
+    let many = dir.join("many.zip");
+    write_zip(&many, &[("a.pdf", PDF), ("b.pdf", PDF)]);
+    let err = prepare_input(&cfg, &many).unwrap_err();
+    assert!(err.to_string().contains("2 PDFs"), "{err:#}");
+
+    let none = dir.join("none.zip");
+    write_zip(&none, &[("notes.txt", b"hello")]);
+    let err = prepare_input(&cfg, &none).unwrap_err();
+    assert!(err.to_string().contains("no PDF"), "{err:#}");
+
+    let inputs = Path::new(&cfg.paths.work_dir).join("inputs");
+    assert_eq!(std::fs::read_dir(inputs).unwrap().count(), 0);
+}
+
+#[test]
+fn decompressed_content_must_be_a_pdf_within_the_size_limit() {
+    let (dir, mut cfg) = scratch("limits");
+    let text = dir.join("notes.txt.gz");
+    write_gz(&text, b"plain text, not a pdf");
+    let err = prepare_input(&cfg, &text).unwrap_err();
+    assert!(err.to_string().contains("not a PDF"), "{err:#}");
+
+    cfg.limits.max_input_file_bytes = 8;
+    let big = dir.join("big.pdf.gz");
+    write_gz(&big, PDF);
+    let err = prepare_input(&cfg, &big).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("max_input_file_bytes"),
+        "{err:#}"
+    );
+}
+
+#[test]
+fn plain_pdf_is_used_in_place() {
+    let (dir, cfg) = scratch("plain");
+    let input = dir.join("plain.pdf");
+    std::fs::write(&input, PDF).unwrap();
+    let prepared = prepare_input(&cfg, &input).unwrap();
+    assert_eq!(prepared.path, input);
+    drop(prepared);
+    assert!(input.exists());
+}
+
+#[test]
+fn batch_discovers_compressed_inputs() {
+    let (dir, _cfg) = scratch("batch");
+    std::fs::write(dir.join("a.pdf"), PDF).unwrap();
+    write_gz(&dir.join("b.PDF.GZ"), PDF);
+    write_zip(&dir.join("c.zip"), &[("c.pdf", PDF)]);
+    std::fs::write(dir.join("d.txt"), "skip").unwrap();
+
+    let names: Vec<String> = discover_inputs(&dir)
+        .unwrap()
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["a.pdf", "b.PDF.GZ", "c.zip"]);
+}
@@ -0,0 +1,52 @@
+use quack_check::{
+    config::Config,
+    config_hash::{canonical, job_id, VERSION},
+};
+
+#[test]
+fn defaults_and_unrelated_settings_do_not_change_the_job_id() {
+    let base = Config::default();
+    assert_eq!(canonical(&base), "{}");
+
+    let mut cfg = base.clone();
+    cfg.global.print_summary = false;
+    cfg.logging.level = "debug".into();
+    cfg.output.tokenizer = "cl100k_base".into();
+    cfg.postprocess.lint.enabled = true;
+    cfg.docling.python_exe = "/opt/venv/bin/python".into();
+    cfg.docling.chunk_timeout_seconds += 60;
+    cfg.chunking.target_pages_per_chunk = base.chunking.target_pages_per_chunk;
+    assert_eq!(canonical(&cfg), "{}");
+    assert_eq!(job_id(&cfg, "abc"), job_id(&base, "abc"));
+    assert!(job_id(&base, "abc").ends_with(&format!("-v{VERSION}")));
+}
+
+#[test]
+fn conversion_settings_are_hashed_with_sorted_keys() {
+    let mut cfg = Config::default();
+    cfg.docling.ocr.langs = vec!["deu".into()];
+    cfg.chunking.target_pages_per_chunk = 20;
+    cfg.docling.env.insert("OMP_NUM_THREADS".into(), "2".into());
+    assert_eq!(
+        canonical(&cfg),
+        r#"{"chunking":{"target_pages_per_chunk":20},"docling":{"env":{"OMP_NUM_THREADS":"2"},"ocr":{"langs":["deu"]}}}"#
+    );
+    assert_ne!(job_id(&cfg, "abc"), job_id(&Config::default(), "abc"));
+}
+
+#[test]
+fn a_toml_round_trip_keeps_the_canonical_form() {
+    let raw = std::fs::read_to_string("quack-check.example.toml").unwrap();
+    let example: Config = toml::from_str(&raw).unwrap();
+    let reparsed: Config = toml::from_str(&toml::to_string(&example).unwrap()).unwrap();
+    assert_eq!(canonical(&example), canonical(&reparsed));
+}
+
+#[test]
+fn the_default_job_id_is_stable() {
+    // Changing this value breaks every existing job directory: bump VERSION instead.
+    assert_eq!(
+        job_id(&Config::default(), "abc"),
+        "76eb77168569d34b8e8adafdad4043ee326033ee7d92eff7b08cb48bbea447f7-v1"
+    );
+}
@@ -0,0 +1,116 @@
+use quack_check::migrate::{migrate, migrate_with, Rule};
+
+fn example() -> String {
+    std::fs::read_to_string("quack-check.example.toml").unwrap()
+}
+
+fn edited(pairs: &[(&str, &str)]) -> String {
+    let mut raw = example();
+    for (from, to) in pairs {
+        assert!(raw.contains(from), "{from}");
+        raw = raw.replacen(from, to, 1);
+    }
+    raw
+}
+
+#[test]
+fn example_config_is_up_to_date() {
+    let migration = migrate(&example()).unwrap();
+    assert!(migration.changes.is_empty(), "{}", migration.diff());
+    assert!(migration.warnings.is_empty(), "{:?}", migration.warnings);
+}
+
+#[test]
+fn single_pdf_backend_becomes_a_list() {
+    let raw = edited(&[("pdf_backend = [\"AUTO\"]", "pdf_backend = \"DLPARSE_V2\"")]);
+    let migration = migrate(&raw).unwrap();
+    assert_eq!(
+        migration.diff(),
+        "# docling.backend.pdf_backend is a list now\n\
+         - docling.backend.pdf_backend = \"DLPARSE_V2\"\n\
+         + docling.backend.pdf_backend = [\"DLPARSE_V2\"]\n"
+    );
+    let migrated: toml::Table = toml::from_str(&migration.migrated).unwrap();
+    assert_eq!(
+        migrated["docling"]["backend"]["pdf_backend"],
+        toml::Value::Array(vec!["DLPARSE_V2".into()])
+    );
+}
+
+#[test]
+fn unknown_keys_are_reported_but_free_form_tables_are_not() {
+    let mut raw = edited(&[
+        ("[limits]\n", "[limits]\nmax_pages = 100\n"),
+        ("# OMP_NUM_THREADS = \"1\"", "OMP_NUM_THREADS = \"1\""),
+    ]);
+    raw.push_str("\n[docling.tier_overrides.SCAN]\n\"ocr.force_full_page_ocr\" = true\n");
+    raw.push_str("\n[retired_section]\nenabled = true\n");
+    let migration = migrate(&raw).unwrap();
+    assert_eq!(
+        migration.warnings,
+        [
+            "unknown key limits.max_pages is ignored",
+            "unknown key retired_section is ignored"
+        ]
+    );
+}
+
+#[test]
+fn renames_move_values_and_keep_an_explicit_new_key() {
+    let rules = [
+        Rule::Rename {
+            from: "output.token_counter",
+            to: "output.tokenizer",
+        },
+        Rule::Rename {
+            from: "global.name",
+            to: "global.job_name",
+        },
+    ];
+    let raw = edited(&[
+        ("tokenizer = \"approx\"", "token_counter = \"cl100k_base\""),
+        ("[global]\n", "[global]\nname = \"old\"\n"),
+    ]);
+    let migration = migrate_with(&raw, &rules).unwrap();
+    let migrated: toml::Table = toml::from_str(&migration.migrated).unwrap();
+    assert_eq!(
+        migrated["output"]["tokenizer"].as_str(),
+        Some("cl100k_base")
+    );
+    assert!(migrated["output"].get("token_counter").is_none());
+    assert_eq!(migrated["global"]["job_name"].as_str(), Some("default"));
+    assert!(migrated["global"].get("name").is_none());
+    assert!(migration
+        .diff()
+        .contains("+ output.tokenizer = \"cl100k_base\""));
+    assert_eq!(migration.warnings.len(), 1);
+    assert!(migration.warnings[0].contains("both are set"));
+}
+
+#[test]
+fn deprecated_keys_and_changed_defaults_warn() {
+    let rules = [
+        Rule::Deprecated {
+            key: "global.keep_intermediates",
+            note: "use chunking.keep_split_pdfs",
+        },
+        Rule::DefaultChanged {
+            key: "docling.preflight_models",
+            old: "false",
+            new: "true",
+        },
+    ];
+    let raw = edited(&[("preflight_models = true\n", "")]);
+    let migration = migrate_with(&raw, &rules).unwrap();
+    assert!(migration.changes.is_empty());
+    assert_eq!(migration.warnings.len(), 2);
+    assert!(migration.warnings[0].contains("deprecated: use chunking.keep_split_pdfs"));
+    assert!(migration.warnings[1].contains("docling.preflight_models is not set"));
+}
+
+#[test]
+fn configs_that_still_do_not_parse_are_rejected() {
+    let raw = edited(&[("max_input_pages = 20000", "max_input_pages = \"many\"")]);
+    let err = migrate(&raw).unwrap_err();
+    assert!(format!("{err:#}").contains("does not parse"), "{err:#}");
+}
@@ -1,4 +1,5 @@
-use quack_check::config::Config;
+use quack_check::{config::Config, xdg};
+use std::path::Path;
 
 #[test]
 fn parse_example_config() {
@@ -7,3 +8,58 @@ fn parse_example_config() {
     assert!(cfg.global.max_parallel_chunks >= 1);
     assert!(!cfg.paths.out_dir.is_empty());
 }
+
+#[test]
+fn relative_paths_resolve_against_the_config_directory() {
+    let mut cfg = Config::default();
+    cfg.paths.out_dir = "out/{job_name}".into();
+    cfg.paths.cache_dir = "../cache".into();
+    cfg.paths.work_dir = "/var/tmp/quack".into();
+    cfg.paths.pdfium_library = "libpdfium.so".into();
+    cfg.resolve_paths(Path::new("/srv/library/config")).unwrap();
+    assert_eq!(cfg.paths.root, "/srv/library/config");
+    assert_eq!(cfg.paths.out_dir, "/srv/library/config/out/{job_name}");
+    assert_eq!(cfg.paths.cache_dir, "/srv/library/cache");
+    assert_eq!(cfg.paths.work_dir, "/var/tmp/quack");
+    assert_eq!(cfg.paths.scripts_dir, "/srv/library/config/scripts");
+    assert_eq!(cfg.paths.docling_artifacts_dir, "");
+    assert_eq!(cfg.paths.pdfium_library, "libpdfium.so");
+
+    // paths.root (or --workdir, which sets it) moves the base; resolving is idempotent.
+    let mut cfg = Config::default();
+    cfg.paths.root = "../shared".into();
+    cfg.paths.pdfium_library = "lib/libpdfium.so".into();
+    cfg.resolve_paths(Path::new("/srv/library/config")).unwrap();
+    assert_eq!(cfg.paths.root, "/srv/library/shared");
+    assert_eq!(cfg.paths.out_dir, "/srv/library/shared/out");
+    assert_eq!(
+        cfg.paths.pdfium_library,
+        "/srv/library/shared/lib/libpdfium.so"
+    );
+    let before = cfg.paths.clone();
+    cfg.resolve_paths(Path::new("/elsewhere")).unwrap();
+    assert_eq!(cfg.paths.out_dir, before.out_dir);
+    assert_eq!(cfg.paths.root, before.root);
+}
+
+#[test]
+fn unset_work_and_cache_dirs_default_to_xdg_cache_home() {
+    let mut cfg: Config = toml::from_str("[paths]\nout_dir = \"out\"\nscripts_dir = \"scripts\"\n")
+        .expect("parse TOML");
+    assert_eq!(cfg.paths.work_dir, xdg::work_dir().display().to_string());
+    cfg.paths.work_dir = String::new();
+    cfg.paths.cache_dir = String::new();
+    cfg.resolve_paths(Path::new("/srv/library/config")).unwrap();
+    assert_eq!(
+        Path::new(&cfg.paths.work_dir),
+        Path::new("/srv/library/config").join(xdg::work_dir())
+    );
+    assert_eq!(
+        Path::new(&cfg.paths.cache_dir),
+        Path::new("/srv/library/config").join(xdg::cache_dir())
+    );
+    if let Some(cache) = xdg::cache_home() {
+        assert!(cfg.paths.work_dir.starts_with(&*cache.to_string_lossy()));
+        assert!(cfg.paths.cache_dir.ends_with("quack-check/cache"));
+    }
+}
@@ -0,0 +1,61 @@
+use quack_check::engine::{ConvertMeta, ConvertOut};
+
+#[test]
+fn runner_meta_is_typed_and_unknown_keys_round_trip() {
+    let raw = r#"{
+        "ok": true,
+        "markdown": "Hello.",
+        "warnings": [],
+        "meta": {
+            "chunk_index": 2,
+            "start_page": 81,
+            "end_page": 120,
+            "pdf_backend": "DLPARSE_V4",
+            "use_page_range": false,
+            "ocr_applied": true,
+            "pages_processed": 40,
+            "table_count": 3,
+            "element_counts": {"texts": 512, "tables": 3, "pictures": 7},
+            "timings": {"convert": 41.5, "export": 0.25},
+            "ignored_flags": ["page_range"],
+            "page_errors": [{"page": 4, "reason": "bad image"}],
+            "docling_future_field": {"nested": [1, 2]}
+        }
+    }"#;
+    let out: ConvertOut = serde_json::from_str(raw).unwrap();
+    let meta = &out.meta;
+    assert_eq!(meta.pages_processed, Some(40));
+    assert_eq!(meta.ocr_applied, Some(true));
+    assert_eq!(meta.table_count, Some(3));
+    assert_eq!(meta.element_counts["pictures"], 7);
+    assert_eq!(meta.timings["convert"], 41.5);
+    assert_eq!(meta.pdf_backend.as_deref(), Some("DLPARSE_V4"));
+    assert!(meta.ignored("page_range"));
+    assert_eq!(meta.page_errors[0].page, 4);
+    assert_eq!(
+        meta.extra["docling_future_field"],
+        serde_json::json!({"nested": [1, 2]})
+    );
+
+    let reparsed: ConvertMeta =
+        serde_json::from_value(serde_json::to_value(meta).unwrap()).unwrap();
+    assert_eq!(&reparsed, meta);
+}
+
+#[test]
+fn missing_or_sparse_meta_defaults() {
+    let out: ConvertOut =
+        serde_json::from_str(r#"{"ok":true,"markdown":"","warnings":[]}"#).unwrap();
+    assert_eq!(out.meta, ConvertMeta::default());
+    assert_eq!(
+        serde_json::to_value(&out.meta).unwrap(),
+        serde_json::json!({})
+    );
+
+    let out: ConvertOut = serde_json::from_str(
+        r#"{"ok":true,"markdown":"","warnings":[],"meta":{"engine":"native_text"}}"#,
+    )
+    .unwrap();
+    assert_eq!(out.meta.engine.as_deref(), Some("native_text"));
+    assert!(out.meta.extra.is_empty());
+}
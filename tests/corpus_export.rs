@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    corpus::{export, LOAD_SCRIPT, TABLES},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::{write_final_outputs, write_index},
+    pipeline::Pipeline,
+};
+use std::path::Path;
+
+struct TextEngine;
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(serde_json::from_str(
+            r#"{"ok":true,
+                "markdown":"<!-- quack:page 1 -->\nFirst page.\n<!-- quack:page 2 -->\nSecond page.",
+                "warnings":["page 2: faint text"],"meta":{}}"#,
+        )?)
+    }
+}
+
+#[test]
+fn finished_jobs_become_typed_tables() {
+    let dir = std::env::temp_dir().join(format!("quack-corpus-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let cfg = Config::default();
+    let out_root = dir.join("out");
+    for job in ["job-a", "job-b"] {
+        let job_dir = out_root.join(job);
+        let out = Pipeline::new(&cfg, TextEngine)
+            .run_job(&dir.join("in.pdf"), &job_dir)
+            .unwrap();
+        write_final_outputs(&cfg, &job_dir, &out).unwrap();
+        write_index(&cfg, &job_dir, job, None).unwrap();
+    }
+    // Still running: chunk records but no final report.
+    std::fs::create_dir_all(out_root.join("job-c").join("chunks")).unwrap();
+
+    let summary = export(&cfg, &out_root, &dir.join("corpus")).unwrap();
+    assert_eq!(summary.documents, 2);
+    assert_eq!(summary.skipped_jobs, ["job-c"]);
+
+    let read = |table: &str| -> Vec<serde_json::Map<String, serde_json::Value>> {
+        std::fs::read_to_string(summary.out.join(format!("{table}.ndjson")))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    };
+    for (table, columns) in TABLES {
+        let columns: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+        for row in read(table) {
+            let mut keys: Vec<&str> = row.keys().map(String::as_str).collect();
+            let mut expected = columns.clone();
+            keys.sort();
+            expected.sort();
+            assert_eq!(keys, expected, "{table}");
+        }
+    }
+
+    let documents = read("documents");
+    assert_eq!(documents[0]["job_id"], "job-a");
+    assert_eq!(documents[0]["page_count"], 2);
+    assert_eq!(documents[0]["complete"], true);
+    assert!(documents[0]["markdown"]
+        .as_str()
+        .unwrap()
+        .contains("Second page."));
+    assert_eq!(read("chunks").len(), 2);
+    let pages = read("pages");
+    assert_eq!(pages.len(), 4);
+    assert_eq!(pages[1]["page"], 2);
+    let warnings = read("warnings");
+    assert_eq!(warnings[0]["code"], "page N");
+    assert_eq!(warnings[0]["warning"], "page 2: faint text");
+    assert_eq!(read("quality")[1]["warnings"], 1);
+
+    let sql = std::fs::read_to_string(summary.out.join(LOAD_SCRIPT)).unwrap();
+    assert_eq!(sql.matches("CREATE OR REPLACE TABLE").count(), TABLES.len());
+    assert!(sql.contains(&format!(
+        "read_ndjson('{}', columns = {{job_id: 'VARCHAR'",
+        summary.out.join("pages.ndjson").display()
+    )));
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    decisions::{effective_decisions, to_canonical_json, DECISIONS_FILE},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+};
+use std::path::Path;
+
+/// A high-text document whose native extraction fails, so docling takes over.
+struct FallbackEngine;
+
+impl Engine for FallbackEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 3,
+            sampled_pages: 3,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "# Title\n\nBody text.".into(),
+            warnings: vec![],
+            meta: ConvertMeta {
+                pdf_backend: Some(req.pdf_backend.clone()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: false,
+            markdown: String::new(),
+            warnings: vec!["empty text layer".into()],
+            meta: Default::default(),
+        })
+    }
+}
+
+#[test]
+fn writes_sorted_decisions_with_fallbacks() {
+    let dir = std::env::temp_dir().join(format!("quack-decisions-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+
+    let cfg = Config::default();
+    let job_dir = dir.join("job");
+    let out = Pipeline::new(&cfg, FallbackEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+
+    let raw = std::fs::read_to_string(job_dir.join("final").join(DECISIONS_FILE)).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&raw).unwrap();
+    assert_eq!(value["tier"], "HighText");
+    assert_eq!(value["engine"], "native_text");
+    let chunk = &value["chunks"][0];
+    assert_eq!(chunk["engine"], "docling");
+    assert_eq!(chunk["pdf_backend"], "AUTO");
+    assert_eq!(
+        (chunk["start_page"].clone(), chunk["end_page"].clone()),
+        (1.into(), 3.into())
+    );
+    assert!(chunk["fallbacks"][0]
+        .as_str()
+        .unwrap()
+        .contains("fell back to docling"));
+    assert!(value["postprocess"]
+        .as_array()
+        .unwrap()
+        .contains(&"remove_repeated_lines".into()));
+
+    // Top-level keys appear in sorted order and the output is reproducible.
+    let positions: Vec<usize> = [
+        "\"chunks\"",
+        "\"do_ocr\"",
+        "\"engine\"",
+        "\"page_count\"",
+        "\"tier\"",
+    ]
+    .iter()
+    .map(|k| raw.find(&format!("\n  {k}")).unwrap())
+    .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    let again = to_canonical_json(&effective_decisions(&cfg, &out.report, 0)).unwrap();
+    assert_eq!(raw, again);
+    assert!(!raw.contains(dir.to_str().unwrap()));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
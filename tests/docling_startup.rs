@@ -0,0 +1,67 @@
+use quack_check::{
+    cancel::{CancellationToken, JobControl},
+    config::Config,
+    engine::startup::StartupGate,
+};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+fn gate(max_concurrent: usize, stagger: f64) -> std::sync::Arc<StartupGate> {
+    let mut cfg = Config::default();
+    cfg.docling.max_concurrent_startups = max_concurrent;
+    cfg.docling.startup_stagger_seconds = stagger;
+    StartupGate::new(&cfg)
+}
+
+#[test]
+fn startups_wait_for_a_free_slot() {
+    let gate = gate(1, 0.0);
+    let mut first = gate.acquire(None).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let waiting = gate.clone();
+    let handle = std::thread::spawn(move || {
+        let permit = waiting.acquire(None).unwrap();
+        tx.send(()).unwrap();
+        permit
+    });
+    assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+    assert_eq!(gate.starting(), 1);
+
+    // Models loaded: the next process may start while this one keeps converting.
+    first.release();
+    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    drop(handle.join().unwrap());
+    assert_eq!(gate.starting(), 0);
+}
+
+#[test]
+fn startups_are_staggered() {
+    let gate = gate(0, 0.2);
+    let started = Instant::now();
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let gate = gate.clone();
+            std::thread::spawn(move || {
+                let _permit = gate.acquire(None).unwrap();
+                started.elapsed()
+            })
+        })
+        .collect();
+    let mut times: Vec<Duration> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    times.sort();
+    assert!(times[0] < Duration::from_millis(150), "{times:?}");
+    assert!(times[1] >= Duration::from_millis(190), "{times:?}");
+    assert!(times[2] >= Duration::from_millis(390), "{times:?}");
+}
+
+#[test]
+fn a_cancelled_job_stops_waiting() {
+    let gate = gate(1, 0.0);
+    let _held = gate.acquire(None).unwrap();
+    let cancel = CancellationToken::new();
+    let control = JobControl::new(cancel.clone(), None);
+    cancel.cancel();
+    let err = gate.acquire(Some(&control)).unwrap_err();
+    assert!(format!("{err:#}").contains("cancelled"), "{err:#}");
+    assert_eq!(gate.starting(), 1);
+}
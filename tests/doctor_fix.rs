@@ -0,0 +1,101 @@
+use quack_check::{
+    config::Config,
+    setup::{plan, set_python_exe, venv_python},
+};
+use std::path::{Path, PathBuf};
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-doctor-fix-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut cfg = Config::default();
+    cfg.global.offline_only = false;
+    cfg.docling.setup.venv_dir = dir.join("venv").display().to_string();
+    (cfg, dir)
+}
+
+fn example() -> String {
+    std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/quack-check.example.toml"
+    ))
+    .unwrap()
+}
+
+#[test]
+fn a_missing_venv_is_created_before_installing_the_pins() {
+    let (cfg, dir) = setup("create");
+    let plan = plan(&cfg).unwrap();
+    let python = venv_python(&dir.join("venv"));
+    assert_eq!(plan.python_exe, python);
+    assert_eq!(plan.steps.len(), 2);
+    assert_eq!(
+        plan.steps[0].argv,
+        [
+            "python3",
+            "-m",
+            "venv",
+            &dir.join("venv").display().to_string()
+        ]
+    );
+    let install = &plan.steps[1].argv;
+    assert_eq!(
+        install[..4],
+        [&python.display().to_string(), "-m", "pip", "install"]
+    );
+    assert!(install.iter().any(|a| a.starts_with("docling==")));
+    assert!(install.iter().any(|a| a.starts_with("pypdf==")));
+    assert!(!install.iter().any(|a| a == "--no-index"));
+}
+
+#[test]
+fn an_existing_venv_is_only_updated() {
+    let (cfg, dir) = setup("existing");
+    let python = venv_python(&dir.join("venv"));
+    std::fs::create_dir_all(python.parent().unwrap()).unwrap();
+    std::fs::write(&python, "").unwrap();
+    let plan = plan(&cfg).unwrap();
+    assert_eq!(plan.steps.len(), 1);
+    assert_eq!(plan.steps[0].argv[3], "install");
+}
+
+#[test]
+fn offline_installs_only_from_the_wheel_dir() {
+    let (mut cfg, dir) = setup("offline");
+    cfg.global.offline_only = true;
+    let err = plan(&cfg).unwrap_err().to_string();
+    assert!(err.contains("docling.setup.wheel_dir"), "{err}");
+
+    cfg.docling.setup.wheel_dir = dir.join("missing").display().to_string();
+    assert!(plan(&cfg).is_err());
+
+    let wheels = dir.join("wheels");
+    std::fs::create_dir_all(&wheels).unwrap();
+    cfg.docling.setup.wheel_dir = wheels.display().to_string();
+    let install = plan(&cfg).unwrap().steps.pop().unwrap().argv;
+    let at = install.iter().position(|a| a == "--no-index").unwrap();
+    assert_eq!(install[at + 1], "--find-links");
+    assert_eq!(install[at + 2], wheels.display().to_string());
+}
+
+#[test]
+fn python_exe_is_rewritten_in_place_keeping_comments() {
+    let raw = example();
+    let python = Path::new("/opt/venv/bin/python");
+    let updated = set_python_exe(&raw, python).unwrap();
+    assert!(updated.contains("python_exe = \"/opt/venv/bin/python\"\n"));
+    assert!(!updated.contains("python_exe = \"auto\""));
+    assert!(updated.contains("#   1) $DOCLING_PYTHON (if set)"));
+    assert_eq!(updated.lines().count(), raw.lines().count());
+    let cfg: Config = toml::from_str(&updated).unwrap();
+    assert_eq!(cfg.docling.python_exe, "/opt/venv/bin/python");
+}
+
+#[test]
+fn python_exe_is_added_when_the_config_omits_it() {
+    let raw = example().replace("python_exe = \"auto\"\n", "");
+    let updated = set_python_exe(&raw, Path::new("/opt/venv/bin/python")).unwrap();
+    assert!(updated.contains("[docling]\npython_exe = \"/opt/venv/bin/python\"\n"));
+    let cfg: Config = toml::from_str(&updated).unwrap();
+    assert_eq!(cfg.docling.python_exe, "/opt/venv/bin/python");
+}
@@ -0,0 +1,162 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    duplicate_pages::{handle_duplicate_pages, DuplicateRange},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Forty words of text no other page shares.
+fn page_text(page: u32) -> String {
+    (0..40)
+        .map(|w| format!("w{w}p{page}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn marked(pages: &[(u32, String)]) -> String {
+    pages
+        .iter()
+        .map(|(page, text)| format!("<!-- quack:page {page} -->\n\n{text}\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn repeated_pages_are_grouped_into_ranges() {
+    // Pages 5-6 repeat pages 3-4; page 2 is too short to compare.
+    let md = marked(&[
+        (1, page_text(1)),
+        (2, "Contents".into()),
+        (3, page_text(3)),
+        (4, page_text(4)),
+        (5, page_text(3)),
+        (6, format!("{} extra", page_text(4))),
+        (7, "Contents".into()),
+    ]);
+    let all: BTreeSet<u32> = (1..=7).collect();
+    let chunks = [(1, 4), (5, 7)];
+
+    let (kept, stats) = handle_duplicate_pages(&md, "report", 0.8, 30, &chunks, &all).unwrap();
+    assert_eq!(kept, md);
+    assert_eq!(stats.ranges.len(), 1, "{:?}", stats.ranges);
+    let range = &stats.ranges[0];
+    assert_eq!(
+        (range.start_page, range.end_page),
+        (5, 6),
+        "{:?}",
+        stats.ranges
+    );
+    assert_eq!((range.original_start_page, range.original_end_page), (3, 4));
+    assert!(range.min_similarity >= 0.8 && range.min_similarity < 1.0);
+    assert_eq!(stats.pages(), [5, 6]);
+
+    let (dropped, _) = handle_duplicate_pages(&md, "drop", 0.8, 30, &chunks, &all).unwrap();
+    assert!(dropped.contains("<!-- quack:page 5 -->\n\n<!-- duplicate of page 3 -->"));
+    assert!(dropped.contains("<!-- duplicate of page 4 -->"));
+    assert_eq!(dropped.matches("w0p3 ").count(), 1, "{dropped}");
+
+    // Pages more than one chunk apart are not compared.
+    let (_, stats) =
+        handle_duplicate_pages(&md, "report", 0.8, 30, &[(1, 3), (4, 4), (5, 7)], &all).unwrap();
+    assert_eq!(stats.pages(), [6]);
+
+    let (unchanged, stats) = handle_duplicate_pages(&md, "off", 0.8, 30, &chunks, &all).unwrap();
+    assert_eq!(unchanged, md);
+    assert!(stats.ranges.is_empty());
+
+    let err = handle_duplicate_pages(&md, "hide", 0.8, 30, &chunks, &all)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("unknown postprocess.duplicate_pages"), "{err}");
+}
+
+/// Four five-page chunks; the third converts the second's pages again, as an engine
+/// ignoring its page range would.
+struct RepeatingEngine;
+
+impl Engine for RepeatingEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let shift = if req.start_page == 11 { 5 } else { 0 };
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| page_text(p - shift))
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn the_pipeline_reports_repeated_chunks() {
+    let dir = std::env::temp_dir().join(format!("quack-duplicate-pages-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+
+    let out = Pipeline::new(&cfg, RepeatingEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(
+        out.report.duplicate_pages.ranges,
+        [DuplicateRange {
+            start_page: 11,
+            end_page: 15,
+            original_start_page: 6,
+            original_end_page: 10,
+            min_similarity: 1.0,
+        }]
+    );
+    assert_eq!(out.markdown.matches("w0p6 ").count(), 2);
+
+    cfg.postprocess.duplicate_pages = "drop".into();
+    let out = Pipeline::new(&cfg, RepeatingEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .unwrap();
+    assert_eq!(out.markdown.matches("w0p6 ").count(), 1);
+    assert!(
+        out.text.contains("<!-- duplicate of page 6 -->"),
+        "{}",
+        out.text
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
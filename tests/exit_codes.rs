@@ -0,0 +1,208 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    cancel::CancellationToken,
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    exit_code::{classify, ErrorClass},
+    pipeline::Pipeline,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A 20-page scan whose probe or conversions fail on request.
+struct FailingEngine {
+    probe: bool,
+    convert: Option<fn() -> anyhow::Error>,
+}
+
+impl Engine for FailingEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        if self.probe {
+            return Err(anyhow!("not a PDF"));
+        }
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        if let Some(err) = self.convert {
+            return Err(err());
+        }
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn scan_config() -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-exit-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    dir
+}
+
+fn run_class(cfg: &Config, engine: FailingEngine, name: &str) -> ErrorClass {
+    let dir = temp_dir(name);
+    let err = Pipeline::new(cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .err()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    classify(&err)
+}
+
+#[test]
+fn every_class_has_its_own_code() {
+    let codes: HashSet<i32> = ErrorClass::ALL.iter().map(|c| c.code()).collect();
+    assert_eq!(codes.len(), ErrorClass::ALL.len());
+    assert_eq!(ErrorClass::Other.code(), 1);
+    assert_eq!(ErrorClass::Config.code(), 2);
+    assert_eq!(ErrorClass::Input.code(), 3);
+    assert_eq!(ErrorClass::Probe.code(), 4);
+    assert_eq!(ErrorClass::Conversion.code(), 5);
+    assert_eq!(ErrorClass::Timeout.code(), 6);
+    assert_eq!(ErrorClass::Cancelled.code(), 130);
+    assert_eq!(classify(&anyhow!("unclassified")), ErrorClass::Other);
+}
+
+#[test]
+fn pipeline_errors_carry_their_stage() {
+    let ok = || FailingEngine {
+        probe: false,
+        convert: None,
+    };
+    let mut bad = scan_config();
+    bad.limits.on_page_error = "shrug".into();
+    assert_eq!(run_class(&bad, ok(), "config"), ErrorClass::Config);
+
+    let probe = FailingEngine {
+        probe: true,
+        convert: None,
+    };
+    assert_eq!(run_class(&scan_config(), probe, "probe"), ErrorClass::Probe);
+
+    let convert = FailingEngine {
+        probe: false,
+        convert: Some(|| anyhow!("docling crashed")),
+    };
+    assert_eq!(
+        run_class(&scan_config(), convert, "convert"),
+        ErrorClass::Conversion
+    );
+
+    // A timeout is the root cause; it wins over the conversion it stopped.
+    let timeout = FailingEngine {
+        probe: false,
+        convert: Some(|| anyhow::Error::new(ErrorClass::Timeout).context("chunk ran past 60s")),
+    };
+    assert_eq!(
+        run_class(&scan_config(), timeout, "timeout"),
+        ErrorClass::Timeout
+    );
+}
+
+#[test]
+fn cancelled_and_late_jobs_are_told_apart() {
+    let dir = temp_dir("cancel");
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let err = Pipeline::new(
+        &scan_config(),
+        FailingEngine {
+            probe: false,
+            convert: None,
+        },
+    )
+    .run_job_with(&dir.join("in.pdf"), &dir.join("job"), cancel, None)
+    .err()
+    .unwrap();
+    assert_eq!(classify(&err), ErrorClass::Cancelled);
+
+    let err = Pipeline::new(
+        &scan_config(),
+        FailingEngine {
+            probe: false,
+            convert: None,
+        },
+    )
+    .run_job_with(
+        &dir.join("in.pdf"),
+        &dir.join("job2"),
+        CancellationToken::new(),
+        Some(std::time::Instant::now()),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(classify(&err), ErrorClass::Timeout);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn the_binary_exits_with_the_class_code() {
+    let dir = temp_dir("binary");
+    std::fs::write(dir.join("bad.toml"), "[chunking\n").unwrap();
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("quack-check.example.toml"),
+        dir.join("ok.toml"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a PDF").unwrap();
+    let quack = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_quack-check"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+
+    assert_eq!(quack(&["--config", "bad.toml", "doctor"]), Some(2));
+    assert_eq!(quack(&["--config", "missing.toml", "doctor"]), Some(2));
+    assert_eq!(
+        quack(&["--config", "ok.toml", "run", "--input", "missing.pdf"]),
+        Some(3)
+    );
+    assert_eq!(
+        quack(&["--config", "ok.toml", "run", "--input", "notes.txt"]),
+        Some(3)
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,116 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    exit_code::{classify, ErrorClass},
+    pipeline::Pipeline,
+    report::CONVERSION_FAILED,
+};
+use std::path::Path;
+
+/// Four five-page chunks; the one starting at page 6 always fails.
+struct FlakyEngine;
+
+impl Engine for FlakyEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        if req.start_page == 6 {
+            return Err(anyhow!("docling crashed"));
+        }
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn config(fraction: f64) -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+    cfg.limits.allow_failed_chunk_fraction = fraction;
+    cfg
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    dir
+}
+
+#[test]
+fn a_failed_chunk_within_the_allowance_degrades_the_job() {
+    let dir = temp_dir("failed-chunks-degraded");
+    let out = Pipeline::new(&config(0.25), FlakyEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let completeness = &out.report.completeness;
+    assert_eq!(completeness.status(), "degraded");
+    assert!(!completeness.complete);
+    assert_eq!(completeness.failed_chunks, [1]);
+    assert_eq!(completeness.converted_pages, 15);
+    let reason = completeness.reason.as_deref().unwrap();
+    assert!(reason.contains("pages 6-10 hold placeholders"), "{reason}");
+    assert_eq!(
+        out.report.chunk_reports[1].skipped.as_deref(),
+        Some(CONVERSION_FAILED)
+    );
+
+    assert!(out.markdown.contains("Pages 11-15."), "{}", out.markdown);
+    for page in 6..=10 {
+        assert!(
+            out.markdown
+                .contains(&format!("<!-- page {page} unreadable: conversion failed")),
+            "{}",
+            out.markdown
+        );
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn failures_past_the_allowance_fail_the_job() {
+    // 0.2 of four chunks rounds down to none.
+    for fraction in [0.0, 0.2] {
+        let dir = temp_dir(&format!("failed-chunks-{fraction}"));
+        let err = Pipeline::new(&config(fraction), FlakyEngine)
+            .run_job(&dir.join("in.pdf"), &dir.join("job"))
+            .err()
+            .unwrap();
+        assert_eq!(classify(&err), ErrorClass::Conversion, "{err:#}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
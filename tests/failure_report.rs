@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    failure::{self, FailureReport, FAILURE_FILE},
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+
+/// Converts the first chunk and fails on the second; with `pages = 0` the probe fails.
+struct FailingEngine {
+    pages: u32,
+}
+
+impl Engine for FailingEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        if self.pages == 0 {
+            return Err(anyhow!("probe crashed"));
+        }
+        Ok(ProbeOut {
+            page_count: self.pages,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        if req.chunk_index == 1 {
+            return Err(anyhow!("docling exited with status 1"));
+        }
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Text.".into(),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-failure-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    (cfg, dir)
+}
+
+#[test]
+fn a_conversion_failure_records_progress_and_how_to_resume() {
+    let (cfg, dir) = setup("convert");
+    let input = dir.join("in.pdf");
+    let job_dir = dir.join("job");
+    let Err(err) = Pipeline::new(&cfg, FailingEngine { pages: 20 }).run_job(&input, &job_dir)
+    else {
+        panic!("expected the job to fail");
+    };
+    std::fs::create_dir_all(job_dir.join("logs")).unwrap();
+    std::fs::write(
+        job_dir.join("logs/chunk_00001.stderr.log"),
+        "Traceback (most recent call last):\nMemoryError\n",
+    )
+    .unwrap();
+
+    let report = failure::describe(
+        &cfg,
+        &job_dir,
+        "abc",
+        &input,
+        &err,
+        "quack-check run --input in.pdf",
+    );
+    assert_eq!(report.stage, "convert");
+    assert_eq!(report.completed_chunks, [0]);
+    assert_eq!(report.planned_chunks, Some(2));
+    assert!(
+        report
+            .error_chain
+            .iter()
+            .any(|e| e.contains("docling exited")),
+        "{:?}",
+        report.error_chain
+    );
+    let stderr = report.stderr.as_ref().unwrap();
+    assert_eq!(stderr.log, "logs/chunk_00001.stderr.log");
+    assert!(stderr.tail.ends_with("MemoryError"));
+    assert!(report.next_actions[0]
+        .starts_with("resume after the 1 converted chunk(s): quack-check run"));
+
+    let path = failure::write(&job_dir, &report).unwrap();
+    let back: FailureReport =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(back.completed_chunks, [0]);
+    failure::clear(&job_dir);
+    assert!(!job_dir.join(FAILURE_FILE).exists());
+}
+
+#[test]
+fn a_probe_failure_is_reported_as_the_prepare_stage() {
+    let (cfg, dir) = setup("probe");
+    let input = dir.join("in.pdf");
+    let job_dir = dir.join("job");
+    let Err(err) = Pipeline::new(&cfg, FailingEngine { pages: 0 }).run_job(&input, &job_dir) else {
+        panic!("expected the job to fail");
+    };
+    let report = failure::describe(
+        &cfg,
+        &job_dir,
+        "abc",
+        &input,
+        &err,
+        "quack-check run --input in.pdf",
+    );
+    assert_eq!(report.stage, "prepare");
+    assert!(report.completed_chunks.is_empty());
+    assert!(report.stderr.is_none());
+    assert!(report.next_actions[0].contains("quack-check classify --input"));
+    assert_eq!(
+        report.next_actions[1],
+        "run the job again: quack-check run --input in.pdf"
+    );
+}
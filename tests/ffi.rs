@@ -0,0 +1,61 @@
+#![cfg(feature = "ffi")]
+
+use quack_check::ffi::{quack_classify, quack_free_result, quack_run_job};
+use std::ffi::{c_char, CStr, CString};
+
+fn take(result: *mut c_char) -> serde_json::Value {
+    assert!(!result.is_null());
+    let text = unsafe { CStr::from_ptr(result) }
+        .to_str()
+        .unwrap()
+        .to_string();
+    unsafe { quack_free_result(result) };
+    serde_json::from_str(&text).unwrap()
+}
+
+#[test]
+fn errors_come_back_as_json_with_their_class() {
+    let dir = std::env::temp_dir().join(format!("quack-ffi-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let missing_config = CString::new(dir.join("nope.toml").display().to_string()).unwrap();
+    let input = CString::new(dir.join("in.pdf").display().to_string()).unwrap();
+
+    let out = take(unsafe { quack_classify(missing_config.as_ptr(), input.as_ptr()) });
+    assert_eq!(out["ok"], false, "{out}");
+    assert_eq!(out["error_class"], "config", "{out}");
+    assert_eq!(out["exit_code"], 2, "{out}");
+
+    let config =
+        CString::new(concat!(env!("CARGO_MANIFEST_DIR"), "/quack-check.example.toml").to_string())
+            .unwrap();
+    let out = take(unsafe { quack_run_job(config.as_ptr(), input.as_ptr(), std::ptr::null()) });
+    assert_eq!(out["ok"], false, "{out}");
+    assert_eq!(out["error_class"], "input", "{out}");
+    assert!(
+        out["error"].as_str().unwrap().contains("does not exist"),
+        "{out}"
+    );
+
+    let out = take(unsafe { quack_classify(config.as_ptr(), std::ptr::null()) });
+    assert_eq!(out["error"], "input_path is NULL", "{out}");
+
+    unsafe { quack_free_result(std::ptr::null_mut()) };
+}
+
+#[test]
+fn the_header_declares_every_exported_function() {
+    let header = include_str!("../include/quack_check.h");
+    let source = include_str!("../src/ffi.rs");
+    let exported: Vec<&str> = source
+        .split("pub unsafe extern \"C\" fn ")
+        .skip(1)
+        .filter_map(|rest| rest.split('(').next())
+        .collect();
+    assert_eq!(exported.len(), 3);
+    for name in exported {
+        assert!(
+            header.contains(&format!("{name}(")),
+            "{name} missing from the header"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::{ChunkPlan, PageRange},
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+
+/// A 30-page scan.
+struct ScanEngine;
+
+impl Engine for ScanEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 30,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-given-plan-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    (cfg, dir)
+}
+
+fn plan(page_count: u32, ranges: &[(u32, u32)]) -> ChunkPlan {
+    ChunkPlan {
+        page_count,
+        chunks: ranges
+            .iter()
+            .map(|&(start_page, end_page)| PageRange {
+                start_page,
+                end_page,
+            })
+            .collect(),
+        strategy: "page_range".into(),
+    }
+}
+
+#[test]
+fn a_given_plan_is_converted_exactly() {
+    let (cfg, dir) = setup("exact");
+    // Small enough that the planner would convert it in one chunk.
+    let out = Pipeline::new(&cfg, ScanEngine)
+        .with_plan(Some(plan(30, &[(1, 4), (5, 17), (18, 30)])))
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let ranges: Vec<(u32, u32)> = out
+        .report
+        .chunk_reports
+        .iter()
+        .map(|c| (c.start_page, c.end_page))
+        .collect();
+    assert_eq!(ranges, [(1, 4), (5, 17), (18, 30)]);
+    assert!(out.markdown.contains("Pages 5-17."), "{}", out.markdown);
+}
+
+#[test]
+fn a_plan_for_another_page_count_is_rejected() {
+    let (cfg, dir) = setup("mismatch");
+    let err = Pipeline::new(&cfg, ScanEngine)
+        .with_plan(Some(plan(40, &[(1, 20), (21, 40)])))
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .err()
+        .unwrap();
+    let err = format!("{err:#}");
+    assert!(err.contains("invalid chunk plan"), "{err}");
+    assert!(err.contains("input has 30"), "{err}");
+}
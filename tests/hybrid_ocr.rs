@@ -0,0 +1,156 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, PageOcr, ProbeOut, SplitChunk},
+    page_quality::HybridOcrStats,
+    pipeline::Pipeline,
+    policy::{decision_for, QualityTier},
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn hybrid_ocr_turns_ocr_on_for_every_tier() {
+    let mut cfg = Config::default();
+    assert!(!decision_for(&cfg, QualityTier::HighText).do_ocr);
+    cfg.docling.ocr.hybrid = true;
+    assert!(decision_for(&cfg, QualityTier::HighText).do_ocr);
+    assert!(decision_for(&cfg, QualityTier::MixedText).do_ocr);
+
+    // An explicit tier decision still wins.
+    cfg.docling
+        .tier_overrides
+        .entry("HIGH_TEXT".into())
+        .or_default()
+        .insert("do_ocr".into(), false.into());
+    assert!(!decision_for(&cfg, QualityTier::HighText).do_ocr);
+
+    let mut cfg = Config::default();
+    cfg.docling
+        .tier_overrides
+        .entry("HIGH_TEXT".into())
+        .or_default()
+        .insert("ocr.hybrid".into(), true.into());
+    assert!(decision_for(&cfg, QualityTier::HighText).do_ocr);
+    assert!(!decision_for(&cfg, QualityTier::MixedText).do_ocr);
+}
+
+/// A six-page text document whose page 3 is a scanned plate and page 5 a text page
+/// with a scanned figure, as a hybrid docling run reports them.
+struct HybridEngine {
+    seen_ocr: Arc<Mutex<Vec<bool>>>,
+}
+
+impl Engine for HybridEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 6,
+            sampled_pages: 6,
+            avg_chars_per_page: 2000,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.seen_ocr.lock().unwrap().push(req.do_ocr);
+        let pages: Vec<u32> = (req.start_page..=req.end_page).collect();
+        let markdown = pages
+            .iter()
+            .map(|p| format!("Text of page {p}."))
+            .collect::<Vec<_>>()
+            .join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n"));
+        let mode = |page: u32| match page {
+            3 => "ocr",
+            5 => "mixed",
+            _ => "text",
+        };
+        Ok(ConvertOut {
+            ok: true,
+            markdown,
+            warnings: vec![],
+            meta: ConvertMeta {
+                use_page_range: Some(true),
+                page_ocr: pages
+                    .iter()
+                    .map(|&page| PageOcr {
+                        page,
+                        mode: mode(page).into(),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn page_modes_reach_the_report() {
+    let dir = std::env::temp_dir().join(format!("quack-hybrid-ocr-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "HIGH_TEXT".into();
+    cfg.engine.high_text_engine = "docling".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 3;
+    cfg.chunking.max_pages_per_chunk = 3;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 3;
+    cfg.docling.ocr.hybrid = true;
+
+    let seen_ocr = Arc::new(Mutex::new(Vec::new()));
+    let engine = HybridEngine {
+        seen_ocr: seen_ocr.clone(),
+    };
+    let out = Pipeline::new(&cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(*seen_ocr.lock().unwrap(), [true, true]);
+    assert_eq!(
+        out.report.hybrid_ocr,
+        Some(HybridOcrStats {
+            text_pages: 4,
+            ocr_pages: 1,
+            mixed_pages: 1,
+            empty_pages: 0,
+            ocr_applied: vec![3, 5],
+        })
+    );
+    let modes: Vec<Option<&str>> = out
+        .page_quality
+        .pages
+        .iter()
+        .map(|p| p.ocr_mode.as_deref())
+        .collect();
+    assert_eq!(
+        modes,
+        [
+            Some("text"),
+            Some("text"),
+            Some("ocr"),
+            Some("text"),
+            Some("mixed"),
+            Some("text")
+        ]
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,103 @@
+#![cfg(unix)]
+
+use quack_check::{
+    batch::discover_inputs,
+    config::Config,
+    input_path::{identity_hash, resolve},
+};
+use std::os::unix::fs::symlink;
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-input-links-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.canonicalize().unwrap()
+}
+
+#[test]
+fn symlinks_resolve_to_the_real_file_and_share_its_identity() {
+    let dir = temp_dir("resolve");
+    std::fs::create_dir_all(dir.join("real")).unwrap();
+    std::fs::write(dir.join("real/book.pdf"), b"%PDF-1.7").unwrap();
+    symlink("real/book.pdf", dir.join("alias.pdf")).unwrap();
+    symlink(dir.join("alias.pdf"), dir.join("alias2.pdf")).unwrap();
+    let mut cfg = Config::default();
+
+    let plain = resolve(&cfg, &dir.join("real/book.pdf")).unwrap();
+    assert!(plain.links.is_empty());
+    let linked = resolve(&cfg, &dir.join("alias2.pdf")).unwrap();
+    assert_eq!(linked.canonical, dir.join("real/book.pdf"));
+    assert_eq!(
+        linked.links,
+        [dir.join("alias2.pdf"), dir.join("alias.pdf")]
+    );
+
+    assert_eq!(identity_hash(&cfg, "abc", &linked).unwrap(), "abc");
+    cfg.hashing.identity = "content_and_path".into();
+    let by_path = identity_hash(&cfg, "abc", &linked).unwrap();
+    assert_ne!(by_path, "abc");
+    assert_eq!(identity_hash(&cfg, "abc", &plain).unwrap(), by_path);
+
+    // A hard link is another path, so it gets another job under content_and_path.
+    std::fs::hard_link(dir.join("real/book.pdf"), dir.join("hard.pdf")).unwrap();
+    let hard = resolve(&cfg, &dir.join("hard.pdf")).unwrap();
+    assert_eq!(hard.hard_links, 2);
+    assert_ne!(identity_hash(&cfg, "abc", &hard).unwrap(), by_path);
+
+    cfg.hashing.identity = "inode".into();
+    assert!(identity_hash(&cfg, "abc", &plain).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cycles_and_dangling_links_are_errors() {
+    let dir = temp_dir("cycles");
+    symlink("b.pdf", dir.join("a.pdf")).unwrap();
+    symlink("./a.pdf", dir.join("b.pdf")).unwrap();
+    symlink("missing.pdf", dir.join("dangling.pdf")).unwrap();
+    let cfg = Config::default();
+
+    let err = resolve(&cfg, &dir.join("a.pdf")).unwrap_err();
+    assert!(err.to_string().starts_with("symlink cycle"), "{err}");
+    let err = resolve(&cfg, &dir.join("dangling.pdf")).unwrap_err();
+    assert!(err.to_string().starts_with("dangling symlink"), "{err}");
+    assert!(err.to_string().ends_with("missing.pdf"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn inputs_must_resolve_into_an_allowed_root() {
+    let dir = temp_dir("roots");
+    std::fs::create_dir_all(dir.join("library")).unwrap();
+    std::fs::create_dir_all(dir.join("private")).unwrap();
+    std::fs::write(dir.join("library/ok.pdf"), b"%PDF-1.7").unwrap();
+    std::fs::write(dir.join("private/secret.pdf"), b"%PDF-1.7").unwrap();
+    symlink(
+        dir.join("private/secret.pdf"),
+        dir.join("library/escape.pdf"),
+    )
+    .unwrap();
+    let mut cfg = Config::default();
+    cfg.security.allowed_input_roots = vec![dir.join("library").display().to_string()];
+
+    assert!(resolve(&cfg, &dir.join("library/ok.pdf")).is_ok());
+    let err = resolve(&cfg, &dir.join("library/escape.pdf")).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("outside security.allowed_input_roots"),
+        "{err}"
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn batch_discovery_survives_directory_cycles() {
+    let dir = temp_dir("batch");
+    std::fs::create_dir_all(dir.join("scans")).unwrap();
+    std::fs::write(dir.join("scans/a.pdf"), b"%PDF-1.7").unwrap();
+    symlink(&dir, dir.join("scans/loop")).unwrap();
+
+    assert_eq!(discover_inputs(&dir).unwrap(), [dir.join("scans/a.pdf")]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,23 @@
+use quack_check::{config::Config, lock::InputLock};
+
+#[test]
+fn second_lock_on_same_input_fails_fast() {
+    let dir = std::env::temp_dir().join(format!("quack-check-lock-{}", std::process::id()));
+    let mut cfg = Config::default();
+    cfg.paths.cache_dir = dir.display().to_string();
+
+    let first = InputLock::acquire(&cfg, "abc123", "job-a").unwrap();
+    let err = match InputLock::acquire(&cfg, "abc123", "job-b") {
+        Ok(_) => panic!("second lock should fail"),
+        Err(err) => err.to_string(),
+    };
+    assert!(err.contains("already being processed by PID"));
+    assert!(err.contains("job-a"));
+
+    // A different input is unaffected.
+    let _other = InputLock::acquire(&cfg, "def456", "job-c").unwrap();
+
+    drop(first);
+    let _again = InputLock::acquire(&cfg, "abc123", "job-b").unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,97 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    inspect::{self, warning_code},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+};
+use std::path::Path;
+
+struct WarningEngine;
+
+impl Engine for WarningEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "# Title\n\nBody text.".into(),
+            warnings: vec![
+                "page 1: low text density".into(),
+                "page 2: low text density".into(),
+            ],
+            meta: ConvertMeta {
+                engine: Some("native_text".into()),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+#[test]
+fn warning_codes_ignore_numbers_and_detail() {
+    assert_eq!(warning_code("page 12: low text density"), "page N");
+    assert_eq!(
+        warning_code("pdf_backend fallback: converted with X after Y"),
+        "pdf_backend fallback"
+    );
+    assert_eq!(warning_code("empty text layer"), "empty text layer");
+}
+
+#[test]
+fn inspects_job_by_prefix() {
+    let dir = std::env::temp_dir().join(format!("quack-inspect-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+
+    let cfg = Config::default();
+    let out_root = dir.join("out");
+    let job_dir = out_root.join("abc123-in");
+    let out = Pipeline::new(&cfg, WarningEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+
+    let resolved = inspect::resolve_job_dir(&out_root, "abc").unwrap();
+    assert_eq!(resolved, job_dir);
+    assert!(inspect::resolve_job_dir(&out_root, "zzz").is_err());
+
+    let view = inspect::load(&cfg, &resolved).unwrap();
+    assert!(view.report.is_some());
+    let summary = view.summary();
+    assert!(summary.contains("job     abc123-in"), "{summary}");
+    assert!(summary.contains("chunks (1)"), "{summary}");
+    assert!(summary.contains("page N (2): chunks 0"), "{summary}");
+
+    let record = view.record(0).unwrap();
+    assert!(record.out.markdown.contains("Body text."));
+    assert!(view.record(7).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
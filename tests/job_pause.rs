@@ -0,0 +1,140 @@
+use anyhow::Result;
+use quack_check::{
+    batch::by_priority,
+    cancel::{interrupted, Interrupted},
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pause,
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Four five-page chunks. Converting the chunk at `pause_at` pauses the job, as an
+/// operator running `jobs pause` meanwhile would.
+struct PausingEngine {
+    job_dir: PathBuf,
+    pause_at: Option<u32>,
+    converted: Arc<Mutex<Vec<u32>>>,
+}
+
+impl Engine for PausingEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.converted.lock().unwrap().push(req.start_page);
+        if self.pause_at == Some(req.start_page) {
+            pause::pause(&self.job_dir)?;
+        }
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn config() -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+    cfg
+}
+
+/// The engine, and the first page of every chunk it converts.
+fn engine(job_dir: &Path, pause_at: Option<u32>) -> (PausingEngine, Arc<Mutex<Vec<u32>>>) {
+    let converted = Arc::new(Mutex::new(Vec::new()));
+    let engine = PausingEngine {
+        job_dir: job_dir.to_path_buf(),
+        pause_at,
+        converted: converted.clone(),
+    };
+    (engine, converted)
+}
+
+#[test]
+fn a_paused_job_stops_and_resumes_where_it_left_off() {
+    let dir = std::env::temp_dir().join(format!("quack-job-pause-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let (input, job_dir) = (dir.join("in.pdf"), dir.join("job"));
+    let cfg = config();
+
+    let (first, converted) = engine(&job_dir, Some(6));
+    let err = Pipeline::new(&cfg, first)
+        .run_job(&input, &job_dir)
+        .err()
+        .unwrap();
+    assert_eq!(interrupted(&err), Some(Interrupted::Paused), "{err:#}");
+    assert_eq!(*converted.lock().unwrap(), [1, 6]);
+    assert!(pause::is_paused(&job_dir));
+
+    // Still paused: nothing is converted.
+    let (idle, converted) = engine(&job_dir, None);
+    assert!(Pipeline::new(&cfg, idle).run_job(&input, &job_dir).is_err());
+    assert!(converted.lock().unwrap().is_empty());
+
+    assert!(pause::resume(&job_dir).unwrap().is_some());
+    let (second, converted) = engine(&job_dir, None);
+    let out = Pipeline::new(&cfg, second)
+        .run_job(&input, &job_dir)
+        .unwrap();
+    assert_eq!(*converted.lock().unwrap(), [11, 16]);
+    assert!(out.markdown.contains("Pages 1-5.") && out.markdown.contains("Pages 16-20."));
+    assert!(pause::load(&job_dir).is_none());
+    assert!(pause::resume(&job_dir).unwrap().is_none());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn batch_runs_higher_priorities_first() {
+    let dir = std::env::temp_dir().join(format!("quack-job-priority-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let inputs: Vec<PathBuf> = ["a.pdf", "b.pdf", "c.pdf"]
+        .iter()
+        .map(|n| dir.join(n))
+        .collect();
+    std::fs::write(dir.join("c.pdf.quack.toml"), "[global]\npriority = 10\n").unwrap();
+    std::fs::write(dir.join("a.pdf.quack.toml"), "[global]\npriority = -1\n").unwrap();
+
+    let mut cfg = Config::default();
+    let order = by_priority(&cfg, inputs.clone());
+    assert_eq!(
+        order,
+        [dir.join("c.pdf"), dir.join("b.pdf"), dir.join("a.pdf")]
+    );
+    cfg.input.sidecar = false;
+    assert_eq!(by_priority(&cfg, inputs.clone()), inputs);
+    let _ = std::fs::remove_dir_all(&dir);
+}
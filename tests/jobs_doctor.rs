@@ -0,0 +1,116 @@
+use quack_check::{
+    chunk_plan::ChunkPlan,
+    config::Config,
+    engine::ConvertOut,
+    jobs::{apply, scan, JobAction, JobState},
+    pipeline::{ChunkRecord, JobManifest, JOB_MANIFEST_FILE},
+    policy::{PolicyDecision, QualityTier},
+    probe::{ProbeInput, ProbeResult, ProbeSampleStats, ProbeStructure},
+};
+use std::path::Path;
+
+fn manifest(pages: u32) -> JobManifest {
+    JobManifest {
+        probe: ProbeResult {
+            input: ProbeInput {
+                path: "x.pdf".into(),
+                canonical_path: None,
+                file_bytes: 1,
+                page_count: pages,
+            },
+            sample: ProbeSampleStats {
+                sampled_pages: 1,
+                avg_chars_per_page: 2000,
+                garbage_ratio: 0.0,
+                whitespace_ratio: 0.2,
+            },
+            structure: ProbeStructure::default(),
+            render: None,
+        },
+        decision: PolicyDecision {
+            tier: QualityTier::HighText,
+            chosen_engine: "native_text".into(),
+            do_ocr: false,
+            pipeline_overrides: Default::default(),
+            pdf_features: Vec::new(),
+            auto_tier: None,
+            tier_mismatch: None,
+            split_spreads: false,
+            tiled_pages: Vec::new(),
+        },
+        plan: ChunkPlan::single(pages, "physical_split"),
+        page_hashes: Vec::new(),
+        excluded_pages: Vec::new(),
+        accelerator: None,
+    }
+}
+
+fn write_unmerged(job_dir: &Path) {
+    std::fs::create_dir_all(job_dir.join("chunks")).unwrap();
+    std::fs::write(
+        job_dir.join(JOB_MANIFEST_FILE),
+        serde_json::to_string(&manifest(2)).unwrap(),
+    )
+    .unwrap();
+    let record = ChunkRecord {
+        chunk_index: 0,
+        start_page: 1,
+        end_page: 2,
+        page_errors: vec![],
+        stderr_log: None,
+        content_type: None,
+        provenance: None,
+        attempts: Vec::new(),
+        out: ConvertOut {
+            ok: true,
+            markdown: "# Title\n\nHello from chunk zero.".into(),
+            warnings: vec![],
+            meta: Default::default(),
+        },
+    };
+    std::fs::write(
+        job_dir.join("chunks").join("chunk_00000.json"),
+        serde_json::to_string(&record).unwrap(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn classifies_and_repairs_job_dirs() {
+    let root = std::env::temp_dir().join(format!("quack-check-jobs-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let mut cfg = Config::default();
+    cfg.paths.cache_dir = root.join("cache").display().to_string();
+    let out = root.join("out");
+
+    write_unmerged(&out.join("a-unmerged"));
+    std::fs::create_dir_all(out.join("b-noindex/final")).unwrap();
+    std::fs::write(out.join("b-noindex/final/report.json"), "{}").unwrap();
+    std::fs::create_dir_all(out.join("c-empty/chunks")).unwrap();
+
+    let checks = scan(&cfg, &out).unwrap();
+    let states: Vec<JobState> = checks.iter().map(|c| c.state).collect();
+    assert_eq!(
+        states,
+        vec![JobState::Unmerged, JobState::MissingIndex, JobState::Empty]
+    );
+
+    for check in &checks {
+        for action in &check.actions {
+            apply(&cfg, check, action).unwrap();
+        }
+    }
+
+    let md = std::fs::read_to_string(out.join("a-unmerged/final/transcript.md")).unwrap();
+    assert!(md.contains("Hello from chunk zero."));
+    assert!(!md.contains("quack:page"));
+    assert!(out.join("a-unmerged/index.json").is_file());
+    assert!(out.join("b-noindex/index.json").is_file());
+    assert!(!out.join("c-empty").exists());
+
+    let after = scan(&cfg, &out).unwrap();
+    assert!(after.iter().all(|c| c.state == JobState::Complete && c.actions.is_empty()));
+    assert!(!after.iter().any(|c| c.actions.contains(&JobAction::Delete)));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
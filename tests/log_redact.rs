@@ -0,0 +1,45 @@
+use quack_check::{
+    config::Config,
+    redact::{effective_config_toml, Redactor},
+};
+
+fn env(name: &str) -> Option<String> {
+    match name {
+        "HOME" => Some("/home/alice".into()),
+        "OPENAI_API_KEY" => Some("sk-test-123456".into()),
+        "OMP_NUM_THREADS" => Some("1".into()),
+        _ => None,
+    }
+}
+
+#[test]
+fn masks_env_values_and_literals() {
+    let mut cfg = Config::default();
+    cfg.logging.redact = vec!["HOME".into(), "OMP_NUM_THREADS".into(), "hunter2".into()];
+    let r = Redactor::with_env(&cfg, env);
+
+    let line = "open /home/alice/doc.pdf key=sk-test-123456 pw=hunter2 threads=1";
+    assert_eq!(
+        r.redact(line),
+        "open [REDACTED:HOME]/doc.pdf key=[REDACTED:OPENAI_API_KEY] pw=[REDACTED] threads=1"
+    );
+    assert_eq!(r.redact("nothing here"), "nothing here");
+}
+
+#[test]
+fn api_key_is_masked_without_config() {
+    let cfg = Config::default();
+    let r = Redactor::with_env(&cfg, env);
+    assert_eq!(r.redact("sk-test-123456"), "[REDACTED:OPENAI_API_KEY]");
+    // An unset api key variable is not treated as a literal.
+    assert_eq!(r.redact("OPENAI_API_KEY"), "OPENAI_API_KEY");
+}
+
+#[test]
+fn effective_config_masks_docling_env() {
+    let mut cfg = Config::default();
+    cfg.docling.env.insert("HF_TOKEN".into(), "hf_secret_value".into());
+    let raw = effective_config_toml(&cfg, &Redactor::with_env(&cfg, env));
+    assert!(raw.contains("HF_TOKEN"));
+    assert!(!raw.contains("hf_secret_value"));
+}
@@ -0,0 +1,85 @@
+use quack_check::{
+    config::Rotate,
+    logrotate::{rotated_path, RotatingFile},
+};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn setup(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-log-rotate-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("quack-check.log")
+}
+
+fn rotate(max_mb: u64, every: &str, keep_files: u32) -> Rotate {
+    Rotate {
+        max_mb,
+        every: every.into(),
+        keep_files,
+    }
+}
+
+fn read(path: &std::path::Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn a_full_log_moves_aside_and_only_keep_files_are_kept() {
+    let path = setup("size");
+    let mut log = RotatingFile::open(&path, &rotate(1, "never", 2)).unwrap();
+    let line = format!("{}\n", "x".repeat(1023));
+    for n in 0..4 {
+        for _ in 0..1024 {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        writeln!(log, "end of {n}").unwrap();
+    }
+    log.flush().unwrap();
+
+    // Each MiB of lines fills a file; the line after it starts the next one.
+    assert!(read(&path).starts_with("end of 3"), "{}", read(&path));
+    assert!(read(&rotated_path(&path, 1)).starts_with("end of 2"));
+    assert!(read(&rotated_path(&path, 2)).starts_with("end of 1"));
+    assert!(!rotated_path(&path, 3).exists());
+    assert_eq!(
+        std::fs::metadata(rotated_path(&path, 1)).unwrap().len(),
+        "end of 2\n".len() as u64 + 1024 * 1024
+    );
+}
+
+#[test]
+fn a_log_from_an_earlier_day_is_rotated_when_reopened() {
+    let path = setup("daily");
+    std::fs::write(&path, "yesterday\n").unwrap();
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(2 * 86_400))
+        .unwrap();
+    drop(file);
+
+    let mut log = RotatingFile::open(&path, &rotate(0, "daily", 3)).unwrap();
+    writeln!(log, "today").unwrap();
+    assert_eq!(read(&path), "today\n");
+    assert_eq!(read(&rotated_path(&path, 1)), "yesterday\n");
+
+    // Reopened the same day, it keeps appending.
+    drop(log);
+    let mut log = RotatingFile::open(&path, &rotate(0, "daily", 3)).unwrap();
+    writeln!(log, "again").unwrap();
+    assert_eq!(read(&path), "today\nagain\n");
+    assert!(!rotated_path(&path, 2).exists());
+}
+
+#[test]
+fn unknown_rotation_periods_are_rejected() {
+    let path = setup("unknown");
+    let err = RotatingFile::open(&path, &rotate(0, "weekly", 3))
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        err.contains("unknown logging.rotate.every: weekly"),
+        "{err}"
+    );
+}
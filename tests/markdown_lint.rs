@@ -0,0 +1,163 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config, PostprocessLint},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    lint::{lint_markdown, LintFinding},
+    pipeline::Pipeline,
+    postprocess::page_marker,
+};
+use std::path::{Path, PathBuf};
+
+fn rules(findings: &[LintFinding]) -> Vec<(&str, usize)> {
+    findings.iter().map(|f| (f.rule.as_str(), f.line)).collect()
+}
+
+#[test]
+fn clean_markdown_has_no_findings() {
+    let md = "\
+# Title
+
+Intro.
+
+## Section
+
+| a | b |
+|---|---|
+| 1 | 2 |
+
+```
+# not a heading
+```
+";
+    let report = lint_markdown(md, &PostprocessLint::default());
+    assert!(report.findings.is_empty(), "{:?}", report.findings);
+}
+
+#[test]
+fn structural_problems_are_found_with_their_lines() {
+    let md = "\
+# Title
+
+### Too deep
+
+Text.
+
+## Empty
+
+## Table
+
+| a | b |
+|---|---|
+| 1 | 2 | 3 |
+| 4 \\| 5 | 6 |
+
+```python
+print(1)
+";
+    let report = lint_markdown(md, &PostprocessLint::default());
+    assert_eq!(
+        rules(&report.findings),
+        [
+            ("heading_jump", 3),
+            ("empty_section", 7),
+            ("broken_table_row", 13),
+            ("unbalanced_fence", 16),
+        ]
+    );
+    assert_eq!((report.errors, report.warnings), (2, 2));
+}
+
+#[test]
+fn long_lines_and_pages_come_from_the_config_and_markers() {
+    let md = format!(
+        "{}\n\n# Title\n\nShort.\n\n{}\n\n{}\n",
+        page_marker(1),
+        page_marker(2),
+        "x".repeat(30)
+    );
+    let cfg = PostprocessLint {
+        max_line_chars: 20,
+        ..Default::default()
+    };
+    let report = lint_markdown(&md, &cfg);
+    assert_eq!(rules(&report.findings), [("long_line", 9)]);
+    assert_eq!(report.findings[0].page, Some(2));
+    assert_eq!(report.findings[0].severity, "warning");
+
+    let off = PostprocessLint {
+        max_line_chars: 0,
+        ..Default::default()
+    };
+    assert!(lint_markdown(&md, &off).findings.is_empty());
+}
+
+/// Converts every chunk to a transcript with an unclosed code fence.
+struct FenceEngine;
+
+impl Engine for FenceEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "# Title\n\nText.\n\n```\ncode".into(),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-lint-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.postprocess.lint.enabled = true;
+    (cfg, dir)
+}
+
+#[test]
+fn findings_are_reported_and_can_fail_the_job() {
+    let (mut cfg, dir) = setup("job");
+    let out = Pipeline::new(&cfg, FenceEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let lint = out.report.lint.expect("lint report");
+    assert_eq!(lint.errors, 1);
+    assert_eq!(lint.findings[0].rule, "unbalanced_fence");
+
+    cfg.postprocess.lint.fail_on_error = true;
+    let Err(err) = Pipeline::new(&cfg, FenceEngine).run_job(&dir.join("in.pdf"), &dir.join("job2"))
+    else {
+        panic!("expected the job to fail");
+    };
+    let msg = format!("{err:#}");
+    assert!(msg.contains("markdown lint found 1 error(s)"), "{msg}");
+    assert!(msg.contains("code fence is never closed"), "{msg}");
+}
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    matrix::{apply, parse_vary, variants, MatrixJob, MatrixReport, MatrixRun},
+    pipeline::{JobOutput, Pipeline},
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+struct TextEngine(&'static str);
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 1,
+            sampled_pages: 1,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: self.0.into(),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+}
+
+fn convert(name: &str, markdown: &'static str) -> (JobOutput, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-matrix-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let cfg = Config::default();
+    let out = Pipeline::new(&cfg, TextEngine(markdown))
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    (out, dir)
+}
+
+fn settings(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn vary_specs_expand_to_a_cross_product() {
+    let engine = parse_vary("engine=docling, native_text").unwrap();
+    assert_eq!(engine.values, ["docling", "native_text"]);
+    let tables = parse_vary("docling.pipeline.do_table_structure=true,false").unwrap();
+    assert!(parse_vary("engine").is_err());
+    assert!(parse_vary("engine=").is_err());
+
+    let combos = variants(&[engine, tables]);
+    assert_eq!(combos.len(), 4);
+    assert_eq!(
+        combos[1],
+        settings(&[
+            ("engine", "docling"),
+            ("docling.pipeline.do_table_structure", "false")
+        ])
+    );
+    assert_eq!(combos[3][0].1, "native_text");
+}
+
+#[test]
+fn settings_apply_with_type_checks() {
+    let cfg = Config::default();
+    let varied = apply(
+        &cfg,
+        &settings(&[
+            ("engine", "docling"),
+            ("docling.pipeline.do_table_structure", "false"),
+            ("chunking.target_pages_per_chunk", "10"),
+            ("classification.sample_pages_percent", "5"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(varied.engine.high_text_engine, "docling");
+    assert_eq!(varied.engine.scan_engine, "docling");
+    assert!(!varied.docling.pipeline.do_table_structure);
+    assert_eq!(varied.chunking.target_pages_per_chunk, 10);
+    assert_eq!(varied.classification.sample_pages_percent, 5.0);
+
+    let err = apply(&cfg, &settings(&[("docling.pipeline.do_tables", "true")])).unwrap_err();
+    assert!(err.to_string().contains("unknown config key"), "{err:#}");
+    let err = apply(
+        &cfg,
+        &settings(&[("chunking.target_pages_per_chunk", "many")]),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("expected a value of type integer"), "{err:#}");
+}
+
+#[test]
+fn report_scores_variants_and_diffs_against_the_first_success() {
+    let (base, dir) = convert("base", "# Title\n\nShared line.\n\nOnly in base.");
+    let (tables, _) = convert(
+        "tables",
+        "# Title\n\nShared line.\n\n| a | b |\n|---|---|\n| 1 | 2 |",
+    );
+    let job = |output| {
+        Ok(MatrixJob {
+            job_id: "j".into(),
+            job_dir: dir.join("job"),
+            output,
+        })
+    };
+    let runs = vec![
+        MatrixRun {
+            settings: settings(&[("engine", "broken")]),
+            duration: Duration::from_millis(5),
+            outcome: Err(anyhow!("unknown engine: broken")),
+        },
+        MatrixRun {
+            settings: settings(&[("engine", "native_text")]),
+            duration: Duration::from_millis(1500),
+            outcome: job(base),
+        },
+        MatrixRun {
+            settings: settings(&[("engine", "docling")]),
+            duration: Duration::from_secs(3),
+            outcome: job(tables),
+        },
+    ];
+    let report = MatrixReport::new(&Config::default(), &dir.join("in.pdf"), runs);
+
+    let failed = &report.variants[0];
+    assert_eq!(failed.status, "failed");
+    assert!(failed.quality.is_none() && failed.diff.is_none());
+
+    let baseline = &report.variants[1];
+    assert_eq!(baseline.status, "ok");
+    assert!(baseline.diff.is_none());
+    assert_eq!(baseline.quality.as_ref().unwrap().headings, 1);
+
+    let variant = &report.variants[2];
+    assert_eq!(variant.duration_seconds, 3.0);
+    assert_eq!(variant.quality.as_ref().unwrap().table_rows, 3);
+    let diff = variant.diff.as_ref().unwrap();
+    assert_eq!(diff.baseline, "engine=native_text");
+    assert_eq!((diff.lines_added, diff.lines_removed), (3, 1));
+    assert!(diff.similarity < 1.0);
+
+    let table = report.table();
+    assert!(table.contains("engine=docling"), "{table}");
+    assert!(table.contains("+3 -1 lines"), "{table}");
+    assert!(table.contains("error: unknown engine: broken"), "{table}");
+}
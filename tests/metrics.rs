@@ -0,0 +1,60 @@
+use quack_check::{config::Config, metrics::Recorder};
+use std::time::Duration;
+
+fn sample(text: &str, name: &str) -> f64 {
+    text.lines()
+        .find_map(|l| l.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("no {name} in\n{text}"))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn metrics_are_off_without_a_textfile_path() {
+    assert!(Recorder::from_config(&Config::default()).is_none());
+}
+
+#[test]
+fn the_textfile_follows_jobs_and_chunks() {
+    let dir = std::env::temp_dir().join(format!("quack-metrics-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("textfile/quack-check.prom");
+    let mut cfg = Config::default();
+    cfg.metrics.textfile_path = path.display().to_string();
+    let metrics = Recorder::from_config(&cfg).unwrap();
+    let read = || std::fs::read_to_string(&path).unwrap();
+    assert_eq!(sample(&read(), "quack_check_jobs_in_progress"), 0.0);
+
+    metrics.set_queue_depth(2);
+    metrics.job_started();
+    metrics.chunk_converted(40);
+    metrics.chunk_converted(10);
+    let text = read();
+    assert_eq!(sample(&text, "quack_check_jobs_in_progress"), 1.0);
+    assert_eq!(sample(&text, "quack_check_chunks_converted_total"), 2.0);
+    assert_eq!(sample(&text, "quack_check_pages_converted_total"), 50.0);
+    assert_eq!(sample(&text, "quack_check_queue_depth"), 2.0);
+    assert!(sample(&text, "quack_check_last_progress_timestamp_seconds") > 0.0);
+    assert!(
+        text.contains("# TYPE quack_check_jobs_total counter"),
+        "{text}"
+    );
+
+    metrics.job_finished("ok", 50, Duration::from_secs(10));
+    metrics.job_started();
+    metrics.job_finished("failed", 0, Duration::from_secs(1));
+    let text = read();
+    assert_eq!(sample(&text, "quack_check_jobs_in_progress"), 0.0);
+    assert_eq!(sample(&text, "quack_check_jobs_total{outcome=\"ok\"}"), 1.0);
+    assert_eq!(
+        sample(&text, "quack_check_jobs_total{outcome=\"partial\"}"),
+        0.0
+    );
+    assert_eq!(
+        sample(&text, "quack_check_jobs_total{outcome=\"failed\"}"),
+        1.0
+    );
+    // A failed job leaves the last rate in place.
+    assert_eq!(sample(&text, "quack_check_pages_per_second"), 5.0);
+    assert!(!dir.join("textfile/quack-check.prom.tmp").exists());
+}
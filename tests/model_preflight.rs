@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        ConvertIn, ConvertOut, DocDiag, Engine, MissingModel, ModelCheck, ProbeOut, SplitChunk,
+    },
+    pipeline::Pipeline,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A scan routed to docling whose table-structure model is not installed.
+struct NoTableModel;
+
+impl Engine for NoTableModel {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 0,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.0,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Scanned text.".into(),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn check_models(
+        &self,
+        do_ocr: bool,
+        _overrides: &BTreeMap<String, serde_json::Value>,
+    ) -> Result<ModelCheck> {
+        assert!(do_ocr, "scans are converted with OCR");
+        Ok(ModelCheck {
+            required: vec!["layout".into(), "tableformer".into(), "ocr:easyocr".into()],
+            missing: vec![MissingModel {
+                name: "tableformer".into(),
+                detail: "no tableformer artifacts found".into(),
+            }],
+            searched: vec!["/models".into()],
+            prefetch_command: Some("docling-tools models download tableformer -o /models".into()),
+        })
+    }
+}
+
+fn run(cfg: &Config, name: &str) -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("quack-preflight-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let result = Pipeline::new(cfg, NoTableModel)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .map(|_| ());
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+#[test]
+fn offline_job_fails_before_converting_with_missing_models() {
+    let cfg = Config::default();
+    assert!(cfg.global.offline_only);
+    let err = format!("{:#}", run(&cfg, "offline").unwrap_err());
+    assert!(
+        err.contains("tableformer: no tableformer artifacts found"),
+        "{err}"
+    );
+    assert!(
+        err.contains("prefetch them with: docling-tools models download tableformer"),
+        "{err}"
+    );
+}
+
+#[test]
+fn preflight_skipped_when_online_or_disabled() {
+    let mut cfg = Config::default();
+    cfg.global.offline_only = false;
+    assert!(run(&cfg, "online").is_ok());
+
+    let mut cfg = Config::default();
+    cfg.docling.preflight_models = false;
+    assert!(run(&cfg, "disabled").is_ok());
+}
@@ -0,0 +1,154 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    ocr_retry::{looks_like_a_word, retry_langs, unknown_word_ratio},
+    pipeline::Pipeline,
+};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const ENGLISH: &str = "In the beginning was the Word, and the Word was with God, and the Word \
+    was God. The same was in the beginning with God. All things were made by him.";
+const MISREAD: &str = "In the beginning the Fathers wrote: Jn prncplq rxt vrbm, t vrbm rxt \
+    pvd Dm, t Dm rxt vrbm. Hwc rxt jn prncplq pvd Dm. Qmnl pr psvm fct snt.";
+const READ: &str = "In the beginning the Fathers wrote: In principio erat verbum, et verbum \
+    erat apud Deum, et Deus erat verbum. Hoc erat in principio apud Deum. Omnia per ipsum facta sunt.";
+
+#[test]
+fn word_shapes_separate_text_from_misreads() {
+    assert!(looks_like_a_word("beginning"));
+    assert!(looks_like_a_word("don't"));
+    assert!(looks_like_a_word("Dm"));
+    assert!(!looks_like_a_word("prncplq"));
+    assert!(!looks_like_a_word("th1s"));
+    assert!(!looks_like_a_word("aaah"));
+    assert!(!looks_like_a_word("ΑlphaΒeta"));
+
+    assert_eq!(unknown_word_ratio(ENGLISH, None), Some(0.0));
+    assert!(unknown_word_ratio(MISREAD, None).unwrap() > 0.3);
+    assert!(unknown_word_ratio(READ, None).unwrap() < 0.1);
+    assert_eq!(unknown_word_ratio("Too short.", None), None);
+
+    let dict: HashSet<String> = ENGLISH
+        .split(|c: char| !c.is_alphabetic())
+        .map(str::to_lowercase)
+        .collect();
+    assert_eq!(unknown_word_ratio(ENGLISH, Some(&dict)), Some(0.0));
+    assert!(unknown_word_ratio(READ, Some(&dict)).unwrap() > 0.5);
+}
+
+#[test]
+fn retry_languages_add_secondary_and_detected_ones() {
+    let mut cfg = Config::default();
+    let eng = vec!["eng".to_string()];
+    assert_eq!(retry_langs(&cfg, &eng, ENGLISH), ["eng"]);
+    assert_eq!(
+        retry_langs(&cfg, &eng, "Thus Paul: ἐν ἀρχῇ ἦν ὁ λόγος, and so on"),
+        ["eng", "ell"]
+    );
+    cfg.docling.ocr_retry.secondary_langs = vec!["lat".into(), "eng".into()];
+    assert_eq!(retry_langs(&cfg, &eng, ENGLISH), ["eng", "lat"]);
+    cfg.docling.ocr.engine = "easyocr".into();
+    assert_eq!(
+        retry_langs(&cfg, &eng, "Thus Paul: ἐν ἀρχῇ ἦν ὁ λόγος"),
+        ["eng", "lat"]
+    );
+}
+
+/// Misreads the Latin passage unless Latin is among the OCR languages.
+#[derive(Default)]
+struct LatinEngine {
+    langs: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+impl Engine for LatinEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let langs: Vec<String> = req
+            .pipeline_overrides
+            .get("ocr.langs")
+            .map(|v| serde_json::from_value(v.clone()).unwrap())
+            .unwrap_or_else(|| vec!["eng".into()]);
+        let latin = langs.iter().any(|l| l == "lat");
+        self.langs.lock().unwrap().push(langs);
+        Ok(ConvertOut {
+            ok: true,
+            markdown: if latin { READ } else { MISREAD }.into(),
+            warnings: vec![],
+            meta: ConvertMeta {
+                ocr_applied: Some(true),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn retry_config() -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.docling.ocr_retry.enabled = true;
+    cfg.docling.ocr_retry.secondary_langs = vec!["lat".into()];
+    cfg
+}
+
+#[test]
+fn gibberish_chunks_keep_the_better_retry() {
+    let dir = std::env::temp_dir().join(format!("quack-ocr-retry-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+
+    let engine = LatinEngine::default();
+    let langs = engine.langs.clone();
+    let out = Pipeline::new(&retry_config(), engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(*langs.lock().unwrap(), [vec!["eng"], vec!["eng", "lat"]]);
+    assert!(out.markdown.contains("In principio erat verbum"));
+    let chunk = &out.report.chunk_reports[0];
+    let retry = chunk.meta.ocr_retry.as_ref().unwrap();
+    assert_eq!(retry.kept, "retry");
+    assert_eq!(retry.retry_langs, ["eng", "lat"]);
+    assert!(retry.first_unknown_word_ratio > 0.3);
+    assert!(retry.retry_unknown_word_ratio.unwrap() < 0.1);
+    assert!(chunk.warnings[0].starts_with("OCR retried with eng+lat"));
+
+    // Disabled, or with nothing to add, the first conversion stands.
+    let mut cfg = retry_config();
+    cfg.docling.ocr_retry.secondary_langs.clear();
+    let engine = LatinEngine::default();
+    let langs = engine.langs.clone();
+    let out = Pipeline::new(&cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .unwrap();
+    assert_eq!(langs.lock().unwrap().len(), 1);
+    assert!(out.report.chunk_reports[0].meta.ocr_retry.is_none());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,211 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        limits::looks_like_oom, ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, PageError,
+        ProbeOut, SplitChunk,
+    },
+    pipeline::Pipeline,
+    postprocess::{parse_page_marker, PAGE_BREAK_PLACEHOLDER},
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Runs out of memory on more than `fits` pages; fails some other way if `broken`.
+struct HungryEngine {
+    fits: u32,
+    broken: bool,
+    calls: Arc<Mutex<Vec<(u32, u32, bool)>>>,
+}
+
+impl HungryEngine {
+    fn new(fits: u32) -> Self {
+        Self {
+            fits,
+            broken: false,
+            calls: Arc::default(),
+        }
+    }
+}
+
+fn ranges(calls: &Mutex<Vec<(u32, u32, bool)>>) -> Vec<(u32, u32)> {
+    calls.lock().unwrap().iter().map(|c| (c.0, c.1)).collect()
+}
+
+impl Engine for HungryEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 40,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((req.start_page, req.end_page, req.use_page_range));
+        if self.broken {
+            return Err(anyhow!(
+                "python script failed: docling_runner.py (exit status: 1)\nValueError: bad xref"
+            ));
+        }
+        if req.end_page - req.start_page + 1 > self.fits {
+            return Err(anyhow!(
+                "python script failed: docling_runner.py (signal: 9 (SIGKILL))\n"
+            ));
+        }
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| format!("Text of page {p}."))
+            .collect();
+        let page_errors: Vec<PageError> = (req.start_page..=req.end_page)
+            .filter(|p| *p == 13)
+            .map(|page| PageError {
+                page,
+                reason: "bad image".into(),
+            })
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta {
+                pdf_backend: Some(req.pdf_backend.clone()),
+                use_page_range: Some(req.use_page_range),
+                page_errors,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-oom-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.postprocess.keep_page_markers = true;
+    cfg.postprocess.remove_repeated_lines = false;
+    cfg.limits.on_page_error = "placeholder".into();
+    (cfg, dir)
+}
+
+#[test]
+fn out_of_memory_chunk_is_converted_in_halves() {
+    let (cfg, dir) = setup("halves");
+    let pipeline = Pipeline::new(&cfg, HungryEngine::new(10));
+    let out = pipeline
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+
+    let markers: Vec<u32> = out.markdown.lines().filter_map(parse_page_marker).collect();
+    assert_eq!(markers, (1..=40).collect::<Vec<_>>());
+    assert!(out
+        .markdown
+        .contains("<!-- quack:page 40 -->\n\nText of page 40."));
+
+    let chunk = &out.report.chunk_reports[0];
+    assert_eq!((chunk.start_page, chunk.end_page), (1, 40));
+    assert!(chunk
+        .warnings
+        .iter()
+        .any(|w| w.contains("out of memory on pages 1-40")));
+    assert_eq!(
+        chunk.page_errors.iter().map(|e| e.page).collect::<Vec<_>>(),
+        [13]
+    );
+}
+
+#[test]
+fn halves_recurse_from_the_original_input() {
+    let (cfg, dir) = setup("recurse");
+    let engine = HungryEngine::new(10);
+    let calls = engine.calls.clone();
+    let pipeline = Pipeline::new(&cfg, engine);
+    pipeline
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(
+        ranges(&calls),
+        [
+            (1, 40),
+            (1, 20),
+            (1, 10),
+            (11, 20),
+            (21, 40),
+            (21, 30),
+            (31, 40)
+        ]
+    );
+    assert!(calls.lock().unwrap()[1..].iter().all(|c| c.2));
+}
+
+#[test]
+fn shrinking_stops_at_the_minimum_chunk_size() {
+    let (mut cfg, dir) = setup("floor");
+    cfg.chunking.oom_min_chunk_pages = 25;
+    let pipeline = Pipeline::new(&cfg, HungryEngine::new(10));
+    let Err(err) = pipeline.run_job(&dir.join("in.pdf"), &dir.join("job")) else {
+        panic!("expected the job to fail");
+    };
+    let msg = format!("{err:#}");
+    assert!(
+        msg.contains("ran out of memory converting pages 1-20"),
+        "{msg}"
+    );
+    assert!(msg.contains("oom_min_chunk_pages=25"), "{msg}");
+}
+
+#[test]
+fn other_failures_are_not_retried() {
+    let (cfg, dir) = setup("other");
+    let mut engine = HungryEngine::new(10);
+    engine.broken = true;
+    let calls = engine.calls.clone();
+    let pipeline = Pipeline::new(&cfg, engine);
+    assert!(pipeline
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .is_err());
+    assert_eq!(ranges(&calls), [(1, 40)]);
+}
+
+#[test]
+fn oom_detection_matches_allocation_failures_and_kills() {
+    assert!(looks_like_oom("Traceback ...\nMemoryError"));
+    assert!(looks_like_oom(
+        "terminate called after throwing an instance of 'std::bad_alloc'"
+    ));
+    assert!(looks_like_oom(
+        "torch.cuda.OutOfMemoryError: CUDA out of memory."
+    ));
+    assert!(looks_like_oom(
+        "python script failed: x.py (signal: 9 (SIGKILL))"
+    ));
+    assert!(!looks_like_oom(
+        "python script failed: x.py (signal: 9 (SIGKILL)) (cpu limit hit: killed past max_cpu_seconds=60)"
+    ));
+    assert!(!looks_like_oom(
+        "python script failed: x.py (exit status: 1)\nValueError"
+    ));
+}
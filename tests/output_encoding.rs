@@ -0,0 +1,57 @@
+use quack_check::{
+    config::Config, encoding, jobs::write_final_outputs, pipeline::postprocess_only,
+};
+use std::path::Path;
+
+#[test]
+fn crlf_bom_and_nfc_apply_to_final_text_files() {
+    let dir = std::env::temp_dir().join(format!("quack-output-encoding-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut cfg = Config::default();
+    cfg.postprocess.normalize_unicode = false;
+    cfg.output.encoding.newline = "crlf".into();
+    cfg.output.encoding.bom = true;
+    cfg.output.encoding.normalization = "nfc".into();
+    encoding::validate(&cfg).unwrap();
+
+    // "e" + combining acute composes under NFC; the "ﬁ" ligature survives it.
+    let out = postprocess_only(
+        &cfg,
+        Path::new("in.md"),
+        "# Cafe\u{301}\n\nThe ﬁrst line.\n",
+    )
+    .unwrap();
+    write_final_outputs(&cfg, &dir, &out).unwrap();
+
+    let md = std::fs::read(dir.join("final/transcript.md")).unwrap();
+    let md = String::from_utf8(md).unwrap();
+    assert!(md.starts_with("\u{feff}# Caf\u{e9}\r\n"), "{md:?}");
+    assert!(md.contains("The ﬁrst line."), "{md:?}");
+    assert!(!md.replace("\r\n", "").contains('\n'), "{md:?}");
+    let txt = std::fs::read_to_string(dir.join("final/transcript.txt")).unwrap();
+    assert!(txt.starts_with('\u{feff}'), "{txt:?}");
+    let report = std::fs::read_to_string(dir.join("final/report.json")).unwrap();
+    assert!(!report.starts_with('\u{feff}') && !report.contains('\r'));
+    let decoded = encoding::decode(&md);
+    assert!(decoded.starts_with("# Caf\u{e9}\n") && !decoded.contains('\r'));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn nfkc_folds_compatibility_characters_and_unknown_values_fail() {
+    let mut cfg = Config::default();
+    cfg.output.encoding.normalization = "nfkc".into();
+    assert_eq!(encoding::encode(&cfg.output.encoding, "ﬁ\r\nx"), "fi\r\nx");
+    cfg.output.encoding.newline = "crlf".into();
+    assert_eq!(
+        encoding::encode(&cfg.output.encoding, "a\r\nb\n"),
+        "a\r\nb\r\n"
+    );
+
+    cfg.output.encoding.newline = "cr".into();
+    let err = encoding::validate(&cfg).err().unwrap();
+    assert!(err.to_string().contains("output.encoding.newline"), "{err}");
+    cfg.output.encoding.newline = "lf".into();
+    cfg.output.encoding.normalization = "nfd".into();
+    assert!(encoding::validate(&cfg).is_err());
+}
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config, Mirror},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    mirror::{expand, mirror_final_outputs, template_vars},
+    pipeline::Pipeline,
+};
+use std::path::Path;
+
+struct TextEngine;
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 1,
+            sampled_pages: 1,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Body text.".into(),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+}
+
+#[test]
+fn mirrors_final_outputs_to_templated_paths() {
+    let dir = std::env::temp_dir().join(format!("quack-mirrors-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("My Book.pdf");
+    std::fs::write(&input, b"%PDF-1.7").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.output.mirrors = vec![
+        Mirror {
+            path: format!("{}/nas/{{input_stem}}-{{tier}}", dir.display()),
+            mode: "copy".into(),
+        },
+        Mirror {
+            path: format!("{}/site/{{job_id_short}}", dir.display()),
+            mode: "hardlink".into(),
+        },
+    ];
+    // The config still serializes (for hashing and effective-config.toml) with mirror
+    // tables after the plain [output] keys.
+    assert!(cfg.normalized_for_hash().contains("[[output.mirrors]]"));
+
+    let job_dir = dir.join("job");
+    let out = Pipeline::new(&cfg, TextEngine).run_job(&input, &job_dir).unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+
+    let vars = template_vars("0123456789abcdef", &input, &out.report);
+    let dests = mirror_final_outputs(&cfg, &job_dir, &vars).unwrap();
+    assert_eq!(dests[0], dir.join("nas/My Book-HIGH_TEXT"));
+    assert_eq!(dests[1], dir.join("site/0123456789ab"));
+    for dest in &dests {
+        let md = std::fs::read_to_string(dest.join("transcript.md")).unwrap();
+        assert_eq!(md, out.markdown);
+        assert!(dest.join("report.json").is_file());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let original = std::fs::metadata(job_dir.join("final/transcript.md")).unwrap();
+        let linked = std::fs::metadata(dests[1].join("transcript.md")).unwrap();
+        assert_eq!(original.ino(), linked.ino());
+    }
+
+    // Re-running replaces the previous files.
+    mirror_final_outputs(&cfg, &job_dir, &vars).unwrap();
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn unknown_placeholders_are_rejected() {
+    let vars = [("input_stem", "book".to_string())].into_iter().collect();
+    assert_eq!(expand("out/{input_stem}/x", &vars).unwrap(), "out/book/x");
+    let err = expand("out/{input_sterm}", &vars).unwrap_err().to_string();
+    assert!(err.contains("unknown placeholder {input_sterm}"), "{err}");
+    assert!(expand("out/{input_stem", &vars).is_err());
+}
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::{ChunkPlan, PageRange},
+    config::{Classification, Config, ExcludedRange},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    exclusions::{kept_runs, resolve, PageExclusion},
+    pipeline::Pipeline,
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::path::{Path, PathBuf};
+
+/// A 20-page scan with one line of text per page.
+struct PagedEngine;
+
+impl Engine for PagedEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| format!("Text of page {p}."))
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn range(pages: &str, reason: &str) -> ExcludedRange {
+    ExcludedRange {
+        pages: pages.into(),
+        reason: reason.into(),
+    }
+}
+
+fn excluded(start_page: u32, end_page: u32, reason: &str) -> PageExclusion {
+    PageExclusion {
+        start_page,
+        end_page,
+        reason: reason.into(),
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-exclusions-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    (cfg, dir)
+}
+
+#[test]
+fn ranges_are_merged_clipped_and_checked() {
+    let mut cfg = Config::default();
+    cfg.input.exclusions.ranges = vec![
+        range("30-40", "ads"),
+        range("5", "plate"),
+        range("6-7", "plate"),
+        range("35-60", "index scan"),
+        range("90-95", ""),
+    ];
+    assert_eq!(
+        resolve(&cfg, 50).unwrap(),
+        [excluded(5, 7, "plate"), excluded(30, 50, "ads; index scan")]
+    );
+    assert_eq!(
+        kept_runs(50, &resolve(&cfg, 50).unwrap()),
+        [(1, 4), (8, 29)]
+    );
+
+    cfg.input.exclusions.ranges = vec![range("1-10", "")];
+    let err = resolve(&cfg, 10).unwrap_err().to_string();
+    assert!(err.contains("exclude every page"), "{err}");
+
+    for bad in ["0-3", "7-5", "a-b", ""] {
+        cfg.input.exclusions.ranges = vec![range(bad, "")];
+        let err = resolve(&cfg, 10).unwrap_err().to_string();
+        assert!(err.contains("invalid input.exclusions pages"), "{err}");
+    }
+}
+
+#[test]
+fn plans_skip_excluded_pages() {
+    let mut cfg = Config::default();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.min_pages_per_chunk = 1;
+    let skip = [excluded(9, 12, "")];
+    let plan = ChunkPlan::from_page_count(&cfg, 30).without_pages(&cfg, &skip);
+    let ranges: Vec<(u32, u32)> = plan
+        .chunks
+        .iter()
+        .map(|c| (c.start_page, c.end_page))
+        .collect();
+    assert_eq!(ranges, [(1, 8), (13, 22), (23, 30)]);
+    plan.validate_excluding(30, &skip).unwrap();
+
+    let err = ChunkPlan::from_page_count(&cfg, 30)
+        .validate_excluding(30, &skip)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("includes excluded pages 9-12"), "{err}");
+}
+
+#[test]
+fn excluded_pages_are_not_converted_and_are_reported() {
+    let (mut cfg, dir) = setup("run");
+    cfg.input.exclusions.ranges = vec![range("9-12", "plates")];
+    let out = Pipeline::new(&cfg, PagedEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+
+    let ranges: Vec<(u32, u32)> = out
+        .report
+        .chunk_reports
+        .iter()
+        .map(|c| (c.start_page, c.end_page))
+        .collect();
+    assert_eq!(ranges, [(1, 8), (13, 20)]);
+    assert_eq!(out.report.excluded_pages, [excluded(9, 12, "plates")]);
+    assert!(out.report.completeness.complete);
+    assert_eq!(out.report.completeness.converted_pages, 16);
+    assert!(!out.markdown.contains("page 10."), "{}", out.markdown);
+    assert!(
+        out.markdown.contains("Text of page 13."),
+        "{}",
+        out.markdown
+    );
+    assert_eq!(
+        out.page_quality.pages[9].warnings,
+        ["excluded: plates".to_string()]
+    );
+}
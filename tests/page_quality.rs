@@ -0,0 +1,139 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, PageConfidence, ProbeOut, SplitChunk,
+    },
+    jobs::write_final_outputs,
+    page_quality::{PageQualityReport, PAGE_QUALITY_FILE, PAGE_QUALITY_SVG},
+    pipeline::Pipeline,
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::path::{Path, PathBuf};
+
+const BODY: &str = "The quick brown fox jumps over the lazy dog and keeps running.";
+
+/// Five pages: a clean one, one full of replacement characters, one nearly empty, one
+/// docling had little confidence in, and another clean one.
+struct FixtureEngine;
+
+impl Engine for FixtureEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 5,
+            sampled_pages: 5,
+            avg_chars_per_page: 60,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages = [
+            BODY.to_string(),
+            format!("{BODY} {}", "\u{FFFD}".repeat(20)),
+            "7".to_string(),
+            BODY.to_string(),
+            BODY.to_string(),
+        ];
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta {
+                use_page_range: Some(req.use_page_range),
+                page_confidence: vec![
+                    PageConfidence {
+                        page: 1,
+                        ocr_score: Some(0.95),
+                        parse_score: None,
+                        layout_score: None,
+                        mean_score: Some(0.9),
+                    },
+                    PageConfidence {
+                        page: 4,
+                        ocr_score: None,
+                        parse_score: Some(0.4),
+                        layout_score: Some(0.4),
+                        mean_score: Some(0.4),
+                    },
+                ],
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-pagequality-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    (cfg, dir)
+}
+
+#[test]
+fn pages_are_scored_from_text_garbage_and_confidence() {
+    let (cfg, dir) = setup("scores");
+    let out = Pipeline::new(&cfg, FixtureEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let quality = &out.page_quality;
+    assert_eq!(quality.page_count, 5);
+    let scores: Vec<f32> = quality.pages.iter().map(|p| p.score.unwrap()).collect();
+
+    assert_eq!(quality.pages[0].ocr_confidence, Some(0.95));
+    assert_eq!(scores[0], 0.95);
+    assert_eq!(scores[4], 1.0);
+    assert!(scores[1] < 0.1, "{scores:?}");
+    assert!(quality.pages[1].warnings[0].contains("garbage characters"));
+    assert!(scores[2] < 0.1, "{scores:?}");
+    assert!(quality.pages[2].warnings[0].starts_with("little text: 1 chars"));
+    assert_eq!(scores[3], 0.4);
+    let mut worst_two = quality.worst[..2].to_vec();
+    worst_two.sort_unstable();
+    assert_eq!(worst_two, [2, 3]);
+    assert_eq!(quality.worst[2..], [4, 1]);
+}
+
+#[test]
+fn json_is_written_by_default_and_svg_on_request() {
+    let (mut cfg, dir) = setup("files");
+    let job = dir.join("job");
+    let out = Pipeline::new(&cfg, FixtureEngine)
+        .run_job(&dir.join("in.pdf"), &job)
+        .unwrap();
+    write_final_outputs(&cfg, &job, &out).unwrap();
+    let written: PageQualityReport = serde_json::from_str(
+        &std::fs::read_to_string(job.join("final").join(PAGE_QUALITY_FILE)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(written.pages.len(), 5);
+    assert!(!job.join("final").join(PAGE_QUALITY_SVG).exists());
+
+    cfg.output.page_quality_svg = true;
+    write_final_outputs(&cfg, &job, &out).unwrap();
+    let svg = std::fs::read_to_string(job.join("final").join(PAGE_QUALITY_SVG)).unwrap();
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(svg.matches("<rect ").count(), 5);
+    assert!(svg.contains("<title>page 4: 0.40</title>"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+
+/// Converts a 20-page scan; `whole` ignores the page range like a backend that
+/// silently converts the full document, `ignored` says so in its meta.
+#[derive(Default)]
+struct RangeEngine {
+    whole: bool,
+    ignored: bool,
+}
+
+impl Engine for RangeEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let (first, last) = if self.whole || self.ignored {
+            (1, 20)
+        } else {
+            (req.start_page, req.end_page)
+        };
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {first}-{last}."),
+            warnings: vec![],
+            meta: ConvertMeta {
+                use_page_range: Some(req.use_page_range),
+                pages_processed: Some(last - first + 1),
+                page_span: (!self.ignored).then_some([first, last]),
+                ignored_flags: if self.ignored {
+                    vec!["page_range".into()]
+                } else {
+                    vec![]
+                },
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn page_range_config(check: &str) -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.chunking.page_range_check = check.into();
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "quack-page-range-check-{name}-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    dir
+}
+
+#[test]
+fn honored_ranges_pass() {
+    let dir = temp_dir("honored");
+    let out = Pipeline::new(&page_range_config("fail"), RangeEngine::default())
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(out.report.chunk_reports.len(), 2);
+    assert!(out.report.chunk_reports[1].warnings.is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn whole_document_conversions_fail_the_chunk() {
+    let dir = temp_dir("fail");
+    let engine = RangeEngine {
+        whole: true,
+        ..Default::default()
+    };
+    let err = Pipeline::new(&page_range_config("fail"), engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .err()
+        .unwrap();
+    let err = format!("{err:#}");
+    assert!(err.contains("page_range 1-10 not honored"), "{err}");
+    assert!(
+        err.contains("20 pages converted, expected at most 10"),
+        "{err}"
+    );
+
+    let engine = RangeEngine {
+        ignored: true,
+        ..Default::default()
+    };
+    let err = Pipeline::new(&page_range_config("fail"), engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .err()
+        .unwrap();
+    let err = format!("{err:#}");
+    assert!(err.contains("ignores page_range"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn warn_keeps_the_chunk_with_a_warning() {
+    let dir = temp_dir("warn");
+    let engine = RangeEngine {
+        whole: true,
+        ..Default::default()
+    };
+    let out = Pipeline::new(&page_range_config("warn"), engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(
+        out.report.chunk_reports[1].warnings,
+        ["page_range 11-20 not honored: 20 pages converted, expected at most 10"]
+    );
+
+    let engine = RangeEngine {
+        whole: true,
+        ..Default::default()
+    };
+    let out = Pipeline::new(&page_range_config("off"), engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .unwrap();
+    assert!(out.report.chunk_reports[1].warnings.is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
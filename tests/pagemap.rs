@@ -0,0 +1,113 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs,
+    pagemap::PageMap,
+    pipeline::Pipeline,
+    postprocess::PAGE_BREAK_PLACEHOLDER,
+};
+use std::path::Path;
+
+/// Ten scanned pages with a running header, accented text, and a printed page number
+/// ten ahead of the PDF page in each footer.
+struct BookEngine;
+
+impl Engine for BookEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 10,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| {
+                format!(
+                    "THE CAFÉ CHRONICLES\n\nPage {p} opens — déjà vu.\n\nPage {p} closes.\n\n{}",
+                    p + 10
+                )
+            })
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn slice(text: &str, start: usize, end: usize) -> String {
+    text.chars().skip(start).take(end - start).collect()
+}
+
+#[test]
+fn pagemap_points_back_to_chunk_and_pages_after_postprocessing() {
+    let dir = std::env::temp_dir().join(format!("quack-pagemap-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 5;
+    cfg.chunking.max_pages_per_chunk = 5;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 5;
+    cfg.postprocess.printed_page_numbers = "detect".into();
+    let out = Pipeline::new(&cfg, BookEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let md = &out.markdown;
+    // Repeated-line removal took the running header out.
+    assert!(!md.contains("CHRONICLES"), "{md}");
+
+    let map = &out.pagemap;
+    for p in 1..=10u32 {
+        let opens = map
+            .paragraphs
+            .iter()
+            .find(|para| slice(md, para.start, para.end) == format!("Page {p} opens — déjà vu."))
+            .unwrap_or_else(|| panic!("page {p} not mapped: {:?}", map.paragraphs));
+        assert_eq!(opens.page, p);
+        assert_eq!(opens.chunk, Some(if p <= 5 { 0 } else { 1 }));
+        assert_eq!(opens.printed_page.as_deref(), Some(&*(p + 10).to_string()));
+        assert_eq!(map.paragraphs[opens.index].start, opens.start);
+    }
+    assert_eq!(map.pages.len(), 10);
+    let page = &map.pages[6];
+    assert_eq!((page.page, page.chunk), (7, Some(1)));
+    let text = slice(md, page.start, page.end);
+    assert!(text.starts_with("Page 7 opens"), "{text}");
+    assert!(text.contains("Page 7 closes."), "{text}");
+    assert!(!text.contains("Page 8"), "{text}");
+
+    jobs::write_final_outputs(&cfg, &dir.join("job"), &out).unwrap();
+    let written: PageMap =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("job/final/pagemap.json")).unwrap())
+            .unwrap();
+    assert_eq!(written.paragraphs.len(), map.paragraphs.len());
+    let _ = std::fs::remove_dir_all(&dir);
+}
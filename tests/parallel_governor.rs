@@ -0,0 +1,166 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config, ParallelGovernor},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    governor::{Governor, HostLoad},
+    pipeline::Pipeline,
+};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+fn load(mem_gib: u64, load_avg: f64) -> HostLoad {
+    HostLoad {
+        mem_available_bytes: Some(mem_gib * GIB),
+        load_avg_1m: Some(load_avg),
+        cpus: 4,
+    }
+}
+
+fn governor() -> Governor {
+    let cfg = ParallelGovernor {
+        enabled: true,
+        min_free_memory_bytes: 2 * GIB,
+        memory_per_chunk_bytes: 3 * GIB,
+        max_load_per_cpu: 1.5,
+    };
+    Governor::new(4, &cfg)
+}
+
+#[test]
+fn memory_headroom_caps_new_conversions() {
+    let mut g = governor();
+    // 8 GiB free, 2 reserved: two more 3 GiB conversions fit.
+    assert_eq!(g.limit(&load(8, 1.0), 0), 2);
+    assert_eq!(g.limit(&load(8, 1.0), 1), 3);
+    // Below the floor nothing new starts until a conversion finishes.
+    assert_eq!(g.limit(&load(1, 1.0), 3), 2);
+    // The limit never drops below one, so the job always progresses.
+    assert_eq!(g.limit(&load(1, 1.0), 0), 1);
+}
+
+#[test]
+fn high_load_shrinks_and_recovery_grows_one_step_at_a_time() {
+    let mut g = governor();
+    assert_eq!(g.limit(&load(64, 1.0), 3), 4);
+    // 4 CPUs at 1.5 per CPU: a load of 7 is too high.
+    assert_eq!(g.limit(&load(64, 7.0), 3), 2);
+    assert_eq!(g.limit(&load(64, 1.0), 1), 3);
+    assert_eq!(g.limit(&load(64, 1.0), 1), 4);
+}
+
+#[test]
+fn missing_signals_and_a_disabled_governor_allow_the_maximum() {
+    let mut g = governor();
+    assert_eq!(g.limit(&HostLoad::default(), 0), 4);
+
+    let cfg = ParallelGovernor {
+        enabled: false,
+        ..Default::default()
+    };
+    assert_eq!(Governor::new(4, &cfg).limit(&load(0, 100.0), 4), 4);
+    assert_eq!(Governor::new(0, &cfg).limit(&load(64, 0.0), 0), 1);
+}
+
+/// Sleeps in every conversion and records the most conversions seen at once.
+#[derive(Clone, Default)]
+struct SlowEngine {
+    running: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl Engine for SlowEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 60,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        // Later chunks finish first, so results arrive out of order.
+        std::thread::sleep(Duration::from_millis(150 - 20 * u64::from(req.chunk_index)));
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta {
+                use_page_range: Some(req.use_page_range),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+/// Six 10-page chunks converted by page range.
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-parallel-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.global.governor.enabled = false;
+    (cfg, dir)
+}
+
+#[test]
+fn chunks_convert_in_parallel_and_merge_in_order() {
+    let (mut cfg, dir) = setup("run");
+    cfg.global.max_parallel_chunks = 3;
+    let engine = SlowEngine::default();
+    let out = Pipeline::new(&cfg, engine.clone())
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(engine.peak.load(Ordering::SeqCst), 3);
+    let starts: Vec<u32> = out
+        .report
+        .chunk_reports
+        .iter()
+        .map(|c| c.start_page)
+        .collect();
+    assert_eq!(starts, [1, 11, 21, 31, 41, 51]);
+    let first = out.markdown.find("Pages 1-10.").unwrap();
+    let last = out.markdown.find("Pages 51-60.").unwrap();
+    assert!(first < last);
+}
+
+#[test]
+fn one_chunk_at_a_time_by_default() {
+    let (cfg, dir) = setup("sequential");
+    let engine = SlowEngine::default();
+    Pipeline::new(&cfg, engine.clone())
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(engine.peak.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+    report::PARTIAL_REPORT_FILE,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A 20-page scan that reads the partial report as each chunk starts converting.
+struct WatchedEngine {
+    partial: PathBuf,
+    seen: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+impl Engine for WatchedEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let raw = std::fs::read_to_string(&self.partial)?;
+        self.seen.lock().unwrap().push(serde_json::from_str(&raw)?);
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![format!("faint scan in chunk {}", req.chunk_index)],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn the_partial_report_follows_the_run_and_becomes_the_report() {
+    let dir = std::env::temp_dir().join(format!("quack-partial-report-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+
+    let job_dir = dir.join("job");
+    let final_dir = job_dir.join("final");
+    let seen = Arc::<Mutex<Vec<serde_json::Value>>>::default();
+    let engine = WatchedEngine {
+        partial: final_dir.join(PARTIAL_REPORT_FILE),
+        seen: seen.clone(),
+    };
+    let out = Pipeline::new(&cfg, engine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0]["completeness"]["complete"], false);
+    assert_eq!(seen[0]["completeness"]["converted_pages"], 0);
+    assert_eq!(seen[0]["decision"]["tier"], "Scan");
+    assert_eq!(seen[1]["completeness"]["converted_pages"], 10);
+    assert_eq!(seen[1]["completeness"]["total_pages"], 20);
+    assert_eq!(
+        seen[1]["completeness"]["reason"],
+        "in progress: 1 of 2 chunk(s) converted"
+    );
+    let chunks = seen[1]["chunk_reports"].as_array().unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0]["warnings"][0], "faint scan in chunk 0");
+
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+    assert!(!final_dir.join(PARTIAL_REPORT_FILE).exists());
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(final_dir.join("report.json")).unwrap())
+            .unwrap();
+    assert_eq!(report["completeness"]["complete"], true);
+    assert_eq!(report["chunk_reports"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,154 @@
+use quack_check::{
+    config::Config,
+    jobs::{find_by_name, job_dirs, wildcard_match, write_index},
+    template::{expand_config, for_job, static_root},
+};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn paths_and_filenames_expand_per_job() {
+    let mut cfg = Config::default();
+    cfg.global.job_name = "books".into();
+    cfg.paths.out_dir = "out/{job_name}/{input_stem}".into();
+    cfg.paths.cache_dir = "cache/{job_name}".into();
+    cfg.output.markdown_filename = "{input_stem}-{job_id_short}.md".into();
+    expand_config(&mut cfg).unwrap();
+    assert_eq!(cfg.paths.out_dir, "out/books/{input_stem}");
+    assert_eq!(cfg.paths.cache_dir, "cache/books");
+    assert_eq!(static_root(&cfg.paths.out_dir), PathBuf::from("out/books"));
+
+    let job = for_job(&cfg, Path::new("/in/My Book.pdf"), "0123456789abcdef-v1").unwrap();
+    assert_eq!(job.paths.out_dir, "out/books/My Book");
+    assert_eq!(job.output.markdown_filename, "My Book-0123456789ab.md");
+    assert_eq!(job.output.text_filename, "transcript.txt");
+}
+
+#[test]
+fn bad_templates_are_rejected() {
+    let mut cfg = Config::default();
+    cfg.paths.work_dir = "work/{input_stem}".into();
+    let err = format!("{:#}", expand_config(&mut cfg).unwrap_err());
+    assert!(err.contains("paths.work_dir"), "{err}");
+    assert!(err.contains("unknown placeholder {input_stem}"), "{err}");
+
+    let mut cfg = Config::default();
+    cfg.paths.out_dir = "out/{input_sterm}".into();
+    assert!(expand_config(&mut cfg).is_err());
+
+    let mut cfg = Config::default();
+    cfg.output.report_filename = "{input_name}/report.json".into();
+    let err = for_job(&cfg, Path::new("a.pdf"), "abc")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("not a file name"), "{err}");
+}
+
+#[test]
+fn sneaky_filenames_are_rejected_at_load() {
+    let load = |edit: fn(&mut Config)| {
+        let mut cfg = Config::default();
+        edit(&mut cfg);
+        expand_config(&mut cfg).err().map(|e| format!("{e:#}"))
+    };
+    let err = load(|c| c.output.markdown_filename = "../../etc/x.md".into()).unwrap();
+    assert!(
+        err.contains("output.markdown_filename expands to a path"),
+        "{err}"
+    );
+    let err = load(|c| c.output.text_filename = "..".into()).unwrap();
+    assert!(err.contains("not a file name"), "{err}");
+    let err = load(|c| c.output.report_filename = "report?.json".into()).unwrap();
+    assert!(err.contains("reserved character '?'"), "{err}");
+    let err = load(|c| c.output.text_filename = "NUL.txt".into()).unwrap();
+    assert!(err.contains("reserved device name"), "{err}");
+    let err = load(|c| c.output.text_filename = "Transcript.MD".into()).unwrap();
+    assert!(
+        err.contains("output.text_filename and output.markdown_filename"),
+        "{err}"
+    );
+    let err = load(|c| c.output.report_filename = "decisions.json".into()).unwrap();
+    assert!(err.contains("collides with final/decisions.json"), "{err}");
+    assert!(load(|c| c.output.markdown_filename = "{input_stem}: notes.md".into()).is_some());
+
+    // Placeholders are fine, and what an input's name brings in is kept.
+    assert!(load(|c| c.output.markdown_filename = "{input_stem}.md".into()).is_none());
+    let mut cfg = Config::default();
+    cfg.output.markdown_filename = "{input_stem}.md".into();
+    expand_config(&mut cfg).unwrap();
+    let job = for_job(&cfg, Path::new("a:b.pdf"), "abc").unwrap();
+    assert_eq!(job.output.markdown_filename, "a:b.md");
+    cfg.output.text_filename = "{input_stem}.md".into();
+    let err = for_job(&cfg, Path::new("a.pdf"), "abc").unwrap_err();
+    assert!(err.to_string().contains("are both a.md"), "{err}");
+}
+
+#[test]
+fn job_directories_are_found_below_templated_roots() {
+    let root = std::env::temp_dir().join(format!("quack-path-templates-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("a/job1/chunks")).unwrap();
+    std::fs::create_dir_all(root.join("b/c/job2")).unwrap();
+    std::fs::write(root.join("b/c/job2/job.json"), "{}").unwrap();
+    std::fs::create_dir_all(root.join("empty")).unwrap();
+
+    assert_eq!(
+        job_dirs(&root).unwrap(),
+        [root.join("a/job1"), root.join("b/c/job2")]
+    );
+    assert!(job_dirs(&root.join("missing")).unwrap().is_empty());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn job_names_may_use_per_input_variables() {
+    let mut cfg = Config::default();
+    cfg.global.job_name = "{input_stem}-{date}".into();
+    cfg.paths.out_dir = "out/{job_name}".into();
+    expand_config(&mut cfg).unwrap();
+    assert_eq!(cfg.paths.out_dir, "out/{job_name}");
+    assert_eq!(static_root(&cfg.paths.out_dir), PathBuf::from("out"));
+
+    let job = for_job(&cfg, Path::new("/in/origin-of-species.pdf"), "abc-v1").unwrap();
+    let date = &job.global.job_name["origin-of-species-".len()..];
+    assert!(job.global.job_name.starts_with("origin-of-species-20"));
+    assert_eq!(date.len(), "YYYY-MM-DD".len());
+    assert_eq!(job.paths.out_dir, format!("out/{}", job.global.job_name));
+
+    // Directories shared by every job cannot depend on the input.
+    let mut cfg = Config::default();
+    cfg.global.job_name = "{input_stem}".into();
+    cfg.paths.cache_dir = "cache/{job_name}".into();
+    let err = format!("{:#}", expand_config(&mut cfg).unwrap_err());
+    assert!(err.contains("paths.cache_dir uses {job_name}"), "{err}");
+}
+
+#[test]
+fn jobs_are_found_by_name() {
+    let root = std::env::temp_dir().join(format!("quack-job-names-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let mut cfg = Config::default();
+    for (job_id, name) in [
+        ("job1", "origin-of-species-1859"),
+        ("job2", "origin-of-species-1872"),
+        ("job3", "descent-of-man"),
+    ] {
+        let job_dir = root.join(job_id);
+        std::fs::create_dir_all(job_dir.join("final")).unwrap();
+        cfg.global.job_name = name.into();
+        write_index(&cfg, &job_dir, job_id, None).unwrap();
+    }
+
+    let found = find_by_name(&root, "origin-of-species*").unwrap();
+    let ids: Vec<&str> = found.iter().map(|j| j.job_id.as_str()).collect();
+    assert_eq!(ids, ["job1", "job2"]);
+    assert_eq!(found[1].job_name, "origin-of-species-1872");
+    assert!(found[1].finished.is_some());
+    assert!(find_by_name(&root, "descent").unwrap().is_empty());
+    assert_eq!(find_by_name(&root, "*of-man").unwrap()[0].job_id, "job3");
+
+    assert!(wildcard_match("a*b?d", "axxxbcd"));
+    assert!(wildcard_match("*", ""));
+    assert!(!wildcard_match("a*b", "ab-c"));
+    assert!(wildcard_match("*a*a*", "banana"));
+    let _ = std::fs::remove_dir_all(&root);
+}
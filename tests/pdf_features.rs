@@ -0,0 +1,154 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    policy::validate_pdf_features,
+    probe::ProbeStructure,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Reports JavaScript and a rebuilt xref, and records what it was asked to strip and
+/// which file it converted.
+#[derive(Clone, Default)]
+struct RiskyEngine {
+    stripped: Arc<Mutex<Vec<String>>>,
+    converted: Arc<Mutex<Vec<String>>>,
+}
+
+impl Engine for RiskyEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: ProbeStructure {
+                risky_features: vec!["javascript".into(), "xref_repaired".into()],
+                ..Default::default()
+            },
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.converted.lock().unwrap().push(req.input_pdf.clone());
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "# Title\n\nText.".into(),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn strip_pdf_features(&self, input: &Path, output: &Path, features: &[String]) -> Result<()> {
+        std::fs::copy(input, output)?;
+        self.stripped
+            .lock()
+            .unwrap()
+            .extend(features.iter().cloned());
+        Ok(())
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir =
+        std::env::temp_dir().join(format!("quack-pdf-features-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    (cfg, dir)
+}
+
+#[test]
+fn warned_features_are_recorded_in_the_decision() {
+    let (cfg, dir) = setup("warn");
+    let engine = RiskyEngine::default();
+    let out = Pipeline::new(&cfg, engine.clone())
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let actions: Vec<(&str, &str)> = out
+        .report
+        .decision
+        .pdf_features
+        .iter()
+        .map(|f| (f.feature.as_str(), f.action.as_str()))
+        .collect();
+    assert_eq!(actions, [("javascript", "warn"), ("xref_repaired", "warn")]);
+    assert!(engine.stripped.lock().unwrap().is_empty());
+    assert!(engine.converted.lock().unwrap()[0].ends_with("in.pdf"));
+}
+
+#[test]
+fn refused_features_fail_the_job_before_conversion() {
+    let (mut cfg, dir) = setup("refuse");
+    cfg.security.pdf_features.xref_repaired = "refuse".into();
+    let engine = RiskyEngine::default();
+    let Err(err) =
+        Pipeline::new(&cfg, engine.clone()).run_job(&dir.join("in.pdf"), &dir.join("job"))
+    else {
+        panic!("expected the job to fail");
+    };
+    let msg = format!("{err:#}");
+    assert!(
+        msg.contains("security.pdf_features: xref_repaired"),
+        "{msg}"
+    );
+    assert!(engine.converted.lock().unwrap().is_empty());
+}
+
+#[test]
+fn stripped_features_convert_the_cleaned_copy() {
+    let (mut cfg, dir) = setup("strip");
+    cfg.security.pdf_features.javascript = "strip".into();
+    let engine = RiskyEngine::default();
+    Pipeline::new(&cfg, engine.clone())
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert_eq!(*engine.stripped.lock().unwrap(), ["javascript"]);
+    let stripped = dir.join("job").join("input.stripped.pdf");
+    assert!(stripped.exists());
+    assert_eq!(
+        engine.converted.lock().unwrap()[0],
+        stripped.display().to_string()
+    );
+}
+
+#[test]
+fn invalid_actions_are_rejected() {
+    let mut cfg = Config::default();
+    assert!(validate_pdf_features(&cfg).is_ok());
+
+    cfg.security.pdf_features.forms = "strip".into();
+    let err = validate_pdf_features(&cfg).unwrap_err().to_string();
+    assert!(
+        err.contains("security.pdf_features.forms cannot be \"strip\""),
+        "{err}"
+    );
+
+    cfg.security.pdf_features.forms = "allow".into();
+    cfg.security.pdf_features.xfa = "delete".into();
+    let err = validate_pdf_features(&cfg).unwrap_err().to_string();
+    assert!(
+        err.contains("unsupported security.pdf_features.xfa: delete"),
+        "{err}"
+    );
+}
@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    hooks::{CommandHooks, PipelineHooks},
+    pipeline::{ChunkRecord, JobOutput, Pipeline},
+    policy::PolicyDecision,
+    probe::ProbeResult,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A 20-page scan, converted in two chunks.
+struct ScanEngine;
+
+impl Engine for ScanEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta {
+                ocr_applied: Some(req.do_ocr),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn scan_config() -> Config {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-hooks-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    dir
+}
+
+#[derive(Default)]
+struct Recording {
+    stages: Arc<Mutex<Vec<String>>>,
+}
+
+impl PipelineHooks for Recording {
+    fn on_probe(&self, probe: &mut ProbeResult) -> Result<()> {
+        self.stages
+            .lock()
+            .unwrap()
+            .push(format!("probe {}", probe.input.page_count));
+        Ok(())
+    }
+
+    fn on_decision(&self, _probe: &ProbeResult, decision: &mut PolicyDecision) -> Result<()> {
+        self.stages.lock().unwrap().push("decision".into());
+        decision.do_ocr = false;
+        Ok(())
+    }
+
+    fn before_chunk(&self, req: &mut ConvertIn) -> Result<()> {
+        self.stages
+            .lock()
+            .unwrap()
+            .push(format!("before_chunk {}", req.chunk_index));
+        Ok(())
+    }
+
+    fn after_chunk(&self, record: &mut ChunkRecord) -> Result<()> {
+        self.stages
+            .lock()
+            .unwrap()
+            .push(format!("after_chunk {}", record.chunk_index));
+        record.out.warnings.push("checked by hook".into());
+        Ok(())
+    }
+
+    fn before_merge(&self, records: &mut Vec<ChunkRecord>) -> Result<()> {
+        self.stages
+            .lock()
+            .unwrap()
+            .push(format!("before_merge {}", records.len()));
+        Ok(())
+    }
+
+    fn after_merge(&self, output: &mut JobOutput) -> Result<()> {
+        self.stages.lock().unwrap().push("after_merge".into());
+        output.markdown.push_str("\nReviewed.\n");
+        Ok(())
+    }
+}
+
+#[test]
+fn hooks_see_every_stage_and_can_edit_it() {
+    let dir = temp_dir("library");
+    let hooks = Recording::default();
+    let stages = hooks.stages.clone();
+    let out = Pipeline::new_with_hooks(&scan_config(), ScanEngine, hooks)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+
+    assert_eq!(
+        *stages.lock().unwrap(),
+        [
+            "probe 20",
+            "decision",
+            "before_chunk 0",
+            "after_chunk 0",
+            "before_chunk 1",
+            "after_chunk 1",
+            "before_merge 2",
+            "after_merge",
+        ]
+    );
+    assert!(!out.report.decision.do_ocr);
+    assert_eq!(out.report.chunk_reports[0].meta.ocr_applied, Some(false));
+    assert_eq!(out.report.chunk_reports[1].warnings, ["checked by hook"]);
+    assert!(out.markdown.ends_with("Reviewed.\n"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+struct Veto;
+
+impl PipelineHooks for Veto {
+    fn before_chunk(&self, req: &mut ConvertIn) -> Result<()> {
+        if req.chunk_index == 1 {
+            return Err(anyhow!("chunk 1 is embargoed"));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn a_failing_hook_fails_the_job() {
+    let dir = temp_dir("veto");
+    let err = Pipeline::new_with_hooks(&scan_config(), ScanEngine, Veto)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .err()
+        .unwrap();
+    assert!(
+        format!("{err:#}").contains("chunk 1 is embargoed"),
+        "{err:#}"
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn command_hooks_get_the_stage_as_json() {
+    let dir = temp_dir("commands");
+    let log = dir.join("hook.log");
+    let mut cfg = scan_config();
+    let append = format!(
+        "printf '%s ' \"$QUACK_HOOK\" >> {0}; cat >> {0}; echo >> {0}",
+        log.display()
+    );
+    cfg.hooks.decision = vec![vec!["/bin/sh".into(), "-c".into(), append.clone()]];
+    cfg.hooks.after_merge = vec![vec!["/bin/sh".into(), "-c".into(), append]];
+    Pipeline::new_with_hooks(&cfg, ScanEngine, CommandHooks::new(&cfg))
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+
+    let logged = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = logged.lines().collect();
+    assert_eq!(lines.len(), 2, "{logged}");
+    let (stage, json) = lines[0].split_once(' ').unwrap();
+    assert_eq!(stage, "decision");
+    let payload: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(payload["decision"]["tier"], "Scan");
+    assert_eq!(payload["probe"]["input"]["page_count"], 20);
+    let (stage, json) = lines[1].split_once(' ').unwrap();
+    assert_eq!(stage, "after_merge");
+    let payload: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert!(payload["markdown"]
+        .as_str()
+        .unwrap()
+        .contains("Pages 11-20."));
+
+    cfg.hooks.after_merge = vec![vec![
+        "/bin/sh".into(),
+        "-c".into(),
+        "echo 'transcript too short' >&2; exit 3".into(),
+    ]];
+    let err = Pipeline::new_with_hooks(&cfg, ScanEngine, CommandHooks::new(&cfg))
+        .run_job(&dir.join("in.pdf"), &dir.join("job2"))
+        .err()
+        .unwrap();
+    let err = format!("{err:#}");
+    assert!(err.contains("hook after_merge"), "{err}");
+    assert!(err.contains("transcript too short"), "{err}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,13 +1,14 @@
 use quack_check::{
     config::Config,
-    policy::{decide, QualityTier},
-    probe::{ProbeInput, ProbeResult, ProbeSampleStats},
+    policy::{decide, validate_tier_overrides, QualityTier},
+    probe::{ProbeInput, ProbeResult, ProbeSampleStats, ProbeStructure},
 };
 
 fn mk_probe(avg: u32, garbage: f32, ws: f32, pages: u32) -> ProbeResult {
     ProbeResult {
         input: ProbeInput {
             path: "x.pdf".into(),
+                canonical_path: None,
             file_bytes: 1,
             page_count: pages,
         },
@@ -17,6 +18,8 @@ fn mk_probe(avg: u32, garbage: f32, ws: f32, pages: u32) -> ProbeResult {
             garbage_ratio: garbage,
             whitespace_ratio: ws,
         },
+        structure: ProbeStructure::default(),
+        render: None,
     }
 }
 
@@ -36,3 +39,60 @@ fn scan_classification() {
     assert!(matches!(d.tier, QualityTier::Scan));
     assert!(d.do_ocr);
 }
+
+#[test]
+fn forced_tier_records_the_auto_tier() {
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    let d = decide(&cfg, &mk_probe(5000, 0.0, 0.2, 300));
+    assert_eq!(d.tier, QualityTier::Scan);
+    assert_eq!(d.auto_tier, Some(QualityTier::HighText));
+    assert_eq!(
+        d.tier_mismatch.as_deref(),
+        Some("classification.forced_tier = SCAN but the probe suggests HIGH_TEXT")
+    );
+    let json = serde_json::to_value(&d).unwrap();
+    assert_eq!(json["auto_tier"], "HighText");
+
+    let d = decide(&cfg, &mk_probe(10, 0.0, 0.1, 50));
+    assert_eq!(d.auto_tier, Some(QualityTier::Scan));
+    assert_eq!(d.tier_mismatch, None);
+
+    cfg.classification.forced_tier = "AUTO".into();
+    let json = serde_json::to_value(decide(&cfg, &mk_probe(10, 0.0, 0.1, 50))).unwrap();
+    assert!(json.get("auto_tier").is_none());
+}
+
+#[test]
+fn tier_overrides_attach_to_decision() {
+    let mut cfg = Config::default();
+    cfg.docling.tier_overrides.insert(
+        "SCAN".into(),
+        [("ocr.force_full_page_ocr".to_string(), serde_json::json!(true))]
+            .into_iter()
+            .collect(),
+    );
+    cfg.docling.tier_overrides.insert(
+        "MIXED_TEXT".into(),
+        [("do_ocr".to_string(), serde_json::json!(true))]
+            .into_iter()
+            .collect(),
+    );
+    assert!(validate_tier_overrides(&cfg).is_ok());
+
+    let scan = decide(&cfg, &mk_probe(10, 0.0, 0.1, 50));
+    assert_eq!(
+        scan.pipeline_overrides.get("ocr.force_full_page_ocr"),
+        Some(&serde_json::json!(true))
+    );
+
+    let mixed = decide(&cfg, &mk_probe(500, 0.0, 0.2, 50));
+    assert!(matches!(mixed.tier, QualityTier::MixedText));
+    assert!(mixed.do_ocr);
+
+    let high = decide(&cfg, &mk_probe(5000, 0.0, 0.2, 50));
+    assert!(high.pipeline_overrides.is_empty());
+
+    cfg.docling.tier_overrides.insert("SCANNED".into(), Default::default());
+    assert!(validate_tier_overrides(&cfg).is_err());
+}
@@ -0,0 +1,158 @@
+use quack_check::{
+    chunk_plan::ChunkPlan,
+    config::Config,
+    policy::{decide, fallback_chain},
+    probe::{ProbeInput, ProbeResult, ProbeSampleStats, ProbeStructure},
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)]
+struct Cases {
+    case: Vec<Case>,
+}
+
+#[derive(Deserialize)]
+struct Case {
+    name: String,
+    probe: Probe,
+    #[serde(default)]
+    config: Option<toml::Table>,
+    expect: Expect,
+}
+
+#[derive(Deserialize)]
+struct Probe {
+    pages: u32,
+    #[serde(default = "one")]
+    bytes: u64,
+    avg_chars: u32,
+    garbage: f32,
+    whitespace: f32,
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+/// Every field is optional; a case checks only what it names.
+#[derive(Deserialize)]
+struct Expect {
+    tier: Option<String>,
+    /// The tier AUTO would have chosen under a forced tier; `""` for none.
+    auto_tier: Option<String>,
+    engine: Option<String>,
+    do_ocr: Option<bool>,
+    chunks: Option<Vec<(u32, u32)>>,
+    fallback: Option<Vec<String>>,
+    features: Option<BTreeMap<String, String>>,
+    overrides: Option<BTreeMap<String, serde_json::Value>>,
+}
+
+fn one() -> u64 {
+    1
+}
+
+/// `overrides` merged table-by-table over the default config.
+fn config(overrides: Option<toml::Table>) -> Config {
+    fn merge(base: &mut toml::Table, over: toml::Table) {
+        for (key, value) in over {
+            match (base.get_mut(&key), value) {
+                (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge(b, o),
+                (_, value) => {
+                    base.insert(key, value);
+                }
+            }
+        }
+    }
+    let mut table = toml::Table::try_from(Config::default()).unwrap();
+    merge(&mut table, overrides.unwrap_or_default());
+    table.try_into().unwrap()
+}
+
+fn probe(p: &Probe) -> ProbeResult {
+    ProbeResult {
+        input: ProbeInput {
+            path: "x.pdf".into(),
+            canonical_path: None,
+            file_bytes: p.bytes,
+            page_count: p.pages,
+        },
+        sample: ProbeSampleStats {
+            sampled_pages: p.pages.min(10),
+            avg_chars_per_page: p.avg_chars,
+            garbage_ratio: p.garbage,
+            whitespace_ratio: p.whitespace,
+        },
+        structure: ProbeStructure {
+            risky_features: p.features.clone(),
+            ..Default::default()
+        },
+        render: None,
+    }
+}
+
+/// Runs every case and reports all mismatches at once.
+#[test]
+fn policy_cases() {
+    let cases: Cases = toml::from_str(include_str!("fixtures/policy_cases.toml")).unwrap();
+    let mut failures = Vec::new();
+    for case in cases.case {
+        let cfg = config(case.config);
+        let probe = probe(&case.probe);
+        let decision = decide(&cfg, &probe);
+        let expect = case.expect;
+        let mut check = |what: &str, want: Option<String>, got: String| {
+            if let Some(want) = want
+                && want != got
+            {
+                failures.push(format!("{}: {what}: want {want}, got {got}", case.name));
+            }
+        };
+
+        check("tier", expect.tier, decision.tier.config_key().to_string());
+        check(
+            "auto_tier",
+            expect.auto_tier,
+            decision
+                .auto_tier
+                .map_or(String::new(), |t| t.config_key().to_string()),
+        );
+        check("engine", expect.engine, decision.chosen_engine.clone());
+        check(
+            "do_ocr",
+            expect.do_ocr.map(|b| b.to_string()),
+            decision.do_ocr.to_string(),
+        );
+        let plan = ChunkPlan::for_job(&cfg, &probe);
+        let chunks: Vec<(u32, u32)> = plan
+            .chunks
+            .iter()
+            .map(|c| (c.start_page, c.end_page))
+            .collect();
+        check(
+            "chunks",
+            expect.chunks.map(|c| format!("{c:?}")),
+            format!("{chunks:?}"),
+        );
+        check(
+            "fallback",
+            expect.fallback.map(|f| format!("{f:?}")),
+            format!("{:?}", fallback_chain(&cfg, &decision)),
+        );
+        let features: BTreeMap<String, String> = decision
+            .pdf_features
+            .iter()
+            .map(|f| (f.feature.clone(), f.action.clone()))
+            .collect();
+        check(
+            "features",
+            expect.features.map(|f| format!("{f:?}")),
+            format!("{features:?}"),
+        );
+        check(
+            "overrides",
+            expect.overrides.map(|o| format!("{o:?}")),
+            format!("{:?}", decision.pipeline_overrides),
+        );
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -1,4 +1,11 @@
-use quack_check::{config::Config, postprocess::merge_markdown};
+use quack_check::{
+    config::Config,
+    postprocess::{
+        handle_embedded_images, mark_pages, merge_markdown, replace_page_content,
+        strip_page_markers, unreadable_page_placeholder, EmbeddedImageStats, MergeOptions,
+        PostprocessContext, PAGE_BREAK_PLACEHOLDER,
+    },
+};
 
 #[test]
 fn removes_repeated_lines() {
@@ -16,6 +23,39 @@ fn removes_repeated_lines() {
     assert!(!merged.contains("BOOK TITLE"));
 }
 
+#[test]
+fn chunk_window_scope_counts_repeats_near_each_chunk() {
+    let mut cfg = Config::default();
+    cfg.postprocess.remove_repeated_lines = true;
+    cfg.postprocess.repeated_line_min_occurrences = 2;
+    cfg.postprocess.repeated_line_scope = "chunk_window".into();
+    cfg.postprocess.repeated_line_chunk_window = 1;
+
+    // "Amen." closes the first and last chunks; "PART ONE" heads three neighbours.
+    let parts = vec![
+        "PART ONE\nAmen.".to_string(),
+        "PART ONE\nMiddle".to_string(),
+        "PART ONE\nMore".to_string(),
+        "Later".to_string(),
+        "Amen.".to_string(),
+    ];
+
+    let merged = merge_markdown(&cfg, parts.clone()).unwrap();
+    assert_eq!(merged.matches("Amen.").count(), 2, "{merged}");
+    assert!(!merged.contains("PART ONE"), "{merged}");
+    assert!(!merged.contains("quack:part"), "{merged}");
+
+    cfg.postprocess.repeated_line_scope = "document".into();
+    let merged = merge_markdown(&cfg, parts).unwrap();
+    assert!(!merged.contains("Amen."), "{merged}");
+
+    cfg.postprocess.repeated_line_scope = "book".into();
+    let err = merge_markdown(&cfg, vec!["x".into()]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unknown postprocess.repeated_line_scope"));
+}
+
 #[test]
 fn sanitizes_control_chars() {
     let cfg = Config::default();
@@ -28,3 +68,74 @@ fn sanitizes_control_chars() {
     assert!(merged.contains('\n'));
     assert!(merged.contains('\t'));
 }
+
+#[test]
+fn externalizes_embedded_images() {
+    let mut cfg = Config::default();
+    cfg.postprocess.embedded_images = "externalize".into();
+    let md = "Intro\n![fig](data:image/png;base64,aGVsbG8=)\nOutro";
+
+    let mut images = Vec::new();
+    let mut stats = EmbeddedImageStats::default();
+    let out = handle_embedded_images(&cfg, md, &mut images, &mut stats).unwrap();
+
+    assert_eq!(out, "Intro\n![fig](images/image_00001.png)\nOutro");
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].bytes, b"hello");
+    assert_eq!(stats.externalized, 1);
+    assert!(stats.markdown_bytes_removed > 0);
+
+    cfg.postprocess.embedded_images = "strip".into();
+    let out = handle_embedded_images(&cfg, md, &mut Vec::new(), &mut stats).unwrap();
+    assert_eq!(out, "Intro\n<!-- image -->\nOutro");
+}
+
+#[test]
+fn a_context_is_checked_once_and_reused() {
+    let mut cfg = Config::default();
+    cfg.postprocess.remove_by_regex = true;
+    cfg.postprocess.embedded_images = "strip".into();
+    let ctx = PostprocessContext::new(&cfg).unwrap();
+    let parts = [
+        "page 1\nFirst ![x](data:image/png;base64,aGVsbG8=)",
+        "Second\n2 / 9",
+    ];
+    let mut stats = EmbeddedImageStats::default();
+    for part in parts {
+        let stripped = ctx.handle_embedded_images(part, &mut Vec::new(), &mut stats);
+        let merged = ctx
+            .merge(vec![stripped.clone()], MergeOptions::default())
+            .unwrap();
+        assert_eq!(merged, merge_markdown(&cfg, vec![stripped]).unwrap());
+        assert!(
+            !merged.contains("page 1") && !merged.contains("2 / 9"),
+            "{merged}"
+        );
+    }
+    assert_eq!(stats.stripped, 1);
+
+    // Bad settings fail when the context is built, before any chunk is merged.
+    cfg.postprocess.regex.patterns = vec!["(unclosed".into()];
+    assert!(PostprocessContext::new(&cfg).is_err());
+    cfg.postprocess.remove_by_regex = false;
+    assert!(PostprocessContext::new(&cfg).is_ok());
+    cfg.postprocess.embedded_images = "inline".into();
+    let err = PostprocessContext::new(&cfg).unwrap_err().to_string();
+    assert!(err.contains("unknown postprocess.embedded_images"), "{err}");
+}
+
+#[test]
+fn replaces_unreadable_page_with_placeholder() {
+    let chunk = format!(
+        "Page ten.\n\n{p}\n\nGarbled text\n\n{p}\n\nPage twelve.",
+        p = PAGE_BREAK_PLACEHOLDER
+    );
+    let marked = mark_pages(&chunk, 10);
+    let placeholder = unreadable_page_placeholder(11, "broken --> xref\nstream");
+    let out = strip_page_markers(&replace_page_content(&marked, 11, &placeholder));
+
+    assert_eq!(
+        out,
+        "Page ten.\n\n<!-- page 11 unreadable: broken -> xref stream -->\n\nPage twelve."
+    );
+}
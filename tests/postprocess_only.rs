@@ -0,0 +1,83 @@
+use quack_check::{
+    config::Config,
+    pipeline::{postprocess_only, EXTERNAL_ENGINE},
+};
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn external_text_is_split_into_pages() {
+    let mut cfg = Config::default();
+    cfg.postprocess.keep_page_markers = true;
+    let md = "# Report\n\n<!-- quack:page 3 -->\n\nFirst page.\n\n<!-- quack:chunk index=0 pages=3-5 engine=docling -->\n<!-- quack:page 4 -->\n\nSecond page.\n\n<!-- quack:page 5 -->\n\nThird page.\n";
+    let out = postprocess_only(&cfg, Path::new("other.md"), md).unwrap();
+    let report = &out.report;
+    assert_eq!(report.source.as_deref(), Some("postprocess"));
+    assert_eq!(report.decision.chosen_engine, EXTERNAL_ENGINE);
+    assert_eq!(report.input.page_count, 5);
+    assert_eq!(report.chunk_reports.len(), 1);
+    assert_eq!(
+        (
+            report.chunk_reports[0].start_page,
+            report.chunk_reports[0].end_page
+        ),
+        (3, 5)
+    );
+    let first = out.markdown.find("<!-- quack:page 3 -->").unwrap();
+    assert!(
+        first < out.markdown.find("# Report").unwrap(),
+        "{}",
+        out.markdown
+    );
+    assert!(out
+        .markdown
+        .contains("<!-- quack:page 5 -->\n\nThird page."));
+    assert_eq!(out.markdown.matches("quack:chunk").count(), 0);
+    assert!(out.text.contains("Second page."));
+
+    // pdftotext output: a form feed after every page.
+    let out = postprocess_only(&cfg, Path::new("other.txt"), "One.\n\x0cTwo.\n\x0c").unwrap();
+    assert_eq!(out.report.input.page_count, 2);
+    assert!(
+        out.markdown.contains("<!-- quack:page 2 -->\n\nTwo."),
+        "{}",
+        out.markdown
+    );
+
+    let out = postprocess_only(&cfg, Path::new("other.txt"), "Just text.").unwrap();
+    assert_eq!(out.report.input.page_count, 1);
+    assert!(out.text.contains("Just text."));
+}
+
+#[test]
+fn the_command_writes_final_outputs() {
+    let dir = std::env::temp_dir().join(format!("quack-postprocess-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("quack-check.example.toml"),
+        dir.join("quack-check.toml"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("raw.md"), "Page one.\n\x0cPage two.\n").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_quack-check"))
+        .current_dir(&dir)
+        .args(["--json", "postprocess", "--input", "raw.md"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let summary: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let job_dir = dir.join(summary["job_dir"].as_str().unwrap());
+    let transcript = std::fs::read_to_string(job_dir.join("final/transcript.md")).unwrap();
+    assert!(transcript.contains("Page two."), "{transcript}");
+    let report: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(job_dir.join("final/report.json")).unwrap()).unwrap();
+    assert_eq!(report["source"], "postprocess");
+    assert!(job_dir.join("index.json").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,115 @@
+use quack_check::{
+    pagenum::{detect_printed_page_numbers, relabel_page_markers},
+    postprocess::{page_marker, parse_page_marker},
+};
+
+fn doc(pages: &[&str]) -> String {
+    pages
+        .iter()
+        .enumerate()
+        .map(|(i, body)| format!("{}\n\n{body}\n", page_marker(i as u32 + 1)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn table(md: &str, page_count: u32) -> Vec<(u32, String, String)> {
+    detect_printed_page_numbers(md, page_count)
+        .pages
+        .into_iter()
+        .map(|p| (p.pdf_page, p.printed, p.source))
+        .collect()
+}
+
+#[test]
+fn footers_map_pdf_pages_to_printed_numbers_with_front_matter_and_gaps() {
+    let md = doc(&[
+        "# A Title",
+        "Preface text.\n\nii",
+        "More preface.\n\niii",
+        "CHAPTER ONE\n\nIt began.",
+        "Body text.\n\n- 2 -",
+        "Body text.\n\n3",
+        "Body text.\n\n4",
+    ]);
+    let stats = detect_printed_page_numbers(&md, 7);
+    assert_eq!(
+        table(&md, 7),
+        vec![
+            (2, "ii".to_string(), "detected".to_string()),
+            (3, "iii".into(), "detected".into()),
+            (5, "2".into(), "detected".into()),
+            (6, "3".into(), "detected".into()),
+            (7, "4".into(), "detected".into()),
+        ]
+    );
+    assert_eq!(
+        (stats.detected, stats.inferred, stats.unnumbered),
+        (5, 0, 2)
+    );
+}
+
+#[test]
+fn unnumbered_pages_between_consistent_neighbours_are_inferred() {
+    let md = doc(&[
+        "Text.\n\n11",
+        "Text.\n\n12",
+        "CHAPTER TWO\n\nOpening page without a folio.",
+        "14 THE RUNNING HEAD\n\nText.",
+        "THE RUNNING HEAD 15\n\nText.",
+    ]);
+    let stats = detect_printed_page_numbers(&md, 5);
+    assert_eq!(stats.printed(3), Some("13"));
+    assert_eq!(stats.pages[2].source, "inferred");
+    assert_eq!(stats.printed(4), Some("14"));
+    assert_eq!(stats.printed(5), Some("15"));
+    assert_eq!(
+        (stats.detected, stats.inferred, stats.unnumbered),
+        (4, 1, 0)
+    );
+}
+
+#[test]
+fn isolated_numbers_without_support_are_ignored() {
+    let md = doc(&[
+        "The answer was\n\n42",
+        "Nothing here.",
+        "Nor here.",
+        "The year was\n\n1066",
+    ]);
+    assert!(table(&md, 4).is_empty());
+}
+
+#[test]
+fn chunks_without_page_breaks_are_not_treated_as_pages() {
+    let md = format!(
+        "{}\n\nText.\n\n1\n\nText.\n\n2\n\n{}\n\nText.\n\n3",
+        page_marker(1),
+        page_marker(3)
+    );
+    let stats = detect_printed_page_numbers(&md, 3);
+    assert!(stats.pages.is_empty(), "{:?}", stats.pages);
+}
+
+#[test]
+fn relabeled_markers_keep_the_pdf_page_readable() {
+    let md = doc(&["Text.\n\n7", "Text.\n\n8", "Text."]);
+    let stats = detect_printed_page_numbers(&md, 3);
+    let relabeled = relabel_page_markers(&md, &stats);
+    let markers: Vec<&str> = relabeled
+        .lines()
+        .filter(|l| l.starts_with("<!-- quack:page"))
+        .collect();
+    assert_eq!(
+        markers,
+        [
+            "<!-- quack:page 7 (pdf 1) -->",
+            "<!-- quack:page 8 (pdf 2) -->",
+            "<!-- quack:page - (pdf 3) -->",
+        ]
+    );
+    let pages: Vec<u32> = markers
+        .iter()
+        .filter_map(|l| parse_page_marker(l))
+        .collect();
+    assert_eq!(pages, [1, 2, 3]);
+}
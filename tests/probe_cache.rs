@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    probe::{read_probe_json, ProbeCache},
+};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+struct CountingEngine {
+    probes: Arc<AtomicUsize>,
+}
+
+impl Engine for CountingEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, sample: &Classification) -> Result<ProbeOut> {
+        self.probes.fetch_add(1, Ordering::SeqCst);
+        Ok(ProbeOut {
+            page_count: 30,
+            sampled_pages: sample.sample_pages,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Body text.".into(),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-probe-cache-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7 probe cache").unwrap();
+    let mut cfg = Config::default();
+    cfg.paths.cache_dir = dir.join("cache").display().to_string();
+    (cfg, dir)
+}
+
+fn cache_files(cfg: &Config) -> Vec<PathBuf> {
+    std::fs::read_dir(Path::new(&cfg.paths.cache_dir).join("probes"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect()
+}
+
+#[test]
+fn second_probe_of_the_same_input_is_served_from_cache() {
+    let (cfg, dir) = setup("hit");
+    let engine = CountingEngine::default();
+    let cache = ProbeCache::from_config(&cfg).unwrap();
+    let input = dir.join("in.pdf");
+
+    let first = cache.probe(&cfg, &engine, &input).unwrap();
+    let copy = dir.join("copy.pdf");
+    std::fs::copy(&input, &copy).unwrap();
+    let second = cache.probe(&cfg, &engine, &copy).unwrap();
+
+    assert_eq!(engine.probes.load(Ordering::SeqCst), 1);
+    assert_eq!(second.input.page_count, first.input.page_count);
+    assert_eq!(second.input.path, copy.display().to_string());
+    assert_eq!(cache_files(&cfg).len(), 1);
+}
+
+#[test]
+fn sample_settings_and_ttl_invalidate_entries() {
+    let (mut cfg, dir) = setup("miss");
+    let engine = CountingEngine::default();
+    let input = dir.join("in.pdf");
+
+    ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &input)
+        .unwrap();
+    cfg.classification.pin_sample_pages(5);
+    let pinned = ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &input)
+        .unwrap();
+    assert_eq!(pinned.sample.sampled_pages, 5);
+    assert_eq!(engine.probes.load(Ordering::SeqCst), 2);
+
+    // Age every entry past the TTL.
+    for path in cache_files(&cfg) {
+        let mut entry: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        entry["created_unix"] = 0.into();
+        std::fs::write(&path, entry.to_string()).unwrap();
+    }
+    ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &input)
+        .unwrap();
+    assert_eq!(engine.probes.load(Ordering::SeqCst), 3);
+
+    cfg.classification.probe_cache_ttl_seconds = 0;
+    assert!(ProbeCache::from_config(&cfg).is_none());
+}
+
+#[test]
+fn limits_still_apply_to_cached_probes() {
+    let (mut cfg, dir) = setup("limits");
+    let engine = CountingEngine::default();
+    let input = dir.join("in.pdf");
+    ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &input)
+        .unwrap();
+
+    cfg.limits.max_input_pages = 10;
+    let err = ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &input)
+        .unwrap_err();
+    assert!(err.to_string().contains("max_input_pages"), "{err:#}");
+    assert_eq!(engine.probes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn pipeline_reuses_a_cached_probe() {
+    let (cfg, dir) = setup("pipeline");
+    let input = dir.join("in.pdf");
+    let engine = CountingEngine::default();
+    let probes = engine.probes.clone();
+    ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &input)
+        .unwrap();
+
+    let pipeline = Pipeline::new(&cfg, engine).with_probe_cache(ProbeCache::from_config(&cfg));
+    pipeline.run_job(&input, &dir.join("job")).unwrap();
+    assert_eq!(probes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn probe_json_accepts_classify_output_or_a_bare_probe() {
+    let (cfg, dir) = setup("json");
+    let engine = CountingEngine::default();
+    let probe = ProbeCache::from_config(&cfg)
+        .unwrap()
+        .probe(&cfg, &engine, &dir.join("in.pdf"))
+        .unwrap();
+
+    let classify = dir.join("classify.json");
+    std::fs::write(
+        &classify,
+        serde_json::json!({ "input": "in.pdf", "probe": probe, "decision": {} }).to_string(),
+    )
+    .unwrap();
+    assert_eq!(read_probe_json(&classify).unwrap().input.page_count, 30);
+
+    let bare = dir.join("probe.json");
+    std::fs::write(&bare, serde_json::to_string(&probe).unwrap()).unwrap();
+    assert_eq!(read_probe_json(&bare).unwrap().sample.sampled_pages, 12);
+
+    std::fs::write(&bare, "{}").unwrap();
+    assert!(read_probe_json(&bare).is_err());
+}
@@ -0,0 +1,51 @@
+#![cfg(unix)]
+
+use quack_check::{config::Config, engine::python::PythonEngine, pipeline::Pipeline};
+
+/// The pypdf scripts run under `native_text.python_exe`, so a text-only job succeeds
+/// while the Docling interpreter is missing.
+#[test]
+fn pypdf_scripts_use_their_own_interpreter() {
+    let root = std::env::temp_dir().join(format!("quack-pypdf-python-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let scripts = root.join("scripts");
+    std::fs::create_dir_all(&scripts).unwrap();
+    let reply = |json: &str| format!("cat >/dev/null\nprintf '%s' '{json}'\n");
+    std::fs::write(
+        scripts.join("pdf_probe.py"),
+        reply(
+            r#"{"page_count":1,"sampled_pages":1,"avg_chars_per_page":3000,"garbage_ratio":0.0,"whitespace_ratio":0.1}"#,
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        scripts.join("pdf_text.py"),
+        reply(r#"{"ok":true,"markdown":"Plain text.","warnings":[],"meta":{}}"#),
+    )
+    .unwrap();
+    for name in ["docling_runner.py", "pdf_split.py"] {
+        std::fs::write(scripts.join(name), "exit 1\n").unwrap();
+    }
+    std::fs::write(root.join("in.pdf"), b"%PDF-1.4").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.paths.scripts_dir = scripts.display().to_string();
+    cfg.security.pin_scripts_dir = false;
+    cfg.docling.python_exe = root.join("no-venv/bin/python").display().to_string();
+    cfg.native_text.python_exe = "/bin/sh".into();
+    let out = Pipeline::new(&cfg, PythonEngine::new(&cfg).unwrap())
+        .run_job(&root.join("in.pdf"), &root.join("job"))
+        .unwrap();
+    assert_eq!(out.report.decision.chosen_engine, "native_text");
+    assert!(out.markdown.contains("Plain text."), "{}", out.markdown);
+
+    // Without it, the probe already needs the (missing) Docling interpreter.
+    cfg.native_text.python_exe.clear();
+    let err = Pipeline::new(&cfg, PythonEngine::new(&cfg).unwrap())
+        .run_job(&root.join("in.pdf"), &root.join("job-shared"))
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("spawning python"), "{err:#}");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
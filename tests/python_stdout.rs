@@ -0,0 +1,100 @@
+use quack_check::engine::{python::parse_json_output, ConvertOut};
+
+#[test]
+fn clean_output_has_no_stray_text() {
+    let (out, stray): (ConvertOut, _) =
+        parse_json_output(br#"{"ok":true,"markdown":"Hi.","warnings":[],"meta":{}}"#).unwrap();
+    assert_eq!(out.markdown, "Hi.");
+    assert_eq!(stray, None);
+}
+
+#[test]
+fn prints_around_the_reply_are_returned_as_stray_text() {
+    let stdout = b"Downloading detection model... done\nprogress: 100%\n\
+        {\"ok\":true,\"markdown\":\"Hi.\",\"warnings\":[],\"meta\":{}}\n\
+        atexit: cleaned up\n";
+    let (out, stray): (ConvertOut, _) = parse_json_output(stdout).unwrap();
+    assert_eq!(out.markdown, "Hi.");
+    assert_eq!(
+        stray.as_deref(),
+        Some("Downloading detection model... done\nprogress: 100% atexit: cleaned up")
+    );
+}
+
+#[test]
+fn the_last_matching_value_wins_and_may_span_lines() {
+    let stdout = b"{\"debug\": 1}\n{\n  \"ok\": true,\n  \"markdown\": \"Hi.\",\n  \
+        \"warnings\": [],\n  \"meta\": {\n    \"engine\": \"docling\"\n  }\n}\n";
+    let (out, stray): (ConvertOut, _) = parse_json_output(stdout).unwrap();
+    assert_eq!(out.meta.engine.as_deref(), Some("docling"));
+    assert_eq!(stray.as_deref(), Some("{\"debug\": 1}"));
+}
+
+#[test]
+fn long_stray_text_is_shortened() {
+    let mut stdout = "x".repeat(2000).into_bytes();
+    stdout.extend_from_slice(b"\n{\"ok\":true,\"markdown\":\"\",\"warnings\":[],\"meta\":{}}");
+    let (_, stray): (ConvertOut, _) = parse_json_output(&stdout).unwrap();
+    let stray = stray.unwrap();
+    assert!(stray.len() < 600 && stray.ends_with("..."), "{stray}");
+}
+
+#[test]
+fn output_without_a_reply_is_still_an_error() {
+    let err = parse_json_output::<ConvertOut>(b"Traceback (most recent call last):\n  boom\n")
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("expected value"), "{err}");
+}
+
+#[cfg(unix)]
+#[test]
+fn stray_prints_become_chunk_warnings() {
+    use quack_check::{config::Config, engine::python::PythonEngine, pipeline::Pipeline};
+
+    let root = std::env::temp_dir().join(format!("quack-python-stdout-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let scripts = root.join("scripts");
+    std::fs::create_dir_all(&scripts).unwrap();
+    let script = |chatter: &str, json: &str| {
+        format!("cat >/dev/null\nprintf '%s\\n' '{chatter}'\nprintf '%s' '{json}'\n")
+    };
+    std::fs::write(
+        scripts.join("pdf_probe.py"),
+        script(
+            "probe chatter",
+            r#"{"page_count":1,"sampled_pages":1,"avg_chars_per_page":3000,"garbage_ratio":0.0,"whitespace_ratio":0.1}"#,
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        scripts.join("pdf_text.py"),
+        script(
+            "Warning: font cache rebuilt",
+            r#"{"ok":true,"markdown":"Hello.","warnings":[],"meta":{}}"#,
+        ),
+    )
+    .unwrap();
+    for name in ["docling_runner.py", "pdf_split.py"] {
+        std::fs::write(scripts.join(name), "exit 1\n").unwrap();
+    }
+    std::fs::write(root.join("in.pdf"), b"%PDF-1.4").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.paths.scripts_dir = scripts.display().to_string();
+    cfg.security.pin_scripts_dir = false;
+    cfg.docling.python_exe = "/bin/sh".into();
+    let out = Pipeline::new(&cfg, PythonEngine::new(&cfg).unwrap())
+        .run_job(&root.join("in.pdf"), &root.join("job"))
+        .unwrap();
+
+    assert!(out.markdown.contains("Hello."));
+    assert!(
+        out.report.chunk_reports[0]
+            .warnings
+            .contains(&"stray stdout from pdf_text.py: Warning: font cache rebuilt".to_string()),
+        "{:?}",
+        out.report.chunk_reports[0].warnings
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+}
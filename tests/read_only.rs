@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+};
+use std::path::Path;
+use std::process::Command;
+
+struct TextEngine;
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 1,
+            sampled_pages: 1,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Body text.".into(),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+}
+
+fn entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn read_only_commands_leave_the_tree_untouched() {
+    let dir = std::env::temp_dir().join(format!("quack-read-only-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    std::fs::write(dir.join("in.pdf.gz"), b"").unwrap();
+
+    let mut cfg = Config::default();
+    cfg.logging.write_to_file = true;
+    std::fs::write(dir.join("quack-check.toml"), toml::to_string(&cfg).unwrap()).unwrap();
+    let job_dir = dir.join("out").join("job1");
+    let out = Pipeline::new(&cfg, TextEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(&cfg, &job_dir, &out).unwrap();
+    let before = (entries(&dir), entries(&job_dir));
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_quack-check"))
+            .current_dir(&dir)
+            .arg("--read-only")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let output = run(&["inspect", "--job", "job1", "--json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    for (args, want) in [
+        (
+            &["classify", "--input", "in.pdf.gz"][..],
+            "cannot decompress",
+        ),
+        (
+            &["plan", "--input", "in.pdf", "--write", "plan.json"][..],
+            "cannot write a plan",
+        ),
+        (&["run", "--input", "in.pdf"][..], "only applies to"),
+    ] {
+        let output = run(args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            !output.status.success() && stderr.contains(want),
+            "{stderr}"
+        );
+    }
+    assert_eq!((entries(&dir), entries(&job_dir)), before);
+    let _ = std::fs::remove_dir_all(&dir);
+}
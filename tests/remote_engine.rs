@@ -0,0 +1,245 @@
+#![cfg(feature = "remote")]
+
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        remote::{form_fields, RemoteEngine},
+        ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk,
+    },
+};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A request the fake docling-serve received.
+#[derive(Debug, Clone)]
+struct Seen {
+    line: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Serves the async convert API: the task is "started" on the first poll and done on
+/// the second.
+fn fake_docling_serve() -> (String, Arc<Mutex<Vec<Seen>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        let mut polls = 0;
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let mut headers = Vec::new();
+            loop {
+                let mut h = String::new();
+                reader.read_line(&mut h).unwrap();
+                let h = h.trim_end();
+                if h.is_empty() {
+                    break;
+                }
+                let (k, v) = h.split_once(':').unwrap();
+                headers.push((k.trim().to_ascii_lowercase(), v.trim().to_string()));
+            }
+            let len = headers
+                .iter()
+                .find(|(k, _)| k == "content-length")
+                .map_or(0, |(_, v)| v.parse().unwrap());
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            let line = line.trim_end().to_string();
+            let response = if line.starts_with("POST /v1/convert/file/async") {
+                r#"{"task_id":"t1","task_status":"pending"}"#.to_string()
+            } else if line.starts_with("GET /v1/status/poll/t1") {
+                polls += 1;
+                let status = if polls == 1 { "started" } else { "success" };
+                format!(r#"{{"task_id":"t1","task_status":"{status}"}}"#)
+            } else if line.starts_with("GET /v1/result/t1") {
+                r#"{"document":{"md_content":"Page one.\n\n<!-- quack:page-break -->\n\nPage two."},"status":"partial_success","errors":[{"component_type":"table","error_message":"no structure"}],"processing_time":1.5}"#.to_string()
+            } else {
+                String::new()
+            };
+            log.lock().unwrap().push(Seen {
+                line,
+                headers,
+                body,
+            });
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                response.len()
+            )
+            .unwrap();
+        }
+    });
+    (url, seen)
+}
+
+/// The local side, which must not be asked to run docling.
+struct LocalEngine;
+
+impl Engine for LocalEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        unimplemented!()
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        panic!("docling ran locally")
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn config(url: &str) -> Config {
+    let mut cfg = Config::default();
+    cfg.engine.remote.enabled = true;
+    cfg.engine.remote.url = format!("{url}/");
+    cfg.engine.remote.poll_interval_seconds = 0;
+    // Cargo sets this for test binaries, so it stands in for a real key variable.
+    cfg.engine.remote.api_key_env = "CARGO_PKG_NAME".into();
+    cfg.docling.pipeline.enable_remote_services = true;
+    cfg
+}
+
+fn request(dir: &Path) -> ConvertIn {
+    let pdf = dir.join("in.pdf");
+    std::fs::write(&pdf, b"%PDF-1.7 chunk bytes").unwrap();
+    ConvertIn {
+        input_pdf: pdf.display().to_string(),
+        out_dir: dir.display().to_string(),
+        chunk_index: 2,
+        start_page: 21,
+        end_page: 22,
+        do_ocr: true,
+        pdf_backend: "dlparse_v4".into(),
+        require_pdf_backend: false,
+        use_page_range: true,
+        page_break_placeholder: "<!-- quack:page-break -->".into(),
+        split_spreads: false,
+        tiles: Vec::new(),
+        on_page_error: "fail".into(),
+        pipeline_overrides: [("ocr.engine".to_string(), "easyocr".into())].into(),
+        stderr_log: None,
+        control: Default::default(),
+        attempts: Default::default(),
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-remote-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn chunks_are_uploaded_polled_and_fetched() {
+    let (url, seen) = fake_docling_serve();
+    let dir = temp_dir("convert");
+    let engine = RemoteEngine::new(&config(&url), LocalEngine).unwrap();
+    let out = engine.convert_docling(&request(&dir)).unwrap();
+
+    assert!(out.ok);
+    assert_eq!(
+        out.markdown,
+        "Page one.\n\n<!-- quack:page-break -->\n\nPage two."
+    );
+    assert_eq!(
+        out.warnings,
+        [
+            "docling-serve table: no structure",
+            "docling-serve reported partial_success"
+        ]
+    );
+    assert_eq!(out.meta.engine.as_deref(), Some("docling_remote"));
+    assert_eq!(out.meta.timings.get("convert"), Some(&1.5));
+
+    let seen = seen.lock().unwrap().clone();
+    let lines: Vec<&str> = seen.iter().map(|s| s.line.as_str()).collect();
+    assert_eq!(
+        lines,
+        [
+            "POST /v1/convert/file/async HTTP/1.1",
+            "GET /v1/status/poll/t1 HTTP/1.1",
+            "GET /v1/status/poll/t1 HTTP/1.1",
+            "GET /v1/result/t1 HTTP/1.1",
+        ]
+    );
+    for s in &seen {
+        assert!(
+            s.headers
+                .contains(&("x-api-key".to_string(), "quack-check".to_string())),
+            "{s:?}"
+        );
+    }
+    let upload = String::from_utf8_lossy(&seen[0].body);
+    assert!(upload.contains("filename=\"in.pdf\""), "{upload}");
+    assert!(upload.contains("%PDF-1.7 chunk bytes"));
+    assert!(upload.contains("name=\"page_range\"\r\n\r\n21\r\n"));
+    assert!(upload.contains("name=\"ocr_engine\"\r\n\r\neasyocr\r\n"));
+}
+
+#[test]
+fn form_fields_follow_the_config_and_the_tier_overrides() {
+    let dir = temp_dir("fields");
+    let mut cfg = Config::default();
+    cfg.docling.ocr.langs = vec!["eng".into(), "deu".into()];
+    let mut req = request(&dir);
+    req.use_page_range = false;
+    req.pipeline_overrides = [("do_table_structure".to_string(), false.into())].into();
+    let fields = form_fields(&cfg, &req);
+    let get = |key: &str| -> Vec<&str> {
+        fields
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    };
+    assert_eq!(get("do_table_structure"), ["false"]);
+    assert_eq!(get("ocr_lang"), ["eng", "deu"]);
+    assert_eq!(get("do_ocr"), ["true"]);
+    assert!(get("page_range").is_empty());
+    assert_eq!(get("force_ocr"), ["false"]);
+
+    // Hybrid OCR relies on docling OCRing only bitmap areas, so it is never forced.
+    let force_ocr = |cfg: &Config| {
+        form_fields(cfg, &req)
+            .into_iter()
+            .find(|(k, _)| k == "force_ocr")
+            .map(|(_, v)| v)
+    };
+    cfg.docling.ocr.force_full_page_ocr = true;
+    assert_eq!(force_ocr(&cfg).as_deref(), Some("true"));
+    cfg.docling.ocr.hybrid = true;
+    assert_eq!(force_ocr(&cfg).as_deref(), Some("false"));
+}
+
+#[test]
+fn remote_services_must_be_allowed() {
+    let mut cfg = config("http://127.0.0.1:9");
+    cfg.docling.pipeline.enable_remote_services = false;
+    let Err(err) = RemoteEngine::new(&cfg, LocalEngine) else {
+        panic!("expected an error");
+    };
+    assert!(err.to_string().contains("enable_remote_services"), "{err}");
+
+    let mut cfg = config("http://127.0.0.1:9");
+    cfg.engine.remote.url = "gpu-box:5001".into();
+    assert!(RemoteEngine::new(&cfg, LocalEngine).is_err());
+}
@@ -0,0 +1,81 @@
+use flate2::read::ZlibDecoder;
+use quack_check::{
+    config::Config,
+    render::{encode_png, ink_coverage, sample_pages, Bitmap},
+};
+use std::io::Read;
+
+fn bitmap(width: u32, height: u32, dark: usize) -> Bitmap {
+    let mut rgba = vec![255u8; (width * height * 4) as usize];
+    for px in rgba.chunks_exact_mut(4).take(dark) {
+        px[..3].fill(0);
+    }
+    Bitmap {
+        width,
+        height,
+        rgba,
+    }
+}
+
+#[test]
+fn ink_coverage_counts_dark_pixels() {
+    assert_eq!(ink_coverage(&bitmap(10, 10, 0)), 0.0);
+    assert_eq!(ink_coverage(&bitmap(10, 10, 25)), 0.25);
+    // Transparent black is paper, not ink.
+    let mut clear = bitmap(2, 1, 2);
+    clear.rgba[3] = 0;
+    assert_eq!(ink_coverage(&clear), 0.5);
+}
+
+#[test]
+fn pngs_have_a_valid_header_and_compressed_rows() {
+    let png = encode_png(&bitmap(3, 2, 1));
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 3);
+    assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 2);
+    assert_eq!(&png[24..26], [8, 6]);
+    // IHDR CRC, as zlib's crc32 computes it.
+    assert_eq!(&png[29..33], [0x9d, 0x74, 0x66, 0x1a]);
+    let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+    assert_eq!(&png[37..41], b"IDAT");
+    let mut rows = Vec::new();
+    ZlibDecoder::new(&png[41..41 + idat_len])
+        .read_to_end(&mut rows)
+        .unwrap();
+    assert_eq!(rows.len(), 2 * (1 + 3 * 4));
+    assert_eq!(&rows[..5], [0, 0, 0, 0, 255]);
+    assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xaeB`\x82");
+}
+
+#[test]
+fn the_render_probe_samples_the_same_pages_as_the_text_probe() {
+    let mut cfg = Config::default();
+    cfg.classification.sample_pages = 3;
+    cfg.classification.sample_pages_percent = 0.0;
+    assert_eq!(sample_pages(&cfg, 9), [1, 5, 9]);
+    assert_eq!(sample_pages(&cfg, 2), [1, 2]);
+    assert_eq!(sample_pages(&cfg, 1), [1]);
+    cfg.classification.sample_pages_percent = 10.0;
+    cfg.classification.max_sample_pages = 5;
+    assert_eq!(sample_pages(&cfg, 100), [1, 26, 51, 75, 100]);
+}
+
+#[cfg(not(feature = "pdfium"))]
+#[test]
+fn page_images_need_the_pdfium_feature() {
+    use quack_check::{engine::python::PythonEngine, pipeline::Pipeline};
+
+    let dir = std::env::temp_dir().join(format!("quack-render-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.output.thumbnails = true;
+    let engine = PythonEngine::new(&cfg).unwrap();
+    let err = Pipeline::new(&cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("--features pdfium"), "{err:#}");
+}
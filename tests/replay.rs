@@ -0,0 +1,170 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        ConvertIn, ConvertMeta, ConvertOut, DeviceCheck, DocDiag, Engine, ProbeOut, SplitChunk,
+    },
+    exit_code::ErrorClass,
+    jobs,
+    pipeline::Pipeline,
+    replay::{self, Exchange, RecordingEngine, ReplayEngine},
+};
+use std::path::{Path, PathBuf};
+
+/// Native text fails for the second chunk, which falls back to docling, and there is
+/// no CUDA device.
+struct BookEngine;
+
+impl Engine for BookEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 3000,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!(
+                "# Part {}\n\nDocling pages {}-{}.",
+                req.chunk_index, req.start_page, req.end_page
+            ),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn check_device(&self, device: &str) -> Result<DeviceCheck> {
+        if device == "cuda" {
+            return Err(anyhow::Error::new(ErrorClass::Conversion).context("no CUDA device"));
+        }
+        Ok(DeviceCheck {
+            device: device.to_string(),
+            available: true,
+            detail: None,
+        })
+    }
+
+    fn convert_native_text(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: req.chunk_index == 0,
+            markdown: format!("Native pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta {
+                engine: Some("native_text".into()),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-replay-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7 the original").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "HIGH_TEXT".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.debug.record_engine_io = true;
+    (cfg, dir)
+}
+
+fn record(cfg: &Config, dir: &Path) -> PathBuf {
+    let job_dir = dir.join("job");
+    std::fs::create_dir_all(job_dir.join("final")).unwrap();
+    let recording = job_dir.join("logs").join(replay::RECORDING_FILE);
+    let engine = RecordingEngine::create(BookEngine, &recording).unwrap();
+    let result = Pipeline::new(cfg, engine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    jobs::write_final_outputs(cfg, &job_dir, &result).unwrap();
+    job_dir
+}
+
+#[test]
+fn a_replayed_job_reproduces_its_transcript_from_the_recording() {
+    let (cfg, dir) = setup("match");
+    let job_dir = record(&cfg, &dir);
+    // The original input is gone; the recording is all a replay needs.
+    std::fs::remove_file(dir.join("in.pdf")).unwrap();
+
+    let summary = replay::replay_job(&cfg, &job_dir).unwrap();
+    assert_eq!(summary.transcript_matches, Some(true), "{summary:?}");
+    assert_eq!(summary.exchanges_unused, 0, "{summary:?}");
+    assert_eq!(summary.status, "ok");
+    let replayed = std::fs::read_to_string(
+        job_dir
+            .join(replay::REPLAY_DIR)
+            .join("final")
+            .join(&cfg.output.markdown_filename),
+    )
+    .unwrap();
+    assert!(replayed.contains("Native pages 1-10."), "{replayed}");
+    assert!(replayed.contains("Docling pages 11-20."), "{replayed}");
+}
+
+#[test]
+fn recorded_errors_replay_with_their_class() {
+    let (_, dir) = setup("errors");
+    let recording = dir.join(replay::RECORDING_FILE);
+    let engine = RecordingEngine::create(BookEngine, &recording).unwrap();
+    assert!(engine.check_device("cuda").is_err());
+    assert!(engine.check_device("cpu").unwrap().available);
+
+    let exchanges: Vec<Exchange> = std::fs::read_to_string(&recording)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(exchanges.len(), 2);
+    assert_eq!(exchanges[0].request["device"], "cuda");
+    assert_eq!(exchanges[0].error_class, Some(ErrorClass::Conversion));
+
+    let engine = ReplayEngine::load(&recording).unwrap();
+    let err = engine.check_device("cuda").unwrap_err();
+    assert_eq!(
+        quack_check::exit_code::classify(&err),
+        ErrorClass::Conversion
+    );
+    assert!(format!("{err:#}").contains("no CUDA device"), "{err:#}");
+    assert!(engine.check_device("cpu").unwrap().available);
+    assert_eq!(engine.usage(), (2, 0));
+
+    // Asked again, the replay has nothing left: the run diverged from the recording.
+    let err = engine.check_device("cpu").unwrap_err().to_string();
+    assert!(
+        err.contains("no recorded engine response left for check_device"),
+        "{err}"
+    );
+}
+
+#[test]
+fn replaying_a_job_without_a_recording_says_how_to_record_one() {
+    let (mut cfg, dir) = setup("unrecorded");
+    cfg.debug.record_engine_io = false;
+    let job_dir = dir.join("job");
+    std::fs::create_dir_all(&job_dir).unwrap();
+    let err = replay::replay_job(&cfg, &job_dir).unwrap_err().to_string();
+    assert!(err.contains("debug.record_engine_io = true"), "{err}");
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{
+        ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, PageError, ProbeOut, SplitChunk,
+    },
+    pipeline::Pipeline,
+    retention::{self, Rule},
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Splits into real files, loses a page in the second chunk, and leaves a rendered
+/// page image behind.
+struct SplitEngine;
+
+impl Engine for SplitEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, out_dir: &Path, ranges: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        std::fs::write(out_dir.join("page_0001.png"), b"png")?;
+        let mut chunks = Vec::new();
+        for (i, r) in ranges.iter().enumerate() {
+            let path = out_dir.join(format!(
+                "chunk_{i:05}_p{:05}-p{:05}.pdf",
+                r.start_page, r.end_page
+            ));
+            std::fs::write(&path, b"%PDF-1.7")?;
+            let page_errors = if i == 1 {
+                vec![PageError {
+                    page: r.start_page,
+                    reason: "bad page".into(),
+                }]
+            } else {
+                vec![]
+            };
+            chunks.push(SplitChunk {
+                chunk_index: i as u32,
+                start_page: r.start_page,
+                end_page: r.end_page,
+                path: path.display().to_string(),
+                page_errors,
+            });
+        }
+        Ok(chunks)
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Text.".into(),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-retention-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    cfg.limits.on_page_error = "placeholder".into();
+    (cfg, dir)
+}
+
+fn names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn rules_parse_and_unknown_rules_are_rejected() {
+    assert_eq!(Rule::parse("keep"), Some(Rule::Keep));
+    assert_eq!(Rule::parse("failed"), Some(Rule::Failed));
+    assert_eq!(Rule::parse("30d"), Some(Rule::Days(30)));
+    assert_eq!(Rule::parse("30"), None);
+
+    let mut cfg = Config::default();
+    cfg.chunking.keep_split_pdfs = false;
+    assert_eq!(retention::rule(&cfg, "split_pdfs").unwrap(), Rule::Delete);
+    cfg.retention.chunk_json = "sometimes".into();
+    let err = retention::validate(&cfg).unwrap_err().to_string();
+    assert!(err.contains("retention.chunk_json"), "{err}");
+}
+
+#[test]
+fn the_cleanup_stage_keeps_only_what_the_rules_say() {
+    let (mut cfg, dir) = setup("job");
+    cfg.global.keep_intermediates = false;
+    cfg.retention.split_pdfs = "failed".into();
+    cfg.retention.chunk_json = "30d".into();
+    let job_dir = dir.join("job");
+    Pipeline::new(&cfg, SplitEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+
+    // Chunk 1 lost a page, so its split PDF stays; the page image is always deleted.
+    assert_eq!(
+        names(&job_dir.join("chunks")),
+        [
+            "chunk_00000.json",
+            "chunk_00001.json",
+            "chunk_00001_p00011-p00020.pdf"
+        ]
+    );
+
+    let now = SystemTime::now();
+    assert!(retention::expired(&cfg, &job_dir, now).unwrap().is_empty());
+    let later = now + Duration::from_secs(31 * 24 * 60 * 60);
+    let expired = retention::expired(&cfg, &job_dir, later).unwrap();
+    let classes: Vec<&str> = expired.iter().map(|a| a.class).collect();
+    assert_eq!(classes, ["chunk_json", "chunk_json"]);
+}
+
+#[test]
+fn keep_intermediates_leaves_everything_for_jobs_clean() {
+    let (mut cfg, dir) = setup("keep");
+    cfg.retention.split_pdfs = "delete".into();
+    let job_dir = dir.join("job");
+    Pipeline::new(&cfg, SplitEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    assert_eq!(names(&job_dir.join("chunks")).len(), 5);
+
+    let expired = retention::expired(&cfg, &job_dir, SystemTime::now()).unwrap();
+    let classes: Vec<&str> = expired.iter().map(|a| a.class).collect();
+    assert_eq!(classes, ["split_pdfs", "split_pdfs", "page_images"]);
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    reuse::ReuseSource,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Hashes pages as given and records which chunks it converted.
+struct ScanEngine {
+    label: &'static str,
+    hashes: Vec<String>,
+    converted: Arc<Mutex<Vec<u32>>>,
+}
+
+impl ScanEngine {
+    fn new(label: &'static str, changed_page: Option<u32>) -> Self {
+        let hashes = (1..=20)
+            .map(|p| match changed_page {
+                Some(c) if c == p => format!("new-{p}"),
+                _ => format!("page-{p}"),
+            })
+            .collect();
+        Self {
+            label,
+            hashes,
+            converted: Arc::default(),
+        }
+    }
+}
+
+impl Engine for ScanEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.converted.lock().unwrap().push(req.chunk_index);
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("{} pages {}-{}.", self.label, req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn page_hashes(&self, _input: &Path) -> Result<Vec<String>> {
+        Ok(self.hashes.clone())
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-reuse-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    (cfg, dir)
+}
+
+#[test]
+fn only_chunks_with_changed_pages_are_converted_again() {
+    let (cfg, dir) = setup("changed");
+    let old_job = dir.join("old-job");
+    Pipeline::new(&cfg, ScanEngine::new("old", None))
+        .run_job(&dir.join("in.pdf"), &old_job)
+        .unwrap();
+
+    let engine = ScanEngine::new("new", Some(15));
+    let converted = engine.converted.clone();
+    let out = Pipeline::new(&cfg, engine)
+        .with_reuse_from(Some(ReuseSource::load(&old_job).unwrap()))
+        .run_job(&dir.join("in.pdf"), &dir.join("new-job"))
+        .unwrap();
+
+    assert_eq!(*converted.lock().unwrap(), [1]);
+    assert!(out.markdown.contains("old pages 1-10."), "{}", out.markdown);
+    assert!(
+        out.markdown.contains("new pages 11-20."),
+        "{}",
+        out.markdown
+    );
+    let reused: Vec<Option<&str>> = out
+        .report
+        .chunk_reports
+        .iter()
+        .map(|c| c.meta.reused_from.as_deref())
+        .collect();
+    assert_eq!(reused, [Some("old-job"), None]);
+}
+
+#[test]
+fn a_different_policy_decision_reuses_nothing() {
+    let (mut cfg, dir) = setup("decision");
+    let old_job = dir.join("old-job");
+    Pipeline::new(&cfg, ScanEngine::new("old", None))
+        .run_job(&dir.join("in.pdf"), &old_job)
+        .unwrap();
+
+    cfg.classification.forced_tier = "MIXED".into();
+    let engine = ScanEngine::new("new", None);
+    let converted = engine.converted.clone();
+    Pipeline::new(&cfg, engine)
+        .with_reuse_from(Some(ReuseSource::load(&old_job).unwrap()))
+        .run_job(&dir.join("in.pdf"), &dir.join("new-job"))
+        .unwrap();
+    assert_eq!(*converted.lock().unwrap(), [0, 1]);
+}
+
+#[test]
+fn jobs_without_page_hashes_cannot_be_reused() {
+    let (mut cfg, dir) = setup("unhashed");
+    cfg.global.record_page_hashes = false;
+    let old_job = dir.join("old-job");
+    Pipeline::new(&cfg, ScanEngine::new("old", None))
+        .run_job(&dir.join("in.pdf"), &old_job)
+        .unwrap();
+    let err = ReuseSource::load(&old_job).unwrap_err().to_string();
+    assert!(err.contains("recorded no page hashes"), "{err}");
+}
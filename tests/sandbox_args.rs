@@ -0,0 +1,84 @@
+use quack_check::{
+    config::Config,
+    engine::sandbox::{bwrap_args, command, SandboxPaths},
+};
+use std::ffi::OsString;
+use std::path::Path;
+
+fn position(args: &[OsString], flag: &str, path: &str) -> Option<usize> {
+    args.windows(3)
+        .position(|w| w[0] == flag && w[1] == path && w[2] == path)
+}
+
+#[test]
+fn bwrap_binds_inputs_read_only_and_outputs_writable() {
+    let mut cfg = Config::default();
+    cfg.paths.work_dir = "/data/work".into();
+    cfg.paths.docling_artifacts_dir = "/data/models".into();
+    let paths = SandboxPaths::none()
+        .read("/data/out/job/chunks/chunk_00000.pdf")
+        .write("/data/out/job/chunks");
+
+    let args = bwrap_args(
+        &cfg,
+        Path::new("/opt/venv/bin/python"),
+        Path::new("/repo/scripts/pdf_text.py"),
+        &paths,
+    )
+    .unwrap();
+
+    assert!(args.iter().any(|a| a == "--unshare-all"));
+    assert!(!args.iter().any(|a| a == "--share-net"));
+    assert!(position(&args, "--ro-bind-try", "/opt/venv").is_some());
+    assert!(position(&args, "--ro-bind-try", "/data/work").is_some());
+    assert!(position(&args, "--ro-bind-try", "/data/models").is_some());
+    let out = position(&args, "--bind", "/data/out/job/chunks").unwrap();
+    let input = position(
+        &args,
+        "--ro-bind-try",
+        "/data/out/job/chunks/chunk_00000.pdf",
+    )
+    .unwrap();
+    assert!(out < input, "parent mounts must come before nested ones");
+    assert_eq!(args.last().unwrap(), "/repo/scripts/pdf_text.py");
+
+    cfg.global.offline_only = false;
+    let args = bwrap_args(
+        &cfg,
+        Path::new("python3"),
+        Path::new("/repo/scripts/pdf_text.py"),
+        &SandboxPaths::none(),
+    )
+    .unwrap();
+    assert!(args.iter().any(|a| a == "--share-net"));
+}
+
+#[test]
+fn wrapper_mode_passes_paths_in_env() {
+    let mut cfg = Config::default();
+    cfg.security.sandbox.mode = "wrapper".into();
+    cfg.security.sandbox.wrapper = vec!["my-jail".into(), "--strict".into()];
+    let cmd = command(
+        &cfg,
+        Path::new("python3"),
+        Path::new("/repo/scripts/pdf_probe.py"),
+        &SandboxPaths::none().write("/data/out"),
+    )
+    .unwrap();
+
+    assert_eq!(cmd.get_program(), "my-jail");
+    let args: Vec<_> = cmd.get_args().collect();
+    assert_eq!(args, ["--strict", "python3", "/repo/scripts/pdf_probe.py"]);
+    let env: Vec<_> = cmd.get_envs().collect();
+    assert!(env.contains(&("QUACK_SANDBOX_RW".as_ref(), Some("/data/out".as_ref()))));
+    assert!(env.contains(&("QUACK_SANDBOX_NET".as_ref(), Some("0".as_ref()))));
+
+    cfg.security.sandbox.mode = "landlock".into();
+    assert!(command(
+        &cfg,
+        Path::new("python3"),
+        Path::new("x.py"),
+        &SandboxPaths::none()
+    )
+    .is_err());
+}
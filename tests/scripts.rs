@@ -0,0 +1,148 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::merge_markdown,
+    script::{self, RLM},
+};
+use std::path::Path;
+
+#[test]
+fn detect_reports_the_dominant_script_and_direction() {
+    let arabic = script::detect("الفصل الأول (Chapter 1) من الكتاب").unwrap();
+    assert_eq!(arabic.script, "arabic");
+    assert_eq!(arabic.direction, "rtl");
+    assert!(arabic.share > 0.5 && arabic.share < 1.0);
+
+    let hebrew = script::detect("שלום עולם").unwrap();
+    assert_eq!(
+        (hebrew.script.as_str(), hebrew.direction.as_str()),
+        ("hebrew", "rtl")
+    );
+
+    let latin = script::detect("Plain English text, 1984.").unwrap();
+    assert_eq!(
+        (latin.script.as_str(), latin.direction.as_str()),
+        ("latin", "ltr")
+    );
+    assert_eq!(latin.share, 1.0);
+
+    assert_eq!(script::detect("漢字の本").unwrap().script, "han");
+    assert_eq!(script::detect("12 — 34").map(|d| d.script), None);
+}
+
+#[test]
+fn spaces_between_cjk_characters_are_removed() {
+    assert_eq!(
+        script::join_cjk_spaces("日 本 語 の テ キ ス ト。 次"),
+        "日本語のテキスト。次"
+    );
+    // Spaces next to Latin words, digits, and Hangul are kept.
+    assert_eq!(
+        script::join_cjk_spaces("第 3 章 Tokyo 東 京\n한국 어"),
+        "第 3 章 Tokyo 東京\n한국 어"
+    );
+}
+
+#[test]
+fn rtl_lines_get_a_mark_after_their_markdown_prefix() {
+    let md = "# مقدمة\n\n- 12 بندًا\n> שלום\nEnglish line\n| جدول |\n```\nكود\n```\n";
+    let marked = script::mark_rtl_lines(md);
+    assert_eq!(
+        marked,
+        format!(
+            "# {RLM}مقدمة\n\n- {RLM}12 بندًا\n> {RLM}שלום\nEnglish line\n| جدول |\n```\nكود\n```\n"
+        )
+    );
+    // Marking twice does not stack marks.
+    assert_eq!(script::mark_rtl_lines(&marked), marked);
+}
+
+#[test]
+fn merging_joins_cjk_and_keeps_bidi_marks() {
+    let mut cfg = Config::default();
+    let parts = vec![format!("漢 字\n\n{RLM}שלום"), "عربي".into()];
+    let merged = merge_markdown(&cfg, parts.clone()).unwrap();
+    assert_eq!(merged, format!("漢字\n\n{RLM}שלום\n\n---\n\nعربي"));
+
+    cfg.postprocess.cjk_spacing = false;
+    cfg.postprocess.rtl_line_marks = true;
+    let merged = merge_markdown(&cfg, parts).unwrap();
+    assert_eq!(merged, format!("漢 字\n\n{RLM}שלום\n\n---\n\n{RLM}عربي"));
+}
+
+/// Returns Arabic for the first chunk and Japanese for the second.
+struct ScriptEngine;
+
+impl Engine for ScriptEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 20,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let markdown = if req.chunk_index == 0 {
+            "هذا نص عربي."
+        } else {
+            "これは 日本語 の 文章 です。"
+        };
+        Ok(ConvertOut {
+            ok: true,
+            markdown: markdown.into(),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn chunk_reports_record_the_detected_script() {
+    let dir = std::env::temp_dir().join(format!("quack-scripts-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+
+    let out = Pipeline::new(&cfg, ScriptEngine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let scripts: Vec<(Option<&str>, Option<&str>)> = out
+        .report
+        .chunk_reports
+        .iter()
+        .map(|c| (c.script.as_deref(), c.direction.as_deref()))
+        .collect();
+    assert_eq!(
+        scripts,
+        [(Some("arabic"), Some("rtl")), (Some("kana"), Some("ltr"))]
+    );
+    assert!(out.markdown.contains("これは日本語の文章です。"));
+}
@@ -0,0 +1,52 @@
+use quack_check::{
+    postprocess::{mark_pages, strip_page_markers, PAGE_BREAK_PLACEHOLDER},
+    sections::{split_sections, SplitSpec},
+    tokens::TokenCounter,
+};
+
+fn marked_book() -> String {
+    let chunk1 = format!(
+        "# Introduction\n\nOpening words.\n\n{p}\n\nMore intro.\n\n{p}\n\n# Chapter One\n\nIt begins.",
+        p = PAGE_BREAK_PLACEHOLDER
+    );
+    let chunk2 = format!("Still chapter one.\n\n{p}\n\n# Chapter Two\n\nThe end.", p = PAGE_BREAK_PLACEHOLDER);
+    format!("{}\n\n---\n\n{}", mark_pages(&chunk1, 1), mark_pages(&chunk2, 4))
+}
+
+#[test]
+fn page_markers_round_trip() {
+    let chunk = format!("Alpha\n\n{}\n\nBeta", PAGE_BREAK_PLACEHOLDER);
+    let marked = mark_pages(&chunk, 7);
+    assert!(marked.contains("<!-- quack:page 7 -->"));
+    assert!(marked.contains("<!-- quack:page 8 -->"));
+    assert_eq!(strip_page_markers(&marked), "Alpha\n\nBeta");
+}
+
+#[test]
+fn splits_by_heading_with_page_ranges() {
+    let spec = SplitSpec::parse("heading_level_1").unwrap().unwrap();
+    let sections = split_sections(&spec, &marked_book(), false, &TokenCounter::approx());
+
+    let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+    assert_eq!(titles, ["Introduction", "Chapter One", "Chapter Two"]);
+    assert_eq!(sections[0].file, "001-introduction.md");
+    assert_eq!((sections[0].start_page, sections[0].end_page), (1, 2));
+    assert_eq!((sections[1].start_page, sections[1].end_page), (3, 4));
+    assert_eq!((sections[2].start_page, sections[2].end_page), (5, 5));
+    assert!(!sections[1].markdown.contains("quack:page"));
+}
+
+#[test]
+fn splits_by_pages() {
+    let spec = SplitSpec::parse("pages:2").unwrap().unwrap();
+    let sections = split_sections(&spec, &marked_book(), false, &TokenCounter::approx());
+    let ranges: Vec<(u32, u32)> = sections.iter().map(|s| (s.start_page, s.end_page)).collect();
+    assert_eq!(ranges, [(1, 2), (3, 4), (5, 5)]);
+}
+
+#[test]
+fn rejects_bad_split_spec() {
+    assert!(SplitSpec::parse("").unwrap().is_none());
+    assert!(SplitSpec::parse("pages:0").is_err());
+    assert!(SplitSpec::parse("chapters").is_err());
+}
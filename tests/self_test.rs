@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    selftest::{fixture_pdf, run, FIXTURE_PHRASE},
+};
+use std::path::Path;
+
+#[test]
+fn fixture_xref_points_at_each_object() {
+    let pdf = fixture_pdf();
+    let text = String::from_utf8(pdf.clone()).unwrap();
+    assert!(text.starts_with("%PDF-1.4\n"));
+    assert!(text.ends_with("%%EOF\n"));
+
+    let startxref: usize = text
+        .rsplit("startxref\n")
+        .next()
+        .and_then(|tail| tail.lines().next())
+        .and_then(|n| n.parse().ok())
+        .unwrap();
+    assert!(text[startxref..].starts_with("xref\n0 8\n"));
+
+    let entries: Vec<&str> = text[startxref..].lines().skip(3).take(7).collect();
+    for (i, entry) in entries.iter().enumerate() {
+        let offset: usize = entry[..10].parse().unwrap();
+        assert!(text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+    }
+    assert_eq!(text.matches(FIXTURE_PHRASE).count(), 2);
+}
+
+/// Reads back the fixture phrase once per page; `docling` is broken.
+struct FakeEngine;
+
+impl Engine for FakeEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        Ok(DocDiag {
+            python_exe: "python3".into(),
+            python_version: "3.12".into(),
+            docling_version: None,
+            ok: true,
+            error: None,
+        })
+    }
+
+    fn probe_pdf(&self, input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        let raw = std::fs::read(input)?;
+        Ok(ProbeOut {
+            page_count: String::from_utf8_lossy(&raw)
+                .matches("/Type /Page ")
+                .count() as u32,
+            sampled_pages: 2,
+            avg_chars_per_page: 2000,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.15,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("split not needed for the fixture"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Err(anyhow!("No module named 'docling'"))
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("{FIXTURE_PHRASE}.\n\n{FIXTURE_PHRASE}."),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+}
+
+#[test]
+fn reports_each_stage() {
+    let dir = std::env::temp_dir().join(format!("quack-self-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let report = run(&Config::default(), FakeEngine, &dir).unwrap();
+    let status: Vec<(&str, bool)> = report
+        .stages
+        .iter()
+        .map(|s| (s.stage.as_str(), s.ok))
+        .collect();
+    assert_eq!(
+        status,
+        vec![
+            ("doctor", true),
+            ("probe", true),
+            ("plan", true),
+            ("convert:docling", false),
+            ("convert:native_text", true),
+            ("pipeline", true),
+        ]
+    );
+    assert!(!report.passed());
+    assert!(report.stages[3].detail.contains("docling"));
+    assert!(dir.join("self-test.pdf").is_file());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,108 @@
+use quack_check::{
+    config::{Config, ExcludedRange},
+    sidecar::{overlay, sidecar_path},
+};
+use std::path::PathBuf;
+
+fn setup(name: &str, sidecar: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("quack-sidecar-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("book.pdf");
+    std::fs::write(&input, b"%PDF-1.7").unwrap();
+    std::fs::write(sidecar_path(&input), sidecar).unwrap();
+    input
+}
+
+#[test]
+fn a_sidecar_overlays_the_config_for_its_input() {
+    let input = setup(
+        "overlay",
+        r#"
+[global]
+job_name = "vol-2"
+
+[classification]
+forced_tier = "SCAN"
+
+[docling.ocr]
+langs = ["deu", "lat"]
+
+[input.exclusions]
+ranges = [{ pages = "1-2", reason = "ads" }]
+"#,
+    );
+    assert_eq!(
+        sidecar_path(&input),
+        input.with_file_name("book.pdf.quack.toml")
+    );
+    let mut cfg = Config::default();
+    cfg.chunking.target_pages_per_chunk = 25;
+    cfg.input.exclusions.ranges = vec![ExcludedRange {
+        pages: "99".into(),
+        reason: String::new(),
+    }];
+
+    let item = overlay(&cfg, &input).unwrap();
+    assert_eq!(item.global.job_name, "vol-2");
+    assert_eq!(item.classification.forced_tier, "SCAN");
+    assert_eq!(item.docling.ocr.langs, ["deu", "lat"]);
+    // Untouched keys keep the main config's values; arrays are replaced.
+    assert_eq!(item.chunking.target_pages_per_chunk, 25);
+    assert_eq!(item.classification.max_avg_chars_per_page_for_scan, 80);
+    let pages: Vec<&str> = item
+        .input
+        .exclusions
+        .ranges
+        .iter()
+        .map(|r| r.pages.as_str())
+        .collect();
+    assert_eq!(pages, ["1-2"]);
+
+    cfg.input.sidecar = false;
+    let off = overlay(&cfg, &input).unwrap();
+    assert_eq!(off.classification.forced_tier, "AUTO");
+
+    let other = input.with_file_name("other.pdf");
+    assert_eq!(
+        overlay(&Config::default(), &other)
+            .unwrap()
+            .classification
+            .forced_tier,
+        "AUTO"
+    );
+}
+
+#[test]
+fn a_sidecar_cannot_change_how_quack_check_runs() {
+    for (name, sidecar, key) in [
+        (
+            "python",
+            "[docling]\npython_exe = \"/tmp/python\"\n",
+            "docling.python_exe",
+        ),
+        (
+            "security",
+            "[security]\nsandbox = \"none\"\n",
+            "security.sandbox",
+        ),
+        ("global", "[global]\nresume = false\n", "global.resume"),
+        ("paths", "[paths]\nout_dir = \"/tmp\"\n", "paths.out_dir"),
+        (
+            "remote",
+            "[engine.remote]\nurl = \"http://example.test\"\n",
+            "engine.remote.url",
+        ),
+    ] {
+        let input = setup(name, sidecar);
+        let err = format!("{:#}", overlay(&Config::default(), &input).unwrap_err());
+        assert!(
+            err.contains(&format!("{key} cannot be set in a sidecar")),
+            "{err}"
+        );
+    }
+
+    let input = setup("typo", "[classification]\nforced_tier = 3\n");
+    let err = format!("{:#}", overlay(&Config::default(), &input).unwrap_err());
+    assert!(err.contains("applying sidecar"), "{err}");
+}
@@ -0,0 +1,170 @@
+#![cfg(feature = "signing")]
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    jobs::write_final_outputs,
+    pipeline::Pipeline,
+    signing::{self, Checksums, CHECKSUMS_FILE, PUBLIC_KEY_FILE, SIGNATURE_FILE},
+};
+use std::path::{Path, PathBuf};
+
+struct TextEngine;
+
+impl Engine for TextEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 1,
+            sampled_pages: 1,
+            avg_chars_per_page: 2500,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        Err(anyhow!("single chunk"))
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        Ok(ConvertOut {
+            ok: true,
+            markdown: "Body text.".into(),
+            warnings: vec![],
+            meta: Default::default(),
+        })
+    }
+}
+
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-signing-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    (Config::default(), dir)
+}
+
+/// Run a job and write its final outputs; returns `final/`.
+fn run(cfg: &Config, dir: &Path) -> PathBuf {
+    let job_dir = dir.join("job");
+    let out = Pipeline::new(cfg, TextEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    write_final_outputs(cfg, &job_dir, &out).unwrap();
+    job_dir.join("final")
+}
+
+#[test]
+fn checksums_cover_every_final_artifact() {
+    let (mut cfg, dir) = setup("sums");
+    let final_dir = run(&cfg, &dir);
+    assert!(!final_dir.join(CHECKSUMS_FILE).exists());
+
+    cfg.output.write_checksums = true;
+    let final_dir = run(&cfg, &dir);
+    let raw = std::fs::read_to_string(final_dir.join(CHECKSUMS_FILE)).unwrap();
+    let sums: Checksums = serde_json::from_str(&raw).unwrap();
+    assert_eq!(sums.algorithm, "sha256");
+    let files: Vec<&str> = sums.files.keys().map(String::as_str).collect();
+    assert!(files.contains(&"transcript.md"), "{files:?}");
+    assert!(files.contains(&"report.json"), "{files:?}");
+    assert!(!final_dir.join(SIGNATURE_FILE).exists());
+    assert!(!signing::verify(&final_dir).unwrap());
+
+    std::fs::write(final_dir.join("transcript.md"), "Edited.").unwrap();
+    let err = signing::verify(&final_dir).unwrap_err().to_string();
+    assert!(err.contains("transcript.md: changed"), "{err}");
+}
+
+#[test]
+fn a_signing_key_signs_the_checksums() {
+    let (mut cfg, dir) = setup("sign");
+    let key_path = dir.join("quack.key");
+    std::fs::write(&key_path, STANDARD.encode([7u8; 32])).unwrap();
+    cfg.security.signing_key_path = key_path.display().to_string();
+    let final_dir = run(&cfg, &dir);
+
+    let sig = std::fs::read_to_string(final_dir.join(SIGNATURE_FILE)).unwrap();
+    let lines: Vec<&str> = sig.lines().collect();
+    assert_eq!(lines.len(), 4, "{sig}");
+    assert!(lines[0].starts_with("untrusted comment: "));
+    assert_eq!(&STANDARD.decode(lines[1]).unwrap()[..2], b"ED");
+    assert!(lines[2].starts_with("trusted comment: timestamp:"));
+    assert!(lines[2].ends_with("\tfile:checksums.json"));
+    assert!(signing::verify(&final_dir).unwrap());
+
+    // Editing a digest to match an edited transcript breaks the signature.
+    let path = final_dir.join(CHECKSUMS_FILE);
+    let mut sums: Checksums =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::write(final_dir.join("transcript.md"), "Edited.").unwrap();
+    sums.files.insert(
+        "transcript.md".into(),
+        quack_check::util::sha256_hex(b"Edited."),
+    );
+    std::fs::write(&path, serde_json::to_string_pretty(&sums).unwrap()).unwrap();
+    let err = signing::verify(&final_dir).unwrap_err().to_string();
+    assert!(err.contains("signature does not match"), "{err}");
+}
+
+/// An unencrypted minisign secret key for `seed`, with `kdf` as its KDF algorithm id.
+fn minisign_secret_key(key_id: [u8; 8], seed: [u8; 32], kdf: &[u8; 2]) -> String {
+    let public = ed25519_dalek::SigningKey::from_bytes(&seed).verifying_key();
+    let mut blob = b"Ed".to_vec();
+    blob.extend_from_slice(kdf);
+    blob.extend_from_slice(b"B2");
+    blob.extend_from_slice(&[0; 48]);
+    blob.extend_from_slice(&key_id);
+    blob.extend_from_slice(&seed);
+    blob.extend_from_slice(public.as_bytes());
+    blob.extend_from_slice(&[0; 32]);
+    format!(
+        "untrusted comment: minisign secret key\n{}\n",
+        STANDARD.encode(blob)
+    )
+}
+
+#[test]
+fn minisign_keys_keep_their_key_id() {
+    let (mut cfg, dir) = setup("minisign");
+    let key_id = [1, 2, 3, 4, 5, 6, 7, 8];
+    let key_path = dir.join("minisign.key");
+    std::fs::write(&key_path, minisign_secret_key(key_id, [9; 32], &[0, 0])).unwrap();
+    cfg.security.signing_key_path = key_path.display().to_string();
+    let final_dir = run(&cfg, &dir);
+
+    let public = std::fs::read_to_string(final_dir.join(PUBLIC_KEY_FILE)).unwrap();
+    assert!(
+        public.starts_with("untrusted comment: minisign public key 0807060504030201\n"),
+        "{public}"
+    );
+    let blob = STANDARD.decode(public.lines().nth(1).unwrap()).unwrap();
+    assert_eq!(&blob[..2], b"Ed");
+    assert_eq!(blob[2..10], key_id);
+    assert!(signing::verify(&final_dir).unwrap());
+
+    std::fs::write(&key_path, minisign_secret_key(key_id, [9; 32], b"Sc")).unwrap();
+    let job_dir = dir.join("job");
+    let out = Pipeline::new(&cfg, TextEngine)
+        .run_job(&dir.join("in.pdf"), &job_dir)
+        .unwrap();
+    let err = format!(
+        "{:#}",
+        write_final_outputs(&cfg, &job_dir, &out).unwrap_err()
+    );
+    assert!(err.contains("encrypted minisign keys"), "{err}");
+}
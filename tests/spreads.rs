@@ -0,0 +1,129 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::{
+        half_page_marker, parse_half_page_marker, parse_page_marker, strip_page_markers,
+        HALF_PAGE_BREAK_PLACEHOLDER, PAGE_BREAK_PLACEHOLDER,
+    },
+    probe::ProbeStructure,
+};
+use std::path::Path;
+
+/// Four two-up scans; with `split_spreads` each page comes back as two halves.
+struct SpreadEngine;
+
+impl Engine for SpreadEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 4,
+            sampled_pages: 4,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: ProbeStructure {
+                spread_pages: 3,
+                ..Default::default()
+            },
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| {
+                if req.split_spreads {
+                    format!("Verso of {p}.\n\n{HALF_PAGE_BREAK_PLACEHOLDER}\n\nRecto of {p}.")
+                } else {
+                    format!("Verso of {p}. Recto of {p}.")
+                }
+            })
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn run(split_spreads: &str, name: &str) -> Result<quack_check::pipeline::JobOutput> {
+    let dir = std::env::temp_dir().join(format!("quack-spreads-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.input.split_spreads = split_spreads.into();
+    cfg.postprocess.keep_page_markers = true;
+    let out = Pipeline::new(&cfg, SpreadEngine).run_job(&dir.join("in.pdf"), &dir.join("job"));
+    let _ = std::fs::remove_dir_all(&dir);
+    out
+}
+
+#[test]
+fn detected_spreads_are_split_into_numbered_half_pages() {
+    let out = run("auto", "auto").unwrap();
+    assert!(out.report.decision.split_spreads);
+    let md = &out.markdown;
+    let page_2 = md.find("<!-- quack:page 2 -->").unwrap();
+    let left = md.find("<!-- quack:half-page 3 (pdf 2 left) -->").unwrap();
+    let verso = md.find("Verso of 2.").unwrap();
+    let right = md.find("<!-- quack:half-page 4 (pdf 2 right) -->").unwrap();
+    let recto = md.find("Recto of 2.").unwrap();
+    assert!(
+        page_2 < left && left < verso && verso < right && right < recto,
+        "{md}"
+    );
+
+    let recto_4 = out
+        .pagemap
+        .paragraphs
+        .iter()
+        .find(|p| p.page == 4 && p.half_page == Some(8))
+        .unwrap();
+    let text: String = md
+        .chars()
+        .skip(recto_4.start)
+        .take(recto_4.end - recto_4.start)
+        .collect();
+    assert_eq!(text, "Recto of 4.");
+    assert!(!out.text.contains("quack:half-page"), "{}", out.text);
+}
+
+#[test]
+fn spreads_are_only_split_when_asked() {
+    let out = run("off", "off").unwrap();
+    assert!(!out.report.decision.split_spreads);
+    assert!(out.markdown.contains("Verso of 3. Recto of 3."));
+    assert!(!out.markdown.contains("half-page"));
+
+    let err = run("sometimes", "bad").err().unwrap();
+    assert!(format!("{err:#}").contains("unknown input.split_spreads: sometimes"));
+}
+
+#[test]
+fn half_page_markers_round_trip_and_are_stripped_with_page_markers() {
+    let marker = half_page_marker(11, 6, "left");
+    assert_eq!(marker, "<!-- quack:half-page 11 (pdf 6 left) -->");
+    assert_eq!(parse_half_page_marker(&marker), Some((11, 6)));
+    assert_eq!(parse_page_marker(&marker), None);
+    let md = format!("<!-- quack:page 6 -->\n\n{marker}\n\nText.\n");
+    assert_eq!(strip_page_markers(&md), "Text.");
+}
@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+use quack_check::systemd::Notifier;
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+fn recv(socket: &UnixDatagram) -> String {
+    let mut buf = [0u8; 512];
+    let n = socket.recv(&mut buf).unwrap();
+    String::from_utf8_lossy(&buf[..n]).into_owned()
+}
+
+#[test]
+fn notifications_reach_the_socket() {
+    let path = std::env::temp_dir().join(format!("quack-notify-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let socket = UnixDatagram::bind(&path).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let notifier = Notifier::connect(path.to_str().unwrap()).unwrap();
+    notifier.ready("batch of 2 input(s)");
+    assert_eq!(recv(&socket), "READY=1\nSTATUS=batch of 2 input(s)");
+    notifier.status("chunk 14/60 of job abc\nREADY=1");
+    assert_eq!(recv(&socket), "STATUS=chunk 14/60 of job abc READY=1");
+    notifier.stopping("done");
+    assert_eq!(recv(&socket), "STOPPING=1\nSTATUS=done");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn abstract_sockets_are_supported() {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::SocketAddr;
+
+    let name = format!("quack-notify-{}", std::process::id());
+    let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+    let socket = UnixDatagram::bind_addr(&addr).unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    Notifier::connect(&format!("@{name}"))
+        .unwrap()
+        .status("idle");
+    assert_eq!(recv(&socket), "STATUS=idle");
+}
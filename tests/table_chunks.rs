@@ -0,0 +1,153 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    policy::validate_tier_overrides,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Pages 21-40 are a statistical appendix: one table per page, which only comes out
+/// well when converted with table settings.
+struct AppendixEngine {
+    calls: Arc<Mutex<Vec<(u32, bool)>>>,
+}
+
+impl Engine for AppendixEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 40,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        let tuned = req.pipeline_overrides.get("table_batch_size") == Some(&32.into());
+        self.calls.lock().unwrap().push((req.start_page, tuned));
+        let appendix = req.start_page > 20;
+        let markdown = match (appendix, tuned) {
+            (false, _) => format!("Chapter text {}.", req.start_page),
+            (true, false) => "Year Value 1990 12".to_string(),
+            (true, true) => "| Year | Value |\n|---|---|\n| 1990 | 12 |".to_string(),
+        };
+        let pages = req.end_page - req.start_page + 1;
+        Ok(ConvertOut {
+            ok: true,
+            markdown,
+            warnings: vec![],
+            meta: ConvertMeta {
+                pages_processed: Some(pages),
+                table_count: Some(if appendix { pages } else { 1 }),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+/// Two 20-page chunks by page range: a prose half and an appendix half.
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-tables-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 20;
+    cfg.chunking.max_pages_per_chunk = 20;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    (cfg, dir)
+}
+
+fn run(cfg: &Config, dir: &Path) -> (quack_check::pipeline::JobOutput, Vec<(u32, bool)>) {
+    let calls = Arc::default();
+    let engine = AppendixEngine {
+        calls: Arc::clone(&calls),
+    };
+    let out = Pipeline::new(cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    let calls = calls.lock().unwrap().clone();
+    (out, calls)
+}
+
+fn content_types(out: &quack_check::pipeline::JobOutput) -> Vec<Option<&str>> {
+    out.report
+        .chunk_reports
+        .iter()
+        .map(|c| c.content_type.as_deref())
+        .collect()
+}
+
+#[test]
+fn chunks_are_labelled_without_reconverting_by_default() {
+    let (cfg, dir) = setup("detect");
+    let (out, calls) = run(&cfg, &dir);
+    assert_eq!(content_types(&out), [Some("prose"), Some("table")]);
+    assert_eq!(calls, [(1, false), (21, false)]);
+    assert!(out.markdown.contains("Year Value 1990 12"));
+}
+
+#[test]
+fn route_mode_converts_table_dominant_chunks_with_table_settings() {
+    let (mut cfg, dir) = setup("route");
+    cfg.docling.table_chunks.mode = "route".into();
+    let (out, calls) = run(&cfg, &dir);
+    assert_eq!(calls, [(1, false), (21, false), (21, true)]);
+    assert_eq!(content_types(&out), [Some("prose"), Some("table")]);
+    assert!(out.markdown.contains("| 1990 | 12 |"));
+    assert!(!out.markdown.contains("Year Value 1990 12"));
+    assert!(out.report.chunk_reports[1]
+        .warnings
+        .iter()
+        .any(|w| w.contains("table_chunks.overrides")));
+}
+
+#[test]
+fn off_mode_and_threshold_control_the_label() {
+    let (mut cfg, dir) = setup("off");
+    cfg.docling.table_chunks.mode = "off".into();
+    let (out, _) = run(&cfg, &dir);
+    assert_eq!(content_types(&out), [None, None]);
+
+    cfg.docling.table_chunks.mode = "detect".into();
+    cfg.docling.table_chunks.min_tables_per_page = 0.01;
+    let (out, _) = run(&cfg, &dir);
+    assert_eq!(content_types(&out), [Some("table"), Some("table")]);
+}
+
+#[test]
+fn invalid_table_chunk_settings_are_rejected() {
+    let mut cfg = Config::default();
+    cfg.docling.table_chunks.mode = "always".into();
+    assert!(validate_tier_overrides(&cfg).is_err());
+
+    let mut cfg = Config::default();
+    cfg.docling
+        .table_chunks
+        .overrides
+        .insert("ocr.".into(), true.into());
+    let err = validate_tier_overrides(&cfg).unwrap_err();
+    assert!(err.to_string().contains("docling.table_chunks.overrides"));
+}
@@ -0,0 +1,137 @@
+use quack_check::{
+    pagenum::{PageNumberStats, PrintedPage},
+    postprocess::page_marker,
+    terms::{extract_terms, replace_terms, TermEntry},
+};
+
+const INDEX: &str = "\
+# Index
+
+## A
+
+abbeys, 12, 45–47
+Aelfric 103
+Alcuin, 88, xii; see also Charlemagne
+
+## B
+
+Bede, 9, 14, 22,
+  31, 40
+Benedictine rule, 61
+Benedictine Rule, 61, 62
+bishops (see clergy)
+";
+
+fn entry<'a>(entries: &'a [TermEntry], term: &str) -> &'a TermEntry {
+    entries
+        .iter()
+        .find(|e| e.term == term)
+        .unwrap_or_else(|| panic!("no entry {term}: {entries:?}"))
+}
+
+#[test]
+fn index_lines_become_terms_with_page_references() {
+    let md = format!(
+        "{}\n\n# Notes\n\nText.\n\n{}\n\n{INDEX}",
+        page_marker(1),
+        page_marker(2)
+    );
+    let report = extract_terms(&md, &PageNumberStats::default());
+    assert_eq!(report.sections.len(), 1);
+    let section = &report.sections[0];
+    assert_eq!(
+        (section.kind.as_str(), section.heading.as_str()),
+        ("index", "Index")
+    );
+    assert_eq!(section.start_page, Some(2));
+    assert_eq!(section.unparsed_lines, 0);
+
+    let entries = &section.entries;
+    assert_eq!(entry(entries, "abbeys").pages, ["12", "45-47"]);
+    assert_eq!(entry(entries, "Aelfric").pages, ["103"]);
+    assert_eq!(entry(entries, "Alcuin").pages, ["88", "xii"]);
+    assert_eq!(entry(entries, "Alcuin").see, ["Charlemagne"]);
+    assert_eq!(entry(entries, "Bede").pages, ["9", "14", "22", "31", "40"]);
+    assert_eq!(entry(entries, "bishops").see, ["clergy"]);
+    // Case-only duplicates are merged, keeping the first spelling.
+    assert_eq!(entry(entries, "Benedictine rule").pages, ["61", "62"]);
+    assert_eq!(entries.len(), 6);
+}
+
+#[test]
+fn printed_page_numbers_map_references_to_pdf_pages() {
+    let stats = PageNumberStats {
+        pages: vec![
+            PrintedPage {
+                pdf_page: 20,
+                printed: "12".into(),
+                source: "detected".into(),
+            },
+            PrintedPage {
+                pdf_page: 53,
+                printed: "45".into(),
+                source: "detected".into(),
+            },
+        ],
+        ..Default::default()
+    };
+    let report = extract_terms(INDEX, &stats);
+    assert_eq!(
+        entry(&report.sections[0].entries, "abbeys").pdf_pages,
+        [20, 53]
+    );
+}
+
+#[test]
+fn glossaries_keep_definitions_and_their_continuation_lines() {
+    let md = "\
+Glossary
+
+**Abbot**: head of a monastery.
+Cellarer: the monk in charge of
+provisions and stores.
+Chapter — the daily meeting of the community.
+Novice: a monk in training.
+Prior: deputy to the abbot.
+";
+    let report = extract_terms(md, &PageNumberStats::default());
+    let section = &report.sections[0];
+    assert_eq!(section.kind, "glossary");
+    let definition = |term: &str| entry(&section.entries, term).definition.clone().unwrap();
+    assert_eq!(definition("Abbot"), "head of a monastery.");
+    assert_eq!(
+        definition("Cellarer"),
+        "the monk in charge of provisions and stores."
+    );
+    assert_eq!(definition("Chapter"), "the daily meeting of the community.");
+    assert_eq!(section.entries.len(), 5);
+}
+
+#[test]
+fn a_stray_index_line_in_body_text_is_ignored() {
+    let md = "\
+Index
+
+The index of refraction is what bends the light, 12 times over.
+It was measured with care.
+Nobody disputed it.
+";
+    assert!(extract_terms(md, &PageNumberStats::default())
+        .sections
+        .is_empty());
+}
+
+#[test]
+fn replace_keeps_the_heading_and_page_markers() {
+    let md = format!(
+        "# Chapter\n\nBody.\n\n{INDEX}\n{}\n\nabbots, 5\n\n# Afterword\n\nThanks.",
+        page_marker(9)
+    );
+    let (replaced, report) = replace_terms(&md, &PageNumberStats::default());
+    assert_eq!(report.sections[0].entries.len(), 7);
+    assert!(replaced.contains("# Index\n\n<!-- index: 7 entries in terms.json -->"));
+    assert!(replaced.contains(&page_marker(9)));
+    assert!(!replaced.contains("Aelfric"));
+    assert!(replaced.contains("# Afterword\n\nThanks."));
+    assert!(replaced.starts_with("# Chapter\n\nBody."));
+}
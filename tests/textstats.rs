@@ -0,0 +1,93 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    policy::{decide, QualityTier},
+    probe::probe_pdf,
+    textstats::{analyze, garbage_ratio},
+};
+use std::path::Path;
+
+const PROSE: &str = "The committee met on the 3rd of May to discuss the budget. After a long \
+debate, the members agreed that the library would remain open in the evenings, and that the \
+reading room would be repaired before the winter. The chair thanked the volunteers for their \
+work on the catalogue, which now lists more than 12,000 titles (about 40% of the collection). \
+The next meeting will be held in the same room; the agenda is on page 7 of the newsletter.";
+
+/// The same paragraph read badly, with no replacement characters at all.
+const NOISY: &str = "Tlie cornmittee rnet on tlie 3rd of May to discuss tlie budget. After a \
+l0ng debate, tlie mernbers agreed tliat tlie library wоuld remain open in tlie evenings, .,;'| \
+and tliat tlie reading rоom wou1d be repaired bef0re tlie winter. Tlie cliair thanked tlie \
+vo1unteers for tlieir w0rk on tlie catalogue, rnmnm which now lists rnore than 12,000 titles. \
+Tlie next meeting will be held in tlie same room; tlie agenda is on page 7 of tlie newsletter.";
+
+#[test]
+fn clean_prose_scores_near_zero() {
+    let stats = analyze(PROSE);
+    assert!(stats.tokens > 60);
+    assert!(stats.garbage_ratio() < 0.01, "{stats:?}");
+}
+
+#[test]
+fn latin_lookalike_noise_is_caught() {
+    assert!(garbage_ratio(NOISY) > 0.05, "{}", garbage_ratio(NOISY));
+    for word in ["l1ke", ".,;'|", "wоrd", "rnmnm", "\u{FFFD}\u{FFFD}"] {
+        assert!(garbage_ratio(word) > 0.4, "{word}: {}", garbage_ratio(word));
+    }
+    // A misreading of a word the text uses often, though itself made of plain letters.
+    let text = format!("{PROSE} Tlie end.");
+    assert!(garbage_ratio(&text) > garbage_ratio(PROSE));
+}
+
+#[test]
+fn markup_numbers_and_codes_are_not_noise() {
+    let text = "| --- | :---: |\n<!-- quack:page 2 -->\n![figure](img/p2.png) \
+        See https://example.com/a?b=1 for the 1920s, the 19th, A4, H2O and 3.5%.";
+    assert!(garbage_ratio(text) < 0.01, "{}", garbage_ratio(text));
+}
+
+/// Reports a clean-looking replacement-character ratio, but sends its sampled text.
+struct NoisyProbe;
+
+impl Engine for NoisyProbe {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 2,
+            sampled_pages: 2,
+            avg_chars_per_page: 2000,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.15,
+            structure: Default::default(),
+            sample_texts: vec![NOISY.to_string(), NOISY.to_string()],
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn classification_scores_the_sampled_text() {
+    let path = std::env::temp_dir().join(format!("quack-textstats-{}.pdf", std::process::id()));
+    std::fs::write(&path, b"%PDF-1.7").unwrap();
+    let cfg = Config::default();
+    let probe = probe_pdf(&cfg, &NoisyProbe, &path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(probe.sample.garbage_ratio > cfg.classification.max_garbage_ratio_for_high_text);
+    assert_ne!(decide(&cfg, &probe).tier, QualityTier::HighText);
+}
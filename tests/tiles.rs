@@ -0,0 +1,167 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    postprocess::{parse_tile_marker, strip_page_markers, tile_marker, PAGE_BREAK_PLACEHOLDER},
+    probe::{LargePage, ProbeStructure},
+    tiles::{grid, megapixels, PageTiles},
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Six pages; page 4 is an A0 fold-out map and page 5 an A3 plate.
+struct MapEngine {
+    tiles_seen: Arc<Mutex<Vec<Vec<PageTiles>>>>,
+}
+
+impl Engine for MapEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 6,
+            sampled_pages: 6,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: ProbeStructure {
+                large_pages: vec![
+                    LargePage {
+                        page: 4,
+                        width_pt: 3370.0,
+                        height_pt: 2384.0,
+                    },
+                    LargePage {
+                        page: 5,
+                        width_pt: 842.0,
+                        height_pt: 1191.0,
+                    },
+                ],
+                ..Default::default()
+            },
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        self.tiles_seen.lock().unwrap().push(req.tiles.clone());
+        let pages: Vec<String> = (req.start_page..=req.end_page)
+            .map(|p| match req.tiles.iter().find(|t| t.page == p) {
+                Some(t) => (1..=t.rows)
+                    .flat_map(|row| (1..=t.cols).map(move |col| (row, col)))
+                    .map(|(row, col)| {
+                        let marker = tile_marker(p, row, col, t.rows, t.cols);
+                        format!("{marker}\n\nLegend {row}.{col}.")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+                None => format!("Text of page {p}."),
+            })
+            .collect();
+        Ok(ConvertOut {
+            ok: true,
+            markdown: pages.join(&format!("\n\n{PAGE_BREAK_PLACEHOLDER}\n\n")),
+            warnings: vec![],
+            meta: ConvertMeta::default(),
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+fn run(
+    tile: bool,
+    name: &str,
+) -> (
+    Result<quack_check::pipeline::JobOutput>,
+    Vec<Vec<PageTiles>>,
+) {
+    let dir = std::env::temp_dir().join(format!("quack-tiles-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 3;
+    cfg.chunking.max_pages_per_chunk = 3;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 3;
+    cfg.input.tile_oversized_pages = tile;
+    cfg.postprocess.keep_page_markers = true;
+    let tiles_seen = Arc::new(Mutex::new(Vec::new()));
+    let engine = MapEngine {
+        tiles_seen: tiles_seen.clone(),
+    };
+    let out = Pipeline::new(&cfg, engine).run_job(&dir.join("in.pdf"), &dir.join("job"));
+    let _ = std::fs::remove_dir_all(&dir);
+    let seen = tiles_seen.lock().unwrap().clone();
+    (out, seen)
+}
+
+#[test]
+fn oversized_pages_are_converted_as_tiles() {
+    let (out, seen) = run(true, "on");
+    let out = out.unwrap();
+    let map = PageTiles {
+        page: 4,
+        rows: 1,
+        cols: 2,
+    };
+    assert_eq!(out.report.decision.tiled_pages, vec![map]);
+    assert_eq!(seen, vec![vec![], vec![map]]);
+
+    let md = &out.markdown;
+    let page = md.find("<!-- quack:page 4 -->").unwrap();
+    let left = md
+        .find("<!-- quack:tile row 1 col 1 of 1x2 (pdf 4) -->")
+        .unwrap();
+    let right = md
+        .find("<!-- quack:tile row 1 col 2 of 1x2 (pdf 4) -->")
+        .unwrap();
+    let next = md.find("<!-- quack:page 5 -->").unwrap();
+    assert!(page < left && left < right && right < next, "{md}");
+    assert!(out.text.contains("Legend 1.2."));
+    assert!(!out.text.contains("quack:tile"), "{}", out.text);
+}
+
+#[test]
+fn oversized_pages_are_left_whole_unless_asked() {
+    let (out, seen) = run(false, "off");
+    let out = out.unwrap();
+    assert!(out.report.decision.tiled_pages.is_empty());
+    assert!(seen.iter().all(Vec::is_empty));
+    assert!(out.markdown.contains("Text of page 4."));
+}
+
+#[test]
+fn grids_keep_every_tile_under_the_limit() {
+    // A4 at 216 dpi is about 4.5 megapixels.
+    assert!((megapixels(595.0, 842.0) - 4.51).abs() < 0.01);
+    assert_eq!(grid(595.0, 842.0, 40.0), (1, 1));
+    assert_eq!(grid(2384.0, 3370.0, 40.0), (2, 1));
+    assert_eq!(grid(3370.0, 2384.0, 40.0), (1, 2));
+    let (rows, cols) = grid(3370.0, 2384.0, 5.0);
+    assert!(megapixels(3370.0 / cols as f64, 2384.0 / rows as f64) <= 5.0);
+    assert_eq!((rows, cols), (3, 5));
+}
+
+#[test]
+fn tile_markers_round_trip_and_are_stripped_with_page_markers() {
+    let marker = tile_marker(14, 1, 2, 2, 3);
+    assert_eq!(marker, "<!-- quack:tile row 1 col 2 of 2x3 (pdf 14) -->");
+    assert_eq!(parse_tile_marker(&marker), Some((14, 1, 2)));
+    let md = format!("<!-- quack:page 14 -->\n\n{marker}\n\nText.\n");
+    assert_eq!(strip_page_markers(&md), "Text.");
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use quack_check::{
+    chunk_plan::PageRange,
+    config::{Classification, Config},
+    engine::{ConvertIn, ConvertMeta, ConvertOut, DocDiag, Engine, ProbeOut, SplitChunk},
+    pipeline::Pipeline,
+    report::SKIPPED_DUE_TO_BUDGET,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Takes `delay` per chunk, so budgets can be exhausted deterministically.
+struct SlowEngine {
+    delay: Duration,
+}
+
+impl Engine for SlowEngine {
+    fn doctor(&self) -> Result<DocDiag> {
+        unimplemented!()
+    }
+
+    fn probe_pdf(&self, _input: &Path, _sample: &Classification) -> Result<ProbeOut> {
+        Ok(ProbeOut {
+            page_count: 40,
+            sampled_pages: 5,
+            avg_chars_per_page: 10,
+            garbage_ratio: 0.0,
+            whitespace_ratio: 0.1,
+            structure: Default::default(),
+            sample_texts: Vec::new(),
+            error: None,
+        })
+    }
+
+    fn split_pdf(&self, _: &Path, _: &Path, _: &[PageRange]) -> Result<Vec<SplitChunk>> {
+        unimplemented!()
+    }
+
+    fn convert_docling(&self, req: &ConvertIn) -> Result<ConvertOut> {
+        std::thread::sleep(self.delay);
+        Ok(ConvertOut {
+            ok: true,
+            markdown: format!("Pages {}-{}.", req.start_page, req.end_page),
+            warnings: vec![],
+            meta: ConvertMeta {
+                pdf_backend: Some(req.pdf_backend.clone()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn convert_native_text(&self, _req: &ConvertIn) -> Result<ConvertOut> {
+        unimplemented!()
+    }
+}
+
+/// Four 10-page chunks converted by page range.
+fn setup(name: &str) -> (Config, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("quack-budget-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("in.pdf"), b"%PDF-1.7").unwrap();
+    let mut cfg = Config::default();
+    cfg.classification.forced_tier = "SCAN".into();
+    cfg.chunking.strategy = "page_range".into();
+    cfg.chunking.target_pages_per_chunk = 10;
+    cfg.chunking.max_pages_per_chunk = 10;
+    cfg.chunking.boundary_slack_pages = 0;
+    cfg.limits.require_chunking_over_pages = 10;
+    (cfg, dir)
+}
+
+fn run(cfg: &Config, dir: &Path) -> quack_check::pipeline::JobOutput {
+    let engine = SlowEngine {
+        delay: Duration::from_millis(50),
+    };
+    Pipeline::new(cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap()
+}
+
+#[test]
+fn exhausted_budget_skips_remaining_chunks_and_keeps_the_partial_transcript() {
+    let (mut cfg, dir) = setup("exhausted");
+    cfg.limits.max_seconds_per_page = 0.001;
+    let out = run(&cfg, &dir);
+
+    let c = &out.report.completeness;
+    assert!(!c.complete);
+    assert_eq!((c.converted_pages, c.total_pages), (10, 40));
+    assert_eq!(c.skipped_chunks, [1, 2, 3]);
+    assert!(c.reason.as_deref().unwrap().contains("pages 11-40"));
+
+    let chunks = &out.report.chunk_reports;
+    assert_eq!(chunks.len(), 4);
+    assert!(chunks[0].ok && chunks[0].skipped.is_none());
+    assert!(chunks[1..]
+        .iter()
+        .all(|c| !c.ok && c.skipped.as_deref() == Some(SKIPPED_DUE_TO_BUDGET)));
+    assert_eq!((chunks[3].start_page, chunks[3].end_page), (31, 40));
+
+    assert!(out.markdown.starts_with("> **Incomplete transcript:**"));
+    assert!(out.markdown.contains("Pages 1-10."));
+    assert!(!out.markdown.contains("Pages 11-20."));
+    assert!(out.text.starts_with("[Incomplete transcript:"));
+}
+
+#[test]
+fn whole_job_budget_applies_without_a_per_page_rate() {
+    let (mut cfg, dir) = setup("whole");
+    cfg.limits.soft_budget_seconds = 1;
+    let engine = SlowEngine {
+        delay: Duration::from_millis(400),
+    };
+    let out = Pipeline::new(&cfg, engine)
+        .run_job(&dir.join("in.pdf"), &dir.join("job"))
+        .unwrap();
+    assert!(!out.report.completeness.complete);
+    assert_eq!(out.report.completeness.skipped_chunks, [1, 2, 3]);
+}
+
+#[test]
+fn generous_or_disabled_budget_reports_a_complete_job() {
+    let (mut cfg, dir) = setup("generous");
+    let out = run(&cfg, &dir);
+    assert!(out.report.completeness.complete);
+    assert_eq!(out.report.completeness.converted_pages, 40);
+
+    cfg.limits.max_seconds_per_page = 60.0;
+    let out = run(&cfg, &dir);
+    assert!(out.report.completeness.complete);
+    assert!(out.report.completeness.skipped_chunks.is_empty());
+    assert!(out.report.chunk_reports.iter().all(|c| c.ok));
+    assert!(!out.markdown.contains("Incomplete transcript"));
+}
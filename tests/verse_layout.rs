@@ -0,0 +1,77 @@
+use quack_check::{
+    config::Config,
+    postprocess::{markdown_to_text, merge_markdown},
+    verse::detect_verse,
+};
+
+const POEM: &str = "\
+Whose woods these are I think I know.
+His house is in the village though;
+He will not see me stopping here
+To watch his woods fill up with snow.
+
+My little horse must think it queer
+  To stop without a farmhouse near
+Between the woods and frozen lake
+The darkest evening of the year.";
+
+const PROSE: &str = "\
+The committee met on Tuesday to review the proposed budget for the coming fiscal
+year. After a lengthy discussion of the capital expenditures, members agreed that
+the maintenance reserve should be increased before any new projects were approved.";
+
+#[test]
+fn detects_stanzas_but_not_wrapped_prose() {
+    let md = format!("# Poems\n\n{POEM}\n\n{PROSE}\n");
+    let mask = detect_verse(&md, 60, 4);
+    let flagged: Vec<&str> = md
+        .lines()
+        .zip(&mask)
+        .filter(|(_, m)| **m)
+        .map(|(l, _)| l)
+        .collect();
+    assert_eq!(flagged.len(), 8);
+    assert_eq!(flagged[0], "Whose woods these are I think I know.");
+    assert!(!flagged.iter().any(|l| l.contains("committee")));
+
+    // Two short lines alone are not a poem.
+    let mask = detect_verse("Jane Doe\n12 Elm Street, Springfield\n", 60, 4);
+    assert!(mask.iter().all(|m| !m));
+}
+
+#[test]
+fn protects_verse_from_line_removal() {
+    // A refrain repeated often enough to look like a running header.
+    let refrain = "And miles to go before I sleep.";
+    let stanzas: Vec<String> = (0..6)
+        .map(|i| format!("Verse number {i} begins the stanza\nand wanders off\n{refrain}"))
+        .collect();
+    let md = stanzas.join("\n\n");
+
+    let mut cfg = Config::default();
+    let flattened = merge_markdown(&cfg, vec![md.clone()]).unwrap();
+    assert!(!flattened.contains(refrain));
+
+    cfg.postprocess.verse = "protect".into();
+    let kept = merge_markdown(&cfg, vec![md]).unwrap();
+    assert_eq!(kept.matches(refrain).count(), 6);
+}
+
+#[test]
+fn hard_breaks_preserve_lines_and_indentation() {
+    let mut cfg = Config::default();
+    cfg.postprocess.verse = "hard_breaks".into();
+    let md = merge_markdown(&cfg, vec![POEM.to_string()]).unwrap();
+
+    assert!(md.contains("Whose woods these are I think I know.\\\nHis house"));
+    assert!(md.contains("To watch his woods fill up with snow.\n\nMy little"));
+    assert!(md.contains("\n\u{a0}\u{a0}To stop without a farmhouse near\\\n"));
+    assert!(md.ends_with("The darkest evening of the year."));
+
+    let text = markdown_to_text(&cfg, &md).unwrap();
+    assert!(text.contains("I think I know.\nHis house"));
+    assert!(text.contains("\n  To stop without"));
+
+    cfg.postprocess.verse = "sonnet".into();
+    assert!(merge_markdown(&cfg, vec![POEM.to_string()]).is_err());
+}
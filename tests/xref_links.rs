@@ -0,0 +1,38 @@
+use quack_check::{postprocess::page_marker, xref::resolve_cross_references};
+
+#[test]
+fn links_chapters_figures_and_pages() {
+    let md = [
+        page_marker(1),
+        "# Chapter 1: Setup".to_string(),
+        "Results are in Chapter 2, see Figure 3.2 and page 2.".to_string(),
+        "Table 9 does not exist.".to_string(),
+        page_marker(2),
+        "# Chapter 2: Results".to_string(),
+        "Figure 3.2: Throughput over time".to_string(),
+        "```".to_string(),
+        "Chapter 1 in code".to_string(),
+        "```".to_string(),
+        "Already [Chapter 1](#elsewhere) linked.".to_string(),
+    ]
+    .join("\n");
+
+    let (out, stats) = resolve_cross_references(&md);
+    assert!(out.contains(
+        "Results are in [Chapter 2](#chapter-2-results), see [Figure 3.2](#figure-3-2) and [page 2](#page-2)."
+    ));
+    assert!(out.contains("<a id=\"figure-3-2\"></a>\nFigure 3.2: Throughput over time"));
+    assert!(out.contains(&format!("{}\n<a id=\"page-2\"></a>", page_marker(2))));
+    assert!(out.contains("# Chapter 1: Setup\n"));
+    assert!(out.contains("\nChapter 1 in code\n"));
+    assert!(out.contains("Already [Chapter 1](#elsewhere) linked."));
+    assert_eq!(stats.resolved, 3);
+    assert_eq!(stats.unresolved, 1);
+}
+
+#[test]
+fn numbered_headings_resolve_sections() {
+    let md = "## 4.1 Method\n\nAs shown in Section 4.1, it works.";
+    let (out, _) = resolve_cross_references(md);
+    assert!(out.ends_with("As shown in [Section 4.1](#41-method), it works."));
+}